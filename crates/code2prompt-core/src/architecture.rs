@@ -0,0 +1,571 @@
+//! Architecture brief generation.
+//!
+//! Condenses a codebase into an overview meant to fit a fixed token budget whatever
+//! the size of the repository: the workspace packages and the dependencies between
+//! them, a directory overview, the READMEs and an outline of the public items of each
+//! source file. When everything does not fit, the least useful parts are pruned step
+//! by step, and every pruning decision is recorded in the brief.
+
+use anyhow::Result;
+use log::info;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::doc_coverage::public_items;
+
+/// Token budget of an architecture brief when none is given.
+pub const DEFAULT_BRIEF_MAX_TOKENS: usize = 8000;
+
+/// Lines of a README kept once READMEs are shortened.
+const README_EXCERPT_LINES: usize = 40;
+
+/// Public items listed per file, at most.
+const OUTLINE_MAX_ITEMS: usize = 25;
+
+/// Depth of the directory overview kept before file outlines are dropped.
+const DIRECTORY_MIN_DEPTH: usize = 3;
+
+/// Longest line kept in an outline.
+const OUTLINE_MAX_LINE: usize = 120;
+
+/// A package of the workspace, declared by a manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkspacePackage {
+    pub name: String,
+    /// Directory of the manifest, `.` for the root
+    pub path: String,
+    /// `cargo`, `npm`, `python` or `go`
+    pub ecosystem: &'static str,
+    /// Dependencies on other packages of the workspace
+    pub internal_dependencies: Vec<String>,
+    /// Number of dependencies from outside the workspace
+    pub external_dependencies: usize,
+}
+
+/// A README, possibly shortened
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReadmeExcerpt {
+    pub path: String,
+    pub content: String,
+    pub truncated: bool,
+}
+
+/// The purpose and public items of a source file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileOutline {
+    pub path: String,
+    /// First line of the module documentation, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Declarations of the public items
+    pub items: Vec<String>,
+}
+
+/// A condensed overview of a codebase
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ArchitectureBrief {
+    pub packages: Vec<WorkspacePackage>,
+    /// Directories with their number of files, indented by depth
+    pub directories: String,
+    pub readmes: Vec<ReadmeExcerpt>,
+    pub outlines: Vec<FileOutline>,
+    /// What was left out to fit the budget, in the order it was decided
+    pub pruned: Vec<String>,
+    /// Size of the prompt rendered with the brief, as measured to prune it
+    pub token_count: usize,
+    pub max_tokens: usize,
+}
+
+/// Builds the architecture brief of a codebase within a token budget.
+///
+/// # Arguments
+///
+/// * `files` - The path, relative to the root, and the content of each file
+/// * `max_tokens` - The token budget of the whole prompt
+/// * `count_tokens` - Counts the tokens of the prompt rendered with a brief
+///
+/// # Returns
+///
+/// * `Result<ArchitectureBrief>` - The brief, with the pruning decisions that made it fit,
+///   or the error of `count_tokens`
+pub fn build_architecture_brief(
+    files: &[(String, String)],
+    max_tokens: usize,
+    count_tokens: impl Fn(&ArchitectureBrief) -> Result<usize>,
+) -> Result<ArchitectureBrief> {
+    let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+    let mut brief = ArchitectureBrief {
+        packages: workspace_packages(files),
+        directories: directory_overview(&paths, usize::MAX),
+        readmes: files
+            .iter()
+            .filter(|(path, _)| is_readme(path))
+            .map(|(path, content)| ReadmeExcerpt {
+                path: path.clone(),
+                content: content.trim().to_string(),
+                truncated: false,
+            })
+            .collect(),
+        outlines: files
+            .iter()
+            .filter_map(|(path, content)| outline(path, content))
+            .collect(),
+        pruned: Vec::new(),
+        token_count: 0,
+        max_tokens,
+    };
+    let fits =
+        |brief: &ArchitectureBrief| -> Result<bool> { Ok(count_tokens(brief)? <= max_tokens) };
+
+    // 1. Shorten the READMEs
+    if !fits(&brief)? {
+        let mut shortened = Vec::new();
+        for readme in &mut brief.readmes {
+            if truncate_lines(readme, README_EXCERPT_LINES) {
+                shortened.push(readme.path.clone());
+            }
+        }
+        if !shortened.is_empty() {
+            brief.record(format!(
+                "Shortened {} to their first {} lines",
+                list(&shortened),
+                README_EXCERPT_LINES
+            ));
+        }
+    }
+
+    // 2. Limit the depth of the directory overview, outlines say more about deep files
+    let mut depth = max_depth(&paths);
+    limit_directory_depth(&mut brief, &paths, &mut depth, DIRECTORY_MIN_DEPTH, &fits)?;
+
+    // 3. Drop file outlines, tests and examples first, then the most deeply nested
+    if !brief.outlines.is_empty() && !fits(&brief)? {
+        let mut order: Vec<usize> = (0..brief.outlines.len()).collect();
+        order.sort_by_key(|&i| {
+            let path = &brief.outlines[i].path;
+            (
+                std::cmp::Reverse(is_auxiliary(path)),
+                std::cmp::Reverse(path.matches('/').count()),
+                brief.outlines[i].items.len(),
+            )
+        });
+        let decision = |count: usize| {
+            let names: Vec<String> = order[..count]
+                .iter()
+                .map(|&i| brief.outlines[i].path.clone())
+                .collect();
+            format!(
+                "Dropped the outlines of {} file(s), tests and the most deeply nested first: {}",
+                count,
+                list(&names)
+            )
+        };
+        let without = |count: usize| {
+            brief.without_outlines(&order[..count].iter().copied().collect::<BTreeSet<_>>())
+        };
+        // The brief shrinks as outlines are dropped: search the fewest that make it fit
+        let (mut low, mut high) = (1, order.len());
+        while low < high {
+            let middle = (low + high) / 2;
+            if fits(&without(middle).with_decision(&decision(middle)))? {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+        let decision = decision(low);
+        brief = without(low);
+        brief.record(decision);
+    }
+
+    // 4. Limit the depth of the directory overview further
+    limit_directory_depth(&mut brief, &paths, &mut depth, 1, &fits)?;
+
+    // 5. Drop the READMEs of subdirectories
+    if !fits(&brief)? {
+        let (root, nested): (Vec<_>, Vec<_>) = brief
+            .readmes
+            .drain(..)
+            .partition(|readme| !readme.path.contains('/'));
+        brief.readmes = root;
+        if !nested.is_empty() {
+            let names: Vec<String> = nested.into_iter().map(|readme| readme.path).collect();
+            brief.record(format!("Dropped {}", list(&names)));
+        }
+    }
+
+    // 6. Shorten the remaining READMEs until they fit
+    let mut lines = README_EXCERPT_LINES;
+    while !fits(&brief)? && lines > 1 && !brief.readmes.is_empty() {
+        lines /= 2;
+        for readme in &mut brief.readmes {
+            truncate_lines(readme, lines);
+        }
+        if fits(&brief)? || lines <= 1 {
+            let names: Vec<String> = brief.readmes.iter().map(|r| r.path.clone()).collect();
+            brief.record(format!(
+                "Shortened {} to their first {} lines",
+                list(&names),
+                lines
+            ));
+        }
+    }
+
+    // 7. Keep the packages that fit, the ones other packages depend on first
+    if !fits(&brief)? && !brief.packages.is_empty() {
+        let total = brief.packages.len();
+        let depended: BTreeSet<String> = brief
+            .packages
+            .iter()
+            .flat_map(|package| package.internal_dependencies.iter().cloned())
+            .collect();
+        brief
+            .packages
+            .sort_by_key(|package| !depended.contains(&package.name));
+        while !fits(&brief)? && !brief.packages.is_empty() {
+            brief.packages.pop();
+        }
+        brief.packages.sort_by(|a, b| a.path.cmp(&b.path));
+        brief.record(format!(
+            "Listed {} of the {} workspace packages",
+            brief.packages.len(),
+            total
+        ));
+    }
+
+    // Measured once the decisions are recorded, they are rendered in the prompt too
+    brief.token_count = count_tokens(&brief)?;
+    Ok(brief)
+}
+
+impl ArchitectureBrief {
+    fn without_outlines(&self, dropped: &BTreeSet<usize>) -> Self {
+        let mut brief = self.clone();
+        brief.outlines = self
+            .outlines
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !dropped.contains(i))
+            .map(|(_, outline)| outline.clone())
+            .collect();
+        brief
+    }
+
+    /// The brief with one more pruning decision, to measure it before deciding
+    fn with_decision(&self, decision: &str) -> Self {
+        let mut brief = self.clone();
+        brief.pruned.push(decision.to_string());
+        brief
+    }
+
+    fn record(&mut self, decision: String) {
+        info!("Architecture brief: {}", decision);
+        self.pruned.push(decision);
+    }
+}
+
+/// The packages declared by the manifests among the files, sorted by path.
+pub(crate) fn workspace_packages(files: &[(String, String)]) -> Vec<WorkspacePackage> {
+    let mut packages: Vec<(WorkspacePackage, Vec<String>)> = files
+        .iter()
+        .filter_map(|(path, content)| {
+            let (dir, file_name) = match path.rsplit_once('/') {
+                Some((dir, file_name)) => (dir, file_name),
+                None => (".", path.as_str()),
+            };
+            let (ecosystem, (name, dependencies)) = match file_name {
+                "Cargo.toml" => ("cargo", cargo_manifest(content)?),
+                "package.json" => ("npm", npm_manifest(content)?),
+                "pyproject.toml" => ("python", python_manifest(content)?),
+                "go.mod" => ("go", go_manifest(content)?),
+                _ => return None,
+            };
+            Some((
+                WorkspacePackage {
+                    name,
+                    path: dir.to_string(),
+                    ecosystem,
+                    internal_dependencies: Vec::new(),
+                    external_dependencies: 0,
+                },
+                dependencies,
+            ))
+        })
+        .collect();
+
+    let names: BTreeSet<String> = packages.iter().map(|(p, _)| p.name.clone()).collect();
+    for (package, dependencies) in &mut packages {
+        let (internal, external): (Vec<String>, Vec<String>) = dependencies
+            .drain(..)
+            .partition(|dependency| names.contains(dependency));
+        package.internal_dependencies = internal;
+        package.external_dependencies = external.len();
+    }
+
+    let mut packages: Vec<WorkspacePackage> = packages.into_iter().map(|(p, _)| p).collect();
+    packages.sort_by(|a, b| a.path.cmp(&b.path).then(a.name.cmp(&b.name)));
+    packages
+}
+
+/// Name and dependencies of a `Cargo.toml`, `None` for a virtual workspace manifest.
+fn cargo_manifest(content: &str) -> Option<(String, Vec<String>)> {
+    let manifest: toml::Table = content.parse().ok()?;
+    let name = manifest.get("package")?.get("name")?.as_str()?.to_string();
+    let dependencies = ["dependencies", "dev-dependencies", "build-dependencies"]
+        .iter()
+        .filter_map(|section| manifest.get(*section)?.as_table())
+        .flat_map(|table| table.keys().cloned())
+        .collect::<BTreeSet<_>>();
+    Some((name, dependencies.into_iter().collect()))
+}
+
+fn npm_manifest(content: &str) -> Option<(String, Vec<String>)> {
+    let manifest: serde_json::Value = serde_json::from_str(content).ok()?;
+    let name = manifest.get("name")?.as_str()?.to_string();
+    let dependencies = ["dependencies", "devDependencies", "peerDependencies"]
+        .iter()
+        .filter_map(|section| manifest.get(*section)?.as_object())
+        .flat_map(|table| table.keys().cloned())
+        .collect::<BTreeSet<_>>();
+    Some((name, dependencies.into_iter().collect()))
+}
+
+fn python_manifest(content: &str) -> Option<(String, Vec<String>)> {
+    let manifest: toml::Table = content.parse().ok()?;
+    if let Some(project) = manifest.get("project") {
+        let name = project.get("name")?.as_str()?.to_string();
+        let dependencies = project
+            .get("dependencies")
+            .and_then(|deps| deps.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|requirement| {
+                let requirement = requirement.as_str()?;
+                let end = requirement
+                    .find(|c: char| !(c.is_alphanumeric() || "-_.".contains(c)))
+                    .unwrap_or(requirement.len());
+                Some(requirement[..end].to_string())
+            })
+            .collect();
+        return Some((name, dependencies));
+    }
+    let poetry = manifest.get("tool")?.get("poetry")?;
+    let name = poetry.get("name")?.as_str()?.to_string();
+    let dependencies = poetry
+        .get("dependencies")
+        .and_then(|deps| deps.as_table())
+        .map(|deps| {
+            deps.keys()
+                .filter(|key| *key != "python")
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    Some((name, dependencies))
+}
+
+fn go_manifest(content: &str) -> Option<(String, Vec<String>)> {
+    let mut name = None;
+    let mut dependencies = Vec::new();
+    let mut in_require = false;
+    for line in content.lines().map(str::trim) {
+        if let Some(module) = line.strip_prefix("module ") {
+            name = Some(module.trim().to_string());
+        } else if line == "require (" {
+            in_require = true;
+        } else if in_require && line == ")" {
+            in_require = false;
+        } else if let Some(require) = line.strip_prefix("require ") {
+            dependencies.extend(require.split_whitespace().next().map(str::to_string));
+        } else if in_require && !line.is_empty() && !line.starts_with("//") {
+            dependencies.extend(line.split_whitespace().next().map(str::to_string));
+        }
+    }
+    Some((name?, dependencies))
+}
+
+/// Directories down to `max_depth`, with their number of files, one per line, indented
+/// by depth after a line with the total number of files.
+fn directory_overview(paths: &[&str], max_depth: usize) -> String {
+    // Files per directory, counting the files of subdirectories. Directories are keyed
+    // by their components so that children are listed right after their parent.
+    let mut counts: BTreeMap<Vec<&str>, usize> = BTreeMap::new();
+    for path in paths {
+        let components: Vec<&str> = path.split('/').collect();
+        for depth in 1..components.len() {
+            *counts.entry(components[..depth].to_vec()).or_default() += 1;
+        }
+    }
+
+    let files = |count: usize| if count == 1 { "file" } else { "files" };
+    let mut overview = format!("{} {} in total\n", paths.len(), files(paths.len()));
+    for (dir, count) in counts {
+        if dir.len() > max_depth {
+            continue;
+        }
+        // The top-level directories, at depth 1, are not indented
+        overview.push_str(&format!(
+            "{}{}/ ({} {})\n",
+            "  ".repeat(dir.len() - 1),
+            dir[dir.len() - 1],
+            count,
+            files(count)
+        ));
+    }
+    overview
+}
+
+/// Lowers the depth of the directory overview until the brief fits, down to `min_depth`.
+fn limit_directory_depth(
+    brief: &mut ArchitectureBrief,
+    paths: &[&str],
+    depth: &mut usize,
+    min_depth: usize,
+    fits: &dyn Fn(&ArchitectureBrief) -> Result<bool>,
+) -> Result<()> {
+    if *depth <= min_depth || fits(brief)? {
+        return Ok(());
+    }
+    let mut limited = brief.clone();
+    let decision = |depth: usize| format!("Limited the directory overview to depth {}", depth);
+    while *depth > min_depth {
+        *depth -= 1;
+        limited.directories = directory_overview(paths, *depth);
+        if fits(&limited.with_decision(&decision(*depth)))? {
+            break;
+        }
+    }
+    brief.directories = limited.directories;
+    brief.record(decision(*depth));
+    Ok(())
+}
+
+fn max_depth(paths: &[&str]) -> usize {
+    paths
+        .iter()
+        .map(|path| path.matches('/').count())
+        .max()
+        .unwrap_or(0)
+        .max(1)
+}
+
+/// The outline of a source file in a supported language.
+fn outline(path: &str, content: &str) -> Option<FileOutline> {
+    let lines: Vec<&str> = content.lines().collect();
+    let (_, items) = public_items(path, &lines)?;
+
+    let mut declarations: Vec<String> = items
+        .iter()
+        .map(|(item, _)| declaration(lines[item.line - 1]))
+        .collect();
+    if declarations.len() > OUTLINE_MAX_ITEMS {
+        let more = declarations.len() - OUTLINE_MAX_ITEMS;
+        declarations.truncate(OUTLINE_MAX_ITEMS);
+        declarations.push(format!("... and {} more", more));
+    }
+    let summary = module_summary(&lines);
+    if declarations.is_empty() && summary.is_none() {
+        return None;
+    }
+    Some(FileOutline {
+        path: path.to_string(),
+        summary,
+        items: declarations,
+    })
+}
+
+/// A declaration line without its body.
+fn declaration(line: &str) -> String {
+    let line = line.trim();
+    let line = line
+        .strip_suffix('{')
+        .or_else(|| line.strip_suffix(':'))
+        .unwrap_or(line)
+        .trim_end();
+    shorten(line)
+}
+
+/// The first line of the documentation of a module: `//!`, a docstring or a leading comment.
+pub(crate) fn module_summary(lines: &[&str]) -> Option<String> {
+    let first = lines
+        .iter()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with("#!") && !line.starts_with("\"use "))?;
+    let text = ["//!", "///", "//", "#", "/**", "/*", "\"\"\"", "'''"]
+        .iter()
+        .find_map(|marker| first.strip_prefix(marker))?;
+    let text = text
+        .trim()
+        .trim_end_matches("*/")
+        .trim_end_matches("\"\"\"")
+        .trim_end_matches("'''")
+        .trim();
+    if text.is_empty() {
+        // The text starts on the next line, as in `/**\n * Text`
+        let index = lines.iter().position(|line| line.trim() == first)?;
+        let next = lines.get(index + 1)?.trim().trim_start_matches('*').trim();
+        return (!next.is_empty()).then(|| shorten(next));
+    }
+    Some(shorten(text))
+}
+
+fn shorten(text: &str) -> String {
+    if text.chars().count() <= OUTLINE_MAX_LINE {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(OUTLINE_MAX_LINE - 3).collect();
+    format!("{}...", cut)
+}
+
+fn is_readme(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    file_name.to_lowercase().starts_with("readme")
+}
+
+/// Tests, examples and benchmarks describe the architecture less than the code itself.
+fn is_auxiliary(path: &str) -> bool {
+    let file_name = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("");
+    path.split('/').any(|segment| {
+        matches!(
+            segment,
+            "tests" | "test" | "__tests__" | "examples" | "benches" | "fixtures"
+        )
+    }) || file_name.starts_with("test_")
+        || file_name.ends_with("_test")
+        || file_name.ends_with(".test")
+        || file_name.ends_with(".spec")
+}
+
+/// Keeps the first lines of a README, returns whether anything was removed.
+fn truncate_lines(readme: &mut ReadmeExcerpt, lines: usize) -> bool {
+    if readme.content.lines().count() <= lines {
+        return false;
+    }
+    readme.content = readme
+        .content
+        .lines()
+        .take(lines)
+        .collect::<Vec<_>>()
+        .join("\n");
+    readme.truncated = true;
+    true
+}
+
+/// Names separated by commas, the first ten only.
+fn list(names: &[String]) -> String {
+    const SHOWN: usize = 10;
+    let mut text = names
+        .iter()
+        .take(SHOWN)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if names.len() > SHOWN {
+        text.push_str(&format!(" and {} more", names.len() - SHOWN));
+    }
+    text
+}
@@ -0,0 +1,627 @@
+//! Selection of Bazel and Buck targets.
+//!
+//! Restricts the codebase to the source files of some targets and of the targets they
+//! depend on, transitively. The build graph is read from the `BUILD`, `BUILD.bazel` and
+//! `BUCK` files without invoking Bazel or Buck: the rules are parsed as the Starlark
+//! calls they are, with their `glob` and `select` expressions. Macros defined in `.bzl`
+//! files are not expanded, only the rules written in the build files are known.
+
+use anyhow::{Result, bail};
+use git2::{ObjectType, Repository, TreeWalkMode, TreeWalkResult};
+use globset::{GlobBuilder, GlobSetBuilder};
+use log::{debug, info};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::configuration::Code2PromptConfig;
+use crate::path::walk_builder;
+use crate::util::path_key;
+
+/// File names of the build files declaring the targets of a package
+const BUILD_FILE_NAMES: [&str; 3] = ["BUILD", "BUILD.bazel", "BUCK"];
+
+/// The build files, with their content, and the paths of all the files of a codebase
+type BuildGraphFiles = (Vec<(String, String)>, Vec<String>);
+
+/// The source files of the selected targets and their transitive dependencies
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BazelScope {
+    /// Source files, relative to the root
+    pub sources: BTreeSet<String>,
+    /// Labels of the targets visited, the selected ones and their dependencies
+    pub targets: Vec<String>,
+}
+
+impl BazelScope {
+    /// Resolves the sources in the transitive closure of the targets.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_files` - The path, relative to the root, and the content of each build file
+    /// * `paths` - The paths of all the files of the codebase, relative to the root
+    /// * `targets` - The labels of the selected targets, like `//services/auth:auth`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<BazelScope>` - An error if a selected target is not declared
+    pub fn resolve(
+        build_files: &[(String, String)],
+        paths: &[String],
+        targets: &[String],
+    ) -> Result<Self> {
+        let packages: BTreeSet<String> = build_files
+            .iter()
+            .map(|(path, _)| package_of(path).to_string())
+            .collect();
+        let rules: BTreeMap<(String, String), Vec<Value>> = build_files
+            .iter()
+            .flat_map(|(path, content)| {
+                let package = package_of(path).to_string();
+                parse_rules(content)
+                    .into_iter()
+                    .map(move |(name, attributes)| ((package.clone(), name), attributes))
+            })
+            .collect();
+        let files: BTreeSet<&str> = paths.iter().map(String::as_str).collect();
+
+        let mut pending: Vec<(String, String)> = Vec::new();
+        for target in targets {
+            let label = parse_label(target, "")
+                .filter(|label| rules.contains_key(label))
+                .map(|label| vec![label]);
+            // `//services/auth/...` selects every target of the package and below
+            let label = label.or_else(|| {
+                let package = target.strip_prefix("//")?.strip_suffix("/...")?;
+                let below: Vec<(String, String)> = rules
+                    .keys()
+                    .filter(|(rule_package, _)| is_within(rule_package, package))
+                    .cloned()
+                    .collect();
+                (!below.is_empty()).then_some(below)
+            });
+            match label {
+                Some(labels) => pending.extend(labels),
+                None => bail!("Unknown Bazel target `{}`", target),
+            }
+        }
+
+        let mut scope = BazelScope::default();
+        let mut visited: BTreeSet<(String, String)> = BTreeSet::new();
+        while let Some(label) = pending.pop() {
+            if !visited.insert(label.clone()) {
+                continue;
+            }
+            let (package, _) = &label;
+            for value in &rules[&label] {
+                for reference in value.strings(&files, package, &packages) {
+                    let Some(dependency) = parse_label(&reference, package) else {
+                        continue;
+                    };
+                    if rules.contains_key(&dependency) {
+                        pending.push(dependency);
+                    } else {
+                        // A file of the package, unless it is generated by the build
+                        let path = join(&dependency.0, &dependency.1);
+                        if files.contains(path.as_str()) {
+                            scope.sources.insert(path);
+                        } else {
+                            debug!("Skipped missing Bazel source: {}", path);
+                        }
+                    }
+                }
+            }
+        }
+
+        scope.targets = visited
+            .into_iter()
+            .map(|(package, name)| format!("//{}:{}", package, name))
+            .collect();
+        Ok(scope)
+    }
+
+    /// Whether a file is a source of the selected targets.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_path` - The path of the file, relative to the root
+    pub fn contains(&self, relative_path: &std::path::Path) -> bool {
+        self.sources.contains(&path_key(relative_path))
+    }
+}
+
+/// Resolves the sources of the targets selected by `config.bazel_targets`, from the
+/// build files of the working tree or, when the codebase is read from a revision, of
+/// its tree.
+///
+/// # Arguments
+///
+/// * `config` - The configuration, whose `bazel_targets` are selected
+/// * `git_tree` - The repository and revision the codebase is read from, if any
+///
+/// # Returns
+///
+/// * `Result<Option<BazelScope>>` - `None` if no target is selected
+pub fn bazel_scope(
+    config: &Code2PromptConfig,
+    git_tree: Option<(&Repository, &str)>,
+) -> Result<Option<BazelScope>> {
+    if config.bazel_targets.is_empty() {
+        return Ok(None);
+    }
+    let (build_files, paths) = match git_tree {
+        Some((repo, rev)) => tree_files(repo, rev)?,
+        None => directory_files(config)?,
+    };
+    if build_files.is_empty() {
+        bail!("No BUILD file found, --bazel-target needs a Bazel or Buck workspace");
+    }
+
+    let scope = BazelScope::resolve(&build_files, &paths, &config.bazel_targets)?;
+    info!(
+        "Bazel targets: {}, {} sources",
+        scope.targets.join(", "),
+        scope.sources.len()
+    );
+    Ok(Some(scope))
+}
+
+/// The build files and the paths of all the files of the directory.
+fn directory_files(config: &Code2PromptConfig) -> Result<BuildGraphFiles> {
+    let root = config.path.canonicalize()?;
+    let mut build_files = Vec::new();
+    let mut paths = Vec::new();
+    let walker = walk_builder(&root, config)
+        .build()
+        .filter_map(|entry| entry.ok());
+    for entry in walker {
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(&root) else {
+            continue;
+        };
+        let relative = path_key(relative);
+        if is_build_file(&relative)
+            && let Ok(content) = std::fs::read_to_string(entry.path())
+        {
+            build_files.push((relative.clone(), content));
+        }
+        paths.push(relative);
+    }
+    Ok((build_files, paths))
+}
+
+/// The build files and the paths of all the files of the tree of a revision.
+fn tree_files(repo: &Repository, rev: &str) -> Result<BuildGraphFiles> {
+    let tree = repo.revparse_single(rev)?.peel_to_tree()?;
+    let mut build_files = Vec::new();
+    let mut paths = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() != Some(ObjectType::Blob) {
+            return TreeWalkResult::Ok;
+        }
+        let path = format!("{}{}", root, entry.name().unwrap_or_default());
+        if is_build_file(&path)
+            && let Ok(blob) = repo.find_blob(entry.id())
+        {
+            build_files.push((
+                path.clone(),
+                String::from_utf8_lossy(blob.content()).into_owned(),
+            ));
+        }
+        paths.push(path);
+        TreeWalkResult::Ok
+    })?;
+    Ok((build_files, paths))
+}
+
+fn is_build_file(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    BUILD_FILE_NAMES.contains(&file_name)
+}
+
+/// The package of a build file: its directory, empty at the root of the workspace.
+fn package_of(build_file: &str) -> &str {
+    build_file
+        .rsplit_once('/')
+        .map(|(dir, _)| dir)
+        .unwrap_or("")
+}
+
+fn is_within(package: &str, ancestor: &str) -> bool {
+    ancestor.is_empty()
+        || package == ancestor
+        || package
+            .strip_prefix(ancestor)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn join(package: &str, name: &str) -> String {
+    if package.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", package, name)
+    }
+}
+
+/// The package and name of a label, relative to the package it is written in. Labels
+/// of external repositories are `None`.
+fn parse_label(label: &str, current_package: &str) -> Option<(String, String)> {
+    let label = label.strip_prefix('@').map_or(Some(label), |external| {
+        // `@//` and `@@//` refer to the main repository
+        external
+            .trim_start_matches('@')
+            .strip_prefix("//")
+            .map(|_| &label[label.find("//").unwrap_or(0)..])
+    })?;
+
+    if let Some(absolute) = label.strip_prefix("//") {
+        return Some(match absolute.split_once(':') {
+            Some((package, name)) => (package.to_string(), name.to_string()),
+            // `//services/auth` is `//services/auth:auth`
+            None => {
+                let name = absolute.rsplit('/').next().unwrap_or(absolute);
+                (absolute.to_string(), name.to_string())
+            }
+        });
+    }
+    let name = label.strip_prefix(':').unwrap_or(label);
+    if name.is_empty() || name.contains(':') {
+        return None;
+    }
+    Some((current_package.to_string(), name.to_string()))
+}
+
+/// A Starlark expression, as far as labels are concerned
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    List(Vec<Value>),
+    Dict(Vec<(Value, Value)>),
+    Call(String, Vec<(Option<String>, Value)>),
+    /// `a + b`, the concatenation of lists
+    Concat(Vec<Value>),
+    Other,
+}
+
+impl Value {
+    /// The strings of the expression, with `glob` expanded to the files it matches and
+    /// every branch of `select` taken.
+    fn strings(
+        &self,
+        files: &BTreeSet<&str>,
+        package: &str,
+        packages: &BTreeSet<String>,
+    ) -> Vec<String> {
+        match self {
+            Value::Str(text) => vec![text.clone()],
+            Value::List(items) | Value::Concat(items) => items
+                .iter()
+                .flat_map(|item| item.strings(files, package, packages))
+                .collect(),
+            Value::Dict(entries) => entries
+                .iter()
+                .flat_map(|(_, value)| value.strings(files, package, packages))
+                .collect(),
+            Value::Call(function, arguments) if function == "glob" => {
+                glob(arguments, files, package, packages)
+            }
+            Value::Call(_, arguments) => arguments
+                .iter()
+                .flat_map(|(_, value)| value.strings(files, package, packages))
+                .collect(),
+            Value::Other => Vec::new(),
+        }
+    }
+}
+
+/// The files of a package matched by a `glob(include, exclude = [...])` call, as names
+/// relative to the package. Files of subpackages are not part of the package.
+fn glob(
+    arguments: &[(Option<String>, Value)],
+    files: &BTreeSet<&str>,
+    package: &str,
+    packages: &BTreeSet<String>,
+) -> Vec<String> {
+    let patterns = |value: &Value| -> Vec<String> {
+        match value {
+            Value::List(items) => items
+                .iter()
+                .filter_map(|item| match item {
+                    Value::Str(pattern) => Some(pattern.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+    let mut include = Vec::new();
+    let mut exclude = Vec::new();
+    for (index, (keyword, value)) in arguments.iter().enumerate() {
+        match (keyword.as_deref(), index) {
+            (Some("include"), _) | (None, 0) => include.extend(patterns(value)),
+            (Some("exclude"), _) | (None, 1) => exclude.extend(patterns(value)),
+            _ => {}
+        }
+    }
+
+    let build = |patterns: &[String]| {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            match GlobBuilder::new(pattern).literal_separator(true).build() {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(error) => debug!("Invalid glob pattern '{}': {}", pattern, error),
+            }
+        }
+        builder.build().unwrap_or_default()
+    };
+    let (include, exclude) = (build(&include), build(&exclude));
+
+    files
+        .iter()
+        .filter_map(|path| {
+            let name = if package.is_empty() {
+                *path
+            } else {
+                path.strip_prefix(package)?.strip_prefix('/')?
+            };
+            let in_subpackage = packages.iter().any(|other| {
+                other.len() > package.len() && is_within(other, package) && is_within(path, other)
+            });
+            (!in_subpackage && include.is_match(name) && !exclude.is_match(name))
+                .then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// The rules of a build file: the calls with a `name` argument, with the values of
+/// their other arguments.
+fn parse_rules(content: &str) -> Vec<(String, Vec<Value>)> {
+    let tokens = tokenize(content);
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+    };
+    let mut rules = Vec::new();
+
+    while parser.position < parser.tokens.len() {
+        let is_call = matches!(parser.peek(), Some(Token::Identifier(_)))
+            && parser.tokens.get(parser.position + 1) == Some(&Token::Punct('('));
+        if !is_call {
+            parser.position += 1;
+            continue;
+        }
+        if let Value::Call(_, arguments) = parser.expression() {
+            let name = arguments.iter().find_map(|(keyword, value)| match value {
+                Value::Str(name) if keyword.as_deref() == Some("name") => Some(name.clone()),
+                _ => None,
+            });
+            if let Some(name) = name {
+                let values = arguments
+                    .into_iter()
+                    .filter(|(keyword, _)| {
+                        !matches!(
+                            keyword.as_deref(),
+                            Some("name" | "visibility" | "tags" | "licenses")
+                        )
+                    })
+                    .map(|(_, value)| value)
+                    .collect();
+                rules.push((name, values));
+            }
+        }
+    }
+    rules
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Str(String),
+    Punct(char),
+    /// Numbers and operators other than `+`
+    Other,
+}
+
+fn tokenize(content: &str) -> Vec<Token> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '"' || c == '\'' {
+            let triple = chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c);
+            let quote_len = if triple { 3 } else { 1 };
+            i += quote_len;
+            let mut text = String::new();
+            while i < chars.len() {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    text.push(chars[i + 1]);
+                    i += 2;
+                    continue;
+                }
+                let closes = chars[i] == c
+                    && (!triple || (chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c)));
+                if closes {
+                    i += quote_len;
+                    break;
+                }
+                text.push(chars[i]);
+                i += 1;
+            }
+            tokens.push(Token::Str(text));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Identifier(chars[start..i].iter().collect()));
+        } else if "()[]{},=+:.".contains(c) {
+            tokens.push(Token::Punct(c));
+            i += 1;
+        } else {
+            tokens.push(Token::Other);
+            i += 1;
+        }
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn eat(&mut self, punct: char) -> bool {
+        if self.peek() == Some(&Token::Punct(punct)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// An expression, with `+` concatenations.
+    fn expression(&mut self) -> Value {
+        let mut operands = vec![self.operand()];
+        while self.eat('+') {
+            operands.push(self.operand());
+        }
+        if operands.len() == 1 {
+            operands.pop().unwrap_or(Value::Other)
+        } else {
+            Value::Concat(operands)
+        }
+    }
+
+    fn operand(&mut self) -> Value {
+        let value = match self.peek().cloned() {
+            Some(Token::Str(mut text)) => {
+                self.position += 1;
+                // Adjacent string literals are concatenated
+                while let Some(Token::Str(next)) = self.peek() {
+                    text.push_str(next);
+                    self.position += 1;
+                }
+                Value::Str(text)
+            }
+            Some(Token::Punct('[')) => {
+                self.position += 1;
+                Value::List(self.sequence(']'))
+            }
+            Some(Token::Punct('(')) => {
+                self.position += 1;
+                let items = self.sequence(')');
+                Value::List(items)
+            }
+            Some(Token::Punct('{')) => {
+                self.position += 1;
+                let mut entries = Vec::new();
+                while self.peek().is_some() && !self.eat('}') {
+                    let key = self.expression();
+                    let value = if self.eat(':') {
+                        self.expression()
+                    } else {
+                        Value::Other
+                    };
+                    entries.push((key, value));
+                    if !self.eat(',') && self.peek() != Some(&Token::Punct('}')) {
+                        self.skip_to(&['}']);
+                    }
+                }
+                Value::Dict(entries)
+            }
+            Some(Token::Identifier(mut name)) => {
+                self.position += 1;
+                // Dotted names, like `native.cc_library`
+                while self.eat('.') {
+                    if let Some(Token::Identifier(part)) = self.peek().cloned() {
+                        self.position += 1;
+                        name = format!("{}.{}", name, part);
+                    }
+                }
+                if self.eat('(') {
+                    let mut arguments = Vec::new();
+                    while self.peek().is_some() && !self.eat(')') {
+                        let keyword =
+                            match (self.peek().cloned(), self.tokens.get(self.position + 1)) {
+                                (Some(Token::Identifier(keyword)), Some(Token::Punct('='))) => {
+                                    self.position += 2;
+                                    Some(keyword)
+                                }
+                                _ => None,
+                            };
+                        arguments.push((keyword, self.expression()));
+                        if !self.eat(',') && self.peek() != Some(&Token::Punct(')')) {
+                            self.skip_to(&[',', ')']);
+                            self.eat(',');
+                        }
+                    }
+                    Value::Call(name, arguments)
+                } else {
+                    Value::Other
+                }
+            }
+            Some(_) => {
+                self.position += 1;
+                Value::Other
+            }
+            None => Value::Other,
+        };
+        // Indexing and method calls on the value are not followed
+        if self.peek() == Some(&Token::Punct('[')) {
+            self.position += 1;
+            self.sequence(']');
+        }
+        value
+    }
+
+    /// The comma-separated items of a list, up to its closing bracket.
+    fn sequence(&mut self, close: char) -> Vec<Value> {
+        let mut items = Vec::new();
+        while self.peek().is_some() && !self.eat(close) {
+            // Comprehensions are not evaluated
+            if matches!(self.peek(), Some(Token::Identifier(keyword)) if keyword == "for") {
+                self.skip_to(&[close]);
+                continue;
+            }
+            items.push(self.expression());
+            if !self.eat(',') && self.peek() != Some(&Token::Punct(close)) {
+                self.skip_to(&[',', close]);
+                self.eat(',');
+            }
+        }
+        items
+    }
+
+    /// Skips tokens up to one of `stops` at the current nesting level.
+    fn skip_to(&mut self, stops: &[char]) {
+        let mut depth = 0usize;
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Punct(c) if depth == 0 && stops.contains(c) => return,
+                Token::Punct('(' | '[' | '{') => depth += 1,
+                Token::Punct(')' | ']' | '}') => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+            self.position += 1;
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Handling of binary files.
+//!
+//! Binary files (images, archives, compiled artifacts...) are recognized by inspecting the
+//! start of their content for null bytes and non-text encodings. Depending on the
+//! [`BinaryHandling`], they are skipped, listed with their size but without content, or
+//! included encoded in base64.
+
+use content_inspector::{ContentType, inspect};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::configuration::Code2PromptConfig;
+use crate::language::source_stats;
+use crate::path::{EntryMetadata, FileEntry, wrap_code_block};
+use crate::tokenizer::count_tokens_with_mode;
+use crate::util::content_hash;
+
+/// Number of bytes inspected to tell binary files from text files
+pub const BINARY_SAMPLE_SIZE: usize = 8192;
+
+/// Width of the lines of base64 content
+const BASE64_LINE_WIDTH: usize = 76;
+
+/// How binary files are handled.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryHandling {
+    /// Left out of the prompt. They still appear in the source tree.
+    #[default]
+    Skip,
+    /// Listed with their size, without their content.
+    PathOnly,
+    /// Included with their content encoded in base64.
+    Base64,
+}
+
+impl std::fmt::Display for BinaryHandling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinaryHandling::Skip => write!(f, "skip"),
+            BinaryHandling::PathOnly => write!(f, "path_only"),
+            BinaryHandling::Base64 => write!(f, "base64"),
+        }
+    }
+}
+
+/// Whether content is binary, judging from its first [`BINARY_SAMPLE_SIZE`] bytes.
+pub fn is_binary(content: &[u8]) -> bool {
+    inspect(&content[..content.len().min(BINARY_SAMPLE_SIZE)]) == ContentType::BINARY
+}
+
+/// Builds the FileEntry of a binary file, following `config.binary_handling`.
+///
+/// # Arguments
+///
+/// * `file_path` - The path displayed in the prompt
+/// * `content` - The raw file content
+/// * `metadata` - The file metadata
+/// * `mod_time` - The modification time, when date sorting is requested
+/// * `config` - The configuration
+///
+/// # Returns
+///
+/// * `Option<FileEntry>` - The file entry, or `None` if binary files are skipped
+pub(crate) fn binary_file_entry(
+    file_path: String,
+    content: &[u8],
+    metadata: EntryMetadata,
+    mod_time: Option<u64>,
+    config: &Code2PromptConfig,
+) -> Option<FileEntry> {
+    let extension = Path::new(&file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_string();
+    let code = match config.binary_handling {
+        BinaryHandling::Skip => return None,
+        BinaryHandling::PathOnly => format!("(binary file, {})", format_size(content.len())),
+        BinaryHandling::Base64 => wrap_code_block(
+            &encode_base64(content),
+            "base64",
+            false,
+            config.no_codeblock,
+        ),
+    };
+    let token_count = count_tokens_with_mode(
+        &code,
+        &extension,
+        &config.encoding,
+        config.tokenizer_model.as_deref(),
+        config.token_count_mode,
+    );
+
+    Some(FileEntry {
+        stats: source_stats(Path::new(&file_path), ""),
+        symbols: None,
+        path: file_path,
+        extension,
+        code,
+        token_count,
+        content_hash: format!("{:016x}", content_hash(content)),
+        metadata,
+        mod_time,
+        history: None,
+        line_range: None,
+    })
+}
+
+/// A size in bytes as `980 B`, `12.3 KB` or `4.5 MB`.
+fn format_size(bytes: usize) -> String {
+    let (value, unit) = match bytes {
+        0..1_000 => return format!("{} B", bytes),
+        1_000..1_000_000 => (bytes as f64 / 1_000.0, "KB"),
+        _ => (bytes as f64 / 1_000_000.0, "MB"),
+    };
+    let text = format!("{:.1}", value);
+    format!("{} {}", text.trim_end_matches(".0"), unit)
+}
+
+/// Encodes content in base64 (RFC 4648, with padding), in lines of 76 characters.
+///
+/// # Arguments
+///
+/// * `content` - The bytes to encode
+///
+/// # Returns
+///
+/// * `String` - The encoded content
+pub fn encode_base64(content: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = Vec::with_capacity(content.len().div_ceil(3) * 4);
+    for chunk in content.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let group = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize]);
+            } else {
+                encoded.push(b'=');
+            }
+        }
+    }
+    encoded
+        .chunks(BASE64_LINE_WIDTH)
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
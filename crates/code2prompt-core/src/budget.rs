@@ -0,0 +1,206 @@
+//! Token budget enforcement.
+//!
+//! When the prompt would exceed `max_tokens`, files are dropped or truncated, following a
+//! [`BudgetStrategy`], until it fits. Template sections marked with a priority are trimmed
+//! around the files: those with a negative priority first, the others last. The sections
+//! trimmed and the files that were dropped or truncated are reported, so that the caller
+//! knows what the model will not see.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::configuration::Code2PromptConfig;
+use crate::path::FileEntry;
+use crate::tokenizer::count_tokens_with_mode;
+
+/// Tokens of the header, fences and separators of a file, besides its path
+pub(crate) const FILE_OVERHEAD_TOKENS: usize = 8;
+
+/// A truncated file keeps at least this many tokens, or it is dropped
+const MIN_TRUNCATED_TOKENS: usize = 64;
+
+/// Which files give way when the prompt exceeds its token budget.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetStrategy {
+    /// Drop the largest files first, keeping as many files as possible.
+    #[default]
+    Largest,
+    /// Drop the smallest contributions first, keeping the large central files whole.
+    Smallest,
+    /// Drop files from the end of the sort order, keeping the first ones.
+    Order,
+    /// Truncate the largest files first, dropping them only if little would be left.
+    Truncate,
+}
+
+impl std::fmt::Display for BudgetStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetStrategy::Largest => write!(f, "largest"),
+            BudgetStrategy::Smallest => write!(f, "smallest"),
+            BudgetStrategy::Order => write!(f, "order"),
+            BudgetStrategy::Truncate => write!(f, "truncate"),
+        }
+    }
+}
+
+/// What to do with a file, when the token budget is negotiated file by file instead of
+/// following a [`BudgetStrategy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetAction {
+    /// Leave the file out of the prompt.
+    Drop,
+    /// Keep the signatures of the file only, its function bodies left out.
+    Summarize,
+    /// Cut the end of the file, so that the prompt fits if half the file is enough.
+    Truncate,
+}
+
+impl std::fmt::Display for BudgetAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetAction::Drop => write!(f, "drop"),
+            BudgetAction::Summarize => write!(f, "summarize"),
+            BudgetAction::Truncate => write!(f, "truncate"),
+        }
+    }
+}
+
+/// What was removed from the prompt to fit the token budget
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BudgetReport {
+    /// The token budget
+    pub max_tokens: usize,
+    /// Paths of the files left out of the prompt
+    pub dropped_files: Vec<String>,
+    /// Paths of the files whose end was cut
+    pub truncated_files: Vec<String>,
+    /// Paths of the files reduced to their signatures
+    pub summarized_files: Vec<String>,
+    /// Names of the template sections left out, in the order they were trimmed
+    pub trimmed_sections: Vec<String>,
+    /// Whether the prompt fits the budget, the rest of the prompt may exceed it on its own
+    pub fits: bool,
+}
+
+/// Drops or truncates files until about `excess` tokens are freed.
+///
+/// # Arguments
+///
+/// * `files` - The files of the prompt, in their sort order
+/// * `excess` - The number of tokens to free
+/// * `config` - The configuration, giving the strategy and the tokenizer
+/// * `report` - The report the dropped and truncated files are added to
+pub fn prune_files(
+    files: &mut Vec<FileEntry>,
+    excess: usize,
+    config: &Code2PromptConfig,
+    report: &mut BudgetReport,
+) {
+    let cost = |file: &FileEntry| {
+        file.token_count
+            + count_tokens_with_mode(
+                &file.path,
+                "",
+                &config.encoding,
+                config.tokenizer_model.as_deref(),
+                config.token_count_mode,
+            )
+            + FILE_OVERHEAD_TOKENS
+    };
+
+    let mut order: Vec<usize> = (0..files.len()).collect();
+    match config.budget_strategy {
+        BudgetStrategy::Largest | BudgetStrategy::Truncate => {
+            order.sort_by_key(|&index| std::cmp::Reverse(files[index].token_count))
+        }
+        BudgetStrategy::Smallest => order.sort_by_key(|&index| files[index].token_count),
+        BudgetStrategy::Order => order.reverse(),
+    }
+
+    let mut freed = 0;
+    let mut dropped = vec![false; files.len()];
+    for index in order {
+        if freed >= excess {
+            break;
+        }
+        let file = &mut files[index];
+        let file_cost = cost(file);
+        let remaining = excess - freed;
+        if config.budget_strategy == BudgetStrategy::Truncate
+            && file.token_count >= remaining + MIN_TRUNCATED_TOKENS
+        {
+            truncate_file(file, file.token_count - remaining, config);
+            if !report.truncated_files.contains(&file.path) {
+                report.truncated_files.push(file.path.clone());
+            }
+            // The token count of the truncated file is only estimated, the next pass
+            // measures what was actually freed before giving up on other files
+            break;
+        } else {
+            freed += file_cost;
+            dropped[index] = true;
+            report.dropped_files.push(file.path.clone());
+        }
+    }
+
+    let mut index = 0;
+    files.retain(|_| {
+        index += 1;
+        !dropped[index - 1]
+    });
+}
+
+/// Cuts the end of a file so that it keeps about `keep_tokens` tokens, with a marker
+/// telling how many lines were omitted.
+pub(crate) fn truncate_file(file: &mut FileEntry, keep_tokens: usize, config: &Code2PromptConfig) {
+    // The content sits between the opening fence line and the closing fence
+    let fenced = !config.no_codeblock;
+    let (opening, content) = match file.code.split_once('\n') {
+        Some((fence, rest)) if fenced => (Some(fence), rest.strip_suffix("```").unwrap_or(rest)),
+        _ => (None, file.code.as_str()),
+    };
+
+    let mut lines: Vec<&str> = content.lines().collect();
+    // A file truncated by a previous pass keeps counting the lines of the original file
+    let marker = Regex::new(r"^\.\.\. \[truncated: \d+ of (\d+) lines omitted\]$").unwrap();
+    let mut total_lines = lines.len();
+    if let Some(captures) = lines.last().and_then(|line| marker.captures(line)) {
+        total_lines = captures[1].parse().unwrap_or(total_lines);
+        lines.pop();
+    }
+
+    let total_chars = content.len().max(1);
+    let keep_chars = total_chars * keep_tokens / file.token_count.max(1);
+    let mut kept_chars = 0;
+    let kept_lines = lines
+        .iter()
+        .take_while(|line| {
+            kept_chars += line.len() + 1;
+            kept_chars <= keep_chars
+        })
+        .count()
+        // At least one line goes, so that each pass makes progress
+        .min(lines.len().saturating_sub(1));
+
+    let mut kept = lines[..kept_lines].join("\n");
+    kept.push_str(&format!(
+        "\n... [truncated: {} of {} lines omitted]",
+        total_lines - kept_lines,
+        total_lines
+    ));
+    let token_count = count_tokens_with_mode(
+        &kept,
+        &file.extension,
+        &config.encoding,
+        config.tokenizer_model.as_deref(),
+        config.token_count_mode,
+    );
+
+    file.code = match opening {
+        Some(fence) => format!("{}\n{}\n```", fence, kept),
+        None => kept,
+    };
+    file.token_count = token_count;
+}
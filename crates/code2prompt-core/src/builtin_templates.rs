@@ -40,6 +40,14 @@ impl BuiltinTemplates {
                         description: "Default XML template for code analysis",
                     },
                 ),
+                (
+                    "architecture-brief",
+                    BuiltinTemplate {
+                        name: "Architecture Brief",
+                        content: include_str!("../templates/architecture-brief.hbs"),
+                        description: "Template for describing the architecture of a project from a brief fitting a token budget",
+                    },
+                ),
                 (
                     "binary-exploitation-ctf-solver",
                     BuiltinTemplate {
@@ -56,6 +64,14 @@ impl BuiltinTemplates {
                         description: "Template for code cleanup and refactoring",
                     },
                 ),
+                (
+                    "commit-message",
+                    BuiltinTemplate {
+                        name: "Commit Message",
+                        content: include_str!("../templates/commit-message.hbs"),
+                        description: "Template for writing a commit message from the staged diff, in the style of recent commits",
+                    },
+                ),
                 (
                     "cryptography-ctf-solver",
                     BuiltinTemplate {
@@ -96,6 +112,22 @@ impl BuiltinTemplates {
                         description: "Template for performance optimization",
                     },
                 ),
+                (
+                    "onboarding-tour",
+                    BuiltinTemplate {
+                        name: "Onboarding Tour",
+                        content: include_str!("../templates/onboarding-tour.hbs"),
+                        description: "Template for explaining a codebase to a new hire from its files ordered as a learning path",
+                    },
+                ),
+                (
+                    "pr-description",
+                    BuiltinTemplate {
+                        name: "Pull Request Description",
+                        content: include_str!("../templates/pr-description.hbs"),
+                        description: "Template for writing a pull request description from the branch diff, commits and touched files",
+                    },
+                ),
                 (
                     "refactor",
                     BuiltinTemplate {
@@ -104,6 +136,14 @@ impl BuiltinTemplates {
                         description: "Template for code refactoring",
                     },
                 ),
+                (
+                    "release-notes",
+                    BuiltinTemplate {
+                        name: "Release Notes",
+                        content: include_str!("../templates/release-notes.hbs"),
+                        description: "Template for writing markdown release notes from the commits, merged pull requests and diff since a release",
+                    },
+                ),
                 (
                     "reverse-engineering-ctf-solver",
                     BuiltinTemplate {
@@ -144,6 +184,14 @@ impl BuiltinTemplates {
                         description: "Template for generating GitHub README files",
                     },
                 ),
+                (
+                    "write-missing-docs",
+                    BuiltinTemplate {
+                        name: "Write Missing Docs",
+                        content: include_str!("../templates/write-missing-docs.hbs"),
+                        description: "Template for documenting the public items reported by the documentation coverage analysis",
+                    },
+                ),
             ])
         })
     }
@@ -0,0 +1,259 @@
+//! This module splits the files into overlapping chunks of lines for the `chunks` output
+//! format, one JSON object per line, to be ingested by a vector store.
+//!
+//! The ID of a chunk is a hash of its path relative to the root, its lines and its content,
+//! so that unchanged chunks keep their ID across runs and machines.
+//!
+//! The lines of a chunk are numbered as in the file. A file whose content is transformed
+//! before it is embedded (a data sample, a log summary, compressed signatures...) no longer
+//! has lines matching those of the file: its chunks have no line numbers and are marked
+//! `transformed`.
+
+use crate::configuration::Code2PromptConfig;
+use crate::line_range::LineRange;
+use crate::path::{FileEntry, fence_language};
+use crate::tokenizer::count_tokens_with_mode;
+use crate::util::content_hash;
+use crate::util::path_key;
+use serde::{Deserialize, Serialize};
+
+/// Default number of lines of a chunk
+pub const DEFAULT_CHUNK_LINES: usize = 60;
+
+/// Default number of lines a chunk shares with the previous one
+pub const DEFAULT_CHUNK_OVERLAP: usize = 10;
+
+/// Size of the chunks and their overlap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkOptions {
+    /// Maximum number of lines of a chunk
+    pub lines: usize,
+    /// Number of lines a chunk repeats from the end of the previous one, lower than `lines`
+    pub overlap: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            lines: DEFAULT_CHUNK_LINES,
+            overlap: DEFAULT_CHUNK_OVERLAP,
+        }
+    }
+}
+
+/// A chunk of a file, as written on a line of the `chunks` output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chunk {
+    /// Stable ID of the chunk, a hash of its relative path, lines and content
+    pub id: String,
+    /// Path of the file, as in the other output formats
+    pub path: String,
+    /// First line of the chunk in the file, numbered from 1, none if the file is transformed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    /// Last line of the chunk in the file, included, none if the file is transformed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    /// Whether the content of the file was transformed before it was embedded, so that its
+    /// lines do not match those of the file
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub transformed: bool,
+    /// Language tag of the file, as in its code fence
+    pub language: String,
+    pub content: String,
+    pub token_count: usize,
+    /// Content hash of the whole file
+    pub file_hash: String,
+    /// Position of the chunk in the file, from 0
+    pub chunk_index: usize,
+    pub chunk_count: usize,
+    /// Number of lines shared with the previous chunk
+    pub overlap: usize,
+}
+
+/// Splits a file into chunks of lines, each repeating the last lines of the previous one.
+///
+/// The lines are those of the code embedded in the prompt, without its code fence and line
+/// numbers. When only ranges of the file are embedded, each range is split on its own, so
+/// that a chunk never spans the lines left out.
+///
+/// # Arguments
+///
+/// * `file` - The file to split
+/// * `config` - The configuration, giving the chunk options, the languages and the tokenizer
+///
+/// # Returns
+///
+/// * `Vec<Chunk>` - The chunks, none for an empty file
+pub fn chunk_file(file: &FileEntry, config: &Code2PromptConfig) -> Vec<Chunk> {
+    let size = config.chunk_options.lines.max(1);
+    let overlap = config.chunk_options.overlap.min(size - 1);
+    let relative_path = path_key(&config.root_relative_path(&file.path));
+    let body = unwrap_code_block(&file.code, config);
+
+    // The lines of a transformed file are numbered by their place in the embedded code,
+    // for the IDs only
+    let (runs, transformed) = match source_lines(file, body, config) {
+        Some(runs) => (runs, false),
+        None => {
+            let lines = body
+                .lines()
+                .enumerate()
+                .map(|(index, line)| (index + 1, line));
+            (vec![lines.collect()], true)
+        }
+    };
+
+    let mut windows = Vec::new();
+    for run in &runs {
+        let mut start = 0;
+        while start < run.len() {
+            let end = (start + size).min(run.len());
+            windows.push((&run[start..end], if start == 0 { 0 } else { overlap }));
+            if end == run.len() {
+                break;
+            }
+            start = end - overlap;
+        }
+    }
+
+    let chunk_count = windows.len();
+    windows
+        .into_iter()
+        .enumerate()
+        .map(|(chunk_index, (lines, overlap))| {
+            let start_line = lines[0].0;
+            let end_line = lines[lines.len() - 1].0;
+            let content = lines
+                .iter()
+                .map(|(_, line)| *line)
+                .collect::<Vec<_>>()
+                .join("\n");
+            let key = format!(
+                "{}\0{}\0{}\0{}",
+                relative_path, start_line, end_line, content
+            );
+            Chunk {
+                id: format!("{:016x}", content_hash(key.as_bytes())),
+                path: file.path.clone(),
+                start_line: (!transformed).then_some(start_line),
+                end_line: (!transformed).then_some(end_line),
+                transformed,
+                language: fence_language(&file.extension, config).to_string(),
+                token_count: count_tokens_with_mode(
+                    &content,
+                    &file.extension,
+                    &config.encoding,
+                    config.tokenizer_model.as_deref(),
+                    config.token_count_mode,
+                ),
+                content,
+                file_hash: file.content_hash.clone(),
+                chunk_index,
+                chunk_count,
+                overlap,
+            }
+        })
+        .collect()
+}
+
+/// The embedded lines of a file with their numbers in the file, in runs of consecutive
+/// lines.
+///
+/// # Arguments
+///
+/// * `file` - The file
+/// * `body` - The embedded code, without its code fence
+/// * `config` - The configuration, telling whether the lines are numbered
+///
+/// # Returns
+///
+/// * `Option<Vec<Vec<(usize, &str)>>>` - The runs of lines, `None` if the content of the
+///   file was transformed
+fn source_lines<'a>(
+    file: &FileEntry,
+    body: &'a str,
+    config: &Code2PromptConfig,
+) -> Option<Vec<Vec<(usize, &'a str)>>> {
+    let lines: Vec<&str> = body
+        .lines()
+        .map(|line| {
+            line.split_once(" | ")
+                .filter(|(number, _)| config.line_numbers && number.trim().parse::<usize>().is_ok())
+                .map_or(line, |(_, line)| line)
+        })
+        .collect();
+
+    let Some(line_range) = &file.line_range else {
+        // The whole file is embedded, as read unless its content hash differs
+        return is_file_content(&lines, body, &file.content_hash).then(|| {
+            vec![
+                lines
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, line)| (index + 1, line))
+                    .collect(),
+            ]
+        });
+    };
+
+    // The lines kept of each range follow each other, with a `...` line where lines are
+    // left out between them
+    let ranges = line_range
+        .split(", ")
+        .map(str::parse)
+        .collect::<anyhow::Result<Vec<LineRange>>>()
+        .ok()?;
+    let mut lines = lines.into_iter().peekable();
+    let mut runs: Vec<Vec<(usize, &str)>> = Vec::new();
+    let mut next_line = 1;
+    for range in ranges {
+        let start = range.start.max(next_line);
+        if start > range.end || lines.peek().is_none() {
+            continue;
+        }
+        if next_line > 1 && start > next_line {
+            lines.next();
+            runs.push(Vec::new());
+        } else if runs.is_empty() {
+            runs.push(Vec::new());
+        }
+        let run = runs.last_mut()?;
+        for number in start..=range.end {
+            let Some(line) = lines.next() else {
+                break;
+            };
+            run.push((number, line));
+            next_line = number + 1;
+        }
+    }
+    Some(runs)
+}
+
+/// Whether the embedded lines are the whole content of the file, compared by content hash.
+fn is_file_content(lines: &[&str], body: &str, file_hash: &str) -> bool {
+    let matches = |text: &str| format!("{:016x}", content_hash(text.as_bytes())) == file_hash;
+    // Line endings and the final line break are lost when the lines are numbered
+    matches(body)
+        || ["\n", "\r\n"].iter().any(|ending| {
+            let text = lines.join(ending);
+            matches(&text) || matches(&format!("{}{}", text, ending))
+        })
+}
+
+/// The code of a file without the code fence wrapped around it.
+fn unwrap_code_block<'a>(code: &'a str, config: &Code2PromptConfig) -> &'a str {
+    if config.no_codeblock {
+        return code;
+    }
+    let Some((opening, rest)) = code.split_once('\n') else {
+        return code;
+    };
+    if !opening.starts_with("```") {
+        return code;
+    }
+    rest.strip_suffix("```")
+        .map(|body| body.strip_suffix('\n').unwrap_or(body))
+        .unwrap_or(rest)
+}
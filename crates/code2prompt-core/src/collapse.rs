@@ -0,0 +1,150 @@
+//! This module collapses directories in the output, such as vendored code to acknowledge
+//! without expanding it.
+//!
+//! The directories matched by `collapse_patterns` are drawn in the tree as a single line,
+//! as `vendor/ (42 files, 18k tokens)`, and their files are replaced in the prompt with a
+//! single entry summarizing them. The selection is left as it is: the files are still
+//! read and counted, only their rendering changes.
+
+use crate::configuration::Code2PromptConfig;
+use crate::filter::build_globset;
+use crate::fixture::compact_count;
+use crate::language::SourceStats;
+use crate::path::{EntryMetadata, FileEntry};
+use crate::tokenizer::count_tokens_with_mode;
+use crate::util::content_hash;
+use globset::GlobSet;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Builds the set of the directories to collapse. A trailing `/` or `/**` is ignored, so
+/// that `vendor/` and `vendor/**` match the directory itself.
+///
+/// # Arguments
+///
+/// * `patterns` - The glob patterns of the directories, relative to the root
+pub(crate) fn collapse_globset(patterns: &[String]) -> GlobSet {
+    let patterns: Vec<String> = patterns
+        .iter()
+        .map(|pattern| {
+            let pattern = pattern.trim_end_matches('/');
+            pattern.strip_suffix("/**").unwrap_or(pattern).to_string()
+        })
+        .collect();
+    build_globset(&patterns)
+}
+
+/// The topmost collapsed directory holding a file, if any.
+///
+/// # Arguments
+///
+/// * `relative_path` - The path of the file, relative to the root
+/// * `collapse` - The set of the directories to collapse
+pub(crate) fn collapsed_directory(relative_path: &Path, collapse: &GlobSet) -> Option<PathBuf> {
+    let mut directory = PathBuf::new();
+    let mut components = relative_path.components().peekable();
+    while let Some(component) = components.next() {
+        // The last component is the file itself
+        components.peek()?;
+        directory.push(component);
+        if collapse.is_match(&directory) {
+            return Some(directory);
+        }
+    }
+    None
+}
+
+/// Replaces the files of each collapsed directory with a single entry summarizing them.
+///
+/// # Arguments
+///
+/// * `files` - The files of the prompt
+/// * `config` - The configuration giving `collapse_patterns`
+///
+/// # Returns
+///
+/// * `Vec<FileEntry>` - The files outside the collapsed directories, then a summary per
+///   collapsed directory, to be sorted
+pub(crate) fn collapse_files(files: Vec<FileEntry>, config: &Code2PromptConfig) -> Vec<FileEntry> {
+    if config.collapse_patterns.is_empty() {
+        return files;
+    }
+    let collapse = collapse_globset(&config.collapse_patterns);
+
+    let mut kept = Vec::with_capacity(files.len());
+    let mut directories: BTreeMap<PathBuf, (String, Vec<FileEntry>)> = BTreeMap::new();
+    for file in files {
+        let relative_path = config.root_relative_path(&file.path);
+        let Some(directory) = collapsed_directory(&relative_path, &collapse) else {
+            kept.push(file);
+            continue;
+        };
+        // The directory is displayed as its files are: absolute, aliased or relative
+        let depth = relative_path
+            .strip_prefix(&directory)
+            .map_or(0, |inner| inner.components().count());
+        let display_path = Path::new(&file.path)
+            .ancestors()
+            .nth(depth)
+            .map_or_else(|| directory.clone(), Path::to_path_buf)
+            .to_string_lossy()
+            .replace('\\', "/");
+        directories
+            .entry(directory)
+            .or_insert_with(|| (display_path, Vec::new()))
+            .1
+            .push(file);
+    }
+
+    kept.extend(
+        directories
+            .into_values()
+            .map(|(display_path, files)| summary_entry(display_path, &files, config)),
+    );
+    kept
+}
+
+/// The entry standing in for the files of a collapsed directory.
+fn summary_entry(path: String, files: &[FileEntry], config: &Code2PromptConfig) -> FileEntry {
+    let tokens: usize = files.iter().map(|file| file.token_count).sum();
+    let count = match files.len() {
+        1 => "1 file".to_string(),
+        count => format!("{} files", count),
+    };
+    let code = format!(
+        "(collapsed directory: {}, {} tokens)",
+        count,
+        compact_count(tokens)
+    );
+    let hashes: String = files
+        .iter()
+        .map(|file| file.content_hash.as_str())
+        .collect();
+    FileEntry {
+        token_count: count_tokens_with_mode(
+            &code,
+            "",
+            &config.encoding,
+            config.tokenizer_model.as_deref(),
+            config.token_count_mode,
+        ),
+        path,
+        extension: String::new(),
+        code,
+        content_hash: format!("{:016x}", content_hash(hashes.as_bytes())),
+        metadata: EntryMetadata {
+            is_dir: true,
+            is_symlink: false,
+        },
+        mod_time: files.iter().filter_map(|file| file.mod_time).max(),
+        history: None,
+        line_range: None,
+        stats: SourceStats {
+            line_count: files.iter().map(|file| file.stats.line_count).sum(),
+            blank_lines: files.iter().map(|file| file.stats.blank_lines).sum(),
+            comment_lines: files.iter().map(|file| file.stats.comment_lines).sum(),
+            ..SourceStats::default()
+        },
+        symbols: None,
+    }
+}
@@ -0,0 +1,523 @@
+//! Code-aware compression of source files.
+//!
+//! At the `signatures` level, the bodies of the functions are replaced with `...`, keeping
+//! the signatures, the doc comments and the type definitions: the API surface of a file
+//! for a fraction of its tokens. The languages with braces are read by a lexer aware of
+//! their strings and comments, Python by its indentation, with a lexer following its
+//! strings. Files in other languages are embedded in full.
+//!
+//! The lexers are a best effort, not parsers: a file they cannot follow to its end, such
+//! as one with a string or a block left open, is embedded in full rather than cut short.
+
+use serde::{Deserialize, Serialize};
+
+/// Extensions of the languages whose blocks are delimited by braces
+const BRACE_EXTENSIONS: &[&str] = &[
+    "rs", "go", "java", "js", "jsx", "mjs", "cjs", "ts", "tsx", "c", "h", "cc", "cpp", "cxx",
+    "hpp", "hh", "cs", "kt", "kts", "swift", "scala", "php", "dart",
+];
+
+/// Extensions of the languages of the JavaScript family, which have regex literals
+const JAVASCRIPT_EXTENSIONS: &[&str] = &["js", "jsx", "mjs", "cjs", "ts", "tsx"];
+
+/// Keywords introducing a function, whose block is its body
+const FUNCTION_KEYWORDS: &[&str] = &["fn", "fun", "func", "function", "def"];
+
+/// Keywords introducing a definition, whose block holds its members
+const CONTAINER_KEYWORDS: &[&str] = &[
+    "struct",
+    "enum",
+    "union",
+    "impl",
+    "trait",
+    "class",
+    "interface",
+    "mod",
+    "namespace",
+    "extern",
+    "object",
+    "record",
+    "protocol",
+    "extension",
+    "module",
+];
+
+/// How much of the source files is kept.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionLevel {
+    /// The whole file.
+    #[default]
+    Full,
+    /// The signatures, doc comments and type definitions, without the function bodies.
+    Signatures,
+}
+
+impl std::fmt::Display for CompressionLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompressionLevel::Full => write!(f, "full"),
+            CompressionLevel::Signatures => write!(f, "signatures"),
+        }
+    }
+}
+
+/// Compresses the code of a file to a level.
+///
+/// # Arguments
+///
+/// * `code` - The content of the file
+/// * `extension` - The extension of the file, telling its language
+/// * `level` - The compression level
+///
+/// # Returns
+///
+/// * `Option<String>` - The compressed code, or `None` when the file is kept in full,
+///   its language not being supported, the lexer not following it or the level being `full`
+pub fn compress(code: &str, extension: &str, level: CompressionLevel) -> Option<String> {
+    if level == CompressionLevel::Full {
+        return None;
+    }
+    let extension = extension.to_lowercase();
+    let dialect = if extension == "rs" {
+        Dialect::Rust
+    } else if JAVASCRIPT_EXTENSIONS.contains(&extension.as_str()) {
+        Dialect::JavaScript
+    } else {
+        Dialect::CLike
+    };
+    if BRACE_EXTENSIONS.contains(&extension.as_str()) {
+        strip_brace_bodies(code, dialect)
+    } else if extension == "py" || extension == "pyi" {
+        strip_python_bodies(code)
+    } else {
+        None
+    }
+}
+
+/// The lexical rules of a language with braces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    /// Lifetimes, raw strings and byte strings
+    Rust,
+    /// Template strings and regex literals
+    JavaScript,
+    /// Characters in single quotes, and digit separators in C++
+    CLike,
+}
+
+/// A string or a comment still open at the end of the code
+struct Unterminated;
+
+/// Replaces the blocks of the functions with `{ ... }`, or `None` if the lexer does not
+/// follow the code to its end.
+fn strip_brace_bodies(code: &str, dialect: Dialect) -> Option<String> {
+    let bytes = code.as_bytes();
+    let mut output = String::with_capacity(code.len());
+    // The code since the last `;`, `{` or `}`, without comments and strings
+    let mut header = String::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(end) = skip_literal(bytes, i, dialect).ok()? {
+            if !is_comment(bytes, i) {
+                header.push_str("\"\"");
+            }
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'{' if is_function_header(&header) => {
+                output.push_str(&code[copied..i]);
+                output.push_str("{ ... }");
+                i = skip_block(bytes, i, dialect)?;
+                copied = i;
+                header.clear();
+                continue;
+            }
+            b'{' | b'}' | b';' => header.clear(),
+            // Only ASCII matters to the header, the bytes of other characters are skipped
+            byte if byte.is_ascii() => header.push(byte as char),
+            _ => {}
+        }
+        i += 1;
+    }
+    output.push_str(&code[copied..]);
+    Some(output)
+}
+
+/// Whether the code before a `{` opens the body of a function.
+fn is_function_header(header: &str) -> bool {
+    let header = header.trim();
+    if header.ends_with("=>") {
+        return true;
+    }
+    // Parameters, generics and attributes may mention types named like keywords
+    let mut outside = String::new();
+    let mut depth: i32 = 0;
+    for c in header.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth == 0 => outside.push(c),
+            _ => {}
+        }
+    }
+    let words: Vec<&str> = outside
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.iter().any(|word| FUNCTION_KEYWORDS.contains(word)) {
+        return true;
+    }
+    if words.iter().any(|word| CONTAINER_KEYWORDS.contains(word)) {
+        return false;
+    }
+    // Methods of languages without a keyword, and statements, have their parameters
+    // or condition in parentheses. An argument list still open is data.
+    header.contains('(') && depth == 0
+}
+
+/// The index after the `}` closing the block opened at `open`, if the code closes it.
+fn skip_block(bytes: &[u8], open: usize, dialect: Dialect) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i < bytes.len() {
+        if let Some(end) = skip_literal(bytes, i, dialect).ok()? {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Whether a comment starts at `start`.
+fn is_comment(bytes: &[u8], start: usize) -> bool {
+    bytes[start] == b'/' && matches!(bytes.get(start + 1), Some(b'/' | b'*'))
+}
+
+/// The index after the comment or string starting at `start`, if one does.
+fn skip_literal(
+    bytes: &[u8],
+    start: usize,
+    dialect: Dialect,
+) -> Result<Option<usize>, Unterminated> {
+    let find = |from: usize, pattern: &[u8]| {
+        bytes
+            .get(from..)
+            .and_then(|rest| {
+                rest.windows(pattern.len())
+                    .position(|window| window == pattern)
+            })
+            .map(|position| from + position + pattern.len())
+            .ok_or(Unterminated)
+    };
+    let previous = start.checked_sub(1).map(|i| bytes[i]);
+    let identifier =
+        |byte: Option<u8>| byte.is_some_and(|b| b.is_ascii_alphanumeric() || b == b'_');
+    let end = match (bytes[start], bytes.get(start + 1)) {
+        (b'/', Some(b'/')) => bytes[start..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(bytes.len(), |position| start + position),
+        (b'/', Some(b'*')) => find(start + 2, b"*/")?,
+        (b'/', _) if dialect == Dialect::JavaScript => match skip_regex(bytes, start) {
+            Some(end) => end,
+            None => return Ok(None),
+        },
+        (b'r' | b'b' | b'c', _) if dialect == Dialect::Rust && !identifier(previous) => {
+            match skip_raw_string(bytes, start) {
+                Some(end) => end?,
+                None => return Ok(None),
+            }
+        }
+        (b'"', _) => skip_quoted(bytes, start)?,
+        (b'`', _) if dialect != Dialect::Rust => skip_quoted(bytes, start)?,
+        // In Rust, a quote also starts a lifetime: only `'x'` and `'\n'` are characters
+        (b'\'', Some(b'\\')) if dialect == Dialect::Rust => find(start + 3, b"'")?,
+        (b'\'', _) if dialect == Dialect::Rust => match char_end(bytes, start) {
+            Some(end) => end,
+            None => return Ok(None),
+        },
+        // A quote between digits separates them in C++, as `1'000'000`
+        (b'\'', Some(next))
+            if previous.is_some_and(|b| b.is_ascii_digit()) && next.is_ascii_alphanumeric() =>
+        {
+            return Ok(None);
+        }
+        (b'\'', _) => skip_quoted(bytes, start)?,
+        _ => return Ok(None),
+    };
+    Ok(Some(end))
+}
+
+/// The index after the Rust character, as `'x'` or `'é'`, starting at `start`.
+fn char_end(bytes: &[u8], start: usize) -> Option<usize> {
+    // The width of a UTF-8 character, from its first byte
+    let width = match bytes.get(start + 1)? {
+        byte if *byte < 0x80 => 1,
+        byte if *byte >= 0xF0 => 4,
+        byte if *byte >= 0xE0 => 3,
+        _ => 2,
+    };
+    (bytes.get(start + 1 + width) == Some(&b'\'')).then_some(start + 2 + width)
+}
+
+/// The index after the Rust raw string, as `r#"..."#`, `br"..."` or `b"..."`, starting at
+/// `start`, if one does.
+fn skip_raw_string(bytes: &[u8], start: usize) -> Option<Result<usize, Unterminated>> {
+    let mut i = start;
+    if matches!(bytes[i], b'b' | b'c') {
+        i += 1;
+    }
+    if bytes.get(i) != Some(&b'r') {
+        // A byte or C string is quoted as any other
+        return (i > start && bytes.get(i) == Some(&b'"')).then(|| skip_quoted(bytes, i));
+    }
+    i += 1;
+    let hashes = bytes[i..].iter().take_while(|&&byte| byte == b'#').count();
+    i += hashes;
+    if bytes.get(i) != Some(&b'"') {
+        return None;
+    }
+    let mut closing = vec![b'"'];
+    closing.extend(std::iter::repeat_n(b'#', hashes));
+    Some(
+        bytes[i + 1..]
+            .windows(closing.len())
+            .position(|window| window == closing.as_slice())
+            .map(|position| i + 1 + position + closing.len())
+            .ok_or(Unterminated),
+    )
+}
+
+/// The index after the JavaScript regex literal starting at `start`, if the slash opens
+/// one rather than dividing: it follows an operator or a bracket, and closes on its line.
+fn skip_regex(bytes: &[u8], start: usize) -> Option<usize> {
+    let previous = bytes[..start]
+        .iter()
+        .rev()
+        .find(|byte| !byte.is_ascii_whitespace());
+    if previous.is_some_and(|byte| !b"(,=:[!&|?{};+-*%<>~^".contains(byte)) {
+        return None;
+    }
+    let mut in_class = false;
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => return None,
+            b'\\' => i += 1,
+            b'[' => in_class = true,
+            b']' => in_class = false,
+            b'/' if !in_class => return Some(i + 1),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The index after the closing quote of the string opened at `start`.
+fn skip_quoted(bytes: &[u8], start: usize) -> Result<usize, Unterminated> {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            byte if byte == quote => return Ok(i + 1),
+            _ => i += 1,
+        }
+    }
+    Err(Unterminated)
+}
+
+/// The lexical state of Python code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PythonState {
+    Code,
+    /// Inside a string opened by `quote`, three times when `triple`
+    String {
+        quote: char,
+        triple: bool,
+    },
+}
+
+/// A line of Python code, read by a lexer aware of its strings and comments.
+struct PythonLine<'a> {
+    text: &'a str,
+    /// Whether the line starts inside a string opened on a previous line
+    in_string: bool,
+    /// The code of the line, its strings emptied to `""` and its comment removed
+    code: String,
+}
+
+/// Splits Python code into lines, following the strings that span several lines, or
+/// `None` if a string is still open at the end of the code.
+fn lex_python(code: &str) -> Option<Vec<PythonLine<'_>>> {
+    let mut state = PythonState::Code;
+    let lines = code
+        .lines()
+        .map(|text| {
+            let in_string = state != PythonState::Code;
+            let chars: Vec<char> = text.chars().collect();
+            let tripled = |j: usize, quote: char| {
+                chars.get(j + 1) == Some(&quote) && chars.get(j + 2) == Some(&quote)
+            };
+            let mut line_code = String::with_capacity(text.len());
+            let mut j = 0;
+            while j < chars.len() {
+                match (state, chars[j]) {
+                    (PythonState::Code, '#') => break,
+                    (PythonState::Code, quote @ ('"' | '\'')) => {
+                        let triple = tripled(j, quote);
+                        state = PythonState::String { quote, triple };
+                        line_code.push_str("\"\"");
+                        j += if triple { 3 } else { 1 };
+                    }
+                    (PythonState::Code, c) => {
+                        line_code.push(c);
+                        j += 1;
+                    }
+                    (PythonState::String { .. }, '\\') => j += 2,
+                    (PythonState::String { quote, triple }, c)
+                        if c == quote && (!triple || tripled(j, quote)) =>
+                    {
+                        state = PythonState::Code;
+                        j += if triple { 3 } else { 1 };
+                    }
+                    (PythonState::String { .. }, _) => j += 1,
+                }
+            }
+            // Only triple-quoted strings, and escaped line ends, go on to the next line
+            if let PythonState::String { triple: false, .. } = state
+                && !text.ends_with('\\')
+            {
+                state = PythonState::Code;
+            }
+            PythonLine {
+                text,
+                in_string,
+                code: line_code,
+            }
+        })
+        .collect();
+    (state == PythonState::Code).then_some(lines)
+}
+
+/// Replaces the bodies of the Python functions with `...`, keeping their docstring, or
+/// `None` if the lexer does not follow the code to its end.
+fn strip_python_bodies(code: &str) -> Option<String> {
+    let lines = lex_python(code)?;
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        output.push(line.text.to_string());
+        i += 1;
+        let trimmed = line.code.trim_start();
+        if line.in_string || !(trimmed.starts_with("def ") || trimmed.starts_with("async def ")) {
+            continue;
+        }
+
+        // The signature may span several lines, up to the colon opening the body
+        let indent = indentation(line.text).len();
+        let mut depth = bracket_depth(&line.code);
+        let mut signature_end = line;
+        while depth > 0 && i < lines.len() {
+            signature_end = &lines[i];
+            output.push(signature_end.text.to_string());
+            depth += bracket_depth(&signature_end.code);
+            i += 1;
+        }
+        if !signature_end.code.trim_end().ends_with(':') {
+            // A function on one line has no body to strip
+            continue;
+        }
+
+        // The lines of a string belong to the body, whatever their indentation
+        let body_start = i;
+        let mut end = i;
+        while end < lines.len()
+            && (lines[end].in_string
+                || lines[end].text.trim().is_empty()
+                || indentation(lines[end].text).len() > indent)
+        {
+            end += 1;
+        }
+        // The blank lines after the body separate it from what follows
+        let mut body_end = end;
+        while body_end > body_start
+            && !lines[body_end - 1].in_string
+            && lines[body_end - 1].text.trim().is_empty()
+        {
+            body_end -= 1;
+        }
+        let Some(first) = (body_start..body_end).find(|&j| !lines[j].text.trim().is_empty()) else {
+            continue;
+        };
+
+        let docstring_end = is_docstring(lines[first].text).then(|| {
+            (first + 1..body_end)
+                .find(|&j| !lines[j].in_string)
+                .unwrap_or(body_end)
+        });
+        if let Some(docstring_end) = docstring_end {
+            output.extend(
+                lines[body_start..docstring_end]
+                    .iter()
+                    .map(|l| l.text.to_string()),
+            );
+        }
+        let rest_empty = docstring_end.is_some_and(|docstring_end| {
+            lines[docstring_end..body_end]
+                .iter()
+                .all(|l| l.text.trim().is_empty())
+        });
+        if !rest_empty {
+            output.push(format!("{}...", indentation(lines[first].text)));
+        }
+        output.extend(lines[body_end..end].iter().map(|l| l.text.to_string()));
+        i = end;
+    }
+
+    let mut compressed = output.join("\n");
+    if code.ends_with('\n') {
+        compressed.push('\n');
+    }
+    Some(compressed)
+}
+
+/// Whether a line starts with a triple-quoted string, the docstring of a body.
+fn is_docstring(line: &str) -> bool {
+    let text = line
+        .trim_start()
+        .trim_start_matches(['r', 'u', 'b', 'R', 'U', 'B']);
+    text.starts_with("\"\"\"") || text.starts_with("\'\'\'")
+}
+
+/// The leading whitespace of a line
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// The brackets the code of a line opens, less those it closes.
+fn bracket_depth(code: &str) -> i32 {
+    code.chars()
+        .map(|c| match c {
+            '(' | '[' | '{' => 1,
+            ')' | ']' | '}' => -1,
+            _ => 0,
+        })
+        .sum()
+}
@@ -2,13 +2,29 @@
 //! of code2prompt in a stateless manner. It includes all parameters needed for file traversal,
 //! code filtering, token counting, and more.
 
-use crate::template::OutputFormat;
-use crate::tokenizer::TokenizerType;
+use crate::binary::BinaryHandling;
+use crate::budget::BudgetStrategy;
+use crate::chunk::ChunkOptions;
+use crate::compression::CompressionLevel;
+use crate::duplication::{DEFAULT_DUPLICATE_MIN_LINES, DuplicateHandling};
+use crate::file_processor::DataSample;
+use crate::fixture::FixturePolicy;
+use crate::git::DiffStyle;
+use crate::git_tree::repository_name;
+use crate::line_range::{LineRange, pattern_line_ranges};
+use crate::path::{SymlinkPolicy, display_name};
+use crate::placement::Placement;
+use crate::post_process::PostProcessStep;
+use crate::prompt_cache::PromptCacheProvider;
+use crate::template::{FileDelimiter, OutputFormat};
+use crate::tokenizer::{TokenCountMode, TokenizerType};
+use crate::tree::TreeOptions;
+use crate::util::path_key;
 use crate::{sort::FileSortMethod, tokenizer::TokenFormat};
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// A stateless configuration object describing all the preferences and filters
 /// applied when generating a code prompt. It does not store any mutable data,
@@ -19,31 +35,133 @@ pub struct Code2PromptConfig {
     /// Path to the root directory of the codebase.
     pub path: PathBuf,
 
+    /// Further root directories rendered in the same prompt, such as sibling packages of
+    /// a monorepo. With several roots, each is labeled with its directory name, which
+    /// prefixes its paths and names it in the tree, in place of `path_alias`.
+    pub extra_paths: Vec<PathBuf>,
+
     /// List of glob-like patterns to include.
     pub include_patterns: Vec<String>,
 
     /// List of glob-like patterns to exclude.
     pub exclude_patterns: Vec<String>,
 
+    /// List of glob-like patterns of the files listed in the source tree without their
+    /// content. The exclude patterns take precedence.
+    pub tree_only_patterns: Vec<String>,
+
+    /// List of glob-like patterns of directories collapsed in the output: drawn in the tree
+    /// as a single line, their files replaced with a single summarized entry. The selection
+    /// is unchanged.
+    pub collapse_patterns: Vec<String>,
+
+    /// Line ranges of the files embedded partially, by path relative to the root. Include
+    /// patterns of the form `src/lib.rs:100-250` add to them.
+    pub line_ranges: HashMap<String, Vec<LineRange>>,
+
+    /// How much of the source files is kept: in full, or their signatures only.
+    pub compression_level: CompressionLevel,
+
+    /// Compression levels of files differing from `compression_level`, by path relative
+    /// to the root.
+    pub compression_overrides: HashMap<String, CompressionLevel>,
+
+    /// Names or directories of workspace packages to keep, with the internal packages
+    /// they depend on. Other packages are pruned. Empty keeps the whole codebase.
+    pub packages: Vec<String>,
+
+    /// Labels of Bazel or Buck targets whose sources, with those of their transitive
+    /// dependencies, are the only files kept. Empty keeps the whole codebase.
+    pub bazel_targets: Vec<String>,
+
+    /// Tags file of ctags or GNU Global giving the symbols of the files, none if unset.
+    pub tags_file: Option<PathBuf>,
+
+    /// Names of symbols whose defining files, found in `tags_file`, are the only files
+    /// kept. Empty keeps the whole codebase.
+    pub symbols: Vec<String>,
+
     /// If true, code lines will be numbered in the output.
     pub line_numbers: bool,
 
     /// If true, paths in the output will be absolute instead of relative.
     pub absolute_path: bool,
 
+    /// If set, paths in the output are relative to the root and prefixed with this alias,
+    /// `{repo}` standing for the name of the codebase, as `{repo}/src/lib.rs`. Takes
+    /// precedence over `absolute_path`.
+    pub path_alias: Option<String>,
+
     /// If true, code2prompt will generate a full directory tree, ignoring include/exclude rules.
     pub full_directory_tree: bool,
 
+    /// How the source tree is rendered: the depth from which directories are collapsed,
+    /// their annotations and whether empty directories are kept.
+    pub tree_options: TreeOptions,
+
+    /// If true, build tool configurations (Vite, webpack, Next.js, Tailwind...) are summarized
+    /// into `build_config` instead of being included, and component files are grouped by
+    /// route or feature in the source tree.
+    pub frontend_summary: bool,
+
+    /// How test fixtures, snapshots and golden files are handled: included, stubbed with
+    /// their dimensions or excluded.
+    pub fixture_policy: FixturePolicy,
+
+    /// How binary files are handled: skipped, listed with their size, or included in base64.
+    pub binary_handling: BinaryHandling,
+
+    /// If set, blocks of code repeated across the files are reported, or also replaced by a
+    /// stub referring to their first copy.
+    pub duplicates: Option<DuplicateHandling>,
+
+    /// Minimum number of non-blank lines of a duplicated block.
+    #[builder(default = "DEFAULT_DUPLICATE_MIN_LINES")]
+    pub duplicate_min_lines: usize,
+
+    /// If set, data files (CSV, TSV, JSON Lines) keep their first and last rows, with their
+    /// columns and row count, instead of a single sample row.
+    pub data_sample: Option<DataSample>,
+
+    /// If true, log files are summarized: similar lines are collapsed, and only the errors and
+    /// warnings with their context, the first and the last lines are kept.
+    pub summarize_logs: bool,
+
+    /// If set, files are dropped or truncated until the prompt fits in this many tokens.
+    pub max_tokens: Option<usize>,
+
+    /// Which files give way first when the prompt exceeds `max_tokens`.
+    pub budget_strategy: BudgetStrategy,
+
+    /// If set, the files over this many tokens are replaced with a summary written by the
+    /// session summarizer, when it has one.
+    pub summarize_over: Option<usize>,
+
+    /// JSON file caching the summaries by content hash, none if unset.
+    pub summary_cache: Option<PathBuf>,
+
     /// If true, code blocks will not be wrapped in Markdown fences (```).
     pub no_codeblock: bool,
 
-    /// If true, symbolic links will be followed during traversal.
-    pub follow_symlinks: bool,
+    /// Language tags of the code fences, by file extension (e.g. `tf` → `hcl`). Files whose
+    /// extension is not mapped are tagged with their extension.
+    pub fence_languages: HashMap<String, String>,
+
+    /// Commands starting a language server, by file extension (e.g. `rs` →
+    /// `rust-analyzer`). The files compressed to their signatures with such an extension
+    /// are compressed from the symbols reported by the server instead.
+    pub lsp_servers: HashMap<String, String>,
+
+    /// Post-processing steps applied to the rendered prompt, in order.
+    pub post_process: Vec<PostProcessStep>,
+
+    /// How symbolic links are handled during traversal.
+    pub symlink_policy: SymlinkPolicy,
 
     /// If true, hidden files and directories will be included.
     pub hidden: bool,
 
-    /// If true, .gitignore rules will be ignored.
+    /// If true, .gitignore and .c2pignore rules will be ignored.
     pub no_ignore: bool,
 
     /// Defines the sorting method for files.
@@ -52,24 +170,82 @@ pub struct Code2PromptConfig {
     /// Determines the output format of the final prompt.
     pub output_format: OutputFormat,
 
+    /// Delimiters written around each file by the `file_header` and `file_footer` partials.
+    pub file_delimiter: FileDelimiter,
+
+    /// Size and overlap of the chunks of the `chunks` output format.
+    pub chunk_options: ChunkOptions,
+
+    /// Custom Handlebars template of the `file_header` partial, replacing the one of
+    /// `file_delimiter`. The file is its context, as `===== FILE: {{path}} =====`.
+    pub file_header: Option<String>,
+
+    /// Custom Handlebars template of the `file_footer` partial, replacing the one of
+    /// `file_delimiter`.
+    pub file_footer: Option<String>,
+
     /// An optional custom Handlebars template string.
     pub custom_template: Option<String>,
 
     /// The tokenizer encoding to use for counting tokens.
     pub encoding: TokenizerType,
 
+    /// The SentencePiece model file (`tokenizer.model` or `tokenizer.json`) of the llama tokenizer.
+    pub tokenizer_model: Option<PathBuf>,
+
     /// The counting format to use for token counting.
     pub token_format: TokenFormat,
 
+    /// Whether token counts are computed exactly or estimated heuristically.
+    pub token_count_mode: TokenCountMode,
+
     /// If true, the git diff between HEAD and index will be included.
     pub diff_enabled: bool,
 
     /// If set, contains two branch names for which code2prompt will generate a git diff.
     pub diff_branches: Option<(String, String)>,
 
+    /// If true, only the files changed by the git diff (the branch diff when
+    /// `diff_branches` is set) are included.
+    pub include_only_changed: bool,
+
+    /// Files whose latest commits are included with them, with the number of commits
+    /// of each. The paths are relative to the root.
+    pub file_history: HashMap<String, usize>,
+
+    /// If set, git diffs larger than this many tokens are truncated.
+    pub diff_max_tokens: Option<usize>,
+
+    /// List of glob-like patterns a changed path must match to appear in git diffs.
+    pub diff_include_patterns: Vec<String>,
+
+    /// List of glob-like patterns excluding changed paths from git diffs.
+    pub diff_exclude_patterns: Vec<String>,
+
+    /// Number of context lines around changes in git diffs.
+    pub diff_context_lines: Option<u32>,
+
+    /// How git diffs are rendered.
+    pub diff_style: DiffStyle,
+
     /// If set, contains two branch names for which code2prompt will retrieve the git log.
     pub log_branches: Option<(String, String)>,
 
+    /// If set, git operations use this repository (which may be bare) instead of `path`.
+    pub git_dir: Option<PathBuf>,
+
+    /// If set, the codebase is read from the tree of this revision instead of the working tree.
+    pub git_rev: Option<String>,
+
+    /// If true, unresolved merge conflicts are collected into the `conflicts` template variable.
+    pub conflicts_enabled: bool,
+
+    /// If set, the diff of this stash (`stash@{N}`, an index or a message excerpt) is included.
+    pub stash: Option<String>,
+
+    /// If true, untracked files are listed in the `untracked_files` template variable.
+    pub untracked_enabled: bool,
+
     /// The name of the template used.
     pub template_name: String,
 
@@ -79,6 +255,34 @@ pub struct Code2PromptConfig {
     /// Extra template data
     pub user_variables: HashMap<String, String>,
 
+    /// An optional instruction appended after the codebase.
+    pub instruction: Option<String>,
+
+    /// If true, the instruction is also rendered before the codebase, so that the model
+    /// reads it both first and last.
+    pub repeat_instruction: bool,
+
+    /// Whether the instruction, the git diffs and the key files go before or after the
+    /// rest of the codebase.
+    pub placement: Placement,
+
+    /// Glob patterns of the files the task is about, placed following `placement`.
+    pub key_files: Vec<String>,
+
+    /// If set, the prompt is emitted in the messages format of the provider, with the
+    /// codebase marked as a cacheable prefix.
+    pub prompt_cache: Option<PromptCacheProvider>,
+
+    /// An optional JSON schema appended as a response contract section.
+    pub response_schema: Option<String>,
+
+    /// If set, the manifest of file content hashes compared with and updated on each run.
+    pub manifest_file: Option<PathBuf>,
+
+    /// If set, the file tracking the context already sent in a conversation.
+    /// Only files changed since the previous turn are included in the prompt.
+    pub conversation_file: Option<PathBuf>,
+
     /// If true, detailed token map breakdown will be displayed in output.
     ///
     /// Note: Token counting always happens internally for performance optimization
@@ -91,6 +295,195 @@ impl Code2PromptConfig {
     pub fn builder() -> Code2PromptConfigBuilder {
         Code2PromptConfigBuilder::default()
     }
+
+    /// Returns the line ranges of a file to embed, from `line_ranges` and the include
+    /// patterns, sorted. An empty list means the whole file.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_path` - The path of the file, relative to the root
+    pub fn file_line_ranges(&self, relative_path: &Path) -> Vec<LineRange> {
+        let mut ranges = pattern_line_ranges(&self.include_patterns, relative_path);
+        let key = path_key(relative_path);
+        if let Some(explicit) = self.line_ranges.get(&key) {
+            ranges.extend(explicit);
+            ranges.sort();
+            ranges.dedup();
+        }
+        ranges
+    }
+
+    /// Returns the compression level of a file, its override or `compression_level`.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_path` - The path of the file, relative to the root
+    pub fn file_compression_level(&self, relative_path: &Path) -> CompressionLevel {
+        let key = path_key(relative_path);
+        self.compression_overrides
+            .get(&key)
+            .copied()
+            .unwrap_or(self.compression_level)
+    }
+
+    /// Returns whether the diff of the working tree is included: when `diff_enabled` is
+    /// set, or when `include_only_changed` keeps the files it changes, which come with it.
+    pub fn working_diff_enabled(&self) -> bool {
+        self.diff_enabled || (self.include_only_changed && self.diff_branches.is_none())
+    }
+
+    /// Returns the path alias with `{repo}` expanded to the name of the codebase and no
+    /// trailing slash, if `path_alias` is set.
+    pub fn path_alias_prefix(&self) -> Option<String> {
+        let alias = self.path_alias.as_ref()?;
+        let name = match &self.git_dir {
+            Some(git_dir) => repository_name(git_dir),
+            None => display_name(&self.path),
+        };
+        Some(
+            alias
+                .replace("{repo}", &name)
+                .trim_end_matches('/')
+                .to_string(),
+        )
+    }
+
+    /// Returns the path of a file as shown in the output: prefixed with the path alias,
+    /// absolute, or relative to the root.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_path` - The path of the file, relative to the root
+    /// * `absolute_path` - The absolute path of the file
+    pub fn output_path(&self, relative_path: &Path, absolute_path: &Path) -> String {
+        if let Some(prefix) = self.path_alias_prefix() {
+            let relative = path_key(relative_path);
+            if prefix.is_empty() {
+                return relative;
+            }
+            return format!("{}/{}", prefix, relative);
+        }
+        if self.absolute_path {
+            absolute_path.to_string_lossy().to_string()
+        } else {
+            relative_path.to_string_lossy().to_string()
+        }
+    }
+
+    /// Returns the path relative to the root of a file, from its path in the output.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file as shown in the output
+    pub fn root_relative_path(&self, path: &str) -> PathBuf {
+        if let Some(prefix) = self.path_alias_prefix()
+            && let Some(relative) = path.strip_prefix(&format!("{}/", prefix))
+        {
+            return PathBuf::from(relative);
+        }
+        let path = self.path.join(path);
+        if let Ok(relative) = path.strip_prefix(&self.path) {
+            return relative.to_path_buf();
+        }
+        let canonical_root = self
+            .path
+            .canonicalize()
+            .unwrap_or_else(|_| self.path.clone());
+        match path.strip_prefix(canonical_root) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => path,
+        }
+    }
+
+    /// Returns the `file_header` and `file_footer` partials: the custom ones if set, those of
+    /// `file_delimiter` otherwise.
+    pub fn file_delimiter_partials(&self) -> (String, String) {
+        let (header, footer) = self.file_delimiter.partials(self.output_format);
+        (
+            self.file_header.as_deref().unwrap_or(header).to_string(),
+            self.file_footer.as_deref().unwrap_or(footer).to_string(),
+        )
+    }
+
+    /// Returns the view of the effective configuration exposed to templates as `config`.
+    pub fn template_config(&self) -> TemplateConfig<'_> {
+        TemplateConfig {
+            output_format: self.output_format,
+            line_numbers: self.line_numbers,
+            absolute_path: self.absolute_path,
+            path_alias: self.path_alias_prefix(),
+            full_directory_tree: self.full_directory_tree,
+            no_codeblock: self.no_codeblock,
+            hidden: self.hidden,
+            sort_method: self.sort_method,
+            include_patterns: &self.include_patterns,
+            exclude_patterns: &self.exclude_patterns,
+            encoding: self.encoding,
+            token_format: self.token_format,
+            token_count_mode: self.token_count_mode,
+            diff_enabled: self.diff_enabled,
+            diff_max_tokens: self.diff_max_tokens,
+            diff_style: self.diff_style,
+            diff_branches: self.diff_branches.as_ref().map(BranchPair::from),
+            include_only_changed: self.include_only_changed,
+            log_branches: self.log_branches.as_ref().map(BranchPair::from),
+            conflicts_enabled: self.conflicts_enabled,
+            untracked_enabled: self.untracked_enabled,
+            stash: self.stash.as_deref(),
+            git_rev: self.git_rev.as_deref(),
+            has_instruction: self.instruction.is_some(),
+            placement: self.placement,
+            focus_first: self.placement.focus_first(),
+            has_response_schema: self.response_schema.is_some(),
+        }
+    }
+}
+
+/// The effective configuration as seen by templates through the `config` variable,
+/// so that they can render sections conditionally, e.g. `{{#if config.diff_enabled}}`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateConfig<'a> {
+    pub output_format: OutputFormat,
+    pub line_numbers: bool,
+    pub absolute_path: bool,
+    pub path_alias: Option<String>,
+    pub full_directory_tree: bool,
+    pub no_codeblock: bool,
+    pub hidden: bool,
+    pub sort_method: Option<FileSortMethod>,
+    pub include_patterns: &'a [String],
+    pub exclude_patterns: &'a [String],
+    pub encoding: TokenizerType,
+    pub token_format: TokenFormat,
+    pub token_count_mode: TokenCountMode,
+    pub diff_enabled: bool,
+    pub diff_max_tokens: Option<usize>,
+    pub diff_style: DiffStyle,
+    pub diff_branches: Option<BranchPair<'a>>,
+    pub include_only_changed: bool,
+    pub log_branches: Option<BranchPair<'a>>,
+    pub conflicts_enabled: bool,
+    pub untracked_enabled: bool,
+    pub stash: Option<&'a str>,
+    pub git_rev: Option<&'a str>,
+    pub has_instruction: bool,
+    pub placement: Placement,
+    /// Whether the templates render the git diffs before the codebase
+    pub focus_first: bool,
+    pub has_response_schema: bool,
+}
+
+/// A pair of branches compared by a diff or a log.
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchPair<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+}
+
+impl<'a> From<&'a (String, String)> for BranchPair<'a> {
+    fn from((from, to): &'a (String, String)) -> Self {
+        BranchPair { from, to }
+    }
 }
 
 /// Output destination for code2prompt
@@ -113,31 +506,132 @@ pub struct TomlConfig {
     /// Path to the codebase directory
     pub path: Option<String>,
 
+    /// Further root directories rendered in the same prompt
+    pub extra_paths: Vec<String>,
+
     /// Patterns to include
     pub include_patterns: Vec<String>,
 
     /// Patterns to exclude
     pub exclude_patterns: Vec<String>,
 
+    /// Patterns of the files listed in the source tree without their content
+    pub tree_only_patterns: Vec<String>,
+
+    /// Patterns of the directories collapsed in the output
+    pub collapse_patterns: Vec<String>,
+
+    /// Line ranges of the files embedded partially, as `"src/lib.rs" = ["100-250"]`
+    pub line_ranges: HashMap<String, Vec<LineRange>>,
+
+    /// Compression level of the source files, `full` or `signatures`
+    pub compression_level: Option<CompressionLevel>,
+
+    /// Compression levels by file, as `"src/lib.rs" = "full"`
+    pub compression_overrides: HashMap<String, CompressionLevel>,
+
+    /// Workspace packages to keep, with their internal dependencies
+    pub packages: Vec<String>,
+
+    /// Bazel or Buck targets to keep, with their transitive dependencies
+    pub bazel_targets: Vec<String>,
+
+    /// Tags file of ctags or GNU Global, and the symbols whose defining files are kept
+    pub tags_file: Option<PathBuf>,
+    pub symbols: Vec<String>,
+
     /// Display options
     pub line_numbers: bool,
     pub absolute_path: bool,
     pub full_directory_tree: bool,
+    pub frontend_summary: bool,
+
+    /// Prefix replacing the root in the output paths, as `{repo}`
+    pub path_alias: Option<String>,
+
+    /// Rendering of the source tree: collapsed depth, annotations and empty directories
+    pub tree_options: TreeOptions,
+
+    /// Handling of symbolic links: "skip", "follow" or "follow_with_cycle_detection"
+    pub symlink_policy: Option<SymlinkPolicy>,
+
+    /// File selection: hidden files, and the files of the ignore rules
+    pub hidden: bool,
+    pub no_ignore: bool,
+
+    /// Leave the source code out of markdown code blocks
+    pub no_codeblock: bool,
+
+    /// Handling of test fixtures, snapshots and golden files
+    pub fixture_policy: Option<FixturePolicy>,
+
+    /// Handling of binary files
+    pub binary_handling: Option<BinaryHandling>,
+
+    /// Handling of duplicated code, and the minimum length of a duplicated block
+    pub duplicates: Option<DuplicateHandling>,
+    pub duplicate_min_lines: Option<usize>,
+
+    /// First and last rows kept from data files
+    pub data_sample: Option<DataSample>,
+
+    /// Summarize log files around their errors and warnings
+    pub summarize_logs: bool,
+
+    /// Token budget, and which files give way first when it is exceeded
+    pub max_tokens: Option<usize>,
+    pub budget_strategy: Option<BudgetStrategy>,
+
+    /// Files over this many tokens are summarized by `summary_model`, through `llm_command`
+    pub summarize_over: Option<usize>,
+    pub summary_model: Option<String>,
+
+    /// Code fence language tags by file extension
+    pub fence_languages: HashMap<String, String>,
+
+    /// Language server commands by file extension, for signature compression
+    pub lsp_servers: HashMap<String, String>,
+
+    /// Post-processing steps applied to the rendered prompt, in order
+    pub post_process: Vec<PostProcessStep>,
 
     /// Output format
     pub output_format: Option<OutputFormat>,
 
-    /// Sort method
+    /// Delimiters around each file: "auto", "markdown", "xml" or "banner"
+    pub file_delimiter: Option<FileDelimiter>,
+
+    /// Lines and overlap of the chunks of the "chunks" output format
+    pub chunk_options: ChunkOptions,
+
+    /// Custom templates of the delimiters around each file, as `===== FILE: {{path}} =====`
+    pub file_header: Option<String>,
+    pub file_footer: Option<String>,
+
+    /// Sort method, also read as `file_sort`
+    #[serde(alias = "file_sort")]
     pub sort_method: Option<FileSortMethod>,
 
     /// Tokenizer settings
     pub encoding: Option<TokenizerType>,
+    pub tokenizer_model: Option<PathBuf>,
     pub token_format: Option<TokenFormat>,
+    pub token_count_mode: Option<TokenCountMode>,
 
     /// Git settings
     pub diff_enabled: bool,
     pub diff_branches: Option<Vec<String>>,
+    pub include_only_changed: bool,
+    pub file_history: HashMap<String, usize>,
+    pub diff_max_tokens: Option<usize>,
+    pub diff_include_patterns: Vec<String>,
+    pub diff_exclude_patterns: Vec<String>,
+    pub diff_context_lines: Option<u32>,
+    pub diff_style: Option<DiffStyle>,
     pub log_branches: Option<Vec<String>>,
+    pub conflicts_enabled: bool,
+    pub stash: Option<String>,
+    pub untracked_enabled: bool,
 
     /// Template settings
     pub template_name: Option<String>,
@@ -146,8 +640,30 @@ pub struct TomlConfig {
     /// User variables
     pub user_variables: HashMap<String, String>,
 
+    /// Instruction appended after the codebase
+    pub instruction: Option<String>,
+
+    /// Also render the instruction before the codebase
+    pub repeat_instruction: bool,
+
+    /// Placement of the instruction, diffs and key files: "end", "beginning" or "sandwich"
+    pub placement: Option<Placement>,
+    pub key_files: Vec<String>,
+
+    /// Prompt caching provider: "anthropic" or "openai"
+    pub prompt_cache: Option<PromptCacheProvider>,
+
+    /// Path to a JSON schema file appended as a response contract
+    pub response_schema: Option<String>,
+
     /// Token map
     pub token_map_enabled: bool,
+
+    /// Command used to query a model, reading the prompt on stdin
+    pub llm_command: Option<String>,
+
+    /// Token used to look up pull requests on GitHub, `GITHUB_TOKEN` takes precedence
+    pub github_token: Option<String>,
 }
 
 impl TomlConfig {
@@ -170,22 +686,82 @@ impl TomlConfig {
         }
 
         builder
+            .extra_paths(
+                self.extra_paths
+                    .iter()
+                    .map(PathBuf::from)
+                    .collect::<Vec<_>>(),
+            )
             .include_patterns(self.include_patterns.clone())
             .exclude_patterns(self.exclude_patterns.clone())
+            .tree_only_patterns(self.tree_only_patterns.clone())
+            .collapse_patterns(self.collapse_patterns.clone())
+            .line_ranges(self.line_ranges.clone())
+            .compression_level(self.compression_level.unwrap_or_default())
+            .compression_overrides(self.compression_overrides.clone())
+            .packages(self.packages.clone())
+            .bazel_targets(self.bazel_targets.clone())
+            .tags_file(self.tags_file.clone())
+            .symbols(self.symbols.clone())
             .line_numbers(self.line_numbers)
             .absolute_path(self.absolute_path)
-            .full_directory_tree(self.full_directory_tree);
+            .path_alias(self.path_alias.clone())
+            .full_directory_tree(self.full_directory_tree)
+            .tree_options(self.tree_options)
+            .frontend_summary(self.frontend_summary)
+            .symlink_policy(self.symlink_policy.unwrap_or_default())
+            .hidden(self.hidden)
+            .no_ignore(self.no_ignore)
+            .no_codeblock(self.no_codeblock)
+            .fixture_policy(self.fixture_policy.unwrap_or_default())
+            .binary_handling(self.binary_handling.unwrap_or_default())
+            .duplicates(self.duplicates)
+            .duplicate_min_lines(
+                self.duplicate_min_lines
+                    .unwrap_or(DEFAULT_DUPLICATE_MIN_LINES),
+            )
+            .data_sample(self.data_sample)
+            .summarize_logs(self.summarize_logs)
+            .max_tokens(self.max_tokens)
+            .budget_strategy(self.budget_strategy.unwrap_or_default())
+            .summarize_over(self.summarize_over)
+            .fence_languages(self.fence_languages.clone())
+            .lsp_servers(self.lsp_servers.clone())
+            .post_process(self.post_process.clone());
 
         builder.output_format(self.output_format.unwrap_or_default());
+        builder
+            .file_delimiter(self.file_delimiter.unwrap_or_default())
+            .chunk_options(self.chunk_options)
+            .file_header(self.file_header.clone())
+            .file_footer(self.file_footer.clone());
 
         builder.sort_method(self.sort_method);
 
         builder.encoding(self.encoding.unwrap_or_default());
 
+        builder.tokenizer_model(self.tokenizer_model.clone());
+
         builder.token_format(self.token_format.unwrap_or_default());
 
+        builder.token_count_mode(self.token_count_mode.unwrap_or_default());
+
         builder.diff_enabled(self.diff_enabled);
 
+        builder.include_only_changed(self.include_only_changed);
+
+        builder.file_history(self.file_history.clone());
+
+        builder
+            .diff_max_tokens(self.diff_max_tokens)
+            .diff_include_patterns(self.diff_include_patterns.clone())
+            .diff_exclude_patterns(self.diff_exclude_patterns.clone())
+            .diff_context_lines(self.diff_context_lines)
+            .diff_style(self.diff_style.unwrap_or_default())
+            .conflicts_enabled(self.conflicts_enabled)
+            .stash(self.stash.clone())
+            .untracked_enabled(self.untracked_enabled);
+
         if let Some(diff_branches) = &self.diff_branches
             && diff_branches.len() == 2
         {
@@ -208,6 +784,11 @@ impl TomlConfig {
 
         builder
             .user_variables(self.user_variables.clone())
+            .instruction(self.instruction.clone())
+            .repeat_instruction(self.repeat_instruction)
+            .placement(self.placement.unwrap_or_default())
+            .key_files(self.key_files.clone())
+            .prompt_cache(self.prompt_cache)
             .token_map_enabled(self.token_map_enabled);
 
         builder.build().unwrap_or_default()
@@ -219,24 +800,74 @@ pub fn export_config_to_toml(config: &Code2PromptConfig) -> Result<String, toml:
     let toml_config = TomlConfig {
         default_output: OutputDestination::Stdout, // Default for new behavior
         path: Some(config.path.to_string_lossy().to_string()),
+        extra_paths: config
+            .extra_paths
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect(),
         include_patterns: config.include_patterns.clone(),
         exclude_patterns: config.exclude_patterns.clone(),
+        tree_only_patterns: config.tree_only_patterns.clone(),
+        collapse_patterns: config.collapse_patterns.clone(),
+        line_ranges: config.line_ranges.clone(),
+        compression_level: Some(config.compression_level),
+        compression_overrides: config.compression_overrides.clone(),
+        packages: config.packages.clone(),
+        bazel_targets: config.bazel_targets.clone(),
+        tags_file: config.tags_file.clone(),
+        symbols: config.symbols.clone(),
         line_numbers: config.line_numbers,
         absolute_path: config.absolute_path,
         full_directory_tree: config.full_directory_tree,
+        frontend_summary: config.frontend_summary,
+        path_alias: config.path_alias.clone(),
+        tree_options: config.tree_options,
+        symlink_policy: Some(config.symlink_policy),
+        hidden: config.hidden,
+        no_ignore: config.no_ignore,
+        no_codeblock: config.no_codeblock,
+        fixture_policy: Some(config.fixture_policy),
+        binary_handling: Some(config.binary_handling),
+        duplicates: config.duplicates,
+        duplicate_min_lines: Some(config.duplicate_min_lines),
+        data_sample: config.data_sample,
+        summarize_logs: config.summarize_logs,
+        max_tokens: config.max_tokens,
+        budget_strategy: Some(config.budget_strategy),
+        summarize_over: config.summarize_over,
+        summary_model: None,
+        fence_languages: config.fence_languages.clone(),
+        lsp_servers: config.lsp_servers.clone(),
+        post_process: config.post_process.clone(),
         output_format: Some(config.output_format),
+        file_delimiter: Some(config.file_delimiter),
+        chunk_options: config.chunk_options,
+        file_header: config.file_header.clone(),
+        file_footer: config.file_footer.clone(),
         sort_method: config.sort_method,
         encoding: Some(config.encoding),
+        tokenizer_model: config.tokenizer_model.clone(),
         token_format: Some(config.token_format),
+        token_count_mode: Some(config.token_count_mode),
         diff_enabled: config.diff_enabled,
         diff_branches: config
             .diff_branches
             .as_ref()
             .map(|(a, b)| vec![a.clone(), b.clone()]),
+        include_only_changed: config.include_only_changed,
+        file_history: config.file_history.clone(),
+        diff_max_tokens: config.diff_max_tokens,
+        diff_include_patterns: config.diff_include_patterns.clone(),
+        diff_exclude_patterns: config.diff_exclude_patterns.clone(),
+        diff_context_lines: config.diff_context_lines,
+        diff_style: Some(config.diff_style),
         log_branches: config
             .log_branches
             .as_ref()
             .map(|(a, b)| vec![a.clone(), b.clone()]),
+        conflicts_enabled: config.conflicts_enabled,
+        stash: config.stash.clone(),
+        untracked_enabled: config.untracked_enabled,
         template_name: if config.template_name.is_empty() {
             None
         } else {
@@ -248,7 +879,16 @@ pub fn export_config_to_toml(config: &Code2PromptConfig) -> Result<String, toml:
             Some(config.template_str.clone())
         },
         user_variables: config.user_variables.clone(),
+        instruction: config.instruction.clone(),
+        repeat_instruction: config.repeat_instruction,
+        placement: Some(config.placement),
+        key_files: config.key_files.clone(),
+        prompt_cache: config.prompt_cache,
+        // The session only holds the schema contents, not the file it came from
+        response_schema: None,
         token_map_enabled: config.token_map_enabled,
+        llm_command: None,
+        github_token: None,
     };
 
     toml_config.to_string()
@@ -0,0 +1,50 @@
+//! This module tracks the context already sent during a conversation with a model,
+//! so that follow-up turns only include the files that changed since the previous turn.
+
+use crate::manifest::{FileChanges, FileManifest};
+use crate::path::FileEntry;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The files sent so far in a conversation, persisted between turns.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConversationState {
+    /// Number of turns generated so far.
+    pub turns: usize,
+    /// Content hash of each file sent.
+    #[serde(flatten)]
+    pub manifest: FileManifest,
+}
+
+impl ConversationState {
+    /// Loads the conversation state from a JSON file. A missing file starts a new conversation.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read conversation file {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid conversation file {}", path.display()))
+    }
+
+    /// Saves the conversation state as a JSON file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write conversation file {}", path.display()))
+    }
+
+    /// Compares the files with the ones already sent.
+    pub fn compute_delta(&self, files: &[FileEntry]) -> FileChanges {
+        self.manifest.diff(files)
+    }
+
+    /// Records the files as sent, replacing the previous state, and starts a new turn.
+    pub fn record(&mut self, files: &[FileEntry]) {
+        self.manifest = FileManifest::from_files(files);
+        self.turns += 1;
+    }
+}
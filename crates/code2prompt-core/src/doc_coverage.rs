@@ -0,0 +1,365 @@
+//! Documentation coverage of public items.
+//!
+//! Finds the public items of Rust, TypeScript/JavaScript and Python files that have no
+//! doc comment. The detection works line by line on the declarations rather than on
+//! a full syntax tree: it recognizes the usual formatting of each language, which is
+//! enough to point a model at the items to document.
+
+use serde::Serialize;
+use std::path::Path;
+
+/// A public item without documentation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DocGap {
+    /// 1-based line of the declaration
+    pub line: usize,
+    /// The kind of item, as written in the source (`fn`, `struct`, `class`, `def`, ...)
+    pub kind: String,
+    pub name: String,
+}
+
+/// Documentation coverage of a single file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileDocCoverage {
+    pub path: String,
+    pub language: &'static str,
+    /// Number of public items
+    pub items: usize,
+    /// Number of public items with a doc comment
+    pub documented: usize,
+    pub gaps: Vec<DocGap>,
+}
+
+/// Documentation coverage of a codebase
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DocCoverageReport {
+    /// Number of files in a supported language
+    pub analyzed_files: usize,
+    pub total_items: usize,
+    pub documented_items: usize,
+    /// Percentage of documented public items, 100 when there are none
+    pub coverage: f64,
+    /// The files with undocumented items, in the order they were analyzed
+    pub files: Vec<FileDocCoverage>,
+}
+
+impl DocCoverageReport {
+    /// Builds the report from the coverage of each analyzed file.
+    pub fn from_files(files: impl IntoIterator<Item = FileDocCoverage>) -> Self {
+        let mut report = DocCoverageReport::default();
+        for file in files {
+            report.analyzed_files += 1;
+            report.total_items += file.items;
+            report.documented_items += file.documented;
+            if !file.gaps.is_empty() {
+                report.files.push(file);
+            }
+        }
+        report.coverage = if report.total_items == 0 {
+            100.0
+        } else {
+            report.documented_items as f64 * 100.0 / report.total_items as f64
+        };
+        report
+    }
+
+    /// Number of public items without documentation
+    pub fn gap_count(&self) -> usize {
+        self.total_items - self.documented_items
+    }
+}
+
+/// Finds the undocumented public items of a file.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file, whose extension selects the language
+/// * `content` - The content of the file
+///
+/// # Returns
+///
+/// * `Option<FileDocCoverage>` - The coverage of the file, `None` if its language is not supported
+pub fn analyze_file(path: &str, content: &str) -> Option<FileDocCoverage> {
+    let lines: Vec<&str> = content.lines().collect();
+    let (language, items) = public_items(path, &lines)?;
+
+    let documented = items.iter().filter(|(_, documented)| *documented).count();
+    Some(FileDocCoverage {
+        path: path.to_string(),
+        language,
+        items: items.len(),
+        documented,
+        gaps: items
+            .into_iter()
+            .filter(|(_, documented)| !documented)
+            .map(|(gap, _)| gap)
+            .collect(),
+    })
+}
+
+/// The language of a file and its public items, with whether each one is documented.
+pub(crate) fn public_items(
+    path: &str,
+    lines: &[&str],
+) -> Option<(&'static str, Vec<(DocGap, bool)>)> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    match extension.as_str() {
+        "rs" => Some(("rust", rust_items(lines))),
+        "ts" | "tsx" | "mts" | "cts" | "js" | "jsx" | "mjs" | "cjs" => {
+            Some(("typescript", typescript_items(lines)))
+        }
+        "py" | "pyi" => Some(("python", python_items(lines))),
+        _ => None,
+    }
+}
+
+/// Public Rust items, with whether they have a doc comment.
+fn rust_items(lines: &[&str]) -> Vec<(DocGap, bool)> {
+    const KINDS: [&str; 9] = [
+        "fn", "struct", "enum", "trait", "type", "const", "static", "mod", "union",
+    ];
+    let mut items = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        // `pub(crate)` and other restricted visibilities are not public
+        let Some(mut rest) = line.trim_start().strip_prefix("pub ") else {
+            continue;
+        };
+        loop {
+            let stripped = ["unsafe ", "async ", "default ", "extern \"C\" ", "extern "]
+                .iter()
+                .find_map(|qualifier| rest.strip_prefix(qualifier))
+                .or_else(|| {
+                    // `const fn` is a qualified function, `const NAME` a constant
+                    rest.strip_prefix("const ").filter(|after| {
+                        ["fn ", "unsafe ", "async "]
+                            .iter()
+                            .any(|q| after.starts_with(q))
+                    })
+                });
+            match stripped {
+                Some(stripped) => rest = stripped.trim_start(),
+                None => break,
+            }
+        }
+
+        let Some((kind, name)) = KINDS.iter().find_map(|kind| {
+            let name = rest.strip_prefix(kind)?.strip_prefix(' ')?;
+            Some((*kind, identifier(name)?))
+        }) else {
+            continue;
+        };
+        // Modules declared in another file are documented there, with `//!`
+        if kind == "mod" && line.trim_end().ends_with(';') {
+            continue;
+        }
+
+        items.push((gap(index, kind, name), rust_documented(lines, index)));
+    }
+    items
+}
+
+/// Whether the item declared on `index` is preceded by `///`, `/** */` or `#[doc]`.
+fn rust_documented(lines: &[&str], index: usize) -> bool {
+    let mut i = index;
+    while i > 0 {
+        i -= 1;
+        let line = lines[i].trim();
+        if line.starts_with("///") || line.starts_with("#[doc") {
+            return true;
+        }
+        if line.ends_with("*/") {
+            return block_comment_is_doc(lines, i);
+        }
+        if line.starts_with("#[") {
+            continue;
+        }
+        // The end of an attribute spanning several lines
+        if line.ends_with(")]") {
+            while i > 0 && !lines[i].trim_start().starts_with("#[") {
+                i -= 1;
+            }
+            let start = lines[i].trim_start();
+            if !start.starts_with("#[") {
+                return false;
+            }
+            if start.starts_with("#[doc") {
+                return true;
+            }
+            continue;
+        }
+        return false;
+    }
+    false
+}
+
+/// Exported TypeScript and JavaScript items, with whether they have a JSDoc comment.
+fn typescript_items(lines: &[&str]) -> Vec<(DocGap, bool)> {
+    const KINDS: [&str; 11] = [
+        "function*",
+        "function",
+        "class",
+        "interface",
+        "type",
+        "enum",
+        "const",
+        "let",
+        "var",
+        "namespace",
+        "module",
+    ];
+    let mut items = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let Some(mut rest) = line.trim_start().strip_prefix("export ") else {
+            continue;
+        };
+        let mut is_default = false;
+        loop {
+            if let Some(stripped) = rest.strip_prefix("default ") {
+                is_default = true;
+                rest = stripped.trim_start();
+            } else if let Some(stripped) = ["declare ", "async ", "abstract "]
+                .iter()
+                .find_map(|qualifier| rest.strip_prefix(qualifier))
+            {
+                rest = stripped.trim_start();
+            } else {
+                break;
+            }
+        }
+
+        let Some((kind, name)) = KINDS.iter().find_map(|kind| {
+            let after = rest.strip_prefix(kind)?;
+            if !after.starts_with([' ', '(', '<', '{']) {
+                return None;
+            }
+            // Anonymous default exports are named after the export
+            let name =
+                identifier(after.trim_start()).or_else(|| is_default.then_some("default"))?;
+            Some((kind.trim_end_matches('*'), name))
+        }) else {
+            continue;
+        };
+
+        items.push((gap(index, kind, name), typescript_documented(lines, index)));
+    }
+    items
+}
+
+/// Whether the item declared on `index` is preceded by a `/** */` comment, decorators aside.
+fn typescript_documented(lines: &[&str], index: usize) -> bool {
+    let mut i = index;
+    while i > 0 {
+        i -= 1;
+        let line = lines[i].trim();
+        if line.starts_with('@') {
+            continue;
+        }
+        return line.ends_with("*/") && block_comment_is_doc(lines, i);
+    }
+    false
+}
+
+/// Whether the block comment ending on `end` starts with `/**`.
+fn block_comment_is_doc(lines: &[&str], end: usize) -> bool {
+    lines[..=end]
+        .iter()
+        .rev()
+        .find(|line| line.contains("/*"))
+        .is_some_and(|line| line.contains("/**") || line.contains("/*!"))
+}
+
+/// Public Python functions, classes and methods, with whether they have a docstring.
+fn python_items(lines: &[&str]) -> Vec<(DocGap, bool)> {
+    let mut items = Vec::new();
+    // Enclosing definitions: indentation, whether it is a public class
+    let mut scopes: Vec<(usize, bool)> = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let (kind, rest) = if let Some(rest) = trimmed.strip_prefix("class ") {
+            ("class", rest)
+        } else if let Some(rest) = trimmed
+            .strip_prefix("def ")
+            .or_else(|| trimmed.strip_prefix("async def "))
+        {
+            ("def", rest)
+        } else {
+            continue;
+        };
+        let Some(name) = identifier(rest.trim_start()) else {
+            continue;
+        };
+
+        while scopes
+            .last()
+            .is_some_and(|(scope_indent, _)| *scope_indent >= indent)
+        {
+            scopes.pop();
+        }
+        // Items nested in functions or in private classes are not part of the API
+        let visible = scopes.iter().all(|(_, public_class)| *public_class);
+        let public = visible && !name.starts_with('_');
+        scopes.push((indent, public && kind == "class"));
+
+        if public {
+            items.push((gap(index, kind, name), python_documented(lines, index)));
+        }
+    }
+    items
+}
+
+/// Whether the definition starting on `index` has a docstring as its first statement.
+fn python_documented(lines: &[&str], index: usize) -> bool {
+    // The signature may span several lines, it ends with the first line ending with `:`
+    let Some(end) = (index..lines.len().min(index + 50))
+        .find(|&i| strip_python_comment(lines[i]).trim_end().ends_with(':'))
+    else {
+        return false;
+    };
+    lines[end + 1..]
+        .iter()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .is_some_and(|line| {
+            let line = line.trim_start_matches(['r', 'R', 'u', 'U', 'b', 'B']);
+            line.starts_with('"') || line.starts_with('\'')
+        })
+}
+
+/// The part of a Python line before a `#` comment, ignoring `#` inside strings.
+fn strip_python_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// The identifier at the start of `text`, if any.
+fn identifier(text: &str) -> Option<&str> {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(text.len());
+    (end > 0).then(|| &text[..end])
+}
+
+fn gap(index: usize, kind: &str, name: &str) -> DocGap {
+    DocGap {
+        line: index + 1,
+        kind: kind.to_string(),
+        name: name.to_string(),
+    }
+}
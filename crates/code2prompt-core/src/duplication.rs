@@ -0,0 +1,242 @@
+//! Detection of code duplicated across the included files.
+//!
+//! Copy-pasted utilities and vendored snippets cost tokens once per copy. Blocks of at least
+//! `duplicate_min_lines` lines repeated from an earlier file, or from earlier in the same
+//! file, are found by comparing their lines with whitespace collapsed. Following the
+//! [`DuplicateHandling`], the duplicates are reported with the tokens they cost, or also
+//! replaced by a stub referring to the first copy.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::configuration::Code2PromptConfig;
+use crate::path::FileEntry;
+use crate::tokenizer::count_tokens_with_mode;
+
+/// Default minimum number of lines of a duplicated block
+pub const DEFAULT_DUPLICATE_MIN_LINES: usize = 6;
+
+/// What is done with duplicated code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateHandling {
+    /// The duplicates are reported, the files are left as is.
+    Report,
+    /// The duplicates are reported and replaced by a stub referring to the first copy.
+    Stub,
+}
+
+impl std::fmt::Display for DuplicateHandling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DuplicateHandling::Report => write!(f, "report"),
+            DuplicateHandling::Stub => write!(f, "stub"),
+        }
+    }
+}
+
+/// A block of lines repeating an earlier block. Lines are numbered from 1 in the content
+/// of the files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Duplicate {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The file of the first copy
+    pub original_path: String,
+    pub original_start_line: usize,
+    pub original_end_line: usize,
+    /// The tokens of the block, saved by stubbing it
+    pub tokens: usize,
+}
+
+/// The duplicated code found in the files
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DuplicationReport {
+    pub duplicates: Vec<Duplicate>,
+    /// Tokens of all the duplicates
+    pub tokens: usize,
+    /// Whether the duplicates were replaced by stubs
+    pub stubbed: bool,
+}
+
+/// The content of a file, split from its code fence.
+struct FileContent<'a> {
+    opening: Option<&'a str>,
+    lines: Vec<&'a str>,
+    /// Index and normalized text of the non-blank lines
+    normalized: Vec<(usize, String)>,
+}
+
+impl<'a> FileContent<'a> {
+    fn new(code: &'a str, config: &Code2PromptConfig, line_number: &Regex) -> Self {
+        let (opening, content) = match code.split_once('\n') {
+            Some((fence, rest)) if !config.no_codeblock => {
+                (Some(fence), rest.strip_suffix("```").unwrap_or(rest))
+            }
+            _ => (None, code),
+        };
+        let lines: Vec<&str> = content.lines().collect();
+        let normalized = lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let line = if config.line_numbers {
+                    line_number.replace(line, "")
+                } else {
+                    (*line).into()
+                };
+                let words: Vec<&str> = line.split_whitespace().collect();
+                (!words.is_empty()).then(|| (index, words.join(" ")))
+            })
+            .collect();
+        Self {
+            opening,
+            lines,
+            normalized,
+        }
+    }
+
+    fn window_hash(&self, start: usize, length: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for (_, line) in &self.normalized[start..start + length] {
+            line.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    fn same_line(&self, index: usize, other: &FileContent, other_index: usize) -> bool {
+        self.normalized[index].1 == other.normalized[other_index].1
+    }
+}
+
+/// Finds the blocks of at least `config.duplicate_min_lines` non-blank lines repeating an
+/// earlier block, the files being read in their order.
+///
+/// # Arguments
+///
+/// * `files` - The files of the prompt, in their sort order
+/// * `config` - The configuration giving the minimum block length and the tokenizer
+///
+/// # Returns
+///
+/// * `DuplicationReport` - The duplicates, in the order of the files
+pub fn find_duplicates(files: &[FileEntry], config: &Code2PromptConfig) -> DuplicationReport {
+    let min_lines = match config.duplicate_min_lines {
+        0 => DEFAULT_DUPLICATE_MIN_LINES,
+        lines => lines,
+    };
+    let line_number = Regex::new(r"^\s*\d+ \| ?").unwrap();
+    let contents: Vec<FileContent> = files
+        .iter()
+        .map(|file| FileContent::new(&file.code, config, &line_number))
+        .collect();
+
+    let mut report = DuplicationReport::default();
+    // First occurrence of each window of lines
+    let mut windows: HashMap<u64, (usize, usize)> = HashMap::new();
+    for (file_index, content) in contents.iter().enumerate() {
+        let mut position = 0;
+        while position + min_lines <= content.normalized.len() {
+            let hash = content.window_hash(position, min_lines);
+            let original = windows.get(&hash).copied().filter(|&(other, start)| {
+                // A block overlapping its own first copy is not a duplicate
+                (other != file_index || start + min_lines <= position)
+                    && (0..min_lines)
+                        .all(|i| content.same_line(position + i, &contents[other], start + i))
+            });
+            let Some((other, start)) = original else {
+                windows.entry(hash).or_insert((file_index, position));
+                position += 1;
+                continue;
+            };
+
+            let other_content = &contents[other];
+            let mut length = min_lines;
+            while position + length < content.normalized.len()
+                && start + length < other_content.normalized.len()
+                && (other != file_index || start + length < position)
+                && content.same_line(position + length, other_content, start + length)
+            {
+                length += 1;
+            }
+
+            let first_line = content.normalized[position].0;
+            let last_line = content.normalized[position + length - 1].0;
+            let text = content.lines[first_line..=last_line].join("\n");
+            let tokens = count_tokens_with_mode(
+                &text,
+                &files[file_index].extension,
+                &config.encoding,
+                config.tokenizer_model.as_deref(),
+                config.token_count_mode,
+            );
+            report.tokens += tokens;
+            report.duplicates.push(Duplicate {
+                path: files[file_index].path.clone(),
+                start_line: first_line + 1,
+                end_line: last_line + 1,
+                original_path: files[other].path.clone(),
+                original_start_line: other_content.normalized[start].0 + 1,
+                original_end_line: other_content.normalized[start + length - 1].0 + 1,
+                tokens,
+            });
+            position += length;
+        }
+    }
+    report
+}
+
+/// Replaces the duplicates of a report by a stub referring to their first copy.
+///
+/// # Arguments
+///
+/// * `files` - The files the report was made from
+/// * `report` - The duplicates found in the files, marked as stubbed
+/// * `config` - The configuration giving the tokenizer
+pub fn stub_duplicates(
+    files: &mut [FileEntry],
+    report: &mut DuplicationReport,
+    config: &Code2PromptConfig,
+) {
+    let line_number = Regex::new(r"^\s*\d+ \| ?").unwrap();
+    for file in files.iter_mut() {
+        let duplicates: Vec<&Duplicate> = report
+            .duplicates
+            .iter()
+            .filter(|duplicate| duplicate.path == file.path)
+            .collect();
+        if duplicates.is_empty() {
+            continue;
+        }
+
+        let content = FileContent::new(&file.code, config, &line_number);
+        let opening = content.opening.map(str::to_string);
+        let mut lines: Vec<String> = content.lines.iter().map(|line| line.to_string()).collect();
+        // From the end, so that the line numbers of the earlier duplicates stay valid
+        for duplicate in duplicates.iter().rev() {
+            let stub = format!(
+                "... [duplicate of {}:{}-{}]",
+                duplicate.original_path, duplicate.original_start_line, duplicate.original_end_line
+            );
+            lines.splice(duplicate.start_line - 1..duplicate.end_line, [stub]);
+        }
+        let code = lines.join("\n");
+        let token_count = count_tokens_with_mode(
+            &code,
+            &file.extension,
+            &config.encoding,
+            config.tokenizer_model.as_deref(),
+            config.token_count_mode,
+        );
+        file.code = match opening {
+            Some(fence) => format!("{}\n{}\n```", fence, code),
+            None => code,
+        };
+        file.token_count = token_count;
+    }
+    report.stubbed = true;
+}
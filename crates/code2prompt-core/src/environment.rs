@@ -0,0 +1,345 @@
+//! Summary of the intended development environment.
+//!
+//! Reads the files declaring the toolchain a project is meant to be built with, Nix
+//! flakes and shells, devcontainers and toolchain version files, and summarizes them
+//! for the `environment_spec` template variable. Prompts about build failures can then
+//! compare the environment the project expects with the one that fails.
+
+use regex::Regex;
+use serde::Serialize;
+use std::path::Path;
+
+/// Devcontainer definitions, in the order the tooling looks them up
+const DEVCONTAINER_FILES: [&str; 2] = [".devcontainer/devcontainer.json", ".devcontainer.json"];
+
+/// The development environment declared by a project
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EnvironmentSpec {
+    /// The files the environment was read from, relative to the root
+    pub sources: Vec<String>,
+    /// The container image or Dockerfile of the devcontainer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_image: Option<String>,
+    /// The nixpkgs revision the Nix environment is pinned to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nixpkgs: Option<String>,
+    /// Tools and their versions, like `rust 1.80.0` or `nodejs_20`
+    pub tools: Vec<String>,
+    /// Commands run when the environment is created or entered
+    pub setup_commands: Vec<String>,
+    /// Environment variables, as `NAME=value`
+    pub env: Vec<String>,
+    /// Markdown list summarizing all of the above
+    pub summary: String,
+}
+
+/// Detects the development environment declared at the root of a project.
+///
+/// # Arguments
+///
+/// * `root` - The root directory of the project
+///
+/// # Returns
+///
+/// * `Option<EnvironmentSpec>` - `None` if the project declares no environment
+pub fn detect_environment(root: &Path) -> Option<EnvironmentSpec> {
+    let mut spec = EnvironmentSpec::default();
+    let read = |path: &str| std::fs::read_to_string(root.join(path)).ok();
+
+    for nix_file in ["flake.nix", "shell.nix"] {
+        if let Some(content) = read(nix_file) {
+            spec.sources.push(nix_file.to_string());
+            read_nix(&content, &mut spec);
+        }
+    }
+    if let Some((path, content)) = DEVCONTAINER_FILES
+        .iter()
+        .find_map(|path| Some((*path, read(path)?)))
+    {
+        spec.sources.push(path.to_string());
+        read_devcontainer(&content, &mut spec);
+    }
+    if let Some(content) = read("rust-toolchain.toml") {
+        spec.sources.push("rust-toolchain.toml".to_string());
+        read_rust_toolchain_toml(&content, &mut spec);
+    } else if let Some(content) = read("rust-toolchain") {
+        spec.sources.push("rust-toolchain".to_string());
+        spec.tools.push(format!("rust {}", content.trim()));
+    }
+    if let Some(content) = read(".tool-versions") {
+        spec.sources.push(".tool-versions".to_string());
+        spec.tools.extend(
+            content
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or("").trim())
+                .filter(|line| !line.is_empty())
+                .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" ")),
+        );
+    }
+    for (path, tool) in [(".nvmrc", "node"), (".python-version", "python")] {
+        if let Some(content) = read(path)
+            && let Some(version) = content.lines().next().map(str::trim)
+            && !version.is_empty()
+        {
+            spec.sources.push(path.to_string());
+            spec.tools.push(format!("{} {}", tool, version));
+        }
+    }
+
+    if spec.sources.is_empty() {
+        return None;
+    }
+    spec.tools.dedup();
+    spec.summary = summarize(&spec);
+    Some(spec)
+}
+
+/// Reads the nixpkgs pin, the packages and the shell hook of a flake or a shell.
+fn read_nix(content: &str, spec: &mut EnvironmentSpec) {
+    // Comments would be taken for packages
+    let content: String = content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let nixpkgs_url = Regex::new(r#"nixpkgs(?:\.url)?\s*=\s*"([^"]+)""#).unwrap();
+    let package_list =
+        Regex::new(r"\b(?:packages|buildInputs|nativeBuildInputs)\s*=\s*(?:with\s+[\w.]+;\s*)?\[")
+            .unwrap();
+    let shell_hook = Regex::new(r"shellHook\s*=\s*''((?s).*?)''").unwrap();
+
+    if spec.nixpkgs.is_none() {
+        spec.nixpkgs = nixpkgs_url
+            .captures(&content)
+            .map(|captures| captures[1].to_string());
+    }
+    for list in package_list.find_iter(&content) {
+        for package in list_items(&content[list.end()..]) {
+            let package = package.strip_prefix("pkgs.").unwrap_or(package);
+            if !spec.tools.iter().any(|tool| tool == package) {
+                spec.tools.push(package.to_string());
+            }
+        }
+    }
+    for hook in shell_hook.captures_iter(&content) {
+        spec.setup_commands.extend(
+            hook[1]
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        );
+    }
+}
+
+/// The items of a Nix list starting at `list`, after its opening bracket, up to its
+/// closing bracket. An expression in parentheses counts as its first name, like
+/// `python3.withPackages` for `(python3.withPackages (ps: [ ps.requests ]))`.
+fn list_items(list: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut expression_named = false;
+    let mut start = None;
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || "_-.".contains(c);
+
+    for (i, c) in list
+        .char_indices()
+        .chain(std::iter::once((list.len(), ' ')))
+    {
+        if is_name_char(c) {
+            start.get_or_insert(i);
+            continue;
+        }
+        if let Some(begin) = start.take() {
+            let name = &list[begin..i];
+            let named = name.starts_with(|c: char| c.is_ascii_alphabetic());
+            if named && (depth == 0 || (depth == 1 && !expression_named)) {
+                items.push(name);
+                expression_named |= depth == 1;
+            }
+        }
+        match c {
+            '(' | '[' | '{' => {
+                depth += 1;
+                if depth == 1 {
+                    expression_named = false;
+                }
+            }
+            ')' | '}' => depth = depth.saturating_sub(1),
+            ']' if depth == 0 => break,
+            ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    items
+}
+
+/// Reads the image, features, lifecycle commands and variables of a devcontainer.
+fn read_devcontainer(content: &str, spec: &mut EnvironmentSpec) {
+    let Ok(config) = serde_json::from_str::<serde_json::Value>(&strip_jsonc(content)) else {
+        log::warn!("Could not parse the devcontainer definition");
+        return;
+    };
+
+    spec.base_image = config
+        .get("image")
+        .and_then(|image| image.as_str())
+        .map(str::to_string)
+        .or_else(|| {
+            let build = config.get("build")?;
+            let dockerfile = build.get("dockerfile")?.as_str()?;
+            Some(format!("built from {}", dockerfile))
+        })
+        .or_else(|| {
+            let compose = config.get("dockerComposeFile")?;
+            let service = config.get("service").and_then(|s| s.as_str()).unwrap_or("");
+            Some(format!("docker compose {} (service {})", compose, service))
+        });
+
+    if let Some(features) = config.get("features").and_then(|f| f.as_object()) {
+        for (feature, options) in features {
+            // `ghcr.io/devcontainers/features/node:1` is the `node` feature
+            let name = feature
+                .rsplit('/')
+                .next()
+                .unwrap_or(feature)
+                .split(':')
+                .next()
+                .unwrap_or(feature);
+            match options.get("version").and_then(|v| v.as_str()) {
+                Some(version) => spec.tools.push(format!("{} {}", name, version)),
+                None => spec.tools.push(name.to_string()),
+            }
+        }
+    }
+
+    for hook in [
+        "onCreateCommand",
+        "updateContentCommand",
+        "postCreateCommand",
+        "postStartCommand",
+    ] {
+        match config.get(hook) {
+            Some(serde_json::Value::String(command)) => {
+                spec.setup_commands.push(command.clone());
+            }
+            Some(serde_json::Value::Array(arguments)) => spec.setup_commands.push(
+                arguments
+                    .iter()
+                    .filter_map(|a| a.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            // Named commands run in parallel
+            Some(serde_json::Value::Object(commands)) => {
+                spec.setup_commands.extend(
+                    commands
+                        .values()
+                        .filter_map(|command| command.as_str())
+                        .map(str::to_string),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    for section in ["containerEnv", "remoteEnv"] {
+        if let Some(variables) = config.get(section).and_then(|v| v.as_object()) {
+            spec.env.extend(
+                variables.iter().map(|(name, value)| {
+                    format!("{}={}", name, value.as_str().unwrap_or_default())
+                }),
+            );
+        }
+    }
+}
+
+fn read_rust_toolchain_toml(content: &str, spec: &mut EnvironmentSpec) {
+    let Ok(manifest) = content.parse::<toml::Table>() else {
+        return;
+    };
+    let Some(toolchain) = manifest.get("toolchain") else {
+        return;
+    };
+    let channel = toolchain
+        .get("channel")
+        .and_then(|c| c.as_str())
+        .unwrap_or("stable");
+    let extras: Vec<&str> = ["components", "targets"]
+        .iter()
+        .filter_map(|key| toolchain.get(*key)?.as_array())
+        .flatten()
+        .filter_map(|item| item.as_str())
+        .collect();
+    if extras.is_empty() {
+        spec.tools.push(format!("rust {}", channel));
+    } else {
+        spec.tools
+            .push(format!("rust {} (with {})", channel, extras.join(", ")));
+    }
+}
+
+/// Removes the comments and trailing commas that devcontainer files allow.
+fn strip_jsonc(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut json = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            json.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                json.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+            json.push(c);
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+            continue;
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if !matches!(next, Some('}') | Some(']')) {
+                json.push(c);
+            }
+        } else {
+            json.push(c);
+        }
+        i += 1;
+    }
+    json
+}
+
+fn summarize(spec: &EnvironmentSpec) -> String {
+    let mut lines = vec![format!("- Declared in: {}", spec.sources.join(", "))];
+    if let Some(image) = &spec.base_image {
+        lines.push(format!("- Container image: {}", image));
+    }
+    if let Some(nixpkgs) = &spec.nixpkgs {
+        lines.push(format!("- Nixpkgs: {}", nixpkgs));
+    }
+    if !spec.tools.is_empty() {
+        lines.push(format!("- Tools: {}", spec.tools.join(", ")));
+    }
+    if !spec.setup_commands.is_empty() {
+        lines.push(format!("- Setup: {}", spec.setup_commands.join("; ")));
+    }
+    if !spec.env.is_empty() {
+        lines.push(format!("- Environment: {}", spec.env.join(", ")));
+    }
+    lines.join("\n")
+}
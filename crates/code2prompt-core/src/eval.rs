@@ -0,0 +1,131 @@
+//! Evaluation of prompt variants.
+//!
+//! Each variant prompt of a [`VariantManifest`](crate::variants::VariantManifest) is sent
+//! to a model, then the response is graded by a model with a rubric prompt. The rubric is a
+//! Handlebars template receiving `response`, `variant` and `settings`; when it does not use
+//! `response`, the response is appended to it. The grader ends its answer with
+//! `Score: <number>`, and the scores are collected into a CSV table.
+
+use anyhow::Result;
+use regex::Regex;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use crate::template::{handlebars_setup, render_template};
+use crate::variants::{VariantAxis, VariantRecord};
+
+/// Line asking the grader for a parsable score, appended to every rubric
+pub const SCORE_INSTRUCTION: &str = "End your answer with a line `Score: <number>`.";
+
+/// The score of a response to a variant prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalScore {
+    pub variant: String,
+    /// The run of the variant, from 1
+    pub run: usize,
+    pub settings: BTreeMap<VariantAxis, String>,
+    /// The token count of the variant prompt
+    pub token_count: usize,
+    /// The score given by the grader, if one could be read from its answer
+    pub score: Option<f64>,
+}
+
+/// Builds the prompt grading a response with the rubric.
+///
+/// # Arguments
+///
+/// * `rubric` - The rubric, a Handlebars template
+/// * `variant` - The variant the response answers
+/// * `response` - The response of the model to the variant prompt
+///
+/// # Returns
+///
+/// * `Result<String>` - The grading prompt
+pub fn grading_prompt(rubric: &str, variant: &VariantRecord, response: &str) -> Result<String> {
+    let handlebars = handlebars_setup(rubric, "rubric")?;
+    let data = json!({
+        "response": response,
+        "variant": variant.name,
+        "settings": variant.settings,
+    });
+    let mut prompt = render_template(&handlebars, "rubric", &data)?;
+
+    let uses_response = Regex::new(r"\{\{\{?~?\s*response\s*~?\}").unwrap();
+    if !uses_response.is_match(rubric) {
+        prompt.push_str("\n\nResponse:\n\n");
+        prompt.push_str(response.trim());
+    }
+    prompt.push_str("\n\n");
+    prompt.push_str(SCORE_INSTRUCTION);
+    Ok(prompt)
+}
+
+/// Reads the score from the answer of the grader: the last `Score: <number>`, or else the
+/// last number of the answer.
+///
+/// # Arguments
+///
+/// * `answer` - The answer of the grader
+///
+/// # Returns
+///
+/// * `Option<f64>` - The score, if the answer gives one
+pub fn parse_score(answer: &str) -> Option<f64> {
+    let score = Regex::new(r"(?i)score[^\w\n]*?(-?\d+(?:\.\d+)?)").unwrap();
+    let number = Regex::new(r"-?\d+(?:\.\d+)?").unwrap();
+    score
+        .captures_iter(answer)
+        .last()
+        .map(|captures| captures[1].to_string())
+        .or_else(|| {
+            number
+                .find_iter(answer)
+                .last()
+                .map(|m| m.as_str().to_string())
+        })
+        .and_then(|value| value.parse().ok())
+}
+
+/// Writes the scores as a CSV table, with a column per axis.
+///
+/// # Arguments
+///
+/// * `axes` - The axes the variants differ on
+/// * `scores` - The scores, one row each
+///
+/// # Returns
+///
+/// * `String` - The CSV table, with a header row
+pub fn scores_csv(axes: &[VariantAxis], scores: &[EvalScore]) -> String {
+    let mut header = vec!["variant".to_string(), "run".to_string()];
+    header.extend(axes.iter().map(|axis| axis.to_string()));
+    header.extend(["token_count".to_string(), "score".to_string()]);
+
+    let mut csv = csv_row(&header);
+    for score in scores {
+        let mut row = vec![score.variant.clone(), score.run.to_string()];
+        row.extend(
+            axes.iter()
+                .map(|axis| score.settings.get(axis).cloned().unwrap_or_default()),
+        );
+        row.push(score.token_count.to_string());
+        row.push(score.score.map(|s| s.to_string()).unwrap_or_default());
+        csv.push_str(&csv_row(&row));
+    }
+    csv
+}
+
+/// A CSV row, quoting the fields that need it.
+fn csv_row(fields: &[String]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+    format!("{}\n", fields.join(","))
+}
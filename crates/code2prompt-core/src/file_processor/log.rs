@@ -0,0 +1,164 @@
+//! Log file processor summarizing what matters for debugging.
+//!
+//! Logs are mostly noise around a few relevant lines. This processor:
+//! - Collapses runs of similar lines (differing only in numbers, like timestamps)
+//! - Keeps error and warning lines with a few lines of context, the first occurrence
+//!   of each only
+//! - Keeps the first and last lines, showing how the run started and ended
+//! - Reports the elided line ranges and counts
+
+use super::{DefaultTextProcessor, FileProcessor};
+use anyhow::Result;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Lines of context kept around error and warning lines
+const CONTEXT_LINES: usize = 2;
+
+/// Lines kept at the start of the log
+const HEAD_LINES: usize = 3;
+
+/// Lines kept at the end of the log
+const TAIL_LINES: usize = 5;
+
+/// Processor summarizing log files around their errors and warnings.
+pub struct LogSummaryProcessor;
+
+/// Consecutive similar lines, shown once
+struct Run<'a> {
+    /// Index of the first line of the run
+    start: usize,
+    line: &'a str,
+    key: String,
+    count: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+impl LogSummaryProcessor {
+    /// Summarizes the lines of a log.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The content of the log
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The summary, starting with a line of counts
+    pub fn summarize(&self, text: &str) -> String {
+        let digits = Regex::new(r"\d+").unwrap();
+        let error = Regex::new(
+            r"(?i)\b(error|fatal|panic|panicked|exception|critical|traceback|failed|failure)\b",
+        )
+        .unwrap();
+        let warning = Regex::new(r"(?i)\b(warn|warning)\b").unwrap();
+        let severity = |line: &str| {
+            if error.is_match(line) {
+                Some(Severity::Error)
+            } else if warning.is_match(line) {
+                Some(Severity::Warning)
+            } else {
+                None
+            }
+        };
+
+        let lines: Vec<&str> = text.lines().collect();
+        let mut runs: Vec<Run> = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            let key = digits.replace_all(line.trim(), "0").into_owned();
+            match runs.last_mut() {
+                Some(run) if run.key == key => run.count += 1,
+                _ => runs.push(Run {
+                    start: index,
+                    line,
+                    key,
+                    count: 1,
+                }),
+            }
+        }
+
+        // Occurrences of each error or warning, only the first one is shown
+        let mut occurrences: HashMap<&str, usize> = HashMap::new();
+        let (mut errors, mut warnings) = (0, 0);
+        for run in &runs {
+            match severity(run.line) {
+                Some(Severity::Error) => errors += run.count,
+                Some(Severity::Warning) => warnings += run.count,
+                None => continue,
+            }
+            *occurrences.entry(run.key.as_str()).or_default() += run.count;
+        }
+
+        let mut kept = vec![false; runs.len()];
+        let mut first_occurrence = vec![false; runs.len()];
+        let mut seen: Vec<&str> = Vec::new();
+        for (index, run) in runs.iter().enumerate() {
+            if index < HEAD_LINES || index + TAIL_LINES >= runs.len() {
+                kept[index] = true;
+            }
+            if severity(run.line).is_some() && !seen.contains(&run.key.as_str()) {
+                seen.push(&run.key);
+                first_occurrence[index] = true;
+                let end = (index + CONTEXT_LINES).min(runs.len() - 1);
+                for keep in &mut kept[index.saturating_sub(CONTEXT_LINES)..=end] {
+                    *keep = true;
+                }
+            }
+        }
+
+        let mut body = String::new();
+        let mut elided = 0;
+        let mut collapsed = 0;
+        let mut index = 0;
+        while index < runs.len() {
+            if !kept[index] {
+                let first = runs[index].start;
+                while index < runs.len() && !kept[index] {
+                    index += 1;
+                }
+                let last = runs.get(index).map_or(lines.len(), |run| run.start);
+                elided += last - first;
+                if last - first == 1 {
+                    body.push_str(&format!("... [line {} elided]\n", last));
+                } else {
+                    body.push_str(&format!("... [lines {}-{} elided]\n", first + 1, last));
+                }
+                continue;
+            }
+            let run = &runs[index];
+            body.push_str(run.line);
+            if run.count > 1 {
+                collapsed += run.count - 1;
+                body.push_str(&format!(" [repeated {} times]", run.count));
+            }
+            let total = occurrences.get(run.key.as_str()).copied().unwrap_or(0);
+            if first_occurrence[index] && total > run.count {
+                body.push_str(&format!(" [{} occurrences in total]", total));
+            }
+            body.push('\n');
+            index += 1;
+        }
+
+        format!(
+            "Log Summary ({} lines, {} errors, {} warnings, {} lines elided, {} repeated lines collapsed):\n{}",
+            lines.len(),
+            errors,
+            warnings,
+            elided,
+            collapsed,
+            body
+        )
+    }
+}
+
+impl FileProcessor for LogSummaryProcessor {
+    fn process(&self, content: &[u8], path: &Path) -> Result<String> {
+        let text = DefaultTextProcessor.process(content, path)?;
+        Ok(self.summarize(&text))
+    }
+}
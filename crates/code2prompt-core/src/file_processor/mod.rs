@@ -4,6 +4,7 @@
 //! in order to optimize for LLM token usage. The main idea is to extract the schema rather than
 //! raw data where applicable. (e.g., schema + sample for CSV, code cells for Jupyter notebooks).
 
+use crate::configuration::Code2PromptConfig;
 use anyhow::Result;
 use std::path::Path;
 
@@ -11,12 +12,16 @@ mod csv;
 mod default;
 mod ipynb;
 mod jsonl;
+mod log;
+mod sample;
 mod tsv;
 
 pub use csv::CsvProcessor;
 pub use default::DefaultTextProcessor;
 pub use ipynb::JupyterNotebookProcessor;
 pub use jsonl::JsonLinesProcessor;
+pub use log::LogSummaryProcessor;
+pub use sample::{DataSample, DataSampleProcessor};
 pub use tsv::TsvProcessor;
 
 /// Trait for processing file contents into LLM-optimized string representations.
@@ -66,3 +71,25 @@ pub fn get_processor_for_extension(extension: &str) -> Box<dyn FileProcessor> {
         _ => Box::new(DefaultTextProcessor),
     }
 }
+
+/// Factory function to get the processor for a file extension, following the
+/// configuration: data files (CSV, TSV, JSON Lines) keep their first and last rows when
+/// `data_sample` is set, and logs are summarized when `summarize_logs` is set.
+///
+/// # Arguments
+///
+/// * `extension` - File extension (without dot)
+/// * `config` - The configuration
+///
+/// # Returns
+///
+/// * `Box<dyn FileProcessor>` - Processor instance for the given extension
+pub fn get_processor(extension: &str, config: &Code2PromptConfig) -> Box<dyn FileProcessor> {
+    match (extension.to_lowercase().as_str(), config.data_sample) {
+        ("csv" | "tsv" | "jsonl" | "ndjson", Some(sample)) => {
+            Box::new(DataSampleProcessor { sample })
+        }
+        ("log", _) if config.summarize_logs => Box::new(LogSummaryProcessor),
+        _ => get_processor_for_extension(extension),
+    }
+}
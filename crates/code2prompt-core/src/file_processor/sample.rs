@@ -0,0 +1,185 @@
+//! Head/tail sampling of data files.
+//!
+//! Instead of a single sample row, this processor keeps the first and last rows of CSV,
+//! TSV and JSON Lines files as written, along with the inferred column names and the
+//! row count. The tail shows how the data ends (totals, latest records, truncation),
+//! which the head alone cannot tell.
+
+use super::{DefaultTextProcessor, FileProcessor};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// Number of rows kept at the start and at the end of a data file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataSample {
+    /// Rows kept from the start, after the header
+    pub head: usize,
+    /// Rows kept from the end
+    #[serde(default)]
+    pub tail: usize,
+}
+
+/// Processor sampling the first and last rows of a data file.
+///
+/// Falls back to sampling plain lines if the file cannot be parsed.
+pub struct DataSampleProcessor {
+    pub sample: DataSample,
+}
+
+impl DataSampleProcessor {
+    /// Samples delimited rows, using the `csv` crate so that quoted fields spanning
+    /// several lines stay in one row.
+    fn sample_delimited(&self, content: &[u8], delimiter: u8, label: &str) -> Result<String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .from_reader(content);
+        let headers: Vec<String> = reader
+            .headers()
+            .context("Failed to read headers")?
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        if headers.is_empty() {
+            anyhow::bail!("{} file has no headers", label);
+        }
+
+        // Byte offset at which each row starts, the rows are sliced from the content
+        let mut starts = Vec::new();
+        for record in reader.records() {
+            let record = record.context("Failed to read a row")?;
+            if let Some(position) = record.position() {
+                starts.push(position.byte() as usize);
+            }
+        }
+        let rows: Vec<&str> = starts
+            .iter()
+            .enumerate()
+            .map(|(index, start)| {
+                let end = starts.get(index + 1).copied().unwrap_or(content.len());
+                std::str::from_utf8(&content[*start..end])
+                    .unwrap_or_default()
+                    .trim_end_matches(['\r', '\n'])
+            })
+            .collect();
+        let header_line =
+            std::str::from_utf8(&content[..starts.first().copied().unwrap_or(content.len())])
+                .unwrap_or_default()
+                .trim_end_matches(['\r', '\n']);
+
+        let mut output = self.title(label, rows.len(), "rows");
+        output.push_str(&format!("Columns: {}\n", headers.join(", ")));
+        output.push_str(header_line);
+        output.push('\n');
+        self.push_rows(&mut output, &rows, "rows");
+        Ok(output)
+    }
+
+    /// Samples JSON Lines, with the fields of the sampled objects in order of appearance.
+    fn sample_json_lines(&self, content: &[u8]) -> Result<String> {
+        let text = String::from_utf8_lossy(content);
+        let rows: Vec<&str> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        if rows.is_empty() {
+            anyhow::bail!("JSONL file is empty or has no valid lines");
+        }
+
+        let mut fields: Vec<String> = Vec::new();
+        for row in self.kept(&rows).into_iter().flatten() {
+            let object: Value = serde_json::from_str(row)
+                .with_context(|| format!("Failed to parse line as JSON: {}", row))?;
+            let Value::Object(map) = object else {
+                anyhow::bail!("Line is not a JSON object");
+            };
+            for key in map.keys() {
+                if !fields.contains(key) {
+                    fields.push(key.clone());
+                }
+            }
+        }
+
+        let mut output = self.title("JSONL", rows.len(), "lines");
+        output.push_str(&format!("Fields: {}\n", fields.join(", ")));
+        self.push_rows(&mut output, &rows, "lines");
+        Ok(output)
+    }
+
+    /// Samples plain lines, for data files that cannot be parsed.
+    fn sample_lines(&self, text: &str) -> String {
+        let rows: Vec<&str> = text.lines().collect();
+        let mut output = self.title("Text", rows.len(), "lines");
+        self.push_rows(&mut output, &rows, "lines");
+        output
+    }
+
+    /// The head and the tail of the rows, without overlap, or `None` for the rows in
+    /// between when some are elided.
+    fn kept<'a>(&self, rows: &[&'a str]) -> Vec<Option<&'a str>> {
+        if self.sample.head + self.sample.tail >= rows.len() {
+            return rows.iter().map(|row| Some(*row)).collect();
+        }
+        let tail_start = rows.len() - self.sample.tail;
+        rows[..self.sample.head]
+            .iter()
+            .map(|row| Some(*row))
+            .chain(std::iter::once(None))
+            .chain(rows[tail_start..].iter().map(|row| Some(*row)))
+            .collect()
+    }
+
+    fn title(&self, label: &str, count: usize, unit: &str) -> String {
+        let DataSample { head, tail } = self.sample;
+        if head + tail >= count {
+            format!("{} Data ({} {}):\n", label, count, unit)
+        } else {
+            format!(
+                "{} Data (first {} and last {} of {} {}):\n",
+                label, head, tail, count, unit
+            )
+        }
+    }
+
+    fn push_rows(&self, output: &mut String, rows: &[&str], unit: &str) {
+        let elided = rows
+            .len()
+            .saturating_sub(self.sample.head + self.sample.tail);
+        for row in self.kept(rows) {
+            match row {
+                Some(row) => output.push_str(row),
+                None => output.push_str(&format!("... [{} {} omitted]", elided, unit)),
+            }
+            output.push('\n');
+        }
+    }
+}
+
+impl FileProcessor for DataSampleProcessor {
+    fn process(&self, content: &[u8], path: &Path) -> Result<String> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        let sampled = match extension.as_str() {
+            "csv" => self.sample_delimited(content, b',', "CSV"),
+            "tsv" => self.sample_delimited(content, b'\t', "TSV"),
+            _ => self.sample_json_lines(content),
+        };
+        match sampled {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                log::warn!(
+                    "Data sampling failed for {:?}: {}. Sampling raw lines instead.",
+                    path,
+                    e
+                );
+                let text = DefaultTextProcessor.process(content, path)?;
+                Ok(self.sample_lines(&text))
+            }
+        }
+    }
+}
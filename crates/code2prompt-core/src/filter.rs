@@ -2,6 +2,7 @@
 //!
 //! This module provides reusable, stateless functions for pattern matching and file filtering.
 
+use crate::line_range::split_range_pattern;
 use bracoxide::explode;
 use colored::*;
 use globset::{Glob, GlobSet, GlobSetBuilder};
@@ -69,13 +70,15 @@ pub fn build_globset(patterns: &[String]) -> GlobSet {
 
     let mut expanded_patterns = Vec::new();
     for pattern in patterns {
+        // The line range of a pattern such as `src/lib.rs:100-250` does not filter files
+        let pattern = split_range_pattern(pattern).0;
         if pattern.contains('{') {
             match explode(pattern) {
                 Ok(exp) => expanded_patterns.extend(exp),
                 Err(e) => warn!("⚠️ Invalid brace pattern '{}': {:?}", pattern, e),
             }
         } else {
-            expanded_patterns.push(pattern.clone());
+            expanded_patterns.push(pattern.to_string());
         }
     }
 
@@ -0,0 +1,178 @@
+//! Handling of test fixtures, snapshots and golden files.
+//!
+//! These files are often large and their content rarely matters to a prompt, but their
+//! existence does: they tell which behavior is pinned by tests. Depending on the
+//! [`FixturePolicy`], they are included as any other file, stubbed with their dimensions
+//! (`snapshot, 1.2k lines, updated 2024-03-01`) or excluded.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+
+use crate::util::iso_date;
+
+/// Directories holding snapshots
+const SNAPSHOT_DIRECTORIES: [&str; 2] = ["__snapshots__", "snapshots"];
+
+/// Extensions of snapshot files: Jest and insta `.snap`, syrupy `.ambr`
+const SNAPSHOT_EXTENSIONS: [&str; 2] = ["snap", "ambr"];
+
+/// Directories holding golden files
+const GOLDEN_DIRECTORIES: [&str; 2] = ["golden", "goldens"];
+
+/// Directories holding test fixtures
+const FIXTURE_DIRECTORIES: [&str; 6] = [
+    "fixtures",
+    "__fixtures__",
+    "testdata",
+    "test-data",
+    "test_data",
+    "test-fixtures",
+];
+
+/// How test fixtures, snapshots and golden files are handled.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixturePolicy {
+    /// Included as any other file.
+    #[default]
+    Include,
+    /// Replaced by a line giving their kind, length and last update.
+    Stub,
+    /// Left out of the prompt, and out of the tree unless the full tree is shown.
+    Exclude,
+}
+
+impl std::fmt::Display for FixturePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixturePolicy::Include => write!(f, "include"),
+            FixturePolicy::Stub => write!(f, "stub"),
+            FixturePolicy::Exclude => write!(f, "exclude"),
+        }
+    }
+}
+
+impl FixturePolicy {
+    /// Whether the policy leaves a file, or a fixture directory, out of the codebase.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_path` - The path of the entry, relative to the root
+    pub fn excludes(&self, relative_path: &Path) -> bool {
+        *self == FixturePolicy::Exclude
+            && (fixture_kind(relative_path).is_some()
+                // `tests/fixtures` itself, since what it holds is fixtures
+                || fixture_kind(&relative_path.join("_")).is_some())
+    }
+
+    /// The kind of a file the policy replaces by a stub.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_path` - The path of the file, relative to the root
+    ///
+    /// # Returns
+    ///
+    /// * `Option<FixtureKind>` - `None` if the file is kept as is
+    pub fn stubs(&self, relative_path: &Path) -> Option<FixtureKind> {
+        if *self == FixturePolicy::Stub {
+            fixture_kind(relative_path)
+        } else {
+            None
+        }
+    }
+}
+
+/// The kind of a test data file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixtureKind {
+    Snapshot,
+    Golden,
+    Fixture,
+}
+
+impl std::fmt::Display for FixtureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixtureKind::Snapshot => write!(f, "snapshot"),
+            FixtureKind::Golden => write!(f, "golden file"),
+            FixtureKind::Fixture => write!(f, "fixture"),
+        }
+    }
+}
+
+/// Detects whether a file is a snapshot, a golden file or a test fixture, from its
+/// directory or its extension.
+///
+/// # Arguments
+///
+/// * `relative_path` - The path of the file, relative to the root
+///
+/// # Returns
+///
+/// * `Option<FixtureKind>` - `None` for any other file
+pub fn fixture_kind(relative_path: &Path) -> Option<FixtureKind> {
+    let directories: Vec<&str> = relative_path
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+    let file_name = relative_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    // `.snap.new` files are pending insta snapshots
+    let extension = file_name
+        .trim_end_matches(".new")
+        .rsplit_once('.')
+        .map(|(_, extension)| extension)
+        .unwrap_or("");
+    let in_directory = |names: &[&str]| directories.iter().any(|dir| names.contains(dir));
+
+    if in_directory(&SNAPSHOT_DIRECTORIES) || SNAPSHOT_EXTENSIONS.contains(&extension) {
+        Some(FixtureKind::Snapshot)
+    } else if in_directory(&GOLDEN_DIRECTORIES) || extension == "golden" {
+        Some(FixtureKind::Golden)
+    } else if in_directory(&FIXTURE_DIRECTORIES) {
+        Some(FixtureKind::Fixture)
+    } else {
+        None
+    }
+}
+
+/// The text standing in for a stubbed file, like `snapshot, 1.2k lines, updated 2024-03-01`.
+///
+/// # Arguments
+///
+/// * `kind` - The kind of the file
+/// * `content` - The content of the file
+/// * `updated` - The last modification time, in seconds since the Unix epoch, if known
+pub fn fixture_stub(kind: FixtureKind, content: &str, updated: Option<u64>) -> String {
+    let lines = content.lines().count();
+    let mut stub = format!(
+        "{}, {} {}",
+        kind,
+        compact_count(lines),
+        if lines == 1 { "line" } else { "lines" }
+    );
+    if let Some(seconds) = updated {
+        stub.push_str(&format!(", updated {}", iso_date(seconds)));
+    }
+    stub
+}
+
+/// A count as `980`, `1.2k` or `3.4M`.
+pub(crate) fn compact_count(count: usize) -> String {
+    let (value, suffix) = match count {
+        0..1_000 => return count.to_string(),
+        1_000..1_000_000 => (count as f64 / 1_000.0, "k"),
+        _ => (count as f64 / 1_000_000.0, "M"),
+    };
+    let text = format!("{:.1}", value);
+    format!("{}{}", text.trim_end_matches(".0"), suffix)
+}
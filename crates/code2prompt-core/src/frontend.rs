@@ -0,0 +1,612 @@
+//! Frontend asset pipeline awareness.
+//!
+//! Build tool configurations (Vite, webpack, Next.js, Tailwind...) are long files whose
+//! gist fits in a few lines: the packages they load and the settings they change. They
+//! are summarized into the `build_config` template variable instead of being included
+//! in full. Component files are also grouped by route or feature when the codebase
+//! follows a known convention (Next.js app or pages router, `routes` directories,
+//! feature folders), so that the tree shows which files make up each screen.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Build tools recognized by the stem of their configuration file
+const BUILD_TOOLS: [(&str, &str); 9] = [
+    ("vite.config", "Vite"),
+    ("webpack.config", "webpack"),
+    ("next.config", "Next.js"),
+    ("tailwind.config", "Tailwind CSS"),
+    ("postcss.config", "PostCSS"),
+    ("svelte.config", "SvelteKit"),
+    ("nuxt.config", "Nuxt"),
+    ("astro.config", "Astro"),
+    ("rollup.config", "Rollup"),
+];
+
+/// Extensions of the configuration files
+const CONFIG_EXTENSIONS: [&str; 6] = ["js", "cjs", "mjs", "ts", "cts", "mts"];
+
+/// Settings listed per configuration file, at most
+const MAX_SETTINGS: usize = 30;
+
+/// Longest value kept in a setting
+const MAX_VALUE_LENGTH: usize = 80;
+
+/// Extensions of the files making up the screens of a frontend
+const COMPONENT_EXTENSIONS: [&str; 10] = [
+    "js", "jsx", "ts", "tsx", "mdx", "vue", "svelte", "astro", "css", "scss",
+];
+
+/// The summary of a build tool configuration file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildConfigFile {
+    pub path: String,
+    pub tool: &'static str,
+    /// Packages imported by the configuration, plugins and presets mostly
+    pub packages: Vec<String>,
+    /// Settings as `key.path = value`, in the order of the file
+    pub settings: Vec<String>,
+}
+
+/// The build tool configurations of a codebase
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BuildConfig {
+    pub files: Vec<BuildConfigFile>,
+    /// Markdown summary of all the files
+    pub summary: String,
+}
+
+/// The tool configured by a file, if it is a build tool configuration.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file
+pub fn build_tool(path: &str) -> Option<&'static str> {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let (stem, extension) = file_name.rsplit_once('.')?;
+    if !CONFIG_EXTENSIONS.contains(&extension) {
+        return None;
+    }
+    BUILD_TOOLS
+        .iter()
+        .find(|(config_stem, _)| *config_stem == stem)
+        .map(|(_, tool)| *tool)
+}
+
+/// Summarizes build tool configuration files.
+///
+/// # Arguments
+///
+/// * `files` - The path and the content of each configuration file
+///
+/// # Returns
+///
+/// * `Option<BuildConfig>` - `None` if none of the files is a build tool configuration
+pub fn summarize_build_configs(files: &[(String, String)]) -> Option<BuildConfig> {
+    let files: Vec<BuildConfigFile> = files
+        .iter()
+        .filter_map(|(path, content)| {
+            let tool = build_tool(path)?;
+            let tokens = tokenize(content);
+            Some(BuildConfigFile {
+                path: path.clone(),
+                tool,
+                packages: imported_packages(&tokens),
+                settings: exported_settings(&tokens),
+            })
+        })
+        .collect();
+    if files.is_empty() {
+        return None;
+    }
+
+    let summary = files
+        .iter()
+        .map(|file| {
+            let mut lines = vec![format!("- `{}` ({})", file.path, file.tool)];
+            if !file.packages.is_empty() {
+                lines.push(format!("  - Uses: {}", file.packages.join(", ")));
+            }
+            lines.extend(
+                file.settings
+                    .iter()
+                    .map(|setting| format!("  - {}", setting)),
+            );
+            lines.join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(BuildConfig { files, summary })
+}
+
+/// A group of component files making up a route or a feature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RouteGroup {
+    /// The route, like `/blog/[slug]`, or the name of the feature
+    pub name: String,
+    pub files: Vec<String>,
+}
+
+/// The component files of a codebase grouped by route or feature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RouteLayout {
+    /// The convention the groups follow
+    pub convention: &'static str,
+    pub groups: Vec<RouteGroup>,
+}
+
+impl RouteLayout {
+    /// The groups as an indented list, to be appended to the source tree.
+    pub fn to_tree(&self) -> String {
+        let mut text = format!("{}:\n", self.convention);
+        for group in &self.groups {
+            text.push_str(&format!("  {}\n", group.name));
+            for file in &group.files {
+                text.push_str(&format!("    {}\n", file));
+            }
+        }
+        text
+    }
+}
+
+/// Finds the route or feature of a file under a convention
+type RouteOf = fn(&str) -> Option<String>;
+
+/// Groups component files by route or feature, following the first convention found
+/// among the paths: the Next.js app router, the pages router, a `routes` directory
+/// (SvelteKit, Remix, TanStack Router) or feature folders.
+///
+/// # Arguments
+///
+/// * `paths` - The paths of the files, relative to the root
+///
+/// # Returns
+///
+/// * `Option<RouteLayout>` - `None` if no convention is detected
+pub fn group_routes(paths: &[String]) -> Option<RouteLayout> {
+    let components: Vec<&str> = paths
+        .iter()
+        .map(String::as_str)
+        .filter(|path| {
+            path.rsplit_once('.')
+                .is_some_and(|(_, extension)| COMPONENT_EXTENSIONS.contains(&extension))
+        })
+        .collect();
+
+    // The app router needs pages, Remix keeps its routes in `app/routes` too
+    let has_app_pages = components.iter().any(|path| {
+        below(path, "app").is_some_and(|rest| {
+            let file_name = rest.rsplit('/').next().unwrap_or(rest);
+            file_name.split('.').next() == Some("page")
+        })
+    });
+    let conventions: [(&'static str, RouteOf); 4] = [
+        ("Routes (Next.js app router)", app_router_route),
+        ("Routes (pages router)", pages_router_route),
+        ("Routes", routes_directory_route),
+        ("Features", feature_name),
+    ];
+    conventions
+        .iter()
+        .skip(if has_app_pages { 0 } else { 1 })
+        .find_map(|(convention, route_of)| {
+            let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+            for path in &components {
+                if let Some(route) = route_of(path) {
+                    groups.entry(route).or_default().push(path.to_string());
+                }
+            }
+            (!groups.is_empty()).then(|| RouteLayout {
+                convention,
+                groups: groups
+                    .into_iter()
+                    .map(|(name, files)| RouteGroup { name, files })
+                    .collect(),
+            })
+        })
+}
+
+/// The directory of a path below a root directory, at the top level or under `src/`.
+fn below<'a>(path: &'a str, root: &str) -> Option<&'a str> {
+    path.strip_prefix(root)
+        .or_else(|| path.strip_prefix("src/")?.strip_prefix(root))
+        .and_then(|rest| rest.strip_prefix('/'))
+}
+
+/// Next.js app router: the route of a file is its directory, without the `(group)`
+/// and `@slot` segments. Private `_folders` belong to the route above them.
+fn app_router_route(path: &str) -> Option<String> {
+    let rest = below(path, "app")?;
+    let segments: Vec<&str> = rest.split('/').collect();
+    let directories = &segments[..segments.len() - 1];
+    let route: Vec<&str> = directories
+        .iter()
+        .take_while(|segment| !segment.starts_with('_') && **segment != "components")
+        .filter(|segment| !(segment.starts_with('(') && segment.ends_with(')')))
+        .filter(|segment| !segment.starts_with('@'))
+        .copied()
+        .collect();
+    Some(format!("/{}", route.join("/")))
+}
+
+/// Pages router: the route of a page is its path, `index` being the directory.
+fn pages_router_route(path: &str) -> Option<String> {
+    let rest = below(path, "pages")?;
+    let without_extension = rest.rsplit_once('.').map_or(rest, |(stem, _)| stem);
+    let route = without_extension
+        .strip_suffix("/index")
+        .or_else(|| (without_extension == "index").then_some(""))
+        .unwrap_or(without_extension);
+    // `_app` and `_document` wrap every page
+    if route.starts_with('_') {
+        return Some("/ (app shell)".to_string());
+    }
+    Some(format!("/{}", route))
+}
+
+/// `routes` directories: the route of a file is its directory, with the dots of Remix
+/// flat routes (`blog.$slug.tsx`) read as separators.
+fn routes_directory_route(path: &str) -> Option<String> {
+    let rest = below(path, "routes").or_else(|| below(path, "app/routes"))?;
+    let (directory, file_name) = rest.rsplit_once('/').unwrap_or(("", rest));
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    let is_flat_route = directory.is_empty()
+        && !stem.starts_with('+')
+        && !matches!(stem, "index" | "_index" | "root" | "__root");
+    let route = if is_flat_route {
+        let without_extension = file_name.rsplit_once('.').map_or(file_name, |(s, _)| s);
+        without_extension.replace('.', "/")
+    } else {
+        directory.to_string()
+    };
+    let route: Vec<&str> = route
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .filter(|segment| !(segment.starts_with('(') && segment.ends_with(')')))
+        .collect();
+    Some(format!("/{}", route.join("/")))
+}
+
+/// Feature folders: `features/<name>/...` or `modules/<name>/...`.
+fn feature_name(path: &str) -> Option<String> {
+    let rest = below(path, "features").or_else(|| below(path, "modules"))?;
+    let (feature, _) = rest.split_once('/')?;
+    Some(feature.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Str(String),
+    /// Numbers, kept as written
+    Number(String),
+    Punct(char),
+}
+
+/// Splits JavaScript or TypeScript into tokens, without comments. Template literals
+/// are kept as strings, with their substitutions.
+fn tokenize(content: &str) -> Vec<Token> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' || c == '\'' || c == '`' {
+            i += 1;
+            let mut text = String::new();
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    i += 1;
+                }
+                text.push(chars[i]);
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token::Str(text));
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+            {
+                i += 1;
+            }
+            tokens.push(Token::Identifier(chars[start..i].iter().collect()));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(Token::Number(chars[start..i].iter().collect()));
+        } else {
+            tokens.push(Token::Punct(c));
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// Packages imported with `import ... from` or `require`, relative imports aside.
+fn imported_packages(tokens: &[Token]) -> Vec<String> {
+    let mut packages: Vec<String> = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let specifier = match token {
+            Token::Identifier(keyword) if keyword == "from" => tokens.get(index + 1),
+            Token::Identifier(keyword) if keyword == "import" => match tokens.get(index + 1) {
+                // `import 'side-effect'` and `import('dynamic')`
+                Some(Token::Str(_)) => tokens.get(index + 1),
+                Some(Token::Punct('(')) => tokens.get(index + 2),
+                _ => None,
+            },
+            Token::Identifier(keyword) if keyword == "require" => match tokens.get(index + 1) {
+                Some(Token::Punct('(')) => tokens.get(index + 2),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(Token::Str(specifier)) = specifier
+            && !specifier.starts_with('.')
+            && !specifier.starts_with('/')
+            && !packages.contains(specifier)
+        {
+            packages.push(specifier.clone());
+        }
+    }
+    packages
+}
+
+/// The settings of the exported configuration object, as `key.path = value`.
+fn exported_settings(tokens: &[Token]) -> Vec<String> {
+    let Some(start) = exported_object(tokens) else {
+        return Vec::new();
+    };
+    let mut settings = Vec::new();
+    let mut parser = ObjectParser {
+        tokens,
+        position: start,
+    };
+    parser.object("", &mut settings);
+    settings.truncate(MAX_SETTINGS);
+    settings
+}
+
+/// The position of the opening brace of the exported configuration object: the object
+/// passed to `export default`, `module.exports =` or a `defineConfig`-like call, or the
+/// object assigned to the exported variable.
+fn exported_object(tokens: &[Token]) -> Option<usize> {
+    let is = |index: usize, expected: &Token| tokens.get(index) == Some(expected);
+    let identifier = |text: &str| Token::Identifier(text.to_string());
+
+    for (index, token) in tokens.iter().enumerate() {
+        let after = match token {
+            Token::Identifier(keyword) if keyword == "default" && index > 0 => {
+                is(index - 1, &identifier("export")).then_some(index + 1)
+            }
+            Token::Identifier(keyword) if keyword == "exports" && index > 1 => {
+                (is(index - 2, &identifier("module"))
+                    && is(index - 1, &Token::Punct('.'))
+                    && is(index + 1, &Token::Punct('=')))
+                .then_some(index + 2)
+            }
+            _ => None,
+        };
+        let Some(mut position) = after else {
+            continue;
+        };
+
+        // `defineConfig({ ... })`, `withPlugins(plugin, { ... })`: the first object argument
+        if let (Some(Token::Identifier(_)), Some(Token::Punct('('))) =
+            (tokens.get(position), tokens.get(position + 1))
+        {
+            position = tokens[position..]
+                .iter()
+                .position(|token| *token == Token::Punct('{'))
+                .map(|offset| position + offset)?;
+            return Some(position);
+        }
+        match tokens.get(position) {
+            Some(Token::Punct('{')) => return Some(position),
+            // `export default config`: the object assigned to `config`
+            Some(Token::Identifier(name)) => {
+                let declaration = tokens.iter().enumerate().position(|(at, token)| {
+                    *token == Token::Identifier(name.clone())
+                        && at > 0
+                        && matches!(&tokens[at - 1], Token::Identifier(keyword) if ["const", "let", "var"].contains(&keyword.as_str()))
+                })?;
+                return tokens[declaration..]
+                    .iter()
+                    .position(|token| *token == Token::Punct('{'))
+                    .map(|offset| declaration + offset);
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+struct ObjectParser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl ObjectParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn eat(&mut self, punct: char) -> bool {
+        if self.peek() == Some(&Token::Punct(punct)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// An object literal, at its opening brace. Its scalar and list properties are
+    /// recorded under `prefix`.
+    fn object(&mut self, prefix: &str, settings: &mut Vec<String>) {
+        if !self.eat('{') {
+            return;
+        }
+        while let Some(token) = self.peek().cloned() {
+            if self.eat('}') {
+                return;
+            }
+            let key = match token {
+                // `async redirects() { ... }`
+                Token::Identifier(modifier)
+                    if ["async", "get", "set"].contains(&modifier.as_str())
+                        && matches!(
+                            self.tokens.get(self.position + 1),
+                            Some(Token::Identifier(_))
+                        ) =>
+                {
+                    self.position += 1;
+                    continue;
+                }
+                Token::Identifier(key) | Token::Str(key) | Token::Number(key) => {
+                    self.position += 1;
+                    key
+                }
+                _ => {
+                    // Spreads and computed keys are skipped
+                    self.skip_value();
+                    self.eat(',');
+                    continue;
+                }
+            };
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            if self.eat(':') {
+                if self.peek() == Some(&Token::Punct('{')) {
+                    self.object(&path, settings);
+                } else if let Some(value) = self.value() {
+                    settings.push(format!("{} = {}", path, shorten(&value)));
+                }
+            } else if self.peek() == Some(&Token::Punct('(')) {
+                // A method: its body is code, not settings
+                settings.push(format!("{} = (function)", path));
+                self.skip_value();
+            }
+            if !self.eat(',') && self.peek() != Some(&Token::Punct('}')) {
+                self.skip_value();
+                self.eat(',');
+            }
+        }
+    }
+
+    /// A property value as short text, `None` for values that are code.
+    fn value(&mut self) -> Option<String> {
+        let start = self.position;
+        let text = match self.peek()?.clone() {
+            Token::Str(text) => {
+                self.position += 1;
+                format!("{:?}", text)
+            }
+            Token::Number(number) => {
+                self.position += 1;
+                number
+            }
+            Token::Punct('[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                while self.peek().is_some() && !self.eat(']') {
+                    items.push(self.value().unwrap_or_else(|| "…".to_string()));
+                    if !self.eat(',') && self.peek() != Some(&Token::Punct(']')) {
+                        self.skip_value();
+                        self.eat(',');
+                    }
+                }
+                format!("[{}]", items.join(", "))
+            }
+            Token::Punct('{') => {
+                self.skip_value();
+                "{…}".to_string()
+            }
+            Token::Identifier(name) => {
+                self.position += 1;
+                let mut name = name;
+                while self.eat('.') {
+                    if let Some(Token::Identifier(part)) = self.peek().cloned() {
+                        self.position += 1;
+                        name = format!("{}.{}", name, part);
+                    }
+                }
+                let is_arrow = self.peek() == Some(&Token::Punct('='))
+                    && self.tokens.get(self.position + 1) == Some(&Token::Punct('>'));
+                if name == "function" || name == "async" || is_arrow {
+                    self.position = start;
+                    self.skip_value();
+                    return Some("(function)".to_string());
+                }
+                if self.peek() == Some(&Token::Punct('(')) {
+                    // Arrow functions are code, calls are plugins and helpers
+                    self.skip_value();
+                    format!("{}()", name)
+                } else {
+                    name
+                }
+            }
+            Token::Punct('(') => {
+                // `(config) => ...`, rarely a parenthesized expression
+                self.skip_value();
+                return Some("(function)".to_string());
+            }
+            Token::Punct(_) => {
+                self.skip_value();
+                return None;
+            }
+        };
+        // The rest of an expression, like `process.env.PORT || 3000`
+        if !matches!(self.peek(), None | Some(Token::Punct(',' | '}' | ']'))) {
+            self.skip_value();
+            return Some(format!("{} …", text));
+        }
+        Some(text)
+    }
+
+    /// Skips tokens up to the next `,`, `}` or `]` of the current nesting level.
+    fn skip_value(&mut self) {
+        let mut depth = 0usize;
+        let start = self.position;
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Punct(',' | '}' | ']' | ')') if depth == 0 => {
+                    // Always move forward, even on a stray closing bracket
+                    if self.position == start && !matches!(token, Token::Punct(',')) {
+                        self.position += 1;
+                    }
+                    return;
+                }
+                Token::Punct('(' | '[' | '{') => depth += 1,
+                Token::Punct(')' | ']' | '}') => depth -= 1,
+                _ => {}
+            }
+            self.position += 1;
+        }
+    }
+}
+
+fn shorten(value: &str) -> String {
+    if value.chars().count() <= MAX_VALUE_LENGTH {
+        value.to_string()
+    } else {
+        let kept: String = value.chars().take(MAX_VALUE_LENGTH - 1).collect();
+        format!("{}…", kept)
+    }
+}
@@ -1,9 +1,69 @@
 //! This module handles git operations.
 
+use crate::configuration::Code2PromptConfig;
+use crate::filter::FilterEngine;
+use crate::tokenizer::{TokenCountMode, TokenizerType, count_tokens_with_mode};
+use crate::util::iso_date;
+use crate::util::path_key;
 use anyhow::{Context, Result};
-use git2::{DiffOptions, Repository};
+use git2::{Diff, DiffOptions, Repository};
 use log::info;
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// How diffs are rendered.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffStyle {
+    /// Standard unified diff.
+    #[default]
+    Unified,
+    /// Word-level diff, marking changes inline as `[-removed-]{+added+}`.
+    Word,
+    /// Unified diff whose hunks span the whole enclosing function (`git diff -W`).
+    Function,
+}
+
+impl std::fmt::Display for DiffStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffStyle::Unified => write!(f, "unified"),
+            DiffStyle::Word => write!(f, "word"),
+            DiffStyle::Function => write!(f, "function"),
+        }
+    }
+}
+
+/// Options controlling which changes are included in a generated diff and how it is rendered.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GitDiffOptions {
+    /// Glob patterns a changed path must match to be part of the diff. Empty includes all paths.
+    pub include_patterns: Vec<String>,
+    /// Glob patterns excluding changed paths from the diff.
+    pub exclude_patterns: Vec<String>,
+    /// Number of context lines around changes. Defaults to git's 3.
+    pub context_lines: Option<u32>,
+    /// How the diff is rendered.
+    pub style: DiffStyle,
+}
+
+/// Context requested from libgit2 for the function style, large enough to cover any file.
+/// Hunks are then cut down to the enclosing functions.
+const FULL_CONTEXT_LINES: u32 = 1 << 24;
+
+impl GitDiffOptions {
+    fn diff_options(&self) -> DiffOptions {
+        let mut diff_options = DiffOptions::new();
+        diff_options.ignore_whitespace(true);
+        if self.style == DiffStyle::Function {
+            diff_options.context_lines(FULL_CONTEXT_LINES);
+        } else if let Some(context_lines) = self.context_lines {
+            diff_options.context_lines(context_lines);
+        }
+        diff_options
+    }
+}
 
 /// Generates a git diff for the repository at the provided path.
 ///
@@ -24,30 +84,29 @@ use std::path::Path;
 ///   or a message indicating that there is no diff between the compared git objects.
 ///   In case of error, returns an appropriate error.
 pub fn get_git_diff(repo_path: &Path) -> Result<String> {
+    get_git_diff_with_options(repo_path, &GitDiffOptions::default())
+}
+
+/// Generates a git diff of the staged changes, restricted to the paths selected by the options.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository.
+/// * `options` - The diff options.
+///
+/// # Returns
+///
+/// * `Result<String>` - The diff, as returned by [`get_git_diff`].
+pub fn get_git_diff_with_options(repo_path: &Path, options: &GitDiffOptions) -> Result<String> {
     info!("Opening repository at path: {:?}", repo_path);
     let repo = Repository::open(repo_path).context("Failed to open repository")?;
+    let filter = FilterEngine::new(&options.include_patterns, &options.exclude_patterns);
 
     let head = repo.head().context("Failed to get repository head")?;
-    let head_tree = head.peel_to_tree().context("Failed to peel to tree")?;
+    head.peel_to_tree().context("Failed to peel to tree")?;
 
     // Generate diff for staged changes (HEAD vs. index)
-    let staged_diff = repo
-        .diff_tree_to_index(
-            Some(&head_tree),
-            None,
-            Some(DiffOptions::new().ignore_whitespace(true)),
-        )
-        .context("Failed to generate diff for staged changes")?;
-
-    let mut staged_diff_text = Vec::new();
-    staged_diff
-        .print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
-            staged_diff_text.extend_from_slice(line.content());
-            true
-        })
-        .context("Failed to print staged diff")?;
-
-    let staged_diff_output = String::from_utf8_lossy(&staged_diff_text).into_owned();
+    let staged_diff_output = staged_diff(&repo, options, &filter)?;
 
     // If there is no staged diff, return a message indicating so.
     if staged_diff_output.trim().is_empty() {
@@ -59,15 +118,8 @@ pub fn get_git_diff(repo_path: &Path) -> Result<String> {
         .diff_index_to_workdir(None, Some(DiffOptions::new().ignore_whitespace(true)))
         .context("Failed to generate diff for unstaged changes")?;
 
-    let mut unstaged_diff_text = Vec::new();
-    unstaged_diff
-        .print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
-            unstaged_diff_text.extend_from_slice(line.content());
-            true
-        })
-        .context("Failed to print unstaged diff")?;
-
-    let unstaged_diff_output = String::from_utf8_lossy(&unstaged_diff_text).into_owned();
+    let unstaged_diff_output =
+        diff_to_string(&unstaged_diff, &filter).context("Failed to print unstaged diff")?;
 
     let mut output = staged_diff_output;
     if !unstaged_diff_output.trim().is_empty() {
@@ -78,6 +130,108 @@ pub fn get_git_diff(repo_path: &Path) -> Result<String> {
     Ok(output)
 }
 
+/// Generates exactly the staged changes (HEAD vs. index), as `git diff --cached` does.
+///
+/// Unlike [`get_git_diff`], the result is empty when nothing is staged and the repository
+/// may have no commit yet.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository.
+/// * `options` - The diff options.
+///
+/// # Returns
+///
+/// * `Result<String>` - The staged diff.
+pub fn get_git_staged_diff(repo_path: &Path, options: &GitDiffOptions) -> Result<String> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let repo = Repository::open(repo_path).context("Failed to open repository")?;
+    let filter = FilterEngine::new(&options.include_patterns, &options.exclude_patterns);
+    staged_diff(&repo, options, &filter)
+}
+
+/// Renders the diff between HEAD (or the empty tree before the first commit) and the index.
+fn staged_diff(
+    repo: &Repository,
+    options: &GitDiffOptions,
+    filter: &FilterEngine,
+) -> Result<String> {
+    let staged_diff = staged_changes(repo, options)?;
+    render_diff(&staged_diff, options, filter).context("Failed to print staged diff")
+}
+
+/// Diffs HEAD (or the empty tree before the first commit) against the index.
+fn staged_changes<'r>(repo: &'r Repository, options: &GitDiffOptions) -> Result<Diff<'r>> {
+    let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+    repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut options.diff_options()))
+        .context("Failed to generate diff for staged changes")
+}
+
+/// Diffs the trees of two branches, failing if either branch does not exist.
+fn branch_changes<'r>(
+    repo: &'r Repository,
+    branch1: &str,
+    branch2: &str,
+    options: &GitDiffOptions,
+) -> Result<Diff<'r>> {
+    for branch in [branch1, branch2].iter() {
+        if !branch_exists(repo, branch) {
+            return Err(anyhow::anyhow!("Branch {} doesn't exist!", branch));
+        }
+    }
+
+    let branch1_tree = repo.revparse_single(branch1)?.peel_to_commit()?.tree()?;
+    let branch2_tree = repo.revparse_single(branch2)?.peel_to_commit()?.tree()?;
+
+    repo.diff_tree_to_tree(
+        Some(&branch1_tree),
+        Some(&branch2_tree),
+        Some(&mut options.diff_options()),
+    )
+    .context("Failed to generate diff between branches")
+}
+
+/// Retrieves the full messages of the latest non-merge commits reachable from HEAD.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+/// * `count` - The maximum number of commit messages to return
+///
+/// # Returns
+///
+/// * `Result<Vec<String>>` - The commit messages, newest first. Empty if there is no commit yet.
+pub fn get_recent_commit_messages(repo_path: &Path, count: usize) -> Result<Vec<String>> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let repo = Repository::open(repo_path).context("Failed to open repository")?;
+
+    let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut revwalk = repo.revwalk().context("Failed to create revwalk")?;
+    revwalk
+        .push(head.id())
+        .context("Failed to push HEAD to revwalk")?;
+
+    let mut messages = Vec::new();
+    for oid in revwalk {
+        if messages.len() >= count {
+            break;
+        }
+        let commit = repo
+            .find_commit(oid.context("Failed to get OID from revwalk")?)
+            .context("Failed to find commit")?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        if let Some(message) = commit.message() {
+            messages.push(message.trim().to_string());
+        }
+    }
+    Ok(messages)
+}
+
 /// Generates a git diff between two branches for the repository at the provided path
 ///
 /// # Arguments
@@ -93,39 +247,496 @@ pub fn get_git_diff_between_branches(
     repo_path: &Path,
     branch1: &str,
     branch2: &str,
+) -> Result<String> {
+    get_git_diff_between_branches_with_options(
+        repo_path,
+        branch1,
+        branch2,
+        &GitDiffOptions::default(),
+    )
+}
+
+/// Generates a git diff between two branches, restricted to the paths selected by the options.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+/// * `branch1` - The name of the first branch
+/// * `branch2` - The name of the second branch
+/// * `options` - The diff options
+///
+/// # Returns
+///
+/// * `Result<String>` - The generated git diff as a string or an error
+pub fn get_git_diff_between_branches_with_options(
+    repo_path: &Path,
+    branch1: &str,
+    branch2: &str,
+    options: &GitDiffOptions,
 ) -> Result<String> {
     info!("Opening repository at path: {:?}", repo_path);
     let repo = Repository::open(repo_path).context("Failed to open repository")?;
+    let filter = FilterEngine::new(&options.include_patterns, &options.exclude_patterns);
 
-    for branch in [branch1, branch2].iter() {
-        if !branch_exists(&repo, branch) {
-            return Err(anyhow::anyhow!("Branch {} doesn't exist!", branch));
+    let diff = branch_changes(&repo, branch1, branch2, options)?;
+    let diff_text = render_diff(&diff, options, &filter).context("Failed to print diff")?;
+
+    info!("Generated git diff between branches successfully");
+    Ok(diff_text)
+}
+
+/// Generates the diff of a stash entry against the commit it was created on.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+/// * `stash` - The stash to show: `stash@{N}`, an index, or text contained in the stash message.
+///   `None` selects the latest stash.
+/// * `options` - The diff options
+///
+/// # Returns
+///
+/// * `Result<String>` - The stash description followed by its diff, or an error if no stash matches
+pub fn get_git_stash_diff(
+    repo_path: &Path,
+    stash: Option<&str>,
+    options: &GitDiffOptions,
+) -> Result<String> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let mut repo = Repository::open(repo_path).context("Failed to open repository")?;
+    let filter = FilterEngine::new(&options.include_patterns, &options.exclude_patterns);
+
+    let wanted_index = stash.and_then(|stash| {
+        let stash = stash.trim();
+        stash
+            .strip_prefix("stash@{")
+            .and_then(|rest| rest.strip_suffix('}'))
+            .unwrap_or(stash)
+            .parse::<usize>()
+            .ok()
+    });
+
+    let mut found = None;
+    repo.stash_foreach(|index, message, oid| {
+        let matches = match (stash, wanted_index) {
+            (None, _) => index == 0,
+            (_, Some(wanted)) => index == wanted,
+            (Some(text), None) => message.contains(text),
+        };
+        if matches {
+            found = Some((index, message.to_string(), *oid));
         }
-    }
+        !matches
+    })
+    .context("Failed to list stashes")?;
 
-    let branch1_commit = repo.revparse_single(branch1)?.peel_to_commit()?;
-    let branch2_commit = repo.revparse_single(branch2)?.peel_to_commit()?;
+    let Some((index, message, oid)) = found else {
+        return Err(anyhow::anyhow!(
+            "No stash matching {} found",
+            stash.unwrap_or("stash@{0}")
+        ));
+    };
 
-    let branch1_tree = branch1_commit.tree()?;
-    let branch2_tree = branch2_commit.tree()?;
+    let stash_commit = repo.find_commit(oid)?;
+    let base_commit = stash_commit.parent(0)?;
 
     let diff = repo
         .diff_tree_to_tree(
-            Some(&branch1_tree),
-            Some(&branch2_tree),
-            Some(DiffOptions::new().ignore_whitespace(true)),
+            Some(&base_commit.tree()?),
+            Some(&stash_commit.tree()?),
+            Some(&mut options.diff_options()),
         )
-        .context("Failed to generate diff between branches")?;
+        .context("Failed to generate stash diff")?;
+    let diff_text = render_diff(&diff, options, &filter).context("Failed to print stash diff")?;
+
+    info!("Generated stash diff successfully");
+    Ok(format!("stash@{{{}}}: {}\n{}", index, message, diff_text))
+}
+
+/// Lists the untracked files of the repository, ignoring the files excluded by `.gitignore`.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+///
+/// # Returns
+///
+/// * `Result<Vec<String>>` - The untracked file paths, relative to the repository root
+pub fn get_untracked_files(repo_path: &Path) -> Result<Vec<String>> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let repo = Repository::open(repo_path).context("Failed to open repository")?;
+
+    let mut status_options = git2::StatusOptions::new();
+    status_options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut status_options))
+        .context("Failed to read repository status")?;
+
+    let mut untracked: Vec<String> = statuses
+        .iter()
+        .filter(|entry| entry.status().contains(git2::Status::WT_NEW))
+        .filter_map(|entry| entry.path().map(str::to_string))
+        .collect();
+    untracked.sort();
+    Ok(untracked)
+}
+
+/// Lists the files changed by the diff included in the prompt: the staged changes of the
+/// working tree, or the changes between two branches. Deleted files are left out, since they
+/// cannot be included.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+/// * `branches` - The branches to compare, if the branch diff is used
+/// * `options` - The diff options, whose patterns also filter the changed files
+///
+/// # Returns
+///
+/// * `Result<Vec<String>>` - The changed file paths, relative to the repository root, sorted
+pub fn get_changed_files(
+    repo_path: &Path,
+    branches: Option<(&str, &str)>,
+    options: &GitDiffOptions,
+) -> Result<Vec<String>> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let repo = Repository::open(repo_path).context("Failed to open repository")?;
+    let filter = FilterEngine::new(&options.include_patterns, &options.exclude_patterns);
+
+    // The diff the prompt renders: the branch diff, or the staged changes
+    let diff = match branches {
+        Some((branch1, branch2)) => branch_changes(&repo, branch1, branch2, options)?,
+        None => staged_changes(&repo, options)?,
+    };
+
+    let mut changed: Vec<String> = diff
+        .deltas()
+        .filter(|delta| delta.status() != git2::Delta::Deleted)
+        .filter_map(|delta| delta.new_file().path())
+        .filter(|path| filter.matches_patterns(path))
+        .map(path_key)
+        .collect();
+    changed.sort();
+    changed.dedup();
+    Ok(changed)
+}
 
+/// Resolves the files kept by `config.include_only_changed`: those changed by the branch
+/// diff when `diff_branches` is set, by the working tree diff otherwise.
+///
+/// # Arguments
+///
+/// * `config` - The configuration
+/// * `root` - The directory the traversed paths are relative to
+///
+/// # Returns
+///
+/// * `Result<Option<HashSet<PathBuf>>>` - The changed paths relative to `root`, or `None`
+///   if all files are kept
+pub fn changed_scope(config: &Code2PromptConfig, root: &Path) -> Result<Option<HashSet<PathBuf>>> {
+    if !config.include_only_changed {
+        return Ok(None);
+    }
+    let repo_path = config.git_dir.as_deref().unwrap_or(&config.path);
+    let workdir = Repository::open(repo_path)
+        .context("Failed to open repository")?
+        .workdir()
+        .unwrap_or(repo_path)
+        .canonicalize()?;
+    let branches = config
+        .diff_branches
+        .as_ref()
+        .map(|(branch1, branch2)| (branch1.as_str(), branch2.as_str()));
+    let options = GitDiffOptions {
+        include_patterns: config.diff_include_patterns.clone(),
+        exclude_patterns: config.diff_exclude_patterns.clone(),
+        ..Default::default()
+    };
+
+    let changed = get_changed_files(repo_path, branches, &options)?;
+    info!("Files changed by the diff: {}", changed.len());
+    Ok(Some(
+        changed
+            .iter()
+            .filter_map(|path| {
+                let path = workdir.join(path);
+                path.strip_prefix(root).ok().map(Path::to_path_buf)
+            })
+            .collect(),
+    ))
+}
+
+/// Prints a diff as a patch, skipping the files rejected by the filter.
+fn diff_to_string(diff: &Diff, filter: &FilterEngine) -> Result<String> {
     let mut diff_text = Vec::new();
-    diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
-        diff_text.extend_from_slice(line.content());
+    diff.print(git2::DiffFormat::Patch, |delta, _hunk, line| {
+        let path = delta.new_file().path().or_else(|| delta.old_file().path());
+        if path.is_none_or(|path| filter.matches_patterns(path)) {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                diff_text.push(line.origin() as u8);
+            }
+            diff_text.extend_from_slice(line.content());
+        }
         true
+    })?;
+    Ok(String::from_utf8_lossy(&diff_text).into_owned())
+}
+
+/// Prints a diff in the requested style, skipping the files rejected by the filter.
+fn render_diff(diff: &Diff, options: &GitDiffOptions, filter: &FilterEngine) -> Result<String> {
+    let patch = diff_to_string(diff, filter)?;
+    let context_lines = options.context_lines.unwrap_or(3) as usize;
+    Ok(match options.style {
+        DiffStyle::Unified => return Ok(patch),
+        DiffStyle::Word => split_diff(&patch).iter().map(word_diff).collect(),
+        DiffStyle::Function => split_diff(&patch)
+            .iter()
+            .map(|file| function_diff(file, context_lines))
+            .collect(),
     })
-    .context("Failed to print diff")?;
+}
 
-    info!("Generated git diff between branches successfully");
-    Ok(String::from_utf8_lossy(&diff_text).into_owned())
+/// Renders a file diff at word level, as `git diff --word-diff=plain` does.
+///
+/// Runs of removed lines followed by added lines are compared word by word and the
+/// differences are marked inline as `[-removed-]{+added+}`.
+fn word_diff(file: &FileDiff) -> String {
+    let mut text = file.header.concat();
+    for hunk in &file.hunks {
+        text.push_str(hunk[0]);
+        let mut lines = hunk[1..].iter().peekable();
+        while let Some(line) = lines.next() {
+            if line.starts_with('\\') {
+                continue;
+            }
+            if !line.starts_with(['-', '+']) {
+                text.push_str(line.get(1..).unwrap_or_default());
+                continue;
+            }
+            let (mut removed, mut added) = (String::new(), String::new());
+            let mut current = Some(*line);
+            while let Some(line) = current {
+                let side = match line.as_bytes()[0] {
+                    b'-' => &mut removed,
+                    b'+' => &mut added,
+                    _ => &mut String::new(),
+                };
+                side.push_str(&line[1..]);
+                if !line.ends_with('\n') {
+                    side.push('\n');
+                }
+                current = lines
+                    .next_if(|next| next.starts_with(['-', '+', '\\']))
+                    .copied();
+            }
+            text.push_str(&word_diff_lines(&removed, &added));
+        }
+    }
+    text
+}
+
+/// Maximum size of the word comparison table, beyond which whole lines are marked instead.
+const MAX_WORD_DIFF_CELLS: usize = 4_000_000;
+
+/// Marks the word-level differences between removed and added lines.
+fn word_diff_lines(removed: &str, added: &str) -> String {
+    let mark_lines = |text: &str, open: &str, close: &str| -> String {
+        text.lines()
+            .map(|line| format!("{}{}{}\n", open, line, close))
+            .collect()
+    };
+    let old = tokenize_words(removed);
+    let new = tokenize_words(added);
+    if old.is_empty() || new.is_empty() || old.len() * new.len() > MAX_WORD_DIFF_CELLS {
+        return mark_lines(removed, "[-", "-]") + &mark_lines(added, "{+", "+}");
+    }
+
+    // Longest common subsequence of the two token lists
+    let mut lcs = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut text = String::new();
+    let (mut deleted, mut inserted) = (String::new(), String::new());
+    let flush = |text: &mut String, deleted: &mut String, inserted: &mut String| {
+        if !deleted.is_empty() {
+            text.push_str(&format!("[-{}-]", deleted));
+            deleted.clear();
+        }
+        if !inserted.is_empty() {
+            text.push_str(&format!("{{+{}+}}", inserted));
+            inserted.clear();
+        }
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            flush(&mut text, &mut deleted, &mut inserted);
+            text.push_str(old[i]);
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            // Markers never span lines: the text follows the layout of the added lines
+            if new[j] == "\n" {
+                flush(&mut text, &mut deleted, &mut inserted);
+                text.push('\n');
+            } else {
+                inserted.push_str(new[j]);
+            }
+            j += 1;
+        } else {
+            if old[i] == "\n" {
+                flush(&mut text, &mut deleted, &mut inserted);
+            } else {
+                deleted.push_str(old[i]);
+            }
+            i += 1;
+        }
+    }
+    flush(&mut text, &mut deleted, &mut inserted);
+    text
+}
+
+/// Splits text into words, whitespace runs, newlines and single punctuation characters.
+fn tokenize_words(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let class = |c: char| {
+        if c == '\n' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else if c.is_alphanumeric() || c == '_' {
+            2
+        } else {
+            3
+        }
+    };
+    let mut chars = text.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        let kind = class(c);
+        if kind == 1 || kind == 2 {
+            while chars.next_if(|&(_, next)| class(next) == kind).is_some() {}
+        }
+        let end = chars.peek().map_or(text.len(), |&(index, _)| index);
+        tokens.push(&text[start..end]);
+        start = end;
+    }
+    tokens
+}
+
+/// Cuts a file diff generated with full context down to the functions enclosing the
+/// changes, as `git diff --function-context` does.
+///
+/// A function starts at a line beginning with a letter, `_` or `$`, git's default
+/// function header heuristic, and ends right before the next one.
+fn function_diff(file: &FileDiff, context_lines: usize) -> String {
+    let mut text = file.header.concat();
+    for hunk in &file.hunks {
+        let Some((old_start, new_start)) = parse_hunk_start(hunk[0]) else {
+            text.push_str(&hunk.concat());
+            continue;
+        };
+        let lines = &hunk[1..];
+        let is_function_start = |index: usize| {
+            lines[index]
+                .get(1..)
+                .and_then(|line| line.chars().next())
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        };
+
+        // Ranges of lines to keep, one per change, merged when they overlap
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            if !line.starts_with(['-', '+']) {
+                continue;
+            }
+            let start = (0..=index)
+                .rev()
+                .find(|&i| is_function_start(i))
+                .unwrap_or(0)
+                .min(index.saturating_sub(context_lines));
+            let end = (index + 1..lines.len())
+                .find(|&i| is_function_start(i) && lines[i].starts_with(' '))
+                .unwrap_or(lines.len())
+                .max((index + 1 + context_lines).min(lines.len()));
+            match ranges.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => ranges.push((start, end)),
+            }
+        }
+
+        let (mut old_line, mut new_line, mut position) = (old_start, new_start, 0);
+        for (start, end) in ranges {
+            for line in &lines[position..start] {
+                old_line += usize::from(!line.starts_with(['+', '\\']));
+                new_line += usize::from(!line.starts_with(['-', '\\']));
+            }
+            let body = &lines[start..end];
+            let old_count = body.iter().filter(|l| !l.starts_with(['+', '\\'])).count();
+            let new_count = body.iter().filter(|l| !l.starts_with(['-', '\\'])).count();
+            text.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                old_line, old_count, new_line, new_count
+            ));
+            text.push_str(&body.concat());
+            old_line += old_count;
+            new_line += new_count;
+            position = end;
+        }
+    }
+    text
+}
+
+/// Parses the old and new start lines of a hunk header such as `@@ -1,4 +1,5 @@`.
+fn parse_hunk_start(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = header.strip_prefix("@@ -")?.split(' ');
+    let start = |range: &str| range.split(',').next()?.parse::<usize>().ok();
+    let old_start = start(ranges.next()?)?;
+    let new_start = start(ranges.next()?.strip_prefix('+')?)?;
+    Some((old_start, new_start))
+}
+
+/// Lists the branches of the repository at the provided path.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+///
+/// # Returns
+///
+/// * `Result<Vec<String>>` - The local branches, sorted by name, followed by the
+///   remote-tracking branches
+pub fn get_branches(repo_path: &Path) -> Result<Vec<String>> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let repo = Repository::open(repo_path).context("Failed to open repository")?;
+
+    let mut branches = Vec::new();
+    for branch_type in [git2::BranchType::Local, git2::BranchType::Remote] {
+        let mut names = Vec::new();
+        for branch in repo.branches(Some(branch_type))? {
+            let (branch, _) = branch.context("Failed to read branch")?;
+            // `origin/HEAD` only points at another remote branch
+            if let Some(name) = branch.name()?
+                && !name.ends_with("/HEAD")
+            {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        branches.extend(names);
+    }
+    Ok(branches)
 }
 
 /// Retrieves the git log between two branches for the repository at the provided path
@@ -176,6 +787,865 @@ pub fn get_git_log(repo_path: &Path, branch1: &str, branch2: &str) -> Result<Str
     Ok(log_text)
 }
 
+/// Number of tags listed in `latest_tags`
+pub const LATEST_TAGS_COUNT: usize = 5;
+
+/// Where a revision stands relative to the tags of the repository, as `git describe`
+/// tells it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VersionInfo {
+    /// The short commit id of the revision
+    pub commit: String,
+    /// The tag pointing at the revision, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// The nearest tag reachable from the revision
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nearest_tag: Option<String>,
+    /// Number of commits since the nearest tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<usize>,
+    /// The revision described as `<tag>-<distance>-g<commit>`, or the tag itself on a tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub describe: Option<String>,
+    /// The most recent tags of the repository, newest first
+    pub latest_tags: Vec<String>,
+}
+
+/// Describes a revision relative to the tags of the repository.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+/// * `rev` - The revision to describe (e.g. "HEAD")
+///
+/// # Returns
+///
+/// * `Result<VersionInfo>` - The version information, whose tag fields are empty if no tag
+///   is reachable from the revision
+pub fn get_version_info(repo_path: &Path, rev: &str) -> Result<VersionInfo> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let repo = Repository::open(repo_path).context("Failed to open repository")?;
+    let commit = repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| format!("Failed to resolve revision {}", rev))?;
+
+    // Tags with the commit they point at, newest commit first
+    let mut tags: Vec<(String, git2::Commit)> = repo
+        .tag_names(None)
+        .context("Failed to list tags")?
+        .iter()
+        .flatten()
+        .filter_map(|name| {
+            let target = repo.revparse_single(&format!("refs/tags/{}", name)).ok()?;
+            Some((name.to_string(), target.peel_to_commit().ok()?))
+        })
+        .collect();
+    tags.sort_by(|(a_name, a), (b_name, b)| {
+        b.time()
+            .seconds()
+            .cmp(&a.time().seconds())
+            .then_with(|| b_name.cmp(a_name))
+    });
+
+    let describe = commit
+        .as_object()
+        .describe(git2::DescribeOptions::new().describe_tags())
+        .and_then(|describe| {
+            describe.format(Some(
+                git2::DescribeFormatOptions::new().always_use_long_format(true),
+            ))
+        })
+        .ok();
+    // The long format is `<tag>-<distance>-g<commit>`, tags may contain dashes
+    let (nearest_tag, distance) = describe
+        .as_deref()
+        .and_then(|describe| {
+            let (rest, _) = describe.rsplit_once("-g")?;
+            let (tag, distance) = rest.rsplit_once('-')?;
+            Some((Some(tag.to_string()), distance.parse().ok()))
+        })
+        .unwrap_or((None, None));
+
+    let short_id = commit.id().to_string()[..7].to_string();
+    info!("Described revision {}", rev);
+    Ok(VersionInfo {
+        tag: tags
+            .iter()
+            .find(|(_, target)| target.id() == commit.id())
+            .map(|(name, _)| name.clone()),
+        describe: match (&nearest_tag, distance) {
+            (Some(tag), Some(0)) => Some(tag.clone()),
+            (Some(tag), Some(distance)) => Some(format!("{}-{}-g{}", tag, distance, short_id)),
+            _ => None,
+        },
+        nearest_tag,
+        distance,
+        commit: short_id,
+        latest_tags: tags
+            .into_iter()
+            .take(LATEST_TAGS_COUNT)
+            .map(|(name, _)| name)
+            .collect(),
+    })
+}
+
+/// Default number of commits listed by the history of a file
+pub const DEFAULT_FILE_HISTORY_COMMITS: usize = 5;
+
+/// A commit changing a file, as listed in its history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileCommit {
+    /// The short commit id
+    pub hash: String,
+    pub author: String,
+    /// The commit date, as `YYYY-MM-DD`
+    pub date: String,
+    /// The full commit message
+    pub message: String,
+    /// The changes of the commit to the file
+    pub diff: String,
+}
+
+/// Returns the id of the commit HEAD points to.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+///
+/// # Returns
+///
+/// * `Option<String>` - The commit id, `None` outside a repository or before its first commit
+pub fn head_commit_id(repo_path: &Path) -> Option<String> {
+    let repo = Repository::open(repo_path).ok()?;
+    let head = repo.head().ok()?.peel_to_commit().ok()?;
+    Some(head.id().to_string())
+}
+
+/// Retrieves the latest non-merge commits reachable from HEAD changing a file, with the
+/// diff of each to the file.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+/// * `path` - The path of the file, relative to the repository root
+/// * `count` - The maximum number of commits to return
+/// * `options` - The diff options
+///
+/// # Returns
+///
+/// * `Result<Vec<FileCommit>>` - The commits, newest first. Empty if the file has no history.
+pub fn get_file_history(
+    repo_path: &Path,
+    path: &str,
+    count: usize,
+    options: &GitDiffOptions,
+) -> Result<Vec<FileCommit>> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let repo = Repository::open(repo_path).context("Failed to open repository")?;
+    let filter = FilterEngine::new(&[], &[]);
+
+    let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) else {
+        return Ok(Vec::new());
+    };
+    let mut revwalk = repo.revwalk().context("Failed to create revwalk")?;
+    revwalk
+        .push(head.id())
+        .context("Failed to push HEAD to revwalk")?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+    let mut history = Vec::new();
+    for oid in revwalk {
+        if history.len() >= count {
+            break;
+        }
+        let commit = repo
+            .find_commit(oid.context("Failed to get OID from revwalk")?)
+            .context("Failed to find commit")?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let parent_tree = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        let mut diff_options = options.diff_options();
+        diff_options.pathspec(path).disable_pathspec_match(true);
+        let diff = repo
+            .diff_tree_to_tree(
+                parent_tree.as_ref(),
+                Some(&commit.tree()?),
+                Some(&mut diff_options),
+            )
+            .context("Failed to generate commit diff")?;
+        if diff.deltas().len() == 0 {
+            continue;
+        }
+
+        history.push(FileCommit {
+            hash: commit.id().to_string()[..7].to_string(),
+            author: commit.author().name().unwrap_or_default().to_string(),
+            date: iso_date(commit.time().seconds().max(0) as u64),
+            message: commit.message().unwrap_or_default().trim().to_string(),
+            diff: render_diff(&diff, options, &filter).context("Failed to print diff")?,
+        });
+    }
+
+    info!("Retrieved {} commits of {}", history.len(), path);
+    Ok(history)
+}
+
+/// Number of commits walked back from HEAD to find the last change of each file
+pub const LAST_CHANGE_MAX_COMMITS: usize = 10_000;
+
+/// The last commit changing a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastChange {
+    pub author: String,
+    /// The commit date, as `YYYY-MM-DD`
+    pub date: String,
+    /// The commit time, in seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+/// Finds the last non-merge commit reachable from HEAD changing each of the given files,
+/// walking back at most `LAST_CHANGE_MAX_COMMITS` commits.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+/// * `paths` - The paths of the files, relative to the repository root
+///
+/// # Returns
+///
+/// * `Result<HashMap<String, LastChange>>` - The last change of each file found in the history
+pub fn get_last_changes(repo_path: &Path, paths: &[String]) -> Result<HashMap<String, LastChange>> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let repo = Repository::open(repo_path).context("Failed to open repository")?;
+    let mut remaining: HashSet<&str> = paths.iter().map(String::as_str).collect();
+    let mut changes = HashMap::new();
+
+    let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) else {
+        return Ok(changes);
+    };
+    let mut revwalk = repo.revwalk().context("Failed to create revwalk")?;
+    revwalk
+        .push(head.id())
+        .context("Failed to push HEAD to revwalk")?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+    for oid in revwalk.take(LAST_CHANGE_MAX_COMMITS) {
+        if remaining.is_empty() {
+            break;
+        }
+        let commit = repo
+            .find_commit(oid.context("Failed to get OID from revwalk")?)
+            .context("Failed to find commit")?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let parent_tree = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        let diff = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)
+            .context("Failed to generate commit diff")?;
+        for delta in diff.deltas() {
+            let Some(path) = delta.new_file().path().and_then(Path::to_str) else {
+                continue;
+            };
+            if remaining.remove(path) {
+                let timestamp = commit.time().seconds().max(0) as u64;
+                changes.insert(
+                    path.to_string(),
+                    LastChange {
+                        author: commit.author().name().unwrap_or_default().to_string(),
+                        date: iso_date(timestamp),
+                        timestamp,
+                    },
+                );
+            }
+        }
+    }
+
+    info!("Found the last change of {} files", changes.len());
+    Ok(changes)
+}
+
+/// A file changed on a branch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangedFile {
+    pub path: String,
+    /// `added`, `modified`, `deleted` or `renamed`
+    pub status: &'static str,
+    /// The previous path of a renamed file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+}
+
+/// What a branch changes compared to its base, as a pull request would show it
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchChanges {
+    pub base: String,
+    pub head: String,
+    /// Full messages of the commits of the branch, oldest first, merges excluded
+    pub commits: Vec<String>,
+    /// The files changed on the branch, sorted by path
+    pub files: Vec<ChangedFile>,
+    /// The diff of the branch against the merge base
+    #[serde(skip)]
+    pub diff: String,
+}
+
+/// Collects the changes a branch brings compared to a base branch: the commits not on
+/// the base, the touched files and the diff against the merge base (`git diff base...head`).
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+/// * `base` - The branch the changes are compared to (e.g. "main")
+/// * `head` - The branch bringing the changes
+/// * `options` - The diff options, whose patterns also filter the touched files
+///
+/// # Returns
+///
+/// * `Result<BranchChanges>` - The changes, or an error if a branch does not exist
+pub fn get_branch_changes(
+    repo_path: &Path,
+    base: &str,
+    head: &str,
+    options: &GitDiffOptions,
+) -> Result<BranchChanges> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let repo = Repository::open(repo_path).context("Failed to open repository")?;
+    let filter = FilterEngine::new(&options.include_patterns, &options.exclude_patterns);
+
+    for branch in [base, head].iter() {
+        if !branch_exists(&repo, branch) {
+            return Err(anyhow::anyhow!("Branch {} doesn't exist!", branch));
+        }
+    }
+
+    let base_commit = repo.revparse_single(base)?.peel_to_commit()?;
+    let head_commit = repo.revparse_single(head)?.peel_to_commit()?;
+    let merge_base = repo
+        .merge_base(base_commit.id(), head_commit.id())
+        .with_context(|| format!("{} and {} have no common ancestor", base, head))?;
+    let merge_base_tree = repo.find_commit(merge_base)?.tree()?;
+
+    let mut diff = repo
+        .diff_tree_to_tree(
+            Some(&merge_base_tree),
+            Some(&head_commit.tree()?),
+            Some(&mut options.diff_options()),
+        )
+        .context("Failed to generate branch diff")?;
+    diff.find_similar(None)
+        .context("Failed to detect renamed files")?;
+
+    let mut files: Vec<ChangedFile> = diff
+        .deltas()
+        .filter_map(|delta| {
+            let new_path = delta.new_file().path();
+            let old_path = delta.old_file().path();
+            let path = new_path.or(old_path)?;
+            if !filter.matches_patterns(path) {
+                return None;
+            }
+            let status = match delta.status() {
+                git2::Delta::Added | git2::Delta::Copied => "added",
+                git2::Delta::Deleted => "deleted",
+                git2::Delta::Renamed => "renamed",
+                _ => "modified",
+            };
+            Some(ChangedFile {
+                path: path.to_string_lossy().into_owned(),
+                status,
+                old_path: (status == "renamed")
+                    .then(|| old_path.map(|path| path.to_string_lossy().into_owned()))
+                    .flatten(),
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut revwalk = repo.revwalk().context("Failed to create revwalk")?;
+    revwalk
+        .push(head_commit.id())
+        .context("Failed to push head commit to revwalk")?;
+    revwalk
+        .hide(merge_base)
+        .context("Failed to hide merge base from revwalk")?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let commit = repo
+            .find_commit(oid.context("Failed to get OID from revwalk")?)
+            .context("Failed to find commit")?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        if let Some(message) = commit.message() {
+            commits.push(message.trim().to_string());
+        }
+    }
+
+    let diff = render_diff(&diff, options, &filter).context("Failed to print diff")?;
+    info!("Collected branch changes successfully");
+    Ok(BranchChanges {
+        base: base.to_string(),
+        head: head.to_string(),
+        commits,
+        files,
+        diff,
+    })
+}
+
+/// Paths left out of release notes diffs unless diff patterns are configured: tests,
+/// lock files, snapshots and CI configuration rarely matter to a changelog.
+pub const RELEASE_NOTES_EXCLUDE_PATTERNS: &[&str] = &[
+    "tests/*",
+    "*/tests/*",
+    "*_test.*",
+    "*.snap",
+    "*.lock",
+    "*-lock.json",
+    "*-lock.yaml",
+    ".github/*",
+];
+
+/// A pull request merged into the history, found from its merge or squash commit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MergedPullRequest {
+    pub number: u64,
+    /// The pull request title, or the merged branch when the merge commit has no title
+    pub title: String,
+}
+
+/// Finds the pull requests merged between two revisions, oldest first.
+///
+/// Both the merge commits created by GitHub (`Merge pull request #12 from user/branch`,
+/// the title on the following line) and squashed commits (`Title (#12)`) are recognized.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+/// * `since` - The revision the history starts after (e.g. "v1.3.0")
+/// * `until` - The last revision of the history (e.g. "HEAD")
+///
+/// # Returns
+///
+/// * `Result<Vec<MergedPullRequest>>` - The pull requests, each listed once
+pub fn get_merged_pull_requests(
+    repo_path: &Path,
+    since: &str,
+    until: &str,
+) -> Result<Vec<MergedPullRequest>> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let repo = Repository::open(repo_path).context("Failed to open repository")?;
+
+    let since_commit = repo
+        .revparse_single(since)
+        .with_context(|| format!("Revision {} doesn't exist!", since))?
+        .peel_to_commit()?;
+    let until_commit = repo
+        .revparse_single(until)
+        .with_context(|| format!("Revision {} doesn't exist!", until))?
+        .peel_to_commit()?;
+
+    let mut revwalk = repo.revwalk().context("Failed to create revwalk")?;
+    revwalk
+        .push(until_commit.id())
+        .context("Failed to push commit to revwalk")?;
+    revwalk
+        .hide(since_commit.id())
+        .context("Failed to hide commit from revwalk")?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+
+    let mut pull_requests: Vec<MergedPullRequest> = Vec::new();
+    for oid in revwalk {
+        let commit = repo
+            .find_commit(oid.context("Failed to get OID from revwalk")?)
+            .context("Failed to find commit")?;
+        if let Some(pull_request) = commit.message().and_then(parse_pull_request)
+            && !pull_requests
+                .iter()
+                .any(|pr| pr.number == pull_request.number)
+        {
+            pull_requests.push(pull_request);
+        }
+    }
+    Ok(pull_requests)
+}
+
+/// Reads the pull request a merge or squash commit message refers to, if any.
+fn parse_pull_request(message: &str) -> Option<MergedPullRequest> {
+    let mut lines = message.lines();
+    let summary = lines.next()?.trim();
+
+    if let Some(rest) = summary.strip_prefix("Merge pull request #") {
+        let (number, branch) = rest.split_once(" from ").unwrap_or((rest, ""));
+        let number = number.trim().parse().ok()?;
+        let title = lines
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or(branch.trim());
+        return Some(MergedPullRequest {
+            number,
+            title: title.to_string(),
+        });
+    }
+
+    let (title, number) = summary.strip_suffix(')')?.rsplit_once(" (#")?;
+    Some(MergedPullRequest {
+        number: number.parse().ok()?,
+        title: title.trim().to_string(),
+    })
+}
+
+/// The diff of a single file, split into its header and its hunks
+struct FileDiff<'a> {
+    path: &'a str,
+    header: Vec<&'a str>,
+    hunks: Vec<Vec<&'a str>>,
+    truncated: bool,
+}
+
+impl FileDiff<'_> {
+    fn render(&self) -> String {
+        let mut text = self.header.concat();
+        if self.truncated {
+            let line_count: usize = self.hunks.iter().map(|hunk| hunk.len() - 1).sum();
+            text.push_str(&format!(
+                "[... {} hunk(s), {} line(s) truncated ...]\n",
+                self.hunks.len(),
+                line_count
+            ));
+        } else {
+            for hunk in &self.hunks {
+                text.push_str(&hunk.concat());
+            }
+        }
+        text
+    }
+}
+
+/// Truncates a diff so that it fits in the given token budget.
+///
+/// File headers and hunk counts are always kept. Hunk bodies are dropped file by file,
+/// starting with generated files (lock files, minified or vendored code) and then the
+/// largest files, until the diff fits. A truncation notice is appended when anything
+/// was dropped.
+///
+/// # Arguments
+///
+/// * `diff` - The diff to truncate
+/// * `max_tokens` - The token budget of the diff
+/// * `tokenizer_type` - The tokenizer used to count tokens
+/// * `model` - The model file of the tokenizer, if any
+/// * `mode` - Whether tokens are counted exactly or estimated
+///
+/// # Returns
+///
+/// * `String` - The diff, truncated if it exceeded the budget
+pub fn truncate_diff(
+    diff: &str,
+    max_tokens: usize,
+    tokenizer_type: &TokenizerType,
+    model: Option<&Path>,
+    mode: TokenCountMode,
+) -> String {
+    let count = |text: &str| count_tokens_with_mode(text, "diff", tokenizer_type, model, mode);
+    let mut total = count(diff);
+    if total <= max_tokens {
+        return diff.to_string();
+    }
+
+    let mut files = split_diff(diff);
+    let mut sizes: Vec<usize> = files.iter().map(|file| count(&file.render())).collect();
+
+    // Generated files first, then the largest ones
+    let mut order: Vec<usize> = (0..files.len()).collect();
+    order.sort_by_key(|&i| {
+        (
+            !is_generated_file(files[i].path),
+            std::cmp::Reverse(sizes[i]),
+        )
+    });
+
+    let mut truncated_files = 0;
+    for i in order {
+        if total <= max_tokens {
+            break;
+        }
+        if files[i].hunks.is_empty() {
+            continue;
+        }
+        files[i].truncated = true;
+        let size = count(&files[i].render());
+        total = total.saturating_sub(sizes[i]) + size;
+        sizes[i] = size;
+        truncated_files += 1;
+    }
+
+    let mut output: String = files.iter().map(FileDiff::render).collect();
+    if truncated_files > 0 {
+        output.push_str(&format!(
+            "\nNote: diff truncated to fit {} tokens; hunk bodies omitted for {} file(s).",
+            max_tokens, truncated_files
+        ));
+    }
+    output
+}
+
+/// Prefixes the paths of the file headers of a patch, as `a/{prefix}/src/lib.rs`, so
+/// that they match the paths of the files in the output. Hunk lines are left untouched.
+///
+/// # Arguments
+///
+/// * `diff` - The patch
+/// * `prefix` - The prefix of the paths, without a trailing slash
+///
+/// # Returns
+///
+/// * `String` - The patch with prefixed paths
+pub fn prefix_diff_paths(diff: &str, prefix: &str) -> String {
+    let prefix_side = |path: &str, side: &str| match path.strip_prefix(side) {
+        Some(path) => format!("{}{}/{}", side, prefix, path),
+        None => path.to_string(),
+    };
+    let mut in_header = false;
+    let mut output = String::with_capacity(diff.len());
+    for line in diff.split_inclusive('\n') {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            in_header = true;
+            match paths.rsplit_once(" b/") {
+                Some((old, new)) => output.push_str(&format!(
+                    "diff --git {} {}",
+                    prefix_side(old, "a/"),
+                    prefix_side(&format!("b/{}", new), "b/")
+                )),
+                None => output.push_str(line),
+            }
+        } else if line.starts_with("@@") {
+            in_header = false;
+            output.push_str(line);
+        } else if in_header && let Some(path) = line.strip_prefix("--- ") {
+            output.push_str(&format!("--- {}", prefix_side(path, "a/")));
+        } else if in_header && let Some(path) = line.strip_prefix("+++ ") {
+            output.push_str(&format!("+++ {}", prefix_side(path, "b/")));
+        } else {
+            output.push_str(line);
+        }
+    }
+    output
+}
+
+/// Splits a patch into the diffs of its files.
+///
+/// # Arguments
+///
+/// * `diff` - The patch to split
+///
+/// # Returns
+///
+/// * `Vec<(String, String)>` - The path and the diff of each file, in patch order
+pub fn split_diff_by_file(diff: &str) -> Vec<(String, String)> {
+    split_diff(diff)
+        .iter()
+        .map(|file| (file.path.to_string(), file.render()))
+        .collect()
+}
+
+/// Splits a patch into per-file diffs.
+fn split_diff(diff: &str) -> Vec<FileDiff<'_>> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    for line in diff.split_inclusive('\n') {
+        if line.starts_with("diff --git ") || files.is_empty() {
+            let path = line
+                .trim_end()
+                .rsplit_once(" b/")
+                .map(|(_, path)| path)
+                .unwrap_or_default();
+            files.push(FileDiff {
+                path,
+                header: Vec::new(),
+                hunks: Vec::new(),
+                truncated: false,
+            });
+        }
+        let file = files.last_mut().unwrap();
+        if line.starts_with("@@") {
+            file.hunks.push(vec![line]);
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            hunk.push(line);
+        } else {
+            file.header.push(line);
+        }
+    }
+    files
+}
+
+/// Whether a path looks like a generated file, whose diff is of little interest.
+fn is_generated_file(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    matches!(
+        file_name,
+        "package-lock.json"
+            | "yarn.lock"
+            | "pnpm-lock.yaml"
+            | "Cargo.lock"
+            | "poetry.lock"
+            | "Gemfile.lock"
+            | "composer.lock"
+            | "go.sum"
+    ) || [".min.js", ".min.css", ".map", ".pb.go", ".snap"]
+        .iter()
+        .any(|suffix| file_name.ends_with(suffix))
+        || file_name.contains("_generated.")
+        || ["vendor/", "dist/", "node_modules/"]
+            .iter()
+            .any(|dir| path.starts_with(dir) || path.contains(&format!("/{}", dir)))
+}
+
+/// A file with unresolved merge conflicts.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConflictedFile {
+    /// Path of the file, relative to the repository root
+    pub path: String,
+    /// Content at the merge base, if the file existed there
+    pub base: Option<String>,
+    /// Content on our side (HEAD), if the file exists there
+    pub ours: Option<String>,
+    /// Content on their side (the merged branch), if the file exists there
+    pub theirs: Option<String>,
+    /// Three-way merge of the file with diff3 conflict markers, when all sides exist
+    pub merged: Option<String>,
+}
+
+/// The state of a merge with unresolved conflicts.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MergeConflicts {
+    /// Name of the current branch
+    pub ours_branch: String,
+    /// Name of the branch being merged, when recorded in the merge message
+    pub theirs_branch: Option<String>,
+    /// The HEAD commit, as `<short id> - <summary>`
+    pub ours_commit: String,
+    /// The commits being merged (MERGE_HEAD), as `<short id> - <summary>`
+    pub theirs_commits: Vec<String>,
+    /// The conflicted files
+    pub files: Vec<ConflictedFile>,
+}
+
+/// Collects the unresolved merge conflicts of the repository at the provided path.
+///
+/// # Arguments
+///
+/// * `repo_path` - A reference to the path of the git repository
+///
+/// # Returns
+///
+/// * `Result<Option<MergeConflicts>>` - The conflicts, or `None` if the index has no conflicts
+pub fn get_merge_conflicts(repo_path: &Path) -> Result<Option<MergeConflicts>> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let mut repo = Repository::open(repo_path).context("Failed to open repository")?;
+
+    let index = repo.index().context("Failed to read index")?;
+    if !index.has_conflicts() {
+        return Ok(None);
+    }
+
+    let mut merge_heads = Vec::new();
+    // Fails when no merge is in progress (e.g. conflicts left by a stash pop)
+    let _ = repo.mergehead_foreach(|oid| {
+        merge_heads.push(*oid);
+        true
+    });
+
+    let head = repo.head().context("Failed to get repository head")?;
+    let ours_branch = head.shorthand().unwrap_or("HEAD").to_string();
+    let ours_commit = describe_commit(&head.peel_to_commit()?);
+    let theirs_commits = merge_heads
+        .iter()
+        .filter_map(|oid| repo.find_commit(*oid).ok())
+        .map(|commit| describe_commit(&commit))
+        .collect();
+
+    // "Merge branch 'feature' into main" → "feature"
+    let theirs_branch = repo.message().ok().and_then(|message| {
+        let first_line = message.lines().next()?;
+        let start = first_line.find('\'')? + 1;
+        let end = start + first_line[start..].find('\'')?;
+        Some(first_line[start..end].to_string())
+    });
+
+    let blob_content = |entry: &Option<git2::IndexEntry>| {
+        entry.as_ref().and_then(|entry| {
+            let blob = repo.find_blob(entry.id).ok()?;
+            Some(String::from_utf8_lossy(blob.content()).into_owned())
+        })
+    };
+
+    let mut files = Vec::new();
+    for conflict in index.conflicts().context("Failed to read conflicts")? {
+        let conflict = conflict?;
+        let Some(entry) = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .or(conflict.ancestor.as_ref())
+        else {
+            continue;
+        };
+        let path = String::from_utf8_lossy(&entry.path).into_owned();
+
+        let merged = match (&conflict.ancestor, &conflict.our, &conflict.their) {
+            (Some(ancestor), Some(our), Some(their)) => {
+                let mut options = git2::MergeFileOptions::new();
+                options
+                    .style_diff3(true)
+                    .ancestor_label("base")
+                    .our_label(ours_branch.as_str())
+                    .their_label(theirs_branch.as_deref().unwrap_or("theirs"));
+                repo.merge_file_from_index(ancestor, our, their, Some(&mut options))
+                    .ok()
+                    .map(|result| String::from_utf8_lossy(result.content()).into_owned())
+            }
+            _ => None,
+        };
+
+        files.push(ConflictedFile {
+            path,
+            base: blob_content(&conflict.ancestor),
+            ours: blob_content(&conflict.our),
+            theirs: blob_content(&conflict.their),
+            merged,
+        });
+    }
+
+    info!("Collected {} conflicted file(s)", files.len());
+    Ok(Some(MergeConflicts {
+        ours_branch,
+        theirs_branch,
+        ours_commit,
+        theirs_commits,
+        files,
+    }))
+}
+
+/// Formats a commit as `<short id> - <summary>`, like the git log output.
+fn describe_commit(commit: &git2::Commit) -> String {
+    format!(
+        "{} - {}",
+        &commit.id().to_string()[..7],
+        commit.summary().unwrap_or("No commit message")
+    )
+}
+
 /// Checks if a git reference exists in the given repository
 ///
 /// This function can validate any git reference including:
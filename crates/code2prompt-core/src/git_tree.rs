@@ -0,0 +1,192 @@
+//! Read-only view of the tree of a git revision.
+//!
+//! Files are read from the object database instead of the filesystem, so prompts can be
+//! generated against bare repositories (e.g. `/srv/git/project.git`) where no working
+//! tree exists.
+
+use crate::bazel::bazel_scope;
+use crate::binary::{BinaryHandling, binary_file_entry, is_binary};
+use crate::collapse::collapse_files;
+use crate::configuration::Code2PromptConfig;
+use crate::filter::build_globset;
+use crate::fixture::fixture_stub;
+use crate::git::changed_scope;
+use crate::path::{EntryMetadata, FileEntry, build_file_entry, stub_file_entry};
+use crate::selection::{FileInclusion, pattern_inclusion};
+use crate::sort::sort_files;
+use crate::tags::symbol_scope;
+use crate::tree::SourceTree;
+use crate::workspace::package_scope;
+use anyhow::{Context, Result};
+use git2::{ObjectType, Repository, TreeWalkMode, TreeWalkResult};
+use log::{debug, info};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Mode of symbolic links in git trees.
+const SYMLINK_FILE_MODE: i32 = 0o120000;
+
+/// Traverses the tree of a revision and returns the string representation of the tree
+/// and the vector of file entries, like [`crate::path::traverse_directory`] does for a
+/// directory.
+///
+/// Hidden files are skipped unless `config.hidden` is set. Ignore files do not apply, since
+/// the tree only holds tracked content.
+///
+/// # Arguments
+///
+/// * `config` - Configuration object containing include/exclude patterns and other settings
+/// * `repo_path` - Path of the repository, bare or not
+/// * `rev` - The revision whose tree is traversed (branch, tag, commit...)
+/// * `selection_engine` - Optional SelectionEngine for advanced file selection with user actions
+///
+/// # Returns
+///
+/// * `Result<(String, Vec<FileEntry>)>` - A tuple containing the string representation of the
+///   tree and a vector of file entries
+pub fn traverse_git_tree(
+    config: &Code2PromptConfig,
+    repo_path: &Path,
+    rev: &str,
+    mut selection_engine: Option<&mut crate::selection::SelectionEngine>,
+) -> Result<(String, Vec<FileEntry>)> {
+    info!("Opening repository at path: {:?}", repo_path);
+    let repo = Repository::open(repo_path).context("Failed to open repository")?;
+    let commit_tree = repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_tree())
+        .with_context(|| format!("Failed to resolve revision {}", rev))?;
+
+    // ~~~ Discovery ~~~
+    let mut blobs = Vec::new();
+    commit_tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        let Some(name) = entry.name() else {
+            return TreeWalkResult::Skip;
+        };
+        if !config.hidden && name.starts_with('.') {
+            return TreeWalkResult::Skip;
+        }
+        if entry.kind() == Some(ObjectType::Blob) {
+            blobs.push((
+                PathBuf::from(format!("{}{}", root, name)),
+                entry.id(),
+                entry.filemode() == SYMLINK_FILE_MODE,
+            ));
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    let include_globset = build_globset(&config.include_patterns);
+    let exclude_globset = build_globset(&config.exclude_patterns);
+    let tree_only_globset = build_globset(&config.tree_only_patterns);
+    let package_scope = package_scope(config, Some((&repo, rev)))?;
+    let bazel_scope = bazel_scope(config, Some((&repo, rev)))?;
+    let symbol_scope = symbol_scope(config)?;
+    let changed_scope =
+        changed_scope(config, &repo.workdir().unwrap_or(repo_path).canonicalize()?)?;
+
+    let mut tree = SourceTree::new(
+        config
+            .path_alias_prefix()
+            .filter(|alias| !alias.is_empty())
+            .unwrap_or_else(|| repository_name(repo_path)),
+    );
+    let mut files_to_process = Vec::new();
+    for (relative_path, oid, is_symlink) in blobs {
+        let inclusion = if let Some(engine) = selection_engine.as_mut() {
+            engine.inclusion(&relative_path)
+        } else {
+            pattern_inclusion(
+                &relative_path,
+                &include_globset,
+                &exclude_globset,
+                &tree_only_globset,
+            )
+        };
+        let entry_match = inclusion.in_tree()
+            && package_scope
+                .as_ref()
+                .is_none_or(|scope| scope.contains(&relative_path))
+            && bazel_scope
+                .as_ref()
+                .is_none_or(|scope| scope.contains(&relative_path))
+            && symbol_scope
+                .as_ref()
+                .is_none_or(|scope| scope.contains(&relative_path))
+            && changed_scope
+                .as_ref()
+                .is_none_or(|scope| scope.contains(&relative_path))
+            && !config.fixture_policy.excludes(&relative_path);
+
+        if config.full_directory_tree || entry_match {
+            tree.add(&relative_path, false);
+        }
+        if !entry_match || inclusion != FileInclusion::Full || is_symlink {
+            continue;
+        }
+
+        let blob = repo.find_blob(oid)?;
+        let content = blob.content();
+        if is_binary(content) && config.binary_handling == BinaryHandling::Skip {
+            debug!("Skipped binary file: {}", relative_path.display());
+            continue;
+        }
+        files_to_process.push((relative_path, content.to_vec()));
+    }
+
+    // ~~~ Processing ~~~
+    let metadata = EntryMetadata {
+        is_dir: false,
+        is_symlink: false,
+    };
+    let files: Vec<FileEntry> = files_to_process
+        .par_iter()
+        .filter_map(|(relative_path, content)| {
+            // A revision has no absolute paths, only the alias applies
+            let file_path = match config.path_alias {
+                Some(_) => config.output_path(relative_path, relative_path),
+                None => relative_path.to_string_lossy().to_string(),
+            };
+            if is_binary(content) {
+                return binary_file_entry(file_path, content, metadata, None, config);
+            }
+            let mut entry =
+                build_file_entry(relative_path, file_path, content, metadata, None, config)?;
+            // The tree of a revision does not tell when a file last changed
+            if let Some(kind) = config.fixture_policy.stubs(relative_path) {
+                let stub = fixture_stub(kind, &String::from_utf8_lossy(content), None);
+                stub_file_entry(&mut entry, &stub, config);
+            }
+            Some(entry)
+        })
+        .collect();
+
+    // ~~~ Assembly ~~~
+    let tokens: HashMap<PathBuf, usize> = files
+        .iter()
+        .map(|file| (config.root_relative_path(&file.path), file.token_count))
+        .collect();
+    let tree = tree.render(&tokens, config);
+    let mut files = collapse_files(files, config);
+    sort_files(&mut files, config.sort_method);
+
+    Ok((tree, files))
+}
+
+/// Returns the name of a repository, without the `.git` suffix of bare repositories.
+///
+/// # Arguments
+///
+/// * `repo_path` - Path of the repository
+///
+/// # Returns
+///
+/// * `String` - The repository name
+pub fn repository_name(repo_path: &Path) -> String {
+    let name = crate::path::display_name(repo_path);
+    match name.strip_suffix(".git") {
+        Some(stripped) if !stripped.is_empty() => stripped.to_string(),
+        _ => name,
+    }
+}
@@ -0,0 +1,312 @@
+//! This module explains how each file of the codebase enters the prompt: embedded, listed
+//! in the tree only or left out, and why, so that a selection can be reviewed file by
+//! file, for instance from the quickfix list of an editor.
+//!
+//! The files left out by `.gitignore`, `.c2pignore` or for being hidden are not walked:
+//! the topmost ignored entry is reported instead, a directory ending with `/`.
+
+use crate::bazel::bazel_scope;
+use crate::binary::{BINARY_SAMPLE_SIZE, BinaryHandling, is_binary};
+use crate::configuration::Code2PromptConfig;
+use crate::filter::build_globset;
+use crate::git::changed_scope;
+use crate::path::{SymlinkPolicy, walk_builder};
+use crate::selection::{FileInclusion, SelectionEngine, pattern_inclusion};
+use crate::tags::symbol_scope;
+use crate::util::path_key;
+use crate::workspace::package_scope;
+use anyhow::Result;
+use ignore::WalkBuilder;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Why a file is included as it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanReason {
+    /// Selected by the include and exclude patterns
+    Patterns,
+    /// Selected by hand
+    UserIncluded,
+    /// Listed in the tree only by hand
+    UserTreeOnly,
+    /// Left out by hand
+    UserExcluded,
+    /// Matched by a tree-only pattern
+    TreeOnlyPattern,
+    /// Matched by an exclude pattern
+    ExcludePattern,
+    /// Matched by none of the include patterns
+    NotIncluded,
+    /// Outside the workspace packages selected
+    OutsidePackages,
+    /// Outside the sources of the Bazel targets selected
+    OutsideTargets,
+    /// Defining none of the symbols selected
+    OutsideSymbols,
+    /// Not changed, when only the changed files are included
+    Unchanged,
+    /// A test fixture, snapshot or golden file
+    Fixture,
+    /// A symbolic link, which is not followed
+    Symlink,
+    /// Ignored by `.gitignore` or `.c2pignore`, or hidden
+    Ignored,
+}
+
+impl fmt::Display for PlanReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanReason::Patterns => write!(f, "selected by the patterns"),
+            PlanReason::UserIncluded => write!(f, "selected by hand"),
+            PlanReason::UserTreeOnly => write!(f, "listed in the tree only by hand"),
+            PlanReason::UserExcluded => write!(f, "left out by hand"),
+            PlanReason::TreeOnlyPattern => write!(f, "matched by a tree-only pattern"),
+            PlanReason::ExcludePattern => write!(f, "matched by an exclude pattern"),
+            PlanReason::NotIncluded => write!(f, "matched by no include pattern"),
+            PlanReason::OutsidePackages => write!(f, "outside the selected packages"),
+            PlanReason::OutsideTargets => write!(f, "outside the selected Bazel targets"),
+            PlanReason::OutsideSymbols => write!(f, "defining none of the selected symbols"),
+            PlanReason::Unchanged => write!(f, "not changed"),
+            PlanReason::Fixture => write!(f, "test fixture"),
+            PlanReason::Symlink => write!(f, "symbolic link not followed"),
+            PlanReason::Ignored => write!(f, "ignored or hidden"),
+        }
+    }
+}
+
+/// How a file of the codebase is included, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlanEntry {
+    /// Path relative to the root, with forward slashes, ending with `/` for a directory
+    pub path: String,
+    pub inclusion: FileInclusion,
+    pub reason: PlanReason,
+    /// Why a file selected in full will not be embedded as expected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// Explains the inclusion of every file of the codebase, sorted by path.
+///
+/// # Arguments
+///
+/// * `config` - The configuration giving the patterns, scopes and traversal settings
+/// * `selection_engine` - Optional SelectionEngine holding the selections made by hand
+///
+/// # Returns
+///
+/// * `Result<Vec<PlanEntry>>` - One entry per file, and per topmost ignored entry
+pub fn inclusion_plan(
+    config: &Code2PromptConfig,
+    selection_engine: Option<&SelectionEngine>,
+) -> Result<Vec<PlanEntry>> {
+    let root = config.path.canonicalize()?;
+    let include_globset = build_globset(&config.include_patterns);
+    let exclude_globset = build_globset(&config.exclude_patterns);
+    let tree_only_globset = build_globset(&config.tree_only_patterns);
+    let package_scope = package_scope(config, None)?;
+    let bazel_scope = bazel_scope(config, None)?;
+    let symbol_scope = symbol_scope(config)?;
+    let changed_scope = changed_scope(config, &root)?;
+
+    let mut plan = Vec::new();
+    let mut walked = HashSet::new();
+    for entry in walk_builder(&root, config).build().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        walked.insert(path.to_path_buf());
+        let Ok(relative_path) = path.strip_prefix(&root) else {
+            continue;
+        };
+        if !path.is_file() {
+            continue;
+        }
+
+        let user_inclusion =
+            selection_engine.and_then(|engine| engine.user_inclusion(relative_path));
+        let (mut inclusion, mut reason) = match user_inclusion {
+            Some(FileInclusion::Full) => (FileInclusion::Full, PlanReason::UserIncluded),
+            Some(FileInclusion::TreeOnly) => (FileInclusion::TreeOnly, PlanReason::UserTreeOnly),
+            Some(FileInclusion::Excluded) => (FileInclusion::Excluded, PlanReason::UserExcluded),
+            None => match pattern_inclusion(
+                relative_path,
+                &include_globset,
+                &exclude_globset,
+                &tree_only_globset,
+            ) {
+                FileInclusion::Full => (FileInclusion::Full, PlanReason::Patterns),
+                FileInclusion::TreeOnly => (FileInclusion::TreeOnly, PlanReason::TreeOnlyPattern),
+                FileInclusion::Excluded if exclude_globset.is_match(relative_path) => {
+                    (FileInclusion::Excluded, PlanReason::ExcludePattern)
+                }
+                FileInclusion::Excluded => (FileInclusion::Excluded, PlanReason::NotIncluded),
+            },
+        };
+
+        // The scopes narrow the selection in the order of the traversal
+        if inclusion.in_tree() {
+            let out_of_scope = if package_scope
+                .as_ref()
+                .is_some_and(|scope| !scope.contains(relative_path))
+            {
+                Some(PlanReason::OutsidePackages)
+            } else if bazel_scope
+                .as_ref()
+                .is_some_and(|scope| !scope.contains(relative_path))
+            {
+                Some(PlanReason::OutsideTargets)
+            } else if symbol_scope
+                .as_ref()
+                .is_some_and(|scope| !scope.contains(relative_path))
+            {
+                Some(PlanReason::OutsideSymbols)
+            } else if changed_scope
+                .as_ref()
+                .is_some_and(|scope| !scope.contains(relative_path))
+            {
+                Some(PlanReason::Unchanged)
+            } else if config.fixture_policy.excludes(relative_path) {
+                Some(PlanReason::Fixture)
+            } else {
+                None
+            };
+            if let Some(out_of_scope) = out_of_scope {
+                inclusion = FileInclusion::Excluded;
+                reason = out_of_scope;
+            }
+        }
+        // Symbolic links not followed are listed in the tree, but not read
+        if inclusion == FileInclusion::Full
+            && config.symlink_policy == SymlinkPolicy::Skip
+            && entry.path_is_symlink()
+        {
+            inclusion = FileInclusion::TreeOnly;
+            reason = PlanReason::Symlink;
+        }
+
+        let warning = if inclusion == FileInclusion::Full {
+            content_warning(path, config)
+        } else {
+            None
+        };
+        plan.push(PlanEntry {
+            path: display_path(relative_path, false),
+            inclusion,
+            reason,
+            warning,
+        });
+    }
+
+    plan.extend(ignored_entries(&root, config, walked));
+    plan.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(plan)
+}
+
+/// Formats the plan as quickfix lines, `path:1:1: inclusion: reason`, which the default
+/// `errorformat` of Vim and Neovim reads. The warnings are given their own lines.
+///
+/// # Arguments
+///
+/// * `plan` - The plan, as given by [`inclusion_plan`]
+/// * `root` - The root of the codebase, prefixed to the paths
+pub fn format_quickfix(plan: &[PlanEntry], root: &Path) -> String {
+    let mut output = String::new();
+    for entry in plan {
+        let path = root.join(&entry.path);
+        let inclusion = match entry.inclusion {
+            FileInclusion::Full => "included",
+            FileInclusion::TreeOnly => "tree-only",
+            FileInclusion::Excluded => "excluded",
+        };
+        output.push_str(&format!(
+            "{}:1:1: {}: {}\n",
+            path.display(),
+            inclusion,
+            entry.reason
+        ));
+        if let Some(warning) = &entry.warning {
+            output.push_str(&format!("{}:1:1: warning: {}\n", path.display(), warning));
+        }
+    }
+    output
+}
+
+/// Why a file selected in full will not be embedded as expected, from its first bytes.
+fn content_warning(path: &Path, config: &Code2PromptConfig) -> Option<String> {
+    let mut sample = Vec::with_capacity(BINARY_SAMPLE_SIZE);
+    let read = std::fs::File::open(path).and_then(|file| {
+        file.take(BINARY_SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)
+    });
+    match read {
+        Err(e) => Some(format!("unreadable, left out: {}", e)),
+        Ok(0) => Some("empty, left out".to_string()),
+        Ok(_) if is_binary(&sample) => match config.binary_handling {
+            BinaryHandling::Skip => Some("binary, left out".to_string()),
+            BinaryHandling::PathOnly => Some("binary, listed without its content".to_string()),
+            BinaryHandling::Base64 => Some("binary, embedded in base64".to_string()),
+        },
+        Ok(_) => None,
+    }
+}
+
+/// The topmost entries the traversal skips, without descending into ignored directories.
+fn ignored_entries(
+    root: &Path,
+    config: &Code2PromptConfig,
+    walked: HashSet<PathBuf>,
+) -> Vec<PlanEntry> {
+    let ignored = Arc::new(Mutex::new(Vec::new()));
+    let filter_ignored = ignored.clone();
+
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(false)
+        .git_ignore(false)
+        .git_global(false)
+        .git_exclude(false)
+        .ignore(false)
+        .parents(false)
+        .follow_links(config.symlink_policy != SymlinkPolicy::Skip)
+        .filter_entry(move |entry| {
+            let path = entry.path();
+            if walked.contains(path) {
+                return true;
+            }
+            if entry.file_name() != ".git"
+                && let Ok(mut ignored) = filter_ignored.lock()
+            {
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                ignored.push((path.to_path_buf(), is_dir));
+            }
+            false
+        });
+    for _ in builder.build() {}
+
+    let ignored = ignored
+        .lock()
+        .map(|ignored| ignored.clone())
+        .unwrap_or_default();
+    ignored
+        .into_iter()
+        .filter_map(|(path, is_dir)| {
+            let relative_path = path.strip_prefix(root).ok()?;
+            Some(PlanEntry {
+                path: display_path(relative_path, is_dir),
+                inclusion: FileInclusion::Excluded,
+                reason: PlanReason::Ignored,
+                warning: None,
+            })
+        })
+        .collect()
+}
+
+/// A path relative to the root with forward slashes, a directory ending with `/`
+fn display_path(relative_path: &Path, is_dir: bool) -> String {
+    let path = path_key(relative_path);
+    if is_dir { format!("{}/", path) } else { path }
+}
@@ -0,0 +1,206 @@
+//! This module describes the source files for the templates: their language, their
+//! line counts and whether they are tests.
+//!
+//! The description is a heuristic from the extension and the path of a file. Comments are
+//! recognized line by line from the comment syntax of the language: a line counts as a
+//! comment when it holds nothing but comments, and strings holding comment markers are
+//! not told apart.
+
+use crate::util::path_key;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The language, the line counts and the test status of a file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SourceStats {
+    /// The name of the language (`Rust`, `TypeScript`), or else the extension of the file
+    pub language: String,
+    /// Lines of the file
+    pub line_count: usize,
+    /// Lines holding nothing but whitespace
+    pub blank_lines: usize,
+    /// Lines holding nothing but comments
+    pub comment_lines: usize,
+    /// Whether the path of the file is that of a test
+    pub is_test: bool,
+}
+
+impl SourceStats {
+    /// Lines holding code, neither blank nor comments only
+    pub fn code_lines(&self) -> usize {
+        self.line_count - self.blank_lines - self.comment_lines
+    }
+}
+
+/// The comment syntax of a language
+struct CommentSyntax {
+    line: &'static [&'static str],
+    block: Option<(&'static str, &'static str)>,
+}
+
+const C_LIKE: CommentSyntax = CommentSyntax {
+    line: &["//"],
+    block: Some(("/*", "*/")),
+};
+const HASH: CommentSyntax = CommentSyntax {
+    line: &["#"],
+    block: None,
+};
+const DASH: CommentSyntax = CommentSyntax {
+    line: &["--"],
+    block: None,
+};
+const SQL: CommentSyntax = CommentSyntax {
+    line: &["--"],
+    block: Some(("/*", "*/")),
+};
+const MARKUP: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: Some(("<!--", "-->")),
+};
+const LISP: CommentSyntax = CommentSyntax {
+    line: &[";"],
+    block: None,
+};
+const NONE: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: None,
+};
+
+/// The name and comment syntax of the language of an extension.
+fn language(extension: &str) -> Option<(&'static str, CommentSyntax)> {
+    Some(match extension.to_lowercase().as_str() {
+        "rs" => ("Rust", C_LIKE),
+        "go" => ("Go", C_LIKE),
+        "java" => ("Java", C_LIKE),
+        "kt" | "kts" => ("Kotlin", C_LIKE),
+        "scala" => ("Scala", C_LIKE),
+        "swift" => ("Swift", C_LIKE),
+        "c" | "h" => ("C", C_LIKE),
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => ("C++", C_LIKE),
+        "cs" => ("C#", C_LIKE),
+        "dart" => ("Dart", C_LIKE),
+        "php" => ("PHP", C_LIKE),
+        "js" | "mjs" | "cjs" | "jsx" => ("JavaScript", C_LIKE),
+        "ts" | "mts" | "cts" | "tsx" => ("TypeScript", C_LIKE),
+        "css" => ("CSS", C_LIKE),
+        "scss" | "less" => ("SCSS", C_LIKE),
+        "proto" => ("Protocol Buffers", C_LIKE),
+        "py" | "pyi" => ("Python", HASH),
+        "rb" => ("Ruby", HASH),
+        "sh" | "bash" | "zsh" => ("Shell", HASH),
+        "pl" | "pm" => ("Perl", HASH),
+        "r" => ("R", HASH),
+        "ex" | "exs" => ("Elixir", HASH),
+        "toml" => ("TOML", HASH),
+        "yaml" | "yml" => ("YAML", HASH),
+        "tf" | "hcl" => ("HCL", C_LIKE),
+        "nix" => ("Nix", HASH),
+        "lua" => ("Lua", DASH),
+        "hs" => ("Haskell", DASH),
+        "sql" => ("SQL", SQL),
+        "html" | "htm" => ("HTML", MARKUP),
+        "xml" | "svg" => ("XML", MARKUP),
+        "vue" => ("Vue", MARKUP),
+        "md" | "markdown" => ("Markdown", MARKUP),
+        "clj" | "cljs" | "el" | "lisp" => ("Lisp", LISP),
+        "json" => ("JSON", NONE),
+        "txt" => ("Text", NONE),
+        _ => return None,
+    })
+}
+
+/// Describes a file from its path and content.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file relative to the root, giving its language and telling
+///   whether it is a test
+/// * `code` - The content of the file
+///
+/// # Returns
+///
+/// * `SourceStats` - The language, the line counts and the test status of the file
+pub fn source_stats(path: &Path, code: &str) -> SourceStats {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    let (language, syntax) = match language(extension) {
+        Some((name, syntax)) => (name.to_string(), syntax),
+        None if extension.is_empty() => ("Text".to_string(), NONE),
+        None => (extension.to_lowercase(), NONE),
+    };
+
+    let mut stats = SourceStats {
+        language,
+        is_test: is_test_path(&path_key(path)),
+        ..SourceStats::default()
+    };
+    let mut in_block = false;
+    for line in code.lines() {
+        stats.line_count += 1;
+        let line = line.trim();
+        if line.is_empty() && !in_block {
+            stats.blank_lines += 1;
+        } else if comment_only(line, &syntax, &mut in_block) {
+            stats.comment_lines += 1;
+        }
+    }
+    stats
+}
+
+/// Whether a trimmed line holds nothing but comments, following the block comments opened
+/// and closed on it.
+fn comment_only(line: &str, syntax: &CommentSyntax, in_block: &mut bool) -> bool {
+    let mut rest = line;
+    let mut code = false;
+    while !rest.is_empty() {
+        if *in_block && let Some((_, close)) = syntax.block {
+            match rest.find(close) {
+                Some(end) => {
+                    *in_block = false;
+                    rest = rest[end + close.len()..].trim_start();
+                }
+                None => return !code,
+            }
+        } else if syntax.line.iter().any(|marker| rest.starts_with(marker)) {
+            return !code;
+        } else if let Some((open, _)) = syntax.block
+            && rest.starts_with(open)
+        {
+            *in_block = true;
+            rest = &rest[open.len()..];
+        } else {
+            // Code, up to a block comment opened later on the line
+            code = true;
+            match syntax.block.and_then(|(open, _)| rest.find(open)) {
+                Some(start) => rest = &rest[start..],
+                None => return false,
+            }
+        }
+    }
+    !code
+}
+
+/// Whether a path is that of a test: in a `tests`, `test`, `__tests__` or `spec`
+/// directory, or named as a test (`test_x.py`, `x_test.go`, `x.test.ts`, `x.spec.js`,
+/// `XTest.java`, `conftest.py`).
+///
+/// # Arguments
+///
+/// * `path` - The path of the file relative to the root, its separators being `/`
+pub fn is_test_path(path: &str) -> bool {
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("");
+    let directories = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    directories
+        .split('/')
+        .any(|segment| matches!(segment, "tests" | "test" | "__tests__" | "spec"))
+        || stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with(".test")
+        || stem.ends_with(".spec")
+        || stem == "conftest"
+        || (stem.len() > 4 && (stem.ends_with("Test") || stem.ends_with("Tests")))
+}
@@ -1,13 +1,55 @@
 //! Core library for code2prompt.
+pub mod architecture;
+pub mod bazel;
+pub mod binary;
+pub mod budget;
 pub mod builtin_templates;
+pub mod chunk;
+pub mod collapse;
+pub mod compression;
 pub mod configuration;
+pub mod conversation;
+pub mod doc_coverage;
+pub mod duplication;
+pub mod environment;
+pub mod eval;
 pub mod file_processor;
 pub mod filter;
+pub mod fixture;
+pub mod frontend;
 pub mod git;
+pub mod git_tree;
+pub mod inclusion_plan;
+pub mod language;
+pub mod line_range;
+pub mod lsp;
+pub mod manifest;
+pub mod metadata;
+#[cfg(feature = "parquet")]
+pub mod parquet;
 pub mod path;
+pub mod placement;
+pub mod post_process;
+pub mod progress;
+pub mod prompt_cache;
+pub mod remote;
+pub mod response;
+pub mod roots;
 pub mod selection;
+pub mod selection_import;
+#[cfg(feature = "sentencepiece")]
+pub mod sentencepiece;
 pub mod session;
+pub mod snapshot;
 pub mod sort;
+pub mod split;
+pub mod summarize;
+pub mod tags;
 pub mod template;
+pub mod template_helpers;
 pub mod tokenizer;
+pub mod tour;
+pub mod tree;
 pub mod util;
+pub mod variants;
+pub mod workspace;
@@ -0,0 +1,150 @@
+//! This module handles the line ranges of files, so that only some lines of a file are
+//! embedded in the prompt.
+//!
+//! Ranges are given with the file in an include pattern, as `src/lib.rs:100-250`, or
+//! through [`crate::session::Code2PromptSession::include_file_range`].
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A range of lines of a file, from `start` to `end` included, numbered from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    /// Creates a range, or fails if it is empty or starts at 0.
+    pub fn new(start: usize, end: usize) -> Result<Self> {
+        if start == 0 || end < start {
+            return Err(anyhow!("Invalid line range {}-{}", start, end));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl fmt::Display for LineRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl FromStr for LineRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| anyhow!("Invalid line range '{}', expected START-END", s))?;
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<usize>()
+                .map_err(|_| anyhow!("Invalid line range '{}', expected START-END", s))
+        };
+        Self::new(parse(start)?, parse(end)?)
+    }
+}
+
+impl TryFrom<String> for LineRange {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<LineRange> for String {
+    fn from(range: LineRange) -> Self {
+        range.to_string()
+    }
+}
+
+/// Splits an include pattern into its path and its line range, if it ends with one.
+///
+/// # Arguments
+///
+/// * `pattern` - The pattern, such as `src/lib.rs:100-250` or `src/**/*.rs`
+///
+/// # Returns
+///
+/// * `(&str, Option<LineRange>)` - The pattern without its range, and the range
+pub fn split_range_pattern(pattern: &str) -> (&str, Option<LineRange>) {
+    match pattern.rsplit_once(':') {
+        Some((path, range)) if !path.is_empty() => match range.parse() {
+            Ok(range) => (path, Some(range)),
+            Err(_) => (pattern, None),
+        },
+        _ => (pattern, None),
+    }
+}
+
+/// The line ranges given for a file by the include patterns, sorted.
+///
+/// # Arguments
+///
+/// * `patterns` - The include patterns
+/// * `relative_path` - The path of the file, relative to the root
+pub fn pattern_line_ranges(patterns: &[String], relative_path: &Path) -> Vec<LineRange> {
+    let mut ranges: Vec<LineRange> = patterns
+        .iter()
+        .filter_map(|pattern| match split_range_pattern(pattern) {
+            (path, Some(range)) if Path::new(path.trim_start_matches("./")) == relative_path => {
+                Some(range)
+            }
+            _ => None,
+        })
+        .collect();
+    ranges.sort();
+    ranges
+}
+
+/// Keeps the lines of the code in the ranges, marking the lines left out between them.
+///
+/// # Arguments
+///
+/// * `code` - The content of the file
+/// * `ranges` - The sorted ranges of the lines to keep
+/// * `line_numbers` - Whether to number the lines with their number in the file
+///
+/// # Returns
+///
+/// * `String` - The kept lines
+pub fn select_lines(code: &str, ranges: &[LineRange], line_numbers: bool) -> String {
+    let lines: Vec<&str> = code.lines().collect();
+    let mut selected = Vec::new();
+    let mut next_line = 1;
+    for range in ranges {
+        let start = range.start.max(next_line);
+        let end = range.end.min(lines.len());
+        if start > end {
+            continue;
+        }
+        if next_line > 1 && start > next_line {
+            selected.push("...".to_string());
+        }
+        for number in start..=end {
+            let line = lines[number - 1];
+            selected.push(if line_numbers {
+                format!("{:4} | {}", number, line)
+            } else {
+                line.to_string()
+            });
+        }
+        next_line = end + 1;
+    }
+    selected.join("\n")
+}
+
+/// The ranges of a file as shown in its header, as `100-250, 300-320`.
+pub fn format_ranges(ranges: &[LineRange]) -> String {
+    ranges
+        .iter()
+        .map(LineRange::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,431 @@
+//! This module asks a language server for the symbols of the files, as a precise
+//! alternative to the lexer behind signature compression.
+//!
+//! The servers are configured by file extension in `lsp_servers`, such as
+//! `rs = "rust-analyzer"`. A server is started in the root of the codebase and queried
+//! over the Language Server Protocol for the `textDocument/documentSymbol` of each file
+//! compressed to its signatures: the bodies of the functions and methods it reports are
+//! replaced with `...`, whatever the language.
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{Receiver, channel};
+use std::time::Duration;
+
+/// How long a server may take to answer a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Symbol kinds of the LSP whose range holds a body: method, constructor and function
+const FUNCTION_KINDS: &[u32] = &[6, 9, 12];
+
+/// A position in a document, its character counted in UTF-16 code units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct LspPosition {
+    pub line: usize,
+    pub character: usize,
+}
+
+/// A range of a document, its end excluded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct LspRange {
+    pub start: LspPosition,
+    pub end: LspPosition,
+}
+
+/// A symbol of a document, as reported by `textDocument/documentSymbol`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspSymbol {
+    pub name: String,
+    pub kind: u32,
+    /// The whole symbol, its body included
+    pub range: LspRange,
+    /// The name of the symbol
+    pub selection_range: LspRange,
+    #[serde(default)]
+    pub children: Vec<LspSymbol>,
+}
+
+/// A symbol of the flat `SymbolInformation` form some servers answer with
+#[derive(Deserialize)]
+struct SymbolInformation {
+    name: String,
+    kind: u32,
+    location: SymbolLocation,
+}
+
+#[derive(Deserialize)]
+struct SymbolLocation {
+    range: LspRange,
+}
+
+/// The LSP language identifier of a file extension.
+///
+/// # Arguments
+///
+/// * `extension` - The extension of the file, without the dot
+pub fn language_id(extension: &str) -> String {
+    match extension.to_lowercase().as_str() {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "javascriptreact",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "typescriptreact",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "cpp",
+        "cs" => "csharp",
+        "kt" | "kts" => "kotlin",
+        "rb" => "ruby",
+        "sh" | "bash" => "shellscript",
+        other => return other.to_string(),
+    }
+    .to_string()
+}
+
+/// The `file://` URI of an absolute path.
+pub fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    let path = path.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Frames a JSON-RPC message with its `Content-Length` header.
+pub fn encode_message(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body).into_bytes()
+}
+
+/// Reads a JSON-RPC message framed with its `Content-Length` header.
+///
+/// # Returns
+///
+/// * `Result<Option<Value>>` - The message, or `None` at the end of the stream
+pub fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = Some(value.trim().parse::<usize>()?);
+        }
+    }
+    let length = length.context("Message without a Content-Length header")?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// A language server, started for the root of a codebase and shut down when dropped.
+pub struct LspClient {
+    child: Child,
+    stdin: ChildStdin,
+    messages: Receiver<Value>,
+    next_id: u64,
+}
+
+impl std::fmt::Debug for LspClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LspClient")
+            .field("pid", &self.child.id())
+            .finish()
+    }
+}
+
+impl LspClient {
+    /// Starts a language server and initializes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command starting the server on its standard input and output,
+    ///   its words separated by spaces
+    /// * `root` - The root of the codebase, the workspace of the server
+    pub fn start(command: &str, root: &Path) -> Result<Self> {
+        let mut words = command.split_whitespace();
+        let program = words.next().context("Empty language server command")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to start the language server `{}`", command))?;
+        let stdin = child.stdin.take().context("No standard input")?;
+        let stdout = child.stdout.take().context("No standard output")?;
+
+        // The server is read on a thread, so that a silent server times out
+        let (sender, messages) = channel();
+        std::thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            while let Ok(Some(message)) = read_message(&mut reader) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut client = Self {
+            child,
+            stdin,
+            messages,
+            next_id: 1,
+        };
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        client.request(
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "rootUri": file_uri(&root),
+                "workspaceFolders": [{ "uri": file_uri(&root), "name": "root" }],
+                "capabilities": {
+                    "textDocument": {
+                        "documentSymbol": { "hierarchicalDocumentSymbolSupport": true }
+                    }
+                }
+            }),
+        )?;
+        client.notify("initialized", json!({}))?;
+        Ok(client)
+    }
+
+    /// Asks the server for the symbols of a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The absolute path of the file
+    /// * `text` - The content of the file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<LspSymbol>>` - The symbols, nested in their parents
+    pub fn document_symbols(&mut self, path: &Path, text: &str) -> Result<Vec<LspSymbol>> {
+        let uri = file_uri(path);
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": language_id(extension),
+                    "version": 1,
+                    "text": text,
+                }
+            }),
+        )?;
+        let result = self.request(
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": uri } }),
+        );
+        self.notify(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": uri } }),
+        )?;
+        parse_symbols(result?)
+    }
+
+    /// Sends a request and waits for its response, answering the requests of the server
+    /// meanwhile.
+    fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+        loop {
+            let message = self
+                .messages
+                .recv_timeout(REQUEST_TIMEOUT)
+                .map_err(|_| anyhow!("The language server did not answer `{}`", method))?;
+            match (message.get("id"), message.get("method")) {
+                (Some(server_id), Some(server_method)) => {
+                    let result = server_request_result(server_method, &message);
+                    let response = json!({ "jsonrpc": "2.0", "id": server_id, "result": result });
+                    self.send(&response)?;
+                }
+                (Some(response_id), None) if response_id.as_u64() == Some(id) => {
+                    if let Some(error) = message.get("error") {
+                        bail!("The language server failed `{}`: {}", method, error);
+                    }
+                    return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+                }
+                // Notifications and stale responses
+                _ => {}
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    fn send(&mut self, message: &Value) -> Result<()> {
+        self.stdin.write_all(&encode_message(message))?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        let _ = self.request("shutdown", Value::Null);
+        let _ = self.notify("exit", Value::Null);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The answer to a request of the server: no configuration for each requested item, and
+/// an empty result for anything else, such as progress tokens and registrations.
+fn server_request_result(method: &Value, message: &Value) -> Value {
+    if method == "workspace/configuration" {
+        let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+        return Value::Array(vec![Value::Null; items]);
+    }
+    Value::Null
+}
+
+/// Reads the symbols of a `textDocument/documentSymbol` response, in either of its forms.
+fn parse_symbols(result: Value) -> Result<Vec<LspSymbol>> {
+    let Value::Array(items) = result else {
+        return Ok(Vec::new());
+    };
+    if items.iter().any(|item| item.get("location").is_some()) {
+        let symbols: Vec<SymbolInformation> = serde_json::from_value(Value::Array(items))?;
+        return Ok(symbols
+            .into_iter()
+            .map(|symbol| LspSymbol {
+                name: symbol.name,
+                kind: symbol.kind,
+                range: symbol.location.range,
+                selection_range: symbol.location.range,
+                children: Vec::new(),
+            })
+            .collect());
+    }
+    Ok(serde_json::from_value(Value::Array(items))?)
+}
+
+/// The byte offset of a position in the code, its character counted in UTF-16 code units.
+/// Positions past the end of a line or of the code are clamped.
+fn byte_offset(code: &str, position: LspPosition) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match code[line_start..].find('\n') {
+            Some(index) => line_start += index + 1,
+            None => return code.len(),
+        }
+    }
+    let line_end = code[line_start..]
+        .find('\n')
+        .map_or(code.len(), |index| line_start + index);
+    let mut units = 0;
+    for (index, c) in code[line_start..line_end].char_indices() {
+        if units >= position.character {
+            return line_start + index;
+        }
+        units += c.len_utf16();
+    }
+    line_end
+}
+
+/// Replaces the bodies of the functions and methods among the symbols with `...`, keeping
+/// everything else: the signatures, the doc comments and the type definitions.
+///
+/// A body is the block between the braces following the name of the symbol, or else the
+/// lines under a header ending with `:`, as in Python.
+///
+/// # Arguments
+///
+/// * `code` - The content of the file
+/// * `symbols` - The symbols of the file, as reported by the language server
+///
+/// # Returns
+///
+/// * `String` - The code without the bodies of the functions
+pub fn compress_with_symbols(code: &str, symbols: &[LspSymbol]) -> String {
+    // The byte ranges of the bodies to replace, with their replacement
+    let mut bodies: Vec<(usize, usize, String)> = Vec::new();
+    let mut pending: Vec<&LspSymbol> = symbols.iter().collect();
+    while let Some(symbol) = pending.pop() {
+        if !FUNCTION_KINDS.contains(&symbol.kind) {
+            pending.extend(&symbol.children);
+            continue;
+        }
+        // Nested functions go with the body of theirs
+        let name_end = byte_offset(code, symbol.selection_range.end);
+        let end = byte_offset(code, symbol.range.end);
+        if let Some(body) = function_body(code, name_end, end) {
+            bodies.push(body);
+        }
+    }
+    bodies.sort_by_key(|(start, _, _)| *start);
+
+    let mut output = String::with_capacity(code.len());
+    let mut copied = 0;
+    for (start, end, replacement) in bodies {
+        // Symbols overlapping a body already replaced
+        if start < copied {
+            continue;
+        }
+        output.push_str(&code[copied..start]);
+        output.push_str(&replacement);
+        copied = end;
+    }
+    output.push_str(&code[copied..]);
+    output
+}
+
+/// The body of a function whose name ends at `name_end` and whose range ends at `end`.
+fn function_body(code: &str, name_end: usize, end: usize) -> Option<(usize, usize, String)> {
+    let symbol = code.get(name_end..end)?;
+    let trimmed = symbol.trim_end();
+    if trimmed.ends_with('}')
+        && let Some(open) = symbol.find('{')
+    {
+        return Some((
+            name_end + open,
+            name_end + trimmed.len(),
+            "{ ... }".to_string(),
+        ));
+    }
+
+    // Indented body, after the line of the header ending with `:`
+    let mut offset = name_end;
+    let mut lines = symbol.split_inclusive('\n');
+    for line in lines.by_ref() {
+        offset += line.len();
+        if line.trim_end().ends_with(':') {
+            break;
+        }
+    }
+    let body_start = offset;
+    let first = lines.find(|line| !line.trim().is_empty())?;
+    let indent: String = first
+        .chars()
+        .take_while(|c| c.is_whitespace() && *c != '\n')
+        .collect();
+    let body_end = name_end + trimmed.len();
+    (body_end > body_start).then(|| (body_start, body_end, format!("{}...", indent)))
+}
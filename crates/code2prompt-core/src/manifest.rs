@@ -0,0 +1,80 @@
+//! This module keeps a manifest of the content hash of every included file,
+//! so that consecutive runs can tell exactly which files changed.
+
+use crate::path::FileEntry;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Content hash of each included file, keyed by path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileManifest {
+    pub files: BTreeMap<String, String>,
+}
+
+/// The difference between a set of files and a manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FileChanges {
+    /// Files whose content changed.
+    pub changed: Vec<String>,
+    /// Files missing from the manifest.
+    pub added: Vec<String>,
+    /// Files whose content is identical.
+    pub unchanged: Vec<String>,
+    /// Files in the manifest that are no longer included.
+    pub removed: Vec<String>,
+}
+
+impl FileManifest {
+    /// Builds the manifest of the given files.
+    pub fn from_files(files: &[FileEntry]) -> Self {
+        Self {
+            files: files
+                .iter()
+                .map(|file| (file.path.clone(), file.content_hash.clone()))
+                .collect(),
+        }
+    }
+
+    /// Loads a manifest from a JSON file. A missing file yields an empty manifest.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read manifest {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid manifest {}", path.display()))
+    }
+
+    /// Saves the manifest as a JSON file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write manifest {}", path.display()))
+    }
+
+    /// Compares the files with the manifest.
+    pub fn diff(&self, files: &[FileEntry]) -> FileChanges {
+        let mut changes = FileChanges::default();
+        for file in files {
+            match self.files.get(&file.path) {
+                None => changes.added.push(file.path.clone()),
+                Some(hash) if *hash == file.content_hash => {
+                    changes.unchanged.push(file.path.clone())
+                }
+                Some(_) => changes.changed.push(file.path.clone()),
+            }
+        }
+        let current: HashSet<&str> = files.iter().map(|file| file.path.as_str()).collect();
+        changes.removed = self
+            .files
+            .keys()
+            .filter(|path| !current.contains(path.as_str()))
+            .cloned()
+            .collect();
+        changes
+    }
+}
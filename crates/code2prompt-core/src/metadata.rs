@@ -0,0 +1,262 @@
+//! This module exports the metadata of the included files, without their content, as a
+//! CSV or Parquet table for analytics dashboards tracking the footprint of a codebase and
+//! of its prompts over time.
+//!
+//! Each row gives the path, the language, the size and token count of a file, its owners
+//! from the `CODEOWNERS` file and its last change in the git history. Parquet files need
+//! the `parquet` feature.
+
+use crate::configuration::Code2PromptConfig;
+use crate::git::get_last_changes;
+#[cfg(feature = "parquet")]
+use crate::parquet::{Column, write_parquet};
+use crate::path::{FileEntry, fence_language};
+use crate::util::path_key;
+use anyhow::{Context, Result, bail};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::Serialize;
+use std::path::Path;
+
+/// Locations of the `CODEOWNERS` file, the first one found being used
+pub const CODEOWNERS_LOCATIONS: &[&str] = &[
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+/// Format of the metadata export, chosen from the extension of the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataFormat {
+    Csv,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl MetadataFormat {
+    /// The format of a file, from its `.csv` or `.parquet` extension.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("csv") => Ok(MetadataFormat::Csv),
+            #[cfg(feature = "parquet")]
+            Some("parquet") => Ok(MetadataFormat::Parquet),
+            #[cfg(not(feature = "parquet"))]
+            Some("parquet") => bail!(
+                "Cannot write {}: code2prompt was built without the parquet feature",
+                path.display()
+            ),
+            _ => bail!(
+                "Unknown metadata format for {}, use a .csv or .parquet file",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// The metadata of a file, a row of the export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileMetadata {
+    /// Path of the file, relative to the root
+    pub path: String,
+    /// Language tag of the file, as in its code fence
+    pub language: String,
+    /// Size of the file on disk, in bytes
+    pub size_bytes: u64,
+    pub tokens: u64,
+    /// Owners of the file in `CODEOWNERS`, separated by spaces
+    pub owners: Option<String>,
+    /// Date of the last commit changing the file, as `YYYY-MM-DD`
+    pub last_commit_date: Option<String>,
+    /// Time of the last commit changing the file, in seconds since the Unix epoch
+    pub last_commit_timestamp: Option<u64>,
+    pub last_commit_author: Option<String>,
+}
+
+/// Rules of a `CODEOWNERS` file, the last matching rule giving the owners of a file
+#[derive(Debug, Clone, Default)]
+pub struct CodeOwners {
+    rules: Vec<(Vec<GlobMatcher>, String)>,
+}
+
+impl CodeOwners {
+    /// Loads the `CODEOWNERS` file of a repository, empty if there is none.
+    pub fn load(root: &Path) -> Self {
+        CODEOWNERS_LOCATIONS
+            .iter()
+            .find_map(|location| std::fs::read_to_string(root.join(location)).ok())
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// Parses the rules of a `CODEOWNERS` file. Rules with an invalid pattern are skipped.
+    pub fn parse(content: &str) -> Self {
+        let rules = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?;
+                let owners = fields
+                    .take_while(|field| !field.starts_with('#'))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let matchers = owner_globs(pattern)
+                    .iter()
+                    .map(|glob| {
+                        GlobBuilder::new(glob)
+                            .literal_separator(true)
+                            .build()
+                            .map(|glob| glob.compile_matcher())
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok()?;
+                Some((matchers, owners))
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// The owners of a file, `None` if no rule matches or the matching rule has no owner.
+    pub fn owners_of(&self, path: &str) -> Option<&str> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(matchers, _)| matchers.iter().any(|matcher| matcher.is_match(path)))
+            .map(|(_, owners)| owners.as_str())
+            .filter(|owners| !owners.is_empty())
+    }
+}
+
+/// The globs matching the files of a `CODEOWNERS` pattern, which follows the `.gitignore`
+/// rules: a pattern without a slash matches at any depth, a leading slash anchors it to the
+/// root, and a directory matches everything below it. A trailing `/*` only matches the
+/// files directly in the directory.
+fn owner_globs(pattern: &str) -> Vec<String> {
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    let directory_only = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    let base = if anchored || pattern.starts_with("**") {
+        pattern.to_string()
+    } else {
+        format!("**/{}", pattern)
+    };
+    let mut globs = Vec::new();
+    if !base.ends_with("/*") {
+        globs.push(format!("{}/**", base));
+    }
+    if !directory_only {
+        globs.push(base);
+    }
+    globs
+}
+
+/// Collects the metadata of the files. The owners and last changes are left empty when
+/// there is no `CODEOWNERS` file or no git repository.
+///
+/// # Arguments
+///
+/// * `files` - The included files
+/// * `config` - The configuration, giving the root, the languages and the repository
+///
+/// # Returns
+///
+/// * `Vec<FileMetadata>` - The metadata of each file, in the order of the files
+pub fn collect_file_metadata(files: &[FileEntry], config: &Code2PromptConfig) -> Vec<FileMetadata> {
+    let repo_path = config.git_dir.as_deref().unwrap_or(&config.path);
+    let owners = CodeOwners::load(repo_path);
+    let paths: Vec<String> = files
+        .iter()
+        .map(|file| path_key(&config.root_relative_path(&file.path)))
+        .collect();
+    let last_changes = get_last_changes(repo_path, &paths).unwrap_or_else(|e| {
+        log::debug!("No last changes for the metadata export: {:#}", e);
+        Default::default()
+    });
+
+    files
+        .iter()
+        .zip(paths)
+        .map(|(file, path)| {
+            let last_change = last_changes.get(&path);
+            FileMetadata {
+                language: fence_language(&file.extension, config).to_string(),
+                size_bytes: std::fs::metadata(config.path.join(&path))
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(file.code.len() as u64),
+                tokens: file.token_count as u64,
+                owners: owners.owners_of(&path).map(str::to_string),
+                last_commit_date: last_change.map(|change| change.date.clone()),
+                last_commit_timestamp: last_change.map(|change| change.timestamp),
+                last_commit_author: last_change.map(|change| change.author.clone()),
+                path,
+            }
+        })
+        .collect()
+}
+
+/// Writes the metadata of the files as CSV or Parquet, depending on the extension of the file.
+///
+/// # Arguments
+///
+/// * `rows` - The metadata of the files
+/// * `path` - The file to write, ending in `.csv` or `.parquet`
+///
+/// # Returns
+///
+/// * `Result<()>` - An error if the extension is unknown or the file cannot be written
+pub fn write_file_metadata(rows: &[FileMetadata], path: &Path) -> Result<()> {
+    let format = MetadataFormat::from_path(path)?;
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    match format {
+        MetadataFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(file);
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        #[cfg(feature = "parquet")]
+        MetadataFormat::Parquet => {
+            let strings = |value: fn(&FileMetadata) -> Option<String>| {
+                rows.iter().map(value).collect::<Vec<_>>()
+            };
+            let integers = |value: fn(&FileMetadata) -> Option<u64>| {
+                rows.iter()
+                    .map(|row| value(row).map(|value| value as i64))
+                    .collect::<Vec<_>>()
+            };
+            let columns = [
+                Column::strings("path", strings(|row| Some(row.path.clone()))),
+                Column::strings("language", strings(|row| Some(row.language.clone()))),
+                Column::integers("size_bytes", integers(|row| Some(row.size_bytes))),
+                Column::integers("tokens", integers(|row| Some(row.tokens))),
+                Column::strings("owners", strings(|row| row.owners.clone())).optional(),
+                Column::strings(
+                    "last_commit_date",
+                    strings(|row| row.last_commit_date.clone()),
+                )
+                .optional(),
+                Column::integers(
+                    "last_commit_timestamp",
+                    integers(|row| row.last_commit_timestamp),
+                )
+                .optional(),
+                Column::strings(
+                    "last_commit_author",
+                    strings(|row| row.last_commit_author.clone()),
+                )
+                .optional(),
+            ];
+            write_parquet(&columns, std::io::BufWriter::new(file))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+//! This module writes flat tables of strings and integers as Parquet files, with the
+//! `parquet` crate.
+//!
+//! Only what the exports need is supported: a single uncompressed row group, and required
+//! or optional columns of UTF-8 strings or 64-bit integers.
+
+use anyhow::{Context, Result};
+use parquet::basic::{ConvertedType, Repetition, Type as PhysicalType};
+use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use std::io::Write;
+use std::sync::Arc;
+
+/// The values of a column, `None` being a null
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnValues {
+    Strings(Vec<Option<String>>),
+    Integers(Vec<Option<i64>>),
+}
+
+impl ColumnValues {
+    fn len(&self) -> usize {
+        match self {
+            ColumnValues::Strings(values) => values.len(),
+            ColumnValues::Integers(values) => values.len(),
+        }
+    }
+}
+
+/// A column of a table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    /// Whether the column may hold nulls
+    pub optional: bool,
+    pub values: ColumnValues,
+}
+
+impl Column {
+    /// A column of strings, optional if it holds a null.
+    pub fn strings(name: &str, values: Vec<Option<String>>) -> Self {
+        Self {
+            name: name.to_string(),
+            optional: values.iter().any(Option::is_none),
+            values: ColumnValues::Strings(values),
+        }
+    }
+
+    /// A column of integers, optional if it holds a null.
+    pub fn integers(name: &str, values: Vec<Option<i64>>) -> Self {
+        Self {
+            name: name.to_string(),
+            optional: values.iter().any(Option::is_none),
+            values: ColumnValues::Integers(values),
+        }
+    }
+
+    /// Marks the column as optional even when it holds no null, so that its schema does
+    /// not depend on the values.
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+}
+
+/// Writes a table as a Parquet file.
+///
+/// # Arguments
+///
+/// * `columns` - The columns of the table, all of the same length
+/// * `writer` - Where the file is written
+///
+/// # Returns
+///
+/// * `Result<()>` - An error if the columns differ in length or the file cannot be written
+pub fn write_parquet(columns: &[Column], writer: impl Write + Send) -> Result<()> {
+    let rows = columns.first().map_or(0, |column| column.values.len());
+    if let Some(column) = columns.iter().find(|column| column.values.len() != rows) {
+        anyhow::bail!(
+            "Column {} has {} values instead of {}",
+            column.name,
+            column.values.len(),
+            rows
+        );
+    }
+
+    let fields = columns
+        .iter()
+        .map(|column| {
+            let field = match column.values {
+                ColumnValues::Strings(_) => {
+                    Type::primitive_type_builder(&column.name, PhysicalType::BYTE_ARRAY)
+                        .with_converted_type(ConvertedType::UTF8)
+                }
+                ColumnValues::Integers(_) => {
+                    Type::primitive_type_builder(&column.name, PhysicalType::INT64)
+                }
+            };
+            let repetition = if column.optional {
+                Repetition::OPTIONAL
+            } else {
+                Repetition::REQUIRED
+            };
+            field.with_repetition(repetition).build().map(Arc::new)
+        })
+        .collect::<parquet::errors::Result<Vec<_>>>()?;
+    let schema = Type::group_type_builder("schema")
+        .with_fields(fields)
+        .build()?;
+    let properties = WriterProperties::builder()
+        .set_created_by(concat!("code2prompt ", env!("CARGO_PKG_VERSION")).to_string())
+        .build();
+
+    let mut file = SerializedFileWriter::new(writer, Arc::new(schema), Arc::new(properties))?;
+    let mut row_group = file.next_row_group()?;
+    for column in columns {
+        let mut writer = row_group
+            .next_column()?
+            .with_context(|| format!("No column {} in the schema", column.name))?;
+        // The definition level of a value is 1, that of a null 0
+        let levels: Option<Vec<i16>> = column.optional.then(|| match &column.values {
+            ColumnValues::Strings(values) => values.iter().map(|v| v.is_some() as i16).collect(),
+            ColumnValues::Integers(values) => values.iter().map(|v| v.is_some() as i16).collect(),
+        });
+        match &column.values {
+            ColumnValues::Strings(values) => {
+                let values: Vec<ByteArray> = values
+                    .iter()
+                    .flatten()
+                    .map(|value| ByteArray::from(value.as_str()))
+                    .collect();
+                writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, levels.as_deref(), None)?;
+            }
+            ColumnValues::Integers(values) => {
+                let values: Vec<i64> = values.iter().flatten().copied().collect();
+                writer
+                    .typed::<Int64Type>()
+                    .write_batch(&values, levels.as_deref(), None)?;
+            }
+        }
+        writer.close()?;
+    }
+    row_group.close()?;
+    file.close()?;
+    Ok(())
+}
@@ -1,19 +1,34 @@
 //! This module contains the functions for traversing the directory and processing the files.
+use crate::bazel::bazel_scope;
+use crate::binary::{BINARY_SAMPLE_SIZE, BinaryHandling, binary_file_entry, is_binary};
+use crate::collapse::collapse_files;
+use crate::compression::compress;
 use crate::configuration::Code2PromptConfig;
 use crate::file_processor;
-use crate::filter::{build_globset, should_include_file};
-use crate::sort::{FileSortMethod, sort_files, sort_tree};
-use crate::tokenizer::count_tokens;
-use crate::util::strip_utf8_bom;
+use crate::filter::build_globset;
+use crate::fixture::fixture_stub;
+use crate::git::{FileCommit, changed_scope};
+use crate::language::{SourceStats, source_stats};
+use crate::line_range::{format_ranges, select_lines};
+use crate::placement::place_key_files;
+use crate::progress::{ProgressEvent, ProgressListener};
+use crate::selection::{FileInclusion, pattern_inclusion};
+use crate::sort::{FileSortMethod, sort_files};
+use crate::tags::{TagSymbol, symbol_scope};
+use crate::tokenizer::{bytes_per_token, count_tokens_with_mode};
+use crate::tree::SourceTree;
+use crate::util::{content_hash, strip_utf8_bom};
+use crate::workspace::package_scope;
 use anyhow::Result;
-use content_inspector::{ContentType, inspect};
 use ignore::WalkBuilder;
 use log::debug;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use termtree::Tree;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -38,11 +53,55 @@ pub struct FileEntry {
     pub extension: String,
     pub code: String,
     pub token_count: usize,
+    /// Stable hash of the file content, usable as a content address across runs
+    pub content_hash: String,
     pub metadata: EntryMetadata,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mod_time: Option<u64>,
+    /// The latest commits changing the file, when its history is requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<Vec<FileCommit>>,
+    /// The line ranges embedded, as `100-250, 300-320`, when only part of the file is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_range: Option<String>,
+    /// The language, the line counts and the test status of the file, flattened into the
+    /// file object of the templates (`{{language}}`, `{{#if is_test}}`)
+    #[serde(flatten)]
+    pub stats: SourceStats,
+    /// The symbols defined in the file, when a tags file is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbols: Option<Vec<TagSymbol>>,
 }
 
+/// How symbolic links are handled during traversal
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkPolicy {
+    /// Symbolic links are listed in the tree, but neither read nor descended into
+    #[default]
+    Skip,
+    /// Symbolic links are followed, a link to one of its ancestors excepted
+    Follow,
+    /// Symbolic links are followed, and every file or directory already reached
+    /// through another path is skipped, so that each is traversed once
+    FollowWithCycleDetection,
+}
+
+impl std::fmt::Display for SymlinkPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymlinkPolicy::Skip => write!(f, "Skip"),
+            SymlinkPolicy::Follow => write!(f, "Follow"),
+            SymlinkPolicy::FollowWithCycleDetection => write!(f, "Follow once"),
+        }
+    }
+}
+
+/// Name of the project-local ignore files, with the syntax of `.gitignore`. They apply to
+/// their directory and below, like `.gitignore` files, on top of the include and exclude
+/// patterns.
+pub const IGNORE_FILE_NAME: &str = ".c2pignore";
+
 /// Represents a file that needs to be processed
 #[derive(Debug, Clone)]
 struct FileToProcess {
@@ -71,17 +130,231 @@ struct FileToProcess {
 pub fn traverse_directory(
     config: &Code2PromptConfig,
     selection_engine: Option<&mut crate::selection::SelectionEngine>,
+) -> Result<(String, Vec<FileEntry>)> {
+    traverse_directory_with_progress(config, selection_engine, None)
+}
+
+/// Traverses the directory like [`traverse_directory`], reporting the files found and each
+/// file read.
+///
+/// # Arguments
+///
+/// * `config` - Configuration object containing path, include/exclude patterns, and other settings
+/// * `selection_engine` - Optional SelectionEngine for advanced file selection with user actions
+/// * `progress` - Optional listener receiving `FilesFound` and `FileLoaded` events
+///
+/// # Returns
+///
+/// * `Result<(String, Vec<FileEntry>)>` - A tuple containing the string representation of the directory
+///   tree and a vector of file entries
+pub fn traverse_directory_with_progress(
+    config: &Code2PromptConfig,
+    selection_engine: Option<&mut crate::selection::SelectionEngine>,
+    progress: Option<&dyn ProgressListener>,
 ) -> Result<(String, Vec<FileEntry>)> {
     // Phase 1: Discovery - Build tree and collect files to process
     let (tree, files_to_process) = discover_files(config, selection_engine)?;
+    if let Some(progress) = progress {
+        progress.on_progress(ProgressEvent::FilesFound {
+            count: files_to_process.len(),
+        });
+    }
 
     // Phase 2: Processing - Process files in parallel
-    let mut files = process_files_parallel(files_to_process, config)?;
+    let mut files = process_files_parallel(files_to_process, config, progress)?;
 
     // Phase 3: Assembly - Sort and return results
     assemble_results(tree, &mut files, config)
 }
 
+/// Traverses the directory like [`traverse_directory`], but reads the files lazily.
+///
+/// Only the tree and the paths of the selected files are held in memory, each file is
+/// read and processed when the returned stream reaches it, so that the memory needed by
+/// very large repositories stays bounded.
+///
+/// # Arguments
+///
+/// * `config` - Configuration object containing path, include/exclude patterns, and other settings
+/// * `selection_engine` - Optional SelectionEngine for advanced file selection with user actions
+///
+/// # Returns
+///
+/// * `Result<(String, FileStream)>` - The string representation of the directory tree and
+///   the stream of file entries, in the configured sort order
+pub fn stream_directory(
+    config: &Code2PromptConfig,
+    selection_engine: Option<&mut crate::selection::SelectionEngine>,
+) -> Result<(String, FileStream)> {
+    let (tree, mut files_to_process) = discover_files(config, selection_engine)?;
+    // The files are not read yet, so the tree is annotated without tokens
+    let tree = tree.render(&HashMap::new(), config);
+
+    sort_unread_files(&mut files_to_process, config);
+
+    Ok((
+        tree,
+        FileStream {
+            pending: files_to_process.into_iter(),
+            config: config.clone(),
+        },
+    ))
+}
+
+/// A file that would be included, estimated from its size without being read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEstimate {
+    pub path: String,
+    /// Size of the file in bytes
+    pub size: u64,
+    /// Tokens estimated from the size and the extension of the file
+    pub token_count: usize,
+}
+
+/// Walks the directory like [`traverse_directory`], listing the files that would be
+/// included, but without reading them: their tokens are estimated from their sizes.
+///
+/// # Arguments
+///
+/// * `config` - Configuration object containing path, include/exclude patterns, and other settings
+/// * `selection_engine` - Optional SelectionEngine for advanced file selection with user actions
+///
+/// # Returns
+///
+/// * `Result<(String, Vec<FileEstimate>)>` - The string representation of the directory
+///   tree and the estimates of the files, in the configured sort order
+pub fn estimate_directory(
+    config: &Code2PromptConfig,
+    selection_engine: Option<&mut crate::selection::SelectionEngine>,
+) -> Result<(String, Vec<FileEstimate>)> {
+    let (tree, mut files_to_process) = discover_files(config, selection_engine)?;
+    let tree = tree.render(&HashMap::new(), config);
+    sort_unread_files(&mut files_to_process, config);
+
+    let estimates = files_to_process
+        .iter()
+        .map(|file| {
+            let extension = file
+                .relative_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("");
+            let size = file.metadata.len();
+            FileEstimate {
+                path: config.output_path(&file.relative_path, &file.absolute_path),
+                size,
+                token_count: (size as f64 / bytes_per_token(extension)).ceil() as usize,
+            }
+        })
+        .collect();
+    Ok((tree, estimates))
+}
+
+/// Sorts the files to process in the orders of [`sort_files`], from what is known
+/// before reading them, and places the key files.
+fn sort_unread_files(files: &mut [FileToProcess], config: &Code2PromptConfig) {
+    let name = |file: &FileToProcess| file.relative_path.to_string_lossy().into_owned();
+    let modified = |file: &FileToProcess| {
+        file.metadata
+            .modified()
+            .ok()
+            .and_then(|mtime| mtime.duration_since(std::time::SystemTime::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs())
+    };
+    match config.sort_method {
+        Some(FileSortMethod::NameAsc) => files.sort_by_cached_key(name),
+        Some(FileSortMethod::NameDesc) => {
+            files.sort_by_cached_key(|file| std::cmp::Reverse(name(file)))
+        }
+        Some(FileSortMethod::DateAsc) => files.sort_by_key(modified),
+        Some(FileSortMethod::DateDesc) => {
+            files.sort_by_key(|file| std::cmp::Reverse(modified(file)))
+        }
+        Some(FileSortMethod::Extension) => files.sort_by_cached_key(|file| {
+            let extension = file.relative_path.extension().map(|ext| ext.to_os_string());
+            (extension, name(file))
+        }),
+        // Token counts and imports are only known once read: the size stands in for the
+        // tokens, and the imports are not followed
+        Some(FileSortMethod::TokensDesc) => {
+            files.sort_by_cached_key(|file| (std::cmp::Reverse(file.metadata.len()), name(file)))
+        }
+        Some(FileSortMethod::Imports) => files.sort_by_cached_key(name),
+        None => {}
+    }
+    place_key_files(files, |file| file.relative_path.as_path(), config);
+}
+
+/// Iterator over the file entries of a traversal, reading each file when it is reached.
+///
+/// Binary, empty and unreadable files are skipped, as in [`traverse_directory`].
+#[derive(Debug)]
+pub struct FileStream {
+    pending: std::vec::IntoIter<FileToProcess>,
+    config: Code2PromptConfig,
+}
+
+impl Iterator for FileStream {
+    type Item = FileEntry;
+
+    fn next(&mut self) -> Option<FileEntry> {
+        self.pending
+            .by_ref()
+            .find_map(|file| process_single_file(&file, &self.config))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.pending.len()))
+    }
+}
+
+/// Creates a directory walker honoring the hidden, ignore and symlink settings.
+///
+/// Unless `config.no_ignore` is set, `.gitignore` and [`IGNORE_FILE_NAME`] files are
+/// respected at every directory level. With [`SymlinkPolicy::FollowWithCycleDetection`],
+/// the entries whose inode was already visited are skipped.
+///
+/// # Arguments
+///
+/// * `root` - The directory to walk
+/// * `config` - The configuration giving the traversal settings
+///
+/// # Returns
+///
+/// * `WalkBuilder` - The walker, to be refined by the caller before being built
+pub fn walk_builder(root: &Path, config: &Code2PromptConfig) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(!config.hidden)
+        .git_ignore(!config.no_ignore)
+        .follow_links(config.symlink_policy != SymlinkPolicy::Skip);
+    if !config.no_ignore {
+        builder.add_custom_ignore_filename(IGNORE_FILE_NAME);
+    }
+    if config.symlink_policy == SymlinkPolicy::FollowWithCycleDetection {
+        let visited = Mutex::new(HashSet::new());
+        builder.filter_entry(move |entry| match file_identity(entry.path()) {
+            Some(identity) => visited.lock().map_or(true, |mut set| set.insert(identity)),
+            None => true,
+        });
+    }
+    builder
+}
+
+/// Identifies the file or directory a path resolves to, as its device and inode.
+#[cfg(unix)]
+fn file_identity(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Identifies the file or directory a path resolves to, as its canonical path.
+#[cfg(not(unix))]
+fn file_identity(path: &Path) -> Option<PathBuf> {
+    path.canonicalize().ok()
+}
+
 /// Phase 1: Discovery - Walk directories, build tree, and collect files that need processing
 ///
 /// This phase is sequential because:
@@ -91,59 +364,75 @@ pub fn traverse_directory(
 fn discover_files(
     config: &Code2PromptConfig,
     mut selection_engine: Option<&mut crate::selection::SelectionEngine>,
-) -> Result<(Tree<String>, Vec<FileToProcess>)> {
+) -> Result<(SourceTree, Vec<FileToProcess>)> {
     let canonical_root_path = config.path.canonicalize()?;
-    let parent_directory = display_name(&canonical_root_path);
+    // The alias stands for the root in the output paths, and so names it in the tree
+    let parent_directory = config
+        .path_alias_prefix()
+        .filter(|alias| !alias.is_empty())
+        .unwrap_or_else(|| display_name(&canonical_root_path));
 
     let include_globset = build_globset(&config.include_patterns);
     let exclude_globset = build_globset(&config.exclude_patterns);
+    let tree_only_globset = build_globset(&config.tree_only_patterns);
+    let package_scope = package_scope(config, None)?;
+    let bazel_scope = bazel_scope(config, None)?;
+    let symbol_scope = symbol_scope(config)?;
+    let changed_scope = changed_scope(config, &canonical_root_path)?;
 
     // Build the Walker
-    let walker = WalkBuilder::new(&canonical_root_path)
-        .hidden(!config.hidden)
-        .git_ignore(!config.no_ignore)
-        .follow_links(config.follow_symlinks)
+    let walker = walk_builder(&canonical_root_path, config)
         .build()
         .filter_map(|entry| entry.ok());
 
     // Build the Tree
-    let mut tree = Tree::new(parent_directory.to_owned());
+    let mut tree = SourceTree::new(parent_directory.to_owned());
     let mut files_to_process = Vec::new();
 
     for entry in walker {
         let path = entry.path();
         if let Ok(relative_path) = path.strip_prefix(&canonical_root_path) {
             // Use SelectionEngine if available, otherwise fall back to pattern matching
-            let entry_match = if let Some(engine) = selection_engine.as_mut() {
-                engine.is_selected(relative_path)
+            let inclusion = if let Some(engine) = selection_engine.as_mut() {
+                engine.inclusion(relative_path)
             } else {
-                should_include_file(relative_path, &include_globset, &exclude_globset)
+                pattern_inclusion(
+                    relative_path,
+                    &include_globset,
+                    &exclude_globset,
+                    &tree_only_globset,
+                )
             };
+            let entry_match = inclusion.in_tree()
+                && package_scope
+                    .as_ref()
+                    .is_none_or(|scope| scope.contains(relative_path))
+                && bazel_scope
+                    .as_ref()
+                    .is_none_or(|scope| scope.contains(relative_path))
+                && symbol_scope
+                    .as_ref()
+                    .is_none_or(|scope| scope.contains(relative_path))
+                && changed_scope
+                    .as_ref()
+                    .is_none_or(|scope| scope.contains(relative_path))
+                && !config.fixture_policy.excludes(relative_path);
 
             // Directory Tree
             let include_in_tree = config.full_directory_tree || entry_match;
 
             if include_in_tree {
-                let mut current_tree = &mut tree;
-                for component in relative_path.components() {
-                    let component_str = component.as_os_str().to_string_lossy().to_string();
-                    current_tree = if let Some(pos) = current_tree
-                        .leaves
-                        .iter_mut()
-                        .position(|child| child.root == component_str)
-                    {
-                        &mut current_tree.leaves[pos]
-                    } else {
-                        let new_tree = Tree::new(component_str.clone());
-                        current_tree.leaves.push(new_tree);
-                        current_tree.leaves.last_mut().unwrap()
-                    };
-                }
+                let is_dir = entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_dir());
+                tree.add(relative_path, is_dir);
             }
 
-            // Collect files for processing
+            // Collect files for processing, symbolic links being read only when followed
             if path.is_file()
                 && entry_match
+                && (config.symlink_policy != SymlinkPolicy::Skip || !entry.path_is_symlink())
+                && inclusion == FileInclusion::Full
                 && let Ok(metadata) = entry.metadata()
             {
                 files_to_process.push(FileToProcess {
@@ -158,6 +447,25 @@ fn discover_files(
     Ok((tree, files_to_process))
 }
 
+/// Adds a path to the directory tree, creating the intermediate nodes as needed.
+pub(crate) fn add_to_tree(tree: &mut Tree<String>, relative_path: &Path) {
+    let mut current_tree = tree;
+    for component in relative_path.components() {
+        let component_str = component.as_os_str().to_string_lossy().to_string();
+        current_tree = if let Some(pos) = current_tree
+            .leaves
+            .iter_mut()
+            .position(|child| child.root == component_str)
+        {
+            &mut current_tree.leaves[pos]
+        } else {
+            let new_tree = Tree::new(component_str.clone());
+            current_tree.leaves.push(new_tree);
+            current_tree.leaves.last_mut().unwrap()
+        };
+    }
+}
+
 /// Phase 2: Processing - Process files in parallel using rayon
 ///
 /// This phase processes files in parallel:
@@ -168,11 +476,21 @@ fn discover_files(
 fn process_files_parallel(
     files_to_process: Vec<FileToProcess>,
     config: &Code2PromptConfig,
+    progress: Option<&dyn ProgressListener>,
 ) -> Result<Vec<FileEntry>> {
     // Process files in parallel with rayon
     let files: Vec<Option<FileEntry>> = files_to_process
         .par_iter()
-        .map(|file_info| process_single_file(file_info, config))
+        .map(|file_info| {
+            let entry = process_single_file(file_info, config);
+            if let (Some(progress), Some(entry)) = (progress, &entry) {
+                progress.on_progress(ProgressEvent::FileLoaded {
+                    path: entry.path.clone(),
+                    tokens: entry.token_count,
+                });
+            }
+            entry
+        })
         .collect();
 
     // Filter out None values (files that failed to process or were empty)
@@ -181,32 +499,41 @@ fn process_files_parallel(
 
 /// Read file with single-pass binary detection
 ///
-/// Reads file incrementally: first 8KB for binary detection, then remainder if text.
-fn read_file_with_binary_check(path: &Path, file_size: u64) -> std::io::Result<Option<Vec<u8>>> {
-    const SAMPLE_SIZE: usize = 8192;
-
+/// Reads file incrementally: first 8KB for binary detection, then remainder if text, or if
+/// binary files are read too.
+///
+/// # Returns
+///
+/// * `std::io::Result<(Vec<u8>, bool)>` - The content, empty for a binary file not read,
+///   and whether the file is binary
+fn read_file_with_binary_check(
+    path: &Path,
+    file_size: u64,
+    read_binary: bool,
+) -> std::io::Result<(Vec<u8>, bool)> {
     let mut file = fs::File::open(path)?;
     let mut buffer = Vec::with_capacity(file_size.min(1024 * 1024 * 10) as usize); // Cap at 10MB initial allocation
 
     // Read first chunk for binary detection
-    let bytes_to_read = SAMPLE_SIZE.min(file_size as usize);
+    let bytes_to_read = BINARY_SAMPLE_SIZE.min(file_size as usize);
     let mut sample_buffer = vec![0u8; bytes_to_read];
     file.read_exact(&mut sample_buffer)?;
 
     // Check if binary
-    if inspect(&sample_buffer) == ContentType::BINARY {
-        return Ok(None); // Return None for binary files
+    let binary = is_binary(&sample_buffer);
+    if binary && !read_binary {
+        return Ok((Vec::new(), true));
     }
 
-    // It's text! Add sample to buffer and read the rest
+    // Add sample to buffer and read the rest
     buffer.extend_from_slice(&sample_buffer);
 
     // Read remaining bytes if file is larger than sample
-    if file_size > SAMPLE_SIZE as u64 {
+    if file_size > BINARY_SAMPLE_SIZE as u64 {
         file.read_to_end(&mut buffer)?;
     }
 
-    Ok(Some(buffer))
+    Ok((buffer, binary))
 }
 
 /// Process a single file and return its FileEntry representation
@@ -215,23 +542,114 @@ fn process_single_file(file_info: &FileToProcess, config: &Code2PromptConfig) ->
     let relative_path = &file_info.relative_path;
     let metadata = &file_info.metadata;
 
-    let code_bytes = match read_file_with_binary_check(path, metadata.len()) {
-        Ok(Some(bytes)) => bytes,
-        Ok(None) => {
+    let read_binary = config.binary_handling != BinaryHandling::Skip;
+    let (code_bytes, binary) = match read_file_with_binary_check(path, metadata.len(), read_binary)
+    {
+        Ok((_, true)) if !read_binary => {
             debug!("Skipped binary file: {}", path.display());
             return None;
         }
+        Ok(content) => content,
         Err(e) => {
             debug!("Failed to read file {}: {}", path.display(), e);
             return None;
         }
     };
 
-    let clean_bytes = strip_utf8_bom(&code_bytes);
+    // Build filepath
+    let file_path = config.output_path(relative_path, path);
+
+    // Get modification time if date sorting is requested
+    let mod_time = if let Some(method) = config.sort_method {
+        if method == FileSortMethod::DateAsc || method == FileSortMethod::DateDesc {
+            metadata
+                .modified()
+                .ok()
+                .and_then(|mtime| mtime.duration_since(std::time::SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    if binary {
+        return binary_file_entry(
+            file_path,
+            &code_bytes,
+            EntryMetadata::from(metadata),
+            mod_time,
+            config,
+        );
+    }
+
+    let mut entry = build_file_entry(
+        relative_path,
+        file_path,
+        &code_bytes,
+        EntryMetadata::from(metadata),
+        mod_time,
+        config,
+    )?;
+    if let Some(kind) = config.fixture_policy.stubs(relative_path) {
+        let updated = metadata
+            .modified()
+            .ok()
+            .and_then(|mtime| mtime.duration_since(std::time::SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        let content = String::from_utf8_lossy(&code_bytes);
+        stub_file_entry(&mut entry, &fixture_stub(kind, &content, updated), config);
+    }
+    Some(entry)
+}
+
+/// Replaces the content of a file entry by a stub describing it, keeping its hash.
+///
+/// # Arguments
+///
+/// * `entry` - The file entry to stub
+/// * `stub` - The description standing in for the content
+/// * `config` - The configuration
+pub(crate) fn stub_file_entry(entry: &mut FileEntry, stub: &str, config: &Code2PromptConfig) {
+    entry.code = format!("({})", stub);
+    entry.token_count = count_tokens_with_mode(
+        &entry.code,
+        &entry.extension,
+        &config.encoding,
+        config.tokenizer_model.as_deref(),
+        config.token_count_mode,
+    );
+}
+
+/// Builds the FileEntry of a text file from its raw content.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file relative to the root, used to pick the file processor
+///   and the line ranges to keep
+/// * `file_path` - The path displayed in the prompt
+/// * `code_bytes` - The raw file content
+/// * `metadata` - The file metadata
+/// * `mod_time` - The modification time, when date sorting is requested
+/// * `config` - The configuration
+///
+/// # Returns
+///
+/// * `Option<FileEntry>` - The file entry, or `None` if the file is empty or not valid UTF-8
+pub(crate) fn build_file_entry(
+    path: &Path,
+    file_path: String,
+    code_bytes: &[u8],
+    metadata: EntryMetadata,
+    mod_time: Option<u64>,
+    config: &Code2PromptConfig,
+) -> Option<FileEntry> {
+    let clean_bytes = strip_utf8_bom(code_bytes);
 
     // Get appropriate processor for file extension
     let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
-    let processor = file_processor::get_processor_for_extension(extension);
+    let processor = file_processor::get_processor(extension, config);
 
     // Process file content
     let code = match processor.process(clean_bytes, path) {
@@ -246,8 +664,30 @@ fn process_single_file(file_info: &FileToProcess, config: &Code2PromptConfig) ->
         }
     };
 
+    // Keep the requested lines only, numbered as in the file, or else the signatures if
+    // the file is compressed. The lines of a compressed file are not numbered, since they
+    // no longer match those of the file.
+    let stats = source_stats(path, &code);
+    let line_ranges = config.file_line_ranges(path);
+    let (code, line_numbers) = if !line_ranges.is_empty() {
+        (
+            select_lines(&code, &line_ranges, config.line_numbers),
+            false,
+        )
+    } else if let Some(compressed) = compress(&code, extension, config.file_compression_level(path))
+    {
+        (compressed, false)
+    } else {
+        (code, config.line_numbers)
+    };
+
     // Wrap code block
-    let code_block = wrap_code_block(&code, extension, config.line_numbers, config.no_codeblock);
+    let code_block = wrap_code_block(
+        &code,
+        fence_language(extension, config),
+        line_numbers,
+        config.no_codeblock,
+    );
 
     // Filter empty or invalid files
     if code.trim().is_empty() || code.contains(char::REPLACEMENT_CHARACTER) {
@@ -255,31 +695,15 @@ fn process_single_file(file_info: &FileToProcess, config: &Code2PromptConfig) ->
         return None;
     }
 
-    // Build filepath
-    let file_path = if config.absolute_path {
-        path.to_string_lossy().to_string()
-    } else {
-        relative_path.to_string_lossy().to_string()
-    };
-
     // Always calculate token count in parallel (amortized by I/O wait time)
     // This enables zero-overhead token counting regardless of display preferences
-    let token_count = count_tokens(&code, &config.encoding);
-
-    // Get modification time if date sorting is requested
-    let mod_time = if let Some(method) = config.sort_method {
-        if method == FileSortMethod::DateAsc || method == FileSortMethod::DateDesc {
-            metadata
-                .modified()
-                .ok()
-                .and_then(|mtime| mtime.duration_since(std::time::SystemTime::UNIX_EPOCH).ok())
-                .map(|d| d.as_secs())
-        } else {
-            None
-        }
-    } else {
-        None
-    };
+    let token_count = count_tokens_with_mode(
+        &code,
+        extension,
+        &config.encoding,
+        config.tokenizer_model.as_deref(),
+        config.token_count_mode,
+    );
 
     debug!(target: "included_files", "Included file: {}", file_path);
 
@@ -288,22 +712,34 @@ fn process_single_file(file_info: &FileToProcess, config: &Code2PromptConfig) ->
         extension: extension.to_string(),
         code: code_block,
         token_count,
-        metadata: EntryMetadata::from(metadata),
+        content_hash: format!("{:016x}", content_hash(clean_bytes)),
+        metadata,
         mod_time,
+        history: None,
+        line_range: (!line_ranges.is_empty()).then(|| format_ranges(&line_ranges)),
+        stats,
+        symbols: None,
     })
 }
 
 /// Phase 3: Assembly - Sort results and return
 fn assemble_results(
-    mut tree: Tree<String>,
+    tree: SourceTree,
     files: &mut [FileEntry],
     config: &Code2PromptConfig,
 ) -> Result<(String, Vec<FileEntry>)> {
-    // Sort tree and files
-    sort_tree(&mut tree, config.sort_method);
-    sort_files(files, config.sort_method);
+    // Annotate the tree with the tokens of the files, by relative path
+    let tokens: HashMap<PathBuf, usize> = files
+        .iter()
+        .map(|file| (config.root_relative_path(&file.path), file.token_count))
+        .collect();
+
+    // Sort tree and files, the files of the collapsed directories being summarized
+    let tree = tree.render(&tokens, config);
+    let mut files = collapse_files(files.to_owned(), config);
+    sort_files(&mut files, config.sort_method);
 
-    Ok((tree.to_string(), files.to_owned()))
+    Ok((tree, files))
 }
 
 /// Returns the file name or the string representation of the path.
@@ -331,12 +767,30 @@ pub fn display_name<P: AsRef<Path>>(p: P) -> String {
     ".".to_string()
 }
 
+/// Returns the language tag of the code fence of a file.
+///
+/// # Arguments
+///
+/// * `extension` - The file extension, without the leading dot.
+/// * `config` - The configuration, whose `fence_languages` map extensions to tags.
+///
+/// # Returns
+///
+/// * `&str` - The mapped tag, or the extension itself if it is not mapped.
+pub fn fence_language<'a>(extension: &'a str, config: &'a Code2PromptConfig) -> &'a str {
+    config
+        .fence_languages
+        .get(extension)
+        .or_else(|| config.fence_languages.get(&extension.to_lowercase()))
+        .map_or(extension, String::as_str)
+}
+
 /// Wraps the code block with a delimiter and adds line numbers if required.
 ///
 /// # Arguments
 ///
 /// * `code` - The code block to wrap.
-/// * `extension` - The file extension of the code block.
+/// * `language` - The language tag of the code block, usually its file extension.
 /// * `line_numbers` - Whether to add line numbers to the code.
 /// * `no_codeblock` - Whether to not wrap the code block with a delimiter.
 ///
@@ -345,7 +799,7 @@ pub fn display_name<P: AsRef<Path>>(p: P) -> String {
 /// * `String` - The wrapped code block.
 pub fn wrap_code_block(
     code: &str,
-    extension: &str,
+    language: &str,
     line_numbers: bool,
     no_codeblock: bool,
 ) -> String {
@@ -365,7 +819,7 @@ pub fn wrap_code_block(
     } else {
         format!(
             "{}{}\n{}\n{}",
-            delimiter, extension, code_with_line_numbers, delimiter
+            delimiter, language, code_with_line_numbers, delimiter
         )
     }
 }
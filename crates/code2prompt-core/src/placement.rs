@@ -0,0 +1,74 @@
+//! Placement of the content a task focuses on.
+//!
+//! Models do not attend to every part of a long prompt equally, the start and the end
+//! usually weigh more. The [`Placement`] strategy decides whether the instruction, the git
+//! diffs and the key files go before or after the rest of the codebase, so that layouts
+//! can be compared on the same task. The strategy is recorded in the JSON output.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::configuration::Code2PromptConfig;
+use crate::filter::build_globset;
+
+/// Where the instruction, the git diffs and the key files are placed in the prompt.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Placement {
+    /// The codebase first, then the key files, the diffs and the instruction.
+    #[default]
+    End,
+    /// The instruction, the diffs and the key files before the rest of the codebase.
+    Beginning,
+    /// As `beginning`, with the instruction repeated after the codebase.
+    Sandwich,
+}
+
+impl Placement {
+    /// Whether the instruction is rendered before the codebase.
+    pub fn instruction_first(self) -> bool {
+        self != Placement::End
+    }
+
+    /// Whether the instruction is rendered after the codebase.
+    pub fn instruction_last(self) -> bool {
+        self != Placement::Beginning
+    }
+
+    /// Whether the diffs and the key files come before the rest of the codebase.
+    pub fn focus_first(self) -> bool {
+        self != Placement::End
+    }
+}
+
+impl std::fmt::Display for Placement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Placement::End => write!(f, "end"),
+            Placement::Beginning => write!(f, "beginning"),
+            Placement::Sandwich => write!(f, "sandwich"),
+        }
+    }
+}
+
+/// Moves the files matching `config.key_files` to the start or to the end of the files,
+/// following `config.placement`. The order is otherwise kept.
+///
+/// # Arguments
+///
+/// * `files` - The files, in their sort order
+/// * `relative_path` - Gives the path of a file relative to the root, matched against the patterns
+/// * `config` - The configuration giving the key file patterns and the placement
+pub fn place_key_files<T>(
+    files: &mut [T],
+    relative_path: impl Fn(&T) -> &Path,
+    config: &Code2PromptConfig,
+) {
+    if config.key_files.is_empty() {
+        return;
+    }
+    let key_files = build_globset(&config.key_files);
+    let first = config.placement.focus_first();
+    // Stable, so that key files and other files keep their relative order
+    files.sort_by_key(|file| key_files.is_match(relative_path(file)) != first);
+}
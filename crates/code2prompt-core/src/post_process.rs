@@ -0,0 +1,408 @@
+//! Post-processing of the rendered prompt.
+//!
+//! After Handlebars rendering, and before the instruction is appended and the prompt is
+//! written out, the prompt goes through a pipeline of [`PostProcessor`]s in the configured
+//! order. The built-in steps are listed in [`PostProcessStep`]; other processors can be
+//! added to a session with `Code2PromptSession::add_post_processor`.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::path::FileEntry;
+use crate::template::OutputFormat;
+
+/// Blank lines kept in a row by [`CollapseBlankLines`]
+const MAX_BLANK_LINES: usize = 2;
+
+/// What post-processors know about the prompt besides its text
+#[derive(Debug, Clone, Copy)]
+pub struct PostProcessContext<'a> {
+    /// The output format the template was chosen for
+    pub output_format: OutputFormat,
+    /// The files of the prompt, in order
+    pub files: &'a [FileEntry],
+    /// Token count of the prompt before post-processing
+    pub token_count: usize,
+    /// The tokenizer the tokens were counted with
+    pub model_info: &'a str,
+}
+
+/// A transformation of the rendered prompt.
+///
+/// Processors must be `Debug` so that a session holding them can still be printed.
+pub trait PostProcessor: Send + Sync + std::fmt::Debug {
+    /// Transform the prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The prompt, as rendered or as left by the previous processor
+    /// * `context` - What is known about the prompt besides its text
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String>` - The transformed prompt
+    fn process(&self, prompt: &str, context: &PostProcessContext) -> Result<String>;
+}
+
+/// The built-in post-processing steps, which can be set in the configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostProcessStep {
+    /// Collapse runs of more than two blank lines.
+    CollapseBlankLines,
+    /// Number Markdown headers hierarchically: `## 1. Setup`, `### 1.1. Install`.
+    NumberHeaders,
+    /// Insert a table of contents of the Markdown headers, linking to their anchors.
+    TableOfContents,
+    /// Insert a table of contents of the files with their token counts, linking to anchors
+    /// added before each file.
+    FileIndex,
+    /// Append a footer with the file and token counts.
+    Footer,
+}
+
+impl std::fmt::Display for PostProcessStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PostProcessStep::CollapseBlankLines => write!(f, "collapse_blank_lines"),
+            PostProcessStep::NumberHeaders => write!(f, "number_headers"),
+            PostProcessStep::TableOfContents => write!(f, "table_of_contents"),
+            PostProcessStep::FileIndex => write!(f, "file_index"),
+            PostProcessStep::Footer => write!(f, "footer"),
+        }
+    }
+}
+
+impl PostProcessStep {
+    /// The processor implementing the step.
+    pub fn processor(&self) -> Box<dyn PostProcessor> {
+        match self {
+            PostProcessStep::CollapseBlankLines => Box::new(CollapseBlankLines),
+            PostProcessStep::NumberHeaders => Box::new(NumberHeaders),
+            PostProcessStep::TableOfContents => Box::new(TableOfContents),
+            PostProcessStep::FileIndex => Box::new(FileIndex),
+            PostProcessStep::Footer => Box::new(Footer),
+        }
+    }
+}
+
+/// Runs processors one after the other, each one receiving the output of the previous one.
+///
+/// # Arguments
+///
+/// * `prompt` - The rendered prompt
+/// * `processors` - The processors, in order
+/// * `context` - What is known about the prompt besides its text
+///
+/// # Returns
+///
+/// * `Result<String>` - The post-processed prompt
+pub fn run_pipeline(
+    prompt: String,
+    processors: &[&dyn PostProcessor],
+    context: &PostProcessContext,
+) -> Result<String> {
+    processors.iter().try_fold(prompt, |prompt, processor| {
+        processor.process(&prompt, context)
+    })
+}
+
+/// Collapses runs of blank lines, which templates leave behind when sections are empty.
+#[derive(Debug)]
+pub struct CollapseBlankLines;
+
+impl PostProcessor for CollapseBlankLines {
+    fn process(&self, prompt: &str, _context: &PostProcessContext) -> Result<String> {
+        let mut output = String::with_capacity(prompt.len());
+        let mut blank_lines = 0;
+        for line in prompt.split_inclusive('\n') {
+            if line.trim().is_empty() {
+                blank_lines += 1;
+                if blank_lines > MAX_BLANK_LINES {
+                    continue;
+                }
+            } else {
+                blank_lines = 0;
+            }
+            output.push_str(line);
+        }
+        Ok(output)
+    }
+}
+
+/// Numbers Markdown headers by their level, the top level being the highest level in use.
+#[derive(Debug)]
+pub struct NumberHeaders;
+
+impl PostProcessor for NumberHeaders {
+    fn process(&self, prompt: &str, _context: &PostProcessContext) -> Result<String> {
+        let headers = markdown_headers(prompt);
+        let Some(top) = headers.iter().map(|header| header.level).min() else {
+            return Ok(prompt.to_string());
+        };
+
+        let mut counters: Vec<usize> = Vec::new();
+        let mut numbered: Vec<(usize, String)> = Vec::new();
+        for header in &headers {
+            let depth = header.level - top + 1;
+            counters.resize(depth, 0);
+            counters[depth - 1] += 1;
+            let number: Vec<String> = counters.iter().map(usize::to_string).collect();
+            numbered.push((
+                header.line,
+                format!(
+                    "{} {}. {}",
+                    "#".repeat(header.level),
+                    number.join("."),
+                    header.title
+                ),
+            ));
+        }
+
+        let mut lines: Vec<&str> = prompt.split('\n').collect();
+        for (line, text) in &numbered {
+            lines[*line] = text;
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Inserts a list of the Markdown headers, linking to their anchors, before the first one.
+#[derive(Debug)]
+pub struct TableOfContents;
+
+impl PostProcessor for TableOfContents {
+    fn process(&self, prompt: &str, _context: &PostProcessContext) -> Result<String> {
+        let headers = markdown_headers(prompt);
+        let Some(first) = headers.first() else {
+            return Ok(prompt.to_string());
+        };
+        let top = headers.iter().map(|header| header.level).min().unwrap_or(1);
+
+        // Anchors follow GitHub: duplicates get a `-1`, `-2`... suffix
+        let mut anchors: Vec<String> = Vec::new();
+        let mut toc = String::from("Table of Contents:\n\n");
+        for header in &headers {
+            let slug = anchor(&header.title);
+            let duplicates = anchors.iter().filter(|a| **a == slug).count();
+            let id = if duplicates == 0 {
+                slug.clone()
+            } else {
+                format!("{}-{}", slug, duplicates)
+            };
+            anchors.push(slug);
+            toc.push_str(&format!(
+                "{}- [{}](#{})\n",
+                "  ".repeat(header.level - top),
+                header.title,
+                id
+            ));
+        }
+        toc.push('\n');
+
+        let mut lines: Vec<&str> = prompt.split('\n').collect();
+        lines.insert(first.line, toc.trim_end_matches('\n'));
+        lines.insert(first.line + 1, "");
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Inserts a table of contents of the files at the top of the prompt, with their token
+/// counts. Each file of the default templates gets an anchor the table links to: an
+/// `<a id>` before its path in Markdown, an `id` attribute on its `<file>` tag in XML.
+/// Files of other templates are listed without links.
+#[derive(Debug)]
+pub struct FileIndex;
+
+impl PostProcessor for FileIndex {
+    fn process(&self, prompt: &str, context: &PostProcessContext) -> Result<String> {
+        if context.files.is_empty() {
+            return Ok(prompt.to_string());
+        }
+        let markdown = context.output_format == OutputFormat::Markdown;
+
+        // Files appear in order, each one is searched for after the code of the previous one
+        let mut body = String::with_capacity(prompt.len());
+        let mut rest = prompt;
+        let mut slugs: Vec<String> = Vec::new();
+        let mut links: Vec<Option<String>> = Vec::new();
+        for file in context.files {
+            let marker = if markdown {
+                format!("`{}`:", file.path)
+            } else {
+                format!("<file path=\"{}\">", file.path)
+            };
+            let Some(index) = find_at_line_start(rest, &marker) else {
+                links.push(None);
+                continue;
+            };
+
+            // Paths differing only by punctuation share a slug, and get a suffix
+            let slug = file_anchor(&file.path);
+            let duplicates = slugs.iter().filter(|s| **s == slug).count();
+            let id = if duplicates == 0 {
+                slug.clone()
+            } else {
+                format!("{}-{}", slug, duplicates)
+            };
+            slugs.push(slug);
+            body.push_str(&rest[..index]);
+            if markdown {
+                body.push_str(&format!("<a id=\"{}\"></a>\n{}", id, marker));
+            } else {
+                body.push_str(&format!("<file path=\"{}\" id=\"{}\">", file.path, id));
+            }
+            rest = &rest[index + marker.len()..];
+            // Paths quoted in the code of the file are not mistaken for the next files
+            if !file.code.is_empty()
+                && let Some(start) = rest.find(&file.code)
+            {
+                let end = start + file.code.len();
+                body.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+            links.push(Some(id));
+        }
+        body.push_str(rest);
+
+        let total: usize = context.files.iter().map(|file| file.token_count).sum();
+        let mut index = String::new();
+        if markdown {
+            index.push_str(&format!(
+                "Table of Files ({} files, {} tokens):\n\n",
+                context.files.len(),
+                total
+            ));
+            for (file, link) in context.files.iter().zip(&links) {
+                match link {
+                    Some(id) => index.push_str(&format!("- [{}](#{})", file.path, id)),
+                    None => index.push_str(&format!("- {}", file.path)),
+                }
+                index.push_str(&format!(" ({} tokens)\n", file.token_count));
+            }
+        } else {
+            index.push_str(&format!(
+                "<table-of-files files=\"{}\" tokens=\"{}\">\n",
+                context.files.len(),
+                total
+            ));
+            for (file, link) in context.files.iter().zip(&links) {
+                index.push_str(&format!("  <file path=\"{}\"", file.path));
+                if let Some(id) = link {
+                    index.push_str(&format!(" ref=\"{}\"", id));
+                }
+                index.push_str(&format!(" tokens=\"{}\" />\n", file.token_count));
+            }
+            index.push_str("</table-of-files>\n");
+        }
+        Ok(format!("{}\n{}", index, body))
+    }
+}
+
+/// Appends a footer with the number of files and tokens of the prompt.
+#[derive(Debug)]
+pub struct Footer;
+
+impl PostProcessor for Footer {
+    fn process(&self, prompt: &str, context: &PostProcessContext) -> Result<String> {
+        let stats = format!(
+            "{} {}, {} tokens ({})",
+            context.files.len(),
+            if context.files.len() == 1 {
+                "file"
+            } else {
+                "files"
+            },
+            context.token_count,
+            context.model_info
+        );
+        let footer = match context.output_format {
+            OutputFormat::Markdown => format!("---\nGenerated by code2prompt: {}", stats),
+            OutputFormat::Xml | OutputFormat::Json | OutputFormat::Jsonl | OutputFormat::Chunks => {
+                format!(
+                    "<generated-by tool=\"code2prompt\">{}</generated-by>",
+                    stats
+                )
+            }
+        };
+        Ok(format!("{}\n\n{}\n", prompt.trim_end(), footer))
+    }
+}
+
+/// A Markdown header, outside of code blocks
+struct Header {
+    /// Index of the line of the header
+    line: usize,
+    level: usize,
+    title: String,
+}
+
+/// The ATX headers of a Markdown text, skipping code blocks, whose `#` are usually comments.
+fn markdown_headers(text: &str) -> Vec<Header> {
+    let mut headers = Vec::new();
+    let mut fence: Option<&str> = None;
+    for (index, line) in text.split('\n').enumerate() {
+        let trimmed = line.trim_start();
+        match fence {
+            Some(marker) => {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                continue;
+            }
+            None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                fence = Some(&trimmed[..3]);
+                continue;
+            }
+            None => {}
+        }
+
+        let level = line.chars().take_while(|c| *c == '#').count();
+        if !(1..=6).contains(&level) {
+            continue;
+        }
+        let rest = &line[level..];
+        if !rest.starts_with(' ') || rest.trim().is_empty() {
+            continue;
+        }
+        headers.push(Header {
+            line: index,
+            level,
+            title: rest.trim().trim_end_matches('#').trim_end().to_string(),
+        });
+    }
+    headers
+}
+
+/// The position of the first occurrence of `marker` starting a line, indentation aside.
+fn find_at_line_start(text: &str, marker: &str) -> Option<usize> {
+    text.match_indices(marker)
+        .map(|(index, _)| index)
+        .find(|&index| {
+            let before = text[..index].trim_end_matches([' ', '\t']);
+            before.is_empty() || before.ends_with('\n')
+        })
+}
+
+/// The anchor of a file: `file-` then its path, lowercase, with dashes for other characters.
+fn file_anchor(path: &str) -> String {
+    let slug: String = path
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("file-{}", slug)
+}
+
+/// The anchor GitHub gives a header: lowercase, punctuation removed, spaces as dashes.
+fn anchor(title: &str) -> String {
+    title
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
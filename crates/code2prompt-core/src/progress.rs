@@ -0,0 +1,65 @@
+//! This module reports the progress of prompt generation, so that interfaces can show a
+//! progress bar on large repositories and library consumers can report where a long run
+//! stands.
+//!
+//! A session reports [`ProgressEvent`]s to the [`ProgressListener`] set with
+//! `Code2PromptSession::set_progress_listener`. A channel sender is a listener, so the
+//! events can be received on another thread; [`ProgressFn`] turns a closure into one.
+
+use serde::Serialize;
+use std::fmt;
+use std::sync::mpsc::Sender;
+
+/// A step of prompt generation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ProgressEvent {
+    /// The codebase is being walked
+    TraversalStarted,
+    /// The walk found the files to read
+    FilesFound { count: usize },
+    /// A file was read and its tokens counted. Files are read in parallel, so the events
+    /// do not come in the order of the files.
+    FileLoaded { path: String, tokens: usize },
+    /// The git sections enabled in the configuration were loaded
+    GitDiffLoaded,
+    /// The template is being rendered
+    RenderStarted,
+    /// The prompt is ready
+    Done { token_count: usize },
+}
+
+/// Receives the progress of prompt generation.
+///
+/// Listeners are called from the threads reading the files, so they must be `Send` and
+/// `Sync`, and they must be `Debug` so that a session holding one can still be printed.
+pub trait ProgressListener: Send + Sync + fmt::Debug {
+    /// Handles a step of prompt generation. It should return quickly, as it delays the
+    /// generation.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The step reached
+    fn on_progress(&self, event: ProgressEvent);
+}
+
+impl ProgressListener for Sender<ProgressEvent> {
+    fn on_progress(&self, event: ProgressEvent) {
+        // A receiver gone away no longer wants the events
+        let _ = self.send(event);
+    }
+}
+
+/// A listener calling a closure
+pub struct ProgressFn<F>(pub F);
+
+impl<F: Fn(ProgressEvent) + Send + Sync> ProgressListener for ProgressFn<F> {
+    fn on_progress(&self, event: ProgressEvent) {
+        (self.0)(event)
+    }
+}
+
+impl<F> fmt::Debug for ProgressFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressFn")
+    }
+}
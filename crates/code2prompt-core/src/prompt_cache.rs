@@ -0,0 +1,56 @@
+//! This module formats the rendered prompt for provider prompt caching.
+//!
+//! The codebase is emitted first, as a stable prefix, followed by the volatile sections
+//! such as the instruction. For providers with explicit cache breakpoints, the codebase
+//! block is marked as cacheable so it is reused across requests while the instruction varies.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// The prompt caching convention to follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptCacheProvider {
+    /// Anthropic messages format with an explicit `cache_control` breakpoint.
+    Anthropic,
+    /// OpenAI chat messages format, which caches stable prefixes automatically.
+    OpenAi,
+}
+
+impl std::fmt::Display for PromptCacheProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PromptCacheProvider::Anthropic => write!(f, "anthropic"),
+            PromptCacheProvider::OpenAi => write!(f, "openai"),
+        }
+    }
+}
+
+/// Builds a messages request body with the codebase as a cacheable prefix.
+///
+/// # Arguments
+///
+/// * `provider` - The prompt caching convention to follow.
+/// * `cacheable` - The stable part of the prompt, typically the rendered codebase.
+/// * `volatile` - The part of the prompt that changes between requests, may be empty.
+///
+/// # Returns
+///
+/// * `Value` - A JSON object with a `messages` array.
+pub fn cached_messages(provider: PromptCacheProvider, cacheable: &str, volatile: &str) -> Value {
+    let mut codebase_block = json!({ "type": "text", "text": cacheable });
+    if provider == PromptCacheProvider::Anthropic {
+        codebase_block["cache_control"] = json!({ "type": "ephemeral" });
+    }
+
+    let mut content = vec![codebase_block];
+    if !volatile.is_empty() {
+        content.push(json!({ "type": "text", "text": volatile }));
+    }
+
+    json!({
+        "messages": [
+            { "role": "user", "content": content }
+        ]
+    })
+}
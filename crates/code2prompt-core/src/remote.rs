@@ -0,0 +1,131 @@
+//! Remote repositories given in place of the path of the codebase.
+//!
+//! A path such as `https://github.com/org/repo#branch` names a repository that is not
+//! cloned locally. It is shallow-cloned to a temporary directory, the codebase is read
+//! from there, and the directory is removed once the session no longer needs it.
+//!
+//! The URLs read are those of the `http`, `https`, `git` and `file` schemes; SSH needs
+//! credentials and is not supported.
+
+use anyhow::{Context, Result};
+use git2::{FetchOptions, build::RepoBuilder};
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCHEMES: [&str; 5] = ["https://", "http://", "git://", "file://", "ssh://"];
+
+/// A repository named by its URL, and the branch to check out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteRepository {
+    pub url: String,
+    /// The branch after the `#` of the URL, the default branch otherwise
+    pub branch: Option<String>,
+}
+
+impl RemoteRepository {
+    /// Reads a path given for the codebase as a repository URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the codebase, as given
+    ///
+    /// # Returns
+    ///
+    /// * `Option<RemoteRepository>` - `None` if the path is not a URL
+    pub fn parse(path: &Path) -> Option<Self> {
+        let path = path.to_str()?;
+        if !SCHEMES.iter().any(|scheme| path.starts_with(scheme)) {
+            return None;
+        }
+        let (url, branch) = match path.split_once('#') {
+            Some((url, branch)) if !branch.is_empty() => (url, Some(branch.to_string())),
+            Some((url, _)) => (url, None),
+            None => (path, None),
+        };
+        Some(Self {
+            url: url.trim_end_matches('/').to_string(),
+            branch,
+        })
+    }
+
+    /// The name of the repository, the last segment of its URL without `.git`.
+    pub fn name(&self) -> &str {
+        let name = self.url.rsplit('/').next().unwrap_or_default();
+        let name = name.strip_suffix(".git").unwrap_or(name);
+        if name.is_empty() { "repository" } else { name }
+    }
+
+    /// Clones the last commit of the branch to a new temporary directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ClonedRepository>` - The checkout, removed when dropped
+    pub fn clone_to_temp(&self) -> Result<ClonedRepository> {
+        if self.url.starts_with("ssh://") {
+            anyhow::bail!(
+                "Cloning {} needs SSH, which is not supported: use its https URL",
+                self.url
+            );
+        }
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.subsec_nanos())
+            .unwrap_or_default();
+        let root = std::env::temp_dir().join(format!(
+            "code2prompt-clone-{}-{}",
+            std::process::id(),
+            nanos
+        ));
+        // The checkout is named after the repository, which names the codebase
+        let checkout = ClonedRepository {
+            path: root.join(self.name()),
+            root,
+        };
+
+        info!("Cloning {} to {}", self.url, checkout.path.display());
+        let mut fetch_options = FetchOptions::new();
+        // The local transport has no shallow fetch
+        if !self.url.starts_with("file://") {
+            fetch_options.depth(1);
+        }
+        let mut builder = RepoBuilder::new();
+        builder.fetch_options(fetch_options);
+        if let Some(branch) = &self.branch {
+            builder.branch(branch);
+        }
+        builder
+            .clone(&self.url, &checkout.path)
+            .with_context(|| match &self.branch {
+                Some(branch) => format!("Failed to clone branch {} of {}", branch, self.url),
+                None => format!("Failed to clone {}", self.url),
+            })?;
+        Ok(checkout)
+    }
+}
+
+/// A temporary checkout of a remote repository, removed when dropped
+#[derive(Debug)]
+pub struct ClonedRepository {
+    /// The temporary directory holding the checkout
+    root: PathBuf,
+    /// The checkout
+    path: PathBuf,
+}
+
+impl ClonedRepository {
+    /// The root of the checkout.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ClonedRepository {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.root)
+            && self.root.exists()
+        {
+            warn!("Failed to remove the clone {}: {}", self.root.display(), e);
+        }
+    }
+}
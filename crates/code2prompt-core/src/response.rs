@@ -0,0 +1,543 @@
+//! This module parses common LLM response conventions into typed edits.
+//! It understands fenced code blocks annotated with a file path, unified diffs and
+//! search/replace blocks, and maps each of them to a path relative to the repository.
+
+use anyhow::{Result, anyhow, bail};
+use std::path::{Component, Path, PathBuf};
+
+/// A single file edit suggested by an LLM response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// Replace the whole content of a file, creating it if needed.
+    WriteFile { path: PathBuf, content: String },
+    /// Apply unified diff hunks to a file. New files are patched from an empty content.
+    Patch { path: PathBuf, hunks: Vec<Hunk> },
+    /// Replace the first occurrence of `search` with `replace`.
+    /// An empty `search` appends `replace` to the file.
+    SearchReplace {
+        path: PathBuf,
+        search: String,
+        replace: String,
+    },
+    /// Delete the file.
+    DeleteFile { path: PathBuf },
+}
+
+/// A hunk of a unified diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The 1-based line in the original file where the hunk starts, used as a hint.
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+/// A line of a unified diff hunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(String),
+    Added(String),
+    Removed(String),
+}
+
+impl Edit {
+    /// Returns the repository relative path targeted by the edit.
+    pub fn path(&self) -> &Path {
+        match self {
+            Edit::WriteFile { path, .. }
+            | Edit::Patch { path, .. }
+            | Edit::SearchReplace { path, .. }
+            | Edit::DeleteFile { path } => path,
+        }
+    }
+
+    /// Resolves the edit path against the repository root.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The repository root directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PathBuf>` - The absolute path, or an error if the path escapes the root.
+    pub fn resolve(&self, root: &Path) -> Result<PathBuf> {
+        let path = self.path();
+        let escapes = path.components().any(|c| {
+            matches!(
+                c,
+                Component::ParentDir | Component::RootDir | Component::Prefix(_)
+            )
+        });
+        if escapes || path.as_os_str().is_empty() {
+            bail!("Edit path {} is not inside the repository", path.display());
+        }
+        Ok(root.join(path))
+    }
+
+    /// Computes the new content of the targeted file.
+    ///
+    /// # Arguments
+    ///
+    /// * `original` - The current content of the file, or `None` if it does not exist.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<String>>` - The new content, `None` when the file must be deleted.
+    pub fn apply(&self, original: Option<&str>) -> Result<Option<String>> {
+        let original = original.unwrap_or("");
+        match self {
+            Edit::WriteFile { content, .. } => Ok(Some(content.clone())),
+            Edit::DeleteFile { .. } => Ok(None),
+            Edit::Patch { hunks, .. } => apply_hunks(original, hunks).map(Some),
+            Edit::SearchReplace {
+                path,
+                search,
+                replace,
+            } => {
+                if search.is_empty() {
+                    let mut content = original.to_string();
+                    if !content.is_empty() && !content.ends_with('\n') {
+                        content.push('\n');
+                    }
+                    content.push_str(replace);
+                    content.push('\n');
+                    Ok(Some(content))
+                } else if original.contains(search.as_str()) {
+                    Ok(Some(original.replacen(search.as_str(), replace, 1)))
+                } else {
+                    Err(anyhow!(
+                        "Search block not found in {}:\n{}",
+                        path.display(),
+                        search
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// Parses an LLM response into a list of edits, in the order they appear.
+///
+/// The following conventions are recognized:
+/// - fenced code blocks whose info string or preceding line names a file path,
+/// - unified diffs, fenced or not,
+/// - `<<<<<<< SEARCH` / `=======` / `>>>>>>> REPLACE` blocks.
+///
+/// # Arguments
+///
+/// * `response` - The raw LLM response.
+///
+/// # Returns
+///
+/// * `Vec<Edit>` - The edits found in the response.
+pub fn parse_response(response: &str) -> Vec<Edit> {
+    let lines: Vec<&str> = response.lines().collect();
+    let mut edits = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        if let Some((fence, info)) = fence_open(lines[i]) {
+            let start = i + 1;
+            let mut end = start;
+            while end < lines.len() && !is_fence_close(lines[end], fence) {
+                end += 1;
+            }
+            let lang = info.split_whitespace().next().unwrap_or("");
+            let path = path_from_info(info).or_else(|| path_from_header(&lines[..i]));
+            edits.extend(parse_block(lang, path, &lines[start..end]));
+            i = end + 1;
+        } else if is_diff_start(&lines, i) {
+            let end = diff_end(&lines, i);
+            edits.extend(parse_unified_diff(&lines[i..end], None));
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+
+    edits
+}
+
+/// Applies unified diff hunks to a text.
+///
+/// Hunks are located using their context lines, starting at the line number hint and
+/// falling back to the closest match, since LLMs rarely produce exact line numbers.
+///
+/// # Arguments
+///
+/// * `original` - The original text.
+/// * `hunks` - The hunks to apply, in order.
+///
+/// # Returns
+///
+/// * `Result<String>` - The patched text, or an error if a hunk does not match.
+pub fn apply_hunks(original: &str, hunks: &[Hunk]) -> Result<String> {
+    let mut lines: Vec<String> = original.lines().map(String::from).collect();
+    let trailing_newline = original.is_empty() || original.ends_with('\n');
+    let mut offset: isize = 0;
+    let mut cursor = 0;
+
+    for hunk in hunks {
+        let old: Vec<&str> = hunk
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(s) | HunkLine::Removed(s) => Some(s.as_str()),
+                HunkLine::Added(_) => None,
+            })
+            .collect();
+        let new: Vec<String> = hunk
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(s) | HunkLine::Added(s) => Some(s.clone()),
+                HunkLine::Removed(_) => None,
+            })
+            .collect();
+
+        let hint = (hunk.old_start.saturating_sub(1) as isize + offset).max(0) as usize;
+        let pos = find_block(&lines, &old, hint, cursor).ok_or_else(|| {
+            anyhow!(
+                "Hunk starting at line {} does not match the file",
+                hunk.old_start
+            )
+        })?;
+
+        offset += new.len() as isize - old.len() as isize;
+        cursor = pos + new.len();
+        lines.splice(pos..pos + old.len(), new);
+    }
+
+    let mut patched = lines.join("\n");
+    if trailing_newline && !lines.is_empty() {
+        patched.push('\n');
+    }
+    Ok(patched)
+}
+
+/// Finds the position of `block` in `lines` at or after `cursor`, preferring `hint`.
+/// Trailing whitespace is ignored when comparing lines.
+fn find_block(lines: &[String], block: &[&str], hint: usize, cursor: usize) -> Option<usize> {
+    if block.is_empty() {
+        return Some(hint.clamp(cursor, lines.len().max(cursor)).min(lines.len()));
+    }
+    if block.len() > lines.len() {
+        return None;
+    }
+
+    let matches_at = |pos: usize| {
+        lines[pos..pos + block.len()]
+            .iter()
+            .zip(block)
+            .all(|(a, b)| a.trim_end() == b.trim_end())
+    };
+
+    (cursor..=lines.len() - block.len())
+        .filter(|&pos| matches_at(pos))
+        .min_by_key(|&pos| pos.abs_diff(hint))
+}
+
+/// Returns the fence marker and info string if the line opens a fenced code block.
+fn fence_open(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let count = trimmed.chars().take_while(|c| *c == marker).count();
+    if count < 3 {
+        return None;
+    }
+    Some((&trimmed[..count], trimmed[count..].trim()))
+}
+
+/// Returns true if the line closes a fenced code block opened with `fence`.
+fn is_fence_close(line: &str, fence: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with(fence) && trimmed.chars().all(|c| fence.starts_with(c))
+}
+
+/// Extracts a file path from a fence info string such as `rust src/main.rs`,
+/// `rust:src/main.rs` or `python title="app.py"`.
+fn path_from_info(info: &str) -> Option<PathBuf> {
+    info.split_whitespace().find_map(|token| {
+        let token = token
+            .split_once('=')
+            .map(|(_, value)| value)
+            .unwrap_or(token);
+        let token = token
+            .split_once(':')
+            .map(|(_, value)| value)
+            .unwrap_or(token);
+        let token = token.trim_matches(|c| c == '"' || c == '\'');
+        looks_like_path(token).then(|| normalize_path(token))
+    })
+}
+
+/// Extracts a file path from the line preceding a fenced code block, such as
+/// `src/main.rs`, `**src/main.rs**`, `### File: src/main.rs` or ``Update `src/main.rs`:``.
+fn path_from_header(previous: &[&str]) -> Option<PathBuf> {
+    let line = previous
+        .iter()
+        .rev()
+        .take(2)
+        .find(|line| !line.trim().is_empty())?
+        .trim();
+
+    // A path quoted in backticks anywhere in the line
+    let quoted: Vec<&str> = line.split('`').skip(1).step_by(2).collect();
+    if quoted.len() == 1 && looks_like_path(quoted[0]) {
+        return Some(normalize_path(quoted[0]));
+    }
+
+    let mut header = line.trim_start_matches('#').trim();
+    for prefix in ["file:", "filename:", "path:"] {
+        if let Some(head) = header.get(..prefix.len())
+            && head.eq_ignore_ascii_case(prefix)
+        {
+            header = header[prefix.len()..].trim();
+        }
+    }
+    let header = header
+        .trim_end_matches(':')
+        .trim_matches(|c| c == '*' || c == '`' || c == '_' || c == '"')
+        .trim_end_matches(':');
+    looks_like_path(header).then(|| normalize_path(header))
+}
+
+/// Returns true if the token looks like a file path rather than a language or a word.
+fn looks_like_path(token: &str) -> bool {
+    !token.is_empty()
+        && !token.contains("://")
+        && !token.ends_with('.')
+        && (token.contains('/') || token.contains('.'))
+        && token
+            .chars()
+            .all(|c| c.is_alphanumeric() || "._-/\\@+".contains(c))
+}
+
+/// Normalizes a path produced by an LLM into a repository relative path.
+fn normalize_path(raw: &str) -> PathBuf {
+    let mut path = raw.trim().replace('\\', "/");
+    while let Some(stripped) = path.strip_prefix("./") {
+        path = stripped.to_string();
+    }
+    PathBuf::from(path)
+}
+
+/// Parses the body of a fenced code block.
+fn parse_block(lang: &str, path: Option<PathBuf>, body: &[&str]) -> Vec<Edit> {
+    if body.iter().any(|line| is_search_marker(line)) {
+        // The path may be given as the first line inside the block
+        let path = path.or_else(|| {
+            body.first()
+                .map(|line| line.trim())
+                .filter(|line| looks_like_path(line))
+                .map(normalize_path)
+        });
+        return path
+            .map(|path| parse_search_replace(body, &path))
+            .unwrap_or_default();
+    }
+
+    let first = body.iter().find(|line| !line.trim().is_empty());
+    let is_diff = lang == "diff"
+        || lang == "patch"
+        || first.is_some_and(|line| line.starts_with("diff --git") || line.starts_with("--- "));
+    if is_diff {
+        return parse_unified_diff(body, path);
+    }
+
+    match path {
+        Some(path) => {
+            let mut content = body.join("\n");
+            content.push('\n');
+            vec![Edit::WriteFile { path, content }]
+        }
+        None => Vec::new(),
+    }
+}
+
+fn is_search_marker(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with("<<<<<<<") && trimmed.ends_with("SEARCH")
+}
+
+fn is_divider_marker(line: &str) -> bool {
+    line.trim() == "======="
+}
+
+fn is_replace_marker(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with(">>>>>>>") && trimmed.ends_with("REPLACE")
+}
+
+/// Parses search/replace blocks targeting a single file.
+fn parse_search_replace(body: &[&str], path: &Path) -> Vec<Edit> {
+    enum State {
+        Outside,
+        Search,
+        Replace,
+    }
+
+    let mut edits = Vec::new();
+    let mut state = State::Outside;
+    let mut search: Vec<&str> = Vec::new();
+    let mut replace: Vec<&str> = Vec::new();
+
+    for line in body {
+        match state {
+            State::Outside if is_search_marker(line) => {
+                search.clear();
+                replace.clear();
+                state = State::Search;
+            }
+            State::Outside => {}
+            State::Search if is_divider_marker(line) => state = State::Replace,
+            State::Search => search.push(line),
+            State::Replace if is_replace_marker(line) => {
+                edits.push(Edit::SearchReplace {
+                    path: path.to_path_buf(),
+                    search: search.join("\n"),
+                    replace: replace.join("\n"),
+                });
+                state = State::Outside;
+            }
+            State::Replace => replace.push(line),
+        }
+    }
+
+    edits
+}
+
+/// Returns true if a unified diff starts at line `i`.
+fn is_diff_start(lines: &[&str], i: usize) -> bool {
+    lines[i].starts_with("diff --git")
+        || (lines[i].starts_with("--- ")
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| next.starts_with("+++ ")))
+}
+
+/// Returns the index of the first line after the unfenced unified diff starting at `i`.
+fn diff_end(lines: &[&str], i: usize) -> usize {
+    const PREFIXES: [&str; 13] = [
+        "diff ",
+        "index ",
+        "--- ",
+        "+++ ",
+        "@@",
+        " ",
+        "+",
+        "-",
+        "\\",
+        "new file mode",
+        "deleted file mode",
+        "similarity",
+        "rename ",
+    ];
+    let mut end = i + 1;
+    while end < lines.len() {
+        let line = lines[end];
+        let continues = if line.is_empty() {
+            // Blank lines are context lines only if the diff continues after them
+            lines.get(end + 1).is_some_and(|next| {
+                !next.is_empty() && PREFIXES.iter().any(|p| next.starts_with(p))
+            })
+        } else {
+            PREFIXES.iter().any(|p| line.starts_with(p))
+        };
+        if !continues {
+            break;
+        }
+        end += 1;
+    }
+    end
+}
+
+/// Strips the `a/` or `b/` prefix of a unified diff path and any trailing timestamp.
+fn diff_path(raw: &str) -> Option<PathBuf> {
+    let raw = raw.split('\t').next().unwrap_or(raw).trim();
+    if raw == "/dev/null" {
+        return None;
+    }
+    let raw = raw
+        .strip_prefix("a/")
+        .or_else(|| raw.strip_prefix("b/"))
+        .unwrap_or(raw);
+    Some(normalize_path(raw))
+}
+
+/// Parses the hunk header `@@ -a,b +c,d @@` and returns the old start line.
+fn hunk_old_start(line: &str) -> usize {
+    line.trim_start_matches('@')
+        .trim()
+        .strip_prefix('-')
+        .and_then(|range| range.split([',', ' ']).next())
+        .and_then(|start| start.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Parses a unified diff that may touch several files.
+fn parse_unified_diff(lines: &[&str], default_path: Option<PathBuf>) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let mut path = default_path;
+    let mut hunks: Vec<Hunk> = Vec::new();
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.starts_with("diff --git") {
+            flush_patch(&mut edits, &path, &mut hunks);
+            path = line.split_whitespace().nth(3).and_then(diff_path);
+        } else if line.starts_with("--- ")
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| next.starts_with("+++ "))
+        {
+            flush_patch(&mut edits, &path, &mut hunks);
+            path = diff_path(&lines[i + 1][4..]);
+            if path.is_none()
+                && let Some(old) = diff_path(&line[4..])
+            {
+                edits.push(Edit::DeleteFile { path: old });
+            }
+            i += 1;
+        } else if line.starts_with("@@") {
+            hunks.push(Hunk {
+                old_start: hunk_old_start(line),
+                lines: Vec::new(),
+            });
+        } else if let Some(hunk) = hunks.last_mut() {
+            if let Some(rest) = line.strip_prefix('+') {
+                hunk.lines.push(HunkLine::Added(rest.to_string()));
+            } else if let Some(rest) = line.strip_prefix('-') {
+                hunk.lines.push(HunkLine::Removed(rest.to_string()));
+            } else if let Some(rest) = line.strip_prefix(' ') {
+                hunk.lines.push(HunkLine::Context(rest.to_string()));
+            } else if line.is_empty() {
+                // LLMs often drop the leading space of empty context lines
+                hunk.lines.push(HunkLine::Context(String::new()));
+            }
+        }
+        i += 1;
+    }
+
+    flush_patch(&mut edits, &path, &mut hunks);
+    edits
+}
+
+/// Pushes the hunks collected for `path` as a patch edit. Hunks of deleted files,
+/// which have no target path, are dropped.
+fn flush_patch(edits: &mut Vec<Edit>, path: &Option<PathBuf>, hunks: &mut Vec<Hunk>) {
+    let mut hunks = std::mem::take(hunks);
+    for hunk in &mut hunks {
+        while hunk.lines.last() == Some(&HunkLine::Context(String::new())) {
+            hunk.lines.pop();
+        }
+    }
+    if let Some(path) = path
+        && !hunks.is_empty()
+    {
+        edits.push(Edit::Patch {
+            path: path.clone(),
+            hunks,
+        });
+    }
+}
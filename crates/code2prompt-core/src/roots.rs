@@ -0,0 +1,96 @@
+//! This module renders several codebase roots in the same prompt, such as two sibling
+//! packages of a monorepo.
+//!
+//! Each root is traversed on its own, labeled with its directory name: the label prefixes
+//! the paths of its files and names it in the tree, the trees of the roots following one
+//! another. The selections made by hand apply to the first root only, the patterns to all.
+
+use crate::configuration::Code2PromptConfig;
+use crate::path::{FileEntry, display_name, traverse_directory};
+use crate::selection::SelectionEngine;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// A root of the codebase, as exposed to the templates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RootSummary {
+    /// The label prefixing the paths of the files of the root
+    pub name: String,
+    /// The path of the root, as given
+    pub path: String,
+    /// Number of files of the root in the prompt
+    pub file_count: usize,
+    /// Tokens of the files of the root
+    pub token_count: usize,
+}
+
+/// The roots of a configuration, the root path first, and their labels, the directory
+/// names made unique by a numeric suffix.
+///
+/// # Arguments
+///
+/// * `config` - The configuration giving the root path and the extra paths
+///
+/// # Returns
+///
+/// * `Vec<(PathBuf, String)>` - Each root and its label
+pub fn labeled_roots(config: &Code2PromptConfig) -> Vec<(PathBuf, String)> {
+    let mut labeled: Vec<(PathBuf, String)> = Vec::new();
+    for root in std::iter::once(&config.path).chain(&config.extra_paths) {
+        let name = display_name(root.canonicalize().unwrap_or_else(|_| root.clone()));
+        let mut label = name.clone();
+        let mut suffix = 2;
+        while labeled.iter().any(|(_, taken)| *taken == label) {
+            label = format!("{}-{}", name, suffix);
+            suffix += 1;
+        }
+        labeled.push((root.clone(), label));
+    }
+    labeled
+}
+
+/// Traverses every root of the configuration, merging their trees and files.
+///
+/// # Arguments
+///
+/// * `config` - The configuration, whose `extra_paths` are the roots after `path`
+/// * `selection_engine` - The selections made by hand, applied to the first root
+///
+/// # Returns
+///
+/// * `Result<(String, Vec<FileEntry>, Vec<RootSummary>)>` - The trees of the roots one
+///   after the other, the files of the roots in order and the summary of each root
+pub fn traverse_roots(
+    config: &Code2PromptConfig,
+    mut selection_engine: Option<&mut SelectionEngine>,
+) -> Result<(String, Vec<FileEntry>, Vec<RootSummary>)> {
+    let mut trees = Vec::new();
+    let mut files = Vec::new();
+    let mut summaries = Vec::new();
+    for (index, (root, label)) in labeled_roots(config).into_iter().enumerate() {
+        let root_config = Code2PromptConfig {
+            path: root.clone(),
+            extra_paths: Vec::new(),
+            path_alias: Some(label.clone()),
+            ..config.clone()
+        };
+        let engine = if index == 0 {
+            selection_engine.as_deref_mut()
+        } else {
+            None
+        };
+        let (tree, root_files) = traverse_directory(&root_config, engine)
+            .with_context(|| format!("Failed to traverse {}", root.display()))?;
+
+        summaries.push(RootSummary {
+            name: label,
+            path: root.to_string_lossy().into_owned(),
+            file_count: root_files.len(),
+            token_count: root_files.iter().map(|file| file.token_count).sum(),
+        });
+        trees.push(tree.trim_end().to_string());
+        files.extend(root_files);
+    }
+    Ok((trees.join("\n"), files, summaries))
+}
@@ -3,8 +3,12 @@
 //! The SelectionEngine implements the A,A',B,B' system where:
 //! - A, B: Base patterns (handled by FilterEngine)
 //! - A', B': User actions with precedence rules (specific > generic, recent > old)
+//!
+//! A file is either included in full, listed in the source tree only, or excluded.
 
-use crate::filter::FilterEngine;
+use crate::filter::{FilterEngine, build_globset, should_include_file};
+use globset::GlobSet;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
@@ -19,10 +23,62 @@ pub struct SelectionAction {
 }
 
 /// Type of selection action
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ActionType {
     Include,
     Exclude,
+    /// List the file in the source tree without its content
+    #[serde(rename = "tree_only")]
+    TreeOnly,
+}
+
+/// How a file is included in the prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileInclusion {
+    /// Listed in the source tree, with its content
+    Full,
+    /// Listed in the source tree, without its content
+    TreeOnly,
+    /// Left out of the prompt
+    Excluded,
+}
+
+impl FileInclusion {
+    /// The next state of the cycle full, tree only, excluded
+    pub fn next(self) -> Self {
+        match self {
+            FileInclusion::Full => FileInclusion::TreeOnly,
+            FileInclusion::TreeOnly => FileInclusion::Excluded,
+            FileInclusion::Excluded => FileInclusion::Full,
+        }
+    }
+
+    /// Whether the file is listed in the source tree
+    pub fn in_tree(self) -> bool {
+        self != FileInclusion::Excluded
+    }
+}
+
+impl From<ActionType> for FileInclusion {
+    fn from(action: ActionType) -> Self {
+        match action {
+            ActionType::Include => FileInclusion::Full,
+            ActionType::Exclude => FileInclusion::Excluded,
+            ActionType::TreeOnly => FileInclusion::TreeOnly,
+        }
+    }
+}
+
+impl From<FileInclusion> for ActionType {
+    fn from(inclusion: FileInclusion) -> Self {
+        match inclusion {
+            FileInclusion::Full => ActionType::Include,
+            FileInclusion::Excluded => ActionType::Exclude,
+            FileInclusion::TreeOnly => ActionType::TreeOnly,
+        }
+    }
 }
 
 /// SelectionEngine handles both pattern-based filtering and user actions
@@ -32,11 +88,14 @@ pub struct SelectionEngine {
     /// Base pattern filtering (A, B in A,A',B,B' system)
     filter_engine: FilterEngine,
 
+    /// Patterns of the files listed in the tree without their content
+    tree_only_globset: GlobSet,
+
     /// User actions (A', B' in A,A',B,B' system)
     user_actions: Vec<SelectionAction>,
 
     /// Cache for performance
-    cache: HashMap<PathBuf, bool>,
+    cache: HashMap<PathBuf, FileInclusion>,
 }
 
 impl SelectionEngine {
@@ -44,42 +103,77 @@ impl SelectionEngine {
     pub fn new(include_patterns: Vec<String>, exclude_patterns: Vec<String>) -> Self {
         Self {
             filter_engine: FilterEngine::new(&include_patterns, &exclude_patterns),
+            tree_only_globset: GlobSet::empty(),
             user_actions: Vec::new(),
             cache: HashMap::new(),
         }
     }
 
-    /// The core decision method: determines if a file should be selected
-    /// Uses precedence rules: specific > generic, recent > old
+    /// Set the patterns of the files listed in the tree without their content
+    pub fn with_tree_only_patterns(mut self, patterns: &[String]) -> Self {
+        self.tree_only_globset = build_globset(patterns);
+        self.cache.clear();
+        self
+    }
+
+    /// Replace the base patterns, keeping the user actions on top of them
+    pub fn set_patterns(&mut self, include_patterns: &[String], exclude_patterns: &[String]) {
+        self.filter_engine = FilterEngine::new(include_patterns, exclude_patterns);
+        self.cache.clear();
+    }
+
+    /// The core decision method: determines if a file should be selected, i.e. included
+    /// with its content. Uses precedence rules: specific > generic, recent > old
     pub fn is_selected(&mut self, path: &Path) -> bool {
+        self.inclusion(path) == FileInclusion::Full
+    }
+
+    /// Determines how a file is included: in full, in the tree only, or not at all
+    pub fn inclusion(&mut self, path: &Path) -> FileInclusion {
         // Check cache first for performance
         if let Some(&cached) = self.cache.get(path) {
             return cached;
         }
 
-        let result = self.compute_selection(path);
+        let result = self.compute_inclusion(path);
         self.cache.insert(path.to_path_buf(), result);
         result
     }
 
-    /// Compute selection without caching
-    fn compute_selection(&self, path: &Path) -> bool {
+    /// Compute inclusion without caching
+    fn compute_inclusion(&self, path: &Path) -> FileInclusion {
         // Rule 1: Find the most specific and recent user action
         if let Some(action) = self.find_applicable_user_action(path) {
-            return action.action == ActionType::Include;
+            return action.action.into();
+        }
+
+        // Rule 2: Tree-only patterns, unless the file is excluded by the exclude patterns
+        if self.tree_only_globset.is_match(path) && !self.filter_engine.is_excluded(path) {
+            return FileInclusion::TreeOnly;
         }
 
-        // Rule 2: Fall back to existing FilterEngine logic (A, B)
-        if self.filter_engine.has_include_patterns() {
+        // Rule 3: Fall back to existing FilterEngine logic (A, B)
+        let selected = if self.filter_engine.has_include_patterns() {
             // If there are include patterns, use them
             self.filter_engine.matches_patterns(path)
         } else {
             // No include patterns: default behavior is to include all files
             // (unless excluded by exclude patterns)
             !self.filter_engine.is_excluded(path)
+        };
+        if selected {
+            FileInclusion::Full
+        } else {
+            FileInclusion::Excluded
         }
     }
 
+    /// The inclusion set by hand for a path, if a user action applies to it
+    pub fn user_inclusion(&self, path: &Path) -> Option<FileInclusion> {
+        self.find_applicable_user_action(path)
+            .map(|action| action.action.into())
+    }
+
     /// Find the most applicable user action using precedence rules
     fn find_applicable_user_action(&self, path: &Path) -> Option<&SelectionAction> {
         let applicable_actions: Vec<&SelectionAction> = self
@@ -135,6 +229,17 @@ impl SelectionEngine {
         self.add_user_action(path, ActionType::Exclude);
     }
 
+    /// User interaction: set how a file or directory is included
+    pub fn set_inclusion(&mut self, path: PathBuf, inclusion: FileInclusion) {
+        self.add_user_action(path, inclusion.into());
+    }
+
+    /// User interaction: cycle a file or directory through full, tree only and excluded
+    pub fn cycle_inclusion(&mut self, path: PathBuf) {
+        let next = self.inclusion(&path).next();
+        self.set_inclusion(path, next);
+    }
+
     /// User interaction: toggle selection state
     pub fn toggle_file(&mut self, path: PathBuf) {
         let current_state = self.is_selected(&path);
@@ -227,6 +332,11 @@ impl SelectionEngine {
         self.cache.clear();
     }
 
+    /// Get the user actions, from the oldest to the most recent
+    pub fn user_actions(&self) -> &[SelectionAction] {
+        &self.user_actions
+    }
+
     /// Get the number of user actions
     pub fn user_action_count(&self) -> usize {
         self.user_actions.len()
@@ -243,10 +353,27 @@ impl SelectionEngine {
     }
 }
 
+/// How the base patterns alone include a file, for the traversals without a SelectionEngine
+pub(crate) fn pattern_inclusion(
+    path: &Path,
+    include_globset: &GlobSet,
+    exclude_globset: &GlobSet,
+    tree_only_globset: &GlobSet,
+) -> FileInclusion {
+    if tree_only_globset.is_match(path) && !exclude_globset.is_match(path) {
+        FileInclusion::TreeOnly
+    } else if should_include_file(path, include_globset, exclude_globset) {
+        FileInclusion::Full
+    } else {
+        FileInclusion::Excluded
+    }
+}
+
 impl std::fmt::Debug for SelectionEngine {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SelectionEngine")
             .field("filter_engine", &self.filter_engine)
+            .field("tree_only_globset", &self.tree_only_globset)
             .field("user_actions", &self.user_actions)
             .field("cache_size", &self.cache.len())
             .finish()
@@ -296,4 +423,45 @@ mod tests {
         engine.include_file(PathBuf::from("main.rs"));
         assert!(engine.is_selected(Path::new("main.rs")));
     }
+
+    #[test]
+    fn test_inclusion_cycle() {
+        let mut engine = SelectionEngine::new(vec![], vec![]);
+        assert_eq!(engine.inclusion(Path::new("main.rs")), FileInclusion::Full);
+
+        engine.cycle_inclusion(PathBuf::from("main.rs"));
+        assert_eq!(
+            engine.inclusion(Path::new("main.rs")),
+            FileInclusion::TreeOnly
+        );
+        assert!(!engine.is_selected(Path::new("main.rs")));
+
+        engine.cycle_inclusion(PathBuf::from("main.rs"));
+        assert_eq!(
+            engine.inclusion(Path::new("main.rs")),
+            FileInclusion::Excluded
+        );
+
+        engine.cycle_inclusion(PathBuf::from("main.rs"));
+        assert_eq!(engine.inclusion(Path::new("main.rs")), FileInclusion::Full);
+    }
+
+    #[test]
+    fn test_tree_only_patterns() {
+        let mut engine = SelectionEngine::new(vec![], vec!["*.log".to_string()])
+            .with_tree_only_patterns(&["*.lock".to_string(), "*.log".to_string()]);
+
+        assert_eq!(
+            engine.inclusion(Path::new("Cargo.lock")),
+            FileInclusion::TreeOnly
+        );
+        // Exclude patterns take precedence
+        assert_eq!(
+            engine.inclusion(Path::new("debug.log")),
+            FileInclusion::Excluded
+        );
+        // User actions take precedence over the patterns
+        engine.include_file(PathBuf::from("Cargo.lock"));
+        assert!(engine.is_selected(Path::new("Cargo.lock")));
+    }
 }
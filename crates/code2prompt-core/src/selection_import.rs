@@ -0,0 +1,111 @@
+//! This module reads the selections handed over by other tools, such as an IDE plugin, so
+//! that a curated set of files can be rendered by the CLI or opened in the TUI.
+//!
+//! A selection is a JSON file:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "includes": ["src/**/*.rs"],
+//!   "excludes": ["src/generated/**"],
+//!   "pins": ["src/main.rs"],
+//!   "ranges": { "src/lib.rs": ["10-40", "120-150"] }
+//! }
+//! ```
+//!
+//! Every field but `version` is optional. Paths are relative to the root of the codebase,
+//! absolute paths under the root are accepted too.
+//!
+//! - `includes` and `excludes` are glob patterns added to those of the configuration.
+//! - `pins` are files included whatever the patterns say, and placed as key files.
+//! - `ranges` are the only lines embedded of a file, which is included whatever the
+//!   patterns say.
+
+use crate::line_range::LineRange;
+use crate::session::Code2PromptSession;
+use crate::util::relative_path;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Version of the selection schema read by this version of code2prompt
+pub const SELECTION_SCHEMA_VERSION: u32 = 1;
+
+/// A selection of files made by another tool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SelectionImport {
+    /// Version of the schema the selection was written for
+    pub version: u32,
+    /// Glob patterns of the files to include
+    pub includes: Vec<String>,
+    /// Glob patterns of the files to exclude
+    pub excludes: Vec<String>,
+    /// Files always included, and placed as key files
+    pub pins: Vec<String>,
+    /// Lines to embed of some files, as `START-END` ranges numbered from 1
+    pub ranges: BTreeMap<String, Vec<LineRange>>,
+}
+
+impl SelectionImport {
+    /// Parses a selection from its JSON content.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - An error if the JSON does not follow the schema, or follows a
+    ///   newer version of it
+    pub fn parse(content: &str) -> Result<Self> {
+        let selection: Self = serde_json::from_str(content)?;
+        if selection.version == 0 {
+            bail!(
+                "Missing selection version, expected {}",
+                SELECTION_SCHEMA_VERSION
+            );
+        }
+        if selection.version > SELECTION_SCHEMA_VERSION {
+            bail!(
+                "Selection version {} is newer than the supported version {}, upgrade code2prompt to read it",
+                selection.version,
+                SELECTION_SCHEMA_VERSION
+            );
+        }
+        Ok(selection)
+    }
+
+    /// Loads a selection from a JSON file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read selection {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid selection {}", path.display()))
+    }
+
+    /// Applies the selection to a session, on top of its patterns and user selections.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - The session, before its codebase is loaded
+    pub fn apply(&self, session: &mut Code2PromptSession) {
+        let root = session.config.path.clone();
+        let relative = |path: &str| relative_path(&root, Path::new(path));
+
+        let mut includes = session.config.include_patterns.clone();
+        let mut excludes = session.config.exclude_patterns.clone();
+        includes.extend(self.includes.iter().map(|pattern| relative(pattern)));
+        excludes.extend(self.excludes.iter().map(|pattern| relative(pattern)));
+        session.set_patterns(includes, excludes);
+
+        for pin in &self.pins {
+            let pin = relative(pin);
+            if !session.config.key_files.contains(&pin) {
+                session.config.key_files.push(pin.clone());
+            }
+            session.select_file(PathBuf::from(pin));
+        }
+        for (path, ranges) in &self.ranges {
+            for range in ranges {
+                session.include_file_range(PathBuf::from(relative(path)), *range);
+            }
+        }
+    }
+}
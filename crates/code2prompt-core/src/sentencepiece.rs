@@ -0,0 +1,235 @@
+//! SentencePiece tokenizers, as used by Llama, Mistral and Gemma models.
+//!
+//! Counting is done by the Hugging Face `tokenizers` crate. Its `tokenizer.json` files are
+//! loaded as they are. The `tokenizer.model` files distributed with SentencePiece models are
+//! protobuf `ModelProto` messages holding the vocabulary with a score per piece, the model
+//! type (unigram or BPE) and the normalization options: they are converted to the same
+//! pipeline the Hugging Face converters build, a precompiled normalizer, a `▁` metaspace
+//! pre-tokenizer and a unigram or BPE model.
+
+use anyhow::{Context, Result, anyhow};
+use log::warn;
+use prost::Message;
+use std::path::Path;
+use tokenizers::models::bpe::{BPE, Vocab};
+use tokenizers::models::unigram::Unigram;
+use tokenizers::normalizers::replace::ReplacePattern;
+use tokenizers::normalizers::{Precompiled, Replace, Sequence, Strip};
+use tokenizers::pre_tokenizers::metaspace::{Metaspace, PrependScheme};
+use tokenizers::{NormalizerWrapper, Tokenizer as HfTokenizer};
+
+use crate::tokenizer::Tokenizer;
+
+/// The character SentencePiece substitutes for spaces
+const SPACE: char = '\u{2581}';
+
+/// Types of the pieces of the vocabulary
+const PIECE_UNKNOWN: i32 = 2;
+const PIECE_CONTROL: i32 = 3;
+const PIECE_UNUSED: i32 = 5;
+const PIECE_BYTE: i32 = 6;
+
+/// The BPE model type of the trainer spec, unigram being the default
+const MODEL_BPE: i32 = 2;
+
+/// The fields of `sentencepiece_model.proto` that counting needs
+#[derive(Clone, PartialEq, Message)]
+struct ModelProto {
+    #[prost(message, repeated, tag = "1")]
+    pieces: Vec<SentencePiece>,
+    #[prost(message, optional, tag = "2")]
+    trainer_spec: Option<TrainerSpec>,
+    #[prost(message, optional, tag = "3")]
+    normalizer_spec: Option<NormalizerSpec>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct SentencePiece {
+    #[prost(string, optional, tag = "1")]
+    piece: Option<String>,
+    #[prost(float, optional, tag = "2")]
+    score: Option<f32>,
+    #[prost(int32, optional, tag = "3")]
+    piece_type: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TrainerSpec {
+    #[prost(int32, optional, tag = "3")]
+    model_type: Option<i32>,
+    #[prost(bool, optional, tag = "35")]
+    byte_fallback: Option<bool>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct NormalizerSpec {
+    #[prost(bytes = "vec", optional, tag = "2")]
+    precompiled_charsmap: Option<Vec<u8>>,
+    #[prost(bool, optional, tag = "3")]
+    add_dummy_prefix: Option<bool>,
+    #[prost(bool, optional, tag = "4")]
+    remove_extra_whitespaces: Option<bool>,
+}
+
+/// A SentencePiece model loaded from a `tokenizer.model` or `tokenizer.json` file.
+#[derive(Debug, Clone)]
+pub struct SentencePieceTokenizer {
+    tokenizer: HfTokenizer,
+}
+
+impl SentencePieceTokenizer {
+    /// Loads a model from a `tokenizer.model` or `tokenizer.json` file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the model file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<SentencePieceTokenizer>` - The model, or an error if the file cannot be read
+    ///   or is not a SentencePiece model
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read tokenizer model {}", path.display()))?;
+        Self::from_bytes(&data)
+            .with_context(|| format!("Invalid SentencePiece model {}", path.display()))
+    }
+
+    /// Loads a model from the content of a `tokenizer.model` or `tokenizer.json` file.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The serialized `ModelProto`, or the JSON of a Hugging Face tokenizer
+    ///
+    /// # Returns
+    ///
+    /// * `Result<SentencePieceTokenizer>` - The model, or an error if it cannot be decoded
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.trim_ascii_start().starts_with(b"{") {
+            let tokenizer = HfTokenizer::from_bytes(data).map_err(|e| anyhow!(e))?;
+            return Ok(Self { tokenizer });
+        }
+        let proto = ModelProto::decode(data).context("Failed to decode the ModelProto")?;
+        let trainer = proto.trainer_spec.clone().unwrap_or_default();
+        let normalizer = proto.normalizer_spec.clone().unwrap_or_default();
+        let byte_fallback = trainer.byte_fallback.unwrap_or(false);
+
+        let mut tokenizer = if trainer.model_type == Some(MODEL_BPE) {
+            HfTokenizer::new(bpe(&proto, byte_fallback)?)
+        } else {
+            HfTokenizer::new(unigram(&proto, byte_fallback)?)
+        };
+        tokenizer.with_normalizer(normalizers(&normalizer)?);
+        let prepend = if normalizer.add_dummy_prefix.unwrap_or(true) {
+            PrependScheme::Always
+        } else {
+            PrependScheme::Never
+        };
+        tokenizer.with_pre_tokenizer(Some(Metaspace::new(SPACE, prepend, true)));
+        Ok(Self { tokenizer })
+    }
+}
+
+/// The pieces text can be split into, without the control and unused ones
+fn usable_pieces(proto: &ModelProto) -> impl Iterator<Item = (&str, f32, i32)> {
+    proto.pieces.iter().filter_map(|piece| {
+        let piece_type = piece.piece_type.unwrap_or(1);
+        match (&piece.piece, piece_type) {
+            (_, PIECE_CONTROL | PIECE_UNUSED) | (None, _) => None,
+            (Some(text), _) => Some((text.as_str(), piece.score.unwrap_or(0.0), piece_type)),
+        }
+    })
+}
+
+/// The unigram model of the pieces, unknown characters scoring below every piece
+fn unigram(proto: &ModelProto, byte_fallback: bool) -> Result<Unigram> {
+    let mut vocab: Vec<(String, f64)> = Vec::new();
+    let mut unk_id = None;
+    for (text, score, piece_type) in usable_pieces(proto) {
+        if piece_type == PIECE_UNKNOWN {
+            unk_id = Some(vocab.len());
+        }
+        vocab.push((text.to_string(), score as f64));
+    }
+    if unk_id.is_none() {
+        unk_id = Some(vocab.len());
+        vocab.push(("<unk>".to_string(), 0.0));
+    }
+    Unigram::from(vocab, unk_id, byte_fallback).map_err(|e| anyhow!(e))
+}
+
+/// The BPE model of the pieces.
+///
+/// SentencePiece models hold no merges: as the Hugging Face converter does, every split of
+/// a piece into two pieces of the vocabulary is a merge, ranked by the score of the piece.
+fn bpe(proto: &ModelProto, byte_fallback: bool) -> Result<BPE> {
+    let pieces: Vec<(&str, f32, i32)> = usable_pieces(proto).collect();
+    let vocab: Vocab = pieces
+        .iter()
+        .enumerate()
+        .map(|(id, (text, _, _))| (text.to_string(), id as u32))
+        .collect();
+
+    let mut merges: Vec<(f32, u32, u32, &str, &str)> = Vec::new();
+    for (text, score, piece_type) in &pieces {
+        if *piece_type == PIECE_BYTE {
+            continue;
+        }
+        for (split, _) in text.char_indices().skip(1) {
+            let (left, right) = text.split_at(split);
+            if let (Some(left_id), Some(right_id)) = (vocab.get(left), vocab.get(right)) {
+                merges.push((*score, *left_id, *right_id, left, right));
+            }
+        }
+    }
+    merges.sort_by(|a, b| b.0.total_cmp(&a.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+    let merges = merges
+        .into_iter()
+        .map(|(_, _, _, left, right)| (left.to_string(), right.to_string()))
+        .collect();
+
+    let mut builder = BPE::builder()
+        .vocab_and_merges(vocab, merges)
+        .fuse_unk(true)
+        .byte_fallback(byte_fallback);
+    if let Some((unk, _, _)) = pieces.iter().find(|piece| piece.2 == PIECE_UNKNOWN) {
+        builder = builder.unk_token(unk.to_string());
+    }
+    builder.build().map_err(|e| anyhow!(e))
+}
+
+/// The normalizers of the normalizer spec, applied before splitting into words
+fn normalizers(spec: &NormalizerSpec) -> Result<Option<NormalizerWrapper>> {
+    let mut normalizers: Vec<NormalizerWrapper> = Vec::new();
+    if let Some(charsmap) = spec
+        .precompiled_charsmap
+        .as_deref()
+        .filter(|c| !c.is_empty())
+    {
+        let precompiled = Precompiled::from(charsmap)
+            .map_err(|e| anyhow!("Invalid precompiled charsmap: {}", e))?;
+        normalizers.push(precompiled.into());
+    }
+    if spec.remove_extra_whitespaces.unwrap_or(true) {
+        normalizers.push(Strip::new(true, true).into());
+        let spaces = Replace::new(ReplacePattern::Regex(" {2,}".to_string()), " ")
+            .map_err(|e| anyhow!(e))?;
+        normalizers.push(spaces.into());
+    }
+    Ok(match normalizers.len() {
+        0 => None,
+        _ => Some(Sequence::new(normalizers).into()),
+    })
+}
+
+impl Tokenizer for SentencePieceTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        match self.tokenizer.encode(text, false) {
+            Ok(encoding) => encoding.len(),
+            Err(e) => {
+                warn!("Failed to tokenize with the SentencePiece model: {}", e);
+                text.chars().count().div_ceil(4)
+            }
+        }
+    }
+}
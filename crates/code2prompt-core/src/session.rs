@@ -2,16 +2,75 @@
 //! It allows you to load codebase data, Git info, and render prompts using a template.
 
 use anyhow::{Context, Result};
+use handlebars::{Handlebars, HelperDef};
 use serde::Serialize;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
 
-use crate::configuration::Code2PromptConfig;
-use crate::git::{get_git_diff, get_git_diff_between_branches, get_git_log};
-use crate::path::{FileEntry, display_name, traverse_directory, wrap_code_block};
-use crate::selection::SelectionEngine;
-use crate::template::{OutputFormat, handlebars_setup, render_template};
-use crate::tokenizer::{TokenizerType, count_tokens};
+use crate::architecture::{ArchitectureBrief, build_architecture_brief};
+use crate::budget::{BudgetAction, BudgetReport, FILE_OVERHEAD_TOKENS, prune_files, truncate_file};
+use crate::chunk::chunk_file;
+use crate::compression::CompressionLevel;
+use crate::configuration::{Code2PromptConfig, TemplateConfig};
+use crate::conversation::ConversationState;
+use crate::doc_coverage::{DocCoverageReport, analyze_file};
+use crate::duplication::{DuplicateHandling, DuplicationReport, find_duplicates, stub_duplicates};
+use crate::environment::{EnvironmentSpec, detect_environment};
+use crate::frontend::{BuildConfig, build_tool, group_routes, summarize_build_configs};
+use crate::git::{
+    BranchChanges, FileCommit, GitDiffOptions, MergeConflicts, MergedPullRequest,
+    RELEASE_NOTES_EXCLUDE_PATTERNS, VersionInfo, changed_scope, get_branch_changes,
+    get_file_history, get_git_diff_between_branches_with_options, get_git_diff_with_options,
+    get_git_log, get_git_staged_diff, get_git_stash_diff, get_merge_conflicts,
+    get_merged_pull_requests, get_recent_commit_messages, get_untracked_files, get_version_info,
+    head_commit_id, prefix_diff_paths, split_diff_by_file, truncate_diff,
+};
+use crate::git_tree::{repository_name, traverse_git_tree};
+use crate::inclusion_plan::{PlanEntry, inclusion_plan};
+use crate::language::SourceStats;
+use crate::line_range::LineRange;
+use crate::lsp::{LspClient, compress_with_symbols};
+use crate::manifest::{FileChanges, FileManifest};
+use crate::path::{
+    EntryMetadata, FileEntry, FileEstimate, FileStream, build_file_entry, display_name,
+    estimate_directory, fence_language, stream_directory, traverse_directory_with_progress,
+    wrap_code_block,
+};
+use crate::placement::place_key_files;
+use crate::post_process::{PostProcessContext, PostProcessStep, PostProcessor, run_pipeline};
+use crate::progress::{ProgressEvent, ProgressListener};
+use crate::prompt_cache::cached_messages;
+use crate::remote::{ClonedRepository, RemoteRepository};
+use crate::roots::{RootSummary, traverse_roots};
+use crate::selection::{FileInclusion, SelectionEngine};
+use crate::snapshot::SessionSnapshot;
+use crate::sort::FileSortMethod;
+use crate::split::{PromptPart, group_files, part_header};
+use crate::summarize::{FileSummarizer, SummaryCache, summary_block, summary_prompt};
+use crate::tags::{TagSymbol, TagsIndex};
+use crate::template::{
+    OutputFormat, handlebars_setup_with_helpers, instruction_section, nest_variables,
+    register_file_delimiters, render_template, response_contract_section, split_file_section,
+};
+use crate::template_helpers::{HelperRegistry, SectionHelper, TemplateSection};
+use crate::tokenizer::{
+    TokenCountMode, TokenizerType, count_tokens_with_mode, estimate_tokens, prepare_tokenizer,
+};
+use crate::tour::{OnboardingTour, plan_tour};
+use crate::util::{path_key, relative_path};
+
+/// Values of the placeholder file `render_to_writer` renders the template with, for its
+/// path, extension, code and content hash. Private use characters keep them from
+/// appearing in the template text.
+const STREAM_PLACEHOLDERS: [&str; 4] = [
+    "\u{E000}path\u{E000}",
+    "\u{E000}extension\u{E000}",
+    "\u{E000}code\u{E000}",
+    "\u{E000}content_hash\u{E000}",
+];
 
 /// Represents a live session that holds stateful data about the user's codebase,
 /// including which files have been added or removed, or other data that evolves over time.
@@ -20,6 +79,52 @@ pub struct Code2PromptSession {
     pub config: Code2PromptConfig,
     pub selection_engine: SelectionEngine,
     pub data: SessionData,
+    /// Post-processors run after the built-in steps of `config.post_process`
+    pub post_processors: Vec<Arc<dyn PostProcessor>>,
+    /// Template helpers added to the built-in ones
+    pub helpers: HelperRegistry,
+    /// Writes the summaries of the files over `config.summarize_over`
+    pub summarizer: Option<Arc<dyn FileSummarizer>>,
+    /// Receives the progress of prompt generation
+    pub progress: Option<Arc<dyn ProgressListener>>,
+    /// The temporary checkout of the remote repository given as the path, removed with
+    /// the last clone of the session
+    pub checkout: Option<Arc<ClonedRepository>>,
+    /// What `load_codebase` read, kept for the next reloads
+    cache: LoadCache,
+}
+
+/// Results of `load_codebase` kept across reloads, with the inputs they were read from
+#[derive(Debug, Clone, Default)]
+struct LoadCache {
+    tags: Option<CachedTags>,
+    history: Option<CachedHistory>,
+    changed: Option<CachedChanges>,
+}
+
+/// The files changed by the diff, relative to the root, for the branches they were
+/// resolved with
+#[derive(Debug, Clone)]
+struct CachedChanges {
+    diff_branches: Option<(String, String)>,
+    files: HashSet<PathBuf>,
+}
+
+/// The symbols of a tags file, read relative to a root
+#[derive(Debug, Clone)]
+struct CachedTags {
+    tags_file: PathBuf,
+    root: PathBuf,
+    modified: SystemTime,
+    index: Arc<TagsIndex>,
+}
+
+/// The histories of files, by path and number of commits, read at a HEAD commit
+#[derive(Debug, Clone)]
+struct CachedHistory {
+    head: String,
+    options: GitDiffOptions,
+    histories: HashMap<(String, usize), Vec<FileCommit>>,
 }
 
 /// Represents the collected data about the code (tree + files) and optional Git info.
@@ -33,6 +138,23 @@ pub struct SessionData {
     pub git_diff: Option<String>,
     pub git_diff_branch: Option<String>,
     pub git_log_branch: Option<String>,
+    pub conflicts: Option<MergeConflicts>,
+    pub git_stash: Option<String>,
+    pub untracked_files: Option<Vec<String>>,
+    pub recent_commits: Option<Vec<String>>,
+    pub branch_changes: Option<BranchChanges>,
+    pub merged_pull_requests: Option<Vec<MergedPullRequest>>,
+    pub doc_coverage: Option<DocCoverageReport>,
+    pub architecture_brief: Option<ArchitectureBrief>,
+    pub onboarding_tour: Option<OnboardingTour>,
+    pub environment_spec: Option<EnvironmentSpec>,
+    pub build_config: Option<BuildConfig>,
+    pub context_delta: Option<FileChanges>,
+    pub manifest_changes: Option<FileChanges>,
+    pub budget_report: Option<BudgetReport>,
+    pub duplicates: Option<DuplicationReport>,
+    pub version_info: Option<VersionInfo>,
+    pub roots: Option<Vec<RootSummary>>,
 }
 
 /// Zero-copy template context for rendering
@@ -56,8 +178,55 @@ pub struct TemplateContext<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub git_log_branch: &'a Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflicts: &'a Option<MergeConflicts>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_stash: &'a Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub untracked_files: &'a Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recent_commits: &'a Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch_changes: &'a Option<BranchChanges>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_pull_requests: &'a Option<Vec<MergedPullRequest>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc_coverage: &'a Option<DocCoverageReport>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub architecture_brief: &'a Option<ArchitectureBrief>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onboarding_tour: &'a Option<OnboardingTour>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment_spec: &'a Option<EnvironmentSpec>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_config: &'a Option<BuildConfig>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_delta: &'a Option<FileChanges>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest_changes: &'a Option<FileChanges>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_info: &'a Option<VersionInfo>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roots: &'a Option<Vec<RootSummary>>,
+
+    pub config: TemplateConfig<'a>,
+
     #[serde(flatten)]
-    pub user_variables: &'a HashMap<String, String>,
+    pub user_variables: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Encapsulates the final rendered prompt and some metadata
@@ -66,8 +235,67 @@ pub struct RenderedPrompt {
     pub prompt: String,
     pub directory_name: String,
     pub token_count: usize,
+    pub token_breakdown: TokenBreakdown,
     pub model_info: &'static str,
     pub files: Vec<String>,
+    /// The files dropped or truncated to fit `max_tokens`, when a budget is set
+    pub budget: Option<BudgetReport>,
+}
+
+/// Summary of a prompt written by `Code2PromptSession::render_to_writer`
+#[derive(Debug)]
+pub struct StreamedPrompt {
+    pub directory_name: String,
+    /// Token count of the written prompt, from the per-file counts and the template text
+    pub token_count: usize,
+    pub model_info: &'static str,
+    pub files: Vec<String>,
+}
+
+/// Preview of a prompt given by `Code2PromptSession::estimate`, without reading the files
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptEstimate {
+    pub directory_name: String,
+    pub source_tree: String,
+    /// The files that would be included, in the configured sort order
+    pub files: Vec<FileEstimate>,
+    /// Total size of the files in bytes
+    pub total_size: u64,
+    /// Tokens estimated from the sizes of the files, the tree and the template text
+    pub token_count: usize,
+}
+
+/// Token counts of the sections making up a rendered prompt
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TokenBreakdown {
+    /// The source tree
+    pub tree: usize,
+    /// The content of the selected files
+    pub files: usize,
+    /// Git diffs and logs
+    pub git: usize,
+    /// The instruction and response contract appended after the codebase
+    pub instruction: usize,
+    /// Everything else: headers, code fences and template text
+    pub template: usize,
+}
+
+impl TokenBreakdown {
+    /// Total number of tokens across all sections
+    pub fn total(&self) -> usize {
+        self.tree + self.files + self.git + self.instruction + self.template
+    }
+
+    /// Sections as `(name, tokens)` pairs, in prompt order
+    pub fn sections(&self) -> [(&'static str, usize); 5] {
+        [
+            ("Tree", self.tree),
+            ("Files", self.files),
+            ("Git", self.git),
+            ("Instruction", self.instruction),
+            ("Template", self.template),
+        ]
+    }
 }
 
 impl Code2PromptSession {
@@ -76,15 +304,76 @@ impl Code2PromptSession {
         let selection_engine = SelectionEngine::new(
             config.include_patterns.clone(),
             config.exclude_patterns.clone(),
-        );
+        )
+        .with_tree_only_patterns(&config.tree_only_patterns);
 
         Self {
             selection_engine,
             config,
             data: SessionData::default(),
+            post_processors: Vec::new(),
+            helpers: HelperRegistry::default(),
+            summarizer: None,
+            progress: None,
+            checkout: None,
+            cache: LoadCache::default(),
         }
     }
 
+    /// A path given by the user, absolute or relative to the root, made relative to the
+    /// root
+    fn root_relative(&self, path: &Path) -> PathBuf {
+        PathBuf::from(relative_path(&self.config.path, path))
+    }
+
+    /// Clones the repository when the path of the codebase is a URL, as
+    /// `https://github.com/org/repo#branch`, and points the path to the clone. A local
+    /// path is left as is.
+    pub fn resolve_remote(&mut self) -> Result<()> {
+        let Some(remote) = RemoteRepository::parse(&self.config.path) else {
+            return Ok(());
+        };
+        let checkout = remote.clone_to_temp()?;
+        self.config.path = checkout.path().to_path_buf();
+        self.checkout = Some(Arc::new(checkout));
+        Ok(())
+    }
+
+    /// Add a post-processor, run on the rendered prompt after the configured steps
+    pub fn add_post_processor(&mut self, processor: Arc<dyn PostProcessor>) -> &mut Self {
+        self.post_processors.push(processor);
+        self
+    }
+
+    /// Set the summarizer writing the summaries of the files over `config.summarize_over`
+    pub fn set_summarizer(&mut self, summarizer: Arc<dyn FileSummarizer>) -> &mut Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// Set the listener receiving the progress of prompt generation
+    pub fn set_progress_listener(&mut self, listener: Arc<dyn ProgressListener>) -> &mut Self {
+        self.progress = Some(listener);
+        self
+    }
+
+    /// Reports a step of prompt generation to the progress listener, if any
+    fn report_progress(&self, event: ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            progress.on_progress(event);
+        }
+    }
+
+    /// Add a template helper, replacing any built-in or added helper of the same name
+    pub fn register_helper(
+        &mut self,
+        name: &str,
+        helper: Arc<dyn HelperDef + Send + Sync>,
+    ) -> &mut Self {
+        self.helpers.register(name, helper);
+        self
+    }
+
     /// Add pattern and recreate SelectionEngine
     pub fn add_include_pattern(&mut self, pattern: String) -> &mut Self {
         self.config.include_patterns.push(pattern);
@@ -92,7 +381,8 @@ impl Code2PromptSession {
         self.selection_engine = SelectionEngine::new(
             self.config.include_patterns.clone(),
             self.config.exclude_patterns.clone(),
-        );
+        )
+        .with_tree_only_patterns(&self.config.tree_only_patterns);
         self
     }
 
@@ -102,19 +392,27 @@ impl Code2PromptSession {
         self.selection_engine = SelectionEngine::new(
             self.config.include_patterns.clone(),
             self.config.exclude_patterns.clone(),
-        );
+        )
+        .with_tree_only_patterns(&self.config.tree_only_patterns);
+        self
+    }
+
+    /// Replace the include and exclude patterns, keeping the user selections
+    pub fn set_patterns(
+        &mut self,
+        include_patterns: Vec<String>,
+        exclude_patterns: Vec<String>,
+    ) -> &mut Self {
+        self.selection_engine
+            .set_patterns(&include_patterns, &exclude_patterns);
+        self.config.include_patterns = include_patterns;
+        self.config.exclude_patterns = exclude_patterns;
         self
     }
 
     /// User interaction: include a file (delegates to SelectionEngine)
     pub fn select_file(&mut self, path: PathBuf) -> &mut Self {
-        let relative_path = if path.is_absolute() {
-            path.strip_prefix(&self.config.path)
-                .unwrap_or(&path)
-                .to_path_buf()
-        } else {
-            path
-        };
+        let relative_path = self.root_relative(&path);
 
         self.selection_engine.include_file(relative_path);
         self
@@ -122,13 +420,7 @@ impl Code2PromptSession {
 
     /// User interaction: exclude a file (delegates to SelectionEngine)
     pub fn deselect_file(&mut self, path: PathBuf) -> &mut Self {
-        let relative_path = if path.is_absolute() {
-            path.strip_prefix(&self.config.path)
-                .unwrap_or(&path)
-                .to_path_buf()
-        } else {
-            path
-        };
+        let relative_path = self.root_relative(&path);
 
         self.selection_engine.exclude_file(relative_path);
         self
@@ -136,27 +428,93 @@ impl Code2PromptSession {
 
     /// User interaction: toggle file selection (delegates to SelectionEngine)
     pub fn toggle_file_selection(&mut self, path: PathBuf) -> &mut Self {
-        let relative_path = if path.is_absolute() {
-            path.strip_prefix(&self.config.path)
-                .unwrap_or(&path)
-                .to_path_buf()
-        } else {
-            path
-        };
+        let relative_path = self.root_relative(&path);
 
         self.selection_engine.toggle_file(relative_path);
         self
     }
 
-    /// Check if a file is selected (delegates to SelectionEngine)
+    /// Includes only a range of lines of a file, numbered from 1. Several ranges of the
+    /// same file are embedded in order, the lines left out between them being marked.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, absolute or relative to the root
+    /// * `range` - The lines to include
+    pub fn include_file_range(&mut self, path: PathBuf, range: LineRange) -> &mut Self {
+        let relative_path = self.root_relative(&path);
+
+        let key = path_key(&relative_path);
+        let ranges = self.config.line_ranges.entry(key).or_default();
+        if !ranges.contains(&range) {
+            ranges.push(range);
+        }
+        self.selection_engine.include_file(relative_path);
+        self
+    }
+
+    /// Sets the compression level of a file, overriding `compression_level`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, absolute or relative to the root
+    /// * `level` - The compression level of the file
+    pub fn set_file_compression(&mut self, path: PathBuf, level: CompressionLevel) -> &mut Self {
+        let relative_path = self.root_relative(&path);
+
+        let key = path_key(&relative_path);
+        self.config.compression_overrides.insert(key, level);
+        self
+    }
+
+    /// User interaction: set whether a file is included in full, listed in the tree only,
+    /// or excluded (delegates to SelectionEngine)
+    pub fn set_file_inclusion(&mut self, path: PathBuf, inclusion: FileInclusion) -> &mut Self {
+        let relative_path = self.root_relative(&path);
+
+        self.selection_engine
+            .set_inclusion(relative_path, inclusion);
+        self
+    }
+
+    /// User interaction: cycle a file through full, tree only and excluded
+    /// (delegates to SelectionEngine)
+    pub fn cycle_file_inclusion(&mut self, path: PathBuf) -> &mut Self {
+        let relative_path = self.root_relative(&path);
+
+        self.selection_engine.cycle_inclusion(relative_path);
+        self
+    }
+
+    /// How a file is included in the prompt (delegates to SelectionEngine)
+    pub fn file_inclusion(&mut self, path: &std::path::Path) -> FileInclusion {
+        let relative_path = self.root_relative(path);
+
+        self.selection_engine.inclusion(&relative_path)
+    }
+
+    /// Check if a file is selected (delegates to SelectionEngine), among the files changed
+    /// by the diff when `include_only_changed` is set and they were loaded by
+    /// `load_changed_files`
     pub fn is_file_selected(&mut self, path: &std::path::Path) -> bool {
-        let relative_path = if path.is_absolute() {
-            path.strip_prefix(&self.config.path).unwrap_or(path)
-        } else {
-            path
-        };
+        let relative_path = self.root_relative(path);
 
-        self.selection_engine.is_selected(relative_path)
+        let changed = self.cache.changed.as_ref().filter(|changed| {
+            self.config.include_only_changed && changed.diff_branches == self.config.diff_branches
+        });
+        self.selection_engine.is_selected(&relative_path)
+            && changed.is_none_or(|changed| changed.files.contains(&relative_path))
+    }
+
+    /// Resolves the files changed by the diff, to which `is_file_selected` narrows the
+    /// selection while `include_only_changed` is set.
+    pub fn load_changed_files(&mut self) -> Result<()> {
+        let root = self.config.path.canonicalize()?;
+        self.cache.changed = changed_scope(&self.config, &root)?.map(|files| CachedChanges {
+            diff_branches: self.config.diff_branches.clone(),
+            files,
+        });
+        Ok(())
     }
 
     /// Get all currently selected files (delegates to SelectionEngine)
@@ -179,125 +537,1605 @@ impl Code2PromptSession {
 
     /// Loads the codebase data (source tree and file list) into the session.
     pub fn load_codebase(&mut self) -> Result<()> {
-        let (tree, files) = traverse_directory(&self.config, Some(&mut self.selection_engine))
-            .with_context(|| "Failed to traverse directory")?;
+        self.resolve_remote()?;
+        self.prepare_tokenizer()?;
+
+        // Prompt caching needs a stable ordering, which modification dates do not provide
+        let stable_config;
+        let config = if self.config.prompt_cache.is_some()
+            && matches!(
+                self.config.sort_method,
+                Some(FileSortMethod::DateAsc | FileSortMethod::DateDesc)
+            ) {
+            log::warn!("Prompt caching enabled: sorting files by name instead of date");
+            stable_config = Code2PromptConfig {
+                sort_method: Some(FileSortMethod::NameAsc),
+                ..self.config.clone()
+            };
+            &stable_config
+        } else {
+            &self.config
+        };
+
+        let git_tree = self.config.git_dir.is_some() || self.config.git_rev.is_some();
+        if git_tree && !self.config.extra_paths.is_empty() {
+            anyhow::bail!("Several roots cannot be read from a git revision");
+        }
+        self.data.roots = None;
+        self.report_progress(ProgressEvent::TraversalStarted);
+        let (tree, files) = if git_tree {
+            let rev = self.config.git_rev.as_deref().unwrap_or("HEAD");
+            let repo_path = self.config.git_dir.as_ref().unwrap_or(&self.config.path);
+            let (tree, files) =
+                traverse_git_tree(config, repo_path, rev, Some(&mut self.selection_engine))
+                    .with_context(|| format!("Failed to traverse the tree of {}", rev))?;
+            self.report_files_loaded(&files);
+            (tree, files)
+        } else if !self.config.extra_paths.is_empty() {
+            let (tree, files, roots) = traverse_roots(config, Some(&mut self.selection_engine))?;
+            self.data.roots = Some(roots);
+            self.report_files_loaded(&files);
+            (tree, files)
+        } else {
+            traverse_directory_with_progress(
+                config,
+                Some(&mut self.selection_engine),
+                self.progress.as_deref(),
+            )
+            .with_context(|| "Failed to traverse directory")?
+        };
+        // Absolute and aliased paths are matched against the key file patterns relative to
+        // the root
+        let mut keyed: Vec<(PathBuf, FileEntry)> = files
+            .into_iter()
+            .map(|file| (self.config.root_relative_path(&file.path), file))
+            .collect();
+        place_key_files(&mut keyed, |(path, _)| path.as_path(), &self.config);
+        let mut files: Vec<FileEntry> = keyed.into_iter().map(|(_, file)| file).collect();
+        if let Some(handling) = self.config.duplicates {
+            let mut report = find_duplicates(&files, &self.config);
+            if handling == DuplicateHandling::Stub {
+                stub_duplicates(&mut files, &mut report, &self.config);
+            }
+            self.data.duplicates = Some(report);
+        }
+        if !self.config.file_history.is_empty() {
+            self.attach_file_history(&mut files)?;
+        }
+        if let Some(tags_file) = self.config.tags_file.clone() {
+            self.attach_symbols(&mut files, &tags_file)?;
+        }
 
         // Store absolute_code_path as Single Source of Truth
-        self.data.absolute_code_path = Some(display_name(&self.config.path));
+        self.data.absolute_code_path = Some(match (&self.config.git_dir, &self.data.roots) {
+            (Some(git_dir), _) => repository_name(git_dir),
+            (None, Some(roots)) => roots
+                .iter()
+                .map(|root| root.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            (None, None) => display_name(&self.config.path),
+        });
         self.data.source_tree = Some(tree);
         self.data.files = Some(files);
+        // The environment is declared by the working tree, which a revision may not match
+        self.data.environment_spec = None;
+        if self.config.git_dir.is_none()
+            && self.config.git_rev.is_none()
+            && self.template_mentions("environment_spec")
+        {
+            self.data.environment_spec = detect_environment(&self.config.path);
+        }
+        if self.config.frontend_summary {
+            self.summarize_frontend();
+        }
+        self.data.version_info = None;
+        if self.template_mentions("version_info") {
+            self.load_version_info();
+        }
+
+        Ok(())
+    }
+
+    /// Reports the files of a traversal that does not report its progress, once read.
+    fn report_files_loaded(&self, files: &[FileEntry]) {
+        self.report_progress(ProgressEvent::FilesFound { count: files.len() });
+        for file in files {
+            self.report_progress(ProgressEvent::FileLoaded {
+                path: file.path.clone(),
+                tokens: file.token_count,
+            });
+        }
+    }
+
+    /// Loads the model of the tokenizer, when token counts are exact and it needs one.
+    fn prepare_tokenizer(&self) -> Result<()> {
+        if self.config.token_count_mode == TokenCountMode::Heuristic {
+            return Ok(());
+        }
+        prepare_tokenizer(
+            &self.config.encoding,
+            self.config.tokenizer_model.as_deref(),
+        )
+    }
+
+    /// Loads where the codebase revision stands relative to the tags into `version_info`,
+    /// left empty when the codebase is not a git repository.
+    pub fn load_version_info(&mut self) {
+        let rev = self.config.git_rev.as_deref().unwrap_or("HEAD");
+        self.data.version_info = match get_version_info(self.repo_path(), rev) {
+            Ok(version_info) => Some(version_info),
+            Err(e) => {
+                log::debug!("No version information: {:#}", e);
+                None
+            }
+        };
+    }
+
+    /// Whether the template refers to a variable, so that it is only loaded when rendered.
+    fn template_mentions(&self, variable: &str) -> bool {
+        self.template().0.contains(variable)
+    }
+
+    /// Attaches to the files of `config.file_history` their latest commits.
+    fn attach_file_history(&mut self, files: &mut [FileEntry]) -> Result<()> {
+        let options = self.git_diff_options();
+        for (requested, &count) in &self.config.file_history.clone() {
+            let requested = Path::new(requested);
+            let file = files
+                .iter_mut()
+                .find(|file| self.config.root_relative_path(&file.path) == requested);
+            let Some(file) = file else {
+                log::warn!(
+                    "No history for {}: the file is not included",
+                    requested.display()
+                );
+                continue;
+            };
+            file.history = Some(self.file_history(&path_key(requested), count, &options)?);
+        }
+        Ok(())
+    }
+
+    /// Reads the latest commits of a file, kept until HEAD or the diff options change.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, relative to the repository root
+    /// * `count` - The maximum number of commits
+    /// * `options` - The diff options
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<FileCommit>>` - The commits, newest first
+    fn file_history(
+        &mut self,
+        path: &str,
+        count: usize,
+        options: &GitDiffOptions,
+    ) -> Result<Vec<FileCommit>> {
+        let repo_path = self.repo_path().to_path_buf();
+        let read = || {
+            get_file_history(&repo_path, path, count, options)
+                .with_context(|| format!("Failed to read the history of {}", path))
+        };
+        let Some(head) = head_commit_id(&repo_path) else {
+            return read();
+        };
+        let cache = match &mut self.cache.history {
+            Some(cache) if cache.head == head && cache.options == *options => cache,
+            history => history.insert(CachedHistory {
+                head,
+                options: options.clone(),
+                histories: HashMap::new(),
+            }),
+        };
+        let key = (path.to_string(), count);
+        if let Some(history) = cache.histories.get(&key) {
+            return Ok(history.clone());
+        }
+        let history = read()?;
+        cache.histories.insert(key, history.clone());
+        Ok(history)
+    }
+
+    /// Attaches to the files the symbols the tags file lists for them.
+    fn attach_symbols(&mut self, files: &mut [FileEntry], tags_file: &Path) -> Result<()> {
+        let index = self.tags_index(tags_file)?;
+        for file in files.iter_mut() {
+            let relative_path = self.config.root_relative_path(&file.path);
+            file.symbols = index.symbols(&relative_path).map(<[TagSymbol]>::to_vec);
+        }
+        Ok(())
+    }
+
+    /// Reads the tags file, kept until it is modified.
+    fn tags_index(&mut self, tags_file: &Path) -> Result<Arc<TagsIndex>> {
+        let modified = std::fs::metadata(tags_file).and_then(|metadata| metadata.modified());
+        if let (Some(cache), Ok(modified)) = (&self.cache.tags, &modified)
+            && cache.tags_file == tags_file
+            && cache.root == self.config.path
+            && cache.modified == *modified
+        {
+            return Ok(Arc::clone(&cache.index));
+        }
+        let index = Arc::new(TagsIndex::load(tags_file, &self.config.path)?);
+        if let Ok(modified) = modified {
+            self.cache.tags = Some(CachedTags {
+                tags_file: tags_file.to_path_buf(),
+                root: self.config.path.clone(),
+                modified,
+                index: Arc::clone(&index),
+            });
+        }
+        Ok(index)
+    }
+
+    /// Attaches to a streamed file its history and symbols, as `load_codebase` does.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file read from the stream
+    /// * `tags` - The symbols of the tags file, if one is set
+    /// * `options` - The diff options the history is read with
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the history of the file cannot be read
+    fn annotate_streamed(
+        &self,
+        file: &mut FileEntry,
+        tags: Option<&TagsIndex>,
+        options: &GitDiffOptions,
+    ) -> Result<()> {
+        let relative_path = self.config.root_relative_path(&file.path);
+        let requested = self
+            .config
+            .file_history
+            .iter()
+            .find(|(requested, _)| Path::new(requested.as_str()) == relative_path);
+        if let Some((_, &count)) = requested {
+            let path = path_key(&relative_path);
+            let history = get_file_history(self.repo_path(), &path, count, options)
+                .with_context(|| format!("Failed to read the history of {}", path))?;
+            file.history = Some(history);
+        }
+        if let Some(index) = tags {
+            file.symbols = index.symbols(&relative_path).map(<[TagSymbol]>::to_vec);
+        }
+        Ok(())
+    }
+
+    /// Loads the Git diff into the session data.
+    pub fn load_git_diff(&mut self) -> Result<()> {
+        let diff = get_git_diff_with_options(self.repo_path(), &self.git_diff_options())?;
+        self.data.git_diff = Some(self.limit_diff(self.prefix_diff(diff)));
+        Ok(())
+    }
+
+    /// Loads the Git diff between two branches into the session data.
+    pub fn load_git_diff_between_branches(&mut self) -> Result<()> {
+        if let Some((b1, b2)) = &self.config.diff_branches {
+            let diff = get_git_diff_between_branches_with_options(
+                self.repo_path(),
+                b1,
+                b2,
+                &self.git_diff_options(),
+            )?;
+            self.data.git_diff_branch = Some(self.limit_diff(self.prefix_diff(diff)));
+        }
+        Ok(())
+    }
+
+    /// Counts the tokens of the diff between two branches, as `load_git_diff_between_branches`
+    /// would include it with the current diff options.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The branch the diff starts from
+    /// * `head` - The branch the diff goes to
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The token count, or an error if a branch does not exist
+    pub fn branch_diff_tokens(&self, base: &str, head: &str) -> Result<usize> {
+        let diff = get_git_diff_between_branches_with_options(
+            self.repo_path(),
+            base,
+            head,
+            &self.git_diff_options(),
+        )?;
+        Ok(count_tokens_with_mode(
+            &self.limit_diff(diff),
+            "",
+            &self.config.encoding,
+            self.config.tokenizer_model.as_deref(),
+            self.config.token_count_mode,
+        ))
+    }
+
+    /// Returns the diff of the prompt split by file: the diff between `diff_branches`
+    /// when set, the working tree diff otherwise. The diff is not truncated to
+    /// `diff_max_tokens`, so that every file can be reviewed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(String, String)>>` - The path and the diff of each file
+    pub fn diff_files(&self) -> Result<Vec<(String, String)>> {
+        let diff = match &self.config.diff_branches {
+            Some((b1, b2)) => get_git_diff_between_branches_with_options(
+                self.repo_path(),
+                b1,
+                b2,
+                &self.git_diff_options(),
+            )?,
+            None => get_git_diff_with_options(self.repo_path(), &self.git_diff_options())?,
+        };
+        Ok(split_diff_by_file(&diff))
+    }
+
+    /// Loads the unresolved merge conflicts into the session data.
+    pub fn load_merge_conflicts(&mut self) -> Result<()> {
+        self.data.conflicts = get_merge_conflicts(self.repo_path())?;
+        Ok(())
+    }
+
+    /// Loads the diff of the configured stash into the session data.
+    pub fn load_git_stash(&mut self) -> Result<()> {
+        if let Some(stash) = &self.config.stash {
+            let diff = get_git_stash_diff(self.repo_path(), Some(stash), &self.git_diff_options())?;
+            self.data.git_stash = Some(self.limit_diff(diff));
+        }
+        Ok(())
+    }
+
+    /// Loads the untracked files of the repository into the session data.
+    pub fn load_untracked_files(&mut self) -> Result<()> {
+        self.data.untracked_files = Some(get_untracked_files(self.repo_path())?);
+        Ok(())
+    }
+
+    /// Loads the context needed to write a commit message: exactly the staged diff,
+    /// as `git_diff`, and the latest commit messages as style examples, as `recent_commits`.
+    ///
+    /// # Arguments
+    ///
+    /// * `examples` - The number of recent commit messages to collect
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if nothing is staged
+    pub fn load_commit_message_context(&mut self, examples: usize) -> Result<()> {
+        let diff = get_git_staged_diff(self.repo_path(), &self.git_diff_options())?;
+        if diff.trim().is_empty() {
+            return Err(anyhow::anyhow!("No staged changes to describe"));
+        }
+        self.data.absolute_code_path = Some(display_name(&self.config.path));
+        self.data.git_diff = Some(self.limit_diff(diff));
+        self.data.recent_commits = Some(get_recent_commit_messages(self.repo_path(), examples)?);
+        Ok(())
+    }
+
+    /// Loads the context needed to describe a pull request: the diff of `head` against its
+    /// merge base with `base`, as `git_diff_branch`, and the commits and touched files of
+    /// the branch, as `branch_changes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The branch the pull request targets
+    /// * `head` - The branch of the pull request
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if a branch does not exist or the branch changes nothing
+    pub fn load_pull_request_context(&mut self, base: &str, head: &str) -> Result<()> {
+        let mut changes =
+            get_branch_changes(self.repo_path(), base, head, &self.git_diff_options())?;
+        if changes.commits.is_empty() && changes.files.is_empty() {
+            return Err(anyhow::anyhow!("No changes between {} and {}", base, head));
+        }
+        self.data.absolute_code_path = Some(display_name(&self.config.path));
+        self.data.git_diff_branch = Some(self.limit_diff(std::mem::take(&mut changes.diff)));
+        self.data.branch_changes = Some(changes);
+        Ok(())
+    }
+
+    /// Loads the context needed to write release notes: the commits and touched files
+    /// since `since`, as `branch_changes`, the pull requests merged in that range, as
+    /// `merged_pull_requests`, and the diff of the changelog-relevant paths, as
+    /// `git_diff_branch`.
+    ///
+    /// Unless diff patterns are configured, `RELEASE_NOTES_EXCLUDE_PATTERNS` are left
+    /// out of the diff and of the touched files.
+    ///
+    /// # Arguments
+    ///
+    /// * `since` - The previous release, usually a tag
+    /// * `until` - The revision being released
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if a revision does not exist or nothing changed
+    pub fn load_release_context(&mut self, since: &str, until: &str) -> Result<()> {
+        let mut options = self.git_diff_options();
+        if options.include_patterns.is_empty() && options.exclude_patterns.is_empty() {
+            options.exclude_patterns = RELEASE_NOTES_EXCLUDE_PATTERNS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect();
+        }
+        let mut changes = get_branch_changes(self.repo_path(), since, until, &options)?;
+        if changes.commits.is_empty() && changes.files.is_empty() {
+            return Err(anyhow::anyhow!("No changes since {}", since));
+        }
+        let pull_requests = get_merged_pull_requests(self.repo_path(), since, until)?;
+
+        self.data.absolute_code_path = Some(display_name(&self.config.path));
+        self.data.git_diff_branch = Some(self.limit_diff(std::mem::take(&mut changes.diff)));
+        self.data.branch_changes = Some(changes);
+        self.data.merged_pull_requests = Some(pull_requests);
+        Ok(())
+    }
+
+    /// Measures the documentation coverage of the loaded files, as `doc_coverage`, and
+    /// keeps only the files with undocumented public items so that the prompt focuses
+    /// on them.
+    ///
+    /// Files are read from the working tree, `load_codebase` must be called first.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the codebase is not loaded
+    pub fn load_doc_coverage(&mut self) -> Result<()> {
+        let files = self
+            .data
+            .files
+            .as_mut()
+            .context("The codebase must be loaded before measuring its documentation")?;
+
+        let root = &self.config.path;
+        let coverage: Vec<_> = files
+            .iter()
+            .filter_map(|file| {
+                let relative_path = self.config.root_relative_path(&file.path);
+                let content = std::fs::read_to_string(root.join(relative_path)).ok()?;
+                analyze_file(&file.path, &content)
+            })
+            .collect();
+        let report = DocCoverageReport::from_files(coverage);
+
+        files.retain(|file| report.files.iter().any(|gaps| gaps.path == file.path));
+        self.data.doc_coverage = Some(report);
+        Ok(())
+    }
+
+    /// Condenses the loaded files into an architecture brief, as `architecture_brief`:
+    /// workspace packages and their dependencies, directory overview, READMEs and
+    /// outlines of the source files, pruned to fit `max_tokens`.
+    ///
+    /// Files are read from the working tree, `load_codebase` must be called first.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_tokens` - The token budget of the prompt
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the codebase is not loaded or the template fails to render
+    pub fn load_architecture_brief(&mut self, max_tokens: usize) -> Result<()> {
+        let contents: Vec<(String, String)> = self
+            .read_working_tree()
+            .context("The codebase must be loaded before building its architecture brief")?
+            .into_iter()
+            .map(|(_, path, content)| (path, content))
+            .collect();
+
+        // The brief is measured in the prompt it is rendered in, as it is reported
+        let brief = build_architecture_brief(&contents, max_tokens, |brief| {
+            let brief = Some(brief.clone());
+            let context = TemplateContext {
+                architecture_brief: &brief,
+                ..self.build_template_data()
+            };
+            let rendered = self.render_prompt(&context)?;
+            Ok(count_tokens_with_mode(
+                &rendered.prompt,
+                "",
+                &self.config.encoding,
+                self.config.tokenizer_model.as_deref(),
+                self.config.token_count_mode,
+            ))
+        })?;
+        self.data.architecture_brief = Some(brief);
+        Ok(())
+    }
+
+    /// Orders the loaded files as an onboarding tour, as `onboarding_tour`: entry
+    /// points first, then the core modules after the modules they depend on, the
+    /// supporting files, and the tests last. The files are reordered to match.
+    ///
+    /// Files are read from the working tree, `load_codebase` must be called first.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the codebase is not loaded
+    pub fn load_onboarding_tour(&mut self) -> Result<()> {
+        let working_tree = self
+            .read_working_tree()
+            .context("The codebase must be loaded before planning its tour")?;
+        let contents: Vec<(String, String)> = working_tree
+            .iter()
+            .map(|(_, path, content)| (path.clone(), content.clone()))
+            .collect();
+        let mut tour = plan_tour(&contents);
+
+        let files = self.data.files.take().unwrap_or_default();
+        let index_of: HashMap<&str, usize> = working_tree
+            .iter()
+            .map(|(index, path, _)| (path.as_str(), *index))
+            .collect();
+        let order: Vec<usize> = tour.paths().map(|path| index_of[path]).collect();
+        for stop in tour
+            .sections
+            .iter_mut()
+            .flat_map(|section| section.stops.iter_mut())
+        {
+            stop.code = Some(files[index_of[stop.path.as_str()]].code.clone());
+        }
+
+        // Unreadable files have no place in the tour, they are kept at the end
+        let mut slots: Vec<Option<FileEntry>> = files.into_iter().map(Some).collect();
+        let mut ordered: Vec<FileEntry> = order
+            .into_iter()
+            .filter_map(|index| slots[index].take())
+            .collect();
+        ordered.extend(slots.into_iter().flatten());
+
+        self.data.files = Some(ordered);
+        self.data.onboarding_tour = Some(tour);
+        Ok(())
+    }
+
+    /// Path of the repository used for git operations: `git_dir` if set, `path` otherwise.
+    pub fn repo_path(&self) -> &Path {
+        self.config.git_dir.as_deref().unwrap_or(&self.config.path)
+    }
+
+    /// Reads the loaded files from the working tree, skipping unreadable ones.
+    ///
+    /// Returns the index of each file in `data.files`, its path relative to the root,
+    /// even with `absolute_path` or `path_alias`, and its raw content.
+    fn read_working_tree(&self) -> Option<Vec<(usize, String, String)>> {
+        let files = self.data.files.as_ref()?;
+        Some(
+            files
+                .iter()
+                .enumerate()
+                .filter_map(|(index, file)| {
+                    let relative_path = self.loaded_relative_path(&file.path);
+                    let content =
+                        std::fs::read_to_string(self.config.path.join(&relative_path)).ok()?;
+                    Some((index, relative_path, content))
+                })
+                .collect(),
+        )
+    }
+
+    /// The path of a loaded file relative to the root, even with `absolute_path` or
+    /// `path_alias`.
+    fn loaded_relative_path(&self, path: &str) -> String {
+        path_key(&self.config.root_relative_path(path))
+    }
+
+    /// Summarizes the build tool configurations into `build_config`, leaving them out of
+    /// the files, and groups the component files by route or feature in the source tree.
+    fn summarize_frontend(&mut self) {
+        let Some(files) = self.data.files.as_ref() else {
+            return;
+        };
+        let paths: Vec<String> = files
+            .iter()
+            .map(|file| self.loaded_relative_path(&file.path))
+            .collect();
+
+        // Configurations are read from the working tree, which a revision may not match
+        if self.config.git_dir.is_none() && self.config.git_rev.is_none() {
+            let configs: Vec<(usize, String, String)> = paths
+                .iter()
+                .enumerate()
+                .filter(|(_, path)| build_tool(path).is_some())
+                .filter_map(|(index, path)| {
+                    let content = std::fs::read_to_string(self.config.path.join(path)).ok()?;
+                    Some((index, path.clone(), content))
+                })
+                .collect();
+            let contents: Vec<(String, String)> = configs
+                .iter()
+                .map(|(_, path, content)| (path.clone(), content.clone()))
+                .collect();
+            if let Some(build_config) = summarize_build_configs(&contents) {
+                let files = self.data.files.take().unwrap_or_default();
+                self.data.files = Some(
+                    files
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(index, _)| !configs.iter().any(|(config, _, _)| config == index))
+                        .map(|(_, file)| file)
+                        .collect(),
+                );
+                self.data.build_config = Some(build_config);
+            }
+        }
+
+        if let Some(layout) = group_routes(&paths)
+            && let Some(tree) = self.data.source_tree.as_mut()
+        {
+            tree.push_str("\n\n");
+            tree.push_str(layout.to_tree().trim_end());
+        }
+    }
+
+    /// Builds the git diff options from the configuration.
+    fn git_diff_options(&self) -> GitDiffOptions {
+        GitDiffOptions {
+            include_patterns: self.config.diff_include_patterns.clone(),
+            exclude_patterns: self.config.diff_exclude_patterns.clone(),
+            context_lines: self.config.diff_context_lines,
+            style: self.config.diff_style,
+        }
+    }
+
+    /// Prefixes the paths of a diff like those of the files: with the path alias, or with
+    /// the path of the repository when paths are absolute.
+    fn prefix_diff(&self, diff: String) -> String {
+        let prefix = match self.config.path_alias_prefix() {
+            Some(alias) => alias,
+            None if self.config.absolute_path => {
+                let repo_path = self.repo_path();
+                let repo_path = repo_path
+                    .canonicalize()
+                    .unwrap_or_else(|_| repo_path.to_path_buf());
+                repo_path
+                    .to_string_lossy()
+                    .replace('\\', "/")
+                    .trim_start_matches('/')
+                    .to_string()
+            }
+            None => return diff,
+        };
+        if prefix.is_empty() {
+            return diff;
+        }
+        prefix_diff_paths(&diff, &prefix)
+    }
+
+    /// Truncates a diff to `diff_max_tokens`, if set.
+    fn limit_diff(&self, diff: String) -> String {
+        match self.config.diff_max_tokens {
+            Some(max_tokens) => truncate_diff(
+                &diff,
+                max_tokens,
+                &self.config.encoding,
+                self.config.tokenizer_model.as_deref(),
+                self.config.token_count_mode,
+            ),
+            None => diff,
+        }
+    }
+
+    /// Loads the Git log between two branches into the session data.
+    pub fn load_git_log_between_branches(&mut self) -> Result<()> {
+        if let Some((b1, b2)) = &self.config.log_branches {
+            let log_output = get_git_log(self.repo_path(), b1, b2)?;
+            self.data.git_log_branch = Some(log_output);
+        }
+        Ok(())
+    }
+
+    /// Returns the manifest of the content hashes of the loaded files.
+    pub fn manifest(&self) -> FileManifest {
+        FileManifest::from_files(self.data.files.as_deref().unwrap_or_default())
+    }
+
+    /// Returns the snapshot of the loaded files, to be compared with another run by
+    /// [`crate::snapshot::diff_snapshots`].
+    ///
+    /// # Arguments
+    ///
+    /// * `token_count` - The tokens of the rendered prompt
+    pub fn snapshot(&self, token_count: usize) -> SessionSnapshot {
+        SessionSnapshot::from_files(self.data.files.as_deref().unwrap_or_default(), token_count)
+    }
+
+    /// Compares the loaded files with the manifest of a previous run.
+    pub fn load_manifest_changes(&mut self, previous: &FileManifest) {
+        let changes = previous.diff(self.data.files.as_deref().unwrap_or_default());
+        self.data.manifest_changes = Some(changes);
+    }
+
+    /// Restricts the loaded files to the ones changed or added since the previous turn
+    /// of the conversation, and records the full set of files as sent in `state`.
+    /// Unchanged files are listed in the context delta as a reminder index.
+    pub fn apply_conversation_delta(&mut self, state: &mut ConversationState) {
+        let files = self.data.files.take().unwrap_or_default();
+        let delta = state.compute_delta(&files);
+        state.record(&files);
+
+        self.data.files = Some(
+            files
+                .into_iter()
+                .filter(|file| !delta.unchanged.contains(&file.path))
+                .collect(),
+        );
+        self.data.context_delta = Some(delta);
+    }
+
+    /// Constructs a zero-copy template context for rendering.
+    pub fn build_template_data(&self) -> TemplateContext<'_> {
+        self.template_context(self.data.files.as_deref())
+    }
+
+    /// Constructs the template context of the session data with the given files.
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - The files listed in the context, in place of the loaded ones
+    ///
+    /// # Returns
+    ///
+    /// * `TemplateContext` - The context borrowing the session data
+    fn template_context<'a>(&'a self, files: Option<&'a [FileEntry]>) -> TemplateContext<'a> {
+        TemplateContext {
+            absolute_code_path: self.data.absolute_code_path.as_deref().unwrap_or("unknown"),
+            source_tree: &self.data.source_tree,
+            files,
+            git_diff: &self.data.git_diff,
+            git_diff_branch: &self.data.git_diff_branch,
+            git_log_branch: &self.data.git_log_branch,
+            conflicts: &self.data.conflicts,
+            git_stash: &self.data.git_stash,
+            untracked_files: &self.data.untracked_files,
+            recent_commits: &self.data.recent_commits,
+            branch_changes: &self.data.branch_changes,
+            merged_pull_requests: &self.data.merged_pull_requests,
+            doc_coverage: &self.data.doc_coverage,
+            architecture_brief: &self.data.architecture_brief,
+            onboarding_tour: &self.data.onboarding_tour,
+            environment_spec: &self.data.environment_spec,
+            build_config: &self.data.build_config,
+            context_delta: &self.data.context_delta,
+            manifest_changes: &self.data.manifest_changes,
+            version_info: &self.data.version_info,
+            roots: &self.data.roots,
+            config: self.config.template_config(),
+            user_variables: nest_variables(&self.config.user_variables),
+        }
+    }
+
+    /// Constructs the template context of a few files without the source tree and the git
+    /// diffs, the largest parts of the context, to render the files on their own.
+    fn file_context<'a>(&'a self, files: &'a [FileEntry]) -> TemplateContext<'a> {
+        TemplateContext {
+            source_tree: &None,
+            git_diff: &None,
+            git_diff_branch: &None,
+            git_log_branch: &None,
+            git_stash: &None,
+            ..self.template_context(Some(files))
+        }
+    }
+
+    /// Reads the start of a few files, a sample to preview a template with before the
+    /// codebase is loaded. Files that cannot be read or are not text are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - The files to sample, absolute or relative to the root
+    /// * `max_lines` - The number of lines kept of each file
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<FileEntry>` - The entries of the sampled files, formatted as in the prompt
+    pub fn sample_files(&self, paths: &[PathBuf], max_lines: usize) -> Vec<FileEntry> {
+        paths
+            .iter()
+            .filter_map(|path| {
+                let absolute_path = self.config.path.join(path);
+                let relative_path = absolute_path
+                    .strip_prefix(&self.config.path)
+                    .unwrap_or(path)
+                    .to_path_buf();
+                let metadata = std::fs::metadata(&absolute_path).ok()?;
+                let content = std::fs::read(&absolute_path).ok()?;
+                let end = content
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &byte)| byte == b'\n')
+                    .nth(max_lines.saturating_sub(1))
+                    .map_or(content.len(), |(index, _)| index + 1);
+                build_file_entry(
+                    &relative_path,
+                    self.config.output_path(&relative_path, &absolute_path),
+                    &content[..end],
+                    EntryMetadata::from(&metadata),
+                    None,
+                    &self.config,
+                )
+            })
+            .collect()
+    }
+
+    /// Renders a template against the session data with the given files, to preview it
+    /// while it is edited. The post-processing steps and the volatile sections are left
+    /// out, and the source tree lists the files when the codebase is not loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `template_str` - The template to render
+    /// * `files` - The files to render, such as a sample from `sample_files`
+    /// * `user_variables` - Variables set while editing, on top of the configured ones
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String>` - The rendered template, or the syntax or render error
+    pub fn preview_template(
+        &self,
+        template_str: &str,
+        files: &[FileEntry],
+        user_variables: &HashMap<String, String>,
+    ) -> Result<String> {
+        let file_list = self.data.source_tree.clone().or_else(|| {
+            let root = display_name(&self.config.path);
+            let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+            Some(format!("{}\n{}", root, paths.join("\n")))
+        });
+        let mut context = self.build_template_data();
+        context.files = Some(files);
+        context.source_tree = &file_list;
+        let mut variables = self.config.user_variables.clone();
+        variables.extend(user_variables.clone());
+        context.user_variables = nest_variables(&variables);
+
+        let handlebars = self.handlebars(template_str, "preview")?;
+        render_template(&handlebars, "preview", &context)
+    }
+
+    /// Renders the final prompt given a template context. Returns both
+    /// the rendered prompt and the token count information.
+    pub fn render_prompt(&self, template_context: &TemplateContext) -> Result<RenderedPrompt> {
+        // ~~~ Rendering ~~~
+        let (template_str, template_name) = self.template();
+        let handlebars = self.handlebars(&template_str, &template_name)?;
+        let rendered = render_template(&handlebars, &template_name, template_context)?;
+
+        // ~~~ Volatile sections ~~~
+        // Kept after the codebase so that the codebase forms a stable, cacheable prefix
+        let tokenizer_type: TokenizerType = self.config.encoding;
+        let volatile = self.volatile_sections();
+        let leading = self.leading_instruction();
+        let volatile_tokens = count_tokens_with_mode(
+            &format!("{}{}", leading.as_deref().unwrap_or_default(), volatile),
+            "",
+            &tokenizer_type,
+            self.config.tokenizer_model.as_deref(),
+            self.config.token_count_mode,
+        );
+
+        // ~~~ Informations ~~~
+        // Always use the cached calculation: Σ(FileTokens) + TemplateOverhead
+        // This avoids re-tokenizing the entire rendered output (sequential bottleneck)
+        let mut token_breakdown = self.calculate_token_breakdown(&tokenizer_type);
+        token_breakdown.instruction = volatile_tokens;
+
+        let model_info = tokenizer_type.description();
+        let directory_name = template_context.absolute_code_path.to_string();
+        let files: Vec<String> = self
+            .data
+            .files
+            .as_ref()
+            .map(|files| files.iter().map(|file| file.path.clone()).collect())
+            .unwrap_or_default();
+
+        // ~~~ Post-processing ~~~
+        let context = PostProcessContext {
+            output_format: self.config.output_format,
+            files: self.data.files.as_deref().unwrap_or_default(),
+            token_count: token_breakdown.total(),
+            model_info,
+        };
+        let template_content = self.post_process(rendered, &context, &mut token_breakdown)?;
+        let token_count = token_breakdown.total();
+
+        let mut prompt = match &leading {
+            Some(leading) => format!("{}\n\n{}", leading, template_content),
+            None => template_content.clone(),
+        };
+        if !volatile.is_empty() {
+            prompt.push_str("\n\n");
+            prompt.push_str(&volatile);
+        }
+
+        // ~~~ Final output format ~~~
+        let final_output = if let Some(provider) = self.config.prompt_cache {
+            let messages = cached_messages(provider, &template_content, &volatile);
+            serde_json::to_string_pretty(&messages)?
+        } else {
+            match self.config.output_format {
+                OutputFormat::Json => {
+                    let json_data = serde_json::json!({
+                        "prompt": prompt,
+                        "directory_name": directory_name.clone(),
+                        "token_count": token_count,
+                        "token_breakdown": token_breakdown,
+                        "model_info": model_info,
+                        "placement": self.config.placement,
+                        "files": files.clone(),
+                        "file_hashes": self.manifest().files,
+                        "changes": self.data.manifest_changes,
+                        "budget": self.data.budget_report,
+                    });
+                    serde_json::to_string_pretty(&json_data)?
+                }
+                OutputFormat::Jsonl => {
+                    let mut lines = Vec::new();
+                    for file in self.data.files.as_deref().unwrap_or_default() {
+                        lines.push(serde_json::to_string(&serde_json::json!({
+                            "type": "file",
+                            "path": file.path,
+                            "extension": file.extension,
+                            "code": file.code,
+                            "token_count": file.token_count,
+                            "content_hash": file.content_hash,
+                            "metadata": file.metadata,
+                        }))?);
+                    }
+                    lines.push(serde_json::to_string(&serde_json::json!({
+                        "type": "summary",
+                        "directory_name": directory_name.clone(),
+                        "file_count": files.len(),
+                        "token_count": token_count,
+                        "token_breakdown": token_breakdown,
+                        "model_info": model_info,
+                        "changes": self.data.manifest_changes,
+                        "budget": self.data.budget_report,
+                    }))?);
+                    lines.join("\n")
+                }
+                OutputFormat::Chunks => {
+                    let mut lines = Vec::new();
+                    for file in self.data.files.as_deref().unwrap_or_default() {
+                        for chunk in chunk_file(file, &self.config) {
+                            lines.push(serde_json::to_string(&chunk)?);
+                        }
+                    }
+                    lines.join("\n")
+                }
+                _ => prompt,
+            }
+        };
+
+        Ok(RenderedPrompt {
+            prompt: final_output,
+            directory_name,
+            token_count,
+            token_breakdown,
+            model_info,
+            files,
+            budget: self.data.budget_report.clone(),
+        })
+    }
+
+    /// Renders the prompt as sequential parts of at most `max_tokens` tokens each, split at
+    /// file boundaries. Each part renders the template with its files and starts with a
+    /// header telling its position; the git sections and the leading instruction go in the
+    /// first part, the closing instruction in the last one.
+    ///
+    /// A file over the ceiling on its own gets a part of its own, which exceeds it.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_tokens` - The ceiling of a part
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<PromptPart>>` - The parts, at least one
+    pub fn render_parts(&self, max_tokens: usize) -> Result<Vec<PromptPart>> {
+        if self.config.prompt_cache.is_some()
+            || !matches!(
+                self.config.output_format,
+                OutputFormat::Markdown | OutputFormat::Xml
+            )
+        {
+            anyhow::bail!("Only markdown and xml prompts can be split into parts");
+        }
+        let count_tokens = |text: &str| {
+            count_tokens_with_mode(
+                text,
+                "",
+                &self.config.encoding,
+                self.config.tokenizer_model.as_deref(),
+                self.config.token_count_mode,
+            )
+        };
+        let (template_str, template_name) = self.template();
+        let handlebars = self.handlebars(&template_str, &template_name)?;
+        let files = self.data.files.as_deref().unwrap_or_default();
+
+        // ~~~ Grouping ~~~
+        // The files are grouped on their estimated tokens, then a part found over the
+        // ceiling hands its last file over to the next part
+        let ceiling = max_tokens.saturating_sub(count_tokens(&part_header(99, 99)) + 2);
+        let base =
+            count_tokens(&self.render_part(&handlebars, &template_name, &[], false, false)?);
+        let costs: Vec<usize> = files
+            .iter()
+            .map(|file| file.token_count + count_tokens(&file.path) + FILE_OVERHEAD_TOKENS)
+            .collect();
+        let mut ranges = group_files(&costs, base, ceiling);
+        let mut bodies = Vec::with_capacity(ranges.len());
+        while bodies.len() < ranges.len() {
+            let index = bodies.len();
+            let range = ranges[index].clone();
+            let body = self.render_part(
+                &handlebars,
+                &template_name,
+                &files[range.clone()],
+                index == 0,
+                index + 1 == ranges.len(),
+            )?;
+            if range.len() > 1 && count_tokens(&body) > ceiling {
+                ranges[index].end -= 1;
+                match ranges.get_mut(index + 1) {
+                    Some(next) => next.start -= 1,
+                    None => ranges.push(range.end - 1..range.end),
+                }
+                continue;
+            }
+            bodies.push(body);
+        }
+
+        let count = bodies.len();
+        Ok(bodies
+            .into_iter()
+            .zip(ranges)
+            .enumerate()
+            .map(|(index, (body, range))| {
+                let prompt = format!("{}\n\n{}", part_header(index + 1, count), body);
+                PromptPart {
+                    token_count: count_tokens(&prompt),
+                    prompt,
+                    files: files[range].iter().map(|file| file.path.clone()).collect(),
+                }
+            })
+            .collect())
+    }
+
+    /// Renders a part of a split prompt, without its header.
+    ///
+    /// # Arguments
+    ///
+    /// * `handlebars` - The Handlebars instance with the template registered
+    /// * `template_name` - The name of the template
+    /// * `files` - The files of the part
+    /// * `first` - Whether the part is the first one, taking the git sections and the
+    ///   leading instruction
+    /// * `last` - Whether the part is the last one, taking the closing instruction
+    fn render_part(
+        &self,
+        handlebars: &Handlebars<'static>,
+        template_name: &str,
+        files: &[FileEntry],
+        first: bool,
+        last: bool,
+    ) -> Result<String> {
+        let mut context = self.build_template_data();
+        context.files = Some(files);
+        if !first {
+            context.git_diff = &None;
+            context.git_diff_branch = &None;
+            context.git_log_branch = &None;
+            context.conflicts = &None;
+            context.git_stash = &None;
+            context.untracked_files = &None;
+            context.recent_commits = &None;
+            context.branch_changes = &None;
+            context.merged_pull_requests = &None;
+            context.context_delta = &None;
+            context.manifest_changes = &None;
+        }
+        let rendered = render_template(handlebars, template_name, &context)?;
+
+        let post_process_context = PostProcessContext {
+            output_format: self.config.output_format,
+            files,
+            token_count: files.iter().map(|file| file.token_count).sum(),
+            model_info: self.config.encoding.description(),
+        };
+        let mut body = self.post_process(
+            rendered,
+            &post_process_context,
+            &mut TokenBreakdown::default(),
+        )?;
+        if first && let Some(leading) = self.leading_instruction() {
+            body = format!("{}\n\n{}", leading, body);
+        }
+        let volatile = self.volatile_sections();
+        if last && !volatile.is_empty() {
+            body.push_str("\n\n");
+            body.push_str(&volatile);
+        }
+        Ok(body)
+    }
+
+    /// The template string and name, the default template of the output format if none is set.
+    fn template(&self) -> (String, String) {
+        if !self.config.template_str.is_empty() {
+            return (
+                self.config.template_str.clone(),
+                self.config.template_name.clone(),
+            );
+        }
+        match self.config.output_format {
+            OutputFormat::Markdown => (
+                include_str!("./default_template_md.hbs").to_string(),
+                "markdown".to_string(),
+            ),
+            OutputFormat::Xml | OutputFormat::Json | OutputFormat::Jsonl | OutputFormat::Chunks => {
+                (
+                    include_str!("./default_template_xml.hbs").to_string(),
+                    "xml".to_string(),
+                )
+            }
+        }
+    }
+
+    /// Sets up Handlebars with the template, the helpers and the file delimiter partials.
+    fn handlebars(&self, template_str: &str, template_name: &str) -> Result<Handlebars<'static>> {
+        let sections = SectionHelper::new(self.trimmed_sections());
+        self.handlebars_with_sections(template_str, template_name, &sections)
+    }
+
+    /// Sets up Handlebars with a `section` helper recording the sections, unless a helper of
+    /// that name was added to the session.
+    fn handlebars_with_sections(
+        &self,
+        template_str: &str,
+        template_name: &str,
+        sections: &SectionHelper,
+    ) -> Result<Handlebars<'static>> {
+        let mut handlebars =
+            handlebars_setup_with_helpers(template_str, template_name, &self.helpers)?;
+        if !self.helpers.names().any(|name| name == "section") {
+            handlebars.register_helper("section", Box::new(sections.clone()));
+        }
+        let (header, footer) = self.config.file_delimiter_partials();
+        register_file_delimiters(&mut handlebars, &header, &footer)?;
+        Ok(handlebars)
+    }
+
+    /// Runs the configured post-processing steps, then the added post-processors, on the
+    /// rendered template. The tokens they add or remove are counted as template tokens.
+    fn post_process(
+        &self,
+        rendered: String,
+        context: &PostProcessContext,
+        token_breakdown: &mut TokenBreakdown,
+    ) -> Result<String> {
+        let steps: Vec<Box<dyn PostProcessor>> = self
+            .config
+            .post_process
+            .iter()
+            .map(PostProcessStep::processor)
+            .collect();
+        let processors: Vec<&dyn PostProcessor> = steps
+            .iter()
+            .map(Box::as_ref)
+            .chain(self.post_processors.iter().map(Arc::as_ref))
+            .collect();
+        if processors.is_empty() {
+            return Ok(rendered);
+        }
 
-        Ok(())
+        let count = |text: &str| {
+            count_tokens_with_mode(
+                text,
+                "",
+                &self.config.encoding,
+                self.config.tokenizer_model.as_deref(),
+                self.config.token_count_mode,
+            )
+        };
+        let before = count(&rendered);
+        let processed = run_pipeline(rendered, &processors, context)?;
+        token_breakdown.template =
+            (token_breakdown.template + count(&processed)).saturating_sub(before);
+        Ok(processed)
     }
 
-    /// Loads the Git diff into the session data.
-    pub fn load_git_diff(&mut self) -> Result<()> {
-        let diff = get_git_diff(&self.config.path)?;
-        self.data.git_diff = Some(diff);
-        Ok(())
+    /// The instruction, unless it is placed before the codebase only, and the response
+    /// contract, appended after the codebase.
+    fn volatile_sections(&self) -> String {
+        let mut volatile_sections = Vec::new();
+        // With prompt caching, the instruction always follows the cacheable codebase
+        let instruction_last = self.config.placement.instruction_last()
+            || self.config.repeat_instruction
+            || self.config.prompt_cache.is_some();
+        if instruction_last && let Some(instruction) = &self.config.instruction {
+            volatile_sections.push(instruction_section(instruction, self.config.output_format));
+        }
+        if let Some(schema) = &self.config.response_schema {
+            volatile_sections.push(response_contract_section(schema, self.config.output_format));
+        }
+        volatile_sections.join("\n\n")
     }
 
-    /// Loads the Git diff between two branches into the session data.
-    pub fn load_git_diff_between_branches(&mut self) -> Result<()> {
-        if let Some((b1, b2)) = &self.config.diff_branches {
-            let diff = get_git_diff_between_branches(&self.config.path, b1, b2)?;
-            self.data.git_diff_branch = Some(diff);
+    /// The instruction rendered before the codebase, following `config.placement` or when
+    /// `config.repeat_instruction` is set. It is left out with prompt caching, which needs
+    /// the codebase first to reuse it.
+    fn leading_instruction(&self) -> Option<String> {
+        let instruction_first =
+            self.config.placement.instruction_first() || self.config.repeat_instruction;
+        if !instruction_first || self.config.prompt_cache.is_some() {
+            return None;
         }
-        Ok(())
+        self.config
+            .instruction
+            .as_ref()
+            .map(|instruction| instruction_section(instruction, self.config.output_format))
     }
 
-    /// Loads the Git log between two branches into the session data.
-    pub fn load_git_log_between_branches(&mut self) -> Result<()> {
-        if let Some((b1, b2)) = &self.config.log_branches {
-            let log_output = get_git_log(&self.config.path, b1, b2)?;
-            self.data.git_log_branch = Some(log_output);
+    /// Trims template sections and drops or truncates files, following
+    /// `config.budget_strategy`, until the prompt fits in `config.max_tokens`. The sections
+    /// with a negative priority are trimmed before any file, the others once no file is
+    /// left. What was removed is recorded in `data.budget_report`.
+    ///
+    /// Call it once everything the prompt includes is loaded, git diffs included, since
+    /// they count against the budget too.
+    pub fn enforce_token_budget(&mut self) {
+        let Some(max_tokens) = self.config.max_tokens else {
+            return;
+        };
+        self.data.budget_report = Some(BudgetReport {
+            max_tokens,
+            ..BudgetReport::default()
+        });
+
+        // Each pass frees an estimate of the excess, the next pass corrects the estimate
+        let mut previous_total = usize::MAX;
+        loop {
+            let total = self.estimated_token_count();
+            if total <= max_tokens {
+                if let Some(report) = self.data.budget_report.as_mut() {
+                    report.fits = true;
+                }
+                break;
+            }
+            if let Some(section) = self.lowest_priority_section(Some(0)) {
+                self.trim_section(section);
+                continue;
+            }
+            let report = self.data.budget_report.get_or_insert_default();
+            if let Some(files) = self.data.files.as_mut().filter(|files| !files.is_empty())
+                && total < previous_total
+            {
+                previous_total = total;
+                prune_files(files, total - max_tokens, &self.config, report);
+                continue;
+            }
+            if let Some(section) = self.lowest_priority_section(None) {
+                self.trim_section(section);
+                continue;
+            }
+            if self
+                .data
+                .files
+                .as_ref()
+                .is_some_and(|files| !files.is_empty())
+            {
+                log::warn!("No file left to prune, the prompt takes {} tokens", total);
+            } else {
+                log::warn!(
+                    "The prompt takes {} tokens without any file, over the budget of {}",
+                    total,
+                    max_tokens
+                );
+            }
+            break;
         }
-        Ok(())
     }
 
-    /// Constructs a zero-copy template context for rendering.
-    pub fn build_template_data(&self) -> TemplateContext<'_> {
-        TemplateContext {
-            absolute_code_path: self.data.absolute_code_path.as_deref().unwrap_or("unknown"),
-            source_tree: &self.data.source_tree,
-            files: self.data.files.as_deref(),
-            git_diff: &self.data.git_diff,
-            git_diff_branch: &self.data.git_diff_branch,
-            git_log_branch: &self.data.git_log_branch,
-            user_variables: &self.config.user_variables,
+    /// Replaces the files over `config.summarize_over` tokens with a summary written by the
+    /// session summarizer, labeled as a summary. The summaries are looked up in
+    /// `config.summary_cache` by content hash first, and the new ones are added to it. A
+    /// file whose summary fails keeps its content.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<String>>` - The paths of the summarized files
+    pub fn summarize_oversized_files(&mut self) -> Result<Vec<String>> {
+        let (Some(max_tokens), Some(summarizer)) =
+            (self.config.summarize_over, self.summarizer.clone())
+        else {
+            return Ok(Vec::new());
+        };
+        let mut cache = match &self.config.summary_cache {
+            Some(path) => SummaryCache::load(path)?,
+            None => SummaryCache::default(),
+        };
+        let mut cache_changed = false;
+
+        let mut summarized = Vec::new();
+        for file in self.data.files.iter_mut().flatten() {
+            if file.metadata.is_dir || file.token_count <= max_tokens {
+                continue;
+            }
+            let summary = match cache.summaries.get(&file.content_hash) {
+                Some(summary) => summary.clone(),
+                None => match summarizer.summarize(&summary_prompt(&file.path, &file.code)) {
+                    Ok(summary) => {
+                        cache
+                            .summaries
+                            .insert(file.content_hash.clone(), summary.clone());
+                        cache_changed = true;
+                        summary
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to summarize {}: {:#}", file.path, e);
+                        continue;
+                    }
+                },
+            };
+            file.code = summary_block(&summary, file.token_count);
+            file.token_count = count_tokens_with_mode(
+                &file.code,
+                "",
+                &self.config.encoding,
+                self.config.tokenizer_model.as_deref(),
+                self.config.token_count_mode,
+            );
+            summarized.push(file.path.clone());
+        }
+
+        if cache_changed && let Some(path) = &self.config.summary_cache {
+            cache.save(path)?;
         }
+        Ok(summarized)
     }
 
-    /// Renders the final prompt given a template context. Returns both
-    /// the rendered prompt and the token count information.
-    pub fn render_prompt(&self, template_context: &TemplateContext) -> Result<RenderedPrompt> {
-        // ~~~ Template selection ~~~
-        let mut template_str = self.config.template_str.clone();
-        let mut template_name = self.config.template_name.clone();
-        if self.config.template_str.is_empty() {
-            template_str = match self.config.output_format {
-                OutputFormat::Markdown => include_str!("./default_template_md.hbs").to_string(),
-                OutputFormat::Xml | OutputFormat::Json => {
-                    include_str!("./default_template_xml.hbs").to_string()
-                }
+    /// Compresses the files at the signatures level from the symbols reported by the
+    /// language servers of `config.lsp_servers`, replacing the heuristic compression. One
+    /// server is started per extension for the pass. A server that fails to start or to
+    /// answer is reported once, and its files keep the heuristic compression.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<String>>` - The paths of the files compressed from the symbols
+    pub fn apply_lsp_signatures(&mut self) -> Result<Vec<String>> {
+        if self.config.lsp_servers.is_empty() {
+            return Ok(Vec::new());
+        }
+        let config = &self.config;
+        let mut clients: HashMap<String, Option<LspClient>> = HashMap::new();
+        let mut compressed = Vec::new();
+        for file in self.data.files.iter_mut().flatten() {
+            let relative = config.root_relative_path(&file.path);
+            if file.metadata.is_dir
+                || file.line_range.is_some()
+                || config.file_compression_level(&relative) != CompressionLevel::Signatures
+            {
+                continue;
+            }
+            let Some(command) = config.lsp_servers.get(&file.extension) else {
+                continue;
             };
-            template_name = match self.config.output_format {
-                OutputFormat::Markdown => "markdown".to_string(),
-                OutputFormat::Xml | OutputFormat::Json => "xml".to_string(),
+            let client = clients.entry(file.extension.clone()).or_insert_with(|| {
+                LspClient::start(command, &config.path)
+                    .inspect_err(|e| log::warn!("{:#}", e))
+                    .ok()
+            });
+            let Some(lsp) = client else {
+                continue;
             };
-        }
 
-        // ~~~ Rendering ~~~
-        let handlebars = handlebars_setup(&template_str, &template_name)?;
-        let template_content = render_template(&handlebars, &template_name, template_context)?;
+            let path = config.path.join(&relative);
+            let Ok(code) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let absolute = path.canonicalize().unwrap_or(path);
+            let symbols = match lsp.document_symbols(&absolute, &code) {
+                Ok(symbols) => symbols,
+                Err(e) => {
+                    log::warn!("{:#}", e);
+                    *client = None;
+                    continue;
+                }
+            };
+            let code = compress_with_symbols(&code, &symbols);
+            file.code = wrap_code_block(
+                &code,
+                fence_language(&file.extension, config),
+                false,
+                config.no_codeblock,
+            );
+            file.token_count = count_tokens_with_mode(
+                &code,
+                &file.extension,
+                &config.encoding,
+                config.tokenizer_model.as_deref(),
+                config.token_count_mode,
+            );
+            compressed.push(file.path.clone());
+        }
+        Ok(compressed)
+    }
 
-        // ~~~ Informations ~~~
-        let tokenizer_type: TokenizerType = self.config.encoding;
-        // Always use the cached calculation: Σ(FileTokens) + TemplateOverhead
-        // This avoids re-tokenizing the entire rendered output (sequential bottleneck)
-        let token_count = self.calculate_token_count_from_cache(&tokenizer_type);
+    /// Measures the prompt against `config.max_tokens` without pruning anything, recording
+    /// whether it fits in `data.budget_report`.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<usize>` - The tokens over the budget, 0 when the prompt fits, `None`
+    ///   without a budget
+    pub fn token_budget_excess(&mut self) -> Option<usize> {
+        let max_tokens = self.config.max_tokens?;
+        let total = self.estimated_token_count();
+        let report = self.data.budget_report.get_or_insert_with(|| BudgetReport {
+            max_tokens,
+            ..BudgetReport::default()
+        });
+        report.fits = total <= max_tokens;
+        Some(total.saturating_sub(max_tokens))
+    }
 
-        let model_info = tokenizer_type.description();
-        let directory_name = template_context.absolute_code_path.to_string();
-        let files: Vec<String> = self
-            .data
-            .files
-            .as_ref()
-            .map(|files| files.iter().map(|file| file.path.clone()).collect())
-            .unwrap_or_default();
+    /// Drops, summarizes or truncates a file of the prompt to reduce its tokens, as decided
+    /// when the budget is negotiated file by file. What was done is recorded in
+    /// `data.budget_report`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, as in the prompt
+    /// * `action` - What to do with the file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The tokens of the file saved by the action
+    pub fn apply_budget_action(&mut self, path: &str, action: BudgetAction) -> Result<usize> {
+        let excess = self.token_budget_excess().unwrap_or(0);
+        let summary = match action {
+            BudgetAction::Summarize => Some(self.summarized_entry(path)?),
+            _ => None,
+        };
+        let files = self.data.files.as_mut();
+        let Some((index, files)) = files.and_then(|files| {
+            let index = files.iter().position(|file| file.path == path)?;
+            Some((index, files))
+        }) else {
+            anyhow::bail!("No file {} in the prompt", path);
+        };
+        let before = files[index].token_count;
 
-        // ~~~ Final output format ~~~
-        let final_output = match self.config.output_format {
-            OutputFormat::Json => {
-                let json_data = serde_json::json!({
-                    "prompt": template_content,
-                    "directory_name": directory_name.clone(),
-                    "token_count": token_count,
-                    "model_info": model_info,
-                    "files": files.clone(),
-                });
-                serde_json::to_string_pretty(&json_data)?
+        let after = if let Some(mut entry) = summary {
+            if entry.token_count >= before {
+                anyhow::bail!("No signatures to keep of {}", path);
             }
-            _ => template_content,
+            entry.mod_time = files[index].mod_time;
+            entry.history = files[index].history.take();
+            let after = entry.token_count;
+            files[index] = entry;
+            after
+        } else if action == BudgetAction::Truncate {
+            let keep_tokens = before.saturating_sub(excess).max(before / 2);
+            truncate_file(&mut files[index], keep_tokens, &self.config);
+            files[index].token_count
+        } else {
+            files.remove(index);
+            0
         };
 
-        Ok(RenderedPrompt {
-            prompt: final_output,
-            directory_name,
-            token_count,
-            model_info,
-            files,
-        })
+        let report = self.data.budget_report.get_or_insert_default();
+        let paths = match action {
+            BudgetAction::Drop => &mut report.dropped_files,
+            BudgetAction::Summarize => &mut report.summarized_files,
+            BudgetAction::Truncate => &mut report.truncated_files,
+        };
+        if !paths.iter().any(|existing| existing == path) {
+            paths.push(path.to_string());
+        }
+        self.token_budget_excess();
+        Ok(before.saturating_sub(after))
+    }
+
+    /// Reads a file again with signatures-only compression, which is kept for the file.
+    fn summarized_entry(&mut self, path: &str) -> Result<FileEntry> {
+        let relative_path = self.config.root_relative_path(path);
+        let absolute_path = self.config.path.join(&relative_path);
+        self.set_file_compression(relative_path.clone(), CompressionLevel::Signatures);
+        let content = std::fs::read(&absolute_path)
+            .with_context(|| format!("Failed to read {}", absolute_path.display()))?;
+        let metadata = std::fs::metadata(&absolute_path)?;
+        build_file_entry(
+            &relative_path,
+            path.to_string(),
+            &content,
+            EntryMetadata::from(&metadata),
+            None,
+            &self.config,
+        )
+        .with_context(|| format!("Failed to summarize {}", path))
+    }
+
+    /// The sections the template renders, left out the trimmed ones, in the order they
+    /// are first rendered.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<TemplateSection>` - The sections and their priorities, empty when the
+    ///   template cannot be rendered
+    pub fn template_sections(&self) -> Vec<TemplateSection> {
+        let sections = SectionHelper::new(self.trimmed_sections());
+        self.render_skeleton(&sections);
+        sections.rendered()
+    }
+
+    /// The sections trimmed to fit the token budget.
+    fn trimmed_sections(&self) -> Vec<String> {
+        self.data
+            .budget_report
+            .as_ref()
+            .map(|report| report.trimmed_sections.clone())
+            .unwrap_or_default()
+    }
+
+    /// The untrimmed section of lowest priority, the last rendered on a tie.
+    ///
+    /// # Arguments
+    ///
+    /// * `below` - Only sections with a lower priority are considered, if set
+    fn lowest_priority_section(&self, below: Option<i64>) -> Option<String> {
+        self.template_sections()
+            .into_iter()
+            .rev()
+            .filter(|section| below.is_none_or(|below| section.priority < below))
+            .min_by_key(|section| section.priority)
+            .map(|section| section.name)
+    }
+
+    /// Leaves a section out of the prompt.
+    fn trim_section(&mut self, name: String) {
+        log::info!("Trimming the '{}' section to fit the token budget", name);
+        self.data
+            .budget_report
+            .get_or_insert_default()
+            .trimmed_sections
+            .push(name);
+    }
+
+    /// The token count of the prompt, as reported by `render_prompt`.
+    pub fn estimated_token_count(&self) -> usize {
+        let tokenizer_type = self.config.encoding;
+        let volatile_tokens = count_tokens_with_mode(
+            &format!(
+                "{}{}",
+                self.leading_instruction().unwrap_or_default(),
+                self.volatile_sections()
+            ),
+            "",
+            &tokenizer_type,
+            self.config.tokenizer_model.as_deref(),
+            self.config.token_count_mode,
+        );
+        self.calculate_token_breakdown(&tokenizer_type).total() + volatile_tokens
     }
 
-    /// Calculate exact token count using cached per-file token counts + skeleton rendering
+    /// Calculate the per-section token counts using cached per-file token counts + skeleton rendering
     ///
     /// This method provides precise token counting by:
     /// 1. Summing the cached per-file token counts (from actual content tokenized in parallel)
     /// 2. Rendering a "skeleton" template with empty file contents to get structural tokens
-    /// 3. Adding them together for an exact count
+    /// 3. Splitting the structural tokens into tree, git and template overhead
     ///
     /// This approach avoids re-tokenizing the entire rendered output (sequential bottleneck).
+    /// The instruction section is left at zero and filled in by the caller.
     ///
     /// # Arguments
     ///
@@ -305,20 +2143,67 @@ impl Code2PromptSession {
     ///
     /// # Returns
     ///
-    /// * `usize` - The exact total token count
-    fn calculate_token_count_from_cache(&self, tokenizer_type: &TokenizerType) -> usize {
+    /// * `TokenBreakdown` - The token counts per section
+    fn calculate_token_breakdown(&self, tokenizer_type: &TokenizerType) -> TokenBreakdown {
+        let mode = self.config.token_count_mode;
+        let model = self.config.tokenizer_model.as_deref();
+
         // Sum up cached per-file token counts (tokens from actual file content)
-        let files_token_count: usize = self
+        let files: usize = self
             .data
             .files
             .as_ref()
             .map(|files| files.iter().map(|file| file.token_count).sum())
             .unwrap_or(0);
 
+        let tree = self
+            .data
+            .source_tree
+            .as_deref()
+            .map(|tree| count_tokens_with_mode(tree, "", tokenizer_type, model, mode))
+            .unwrap_or(0);
+
+        let git = [
+            &self.data.git_diff,
+            &self.data.git_diff_branch,
+            &self.data.git_log_branch,
+            &self.data.git_stash,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|section| count_tokens_with_mode(section, "diff", tokenizer_type, model, mode))
+        .sum::<usize>()
+            + self
+                .data
+                .conflicts
+                .iter()
+                .flat_map(|conflicts| &conflicts.files)
+                .map(|file| match &file.merged {
+                    Some(merged) => count_tokens_with_mode(merged, "", tokenizer_type, model, mode),
+                    None => [&file.base, &file.ours, &file.theirs]
+                        .into_iter()
+                        .flatten()
+                        .map(|side| count_tokens_with_mode(side, "", tokenizer_type, model, mode))
+                        .sum(),
+                })
+                .sum::<usize>();
+
         // Calculate exact structural/template overhead using skeleton rendering
         let structural_tokens = self.calculate_structural_tokens(tokenizer_type);
 
-        files_token_count + structural_tokens
+        // The trimmed sections take their tree and git tokens with them, git first
+        let trimmed = self.trimmed_section_tokens(structural_tokens, tokenizer_type);
+        let git_trimmed = trimmed.min(git);
+        let git = git - git_trimmed;
+        let tree = tree.saturating_sub(trimmed - git_trimmed);
+
+        TokenBreakdown {
+            tree,
+            files,
+            git,
+            instruction: 0,
+            template: structural_tokens.saturating_sub(tree + git),
+        }
     }
 
     /// Calculate structural tokens by rendering a skeleton template
@@ -335,6 +2220,58 @@ impl Code2PromptSession {
     ///
     /// * `usize` - The number of structural tokens
     fn calculate_structural_tokens(&self, tokenizer_type: &TokenizerType) -> usize {
+        match self.render_skeleton(&SectionHelper::new(self.trimmed_sections())) {
+            Some(skeleton_rendered) => count_tokens_with_mode(
+                &skeleton_rendered,
+                "",
+                tokenizer_type,
+                self.config.tokenizer_model.as_deref(),
+                self.config.token_count_mode,
+            ),
+            // Fallback to simple estimation if rendering fails
+            None => self.fallback_structural_estimate(tokenizer_type),
+        }
+    }
+
+    /// The structural tokens of the trimmed sections, what the skeleton loses when they are
+    /// left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `structural_tokens` - The structural tokens, the trimmed sections left out
+    /// * `tokenizer_type` - The tokenizer to use for counting
+    fn trimmed_section_tokens(
+        &self,
+        structural_tokens: usize,
+        tokenizer_type: &TokenizerType,
+    ) -> usize {
+        if self.trimmed_sections().is_empty() {
+            return 0;
+        }
+        self.render_skeleton(&SectionHelper::default())
+            .map(|skeleton| {
+                count_tokens_with_mode(
+                    &skeleton,
+                    "",
+                    tokenizer_type,
+                    self.config.tokenizer_model.as_deref(),
+                    self.config.token_count_mode,
+                )
+            })
+            .map_or(0, |full| full.saturating_sub(structural_tokens))
+    }
+
+    /// Renders the template with empty file contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `sections` - The `section` helper, giving the trimmed sections and recording the
+    ///   rendered ones
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - The rendered skeleton, `None` if the template fails to render
+    fn render_skeleton(&self, sections: &SectionHelper) -> Option<String> {
         // Create skeleton file entries (empty code, but same structure/metadata)
         let skeleton_files: Option<Vec<FileEntry>> = self.data.files.as_ref().map(|files| {
             files
@@ -343,7 +2280,7 @@ impl Code2PromptSession {
                     // Create empty code block with same wrapping structure
                     let empty_code_block = wrap_code_block(
                         "",
-                        &file.extension,
+                        fence_language(&file.extension, &self.config),
                         self.config.line_numbers,
                         self.config.no_codeblock,
                     );
@@ -353,31 +2290,28 @@ impl Code2PromptSession {
                         extension: file.extension.clone(),
                         code: empty_code_block,
                         token_count: 0, // Not used in skeleton
+                        content_hash: file.content_hash.clone(),
                         metadata: file.metadata,
                         mod_time: file.mod_time,
+                        history: None,
+                        line_range: None,
+                        stats: file.stats.clone(),
+                        symbols: None,
                     }
                 })
                 .collect()
         });
 
-        // Build skeleton template context (same structure, but with empty file contents)
-        let skeleton_context = TemplateContext {
-            absolute_code_path: self.data.absolute_code_path.as_deref().unwrap_or("unknown"),
-            source_tree: &self.data.source_tree,
-            files: skeleton_files.as_deref(),
-            git_diff: &self.data.git_diff,
-            git_diff_branch: &self.data.git_diff_branch,
-            git_log_branch: &self.data.git_log_branch,
-            user_variables: &self.config.user_variables,
-        };
+        let skeleton_context = self.template_context(skeleton_files.as_deref());
 
         // Render skeleton template
         let template_str = if self.config.template_str.is_empty() {
             match self.config.output_format {
                 OutputFormat::Markdown => include_str!("./default_template_md.hbs").to_string(),
-                OutputFormat::Xml | OutputFormat::Json => {
-                    include_str!("./default_template_xml.hbs").to_string()
-                }
+                OutputFormat::Xml
+                | OutputFormat::Json
+                | OutputFormat::Jsonl
+                | OutputFormat::Chunks => include_str!("./default_template_xml.hbs").to_string(),
             }
         } else {
             self.config.template_str.clone()
@@ -386,28 +2320,19 @@ impl Code2PromptSession {
         let template_name = if self.config.template_name.is_empty() {
             match self.config.output_format {
                 OutputFormat::Markdown => "markdown".to_string(),
-                OutputFormat::Xml | OutputFormat::Json => "xml".to_string(),
+                OutputFormat::Xml
+                | OutputFormat::Json
+                | OutputFormat::Jsonl
+                | OutputFormat::Chunks => "xml".to_string(),
             }
         } else {
             self.config.template_name.clone()
         };
 
-        // Render and count tokens
-        match handlebars_setup(&template_str, &template_name) {
-            Ok(handlebars) => {
-                match render_template(&handlebars, &template_name, &skeleton_context) {
-                    Ok(skeleton_rendered) => count_tokens(&skeleton_rendered, tokenizer_type),
-                    Err(_) => {
-                        // Fallback to simple estimation if rendering fails
-                        self.fallback_structural_estimate(tokenizer_type)
-                    }
-                }
-            }
-            Err(_) => {
-                // Fallback to simple estimation if handlebars setup fails
-                self.fallback_structural_estimate(tokenizer_type)
-            }
-        }
+        let handlebars = self
+            .handlebars_with_sections(&template_str, &template_name, sections)
+            .ok()?;
+        render_template(&handlebars, &template_name, &skeleton_context).ok()
     }
 
     /// Fallback estimation when skeleton rendering fails
@@ -449,7 +2374,13 @@ impl Code2PromptSession {
                 self.data.git_diff_branch.as_deref().unwrap_or(""),
                 self.data.git_log_branch.as_deref().unwrap_or("")
             );
-            count_tokens(&combined, tokenizer_type)
+            count_tokens_with_mode(
+                &combined,
+                "",
+                tokenizer_type,
+                self.config.tokenizer_model.as_deref(),
+                self.config.token_count_mode,
+            )
         } else {
             estimated
         }
@@ -457,9 +2388,50 @@ impl Code2PromptSession {
 
     pub fn generate_prompt(&mut self) -> Result<RenderedPrompt> {
         self.load_codebase()?;
+        self.apply_lsp_signatures()?;
+        self.summarize_oversized_files()?;
+
+        // ~~~ Manifest ~~~
+        let manifest = match self.config.manifest_file.clone() {
+            Some(path) => {
+                self.load_manifest_changes(&FileManifest::load(&path)?);
+                Some((path, self.manifest()))
+            }
+            None => None,
+        };
+
+        // ~~~ Conversation delta ~~~
+        let conversation = match self.config.conversation_file.clone() {
+            Some(path) => {
+                let mut state = ConversationState::load(&path)?;
+                self.apply_conversation_delta(&mut state);
+                Some((path, state))
+            }
+            None => None,
+        };
+
+        self.load_git_sections();
+        self.enforce_token_budget();
+        let template_data = self.build_template_data();
+        self.report_progress(ProgressEvent::RenderStarted);
+        let rendered = self.render_prompt(&template_data)?;
+
+        if let Some((path, manifest)) = manifest {
+            manifest.save(&path)?;
+        }
+        if let Some((path, state)) = conversation {
+            state.save(&path)?;
+        }
+        self.report_progress(ProgressEvent::Done {
+            token_count: rendered.token_count,
+        });
+        Ok(rendered)
+    }
 
+    /// Loads the git sections enabled in the configuration, logging those that fail.
+    fn load_git_sections(&mut self) {
         // ~~~~ Load Git info ~~~
-        if self.config.diff_enabled {
+        if self.config.working_diff_enabled() {
             match self.load_git_diff() {
                 Ok(_) => {}
                 Err(e) => log::warn!("Git diff could not be loaded: {}", e),
@@ -481,8 +2453,257 @@ impl Code2PromptSession {
                 Err(e) => log::warn!("Git branch log could not be loaded: {}", e),
             }
         }
-        let template_data = self.build_template_data();
-        let rendered = self.render_prompt(&template_data)?;
-        Ok(rendered)
+
+        // ~~~ Load work in progress ~~~
+        if self.config.stash.is_some() {
+            match self.load_git_stash() {
+                Ok(_) => {}
+                Err(e) => log::warn!("Git stash could not be loaded: {}", e),
+            }
+        }
+        if self.config.untracked_enabled {
+            match self.load_untracked_files() {
+                Ok(_) => {}
+                Err(e) => log::warn!("Untracked files could not be listed: {}", e),
+            }
+        }
+
+        // ~~~ Load merge conflicts ~~~
+        if self.config.conflicts_enabled {
+            match self.load_merge_conflicts() {
+                Ok(_) => {}
+                Err(e) => log::warn!("Merge conflicts could not be loaded: {}", e),
+            }
+        }
+
+        let config = &self.config;
+        if config.working_diff_enabled()
+            || config.diff_branches.is_some()
+            || config.log_branches.is_some()
+            || config.stash.is_some()
+            || config.untracked_enabled
+            || config.conflicts_enabled
+        {
+            self.report_progress(ProgressEvent::GitDiffLoaded);
+        }
+    }
+
+    /// Traverses the codebase, loading the source tree but not the files, which are read
+    /// one at a time as the returned stream is consumed.
+    ///
+    /// The files are not kept in `data.files`, and what needs all of them at once (the token
+    /// budget, frontend summaries, manifests and conversation deltas) is not applied.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<FileStream>` - The file entries, in the configured sort order
+    pub fn stream_files(&mut self) -> Result<FileStream> {
+        if self.config.git_dir.is_some() || self.config.git_rev.is_some() {
+            anyhow::bail!("Files of a git revision cannot be streamed, load the codebase instead");
+        }
+        self.prepare_tokenizer()?;
+        let (tree, stream) = stream_directory(&self.config, Some(&mut self.selection_engine))
+            .with_context(|| "Failed to traverse directory")?;
+
+        self.data.absolute_code_path = Some(display_name(&self.config.path));
+        self.data.source_tree = Some(tree);
+        self.data.files = None;
+        self.data.environment_spec = None;
+        if self.template_mentions("environment_spec") {
+            self.data.environment_spec = detect_environment(&self.config.path);
+        }
+        self.data.version_info = None;
+        if self.template_mentions("version_info") {
+            self.load_version_info();
+        }
+        Ok(stream)
+    }
+
+    /// Lists the files that would be included and estimates the tokens of the prompt from
+    /// their sizes, without reading them or rendering the template: a quick preview of a
+    /// full run. Git sections and the token budget are left out.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PromptEstimate>` - The files that would be included and the estimate
+    pub fn estimate(&mut self) -> Result<PromptEstimate> {
+        if self.config.git_dir.is_some() || self.config.git_rev.is_some() {
+            anyhow::bail!("Files of a git revision cannot be estimated, load the codebase instead");
+        }
+        let (source_tree, files) =
+            estimate_directory(&self.config, Some(&mut self.selection_engine))
+                .with_context(|| "Failed to traverse directory")?;
+
+        let token_count = estimate_tokens(&source_tree, "")
+            + estimate_tokens(&self.config.template_str, "")
+            + files
+                .iter()
+                .map(|file| file.token_count + estimate_tokens(&file.path, ""))
+                .sum::<usize>();
+        Ok(PromptEstimate {
+            directory_name: display_name(&self.config.path),
+            source_tree,
+            total_size: files.iter().map(|file| file.size).sum(),
+            files,
+            token_count,
+        })
+    }
+
+    /// Explains how each file of the codebase is included and why, without rendering the
+    /// prompt (delegates to [`inclusion_plan`]).
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<PlanEntry>>` - One entry per file, sorted by path
+    pub fn inclusion_plan(&self) -> Result<Vec<PlanEntry>> {
+        if self.config.git_dir.is_some() || self.config.git_rev.is_some() {
+            anyhow::bail!("The files of a git revision have no inclusion plan");
+        }
+        inclusion_plan(&self.config, Some(&self.selection_engine))
+    }
+
+    /// Renders the prompt straight into a writer, file by file, so that memory stays bounded
+    /// on very large codebases. The git sections are loaded as in `generate_prompt`, and the
+    /// files get their history and symbols as in `load_codebase`.
+    ///
+    /// The template is rendered once without files and once with a placeholder file, the
+    /// difference giving the text before and after the files. Each file is then rendered
+    /// alone and written without that text. Templates must therefore list the files in a
+    /// single `{{#each files}}` loop rendering each file alike, whatever its place in the
+    /// list (`@index`, `@first` and `@last` are refused). Post-processing is not applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Where the prompt is written
+    ///
+    /// # Returns
+    ///
+    /// * `Result<StreamedPrompt>` - The files written and the token count of the prompt
+    pub fn render_to_writer<W: Write>(&mut self, writer: &mut W) -> Result<StreamedPrompt> {
+        let stream = self.stream_files()?;
+        self.load_git_sections();
+        let tags = match self.config.tags_file.clone() {
+            Some(tags_file) => Some(self.tags_index(&tags_file)?),
+            None => None,
+        };
+        let options = self.git_diff_options();
+
+        let (template_str, template_name) = self.template();
+        let handlebars = self.handlebars(&template_str, &template_name)?;
+        let placeholder = FileEntry {
+            path: STREAM_PLACEHOLDERS[0].to_string(),
+            extension: STREAM_PLACEHOLDERS[1].to_string(),
+            code: STREAM_PLACEHOLDERS[2].to_string(),
+            token_count: 0,
+            content_hash: STREAM_PLACEHOLDERS[3].to_string(),
+            metadata: EntryMetadata {
+                is_dir: false,
+                is_symlink: false,
+            },
+            mod_time: None,
+            history: None,
+            line_range: None,
+            stats: SourceStats::default(),
+            symbols: None,
+        };
+        let placeholders = [placeholder.clone(), placeholder];
+        let render = |files: &[FileEntry]| {
+            render_template(
+                &handlebars,
+                &template_name,
+                &self.template_context(Some(files)),
+            )
+        };
+        let without_files = render(&[])?;
+        let with_file = render(&placeholders[..1])?;
+        let (before, per_file, after) = split_file_section(&without_files, &with_file)?;
+        if render(&placeholders)? != format!("{}{}{}{}", before, per_file, per_file, after) {
+            anyhow::bail!(
+                "The template must render each file alike to be streamed, without `@index`, `@first` or `@last` in the files loop"
+            );
+        }
+
+        // Files are rendered without the source tree and the git diffs, unless their text
+        // depends on them
+        let render_alone = |files: &[FileEntry]| {
+            render_template(&handlebars, &template_name, &self.file_context(files))
+        };
+        let alone_without_files = render_alone(&[])?;
+        let alone_with_file = render_alone(&placeholders[..1])?;
+        let (alone_before, alone_per_file, alone_after) =
+            split_file_section(&alone_without_files, &alone_with_file)?;
+        let alone = alone_per_file == per_file;
+        let (file_before, file_after) = if alone {
+            (alone_before, alone_after)
+        } else {
+            (before, after)
+        };
+
+        let count = |text: &str| {
+            count_tokens_with_mode(
+                text,
+                "",
+                &self.config.encoding,
+                self.config.tokenizer_model.as_deref(),
+                self.config.token_count_mode,
+            )
+        };
+        let volatile = self.volatile_sections();
+        let leading = self.leading_instruction();
+        let mut token_count = count(before) + count(after) + count(&volatile);
+
+        if let Some(leading) = &leading {
+            writer.write_all(leading.as_bytes())?;
+            writer.write_all(b"\n\n")?;
+            token_count += count(leading);
+        }
+        writer.write_all(before.as_bytes())?;
+        let mut files = Vec::new();
+        for mut file in stream {
+            self.annotate_streamed(&mut file, tags.as_deref(), &options)?;
+            let entry = std::slice::from_ref(&file);
+            let rendered = if alone {
+                render_alone(entry)?
+            } else {
+                render(entry)?
+            };
+            let text = rendered
+                .strip_prefix(file_before)
+                .and_then(|text| text.strip_suffix(file_after))
+                .with_context(|| {
+                    format!(
+                        "The text of {} cannot be told apart from the rest of the template",
+                        file.path
+                    )
+                })?;
+            writer.write_all(text.as_bytes())?;
+            token_count += count(&text.replacen(&file.code, "", 1)) + file.token_count;
+            files.push(file.path);
+        }
+        for requested in self.config.file_history.keys() {
+            let requested = Path::new(requested);
+            if !files
+                .iter()
+                .any(|path| self.config.root_relative_path(path) == requested)
+            {
+                log::warn!(
+                    "No history for {}: the file is not included",
+                    requested.display()
+                );
+            }
+        }
+        writer.write_all(after.as_bytes())?;
+        if !volatile.is_empty() {
+            writer.write_all(b"\n\n")?;
+            writer.write_all(volatile.as_bytes())?;
+        }
+        writer.flush()?;
+
+        Ok(StreamedPrompt {
+            directory_name: self.data.absolute_code_path.clone().unwrap_or_default(),
+            token_count,
+            model_info: self.config.encoding.description(),
+            files,
+        })
     }
 }
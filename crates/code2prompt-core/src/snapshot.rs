@@ -0,0 +1,158 @@
+//! This module compares two generated runs of a session: the files added, removed or
+//! changed between them, and the tokens each one adds or saves, so that a prompt that
+//! suddenly grew can be explained file by file.
+
+use crate::path::FileEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What a file weighed in a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    pub token_count: usize,
+    pub content_hash: String,
+}
+
+/// The files of a generated run and the tokens of its prompt
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSnapshot {
+    /// Tokens of the whole prompt, the template included
+    pub token_count: usize,
+    /// The files of the prompt, keyed by path
+    pub files: BTreeMap<String, FileSnapshot>,
+}
+
+/// The tokens of a file in two runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileDelta {
+    pub path: String,
+    /// Tokens in the first run, 0 for an added file
+    pub before: usize,
+    /// Tokens in the second run, 0 for a removed file
+    pub after: usize,
+}
+
+impl FileDelta {
+    /// The tokens the file adds, negative when it saves some
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+/// The differences between two runs, each list sorted by decreasing token change
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SnapshotDiff {
+    /// Tokens of the prompt in the first run
+    pub before_tokens: usize,
+    /// Tokens of the prompt in the second run
+    pub after_tokens: usize,
+    pub added: Vec<FileDelta>,
+    pub removed: Vec<FileDelta>,
+    /// Files whose content or tokens changed
+    pub changed: Vec<FileDelta>,
+    /// Number of files identical in both runs
+    pub unchanged: usize,
+}
+
+impl SnapshotDiff {
+    /// The tokens the second run adds, negative when it saves some
+    pub fn token_delta(&self) -> i64 {
+        self.after_tokens as i64 - self.before_tokens as i64
+    }
+
+    /// The part of the token change due to the files, the rest being due to the template,
+    /// the tree or the git data
+    pub fn files_token_delta(&self) -> i64 {
+        self.added
+            .iter()
+            .chain(&self.removed)
+            .chain(&self.changed)
+            .map(FileDelta::delta)
+            .sum()
+    }
+
+    /// Whether the two runs have the same files and tokens
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.before_tokens == self.after_tokens
+    }
+}
+
+impl SessionSnapshot {
+    /// Builds the snapshot of a run.
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - The files of the prompt
+    /// * `token_count` - The tokens of the whole prompt
+    pub fn from_files(files: &[FileEntry], token_count: usize) -> Self {
+        Self {
+            token_count,
+            files: files
+                .iter()
+                .map(|file| {
+                    (
+                        file.path.clone(),
+                        FileSnapshot {
+                            token_count: file.token_count,
+                            content_hash: file.content_hash.clone(),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Compares two runs of a session.
+///
+/// # Arguments
+///
+/// * `before` - The earlier run
+/// * `after` - The later run
+///
+/// # Returns
+///
+/// * `SnapshotDiff` - The files added, removed and changed, and the tokens of both runs
+pub fn diff_snapshots(before: &SessionSnapshot, after: &SessionSnapshot) -> SnapshotDiff {
+    let mut diff = SnapshotDiff {
+        before_tokens: before.token_count,
+        after_tokens: after.token_count,
+        ..SnapshotDiff::default()
+    };
+    for (path, file) in &after.files {
+        let delta = |before: usize| FileDelta {
+            path: path.clone(),
+            before,
+            after: file.token_count,
+        };
+        match before.files.get(path) {
+            None => diff.added.push(delta(0)),
+            Some(previous) if previous == file => diff.unchanged += 1,
+            Some(previous) => diff.changed.push(delta(previous.token_count)),
+        }
+    }
+    diff.removed = before
+        .files
+        .iter()
+        .filter(|(path, _)| !after.files.contains_key(*path))
+        .map(|(path, file)| FileDelta {
+            path: path.clone(),
+            before: file.token_count,
+            after: 0,
+        })
+        .collect();
+
+    for deltas in [&mut diff.added, &mut diff.removed, &mut diff.changed] {
+        deltas.sort_by(|a, b| {
+            b.delta()
+                .unsigned_abs()
+                .cmp(&a.delta().unsigned_abs())
+                .then_with(|| a.path.cmp(&b.path))
+        });
+    }
+    diff
+}
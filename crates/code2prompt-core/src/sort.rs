@@ -1,7 +1,9 @@
 //! This module provides sorting methods for files and directory trees.
 
 use crate::path::FileEntry;
+use crate::tour::{dependency_order, local_imports};
 use serde::{self, Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use termtree::Tree;
 
@@ -17,6 +19,31 @@ pub enum FileSortMethod {
     DateAsc,
     /// Sort files by modification date (newest first)
     DateDesc,
+    /// Sort files by extension, then by name
+    Extension,
+    /// Sort files by token count (largest first)
+    TokensDesc,
+    /// Sort files so that each comes after the local files it imports
+    Imports,
+}
+
+impl FileSortMethod {
+    /// All the sort methods, in the order they are cycled through
+    pub const ALL: [FileSortMethod; 7] = [
+        FileSortMethod::NameAsc,
+        FileSortMethod::NameDesc,
+        FileSortMethod::DateAsc,
+        FileSortMethod::DateDesc,
+        FileSortMethod::Extension,
+        FileSortMethod::TokensDesc,
+        FileSortMethod::Imports,
+    ];
+
+    /// Returns the sort method following this one, wrapping around
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
 }
 
 impl fmt::Display for FileSortMethod {
@@ -26,6 +53,9 @@ impl fmt::Display for FileSortMethod {
             FileSortMethod::NameDesc => write!(f, "Name (Z → A)"),
             FileSortMethod::DateAsc => write!(f, "Date (Old → New)"),
             FileSortMethod::DateDesc => write!(f, "Date (New → Old)"),
+            FileSortMethod::Extension => write!(f, "Extension"),
+            FileSortMethod::TokensDesc => write!(f, "Tokens (Large → Small)"),
+            FileSortMethod::Imports => write!(f, "Imports (Dependencies first)"),
         }
     }
 }
@@ -53,13 +83,48 @@ pub fn sort_files(files: &mut [FileEntry], sort_method: Option<FileSortMethod>)
             FileSortMethod::DateDesc => {
                 files.sort_by_key(|f| std::cmp::Reverse(f.mod_time.unwrap_or(0)));
             }
+            FileSortMethod::Extension => {
+                files.sort_by(|a, b| (&a.extension, &a.path).cmp(&(&b.extension, &b.path)));
+            }
+            FileSortMethod::TokensDesc => {
+                files.sort_by(|a, b| {
+                    b.token_count
+                        .cmp(&a.token_count)
+                        .then_with(|| a.path.cmp(&b.path))
+                });
+            }
+            FileSortMethod::Imports => sort_by_imports(files),
+        }
+    }
+}
+
+/// Sorts the files so that each comes after the local files it imports, by name
+/// otherwise. Files in an import cycle keep their name order.
+fn sort_by_imports(files: &mut [FileEntry]) {
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    let members: BTreeSet<&str> = paths.iter().copied().collect();
+
+    let mut depends_on: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for file in files.iter() {
+        for dependency in local_imports(&file.path, &file.code, &members) {
+            if dependency != file.path {
+                depends_on.entry(&file.path).or_default().insert(dependency);
+            }
         }
     }
+    let rank: BTreeMap<String, usize> = dependency_order(&paths, &depends_on)
+        .into_iter()
+        .enumerate()
+        .map(|(index, path)| (path.to_string(), index))
+        .collect();
+    files.sort_by_key(|file| rank.get(&file.path).copied().unwrap_or(usize::MAX));
 }
 
 /// Recursively sorts a directory tree (represented by `termtree::Tree<D>`) in place using the specified
 /// `FileSortMethod`. For directory nodes, since modification time is typically unavailable, this function
-/// falls back to sorting by name. In effect, DateAsc is treated as NameAsc and DateDesc as NameDesc for directories.
+/// falls back to sorting by name. In effect, DateAsc is treated as NameAsc and DateDesc as NameDesc for directories,
+/// and the sorts by extension, tokens or imports as NameAsc.
 ///
 /// If `sort_method` is `None`, no sorting is performed.
 ///
@@ -74,7 +139,11 @@ pub fn sort_tree<D: Ord + std::fmt::Display>(
     if let Some(method) = sort_method {
         // For directories we only have the name (the root), so date-based sorts fall back to name sorting.
         let ascending = match method {
-            FileSortMethod::NameAsc | FileSortMethod::DateAsc => true,
+            FileSortMethod::NameAsc
+            | FileSortMethod::DateAsc
+            | FileSortMethod::Extension
+            | FileSortMethod::TokensDesc
+            | FileSortMethod::Imports => true,
             FileSortMethod::NameDesc | FileSortMethod::DateDesc => false,
         };
         sort_tree_impl(tree, ascending);
@@ -0,0 +1,88 @@
+//! This module splits a prompt into sequential parts under a token ceiling, for models with
+//! small context windows and chat interfaces limiting the size of a message.
+//!
+//! The parts are split at file boundaries: each part renders the template with a run of the
+//! files, so that the source tree is repeated in every part. The git sections and the
+//! leading instruction go in the first part, the closing instruction in the last one, and
+//! each part starts with a header telling its position.
+
+use serde::Serialize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// A part of a split prompt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PromptPart {
+    /// The text of the part, its header included
+    pub prompt: String,
+    pub token_count: usize,
+    /// Paths of the files of the part
+    pub files: Vec<String>,
+}
+
+/// The header of a part, asking the model to wait for the last one.
+///
+/// # Arguments
+///
+/// * `index` - The position of the part, from 1
+/// * `count` - The number of parts
+pub fn part_header(index: usize, count: usize) -> String {
+    if index == count {
+        format!(
+            "[Part {}/{} of the prompt, the last one: answer now, with all the parts in mind.]",
+            index, count
+        )
+    } else {
+        format!(
+            "[Part {}/{} of the prompt. More parts follow: reply only \"OK\" and wait for part {}/{}.]",
+            index, count, count, count
+        )
+    }
+}
+
+/// The path a part is written to, the part number inserted before the extension, as
+/// `prompt.part2.md` for `prompt.md`.
+///
+/// # Arguments
+///
+/// * `path` - The path of the whole prompt
+/// * `index` - The position of the part, from 1
+pub fn part_path(path: &Path, index: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => format!("{}.part{}.{}", stem, index, extension.to_string_lossy()),
+        None => format!("{}.part{}", stem, index),
+    };
+    path.with_file_name(name)
+}
+
+/// Groups consecutive files into parts whose estimated tokens stay under the ceiling. A file
+/// over the ceiling on its own gets a part of its own.
+///
+/// # Arguments
+///
+/// * `costs` - The estimated tokens of each file in the prompt
+/// * `base` - The estimated tokens of a part without any file
+/// * `max_tokens` - The ceiling of a part
+///
+/// # Returns
+///
+/// * `Vec<Range<usize>>` - The ranges of the files of each part, at least one
+pub(crate) fn group_files(costs: &[usize], base: usize, max_tokens: usize) -> Vec<Range<usize>> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut tokens = base;
+    for (index, &cost) in costs.iter().enumerate() {
+        if index > start && tokens + cost > max_tokens {
+            parts.push(start..index);
+            start = index;
+            tokens = base;
+        }
+        tokens += cost;
+    }
+    parts.push(start..costs.len());
+    parts
+}
@@ -0,0 +1,89 @@
+//! This module replaces the files over a token cap with a summary written by a model, so
+//! that a generated parser or a huge module still tells what it does without taking the
+//! whole context window.
+//!
+//! The model is reached through a [`FileSummarizer`] supplied by the caller, code2prompt
+//! itself does not talk to model providers. The summaries are cached by the content hash
+//! of the files, so that a file is only summarized again once it changes, and they are
+//! labeled as summaries in the output.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Writes the summary of a file, usually by querying a cheap model.
+///
+/// Summarizers must be `Debug` so that a session holding one can still be printed.
+pub trait FileSummarizer: Send + Sync + std::fmt::Debug {
+    /// Answers a prompt asking for the summary of a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The prompt, as built by [`summary_prompt`]
+    fn summarize(&self, prompt: &str) -> Result<String>;
+}
+
+/// The prompt asking a model for the summary of a file.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file
+/// * `code` - The content of the file
+pub fn summary_prompt(path: &str, code: &str) -> String {
+    format!(
+        "Summarize the file `{}` for a developer who has to work with it without reading it. \
+         Describe its purpose, then list its public types and functions with their signatures \
+         and what they do, and any side effect or invariant worth knowing. Answer with the \
+         summary only, in a few dozen lines at most.\n\n{}",
+        path, code
+    )
+}
+
+/// The content standing in for a summarized file, labeled as a summary.
+///
+/// # Arguments
+///
+/// * `summary` - The summary written by the model
+/// * `token_count` - The tokens of the content of the file
+pub fn summary_block(summary: &str, token_count: usize) -> String {
+    format!(
+        "[Summary written by a model in place of the {} tokens of this file. It may be \
+         inaccurate: ask for the file to see its content.]\n{}",
+        token_count,
+        summary.trim()
+    )
+}
+
+/// Summaries of files, keyed by the content hash of the files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SummaryCache {
+    pub summaries: BTreeMap<String, String>,
+}
+
+impl SummaryCache {
+    /// Loads a cache from a JSON file. A missing file yields an empty cache.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read summary cache {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid summary cache {}", path.display()))
+    }
+
+    /// Saves the cache as a JSON file, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write summary cache {}", path.display()))
+    }
+}
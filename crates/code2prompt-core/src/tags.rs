@@ -0,0 +1,242 @@
+//! Symbols read from an existing tags file.
+//!
+//! A lighter alternative to a language server: the symbols of the files are read from a
+//! tags file generated beforehand, without running any parser. The files are given the
+//! list of their symbols, and `config.symbols` restricts the codebase to the files
+//! defining some symbols.
+//!
+//! Two formats are read: the tags files of ctags (`ctags -R`, Universal or Exuberant),
+//! and the cross reference output of `ctags -x` or of GNU Global (`global -x`). The paths
+//! of a tags file are relative to its directory.
+
+use anyhow::{Context, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::configuration::Code2PromptConfig;
+use crate::util::path_key;
+
+/// A symbol defined in a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagSymbol {
+    pub name: String,
+    /// The kind of the symbol (`function`, `struct`, ...), when the tags file gives it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// The 1-based line of the definition, when the tags file gives it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+/// The symbols of a tags file, by file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagsIndex {
+    /// The symbols of each file in the order of the tags file, by path as written in it
+    pub files: BTreeMap<String, Vec<TagSymbol>>,
+}
+
+impl TagsIndex {
+    /// Reads a tags file, in the format of ctags or in the cross reference format of
+    /// `ctags -x` and `global -x`. Lines in neither format are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content of the tags file
+    pub fn parse(content: &str) -> Self {
+        let mut index = Self::default();
+        for line in content.lines() {
+            if line.starts_with("!_TAG_") || line.trim().is_empty() {
+                continue;
+            }
+            let parsed = parse_tag_line(line).or_else(|| parse_cross_reference(line));
+            if let Some((path, symbol)) = parsed {
+                let path = path.strip_prefix("./").unwrap_or(path).replace('\\', "/");
+                index.files.entry(path).or_default().push(symbol);
+            }
+        }
+        index
+    }
+
+    /// Reads a tags file and makes its paths relative to the root of the codebase. The
+    /// files out of the root are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The tags file, its paths being relative to its directory
+    /// * `root` - The root of the codebase
+    pub fn load(path: &Path, root: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the tags file {}", path.display()))?;
+        let root = root.canonicalize()?;
+        let tags_dir = path
+            .canonicalize()?
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        let mut index = Self::default();
+        for (file, symbols) in Self::parse(&content).files {
+            let absolute = normalize(&tags_dir.join(&file));
+            if let Ok(relative) = absolute.strip_prefix(&root) {
+                let relative = path_key(relative);
+                index.files.entry(relative).or_default().extend(symbols);
+            }
+        }
+        info!("Tags file {}: {} files", path.display(), index.files.len());
+        Ok(index)
+    }
+
+    /// The files defining any of the symbols.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The names of the symbols, matched exactly
+    pub fn files_defining(&self, names: &[String]) -> BTreeSet<String> {
+        self.files
+            .iter()
+            .filter(|(_, symbols)| symbols.iter().any(|symbol| names.contains(&symbol.name)))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// The symbols of a file, by its path relative to the root.
+    pub fn symbols(&self, relative_path: &Path) -> Option<&[TagSymbol]> {
+        self.files.get(&path_key(relative_path)).map(Vec::as_slice)
+    }
+}
+
+/// The files kept by `config.symbols`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolScope {
+    /// Files relative to the root
+    pub files: BTreeSet<String>,
+}
+
+impl SymbolScope {
+    pub fn contains(&self, relative_path: &Path) -> bool {
+        self.files.contains(&path_key(relative_path))
+    }
+}
+
+/// Resolves the files defining the symbols of `config.symbols`, from `config.tags_file`.
+///
+/// # Arguments
+///
+/// * `config` - The configuration
+///
+/// # Returns
+///
+/// * `Result<Option<SymbolScope>>` - `None` if no symbol is selected
+pub fn symbol_scope(config: &Code2PromptConfig) -> Result<Option<SymbolScope>> {
+    if config.symbols.is_empty() {
+        return Ok(None);
+    }
+    let tags_file = config
+        .tags_file
+        .as_ref()
+        .context("Selecting symbols needs a tags file, given with --tags")?;
+    let files = TagsIndex::load(tags_file, &config.path)?.files_defining(&config.symbols);
+    info!(
+        "Symbols {}: {} files",
+        config.symbols.join(", "),
+        files.len()
+    );
+    Ok(Some(SymbolScope { files }))
+}
+
+/// A line of a ctags tags file: `name<TAB>file<TAB>address;"<TAB>fields`.
+fn parse_tag_line(line: &str) -> Option<(&str, TagSymbol)> {
+    let mut columns = line.split('\t');
+    let name = columns.next()?;
+    let path = columns.next()?;
+    let rest: Vec<&str> = columns.collect();
+    if name.is_empty() || path.is_empty() || rest.is_empty() {
+        return None;
+    }
+
+    // The address is a line number or a search pattern, which may hold tabs itself. It
+    // ends with `;"` but in the original format, where it is the last column.
+    let address_end = match rest.iter().position(|column| column.ends_with(";\"")) {
+        Some(end) => end,
+        None if rest.len() == 1
+            && (rest[0].starts_with('/')
+                || rest[0].starts_with('?')
+                || rest[0].parse::<usize>().is_ok()) =>
+        {
+            0
+        }
+        None => return None,
+    };
+    let address = rest[..=address_end].join("\t");
+    let mut symbol = TagSymbol {
+        name: name.to_string(),
+        kind: None,
+        line: address.trim_end_matches(";\"").parse().ok(),
+    };
+    for field in &rest[address_end + 1..] {
+        match field.split_once(':') {
+            Some(("kind", kind)) => symbol.kind = Some(kind_name(kind)),
+            Some(("line", line)) => symbol.line = line.parse().ok().or(symbol.line),
+            Some(_) => {}
+            None if !field.is_empty() => symbol.kind = Some(kind_name(field)),
+            None => {}
+        }
+    }
+    Some((path, symbol))
+}
+
+/// A line of cross reference: `name kind line file text` from `ctags -x`, or
+/// `name line file text` from `global -x`.
+fn parse_cross_reference(line: &str) -> Option<(&str, TagSymbol)> {
+    let columns: Vec<&str> = line.split_whitespace().collect();
+    let (kind, line_number, path) = match columns.as_slice() {
+        [_, line, path, ..] if line.parse::<usize>().is_ok() => (None, line, path),
+        [_, kind, line, path, ..] if line.parse::<usize>().is_ok() => (Some(kind), line, path),
+        _ => return None,
+    };
+    Some((
+        path,
+        TagSymbol {
+            name: columns[0].to_string(),
+            kind: kind.map(|kind| kind_name(kind)),
+            line: line_number.parse().ok(),
+        },
+    ))
+}
+
+/// The name of a kind, spelled out from the one-letter kinds of ctags.
+fn kind_name(kind: &str) -> String {
+    match kind {
+        "c" => "class",
+        "d" => "macro",
+        "e" => "enumerator",
+        "f" => "function",
+        "g" => "enum",
+        "i" => "interface",
+        "m" => "member",
+        "n" => "namespace",
+        "p" => "prototype",
+        "s" => "struct",
+        "t" => "typedef",
+        "v" => "variable",
+        kind => kind,
+    }
+    .to_string()
+}
+
+/// Removes the `.` and `..` components of a path, without reading the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
@@ -4,9 +4,14 @@ use anyhow::{Result, anyhow};
 use handlebars::{Handlebars, no_escape};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
 use std::io::Write;
 
+use crate::template_helpers::{HelperRegistry, register_builtin_helpers};
+
 /// Set up the Handlebars template engine with a template string and a template name.
+/// The built-in helpers of [`crate::template_helpers`] are registered.
 ///
 /// # Arguments
 ///
@@ -17,8 +22,32 @@ use std::io::Write;
 ///
 /// * `Result<Handlebars<'static>>` - The configured Handlebars instance.
 pub fn handlebars_setup(template_str: &str, template_name: &str) -> Result<Handlebars<'static>> {
+    handlebars_setup_with_helpers(template_str, template_name, &HelperRegistry::default())
+}
+
+/// Set up the Handlebars template engine with the built-in helpers and the helpers of a
+/// registry, which replace built-in helpers of the same name.
+///
+/// # Arguments
+///
+/// * `template_str` - The Handlebars template string.
+/// * `template_name` - The name of the template.
+/// * `helpers` - The helpers added by the library consumer.
+///
+/// # Returns
+///
+/// * `Result<Handlebars<'static>>` - The configured Handlebars instance.
+pub fn handlebars_setup_with_helpers(
+    template_str: &str,
+    template_name: &str,
+    helpers: &HelperRegistry,
+) -> Result<Handlebars<'static>> {
     let mut handlebars = Handlebars::new();
     handlebars.register_escape_fn(no_escape);
+    register_builtin_helpers(&mut handlebars);
+    helpers.register_all(&mut handlebars);
+    let (header, footer) = FileDelimiter::Markdown.partials(OutputFormat::Markdown);
+    register_file_delimiters(&mut handlebars, header, footer)?;
 
     handlebars
         .register_template_string(template_name, template_str)
@@ -27,7 +56,133 @@ pub fn handlebars_setup(template_str: &str, template_name: &str) -> Result<Handl
     Ok(handlebars)
 }
 
-/// Extracts the undefined variables from the template string.
+/// The variables of the template context, which are never asked for.
+///
+/// `path` and `code` are kept for templates that use them outside of `{{#each files}}`.
+pub const SYSTEM_VARIABLES: &[&str] = &[
+    "absolute_code_path",
+    "source_tree",
+    "files",
+    "git_diff",
+    "git_diff_branch",
+    "git_log_branch",
+    "conflicts",
+    "git_stash",
+    "untracked_files",
+    "recent_commits",
+    "branch_changes",
+    "merged_pull_requests",
+    "doc_coverage",
+    "architecture_brief",
+    "onboarding_tour",
+    "environment_spec",
+    "build_config",
+    "context_delta",
+    "manifest_changes",
+    "version_info",
+    "roots",
+    "config",
+    "path",
+    "code",
+];
+
+/// Block helpers whose content is rendered against another context than the root one
+const SCOPING_HELPERS: &[&str] = &["each", "with"];
+
+/// A block opened by `{{#helper ...}}` and not closed yet
+struct Block {
+    /// Whether the content of the block is rendered against another context
+    scoped: bool,
+    /// The names bound by `as |name ...|`
+    params: Vec<String>,
+}
+
+/// Extracts the variables a template reads from the root context, in order of first use.
+///
+/// Only plain `{{variable}}` expressions are considered. Helper keywords such as `else`
+/// and `this`, `@` data variables, block parameters and the fields read inside
+/// `{{#each}}` and `{{#with}}` blocks are not variables of the root context and are skipped.
+///
+/// # Arguments
+///
+/// * `template` - The Handlebars template string.
+///
+/// # Returns
+///
+/// * `Vec<String>` - The variable names, dot paths included.
+pub fn extract_template_variables(template: &str) -> Vec<String> {
+    let variable_re = Regex::new(r"^[a-zA-Z_][a-zA-Z_0-9]*(?:\.[a-zA-Z_][a-zA-Z_0-9]*)*$").unwrap();
+    let params_re = Regex::new(r"\bas\s*\|([^|]*)\|").unwrap();
+
+    let mut variables: Vec<String> = Vec::new();
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        rest = &rest[open + 2..];
+        let close = if rest.starts_with("!--") {
+            "--}}"
+        } else if rest.starts_with('{') {
+            "}}}"
+        } else {
+            "}}"
+        };
+        let Some(end) = rest.find(close) else {
+            break;
+        };
+        let expression = rest[..end].trim_start_matches(['{', '~']);
+        let expression = expression.trim_end_matches('~').trim();
+        rest = &rest[end + close.len()..];
+
+        match expression.chars().next() {
+            Some('#') => {
+                let expression = expression[1..].trim_start_matches(['*', '>']);
+                let helper = expression.split_whitespace().next().unwrap_or_default();
+                let params = params_re
+                    .captures(expression)
+                    .map(|cap| cap[1].split_whitespace().map(str::to_string).collect())
+                    .unwrap_or_default();
+                blocks.push(Block {
+                    scoped: SCOPING_HELPERS.contains(&helper),
+                    params,
+                });
+            }
+            Some('/') => {
+                blocks.pop();
+            }
+            Some('^') if expression.len() > 1 => blocks.push(Block {
+                scoped: false,
+                params: Vec::new(),
+            }),
+            Some('!' | '>' | '^') | None => {}
+            Some(_) => {
+                let name = expression.trim_start_matches('&').trim();
+                if name == "else" || name.starts_with("else ") {
+                    // The inverse section of `{{#each}}` is rendered against the outer context
+                    if let Some(block) = blocks.last_mut() {
+                        block.scoped = false;
+                        block.params.clear();
+                    }
+                    continue;
+                }
+                if !variable_re.is_match(name) {
+                    continue;
+                }
+                let root = name.split('.').next().unwrap_or_default();
+                let in_scope = root == "this"
+                    || blocks
+                        .iter()
+                        .any(|block| block.scoped || block.params.iter().any(|p| p == root));
+                if !in_scope && !variables.iter().any(|var| var == name) {
+                    variables.push(name.to_string());
+                }
+            }
+        }
+    }
+    variables
+}
+
+/// Extracts the undefined variables from the template string: the variables of
+/// [`extract_template_variables`] that are not [`SYSTEM_VARIABLES`].
 ///
 /// # Arguments
 ///
@@ -37,14 +192,160 @@ pub fn handlebars_setup(template_str: &str, template_name: &str) -> Result<Handl
 ///
 /// * `Vec<String>` - A vector of undefined variable names.
 pub fn extract_undefined_variables(template: &str) -> Vec<String> {
-    let registered_identifiers = ["path", "code", "git_diff"];
-    let re = Regex::new(r"\{\{\s*(?P<var>[a-zA-Z_][a-zA-Z_0-9]*)\s*\}\}").unwrap();
-    re.captures_iter(template)
-        .map(|cap| cap["var"].to_string())
-        .filter(|var| !registered_identifiers.contains(&var.as_str()))
+    extract_template_variables(template)
+        .into_iter()
+        .filter(|var| {
+            let root = var.split('.').next().unwrap_or_default();
+            !SYSTEM_VARIABLES.contains(&root)
+        })
         .collect()
 }
 
+/// Builds the template data of user variables, turning dot-path names into nested objects.
+///
+/// For example `meta.author=alice` and `meta.ticket=JIRA-42` become
+/// `{"meta": {"author": "alice", "ticket": "JIRA-42"}}`, available in templates as
+/// `{{meta.author}}`. When a name is both a value and a namespace, the namespace wins.
+///
+/// # Arguments
+///
+/// * `variables` - The user variables, keyed by their (possibly dotted) names.
+///
+/// # Returns
+///
+/// * `Map<String, Value>` - The nested variables.
+pub fn nest_variables(variables: &HashMap<String, String>) -> Map<String, Value> {
+    let mut names: Vec<&String> = variables.keys().collect();
+    // A value sorts before the names nested under it, so namespaces replace values
+    names.sort();
+
+    let mut root = Map::new();
+    for name in names {
+        let mut segments = name.split('.').peekable();
+        let mut object = &mut root;
+        while let Some(segment) = segments.next() {
+            if segments.peek().is_none() {
+                object.insert(segment.to_string(), Value::String(variables[name].clone()));
+                break;
+            }
+            let entry = object
+                .entry(segment.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                log::warn!(
+                    "Variable namespace '{}' replaces a value of the same name",
+                    segment
+                );
+                *entry = Value::Object(Map::new());
+            }
+            object = entry.as_object_mut().unwrap();
+        }
+    }
+    root
+}
+
+/// Metadata declared in a leading `{{!-- ... --}}` comment of a template.
+///
+/// The comment body is parsed as TOML, for example:
+///
+/// ```text
+/// {{!--
+/// name = "Bug fix"
+/// description = "Explain and fix a bug"
+/// args = ["error", "component"]
+/// --}}
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct TemplateFrontMatter {
+    /// Display name of the template, the file name being used when unset.
+    pub name: Option<String>,
+    /// Short human readable description of the template.
+    pub description: Option<String>,
+    /// Declared argument names, in the order positional arguments are mapped to them.
+    pub args: Vec<String>,
+}
+
+/// Parses the front-matter comment at the top of a template, if any.
+///
+/// # Arguments
+///
+/// * `template` - The Handlebars template string.
+///
+/// # Returns
+///
+/// * `TemplateFrontMatter` - The parsed front-matter, or the default value when the template
+///   has no front-matter or it cannot be parsed.
+pub fn parse_front_matter(template: &str) -> TemplateFrontMatter {
+    let Some(body) = template
+        .trim_start()
+        .strip_prefix("{{!--")
+        .and_then(|rest| rest.split_once("--}}"))
+        .map(|(body, _)| body)
+    else {
+        return TemplateFrontMatter::default();
+    };
+
+    toml::from_str(body).unwrap_or_else(|e| {
+        log::warn!("Ignoring invalid template front-matter: {}", e);
+        TemplateFrontMatter::default()
+    })
+}
+
+/// Maps command-line template arguments to template variables.
+///
+/// Each argument is either `name=value`, which sets the variable directly, or a bare value,
+/// which is assigned to the next argument declared in the front-matter that has not been
+/// set by name.
+///
+/// # Arguments
+///
+/// * `front_matter` - The template front-matter declaring the argument order.
+/// * `args` - The raw arguments as given on the command line.
+///
+/// # Returns
+///
+/// * `Result<HashMap<String, String>>` - The resulting variables, or an error if there are more
+///   positional values than declared arguments.
+pub fn map_template_args(
+    front_matter: &TemplateFrontMatter,
+    args: &[String],
+) -> Result<HashMap<String, String>> {
+    let name_re = Regex::new(r"^[a-zA-Z_][a-zA-Z_0-9]*(\.[a-zA-Z_][a-zA-Z_0-9]*)*$").unwrap();
+    let mut variables = HashMap::new();
+    let mut positional = Vec::new();
+
+    for arg in args {
+        match arg.split_once('=') {
+            Some((name, value)) if name_re.is_match(name.trim()) => {
+                variables.insert(name.trim().to_string(), value.to_string());
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let mut free_slots = front_matter
+        .args
+        .iter()
+        .filter(|name| !variables.contains_key(*name))
+        .cloned()
+        .collect::<Vec<_>>()
+        .into_iter();
+
+    for value in positional {
+        let name = free_slots.next().ok_or_else(|| {
+            anyhow!(
+                "Too many template arguments: '{}' does not match any declared argument ({})",
+                value,
+                front_matter.args.join(", ")
+            )
+        })?;
+        variables.insert(name, value);
+    }
+
+    Ok(variables)
+}
+
 /// Renders the template with the provided data.
 ///
 /// # Arguments
@@ -67,6 +368,98 @@ pub fn render_template<T: Serialize>(
     Ok(rendered.trim().to_string())
 }
 
+/// Splits a template rendered with a single file into the text before the files, the text
+/// of the file and the text after, by comparing it with the template rendered without files.
+///
+/// # Arguments
+///
+/// * `without_files` - The template rendered with an empty list of files.
+/// * `with_file` - The template rendered with a single placeholder file.
+///
+/// # Returns
+///
+/// * `Result<(&str, &str, &str)>` - The parts of `with_file` before, for and after the file,
+///   or an error if the file does not appear in a single place.
+pub fn split_file_section<'a>(
+    without_files: &str,
+    with_file: &'a str,
+) -> Result<(&'a str, &'a str, &'a str)> {
+    let prefix = without_files
+        .char_indices()
+        .zip(with_file.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(
+            without_files.len().min(with_file.len()),
+            |((index, _), _)| index,
+        );
+    // The suffix may not overlap the prefix in the text without files
+    let suffix: usize = without_files[prefix..]
+        .chars()
+        .rev()
+        .zip(with_file[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+
+    let end = with_file.len() - suffix;
+    let file = &with_file[prefix..end];
+    if file.is_empty() || file.len() != with_file.len() - without_files.len() {
+        return Err(anyhow!(
+            "The template must list the files in a single `{{{{#each files}}}}` loop"
+        ));
+    }
+    Ok((&with_file[..prefix], file, &with_file[end..]))
+}
+
+/// Builds the instruction section appended after the codebase.
+///
+/// # Arguments
+///
+/// * `instruction` - The instruction or task given to the model.
+/// * `output_format` - The output format, used to pick Markdown or XML framing.
+///
+/// # Returns
+///
+/// * `String` - The section to append to the rendered prompt.
+pub fn instruction_section(instruction: &str, output_format: OutputFormat) -> String {
+    match output_format {
+        OutputFormat::Markdown => format!("## Instruction\n\n{}", instruction.trim()),
+        OutputFormat::Xml | OutputFormat::Json | OutputFormat::Jsonl | OutputFormat::Chunks => {
+            format!("<instruction>\n{}\n</instruction>", instruction.trim())
+        }
+    }
+}
+
+/// Builds the response contract section asking the model to answer with JSON
+/// matching the given schema.
+///
+/// # Arguments
+///
+/// * `schema` - The JSON schema the response must conform to.
+/// * `output_format` - The output format, used to pick Markdown or XML framing.
+///
+/// # Returns
+///
+/// * `String` - The section to append to the rendered prompt.
+pub fn response_contract_section(schema: &str, output_format: OutputFormat) -> String {
+    let instructions = "Respond only with a single JSON document matching the following JSON schema. \
+Do not include any text outside of the JSON document.";
+    match output_format {
+        OutputFormat::Markdown => format!(
+            "## Response Format\n\n{}\n\n```json\n{}\n```",
+            instructions,
+            schema.trim()
+        ),
+        OutputFormat::Xml | OutputFormat::Json | OutputFormat::Jsonl | OutputFormat::Chunks => {
+            format!(
+                "<response_format>\n{}\n<schema>\n{}\n</schema>\n</response_format>",
+                instructions,
+                schema.trim()
+            )
+        }
+    }
+}
+
 /// Writes the rendered template to a specified output file
 ///
 /// # Arguments
@@ -92,6 +485,10 @@ pub enum OutputFormat {
     Markdown,
     Json,
     Xml,
+    /// One JSON object per file followed by a summary record, one per line
+    Jsonl,
+    /// One JSON object per chunk of a file, with a stable ID, for vector-store ingestion
+    Chunks,
 }
 
 impl std::fmt::Display for OutputFormat {
@@ -100,6 +497,98 @@ impl std::fmt::Display for OutputFormat {
             OutputFormat::Markdown => write!(f, "markdown"),
             OutputFormat::Json => write!(f, "json"),
             OutputFormat::Xml => write!(f, "xml"),
+            OutputFormat::Jsonl => write!(f, "jsonl"),
+            OutputFormat::Chunks => write!(f, "chunks"),
+        }
+    }
+}
+
+/// Delimiters written around the content of each file by the `file_header` and
+/// `file_footer` partials of the templates.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileDelimiter {
+    /// The delimiters of the output format: Markdown headers, or XML tags
+    #[default]
+    Auto,
+    /// The path in backquotes, as `` `src/lib.rs`: ``
+    Markdown,
+    /// A `<file path="src/lib.rs">` element
+    Xml,
+    /// Banner lines, as `===== FILE: src/lib.rs =====`
+    Banner,
+}
+
+impl std::fmt::Display for FileDelimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileDelimiter::Auto => write!(f, "auto"),
+            FileDelimiter::Markdown => write!(f, "markdown"),
+            FileDelimiter::Xml => write!(f, "xml"),
+            FileDelimiter::Banner => write!(f, "banner"),
+        }
+    }
+}
+
+impl FileDelimiter {
+    /// The header and footer partials of the delimiters.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_format` - The output format, picking the delimiters of `Auto`
+    ///
+    /// # Returns
+    ///
+    /// * `(&str, &str)` - The `file_header` and `file_footer` partials
+    pub fn partials(&self, output_format: OutputFormat) -> (&'static str, &'static str) {
+        let delimiter = match (self, output_format) {
+            (FileDelimiter::Auto, OutputFormat::Markdown) => FileDelimiter::Markdown,
+            (FileDelimiter::Auto, _) => FileDelimiter::Xml,
+            (delimiter, _) => *delimiter,
+        };
+        match delimiter {
+            FileDelimiter::Xml => (
+                "<file path=\"{{path}}\"{{#if line_range}} lines=\"{{line_range}}\"{{/if}}>\n",
+                "</file>\n",
+            ),
+            FileDelimiter::Banner => (
+                "===== FILE: {{path}}{{#if line_range}} (lines {{line_range}}){{/if}} =====\n",
+                "===== END FILE: {{path}} =====\n",
+            ),
+            _ => (
+                "`{{path}}`{{#if line_range}} (lines {{line_range}}){{/if}}:\n",
+                "",
+            ),
+        }
+    }
+}
+
+/// Registers the `file_header` and `file_footer` partials, written by the templates around
+/// the content of each file with the file as context.
+///
+/// # Arguments
+///
+/// * `handlebars` - The Handlebars instance
+/// * `header` - The template of the header, such as `===== FILE: {{path}} =====`
+/// * `footer` - The template of the footer, empty for none
+///
+/// # Returns
+///
+/// * `Result<()>` - An error if a partial is not a valid template
+pub fn register_file_delimiters(
+    handlebars: &mut Handlebars,
+    header: &str,
+    footer: &str,
+) -> Result<()> {
+    for (name, partial) in [("file_header", header), ("file_footer", footer)] {
+        // Each delimiter is a line of its own
+        let mut partial = partial.to_string();
+        if !partial.is_empty() && !partial.ends_with('\n') {
+            partial.push('\n');
         }
+        handlebars
+            .register_partial(name, partial)
+            .map_err(|e| anyhow!("Failed to register the {} partial: {}", name, e))?;
     }
+    Ok(())
 }
@@ -0,0 +1,386 @@
+//! Handlebars helpers available in every template.
+//!
+//! The built-in helpers post-process file contents in the template itself:
+//!
+//! - `{{truncate_tokens code 200}}` keeps the first lines of `code` fitting in about 200 tokens
+//! - `{{strip_comments code extension}}` removes the comments, following the language of the extension
+//! - `{{dedent code}}` removes the indentation common to all the lines
+//! - `{{basename path}}` gives the file name of a path
+//! - `{{uppercase text}}` and `{{lowercase text}}` change the case
+//! - `{{#section "git_log" priority=-1}}...{{/section}}` marks a section the token budget
+//!   may trim, the lowest priorities first
+//!
+//! Library consumers add their own helpers to a [`HelperRegistry`], usually through
+//! `Code2PromptSession::register_helper`.
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason, Renderable, ScopedJson, handlebars_helper,
+};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::tokenizer::estimate_tokens;
+
+/// Marker ending a text truncated by `truncate_tokens`.
+pub const TRUNCATED_MARKER: &str = "... [truncated]";
+
+handlebars_helper!(truncate_tokens_helper: |text: str, max_tokens: u64| {
+    truncate_tokens(text, max_tokens as usize)
+});
+handlebars_helper!(strip_comments_helper: |code: str, extension: str| {
+    strip_comments(code, extension)
+});
+handlebars_helper!(dedent_helper: |text: str| dedent(text));
+handlebars_helper!(basename_helper: |path: str| {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+});
+handlebars_helper!(uppercase_helper: |text: str| text.to_uppercase());
+handlebars_helper!(lowercase_helper: |text: str| text.to_lowercase());
+
+/// Registers the built-in helpers.
+///
+/// # Arguments
+///
+/// * `handlebars` - The Handlebars instance to register the helpers on
+pub fn register_builtin_helpers(handlebars: &mut Handlebars<'static>) {
+    handlebars.register_helper("truncate_tokens", Box::new(truncate_tokens_helper));
+    handlebars.register_helper("strip_comments", Box::new(strip_comments_helper));
+    handlebars.register_helper("dedent", Box::new(dedent_helper));
+    handlebars.register_helper("basename", Box::new(basename_helper));
+    handlebars.register_helper("uppercase", Box::new(uppercase_helper));
+    handlebars.register_helper("lowercase", Box::new(lowercase_helper));
+    handlebars.register_helper("section", Box::new(SectionHelper::default()));
+}
+
+/// A section of a template, marked with `{{#section "name" priority=n}}...{{/section}}`.
+///
+/// When the prompt exceeds its token budget, the sections with a negative priority are
+/// trimmed before any file is dropped, the lowest priority first, and the other sections
+/// once no file is left. Sections without a priority have priority 0, content outside of
+/// any section is never trimmed. The files are budgeted on their own and are best kept out
+/// of the sections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateSection {
+    pub name: String,
+    pub priority: i64,
+}
+
+/// The `section` block helper: renders its block unless the section is trimmed, and
+/// records the sections it rendered.
+#[derive(Clone, Default)]
+pub struct SectionHelper {
+    trimmed: Vec<String>,
+    rendered: Arc<Mutex<Vec<TemplateSection>>>,
+}
+
+impl SectionHelper {
+    /// A helper leaving out the blocks of the given sections.
+    ///
+    /// # Arguments
+    ///
+    /// * `trimmed` - The names of the sections to leave out
+    pub fn new(trimmed: Vec<String>) -> Self {
+        Self {
+            trimmed,
+            rendered: Arc::default(),
+        }
+    }
+
+    /// The sections rendered so far, once per name, in the order they were first rendered.
+    pub fn rendered(&self) -> Vec<TemplateSection> {
+        self.rendered.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+}
+
+impl HelperDef for SectionHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let name = h
+            .param(0)
+            .and_then(|param| param.value().as_str())
+            .ok_or_else(|| RenderErrorReason::ParamNotFoundForName("section", "name".into()))?;
+        if self.trimmed.iter().any(|trimmed| trimmed == name) {
+            return Ok(());
+        }
+        let priority = h
+            .hash_get("priority")
+            .and_then(|priority| priority.value().as_i64())
+            .unwrap_or(0);
+        if let Ok(mut rendered) = self.rendered.lock()
+            && !rendered.iter().any(|section| section.name == name)
+        {
+            rendered.push(TemplateSection {
+                name: name.to_string(),
+                priority,
+            });
+        }
+
+        match h.template() {
+            Some(template) => template.render(r, ctx, rc, out),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Helpers added by library consumers, registered after the built-in helpers so that they
+/// can replace them.
+#[derive(Clone, Default)]
+pub struct HelperRegistry {
+    helpers: Vec<(String, Arc<dyn HelperDef + Send + Sync>)>,
+}
+
+impl HelperRegistry {
+    /// Adds a helper, replacing any helper of the same name.
+    pub fn register(&mut self, name: &str, helper: Arc<dyn HelperDef + Send + Sync>) -> &mut Self {
+        self.helpers.retain(|(existing, _)| existing != name);
+        self.helpers.push((name.to_string(), helper));
+        self
+    }
+
+    /// The names of the registered helpers.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.helpers.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Registers the helpers on a Handlebars instance.
+    pub fn register_all(&self, handlebars: &mut Handlebars<'static>) {
+        for (name, helper) in &self.helpers {
+            handlebars.register_helper(name, Box::new(SharedHelper(helper.clone())));
+        }
+    }
+}
+
+impl std::fmt::Debug for HelperRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// A helper shared between the registry and the Handlebars instances.
+struct SharedHelper(Arc<dyn HelperDef + Send + Sync>);
+
+impl HelperDef for SharedHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        self.0.call_inner(h, r, ctx, rc)
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        self.0.call(h, r, ctx, rc, out)
+    }
+}
+
+/// Keeps the first lines of a text fitting in about `max_tokens` tokens.
+///
+/// Tokens are estimated from the length of the lines. A truncated text ends with
+/// [`TRUNCATED_MARKER`].
+///
+/// # Arguments
+///
+/// * `text` - The text to truncate
+/// * `max_tokens` - The number of tokens to keep
+///
+/// # Returns
+///
+/// * `String` - The text, truncated on a line boundary
+pub fn truncate_tokens(text: &str, max_tokens: usize) -> String {
+    if estimate_tokens(text, "") <= max_tokens {
+        return text.to_string();
+    }
+    let mut kept = String::new();
+    for line in text.lines() {
+        if estimate_tokens(&kept, "") + estimate_tokens(line, "") > max_tokens {
+            break;
+        }
+        kept.push_str(line);
+        kept.push('\n');
+    }
+    kept.push_str(TRUNCATED_MARKER);
+    kept
+}
+
+/// Comment syntax of a language.
+struct CommentSyntax {
+    line: &'static [&'static str],
+    block: Option<(&'static str, &'static str)>,
+    /// Characters delimiting string literals, in which comments are not looked for
+    quotes: &'static [char],
+}
+
+fn comment_syntax(extension: &str) -> Option<CommentSyntax> {
+    let syntax = match extension.to_ascii_lowercase().as_str() {
+        // Single quotes delimit lifetimes and characters in Rust
+        "rs" => CommentSyntax {
+            line: &["//"],
+            block: Some(("/*", "*/")),
+            quotes: &['"'],
+        },
+        "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "java" | "kt" | "kts" | "scala" | "swift"
+        | "go" | "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "php" | "dart" | "css" | "scss" => {
+            CommentSyntax {
+                line: &["//"],
+                block: Some(("/*", "*/")),
+                quotes: &['"', '\'', '`'],
+            }
+        }
+        "py" | "rb" | "sh" | "bash" | "zsh" | "pl" | "r" | "yaml" | "yml" | "toml" | "tf"
+        | "cmake" | "mk" | "ex" | "exs" | "nix" => CommentSyntax {
+            line: &["#"],
+            block: None,
+            quotes: &['"', '\''],
+        },
+        "sql" | "lua" | "hs" => CommentSyntax {
+            line: &["--"],
+            block: None,
+            quotes: &['\''],
+        },
+        "html" | "htm" | "xml" | "svg" | "vue" | "md" => CommentSyntax {
+            line: &[],
+            block: Some(("<!--", "-->")),
+            quotes: &[],
+        },
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+/// Removes the comments of a source file. Lines left blank by the removal are dropped.
+///
+/// Languages are recognized by their extension, files of other languages are left as is.
+/// String literals are skipped, so that comment markers in strings are kept.
+///
+/// # Arguments
+///
+/// * `code` - The source code
+/// * `extension` - The file extension, without the leading dot
+///
+/// # Returns
+///
+/// * `String` - The code without comments
+pub fn strip_comments(code: &str, extension: &str) -> String {
+    let Some(syntax) = comment_syntax(extension) else {
+        return code.to_string();
+    };
+
+    // Each line, with whether a comment was removed from it
+    let mut lines: Vec<(String, bool)> = Vec::new();
+    let mut line = String::new();
+    let mut stripped = false;
+    let mut quote: Option<char> = None;
+    let mut block_end: Option<&str> = None;
+    let mut rest = code;
+    while let Some(c) = rest.chars().next() {
+        if c == '\n' {
+            lines.push((std::mem::take(&mut line), stripped));
+            stripped = block_end.is_some();
+            rest = &rest[1..];
+            continue;
+        }
+        if let Some(end) = block_end {
+            if rest.starts_with(end) {
+                block_end = None;
+                rest = &rest[end.len()..];
+            } else {
+                rest = &rest[c.len_utf8()..];
+            }
+            continue;
+        }
+        if let Some(open) = quote {
+            line.push(c);
+            rest = &rest[c.len_utf8()..];
+            if c == '\\' {
+                if let Some(escaped) = rest.chars().next().filter(|e| *e != '\n') {
+                    line.push(escaped);
+                    rest = &rest[escaped.len_utf8()..];
+                }
+            } else if c == open {
+                quote = None;
+            }
+            continue;
+        }
+        if let Some((start, end)) = syntax.block
+            && rest.starts_with(start)
+        {
+            block_end = Some(end);
+            stripped = true;
+            rest = &rest[start.len()..];
+            continue;
+        }
+        if syntax.line.iter().any(|marker| rest.starts_with(marker)) {
+            stripped = true;
+            rest = rest.find('\n').map_or("", |end| &rest[end..]);
+            continue;
+        }
+        if syntax.quotes.contains(&c) {
+            quote = Some(c);
+        }
+        line.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    if !line.is_empty() || stripped {
+        lines.push((line, stripped));
+    }
+
+    let mut output: Vec<&str> = Vec::new();
+    for (line, stripped) in &lines {
+        if !stripped {
+            output.push(line);
+        } else if !line.trim().is_empty() {
+            output.push(line.trim_end());
+        }
+    }
+    let mut output = output.join("\n");
+    if code.ends_with('\n') && !output.is_empty() {
+        output.push('\n');
+    }
+    output
+}
+
+/// Removes the indentation common to all the non-blank lines.
+///
+/// # Arguments
+///
+/// * `text` - The text to dedent
+///
+/// # Returns
+///
+/// * `String` - The dedented text
+pub fn dedent(text: &str) -> String {
+    let indent = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut output = text
+        .lines()
+        .map(|line| line.get(indent..).unwrap_or(line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
@@ -1,10 +1,26 @@
 //! This module encapsulates the logic for counting the tokens in the rendered text.
-use log::debug;
+//!
+//! Each [`TokenizerType`] is backed by a [`Tokenizer`]: the tiktoken encodings of OpenAI
+//! models, character ratio estimators for Anthropic models and quick estimates, and a
+//! SentencePiece model loaded from a file for Llama-like models, with the `sentencepiece`
+//! feature.
+use anyhow::{Result, bail};
+use log::{debug, warn};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "sentencepiece")]
+use std::collections::BTreeMap;
 use std::fmt;
-use std::sync::OnceLock;
+use std::path::Path;
+#[cfg(feature = "sentencepiece")]
+use std::path::PathBuf;
+#[cfg(feature = "sentencepiece")]
+use std::sync::RwLock;
+use std::sync::{Arc, OnceLock};
 use tiktoken_rs::{CoreBPE, cl100k_base, o200k_base, p50k_base, p50k_edit, r50k_base};
 
+#[cfg(feature = "sentencepiece")]
+use crate::sentencepiece::SentencePieceTokenizer;
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TokenFormat {
@@ -22,7 +38,7 @@ impl fmt::Display for TokenFormat {
     }
 }
 
-/// Tokenizer types supported by tiktoken.
+/// Tokenizer types: the tiktoken encodings, and backends for other model families.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum TokenizerType {
     #[serde(alias = "o200k")]
@@ -36,6 +52,16 @@ pub enum TokenizerType {
     P50kEdit,
     #[serde(alias = "r50k")]
     R50kBase,
+    /// Approximation of the tokenizer of Anthropic's Claude models
+    #[serde(alias = "claude", alias = "anthropic")]
+    Claude,
+    /// A SentencePiece model, read from the `tokenizer_model` file. Needs the
+    /// `sentencepiece` feature.
+    #[serde(alias = "llama", alias = "sentencepiece")]
+    Llama,
+    /// Estimate of one token per four characters
+    #[serde(alias = "chars")]
+    Chars,
 }
 
 impl fmt::Display for TokenizerType {
@@ -46,6 +72,9 @@ impl fmt::Display for TokenizerType {
             TokenizerType::P50kBase => write!(f, "p50k (Code models)"),
             TokenizerType::P50kEdit => write!(f, "p50k_edit (Edit models)"),
             TokenizerType::R50kBase => write!(f, "r50k (GPT-3)"),
+            TokenizerType::Claude => write!(f, "claude (Anthropic)"),
+            TokenizerType::Llama => write!(f, "llama (SentencePiece)"),
+            TokenizerType::Chars => write!(f, "chars (Estimate)"),
         }
     }
 }
@@ -61,16 +90,167 @@ impl TokenizerType {
                 "Edit models like text-davinci-edit-001, code-davinci-edit-001"
             }
             TokenizerType::R50kBase => "GPT-3 models like davinci",
+            TokenizerType::Claude => "Claude models, approximated at 3.5 characters per token",
+            TokenizerType::Llama => {
+                "Llama, Mistral and Gemma models, from their tokenizer.model or tokenizer.json"
+            }
+            TokenizerType::Chars => "Any model, estimated at 4 characters per token",
+        }
+    }
+}
+
+/// How token counts are computed.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenCountMode {
+    /// Run the BPE tokenizer over the text. Accurate but slow on large inputs.
+    #[default]
+    Exact,
+    /// Estimate the count from the byte length of the text. Fast but approximate.
+    Heuristic,
+}
+
+impl fmt::Display for TokenCountMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenCountMode::Exact => write!(f, "Exact"),
+            TokenCountMode::Heuristic => write!(f, "Heuristic"),
         }
     }
 }
 
+/// A tokenizer backend, counting the tokens of a text for a model family.
+pub trait Tokenizer: Send + Sync {
+    /// Counts the tokens in the text.
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+impl Tokenizer for CoreBPE {
+    fn count_tokens(&self, text: &str) -> usize {
+        self.encode_with_special_tokens(text).len()
+    }
+}
+
+/// Estimates the tokens of a text from its number of characters.
+#[derive(Debug, Clone, Copy)]
+pub struct CharRatioTokenizer {
+    pub chars_per_token: f64,
+}
+
+impl Tokenizer for CharRatioTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        (text.chars().count() as f64 / self.chars_per_token).ceil() as usize
+    }
+}
+
+/// Anthropic documents Claude tokens as about 3.5 English characters each
+const CLAUDE: CharRatioTokenizer = CharRatioTokenizer {
+    chars_per_token: 3.5,
+};
+const CHARS: CharRatioTokenizer = CharRatioTokenizer {
+    chars_per_token: 4.0,
+};
+
 // Cache tokenizers to avoid expensive re-initialization
-static O200K_BASE: OnceLock<CoreBPE> = OnceLock::new();
-static CL100K_BASE: OnceLock<CoreBPE> = OnceLock::new();
-static P50K_BASE: OnceLock<CoreBPE> = OnceLock::new();
-static P50K_EDIT: OnceLock<CoreBPE> = OnceLock::new();
-static R50K_BASE: OnceLock<CoreBPE> = OnceLock::new();
+static O200K_BASE: OnceLock<Arc<CoreBPE>> = OnceLock::new();
+static CL100K_BASE: OnceLock<Arc<CoreBPE>> = OnceLock::new();
+static P50K_BASE: OnceLock<Arc<CoreBPE>> = OnceLock::new();
+static P50K_EDIT: OnceLock<Arc<CoreBPE>> = OnceLock::new();
+static R50K_BASE: OnceLock<Arc<CoreBPE>> = OnceLock::new();
+/// The SentencePiece models of [`TokenizerType::Llama`], by the file they were loaded from
+#[cfg(feature = "sentencepiece")]
+static SENTENCEPIECE: RwLock<BTreeMap<PathBuf, Arc<SentencePieceTokenizer>>> =
+    RwLock::new(BTreeMap::new());
+
+/// Loads a SentencePiece model for [`TokenizerType::Llama`], unless it is loaded already.
+///
+/// # Arguments
+///
+/// * `path` - The path of the `tokenizer.model` or `tokenizer.json` file
+///
+/// # Returns
+///
+/// * `Result<Arc<SentencePieceTokenizer>>` - The model, or an error if it cannot be read
+#[cfg(feature = "sentencepiece")]
+pub fn load_sentencepiece_model(path: &Path) -> Result<Arc<SentencePieceTokenizer>> {
+    if let Some(model) = SENTENCEPIECE.read().unwrap().get(path) {
+        return Ok(model.clone());
+    }
+    let model = Arc::new(SentencePieceTokenizer::from_file(path)?);
+    SENTENCEPIECE
+        .write()
+        .unwrap()
+        .insert(path.to_path_buf(), model.clone());
+    Ok(model)
+}
+
+/// Checks that the tokenizer can count tokens, loading its model file if it needs one.
+///
+/// # Arguments
+///
+/// * `tokenizer_type` - The tokenizer
+/// * `model` - The model file of the tokenizer, if any
+///
+/// # Returns
+///
+/// * `Result<()>` - An error if the tokenizer needs a model that cannot be loaded
+pub fn prepare_tokenizer(tokenizer_type: &TokenizerType, model: Option<&Path>) -> Result<()> {
+    match (tokenizer_type, model) {
+        #[cfg(feature = "sentencepiece")]
+        (TokenizerType::Llama, Some(path)) => load_sentencepiece_model(path).map(|_| ()),
+        #[cfg(not(feature = "sentencepiece"))]
+        (TokenizerType::Llama, Some(_)) => {
+            bail!("The llama tokenizer needs code2prompt built with the sentencepiece feature")
+        }
+        (TokenizerType::Llama, None) => {
+            bail!(
+                "The llama tokenizer needs a SentencePiece model file (tokenizer.model or tokenizer.json)"
+            )
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns the tokenizer backing a tokenizer type.
+///
+/// [`TokenizerType::Llama`] falls back to the character estimate when its model cannot be
+/// loaded; [`prepare_tokenizer`] reports why.
+///
+/// # Arguments
+///
+/// * `tokenizer_type` - The tokenizer type
+/// * `model` - The model file of the tokenizer, if any
+///
+/// # Returns
+///
+/// * `Arc<dyn Tokenizer>` - The tokenizer
+pub fn tokenizer(tokenizer_type: &TokenizerType, model: Option<&Path>) -> Arc<dyn Tokenizer> {
+    let bpe = |cell: &OnceLock<Arc<CoreBPE>>, init: fn() -> anyhow::Result<CoreBPE>| {
+        cell.get_or_init(|| Arc::new(init().unwrap())).clone() as Arc<dyn Tokenizer>
+    };
+    match tokenizer_type {
+        TokenizerType::O200kBase => bpe(&O200K_BASE, o200k_base),
+        TokenizerType::Cl100kBase => bpe(&CL100K_BASE, cl100k_base),
+        TokenizerType::P50kBase => bpe(&P50K_BASE, p50k_base),
+        TokenizerType::P50kEdit => bpe(&P50K_EDIT, p50k_edit),
+        TokenizerType::R50kBase => bpe(&R50K_BASE, r50k_base),
+        TokenizerType::Claude => Arc::new(CLAUDE),
+        TokenizerType::Chars => Arc::new(CHARS),
+        TokenizerType::Llama => {
+            #[cfg(feature = "sentencepiece")]
+            if let Some(model) = model.and_then(|path| load_sentencepiece_model(path).ok()) {
+                return model;
+            }
+            #[cfg(not(feature = "sentencepiece"))]
+            let _ = model;
+            static WARNED: OnceLock<()> = OnceLock::new();
+            WARNED.get_or_init(|| {
+                warn!("No SentencePiece model loaded, estimating llama token counts")
+            });
+            Arc::new(CHARS)
+        }
+    }
+}
 
 /// Counts the tokens in the provided text using the specified tokenizer type.
 ///
@@ -78,23 +258,16 @@ static R50K_BASE: OnceLock<CoreBPE> = OnceLock::new();
 ///
 /// * `rendered` - The text to count tokens in
 /// * `tokenizer_type` - The tokenizer encoding to use
+/// * `model` - The model file of the tokenizer, if any
 ///
 /// # Returns
 ///
 /// * `usize` - The number of tokens in the text
-pub fn count_tokens(rendered: &str, tokenizer_type: &TokenizerType) -> usize {
+pub fn count_tokens(rendered: &str, tokenizer_type: &TokenizerType, model: Option<&Path>) -> usize {
     use std::time::Instant;
     let start = Instant::now();
 
-    let bpe = match tokenizer_type {
-        TokenizerType::O200kBase => O200K_BASE.get_or_init(|| o200k_base().unwrap()),
-        TokenizerType::Cl100kBase => CL100K_BASE.get_or_init(|| cl100k_base().unwrap()),
-        TokenizerType::P50kBase => P50K_BASE.get_or_init(|| p50k_base().unwrap()),
-        TokenizerType::P50kEdit => P50K_EDIT.get_or_init(|| p50k_edit().unwrap()),
-        TokenizerType::R50kBase => R50K_BASE.get_or_init(|| r50k_base().unwrap()),
-    };
-
-    let token_count = bpe.encode_with_special_tokens(rendered).len();
+    let token_count = tokenizer(tokenizer_type, model).count_tokens(rendered);
 
     if std::env::var("DEBUG_TOKENIZER").is_ok() {
         debug!(
@@ -106,3 +279,65 @@ pub fn count_tokens(rendered: &str, tokenizer_type: &TokenizerType) -> usize {
 
     token_count
 }
+
+/// Returns the average number of bytes per token for a file extension.
+///
+/// Markup and data formats tokenize densely, prose loosely; everything else
+/// falls back to the general average of 3.6 bytes per token.
+///
+/// # Arguments
+///
+/// * `extension` - The file extension, without the leading dot
+///
+/// # Returns
+///
+/// * `f64` - The average number of bytes per token
+pub fn bytes_per_token(extension: &str) -> f64 {
+    match extension.to_ascii_lowercase().as_str() {
+        "json" | "yaml" | "yml" | "toml" | "xml" | "html" | "htm" | "svg" | "csv" => 3.0,
+        "md" | "markdown" | "txt" | "rst" | "adoc" => 4.0,
+        "rs" | "py" | "js" | "jsx" | "ts" | "tsx" | "go" | "java" | "kt" | "c" | "h" | "cpp"
+        | "hpp" | "cs" | "rb" | "php" | "swift" | "scala" | "sh" => 3.4,
+        _ => 3.6,
+    }
+}
+
+/// Estimates the tokens in the provided text from its byte length.
+///
+/// # Arguments
+///
+/// * `text` - The text to estimate tokens for
+/// * `extension` - The file extension the text comes from, or an empty string
+///
+/// # Returns
+///
+/// * `usize` - The estimated number of tokens in the text
+pub fn estimate_tokens(text: &str, extension: &str) -> usize {
+    (text.len() as f64 / bytes_per_token(extension)).ceil() as usize
+}
+
+/// Counts the tokens in the provided text, exactly or heuristically depending on the mode.
+///
+/// # Arguments
+///
+/// * `text` - The text to count tokens in
+/// * `extension` - The file extension the text comes from, or an empty string
+/// * `tokenizer_type` - The tokenizer encoding to use in exact mode
+/// * `model` - The model file of the tokenizer, if any
+/// * `mode` - Whether to tokenize or estimate
+///
+/// # Returns
+///
+/// * `usize` - The number of tokens in the text
+pub fn count_tokens_with_mode(
+    text: &str,
+    extension: &str,
+    tokenizer_type: &TokenizerType,
+    model: Option<&Path>,
+    mode: TokenCountMode,
+) -> usize {
+    match mode {
+        TokenCountMode::Exact => count_tokens(text, tokenizer_type, model),
+        TokenCountMode::Heuristic => estimate_tokens(text, extension),
+    }
+}
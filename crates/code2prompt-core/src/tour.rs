@@ -0,0 +1,534 @@
+//! Onboarding tour of a codebase.
+//!
+//! Orders the files as a learning path for someone new to the project: the entry
+//! points first, then the core modules with each module after the modules it relies
+//! on, then the supporting files, and the tests last. Dependencies between files are
+//! read from the local imports of Rust, TypeScript/JavaScript and Python files.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::architecture::module_summary;
+use crate::language::is_test_path;
+
+/// Extensions of the source files whose imports are followed
+const SOURCE_EXTENSIONS: [&str; 11] = [
+    "rs", "ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs", "py", "go",
+];
+
+/// Extensions tried when resolving a TypeScript or JavaScript import
+const SCRIPT_EXTENSIONS: [&str; 6] = ["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+
+/// Files listed per note, at most
+const NOTE_MAX_FILES: usize = 5;
+
+/// The part of the tour a file belongs to, in reading order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TourSectionKind {
+    EntryPoints,
+    CoreModules,
+    SupportingFiles,
+    Tests,
+}
+
+impl TourSectionKind {
+    fn title(&self) -> &'static str {
+        match self {
+            TourSectionKind::EntryPoints => "Entry Points",
+            TourSectionKind::CoreModules => "Core Modules",
+            TourSectionKind::SupportingFiles => "Supporting Files",
+            TourSectionKind::Tests => "Tests",
+        }
+    }
+}
+
+/// A file of the tour, with what it is for and how it relates to the others
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TourStop {
+    pub path: String,
+    /// First line of the module documentation, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Files of the codebase this file imports
+    pub depends_on: Vec<String>,
+    /// Files of the codebase importing this file
+    pub used_by: Vec<String>,
+    /// How the file relates to the rest of the tour, in a sentence
+    pub note: String,
+    /// The formatted content of the file, filled in by the session
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// A group of files read together, introduced by a short text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TourSection {
+    pub kind: TourSectionKind,
+    pub title: &'static str,
+    pub intro: String,
+    pub stops: Vec<TourStop>,
+}
+
+/// The files of a codebase ordered as a learning path
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OnboardingTour {
+    /// The non-empty sections, in reading order
+    pub sections: Vec<TourSection>,
+}
+
+impl OnboardingTour {
+    /// The paths of all the files, in tour order
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.sections
+            .iter()
+            .flat_map(|section| section.stops.iter().map(|stop| stop.path.as_str()))
+    }
+}
+
+/// Orders files as an onboarding tour.
+///
+/// # Arguments
+///
+/// * `files` - The path, relative to the root, and the content of each file
+///
+/// # Returns
+///
+/// * `OnboardingTour` - The files grouped in sections, in reading order
+pub fn plan_tour(files: &[(String, String)]) -> OnboardingTour {
+    let paths: BTreeSet<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+
+    // Local imports, as edges from a file to the files it depends on
+    let mut depends_on: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut used_by: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for (path, content) in files {
+        for dependency in local_imports(path, content, &paths) {
+            if dependency != path.as_str() {
+                depends_on.entry(path).or_default().insert(dependency);
+                used_by.entry(dependency).or_default().insert(path);
+            }
+        }
+    }
+
+    let mut grouped: BTreeMap<TourSectionKind, Vec<&str>> = BTreeMap::new();
+    for path in &paths {
+        grouped.entry(section_kind(path)).or_default().push(path);
+    }
+    if let Some(core) = grouped.get_mut(&TourSectionKind::CoreModules) {
+        *core = dependency_order(core, &depends_on);
+    }
+
+    let contents: BTreeMap<&str, &str> = files
+        .iter()
+        .map(|(path, content)| (path.as_str(), content.as_str()))
+        .collect();
+    let sections = grouped
+        .into_iter()
+        .map(|(kind, section_paths)| {
+            let stops: Vec<TourStop> = section_paths
+                .iter()
+                .map(|path| {
+                    let lines: Vec<&str> = contents[path].lines().collect();
+                    let depends: Vec<String> = depends_on
+                        .get(path)
+                        .map(|set| set.iter().map(|p| p.to_string()).collect())
+                        .unwrap_or_default();
+                    let users: Vec<String> = used_by
+                        .get(path)
+                        .map(|set| set.iter().map(|p| p.to_string()).collect())
+                        .unwrap_or_default();
+                    TourStop {
+                        path: path.to_string(),
+                        summary: is_source(path).then(|| module_summary(&lines)).flatten(),
+                        note: stop_note(kind, &depends, &users),
+                        depends_on: depends,
+                        used_by: users,
+                        code: None,
+                    }
+                })
+                .collect();
+            TourSection {
+                kind,
+                title: kind.title(),
+                intro: section_intro(kind, &stops),
+                stops,
+            }
+        })
+        .collect();
+
+    OnboardingTour { sections }
+}
+
+/// The section of the tour a file belongs to.
+fn section_kind(path: &str) -> TourSectionKind {
+    if is_test_path(path) {
+        TourSectionKind::Tests
+    } else if is_entry_point(path) {
+        TourSectionKind::EntryPoints
+    } else if is_source(path) {
+        TourSectionKind::CoreModules
+    } else {
+        TourSectionKind::SupportingFiles
+    }
+}
+
+fn is_source(path: &str) -> bool {
+    extension(path).is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext))
+}
+
+/// Files where execution starts, or the root of a library.
+fn is_entry_point(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let parent = path
+        .rsplit_once('/')
+        .map(|(dir, _)| dir.rsplit('/').next().unwrap_or(dir))
+        .unwrap_or("");
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+
+    match extension(path) {
+        Some("rs") => matches!(file_name, "main.rs" | "lib.rs") || parent == "bin",
+        Some("py") => matches!(
+            file_name,
+            "__main__.py" | "main.py" | "app.py" | "cli.py" | "manage.py" | "wsgi.py" | "asgi.py"
+        ),
+        Some("go") => file_name == "main.go",
+        Some(ext) if SCRIPT_EXTENSIONS.contains(&ext) => {
+            // Index files of nested directories only re-export their directory
+            let top_level = path.matches('/').count() <= 1;
+            matches!(stem, "main" | "app" | "server" | "cli")
+                || (stem == "index" && top_level)
+                || parent == "bin"
+        }
+        _ => false,
+    }
+}
+
+fn extension(path: &str) -> Option<&str> {
+    Path::new(path).extension().and_then(|ext| ext.to_str())
+}
+
+/// Orders files so that each one comes after the files it depends on. Files in a
+/// dependency cycle keep their path order.
+pub(crate) fn dependency_order<'a>(
+    paths: &[&'a str],
+    depends_on: &BTreeMap<&str, BTreeSet<&str>>,
+) -> Vec<&'a str> {
+    let members: BTreeSet<&str> = paths.iter().copied().collect();
+    let mut pending: BTreeMap<&str, usize> = paths
+        .iter()
+        .map(|path| {
+            let count = depends_on
+                .get(path)
+                .map(|deps| deps.iter().filter(|dep| members.contains(*dep)).count())
+                .unwrap_or(0);
+            (*path, count)
+        })
+        .collect();
+
+    let mut order = Vec::with_capacity(paths.len());
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .find(|(_, count)| **count == 0)
+            .map(|(path, _)| *path)
+            // A cycle: take the file with the fewest unresolved dependencies
+            .or_else(|| {
+                pending
+                    .iter()
+                    .min_by_key(|(_, count)| **count)
+                    .map(|(path, _)| *path)
+            });
+        let Some(next) = ready else { break };
+        pending.remove(next);
+        order.push(paths[paths.iter().position(|p| *p == next).unwrap_or(0)]);
+        for (path, count) in pending.iter_mut() {
+            if depends_on.get(path).is_some_and(|deps| deps.contains(next)) {
+                *count = count.saturating_sub(1);
+            }
+        }
+    }
+    order
+}
+
+/// The files of the codebase a source file imports.
+pub(crate) fn local_imports<'a>(
+    path: &str,
+    content: &str,
+    paths: &BTreeSet<&'a str>,
+) -> Vec<&'a str> {
+    let dir = path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+    let mut found = Vec::new();
+
+    match extension(path) {
+        Some("rs") => {
+            let crate_root = rust_crate_root(path);
+            for line in content.lines() {
+                let line = line.trim_start();
+                let Some(rest) = line
+                    .strip_prefix("use ")
+                    .or_else(|| line.strip_prefix("pub use "))
+                    .or_else(|| line.strip_prefix("pub(crate) use "))
+                else {
+                    continue;
+                };
+                let module_path: Vec<&str> = rest
+                    .split("::")
+                    .map(|segment| {
+                        segment.trim_matches(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    })
+                    .collect();
+                let (base, segments) = match module_path.first() {
+                    Some(&"crate") => (crate_root.clone(), &module_path[1..]),
+                    Some(&"super") => (rust_module_dir(path, 1), &module_path[1..]),
+                    Some(&"self") => (rust_module_dir(path, 0), &module_path[1..]),
+                    _ => continue,
+                };
+                found.extend(resolve_rust(&base, segments, paths));
+            }
+        }
+        Some("py") => {
+            for line in content.lines() {
+                let line = line.trim_start();
+                let module = if let Some(rest) = line.strip_prefix("from ") {
+                    rest.split_whitespace().next()
+                } else if let Some(rest) = line.strip_prefix("import ") {
+                    rest.split([',', ' ']).next()
+                } else {
+                    None
+                };
+                if let Some(module) = module {
+                    found.extend(resolve_python(dir, module, paths));
+                }
+            }
+        }
+        Some(ext) if SCRIPT_EXTENSIONS.contains(&ext) => {
+            for specifier in script_specifiers(content) {
+                if specifier.starts_with('.') {
+                    found.extend(resolve_script(dir, specifier, paths));
+                }
+            }
+        }
+        _ => {}
+    }
+    found.sort_unstable();
+    found.dedup();
+    found
+}
+
+/// Directory of the crate root of a Rust file: the nearest `src` directory.
+fn rust_crate_root(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').collect();
+    match segments.iter().rposition(|segment| *segment == "src") {
+        Some(index) => segments[..=index].join("/"),
+        None => segments[..segments.len() - 1].join("/"),
+    }
+}
+
+/// Directory holding the submodules of a Rust file, `up` levels above it.
+fn rust_module_dir(path: &str, up: usize) -> String {
+    let (dir, file_name) = path.rsplit_once('/').unwrap_or(("", path));
+    // `foo.rs` keeps its submodules in `foo/`, `mod.rs`, `lib.rs` and `main.rs` in their directory
+    let mut module_dir = match file_name {
+        "mod.rs" | "lib.rs" | "main.rs" => dir.to_string(),
+        _ => join(dir, file_name.trim_end_matches(".rs")),
+    };
+    for _ in 0..up {
+        module_dir = module_dir
+            .rsplit_once('/')
+            .map(|(parent, _)| parent.to_string())
+            .unwrap_or_default();
+    }
+    module_dir
+}
+
+/// The file of the longest module path prefix that exists, `a/b.rs` or `a/b/mod.rs`.
+fn resolve_rust<'a>(base: &str, segments: &[&str], paths: &BTreeSet<&'a str>) -> Option<&'a str> {
+    (1..=segments.len()).rev().find_map(|len| {
+        let module = join(base, &segments[..len].join("/"));
+        [format!("{}.rs", module), format!("{}/mod.rs", module)]
+            .iter()
+            .find_map(|candidate| paths.get(candidate.as_str()).copied())
+    })
+}
+
+/// The file of a Python import: relative to the file when it starts with dots, or
+/// else relative to any directory of the codebase.
+fn resolve_python<'a>(dir: &str, module: &str, paths: &BTreeSet<&'a str>) -> Option<&'a str> {
+    let dots = module.chars().take_while(|c| *c == '.').count();
+    let module_path = module[dots..].replace('.', "/");
+    let candidates = |base: &str| {
+        [
+            format!("{}.py", join(base, &module_path)),
+            format!("{}/__init__.py", join(base, &module_path)),
+        ]
+    };
+
+    if dots > 0 {
+        let mut base = dir.to_string();
+        for _ in 1..dots {
+            base = base
+                .rsplit_once('/')
+                .map(|(parent, _)| parent.to_string())
+                .unwrap_or_default();
+        }
+        return candidates(&base)
+            .iter()
+            .find_map(|candidate| paths.get(candidate.as_str()).copied());
+    }
+    if module_path.is_empty() {
+        return None;
+    }
+    let [module_file, package_file] = candidates("");
+    paths.iter().copied().find(|path| {
+        [&module_file, &package_file]
+            .iter()
+            .any(|suffix| *path == suffix.as_str() || path.ends_with(&format!("/{}", suffix)))
+    })
+}
+
+/// The file of a relative TypeScript or JavaScript import.
+fn resolve_script<'a>(dir: &str, specifier: &str, paths: &BTreeSet<&'a str>) -> Option<&'a str> {
+    let target = normalize(&join(dir, specifier));
+    std::iter::once(target.clone())
+        .chain(
+            SCRIPT_EXTENSIONS
+                .iter()
+                .map(|ext| format!("{}.{}", target, ext)),
+        )
+        .chain(
+            SCRIPT_EXTENSIONS
+                .iter()
+                .map(|ext| format!("{}/index.{}", target, ext)),
+        )
+        .find_map(|candidate| paths.get(candidate.as_str()).copied())
+}
+
+/// Module specifiers of `import ... from '...'`, `import '...'`, `export ... from '...'`
+/// and `require('...')` statements.
+fn script_specifiers(content: &str) -> Vec<&str> {
+    let mut specifiers = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let from = if line.starts_with("import ") || line.starts_with("export ") {
+            line.rfind(" from ")
+                .map(|index| &line[index + 6..])
+                .or_else(|| line.strip_prefix("import "))
+        } else {
+            line.find("require(").map(|index| &line[index + 8..])
+        };
+        if let Some(quoted) = from.map(str::trim_start)
+            && let Some(quote) = quoted
+                .chars()
+                .next()
+                .filter(|c| matches!(c, '\'' | '"' | '`'))
+            && let Some(end) = quoted[1..].find(quote)
+        {
+            specifiers.push(&quoted[1..1 + end]);
+        }
+    }
+    specifiers
+}
+
+/// Resolves the `.` and `..` segments of a relative path.
+fn normalize(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+fn join(dir: &str, path: &str) -> String {
+    if dir.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", dir, path)
+    }
+}
+
+/// How a file relates to the rest of the tour.
+fn stop_note(kind: TourSectionKind, depends_on: &[String], used_by: &[String]) -> String {
+    match (kind, depends_on.is_empty(), used_by.is_empty()) {
+        (TourSectionKind::Tests, true, _) => "Tests the project as a whole.".to_string(),
+        (TourSectionKind::Tests, false, _) => format!("Tests {}.", list(depends_on)),
+        (_, true, true) if kind == TourSectionKind::SupportingFiles => {
+            "Read as needed.".to_string()
+        }
+        (_, true, true) => "Stands on its own: no other file of the tour imports it.".to_string(),
+        (_, true, false) => format!(
+            "A foundation with no local dependency, used by {}.",
+            list(used_by)
+        ),
+        (_, false, true) => format!("Builds on {}.", list(depends_on)),
+        (_, false, false) => format!(
+            "Builds on {}, and is used by {}.",
+            list(depends_on),
+            list(used_by)
+        ),
+    }
+}
+
+/// The introduction of a section, from the files it holds.
+fn section_intro(kind: TourSectionKind, stops: &[TourStop]) -> String {
+    let count = stops.len();
+    let files = if count == 1 { "file" } else { "files" };
+    match kind {
+        TourSectionKind::EntryPoints => format!(
+            "Start here. Execution begins in these {} {}: they show what the project does \
+             and which modules it brings together.",
+            count, files
+        ),
+        TourSectionKind::CoreModules => {
+            let mut most_used: Vec<&TourStop> = stops
+                .iter()
+                .filter(|stop| !stop.used_by.is_empty())
+                .collect();
+            most_used.sort_by_key(|stop| std::cmp::Reverse(stop.used_by.len()));
+            let names: Vec<String> = most_used
+                .iter()
+                .take(3)
+                .map(|stop| stop.path.clone())
+                .collect();
+            let mut intro = format!(
+                "The {} {} holding the logic of the project, each one after the modules it \
+                 relies on, so that every file can be read knowing what it builds on.",
+                count, files
+            );
+            if !names.is_empty() {
+                intro.push_str(&format!(" The most widely used are {}.", list(&names)));
+            }
+            intro
+        }
+        TourSectionKind::SupportingFiles => format!(
+            "Configuration, documentation, scripts and other resources ({} {}). Skim them \
+             to know where things are set up, and come back when a module refers to them.",
+            count, files
+        ),
+        TourSectionKind::Tests => format!(
+            "Read last: these {} {} show how the modules above are meant to behave, and \
+             are a good place to try changes safely.",
+            count, files
+        ),
+    }
+}
+
+/// Paths in backquotes separated by commas, the first few only.
+fn list(paths: &[String]) -> String {
+    let mut text = paths
+        .iter()
+        .take(NOTE_MAX_FILES)
+        .map(|path| format!("`{}`", path))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if paths.len() > NOTE_MAX_FILES {
+        text.push_str(&format!(" and {} more", paths.len() - NOTE_MAX_FILES));
+    }
+    text
+}
@@ -0,0 +1,283 @@
+//! This module renders the source tree: it prunes the empty directories, collapses the
+//! directories deeper than the configured depth or matched by the collapse patterns,
+//! annotates them with the number of files and tokens they hold, and draws the tree in
+//! the configured style.
+
+use crate::collapse::collapse_globset;
+use crate::configuration::Code2PromptConfig;
+use crate::fixture::compact_count;
+use crate::path::add_to_tree;
+use crate::sort::sort_tree;
+use globset::GlobSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use termtree::{GlyphPalette, Tree};
+
+/// Options of the source tree rendering
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TreeOptions {
+    /// Depth from which the directories are collapsed into a single annotated line, the
+    /// top-level entries being at depth 1
+    pub max_depth: Option<usize>,
+    /// If true, every directory is annotated with its file count and tokens, not only the
+    /// collapsed ones
+    pub annotate_directories: bool,
+    /// If true, the directories holding no file of the tree are kept
+    pub include_empty_dirs: bool,
+    /// How the tree is drawn
+    pub style: TreeStyle,
+}
+
+/// Drawing style of the source tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeStyle {
+    /// Box-drawing characters, as `├── src`
+    #[default]
+    Unicode,
+    /// Plain ASCII, as `|-- src`, for the models and tools mangling Unicode
+    Ascii,
+    /// Entries indented by depth and prefixed with a dash, as `  - src`
+    Indented,
+    /// A JSON object per entry, with its name, type and children
+    Json,
+}
+
+impl std::fmt::Display for TreeStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TreeStyle::Unicode => write!(f, "Unicode"),
+            TreeStyle::Ascii => write!(f, "ASCII"),
+            TreeStyle::Indented => write!(f, "Indented"),
+            TreeStyle::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+/// The source tree being built, knowing which of its entries are files.
+#[derive(Debug)]
+pub(crate) struct SourceTree {
+    tree: Tree<String>,
+    /// Relative paths of the entries of the tree that are not directories
+    files: HashSet<PathBuf>,
+}
+
+/// Number of files and tokens under a directory
+#[derive(Debug, Default)]
+struct DirectorySummary {
+    files: usize,
+    tokens: usize,
+    /// Whether the token count of at least one of the files is known
+    counted: bool,
+}
+
+impl SourceTree {
+    pub(crate) fn new(root: String) -> Self {
+        Self {
+            tree: Tree::new(root),
+            files: HashSet::new(),
+        }
+    }
+
+    /// Adds an entry to the tree, with its intermediate directories.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_path` - The path of the entry, relative to the root
+    /// * `is_dir` - Whether the entry is a directory
+    pub(crate) fn add(&mut self, relative_path: &Path, is_dir: bool) {
+        add_to_tree(&mut self.tree, relative_path);
+        if !is_dir && relative_path.components().next().is_some() {
+            self.files.insert(relative_path.to_path_buf());
+        }
+    }
+
+    /// Sorts, prunes, collapses and annotates the tree, then renders it in the configured
+    /// style.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokens` - The token counts of the files, by relative path
+    /// * `config` - The configuration giving the order of the entries, the rendering
+    ///   options and the directories to collapse
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The rendered tree
+    pub(crate) fn render(
+        self,
+        tokens: &HashMap<PathBuf, usize>,
+        config: &Code2PromptConfig,
+    ) -> String {
+        let SourceTree { mut tree, files } = self;
+        let options = &config.tree_options;
+        let collapse = collapse_globset(&config.collapse_patterns);
+        sort_tree(&mut tree, config.sort_method);
+        let (root, _) = finish_node(tree, Path::new(""), 0, &files, tokens, options, &collapse);
+        match options.style {
+            TreeStyle::Unicode => root.to_termtree().to_string(),
+            TreeStyle::Ascii => root.to_termtree().with_glyphs(ASCII_GLYPHS).to_string(),
+            TreeStyle::Indented => {
+                let mut lines = vec![root.label()];
+                root.indented_lines(0, &mut lines);
+                lines.join("\n") + "\n"
+            }
+            TreeStyle::Json => serde_json::to_string_pretty(&root).unwrap_or_default(),
+        }
+    }
+}
+
+/// Glyphs of the ASCII style, as drawn by `tree --charset=ascii`
+const ASCII_GLYPHS: GlyphPalette = GlyphPalette {
+    middle_item: "|",
+    last_item: "`",
+    item_indent: "-- ",
+    middle_skip: "|",
+    last_skip: " ",
+    skip_indent: "   ",
+};
+
+/// An entry of the finished tree, serialized by the JSON style
+#[derive(Debug, Serialize)]
+struct TreeNode {
+    name: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// Number of files under an annotated directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<usize>,
+    /// Tokens of the files under an annotated directory, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tokens: Option<usize>,
+    /// Whether the entries of the directory are left out
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    collapsed: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<TreeNode>,
+    #[serde(skip)]
+    annotation: Option<String>,
+}
+
+impl TreeNode {
+    fn file(name: String) -> Self {
+        Self {
+            name,
+            kind: "file",
+            files: None,
+            tokens: None,
+            collapsed: false,
+            children: Vec::new(),
+            annotation: None,
+        }
+    }
+
+    /// The name of the entry, followed by its annotation, as `src/ (42 files, 18k tokens)`
+    fn label(&self) -> String {
+        match &self.annotation {
+            Some(annotation) => format!("{}/ ({})", self.name, annotation),
+            None => self.name.clone(),
+        }
+    }
+
+    fn to_termtree(&self) -> Tree<String> {
+        Tree::new(self.label()).with_leaves(self.children.iter().map(TreeNode::to_termtree))
+    }
+
+    /// The lines of the entries, indented by depth and prefixed with a dash
+    fn indented_lines(&self, depth: usize, lines: &mut Vec<String>) {
+        for child in &self.children {
+            lines.push(format!("{}- {}", "  ".repeat(depth), child.label()));
+            child.indented_lines(depth + 1, lines);
+        }
+    }
+}
+
+/// Prunes, collapses and annotates an entry of the tree and its descendants.
+///
+/// # Returns
+///
+/// * `(TreeNode, DirectorySummary)` - The finished entry, and the number of files and
+///   tokens under it
+fn finish_node(
+    node: Tree<String>,
+    path: &Path,
+    depth: usize,
+    files: &HashSet<PathBuf>,
+    tokens: &HashMap<PathBuf, usize>,
+    options: &TreeOptions,
+    collapse: &GlobSet,
+) -> (TreeNode, DirectorySummary) {
+    let mut summary = DirectorySummary::default();
+    let mut children = Vec::new();
+    for leaf in node.leaves {
+        let leaf_path = path.join(&leaf.root);
+        if files.contains(&leaf_path) {
+            summary.files += 1;
+            if let Some(count) = tokens.get(&leaf_path) {
+                summary.tokens += count;
+                summary.counted = true;
+            }
+            children.push(TreeNode::file(leaf.root));
+            continue;
+        }
+        let (child, directory) = finish_node(
+            leaf,
+            &leaf_path,
+            depth + 1,
+            files,
+            tokens,
+            options,
+            collapse,
+        );
+        summary.files += directory.files;
+        summary.tokens += directory.tokens;
+        summary.counted |= directory.counted;
+        if directory.files > 0 || options.include_empty_dirs {
+            children.push(child);
+        }
+    }
+
+    let mut directory = TreeNode {
+        name: node.root,
+        kind: "directory",
+        files: None,
+        tokens: None,
+        collapsed: false,
+        children,
+        annotation: None,
+    };
+    // The root keeps its name and its entries
+    if depth == 0 {
+        return (directory, summary);
+    }
+    directory.collapsed = options
+        .max_depth
+        .is_some_and(|max_depth| depth >= max_depth)
+        || collapse.is_match(path);
+    if directory.collapsed {
+        directory.children.clear();
+    }
+    if directory.collapsed || options.annotate_directories {
+        directory.files = Some(summary.files);
+        directory.tokens = summary.counted.then_some(summary.tokens);
+        directory.annotation = Some(summary.annotation());
+    }
+    (directory, summary)
+}
+
+impl DirectorySummary {
+    /// The annotation of the directory, as `42 files, 18k tokens`
+    fn annotation(&self) -> String {
+        let mut annotation = match self.files {
+            0 => "empty".to_string(),
+            1 => "1 file".to_string(),
+            files => format!("{} files", files),
+        };
+        if self.counted {
+            annotation.push_str(&format!(", {} tokens", compact_count(self.tokens)));
+        }
+        annotation
+    }
+}
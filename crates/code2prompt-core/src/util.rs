@@ -1,5 +1,7 @@
 //! This module contains util functions
 
+use std::path::Path;
+
 /// Removes a UTF‑8 Byte Order Mark (BOM) from the beginning of a byte slice if present.
 ///
 /// The UTF‑8 BOM is the byte sequence `[0xEF, 0xBB, 0xBF]`. This function checks whether
@@ -13,3 +15,52 @@ pub fn strip_utf8_bom(data: &[u8]) -> &[u8] {
         data
     }
 }
+
+/// Computes a stable 64-bit FNV-1a hash of the given bytes.
+///
+/// Unlike the hashers of the standard library, the result is guaranteed to be the same
+/// across runs, platforms and Rust versions, so it can be persisted.
+pub fn content_hash(data: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    data.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// The UTC date of a Unix timestamp, as `YYYY-MM-DD`.
+pub fn iso_date(seconds: u64) -> String {
+    // Days to civil date, after Howard Hinnant's `civil_from_days`
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The path of a file relative to the root, with `/` separators on every platform: the
+/// form of the keys of the per-file settings, such as `line_ranges` and
+/// `compression_overrides`. A path out of the root, or already relative, is kept as is.
+///
+/// # Arguments
+///
+/// * `root` - The root of the codebase
+/// * `path` - The path of the file, absolute or relative to the root
+pub fn relative_path(root: &Path, path: &Path) -> String {
+    path_key(path.strip_prefix(root).unwrap_or(path))
+}
+
+/// A path with `/` separators on every platform.
+pub fn path_key(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
@@ -0,0 +1,225 @@
+//! A/B prompt variants.
+//!
+//! The same codebase is rendered into several prompts differing along declared axes
+//! (placement, summaries, output format...), so that prompt layouts can be compared on
+//! the same task. The variants are recorded in a manifest giving the settings and the token
+//! count of each one.
+
+use anyhow::{Context, Result, anyhow};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::configuration::Code2PromptConfig;
+
+/// A setting the variants differ on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariantAxis {
+    /// Where the instruction, the git diffs and the key files go.
+    Placement,
+    /// Whether build configurations and logs are summarized or kept in full.
+    SummaryMode,
+    /// Markdown, XML or JSON output.
+    OutputFormat,
+    /// Whether code lines are numbered.
+    LineNumbers,
+    /// How test fixtures are handled.
+    FixturePolicy,
+    /// Which files give way first when the prompt exceeds the token budget.
+    BudgetStrategy,
+    /// The format of the git diffs.
+    DiffStyle,
+}
+
+impl VariantAxis {
+    /// All the values of the axis.
+    pub fn values(self) -> &'static [&'static str] {
+        match self {
+            VariantAxis::Placement => &["end", "beginning", "sandwich"],
+            VariantAxis::SummaryMode => &["full", "summarized"],
+            VariantAxis::OutputFormat => &["markdown", "xml", "json"],
+            VariantAxis::LineNumbers => &["off", "on"],
+            VariantAxis::FixturePolicy => &["include", "stub", "exclude"],
+            VariantAxis::BudgetStrategy => &["largest", "smallest", "order", "truncate"],
+            VariantAxis::DiffStyle => &["unified", "word", "function"],
+        }
+    }
+
+    /// The value of the axis in `config`.
+    pub fn value(self, config: &Code2PromptConfig) -> String {
+        fn on_off(value: bool) -> String {
+            if value { "on" } else { "off" }.to_string()
+        }
+        match self {
+            VariantAxis::Placement => config.placement.to_string(),
+            VariantAxis::SummaryMode => {
+                if config.frontend_summary || config.summarize_logs {
+                    "summarized".to_string()
+                } else {
+                    "full".to_string()
+                }
+            }
+            VariantAxis::OutputFormat => config.output_format.to_string(),
+            VariantAxis::LineNumbers => on_off(config.line_numbers),
+            VariantAxis::FixturePolicy => config.fixture_policy.to_string(),
+            VariantAxis::BudgetStrategy => config.budget_strategy.to_string(),
+            VariantAxis::DiffStyle => config.diff_style.to_string(),
+        }
+    }
+
+    /// Sets the axis to `value` in `config`.
+    fn apply(self, config: &mut Code2PromptConfig, value: &str) -> Result<()> {
+        fn parse<T: DeserializeOwned>(value: &str) -> Result<T> {
+            serde_json::from_value(serde_json::Value::String(value.to_string()))
+                .map_err(|e| anyhow!("Invalid value {}: {}", value, e))
+        }
+        match self {
+            VariantAxis::Placement => config.placement = parse(value)?,
+            VariantAxis::SummaryMode => {
+                let summarized = value == "summarized";
+                config.frontend_summary = summarized;
+                config.summarize_logs = summarized;
+            }
+            VariantAxis::OutputFormat => config.output_format = parse(value)?,
+            VariantAxis::LineNumbers => config.line_numbers = value == "on",
+            VariantAxis::FixturePolicy => config.fixture_policy = parse(value)?,
+            VariantAxis::BudgetStrategy => config.budget_strategy = parse(value)?,
+            VariantAxis::DiffStyle => config.diff_style = parse(value)?,
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for VariantAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VariantAxis::Placement => write!(f, "placement"),
+            VariantAxis::SummaryMode => write!(f, "summary_mode"),
+            VariantAxis::OutputFormat => write!(f, "output_format"),
+            VariantAxis::LineNumbers => write!(f, "line_numbers"),
+            VariantAxis::FixturePolicy => write!(f, "fixture_policy"),
+            VariantAxis::BudgetStrategy => write!(f, "budget_strategy"),
+            VariantAxis::DiffStyle => write!(f, "diff_style"),
+        }
+    }
+}
+
+/// A prompt variant: the configuration with the axes set to one combination of values.
+#[derive(Debug, Clone)]
+pub struct Variant {
+    /// The name of the variant, `variant-1` for the first one
+    pub name: String,
+    /// The value of each axis
+    pub settings: BTreeMap<VariantAxis, String>,
+    pub config: Code2PromptConfig,
+}
+
+/// Plans up to `count` variants of `config` differing along `axes`.
+///
+/// The combinations of the axis values are taken in order, the last axis changing first,
+/// and each axis starts from its configured value, so that the first variant is the
+/// configured prompt. Fewer variants are planned when the axes have fewer combinations.
+///
+/// # Arguments
+///
+/// * `config` - The configuration the variants are derived from
+/// * `axes` - The settings the variants differ on
+/// * `count` - The number of variants
+///
+/// # Returns
+///
+/// * `Result<Vec<Variant>>` - The variants, with their manifest and conversation files unset
+pub fn plan_variants(
+    config: &Code2PromptConfig,
+    axes: &[VariantAxis],
+    count: usize,
+) -> Result<Vec<Variant>> {
+    let mut unique_axes: Vec<VariantAxis> = Vec::new();
+    for axis in axes {
+        if !unique_axes.contains(axis) {
+            unique_axes.push(*axis);
+        }
+    }
+    let axes = unique_axes;
+    let values: Vec<Vec<&str>> = axes
+        .iter()
+        .map(|axis| {
+            let current = axis.value(config);
+            let mut values = axis.values().to_vec();
+            // The configured value first
+            values.sort_by_key(|value| *value != current);
+            values
+        })
+        .collect();
+
+    let mut variants = Vec::new();
+    let mut indices = vec![0; axes.len()];
+    while variants.len() < count {
+        let mut variant_config = config.clone();
+        // Each variant would otherwise update the same files
+        variant_config.manifest_file = None;
+        variant_config.conversation_file = None;
+        let mut settings = BTreeMap::new();
+        for ((axis, values), index) in axes.iter().zip(&values).zip(&indices) {
+            axis.apply(&mut variant_config, values[*index])?;
+            settings.insert(*axis, values[*index].to_string());
+        }
+        variants.push(Variant {
+            name: format!("variant-{}", variants.len() + 1),
+            settings,
+            config: variant_config,
+        });
+
+        // Next combination, the last axis first
+        let mut position = axes.len();
+        loop {
+            if position == 0 {
+                return Ok(variants);
+            }
+            position -= 1;
+            indices[position] += 1;
+            if indices[position] < values[position].len() {
+                break;
+            }
+            indices[position] = 0;
+        }
+    }
+    Ok(variants)
+}
+
+/// A variant written to a file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariantRecord {
+    pub name: String,
+    /// The file of the prompt, relative to the manifest
+    pub file: String,
+    pub settings: BTreeMap<VariantAxis, String>,
+    pub token_count: usize,
+}
+
+/// The manifest of the variants generated for an experiment.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VariantManifest {
+    /// The axes the variants differ on
+    pub axes: Vec<VariantAxis>,
+    pub variants: Vec<VariantRecord>,
+}
+
+impl VariantManifest {
+    /// Loads a manifest saved by [`VariantManifest::save`].
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read manifest {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid manifest {}", path.display()))
+    }
+
+    /// Saves the manifest as a JSON file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write manifest {}", path.display()))
+    }
+}
@@ -0,0 +1,233 @@
+//! Selection of workspace packages.
+//!
+//! In a monorepo, restricts the codebase to some packages and the internal packages
+//! they depend on, transitively. Packages are declared by their manifests
+//! (`Cargo.toml`, `package.json`, `pyproject.toml`, `go.mod`); a file belongs to the
+//! package of the closest manifest above it. Files of unrelated packages are pruned,
+//! files belonging to no package (the workspace configuration, shared documentation)
+//! are kept.
+
+use anyhow::{Result, bail};
+use git2::{ObjectType, Repository, TreeWalkMode, TreeWalkResult};
+use log::info;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::architecture::{WorkspacePackage, workspace_packages};
+use crate::configuration::Code2PromptConfig;
+use crate::path::walk_builder;
+use crate::util::path_key;
+
+/// File names of the manifests declaring packages
+const MANIFEST_NAMES: [&str; 4] = ["Cargo.toml", "package.json", "pyproject.toml", "go.mod"];
+
+/// The packages kept in the codebase and those pruned from it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageScope {
+    /// Directory of each package of the workspace, relative to the root, with whether it is kept
+    directories: Vec<(String, bool)>,
+    /// Names of the kept packages, the requested ones and their dependencies
+    pub packages: Vec<String>,
+}
+
+impl PackageScope {
+    /// Resolves the requested packages and their internal dependencies.
+    ///
+    /// # Arguments
+    ///
+    /// * `packages` - The packages of the workspace
+    /// * `requested` - Names or directories of the packages to keep
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PackageScope>` - An error if a requested package is unknown, or if its
+    ///   name is declared by manifests in several directories
+    pub fn resolve(packages: &[WorkspacePackage], requested: &[String]) -> Result<Self> {
+        let mut kept: BTreeSet<usize> = BTreeSet::new();
+        let mut pending: Vec<usize> = Vec::new();
+
+        for request in requested {
+            let by_path: Vec<usize> = matching(packages, |package| {
+                package.path == request.trim_end_matches('/')
+            });
+            let candidates = if by_path.is_empty() {
+                matching(packages, |package| package.name == *request)
+            } else {
+                by_path
+            };
+            let directories: BTreeSet<&str> = candidates
+                .iter()
+                .map(|&index| packages[index].path.as_str())
+                .collect();
+            if candidates.is_empty() {
+                let names: BTreeSet<&str> = packages
+                    .iter()
+                    .map(|package| package.name.as_str())
+                    .collect();
+                bail!(
+                    "Unknown package `{}`, the workspace declares: {}",
+                    request,
+                    names.into_iter().collect::<Vec<_>>().join(", ")
+                );
+            }
+            if directories.len() > 1 {
+                bail!(
+                    "Package `{}` is declared in several directories ({}), select one by its directory",
+                    request,
+                    directories.into_iter().collect::<Vec<_>>().join(", ")
+                );
+            }
+            pending.extend(candidates);
+        }
+
+        while let Some(index) = pending.pop() {
+            if !kept.insert(index) {
+                continue;
+            }
+            let package = &packages[index];
+            for dependency in &package.internal_dependencies {
+                // Several packages may share a name, the ones of the same ecosystem win
+                let same_ecosystem = matching(packages, |other| {
+                    other.name == *dependency && other.ecosystem == package.ecosystem
+                });
+                if same_ecosystem.is_empty() {
+                    pending.extend(matching(packages, |other| other.name == *dependency));
+                } else {
+                    pending.extend(same_ecosystem);
+                }
+            }
+        }
+
+        let mut directories: Vec<(String, bool)> = Vec::new();
+        for (index, package) in packages.iter().enumerate() {
+            let is_kept = kept.contains(&index);
+            // A directory holding several manifests is kept if any of its packages is
+            match directories
+                .iter_mut()
+                .find(|(path, _)| *path == package.path)
+            {
+                Some((_, directory_kept)) => *directory_kept |= is_kept,
+                None => directories.push((package.path.clone(), is_kept)),
+            }
+        }
+        let names: BTreeSet<String> = kept
+            .iter()
+            .map(|&index| packages[index].name.clone())
+            .collect();
+
+        Ok(PackageScope {
+            directories,
+            packages: names.into_iter().collect(),
+        })
+    }
+
+    /// Whether a file belongs to a kept package, or to no package at all.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_path` - The path of the file, relative to the root
+    pub fn contains(&self, relative_path: &Path) -> bool {
+        self.directories
+            .iter()
+            .filter(|(directory, _)| directory == "." || relative_path.starts_with(directory))
+            .max_by_key(|(directory, _)| {
+                if directory == "." {
+                    0
+                } else {
+                    Path::new(directory).components().count()
+                }
+            })
+            .is_none_or(|(_, kept)| *kept)
+    }
+}
+
+/// Resolves the packages selected by `config.packages`, from the manifests of the
+/// working tree or, when the codebase is read from a revision, of its tree.
+///
+/// # Arguments
+///
+/// * `config` - The configuration, whose `packages` are kept
+/// * `git_tree` - The repository and revision the codebase is read from, if any
+///
+/// # Returns
+///
+/// * `Result<Option<PackageScope>>` - `None` if no package is selected
+pub fn package_scope(
+    config: &Code2PromptConfig,
+    git_tree: Option<(&Repository, &str)>,
+) -> Result<Option<PackageScope>> {
+    if config.packages.is_empty() {
+        return Ok(None);
+    }
+    let manifests = match git_tree {
+        Some((repo, rev)) => tree_manifests(repo, rev)?,
+        None => directory_manifests(config)?,
+    };
+    let packages = workspace_packages(&manifests);
+    if packages.is_empty() {
+        bail!("No workspace package found, --package needs package manifests");
+    }
+
+    let scope = PackageScope::resolve(&packages, &config.packages)?;
+    info!("Packages kept: {}", scope.packages.join(", "));
+    Ok(Some(scope))
+}
+
+/// The manifests of the directory, respecting the ignore rules of the traversal.
+fn directory_manifests(config: &Code2PromptConfig) -> Result<Vec<(String, String)>> {
+    let root = config.path.canonicalize()?;
+    let manifests = walk_builder(&root, config)
+        // Installed dependencies declare packages too, but not of the workspace
+        .filter_entry(|entry| entry.file_name() != "node_modules")
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| MANIFEST_NAMES.contains(&name))
+        })
+        .filter_map(|entry| {
+            let content = std::fs::read_to_string(entry.path()).ok()?;
+            let relative = entry.path().strip_prefix(&root).ok()?;
+            Some((path_key(relative), content))
+        })
+        .collect();
+    Ok(manifests)
+}
+
+/// The manifests of the tree of a revision.
+fn tree_manifests(repo: &Repository, rev: &str) -> Result<Vec<(String, String)>> {
+    let tree = repo.revparse_single(rev)?.peel_to_tree()?;
+    let mut manifests = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if entry.name() == Some("node_modules") {
+            return TreeWalkResult::Skip;
+        }
+        if entry.kind() == Some(ObjectType::Blob)
+            && entry
+                .name()
+                .is_some_and(|name| MANIFEST_NAMES.contains(&name))
+            && let Ok(blob) = repo.find_blob(entry.id())
+        {
+            manifests.push((
+                format!("{}{}", root, entry.name().unwrap_or_default()),
+                String::from_utf8_lossy(blob.content()).into_owned(),
+            ));
+        }
+        TreeWalkResult::Ok
+    })?;
+    Ok(manifests)
+}
+
+fn matching(
+    packages: &[WorkspacePackage],
+    predicate: impl Fn(&WorkspacePackage) -> bool,
+) -> Vec<usize> {
+    packages
+        .iter()
+        .enumerate()
+        .filter(|(_, package)| predicate(package))
+        .map(|(index, _)| index)
+        .collect()
+}
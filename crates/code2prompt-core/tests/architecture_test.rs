@@ -0,0 +1,195 @@
+//! Integration tests for the architecture brief
+
+use code2prompt_core::architecture::{ArchitectureBrief, build_architecture_brief};
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::session::Code2PromptSession;
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Estimates the prompt of a brief from its serialized size
+    fn estimate(brief: &ArchitectureBrief) -> anyhow::Result<usize> {
+        Ok(serde_json::to_string(brief)?.len() / 4)
+    }
+
+    fn file(path: &str, content: &str) -> (String, String) {
+        (path.to_string(), content.to_string())
+    }
+
+    fn workspace() -> Vec<(String, String)> {
+        vec![
+            file("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n"),
+            file(
+                "crates/app/Cargo.toml",
+                "[package]\nname = \"app\"\n\n[dependencies]\ncore = { path = \"../core\" }\nclap = \"4\"\n",
+            ),
+            file(
+                "crates/core/Cargo.toml",
+                "[package]\nname = \"core\"\n\n[dependencies]\nserde = \"1\"\n",
+            ),
+            file(
+                "web/package.json",
+                r#"{"name": "web", "dependencies": {"react": "18"}}"#,
+            ),
+            file("README.md", "# Project\n\nDoes things.\n"),
+            file(
+                "crates/core/src/lib.rs",
+                "//! Core logic.\n\n/// Runs\npub fn run(input: &str) -> String {\n    input.to_string()\n}\nfn helper() {}\n",
+            ),
+            file("crates/app/tests/cli_test.rs", "pub fn fixture() {}\n"),
+        ]
+    }
+
+    #[test]
+    fn test_brief_describes_the_workspace() {
+        let brief = build_architecture_brief(&workspace(), 8000, estimate).unwrap();
+
+        let packages: Vec<(&str, &str, Vec<String>, usize)> = brief
+            .packages
+            .iter()
+            .map(|package| {
+                (
+                    package.name.as_str(),
+                    package.path.as_str(),
+                    package.internal_dependencies.clone(),
+                    package.external_dependencies,
+                )
+            })
+            .collect();
+        assert_eq!(
+            packages,
+            vec![
+                ("app", "crates/app", vec!["core".to_string()], 1),
+                ("core", "crates/core", vec![], 1),
+                ("web", "web", vec![], 1),
+            ]
+        );
+
+        assert!(
+            brief
+                .directories
+                .starts_with("7 files in total\ncrates/ (4 files)\n  app/ (2 files)\n")
+        );
+        assert_eq!(brief.readmes[0].path, "README.md");
+        let outline = brief
+            .outlines
+            .iter()
+            .find(|outline| outline.path == "crates/core/src/lib.rs")
+            .unwrap();
+        assert_eq!(outline.summary.as_deref(), Some("Core logic."));
+        assert_eq!(outline.items, vec!["pub fn run(input: &str) -> String"]);
+        assert!(brief.pruned.is_empty());
+        assert!(brief.token_count <= 8000);
+    }
+
+    #[test]
+    fn test_brief_is_pruned_to_the_budget() {
+        let mut files = workspace();
+        let readme: String = (0..200)
+            .map(|i| format!("Line {} of the readme\n", i))
+            .collect();
+        files.push(file("docs/README.md", &readme));
+        for i in 0..200 {
+            files.push(file(
+                &format!("crates/core/src/module_{}/deep/item.rs", i),
+                "//! A module.\npub struct Item;\npub fn make_item() -> Item { Item }\n",
+            ));
+        }
+
+        let brief = build_architecture_brief(&files, 1500, estimate).unwrap();
+        assert!(brief.token_count <= 1500, "{} tokens", brief.token_count);
+
+        assert_eq!(
+            brief.pruned[..2],
+            [
+                "Shortened docs/README.md to their first 40 lines".to_string(),
+                "Limited the directory overview to depth 3".to_string(),
+            ]
+        );
+        // Tests go first, then the most deeply nested files
+        assert!(
+            brief.pruned[2].starts_with("Dropped the outlines of"),
+            "{:?}",
+            brief.pruned
+        );
+        assert!(
+            brief.pruned[2].contains(": crates/app/tests/cli_test.rs, crates/core/src/module_")
+        );
+        assert!(
+            brief
+                .outlines
+                .iter()
+                .any(|outline| outline.path == "crates/core/src/lib.rs")
+        );
+        assert!(!brief.directories.contains("module_"));
+        assert!(!brief.packages.is_empty());
+    }
+
+    #[test]
+    fn test_depth_one_overview_lists_top_level_directories() {
+        let mut files = workspace();
+        for i in 0..100 {
+            files.push(file(
+                &format!("pkg_{}/src/nested/item.rs", i),
+                "fn item() {}\n",
+            ));
+        }
+
+        let brief = build_architecture_brief(&files, 300, estimate).unwrap();
+        assert!(
+            brief
+                .pruned
+                .contains(&"Limited the directory overview to depth 1".to_string()),
+            "{:?}",
+            brief.pruned
+        );
+        let entries: Vec<&str> = brief.directories.lines().skip(1).collect();
+        assert!(entries.contains(&"crates/ (4 files)"));
+        assert!(entries.contains(&"pkg_7/ (1 file)"));
+        assert!(entries.iter().all(|entry| !entry.starts_with(' ')));
+    }
+
+    #[test]
+    fn test_brief_token_count_is_the_measured_one() {
+        let brief = build_architecture_brief(&workspace(), 8000, estimate).unwrap();
+        // Measured before the count was stored in the brief
+        let measured = ArchitectureBrief {
+            token_count: 0,
+            ..brief.clone()
+        };
+        assert_eq!(brief.token_count, estimate(&measured).unwrap());
+
+        assert!(build_architecture_brief(&workspace(), 10, |_| anyhow::bail!("no")).is_err());
+    }
+
+    #[test]
+    fn test_session_loads_brief() {
+        let project = TempDir::new().unwrap();
+        fs::write(project.path().join("README.md"), "# Tool\n").unwrap();
+        fs::write(
+            project.path().join("main.py"),
+            "\"\"\"Entry point.\"\"\"\n\ndef main():\n    pass\n",
+        )
+        .unwrap();
+
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .absolute_path(true)
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        assert!(session.load_architecture_brief(8000).is_err());
+
+        session.load_codebase().unwrap();
+        session.load_architecture_brief(8000).unwrap();
+
+        let brief = session.data.architecture_brief.as_ref().unwrap();
+        assert_eq!(brief.readmes[0].path, "README.md");
+        assert_eq!(brief.outlines[0].path, "main.py");
+        assert_eq!(brief.outlines[0].summary.as_deref(), Some("Entry point."));
+        assert_eq!(brief.outlines[0].items, vec!["def main()"]);
+    }
+}
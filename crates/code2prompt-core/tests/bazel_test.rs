@@ -0,0 +1,181 @@
+//! Integration tests for the selection of Bazel targets
+
+use code2prompt_core::bazel::BazelScope;
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::session::Code2PromptSession;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_files() -> Vec<(String, String)> {
+        vec![
+            (
+                "services/auth/BUILD.bazel".to_string(),
+                r#"
+load("@rules_go//go:def.bzl", "go_binary", "go_library")
+
+go_library(
+    name = "auth_lib",
+    srcs = glob(["*.go"], exclude = ["*_test.go"]) + ["generated.go"],
+    deps = [
+        "//libs/crypto",
+        "@com_github_pkg_errors//:errors",
+    ] + select({
+        "//conditions:linux": ["//libs/platform:linux"],
+        "//conditions:default": [],
+    }),
+    visibility = ["//visibility:public"],
+)
+
+go_binary(
+    name = "auth",
+    embed = [":auth_lib"],
+    data = ["config/auth.yaml"],
+)
+
+go_test(
+    name = "auth_test",
+    srcs = ["auth_test.go"],
+    embed = [":auth_lib"],
+)
+"#
+                .to_string(),
+            ),
+            (
+                "libs/crypto/BUILD".to_string(),
+                "go_library(name = 'crypto', srcs = glob(['**/*.go']))\n".to_string(),
+            ),
+            (
+                "libs/platform/BUILD".to_string(),
+                "go_library(name = \"linux\", srcs = [\"linux.go\"])\ngo_library(name = \"darwin\", srcs = [\"darwin.go\"])\n"
+                    .to_string(),
+            ),
+            (
+                "libs/crypto/internal/BUILD".to_string(),
+                "go_library(name = \"internal\", srcs = [\"hidden.go\"])\n".to_string(),
+            ),
+        ]
+    }
+
+    fn paths() -> Vec<String> {
+        [
+            "services/auth/BUILD.bazel",
+            "services/auth/main.go",
+            "services/auth/token.go",
+            "services/auth/auth_test.go",
+            "services/auth/config/auth.yaml",
+            "services/auth/README.md",
+            "libs/crypto/BUILD",
+            "libs/crypto/hash.go",
+            "libs/crypto/aes/block.go",
+            "libs/crypto/internal/BUILD",
+            "libs/crypto/internal/hidden.go",
+            "libs/platform/BUILD",
+            "libs/platform/linux.go",
+            "libs/platform/darwin.go",
+        ]
+        .iter()
+        .map(|path| path.to_string())
+        .collect()
+    }
+
+    fn targets(labels: &[&str]) -> Vec<String> {
+        labels.iter().map(|label| label.to_string()).collect()
+    }
+
+    #[test]
+    fn test_transitive_closure_of_a_target() {
+        let scope = BazelScope::resolve(
+            &build_files(),
+            &paths(),
+            &targets(&["//services/auth:auth"]),
+        )
+        .unwrap();
+
+        let sources: Vec<&str> = scope.sources.iter().map(String::as_str).collect();
+        assert_eq!(
+            sources,
+            vec![
+                "libs/crypto/aes/block.go",
+                "libs/crypto/hash.go",
+                "libs/platform/linux.go",
+                "services/auth/config/auth.yaml",
+                "services/auth/main.go",
+                "services/auth/token.go",
+            ]
+        );
+        assert_eq!(
+            scope.targets,
+            vec![
+                "//libs/crypto:crypto",
+                "//libs/platform:linux",
+                "//services/auth:auth",
+                "//services/auth:auth_lib",
+            ]
+        );
+        assert!(scope.contains(Path::new("services/auth/main.go")));
+        assert!(!scope.contains(Path::new("services/auth/auth_test.go")));
+    }
+
+    #[test]
+    fn test_recursive_patterns_and_unknown_targets() {
+        let scope =
+            BazelScope::resolve(&build_files(), &paths(), &targets(&["//libs/..."])).unwrap();
+        assert!(scope.contains(Path::new("libs/crypto/internal/hidden.go")));
+        assert!(scope.contains(Path::new("libs/platform/darwin.go")));
+        assert!(!scope.contains(Path::new("services/auth/main.go")));
+
+        let error =
+            BazelScope::resolve(&build_files(), &paths(), &targets(&["//services/billing"]))
+                .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown Bazel target `//services/billing`"
+        );
+    }
+
+    #[test]
+    fn test_session_loads_target_sources() {
+        let project = TempDir::new().unwrap();
+        let root = project.path();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(
+            "app/BUCK",
+            "python_binary(name = 'app', main = 'main.py', deps = ['//lib:util'])\n",
+        );
+        write("app/main.py", "print('app')\n");
+        write(
+            "lib/BUCK",
+            "python_library(name = 'util', srcs = ['util.py'])\n",
+        );
+        write("lib/util.py", "def util(): pass\n");
+        write("lib/unused.py", "def unused(): pass\n");
+
+        let config = Code2PromptConfig::builder()
+            .path(root.to_path_buf())
+            .bazel_targets(vec!["//app".to_string()])
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.load_codebase().unwrap();
+
+        let mut files: Vec<String> = session
+            .data
+            .files
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|file| file.path.replace('\\', "/"))
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["app/main.py", "lib/util.py"]);
+    }
+}
@@ -0,0 +1,63 @@
+//! Tests for the detection and handling of binary files
+
+use code2prompt_core::binary::{BinaryHandling, encode_base64, is_binary};
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::path::traverse_directory;
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    fn traverse(
+        root: &std::path::Path,
+        binary_handling: BinaryHandling,
+    ) -> (String, Vec<(String, String)>) {
+        let config = Code2PromptConfig::builder()
+            .path(root.to_path_buf())
+            .binary_handling(binary_handling)
+            .build()
+            .unwrap();
+        let (tree, files) = traverse_directory(&config, None).unwrap();
+        (tree, files.into_iter().map(|f| (f.path, f.code)).collect())
+    }
+
+    #[test]
+    fn test_is_binary_and_base64() {
+        assert!(is_binary(PNG_HEADER));
+        assert!(!is_binary(b"fn main() {}\n"));
+
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+        let long = encode_base64(&[0u8; 100]);
+        assert_eq!(long.lines().map(str::len).collect::<Vec<_>>(), [76, 60]);
+    }
+
+    #[test]
+    fn test_binary_handling() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.path().join("logo.png"), PNG_HEADER).unwrap();
+
+        // Binary files stay in the tree
+        let (tree, files) = traverse(dir.path(), BinaryHandling::Skip);
+        assert!(tree.contains("logo.png"));
+        assert_eq!(files.len(), 1);
+
+        let (_, files) = traverse(dir.path(), BinaryHandling::PathOnly);
+        let logo = files.iter().find(|(path, _)| path == "logo.png").unwrap();
+        assert_eq!(logo.1, "(binary file, 16 B)");
+
+        let (_, files) = traverse(dir.path(), BinaryHandling::Base64);
+        let logo = files.iter().find(|(path, _)| path == "logo.png").unwrap();
+        assert_eq!(
+            logo.1,
+            format!("```base64\n{}\n```", encode_base64(PNG_HEADER))
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Integration tests for the token budget enforcement
+
+use code2prompt_core::budget::{BudgetAction, BudgetStrategy};
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::session::{Code2PromptSession, RenderedPrompt};
+use code2prompt_core::sort::FileSortMethod;
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A codebase with one large file and a few small ones
+    fn codebase() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let large: String = (0..400)
+            .map(|i| format!("fn function_{}() -> usize {{ {} }}\n", i, i))
+            .collect();
+        fs::write(dir.path().join("a_large.rs"), large).unwrap();
+        for name in ["b_small.rs", "c_small.rs", "d_small.rs"] {
+            fs::write(
+                dir.path().join(name),
+                format!("// {}\nfn small() {{}}\n", name),
+            )
+            .unwrap();
+        }
+        dir
+    }
+
+    fn generate(
+        dir: &TempDir,
+        max_tokens: Option<usize>,
+        strategy: BudgetStrategy,
+    ) -> (Code2PromptSession, RenderedPrompt) {
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .max_tokens(max_tokens)
+            .budget_strategy(strategy)
+            .sort_method(FileSortMethod::NameAsc)
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+        (session, rendered)
+    }
+
+    fn kept(session: &Code2PromptSession) -> Vec<String> {
+        session
+            .data
+            .files
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|file| file.path.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_no_budget() {
+        let dir = codebase();
+        let (session, rendered) = generate(&dir, None, BudgetStrategy::Largest);
+        assert_eq!(kept(&session).len(), 4);
+        assert!(rendered.budget.is_none());
+    }
+
+    #[test]
+    fn test_largest_first() {
+        let dir = codebase();
+        let (session, rendered) = generate(&dir, Some(600), BudgetStrategy::Largest);
+        let budget = rendered.budget.unwrap();
+        assert!(budget.fits);
+        assert!(rendered.token_count <= 600);
+        assert_eq!(budget.dropped_files, vec!["a_large.rs"]);
+        assert_eq!(
+            kept(&session),
+            vec!["b_small.rs", "c_small.rs", "d_small.rs"]
+        );
+        assert!(!rendered.prompt.contains("function_1"));
+    }
+
+    #[test]
+    fn test_budget_already_met() {
+        let dir = codebase();
+        let (session, rendered) = generate(&dir, Some(1_000_000), BudgetStrategy::Largest);
+        let budget = rendered.budget.unwrap();
+        assert!(budget.fits);
+        assert!(budget.dropped_files.is_empty());
+        assert_eq!(kept(&session).len(), 4);
+    }
+
+    #[test]
+    fn test_smallest_and_order() {
+        let dir = codebase();
+        let (_, full) = generate(&dir, None, BudgetStrategy::Largest);
+        // Room for everything but about two small files
+        let budget_tokens = full.token_count - 30;
+
+        let (session, rendered) = generate(&dir, Some(budget_tokens), BudgetStrategy::Smallest);
+        assert!(rendered.budget.unwrap().fits);
+        assert!(kept(&session).contains(&"a_large.rs".to_string()));
+        assert!(kept(&session).len() < 4);
+
+        let (session, rendered) = generate(&dir, Some(budget_tokens), BudgetStrategy::Order);
+        let budget = rendered.budget.unwrap();
+        assert!(budget.fits);
+        assert_eq!(budget.dropped_files[0], "d_small.rs");
+        assert_eq!(kept(&session)[0], "a_large.rs");
+    }
+
+    #[test]
+    fn test_truncate() {
+        let dir = codebase();
+        let (session, rendered) = generate(&dir, Some(1500), BudgetStrategy::Truncate);
+        let budget = rendered.budget.unwrap();
+        assert!(budget.fits);
+        assert!(rendered.token_count <= 1500);
+        assert!(budget.dropped_files.is_empty());
+        assert_eq!(budget.truncated_files, vec!["a_large.rs"]);
+        assert_eq!(kept(&session).len(), 4);
+        assert!(rendered.prompt.contains("fn function_0()"));
+        assert!(rendered.prompt.contains("lines omitted]\n```"));
+        assert!(!rendered.prompt.contains("function_399"));
+    }
+
+    /// A template whose notes give way before the files, and whose instruction after them
+    const SECTIONED_TEMPLATE: &str = "{{#section \"instruction\" priority=1}}Review the code.\n{{/section}}\
+{{#section \"notes\" priority=-1}}{{notes}}{{/section}}\
+{{#each files}}{{path}}\n{{code}}\n{{/each}}";
+
+    fn generate_sectioned(
+        dir: &TempDir,
+        max_tokens: usize,
+    ) -> (Code2PromptSession, RenderedPrompt) {
+        let notes: String = (0..200).map(|i| format!("note number {}\n", i)).collect();
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .max_tokens(Some(max_tokens))
+            .sort_method(FileSortMethod::NameAsc)
+            .template_str(SECTIONED_TEMPLATE.to_string())
+            .template_name("sectioned".to_string())
+            .user_variables(std::collections::HashMap::from([(
+                "notes".to_string(),
+                notes,
+            )]))
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+        (session, rendered)
+    }
+
+    #[test]
+    fn test_low_priority_sections_trimmed_before_files() {
+        let dir = codebase();
+        let (session, rendered) = generate_sectioned(&dir, 5000);
+        let budget = rendered.budget.unwrap();
+        assert!(budget.fits);
+        assert!(rendered.token_count <= 5000);
+        assert_eq!(budget.trimmed_sections, vec!["notes"]);
+        assert!(budget.dropped_files.is_empty());
+        assert_eq!(kept(&session).len(), 4);
+        assert!(!rendered.prompt.contains("note number"));
+        assert!(rendered.prompt.starts_with("Review the code."));
+        assert_eq!(session.template_sections().len(), 1);
+    }
+
+    #[test]
+    fn test_high_priority_sections_trimmed_after_files() {
+        let dir = codebase();
+        let (session, rendered) = generate_sectioned(&dir, 2);
+        let budget = rendered.budget.unwrap();
+        assert_eq!(budget.trimmed_sections, vec!["notes", "instruction"]);
+        assert!(kept(&session).is_empty());
+        assert!(!rendered.prompt.contains("Review the code."));
+    }
+
+    fn negotiation(dir: &TempDir, max_tokens: usize) -> Code2PromptSession {
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .max_tokens(Some(max_tokens))
+            .sort_method(FileSortMethod::NameAsc)
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.load_codebase().unwrap();
+        session
+    }
+
+    #[test]
+    fn test_negotiated_actions() {
+        let dir = codebase();
+        let body: String = (0..50)
+            .map(|i| format!("    let value_{} = {} * 2;\n", i, i))
+            .collect();
+        fs::write(
+            dir.path().join("e_bodies.rs"),
+            format!("/// Doubles\nfn double() {{\n{}}}\n", body),
+        )
+        .unwrap();
+        let mut session = negotiation(&dir, 800);
+        let excess = session.token_budget_excess().unwrap();
+        assert!(excess > 0);
+        assert!(!session.data.budget_report.as_ref().unwrap().fits);
+
+        let saved = session
+            .apply_budget_action("e_bodies.rs", BudgetAction::Summarize)
+            .unwrap();
+        assert!(saved > 0);
+        let file = session.data.files.as_ref().unwrap().last().unwrap();
+        assert!(file.code.contains("fn double()"));
+        assert!(!file.code.contains("value_1"));
+
+        session
+            .apply_budget_action("a_large.rs", BudgetAction::Truncate)
+            .unwrap();
+        assert!(rendered_prompt(&mut session).contains("lines omitted]"));
+        session
+            .apply_budget_action("b_small.rs", BudgetAction::Drop)
+            .unwrap();
+        assert!(
+            session
+                .apply_budget_action("b_small.rs", BudgetAction::Drop)
+                .is_err()
+        );
+
+        let report = session.data.budget_report.clone().unwrap();
+        assert_eq!(report.summarized_files, vec!["e_bodies.rs"]);
+        assert_eq!(report.truncated_files, vec!["a_large.rs"]);
+        assert_eq!(report.dropped_files, vec!["b_small.rs"]);
+        // A truncated file keeps at least half of its tokens
+        assert!(session.token_budget_excess().unwrap() < excess);
+        assert!(!report.fits);
+    }
+
+    fn rendered_prompt(session: &mut Code2PromptSession) -> String {
+        let data = session.build_template_data();
+        session.render_prompt(&data).unwrap().prompt
+    }
+}
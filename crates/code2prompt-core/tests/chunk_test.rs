@@ -0,0 +1,209 @@
+//! # Chunk Module Tests
+//!
+//! Tests for the splitting of the files into overlapping chunks with stable IDs, and for
+//! the `chunks` output format.
+
+use code2prompt_core::{
+    chunk::{Chunk, ChunkOptions, chunk_file},
+    configuration::Code2PromptConfig,
+    language::SourceStats,
+    path::{EntryMetadata, FileEntry, wrap_code_block},
+    session::Code2PromptSession,
+    template::OutputFormat,
+    tokenizer::TokenCountMode,
+    util::content_hash,
+};
+use rstest::*;
+use std::fs;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+/// A file of `count` numbered lines, wrapped in a code fence
+fn numbered_file(path: &str, count: usize) -> FileEntry {
+    let code: Vec<String> = (1..=count).map(|n| format!("line {}", n)).collect();
+    let code = code.join("\n");
+    FileEntry {
+        path: path.to_string(),
+        extension: "rs".to_string(),
+        code: wrap_code_block(&code, "rs", false, false),
+        token_count: 0,
+        content_hash: format!("{:016x}", content_hash(code.as_bytes())),
+        metadata: EntryMetadata {
+            is_dir: false,
+            is_symlink: false,
+        },
+        mod_time: None,
+        history: None,
+        line_range: None,
+        stats: SourceStats::default(),
+        symbols: None,
+    }
+}
+
+/// A configuration chunking by `lines` lines with `overlap` lines shared
+fn chunk_config(lines: usize, overlap: usize) -> Code2PromptConfig {
+    Code2PromptConfig::builder()
+        .path(".")
+        .chunk_options(ChunkOptions { lines, overlap })
+        .token_count_mode(TokenCountMode::Heuristic)
+        .build()
+        .expect("Failed to build config")
+}
+
+/// A project with a source file of 25 lines
+#[fixture]
+fn project() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let code: Vec<String> = (1..=25).map(|n| format!("let x{} = {};", n, n)).collect();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/lib.rs"), code.join("\n")).unwrap();
+    dir
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_overlap() {
+        let chunks = chunk_file(&numbered_file("src/lib.rs", 25), &chunk_config(10, 3));
+        let lines: Vec<(usize, usize)> = chunks
+            .iter()
+            .map(|chunk| (chunk.start_line.unwrap(), chunk.end_line.unwrap()))
+            .collect();
+        assert_eq!(lines, [(1, 10), (8, 17), (15, 24), (22, 25)]);
+        assert!(chunks[1].content.starts_with("line 8\n"));
+        assert!(chunks[3].content.ends_with("line 25"));
+        assert!(!chunks[0].content.contains("```"));
+        assert_eq!(chunks[0].overlap, 0);
+        assert_eq!(chunks[1].overlap, 3);
+        assert!(chunks.iter().all(|chunk| chunk.chunk_count == 4));
+        assert_eq!(chunks[0].language, "rs");
+        assert!(chunks.iter().all(|chunk| !chunk.transformed));
+    }
+
+    #[test]
+    fn test_small_and_empty_files() {
+        let chunks = chunk_file(&numbered_file("a.rs", 5), &chunk_config(10, 3));
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(
+            (chunks[0].start_line, chunks[0].end_line),
+            (Some(1), Some(5))
+        );
+        // An overlap as large as the chunks still moves forward
+        assert_eq!(
+            chunk_file(&numbered_file("a.rs", 5), &chunk_config(2, 5)).len(),
+            4
+        );
+        assert!(chunk_file(&numbered_file("a.rs", 0), &chunk_config(10, 3)).is_empty());
+    }
+
+    #[test]
+    fn test_chunk_ids_are_stable() {
+        let config = chunk_config(10, 3);
+        let first = chunk_file(&numbered_file("src/lib.rs", 25), &config);
+        let again = chunk_file(&numbered_file("src/lib.rs", 25), &config);
+        assert_eq!(first, again);
+        let ids: std::collections::HashSet<&str> =
+            first.iter().map(|chunk| chunk.id.as_str()).collect();
+        assert_eq!(ids.len(), first.len());
+
+        // Another path or content changes the ID
+        let moved = chunk_file(&numbered_file("src/main.rs", 25), &config);
+        assert_ne!(first[0].id, moved[0].id);
+        let grown = chunk_file(&numbered_file("src/lib.rs", 26), &config);
+        let chunk_ids = |chunks: &[Chunk]| {
+            chunks
+                .iter()
+                .map(|chunk| chunk.id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(chunk_ids(&first[..3]), chunk_ids(&grown[..3]));
+        assert_ne!(first[3].id, grown[3].id);
+    }
+
+    #[rstest]
+    fn test_chunks_output_format(project: TempDir) {
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .output_format(OutputFormat::Chunks)
+            .chunk_options(ChunkOptions {
+                lines: 10,
+                overlap: 2,
+            })
+            .build()
+            .unwrap();
+        let rendered = Code2PromptSession::new(config).generate_prompt().unwrap();
+
+        let chunks: Vec<Chunk> = rendered
+            .prompt
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|chunk| chunk.path == "src/lib.rs"));
+        assert_eq!(
+            (chunks[2].start_line, chunks[2].end_line),
+            (Some(17), Some(25))
+        );
+        assert_eq!(chunks[0].content.lines().next(), Some("let x1 = 1;"));
+        assert!(chunks[0].token_count > 0);
+    }
+
+    #[test]
+    fn test_transformed_file_has_no_lines() {
+        let mut file = numbered_file("src/lib.rs", 5);
+        file.code = wrap_code_block("fn a() { ... }", "rs", false, false);
+        let chunks = chunk_file(&file, &chunk_config(10, 3));
+        assert_eq!(chunks.len(), 1);
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (None, None));
+        assert!(chunks[0].transformed);
+
+        let json = serde_json::to_string(&chunks[0]).unwrap();
+        assert!(json.contains("\"transformed\":true"));
+        assert!(!json.contains("start_line"));
+    }
+
+    #[rstest]
+    fn test_chunk_lines_numbered_as_in_file(project: TempDir) {
+        let chunks = |config: Code2PromptConfig| {
+            let mut session = Code2PromptSession::new(config);
+            session.load_codebase().unwrap();
+            chunk_file(&session.data.files.unwrap()[0], &session.config)
+        };
+        let config = || {
+            Code2PromptConfig::builder()
+                .path(project.path().to_path_buf())
+                .chunk_options(ChunkOptions {
+                    lines: 4,
+                    overlap: 1,
+                })
+                .line_numbers(true)
+                .clone()
+        };
+
+        // Line numbers are left out of the content
+        let whole = chunks(config().build().unwrap());
+        assert_eq!((whole[1].start_line, whole[1].end_line), (Some(4), Some(7)));
+        assert_eq!(whole[1].content.lines().next(), Some("let x4 = 4;"));
+        assert!(!whole[1].transformed);
+
+        // Each range is split on its own
+        let ranges = chunks(
+            config()
+                .include_patterns(vec!["src/lib.rs:3-7".into(), "src/lib.rs:20-21".into()])
+                .build()
+                .unwrap(),
+        );
+        let lines: Vec<_> = ranges
+            .iter()
+            .map(|chunk| (chunk.start_line.unwrap(), chunk.end_line.unwrap()))
+            .collect();
+        assert_eq!(lines, [(3, 6), (6, 7), (20, 21)]);
+        assert_eq!(ranges[2].content, "let x20 = 20;\nlet x21 = 21;");
+        assert_eq!(ranges[2].overlap, 0);
+    }
+}
@@ -0,0 +1,110 @@
+//! # Collapse Tests
+//!
+//! Tests for the directories collapsed in the output: their line in the tree, the entry
+//! summarizing their files, and the selection left unchanged.
+
+use code2prompt_core::{
+    configuration::Code2PromptConfig, path::traverse_directory, session::Code2PromptSession,
+    sort::FileSortMethod,
+};
+use rstest::*;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+/// Creates a project with vendored code
+#[fixture]
+fn vendored_project() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let files = [
+        ("src/main.rs", "fn main() {}\n"),
+        ("vendor/json/lib.rs", "pub fn parse() {}\n"),
+        ("vendor/json/value.rs", "pub enum Value {}\n"),
+        ("vendor/yaml.rs", "pub fn load() {}\n"),
+    ];
+    for (path, content) in files {
+        let full_path = dir.path().join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).expect("Failed to create dir");
+        fs::write(full_path, content).expect("Failed to write file");
+    }
+    dir
+}
+
+fn config(dir: &TempDir, collapse: &[&str]) -> Code2PromptConfig {
+    Code2PromptConfig::builder()
+        .path(dir.path().to_path_buf())
+        .sort_method(Some(FileSortMethod::NameAsc))
+        .collapse_patterns(collapse.iter().map(|p| p.to_string()).collect::<Vec<_>>())
+        .build()
+        .expect("Failed to build config")
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    #[case("vendor")]
+    #[case("vendor/")]
+    #[case("vendor/**")]
+    fn test_collapsed_directory_in_tree_and_files(
+        vendored_project: TempDir,
+        #[case] pattern: &str,
+    ) {
+        let (tree, files) = traverse_directory(&config(&vendored_project, &[pattern]), None)
+            .expect("Failed to traverse");
+
+        assert!(tree.contains("vendor/ (3 files, "), "{}", tree);
+        assert!(!tree.contains("yaml.rs"));
+        assert!(tree.contains("main.rs"));
+
+        let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/main.rs", "vendor"]);
+        let summary = &files[1];
+        assert!(summary.code.starts_with("(collapsed directory: 3 files, "));
+        assert!(summary.metadata.is_dir);
+    }
+
+    #[rstest]
+    fn test_nested_directory(vendored_project: TempDir) {
+        let (tree, files) = traverse_directory(&config(&vendored_project, &["json"]), None)
+            .expect("Failed to traverse");
+
+        assert!(tree.contains("json/ (2 files, "));
+        assert!(tree.contains("yaml.rs"));
+        let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/main.rs", "vendor/json", "vendor/yaml.rs"]);
+    }
+
+    #[rstest]
+    fn test_selection_is_unchanged(vendored_project: TempDir) {
+        let mut session = Code2PromptSession::new(config(&vendored_project, &["vendor"]));
+        session.deselect_file(PathBuf::from("vendor/yaml.rs"));
+        session.load_codebase().unwrap();
+
+        let files = session.data.files.as_deref().unwrap_or_default();
+        let summary = files.iter().find(|file| file.path == "vendor").unwrap();
+        assert!(summary.code.starts_with("(collapsed directory: 2 files, "));
+        assert!(session.is_file_selected(&PathBuf::from("vendor/json/lib.rs")));
+        assert!(!session.is_file_selected(&PathBuf::from("vendor/yaml.rs")));
+    }
+
+    #[rstest]
+    fn test_absolute_paths(vendored_project: TempDir) {
+        let config = Code2PromptConfig {
+            absolute_path: true,
+            ..config(&vendored_project, &["vendor"])
+        };
+        let (_, files) = traverse_directory(&config, None).expect("Failed to traverse");
+
+        assert!(
+            files
+                .iter()
+                .any(|file| file.path.ends_with("/vendor") && file.metadata.is_dir)
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! # Compression Tests
+//!
+//! Tests for the code-aware compression keeping the signatures of the source files: the
+//! bodies stripped by language, the files kept in full, and the per-file overrides.
+
+use code2prompt_core::{
+    compression::{CompressionLevel, compress},
+    configuration::Code2PromptConfig,
+    session::Code2PromptSession,
+};
+use rstest::*;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+const RUST_SOURCE: &str = r#"//! Parsing of the settings.
+
+/// A parsed setting
+#[derive(Debug)]
+pub struct Setting {
+    pub name: String,
+}
+
+impl Setting {
+    /// Parses a line, as `name = value`
+    pub fn parse(line: &str) -> Option<Self> {
+        let (name, _) = line.split_once('=')?;
+        if name.contains('{') {
+            return None;
+        }
+        Some(Setting { name: name.trim().to_string() })
+    }
+}
+
+pub fn render<'a>(setting: &'a Setting) -> &'a str {
+    // A brace in a string: "}"
+    let _ = "}";
+    &setting.name
+}
+"#;
+
+#[fixture]
+fn codebase() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    fs::write(dir.path().join("lib.rs"), RUST_SOURCE).unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {\n    run();\n}\n").unwrap();
+    fs::write(dir.path().join("notes.txt"), "fn is not code here {\n}\n").unwrap();
+    dir
+}
+
+fn session(dir: &TempDir, level: CompressionLevel) -> Code2PromptSession {
+    let config = Code2PromptConfig::builder()
+        .path(dir.path().to_path_buf())
+        .compression_level(level)
+        .line_numbers(true)
+        .build()
+        .unwrap();
+    Code2PromptSession::new(config)
+}
+
+fn file_code(session: &Code2PromptSession, name: &str) -> String {
+    session
+        .data
+        .files
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .find(|file| file.path.ends_with(name))
+        .map(|file| file.code.clone())
+        .unwrap_or_default()
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn test_rust_keeps_signatures_docs_and_types() {
+        let compressed = compress(RUST_SOURCE, "rs", CompressionLevel::Signatures).unwrap();
+
+        assert!(compressed.contains("/// A parsed setting"));
+        assert!(compressed.contains("pub struct Setting {\n    pub name: String,\n}"));
+        assert!(compressed.contains("impl Setting {"));
+        assert!(compressed.contains("/// Parses a line, as `name = value`"));
+        assert!(compressed.contains("pub fn parse(line: &str) -> Option<Self> { ... }"));
+        assert!(compressed.contains("pub fn render<'a>(setting: &'a Setting) -> &'a str { ... }"));
+        assert!(!compressed.contains("split_once"));
+        assert!(!compressed.contains("A brace in a string"));
+    }
+
+    #[rstest]
+    #[case(
+        "ts",
+        "export class Client {\n  send(body: string): void {\n    fetch(body);\n  }\n}\nexport const ping = () => {\n  return 1;\n};\n",
+        "export class Client {\n  send(body: string): void { ... }\n}\nexport const ping = () => { ... };\n"
+    )]
+    #[case(
+        "go",
+        "type Server struct {\n\tport int\n}\n\nfunc (s *Server) Run() error {\n\treturn nil\n}\n",
+        "type Server struct {\n\tport int\n}\n\nfunc (s *Server) Run() error { ... }\n"
+    )]
+    #[case(
+        "java",
+        "public class App {\n    @Override\n    public String toString() {\n        return \"{\";\n    }\n}\n",
+        "public class App {\n    @Override\n    public String toString() { ... }\n}\n"
+    )]
+    #[case(
+        "c",
+        "struct point { int x; };\n\nint area(struct point *p) {\n    return p->x;\n}\n",
+        "struct point { int x; };\n\nint area(struct point *p) { ... }\n"
+    )]
+    fn test_brace_languages(#[case] extension: &str, #[case] code: &str, #[case] expected: &str) {
+        let compressed = compress(code, extension, CompressionLevel::Signatures).unwrap();
+        assert_eq!(compressed, expected);
+    }
+
+    #[rstest]
+    #[case(
+        "rs",
+        "fn a() -> &'static str {\n    r#\"x \" {\"#\n}\n\npub fn b(x: u32) -> u32 { x + 1 }\n",
+        "fn a() -> &'static str { ... }\n\npub fn b(x: u32) -> u32 { ... }\n"
+    )]
+    #[case(
+        "rs",
+        "fn a() -> &'static [u8] {\n    br\"{\"\n}\n\nfn b() -> u8 {\n    b'}'\n}\n",
+        "fn a() -> &'static [u8] { ... }\n\nfn b() -> u8 { ... }\n"
+    )]
+    #[case(
+        "cpp",
+        "int big() {\n    return 1'000'000;\n}\n\nint small() {\n    return 1;\n}\n",
+        "int big() { ... }\n\nint small() { ... }\n"
+    )]
+    #[case(
+        "js",
+        "function strip(text) {\n  return text.replace(/}/g, '');\n}\n\nfunction half(x) {\n  return x / 2;\n}\n",
+        "function strip(text) { ... }\n\nfunction half(x) { ... }\n"
+    )]
+    fn test_literals_hiding_braces(
+        #[case] extension: &str,
+        #[case] code: &str,
+        #[case] expected: &str,
+    ) {
+        let compressed = compress(code, extension, CompressionLevel::Signatures).unwrap();
+        assert_eq!(compressed, expected);
+    }
+
+    /// A file the lexer cannot follow to its end is embedded in full
+    #[rstest]
+    #[case("rs", "fn a() {\n    let s = \"open;\n}\n\npub fn b() {}\n")]
+    #[case("rs", "fn a() {\n    if x {\n}\n\npub fn b() {}\n")]
+    #[case("rs", "/* open\nfn a() {}\n")]
+    #[case("cpp", "int a() {\n    char c = ';\n}\n")]
+    #[case("py", "def a():\n    \"\"\"open\n\ndef b():\n    pass\n")]
+    fn test_lexer_failures_keep_the_file(#[case] extension: &str, #[case] code: &str) {
+        assert_eq!(
+            compress(code, extension, CompressionLevel::Signatures),
+            None
+        );
+    }
+
+    #[rstest]
+    fn test_python_keeps_docstrings() {
+        let code = r#"class Parser:
+    """Parses the settings."""
+
+    def __init__(self, path):
+        self.path = path
+
+    @staticmethod
+    def parse(
+        line: str,
+    ) -> dict:
+        """Parses a line.
+
+        Returns a dictionary.
+        """
+        name, value = line.split("=")
+        return {name: value}
+
+    def name(self): return self.path
+
+
+def main():
+    Parser("x")
+"#;
+        let expected = r#"class Parser:
+    """Parses the settings."""
+
+    def __init__(self, path):
+        ...
+
+    @staticmethod
+    def parse(
+        line: str,
+    ) -> dict:
+        """Parses a line.
+
+        Returns a dictionary.
+        """
+        ...
+
+    def name(self): return self.path
+
+
+def main():
+    ...
+"#;
+        let compressed = compress(code, "py", CompressionLevel::Signatures).unwrap();
+        assert_eq!(compressed, expected);
+    }
+
+    #[rstest]
+    fn test_python_docstring_mentioning_def() {
+        let code = r#"def render():
+    """Renders an example:
+
+def fake():
+    pass
+"""
+    return template
+
+
+def after():
+    pass
+"#;
+        let expected = r#"def render():
+    """Renders an example:
+
+def fake():
+    pass
+"""
+    ...
+
+
+def after():
+    ...
+"#;
+        let compressed = compress(code, "py", CompressionLevel::Signatures).unwrap();
+        assert_eq!(compressed, expected);
+    }
+
+    #[rstest]
+    fn test_python_sql_string_mentioning_def() {
+        let code = r#"def query():
+    s = """
+SELECT def FROM t;
+def fake():
+"""
+    return s
+"#;
+        let expected = "def query():\n    ...\n";
+        let compressed = compress(code, "py", CompressionLevel::Signatures).unwrap();
+        assert_eq!(compressed, expected);
+    }
+
+    #[rstest]
+    fn test_python_brackets_in_default_values() {
+        let code = r#"def split(line, sep=")", pairs="([{"):
+    return line.split(sep)
+
+
+def join(
+    parts,
+    sep=")",  # a closing ) in a comment
+    end='(',
+) -> str:
+    return sep.join(parts) + end
+"#;
+        let expected = r#"def split(line, sep=")", pairs="([{"):
+    ...
+
+
+def join(
+    parts,
+    sep=")",  # a closing ) in a comment
+    end='(',
+) -> str:
+    ...
+"#;
+        let compressed = compress(code, "py", CompressionLevel::Signatures).unwrap();
+        assert_eq!(compressed, expected);
+    }
+
+    #[rstest]
+    fn test_unsupported_languages_and_full_level_are_kept() {
+        assert_eq!(
+            compress("a {\n}\n", "txt", CompressionLevel::Signatures),
+            None
+        );
+        assert_eq!(compress(RUST_SOURCE, "rs", CompressionLevel::Full), None);
+    }
+
+    #[rstest]
+    fn test_compressed_files_in_the_prompt(codebase: TempDir) {
+        let mut session = session(&codebase, CompressionLevel::Signatures);
+        session.load_codebase().unwrap();
+
+        let lib = file_code(&session, "lib.rs");
+        assert!(lib.contains("pub fn parse(line: &str) -> Option<Self> { ... }"));
+        // The lines of a compressed file no longer match those of the file
+        assert!(!lib.contains("1 | "));
+        assert_eq!(
+            file_code(&session, "main.rs"),
+            "```rs\nfn main() { ... }\n\n```"
+        );
+        assert!(file_code(&session, "notes.txt").contains("fn is not code here {"));
+    }
+
+    #[rstest]
+    fn test_file_override(codebase: TempDir) {
+        let mut compressed = session(&codebase, CompressionLevel::Signatures);
+        compressed.set_file_compression(PathBuf::from("main.rs"), CompressionLevel::Full);
+        compressed.load_codebase().unwrap();
+        assert!(file_code(&compressed, "main.rs").contains("run();"));
+        assert!(file_code(&compressed, "lib.rs").contains("{ ... }"));
+
+        let mut full = session(&codebase, CompressionLevel::Full);
+        full.set_file_compression(codebase.path().join("lib.rs"), CompressionLevel::Signatures);
+        full.load_codebase().unwrap();
+        assert!(file_code(&full, "main.rs").contains("run();"));
+        assert!(!file_code(&full, "lib.rs").contains("split_once"));
+    }
+}
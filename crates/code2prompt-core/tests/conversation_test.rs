@@ -0,0 +1,57 @@
+//! Integration tests for conversation delta context
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::conversation::ConversationState;
+use code2prompt_core::session::Code2PromptSession;
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(project: &TempDir, conversation_file: &std::path::Path) -> String {
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .conversation_file(Some(conversation_file.to_path_buf()))
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.generate_prompt().unwrap().prompt
+    }
+
+    #[test]
+    fn test_follow_up_turn_only_includes_delta() {
+        let project = TempDir::new().unwrap();
+        let state_dir = TempDir::new().unwrap();
+        let conversation_file = state_dir.path().join("conversation.json");
+        fs::write(project.path().join("a.rs"), "fn a() {}").unwrap();
+        fs::write(project.path().join("b.rs"), "fn b() {}").unwrap();
+        fs::write(project.path().join("c.rs"), "fn c() {}").unwrap();
+
+        // First turn sends everything
+        let first = generate(&project, &conversation_file);
+        assert!(first.contains("fn a() {}"));
+        assert!(first.contains("fn b() {}"));
+        assert!(!first.contains("already shared"));
+
+        // Follow-up turn only sends the changed and added files
+        fs::write(project.path().join("b.rs"), "fn b() -> u8 { 1 }").unwrap();
+        fs::write(project.path().join("d.rs"), "fn d() {}").unwrap();
+        fs::remove_file(project.path().join("c.rs")).unwrap();
+        let second = generate(&project, &conversation_file);
+        assert!(!second.contains("fn a() {}"));
+        assert!(second.contains("fn b() -> u8 { 1 }"));
+        assert!(second.contains("fn d() {}"));
+        assert!(second.contains("already shared earlier in this conversation"));
+        assert!(second.contains("- `a.rs`"));
+        assert!(second.contains("removed since:\n- `c.rs`"));
+
+        let state = ConversationState::load(&conversation_file).unwrap();
+        assert_eq!(state.turns, 2);
+        assert_eq!(
+            state.manifest.files.keys().cloned().collect::<Vec<_>>(),
+            vec!["a.rs", "b.rs", "d.rs"]
+        );
+    }
+}
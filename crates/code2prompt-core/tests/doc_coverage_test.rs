@@ -0,0 +1,176 @@
+//! Integration tests for the documentation coverage analysis
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::doc_coverage::{DocCoverageReport, analyze_file};
+use code2prompt_core::session::Code2PromptSession;
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gap_names(path: &str, content: &str) -> Vec<String> {
+        analyze_file(path, content)
+            .expect("Language should be supported")
+            .gaps
+            .into_iter()
+            .map(|gap| format!("{} {}", gap.kind, gap.name))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_public_items() {
+        let source = r#"//! Crate docs
+mod private;
+pub mod parser;
+
+/// Documented
+pub fn documented() {}
+
+#[derive(
+    Debug,
+    Clone,
+)]
+pub struct Undocumented;
+
+/// Documented through attributes
+#[derive(Debug)]
+#[serde(default)]
+pub enum Mode {}
+
+pub(crate) fn internal() {}
+fn private() {}
+
+pub const fn new() -> Self {}
+pub const LIMIT: usize = 3;
+/** Block doc */
+pub trait Api {}
+/* Plain comment */
+pub async unsafe fn run() {}
+"#;
+        let coverage = analyze_file("src/lib.rs", source).unwrap();
+        assert_eq!(coverage.language, "rust");
+        assert_eq!(coverage.items, 7);
+        assert_eq!(coverage.documented, 3);
+        assert_eq!(
+            gap_names("src/lib.rs", source),
+            vec!["struct Undocumented", "fn new", "const LIMIT", "fn run"]
+        );
+        assert_eq!(coverage.gaps[0].line, 12);
+    }
+
+    #[test]
+    fn test_typescript_exported_items() {
+        let source = r#"/** Documented */
+export function documented() {}
+export async function load() {}
+/**
+ * Multi-line doc
+ */
+@Component({})
+export class Widget {}
+// Plain comment
+export interface Props {}
+export const DEFAULT = 3;
+export default function () {}
+function internal() {}
+export { internal };
+"#;
+        assert_eq!(
+            gap_names("src/index.ts", source),
+            vec![
+                "function load",
+                "interface Props",
+                "const DEFAULT",
+                "function default"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_python_public_items() {
+        let source = r#"def documented():
+    """Docs."""
+
+def undocumented(
+    a,
+    b,
+):  # trailing comment
+    return a
+
+class Service:
+    '''Service docs.'''
+
+    def method(self):
+        pass
+
+    def _private(self):
+        def nested():
+            pass
+
+class _Hidden:
+    def method(self):
+        pass
+
+async def fetch():
+    # comment before the docstring
+    r"""Docs."""
+"#;
+        assert_eq!(
+            gap_names("app/service.py", source),
+            vec!["def undocumented", "def method"]
+        );
+        assert!(analyze_file("README.md", "# Title").is_none());
+    }
+
+    #[test]
+    fn test_report_totals() {
+        let report = DocCoverageReport::from_files([
+            analyze_file("a.rs", "/// Doc\npub fn a() {}\npub fn b() {}\n").unwrap(),
+            analyze_file("b.rs", "/// Doc\npub fn c() {}\n").unwrap(),
+        ]);
+        assert_eq!(report.analyzed_files, 2);
+        assert_eq!(report.total_items, 3);
+        assert_eq!(report.gap_count(), 1);
+        assert_eq!(report.files.len(), 1);
+        assert!((report.coverage - 200.0 / 3.0).abs() < 1e-9);
+        assert_eq!(DocCoverageReport::from_files([]).coverage, 100.0);
+    }
+
+    #[test]
+    fn test_session_keeps_files_with_gaps() {
+        let project = TempDir::new().unwrap();
+        fs::write(project.path().join("lib.rs"), "pub fn undocumented() {}\n").unwrap();
+        fs::write(
+            project.path().join("done.rs"),
+            "/// Doc\npub fn done() {}\n",
+        )
+        .unwrap();
+        fs::write(project.path().join("notes.txt"), "notes").unwrap();
+
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .line_numbers(true)
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        assert!(session.load_doc_coverage().is_err());
+
+        session.load_codebase().unwrap();
+        session.load_doc_coverage().unwrap();
+
+        let files: Vec<&str> = session
+            .data
+            .files
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect();
+        assert_eq!(files, vec!["lib.rs"]);
+        let report = session.data.doc_coverage.as_ref().unwrap();
+        assert_eq!(report.analyzed_files, 2);
+        assert_eq!(report.files[0].gaps[0].name, "undocumented");
+    }
+}
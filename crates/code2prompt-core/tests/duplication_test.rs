@@ -0,0 +1,88 @@
+//! Tests for the detection of code duplicated across files
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::duplication::{find_duplicates, stub_duplicates};
+use code2prompt_core::path::{FileEntry, traverse_directory};
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELPER: &str = "fn clamp(value: i32) -> i32 {\n    if value < 0 {\n        return 0;\n    }\n    if value > 100 {\n        return 100;\n    }\n    value\n}\n";
+
+    fn files(root: &std::path::Path, line_numbers: bool) -> (Code2PromptConfig, Vec<FileEntry>) {
+        let config = Code2PromptConfig::builder()
+            .path(root.to_path_buf())
+            .line_numbers(line_numbers)
+            .build()
+            .unwrap();
+        let (_, mut files) = traverse_directory(&config, None).unwrap();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        (config, files)
+    }
+
+    fn write_sources(dir: &TempDir) {
+        fs::write(dir.path().join("a.rs"), HELPER).unwrap();
+        // Reindented, after a line of its own
+        let copy = HELPER.replace("    ", "\t");
+        fs::write(
+            dir.path().join("b.rs"),
+            format!("use std::io;\n\n{}\nfn main() {{}}\n", copy),
+        )
+        .unwrap();
+        fs::write(dir.path().join("c.rs"), "fn other() {}\n").unwrap();
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let dir = TempDir::new().unwrap();
+        write_sources(&dir);
+
+        for line_numbers in [false, true] {
+            let (config, files) = files(dir.path(), line_numbers);
+            let report = find_duplicates(&files, &config);
+            assert_eq!(report.duplicates.len(), 1);
+            let duplicate = &report.duplicates[0];
+            assert_eq!(duplicate.path, "b.rs");
+            assert_eq!((duplicate.start_line, duplicate.end_line), (3, 11));
+            assert_eq!(duplicate.original_path, "a.rs");
+            assert_eq!(
+                (duplicate.original_start_line, duplicate.original_end_line),
+                (1, 9)
+            );
+            assert!(duplicate.tokens > 0);
+            assert_eq!(report.tokens, duplicate.tokens);
+            assert!(!report.stubbed);
+        }
+    }
+
+    #[test]
+    fn test_short_blocks_are_not_duplicates() {
+        let dir = TempDir::new().unwrap();
+        write_sources(&dir);
+        let (mut config, files) = files(dir.path(), false);
+        config.duplicate_min_lines = 10;
+        assert!(find_duplicates(&files, &config).duplicates.is_empty());
+    }
+
+    #[test]
+    fn test_stub_duplicates() {
+        let dir = TempDir::new().unwrap();
+        write_sources(&dir);
+        let (config, mut files) = files(dir.path(), false);
+        let before = files[1].token_count;
+
+        let mut report = find_duplicates(&files, &config);
+        stub_duplicates(&mut files, &mut report, &config);
+        assert!(report.stubbed);
+        assert_eq!(
+            files[1].code,
+            "```rs\nuse std::io;\n\n... [duplicate of a.rs:1-9]\n\nfn main() {}\n\n```"
+        );
+        assert!(files[1].token_count < before);
+        // The first copy is kept
+        assert!(files[0].code.contains("fn clamp"));
+    }
+}
@@ -0,0 +1,153 @@
+//! Integration tests for the detection of the development environment
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::environment::detect_environment;
+use code2prompt_core::session::Code2PromptSession;
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nix_flake() {
+        let project = TempDir::new().unwrap();
+        fs::write(
+            project.path().join("flake.nix"),
+            r#"{
+  inputs.nixpkgs.url = "github:NixOS/nixpkgs/nixos-24.05";
+  outputs = { self, nixpkgs }: {
+    devShells.x86_64-linux.default = pkgs.mkShell {
+      packages = with pkgs; [
+        nodejs_20 # the web frontend
+        pkgs.postgresql_16
+        (python3.withPackages (ps: [ ps.requests ]))
+      ];
+      buildInputs = [ pkgs.openssl ];
+      shellHook = ''
+        export DATABASE_URL=postgres://localhost/dev
+      '';
+    };
+  };
+}
+"#,
+        )
+        .unwrap();
+
+        let spec = detect_environment(project.path()).unwrap();
+        assert_eq!(spec.sources, vec!["flake.nix"]);
+        assert_eq!(
+            spec.nixpkgs.as_deref(),
+            Some("github:NixOS/nixpkgs/nixos-24.05")
+        );
+        assert_eq!(
+            spec.tools,
+            vec![
+                "nodejs_20",
+                "postgresql_16",
+                "python3.withPackages",
+                "openssl"
+            ]
+        );
+        assert_eq!(
+            spec.setup_commands,
+            vec!["export DATABASE_URL=postgres://localhost/dev"]
+        );
+    }
+
+    #[test]
+    fn test_devcontainer_and_toolchain_files() {
+        let project = TempDir::new().unwrap();
+        let root = project.path();
+        fs::create_dir(root.join(".devcontainer")).unwrap();
+        fs::write(
+            root.join(".devcontainer/devcontainer.json"),
+            r#"{
+  // Development container
+  "image": "mcr.microsoft.com/devcontainers/rust:1-bookworm",
+  "features": {
+    "ghcr.io/devcontainers/features/node:1": { "version": "20" },
+    "ghcr.io/devcontainers/features/docker-in-docker:2": {},
+  },
+  /* Lifecycle */
+  "postCreateCommand": "cargo fetch",
+  "containerEnv": { "RUST_LOG": "debug" },
+}
+"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"1.80.0\"\ncomponents = [\"clippy\"]\n",
+        )
+        .unwrap();
+        fs::write(root.join(".tool-versions"), "golang 1.22.1 # backend\n").unwrap();
+
+        let spec = detect_environment(root).unwrap();
+        assert_eq!(
+            spec.sources,
+            vec![
+                ".devcontainer/devcontainer.json",
+                "rust-toolchain.toml",
+                ".tool-versions"
+            ]
+        );
+        assert_eq!(
+            spec.base_image.as_deref(),
+            Some("mcr.microsoft.com/devcontainers/rust:1-bookworm")
+        );
+        assert_eq!(
+            spec.tools,
+            vec![
+                "docker-in-docker",
+                "node 20",
+                "rust 1.80.0 (with clippy)",
+                "golang 1.22.1"
+            ]
+        );
+        assert_eq!(spec.setup_commands, vec!["cargo fetch"]);
+        assert_eq!(spec.env, vec!["RUST_LOG=debug"]);
+        assert!(
+            spec.summary
+                .contains("- Container image: mcr.microsoft.com/devcontainers/rust:1-bookworm")
+        );
+        assert!(detect_environment(&root.join(".devcontainer")).is_none());
+    }
+
+    #[test]
+    fn test_environment_spec_variable() {
+        let project = TempDir::new().unwrap();
+        fs::write(project.path().join(".nvmrc"), "v20.11.0\n").unwrap();
+        fs::write(project.path().join("index.js"), "console.log(1)\n").unwrap();
+
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .template_str("{{environment_spec.summary}}".to_string())
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.load_codebase().unwrap();
+
+        let rendered = session
+            .render_prompt(&session.build_template_data())
+            .unwrap();
+        assert!(rendered.prompt.contains("- Tools: node v20.11.0"));
+    }
+
+    #[test]
+    fn test_environment_detected_only_for_templates_using_it() {
+        let project = TempDir::new().unwrap();
+        fs::write(project.path().join(".nvmrc"), "v20.11.0\n").unwrap();
+
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .template_str("{{#each files}}{{path}}{{/each}}".to_string())
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.load_codebase().unwrap();
+        assert!(session.data.environment_spec.is_none());
+        assert!(session.data.version_info.is_none());
+    }
+}
@@ -0,0 +1,71 @@
+//! Tests for the grading of prompt variant responses
+
+use code2prompt_core::eval::{
+    EvalScore, SCORE_INSTRUCTION, grading_prompt, parse_score, scores_csv,
+};
+use code2prompt_core::variants::{VariantAxis, VariantRecord};
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> VariantRecord {
+        VariantRecord {
+            name: "variant-2".to_string(),
+            file: "variant-2.md".to_string(),
+            settings: BTreeMap::from([(VariantAxis::Placement, "beginning".to_string())]),
+            token_count: 120,
+        }
+    }
+
+    #[test]
+    fn test_grading_prompt() {
+        let prompt = grading_prompt(
+            "Grade {{variant}} ({{settings.placement}}):\n{{response}}",
+            &record(),
+            "The answer",
+        )
+        .unwrap();
+        assert_eq!(
+            prompt,
+            format!(
+                "Grade variant-2 (beginning):\nThe answer\n\n{}",
+                SCORE_INSTRUCTION
+            )
+        );
+
+        // The response is appended when the rubric does not use it
+        let prompt = grading_prompt("Is it correct?", &record(), "Yes\n").unwrap();
+        assert!(prompt.starts_with("Is it correct?\n\nResponse:\n\nYes\n\n"));
+    }
+
+    #[test]
+    fn test_parse_score() {
+        assert_eq!(parse_score("Clear and correct.\nScore: 8"), Some(8.0));
+        assert_eq!(parse_score("**Score**: 7.5/10"), Some(7.5));
+        assert_eq!(parse_score("score 2 at first, final score: 4"), Some(4.0));
+        assert_eq!(parse_score("I would give it 6"), Some(6.0));
+        assert_eq!(parse_score("No idea"), None);
+    }
+
+    #[test]
+    fn test_scores_csv() {
+        let score = |run, score| EvalScore {
+            variant: "variant-2".to_string(),
+            run,
+            settings: record().settings,
+            token_count: 120,
+            score,
+        };
+        assert_eq!(
+            scores_csv(
+                &[VariantAxis::Placement, VariantAxis::SummaryMode],
+                &[score(1, Some(8.0)), score(2, None)]
+            ),
+            "variant,run,placement,summary_mode,token_count,score\n\
+             variant-2,1,beginning,,120,8\n\
+             variant-2,2,beginning,,120,\n"
+        );
+    }
+}
@@ -3,6 +3,7 @@
 //! This file contains all tests for the file processor implementations,
 //! organized by processor type.
 
+use code2prompt_core::configuration::Code2PromptConfig;
 use code2prompt_core::file_processor::*;
 use std::path::PathBuf;
 
@@ -193,6 +194,171 @@ mod jsonl_tests {
     }
 }
 
+// ============================================================================
+// Data Sample Processor Tests
+// ============================================================================
+
+mod sample_tests {
+    use super::*;
+
+    fn sampler(head: usize, tail: usize) -> DataSampleProcessor {
+        DataSampleProcessor {
+            sample: DataSample { head, tail },
+        }
+    }
+
+    #[test]
+    fn test_csv_head_and_tail() {
+        let content = b"id,note\n1,a\n2,\"multi\nline\"\n3,c\n4,d\n5,e\n";
+        let result = sampler(2, 1)
+            .process(content, &PathBuf::from("data.csv"))
+            .unwrap();
+
+        assert_eq!(
+            result,
+            "CSV Data (first 2 and last 1 of 5 rows):\n\
+             Columns: id, note\n\
+             id,note\n\
+             1,a\n\
+             2,\"multi\nline\"\n\
+             ... [2 rows omitted]\n\
+             5,e\n"
+        );
+    }
+
+    #[test]
+    fn test_small_file_kept_whole() {
+        let content = b"a\tb\n1\t2\n";
+        let result = sampler(5, 5)
+            .process(content, &PathBuf::from("data.tsv"))
+            .unwrap();
+
+        assert_eq!(result, "TSV Data (1 rows):\nColumns: a, b\na\tb\n1\t2\n");
+    }
+
+    #[test]
+    fn test_jsonl_fields_from_sampled_lines() {
+        let content = b"{\"id\":1}\n{\"id\":2,\"skipped\":true}\n{\"id\":3,\"late\":1}\n";
+        let result = sampler(1, 1)
+            .process(content, &PathBuf::from("events.ndjson"))
+            .unwrap();
+
+        assert!(result.starts_with("JSONL Data (first 1 and last 1 of 3 lines):\n"));
+        assert!(result.contains("Fields: id, late\n"));
+        assert!(result.contains("... [1 lines omitted]\n{\"id\":3,\"late\":1}\n"));
+    }
+
+    #[test]
+    fn test_unparsable_data_samples_lines() {
+        let content = b"not json\nstill not\nlast";
+        let result = sampler(1, 1)
+            .process(content, &PathBuf::from("broken.jsonl"))
+            .unwrap();
+
+        assert_eq!(
+            result,
+            "Text Data (first 1 and last 1 of 3 lines):\nnot json\n... [1 lines omitted]\nlast\n"
+        );
+    }
+
+    #[test]
+    fn test_get_processor_samples_data_files_only() {
+        let config = Code2PromptConfig::builder()
+            .data_sample(DataSample { head: 1, tail: 0 })
+            .build()
+            .unwrap();
+        let content = b"x\n1\n2\n";
+        let csv = get_processor("CSV", &config)
+            .process(content, &PathBuf::from("a.CSV"))
+            .unwrap();
+        assert!(csv.starts_with("CSV Data (first 1 and last 0 of 2 rows)"));
+
+        let text = get_processor("txt", &config)
+            .process(content, &PathBuf::from("a.txt"))
+            .unwrap();
+        assert_eq!(text, "x\n1\n2\n");
+    }
+}
+
+// ============================================================================
+// Log Summary Processor Tests
+// ============================================================================
+
+mod log_tests {
+    use super::*;
+
+    #[test]
+    fn test_log_summary_keeps_errors_with_context() {
+        let mut log = vec!["starting".to_string(), "loading config".to_string()];
+        log.extend((0..50).map(|i| format!("12:00:{:02} heartbeat ok", i)));
+        log.extend(
+            [
+                "connecting to db",
+                "ERROR connection refused (attempt 1)",
+                "retrying",
+            ]
+            .map(str::to_string),
+        );
+        log.extend((0..20).map(|i| format!("processed item {}", i * 7 % 13)));
+        log.push("ERROR connection refused (attempt 2)".to_string());
+        log.extend(["done", "exit 1", "bye", "flush", "closed"].map(str::to_string));
+
+        let result = LogSummaryProcessor.summarize(&log.join("\n"));
+
+        assert_eq!(
+            result,
+            "Log Summary (81 lines, 2 errors, 0 warnings, 1 lines elided, 68 repeated lines collapsed):\n\
+             starting\n\
+             loading config\n\
+             12:00:00 heartbeat ok [repeated 50 times]\n\
+             connecting to db\n\
+             ERROR connection refused (attempt 1) [2 occurrences in total]\n\
+             retrying\n\
+             processed item 0 [repeated 20 times]\n\
+             ... [line 76 elided]\n\
+             done\n\
+             exit 1\n\
+             bye\n\
+             flush\n\
+             closed\n"
+        );
+    }
+
+    #[test]
+    fn test_log_summary_elides_noise() {
+        let mut log: Vec<String> = (0..30).map(|i| format!("step {}", "x".repeat(i))).collect();
+        log[15] = "WARNING disk almost full".to_string();
+
+        let result = LogSummaryProcessor.summarize(&log.join("\n"));
+
+        assert!(result.starts_with(
+            "Log Summary (30 lines, 0 errors, 1 warnings, 17 lines elided, 0 repeated lines collapsed):\n"
+        ));
+        assert!(result.contains("step xx\n... [lines 4-13 elided]\nstep xxxxxxxxxxxxx\n"));
+        assert!(result.contains("WARNING disk almost full\n"));
+        assert!(result.contains("... [lines 19-25 elided]\n"));
+        assert!(result.ends_with("step xxxxxxxxxxxxxxxxxxxxxxxxxxxxx\n"));
+    }
+
+    #[test]
+    fn test_log_processor_selection() {
+        let config = Code2PromptConfig::builder()
+            .summarize_logs(true)
+            .build()
+            .unwrap();
+        let content = b"one\ntwo\n";
+        let summarized = get_processor("log", &config)
+            .process(content, &PathBuf::from("app.log"))
+            .unwrap();
+        assert!(summarized.starts_with("Log Summary (2 lines"));
+
+        let raw = get_processor("log", &Code2PromptConfig::default())
+            .process(content, &PathBuf::from("app.log"))
+            .unwrap();
+        assert_eq!(raw, "one\ntwo\n");
+    }
+}
+
 // ============================================================================
 // Jupyter Notebook Processor Tests
 // ============================================================================
@@ -0,0 +1,111 @@
+//! Integration tests for the handling of test fixtures, snapshots and golden files
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::fixture::{FixtureKind, FixturePolicy, fixture_kind, fixture_stub};
+use code2prompt_core::session::Code2PromptSession;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_kind() {
+        let kind = |path: &str| fixture_kind(Path::new(path));
+        assert_eq!(
+            kind("src/__snapshots__/App.test.tsx.snap"),
+            Some(FixtureKind::Snapshot)
+        );
+        assert_eq!(
+            kind("tests/snapshots/render__basic.snap.new"),
+            Some(FixtureKind::Snapshot)
+        );
+        assert_eq!(kind("tests/test_api.ambr"), Some(FixtureKind::Snapshot));
+        assert_eq!(
+            kind("parser/testdata/golden/expr.txt"),
+            Some(FixtureKind::Golden)
+        );
+        assert_eq!(kind("cmd/help.golden"), Some(FixtureKind::Golden));
+        assert_eq!(
+            kind("tests/fixtures/users.json"),
+            Some(FixtureKind::Fixture)
+        );
+        assert_eq!(kind("pkg/testdata/input.yaml"), Some(FixtureKind::Fixture));
+        assert_eq!(kind("src/fixtures.rs"), None);
+        assert_eq!(kind("tests/api_test.rs"), None);
+    }
+
+    #[test]
+    fn test_fixture_stub() {
+        let content = "line\n".repeat(1234);
+        assert_eq!(
+            fixture_stub(FixtureKind::Snapshot, &content, Some(1_709_251_200)),
+            "snapshot, 1.2k lines, updated 2024-03-01"
+        );
+        assert_eq!(
+            fixture_stub(FixtureKind::Golden, "one line", None),
+            "golden file, 1 line"
+        );
+        assert_eq!(
+            fixture_stub(FixtureKind::Fixture, &"x\n".repeat(2000), Some(0)),
+            "fixture, 2k lines, updated 1970-01-01"
+        );
+    }
+
+    #[test]
+    fn test_fixture_policies() {
+        let project = TempDir::new().unwrap();
+        let root = project.path();
+        fs::create_dir_all(root.join("src/__snapshots__")).unwrap();
+        fs::write(root.join("src/app.js"), "export const app = 1;\n").unwrap();
+        fs::write(
+            root.join("src/__snapshots__/app.test.js.snap"),
+            "exports[`renders`] = `<div />`;\n".repeat(3),
+        )
+        .unwrap();
+
+        let load = |policy: FixturePolicy| {
+            let config = Code2PromptConfig::builder()
+                .path(root.to_path_buf())
+                .fixture_policy(policy)
+                .build()
+                .unwrap();
+            let mut session = Code2PromptSession::new(config);
+            session.load_codebase().unwrap();
+            session
+        };
+
+        let session = load(FixturePolicy::Stub);
+        let files = session.data.files.as_ref().unwrap();
+        let snapshot = files
+            .iter()
+            .find(|file| file.path.ends_with(".snap"))
+            .unwrap();
+        assert!(snapshot.code.starts_with("(snapshot, 3 lines, updated "));
+        assert!(!snapshot.code.contains("renders"));
+
+        let session = load(FixturePolicy::Exclude);
+        assert_eq!(session.data.files.as_ref().unwrap().len(), 1);
+        assert!(
+            !session
+                .data
+                .source_tree
+                .as_ref()
+                .unwrap()
+                .contains("__snapshots__")
+        );
+
+        let session = load(FixturePolicy::Include);
+        assert!(
+            session
+                .data
+                .files
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|file| file.code.contains("renders"))
+        );
+    }
+}
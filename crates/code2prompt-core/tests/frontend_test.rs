@@ -0,0 +1,232 @@
+//! Integration tests for the frontend asset pipeline summary
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::frontend::{build_tool, group_routes, summarize_build_configs};
+use code2prompt_core::session::Code2PromptSession;
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &str) -> (String, String) {
+        (path.to_string(), content.to_string())
+    }
+
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn test_vite_config_summary() {
+        let config = summarize_build_configs(&[file(
+            "vite.config.ts",
+            r#"import { defineConfig } from 'vite'
+import react from '@vitejs/plugin-react'
+import path from "node:path"
+import { aliases } from './aliases'
+
+// https://vitejs.dev/config/
+export default defineConfig({
+  plugins: [react(), tsconfigPaths()],
+  resolve: {
+    alias: { '@': path.resolve(__dirname, './src') },
+  },
+  server: {
+    port: 3000,
+    proxy: { '/api': 'http://localhost:8080' },
+  },
+  build: { outDir: 'dist', sourcemap: true },
+  define: { __VERSION__: JSON.stringify(process.env.npm_package_version) },
+})
+"#,
+        )])
+        .unwrap();
+
+        let vite = &config.files[0];
+        assert_eq!(vite.tool, "Vite");
+        assert_eq!(
+            vite.packages,
+            vec!["vite", "@vitejs/plugin-react", "node:path"]
+        );
+        assert_eq!(
+            vite.settings,
+            vec![
+                "plugins = [react(), tsconfigPaths()]",
+                "resolve.alias.@ = path.resolve()",
+                "server.port = 3000",
+                "server.proxy./api = \"http://localhost:8080\"",
+                "build.outDir = \"dist\"",
+                "build.sourcemap = true",
+                "define.__VERSION__ = JSON.stringify()",
+            ]
+        );
+        assert!(
+            config
+                .summary
+                .starts_with("- `vite.config.ts` (Vite)\n  - Uses: vite")
+        );
+    }
+
+    #[test]
+    fn test_commonjs_and_named_configs() {
+        let config = summarize_build_configs(&[
+            file(
+                "tailwind.config.js",
+                r#"/** @type {import('tailwindcss').Config} */
+module.exports = {
+  content: ["./src/**/*.{js,tsx}"],
+  theme: { extend: { colors: { brand: '#ff6600' } } },
+  plugins: [require('@tailwindcss/forms')],
+}
+"#,
+            ),
+            file(
+                "next.config.mjs",
+                r#"const nextConfig = {
+  output: 'standalone',
+  images: { remotePatterns: [{ hostname: 'cdn.example.com' }] },
+  webpack: (config) => {
+    config.resolve.fallback = { fs: false }
+    return config
+  },
+  async redirects() {
+    return []
+  },
+}
+
+export default nextConfig
+"#,
+            ),
+            file("src/app.config.ts", "export default {}"),
+        ])
+        .unwrap();
+
+        assert_eq!(config.files.len(), 2);
+        let tailwind = &config.files[0];
+        assert_eq!(tailwind.packages, vec!["@tailwindcss/forms"]);
+        assert_eq!(
+            tailwind.settings,
+            vec![
+                "content = [\"./src/**/*.{js,tsx}\"]",
+                "theme.extend.colors.brand = \"#ff6600\"",
+                "plugins = [require()]",
+            ]
+        );
+        assert_eq!(
+            config.files[1].settings,
+            vec![
+                "output = \"standalone\"",
+                "images.remotePatterns = [{…}]",
+                "webpack = (function)",
+                "redirects = (function)",
+            ]
+        );
+        assert_eq!(build_tool("apps/web/webpack.config.cjs"), Some("webpack"));
+        assert_eq!(build_tool("vite.config.json"), None);
+    }
+
+    #[test]
+    fn test_route_conventions() {
+        let layout = group_routes(&paths(&[
+            "src/app/layout.tsx",
+            "src/app/page.tsx",
+            "src/app/(marketing)/about/page.tsx",
+            "src/app/dashboard/[id]/page.tsx",
+            "src/app/dashboard/[id]/_components/Chart.tsx",
+            "src/lib/db.ts",
+        ]))
+        .unwrap();
+        assert_eq!(layout.convention, "Routes (Next.js app router)");
+        let groups: Vec<(&str, usize)> = layout
+            .groups
+            .iter()
+            .map(|group| (group.name.as_str(), group.files.len()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![("/", 2), ("/about", 1), ("/dashboard/[id]", 2)]
+        );
+
+        let layout = group_routes(&paths(&[
+            "app/root.tsx",
+            "app/routes/_index.tsx",
+            "app/routes/blog.$slug.tsx",
+            "app/routes/blog.$slug.module.css",
+        ]))
+        .unwrap();
+        assert_eq!(layout.convention, "Routes");
+        assert_eq!(layout.groups[0].name, "/");
+        assert_eq!(layout.groups[1].name, "/blog/$slug");
+        assert_eq!(layout.groups[1].files.len(), 1);
+
+        let layout = group_routes(&paths(&[
+            "pages/_app.tsx",
+            "pages/index.tsx",
+            "pages/blog/[slug].tsx",
+        ]))
+        .unwrap();
+        assert_eq!(
+            layout.to_tree(),
+            "Routes (pages router):\n  /\n    pages/index.tsx\n  / (app shell)\n    pages/_app.tsx\n  /blog/[slug]\n    pages/blog/[slug].tsx\n"
+        );
+
+        let layout = group_routes(&paths(&[
+            "src/features/cart/Cart.tsx",
+            "src/features/cart/cartSlice.ts",
+            "src/features/auth/Login.tsx",
+        ]))
+        .unwrap();
+        assert_eq!(layout.convention, "Features");
+        assert_eq!(layout.groups[0].name, "auth");
+        assert!(group_routes(&paths(&["src/main.rs"])).is_none());
+    }
+
+    #[test]
+    fn test_session_summarizes_frontend() {
+        let project = TempDir::new().unwrap();
+        let root = project.path();
+        fs::create_dir_all(root.join("src/features/search")).unwrap();
+        fs::write(
+            root.join("vite.config.js"),
+            "export default { server: { port: 5173 } }\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/features/search/Search.jsx"), "export {}\n").unwrap();
+
+        let config = Code2PromptConfig::builder()
+            .path(root.to_path_buf())
+            .frontend_summary(true)
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.load_codebase().unwrap();
+
+        let files: Vec<&str> = session
+            .data
+            .files
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect();
+        assert_eq!(files, vec!["src/features/search/Search.jsx"]);
+        let build_config = session.data.build_config.as_ref().unwrap();
+        assert_eq!(build_config.files[0].settings, vec!["server.port = 5173"]);
+        assert!(
+            session
+                .data
+                .source_tree
+                .as_ref()
+                .unwrap()
+                .ends_with("Features:\n  search\n    src/features/search/Search.jsx")
+        );
+
+        let rendered = session
+            .render_prompt(&session.build_template_data())
+            .unwrap();
+        assert!(rendered.prompt.contains("Build Configuration:"));
+        assert!(!rendered.prompt.contains("`vite.config.js`:"));
+    }
+}
@@ -1,4 +1,10 @@
-use code2prompt_core::git::{get_git_diff, get_git_diff_between_branches, get_git_log};
+use code2prompt_core::git::{
+    DiffStyle, GitDiffOptions, get_branch_changes, get_branches, get_changed_files,
+    get_file_history, get_git_diff, get_git_diff_between_branches, get_git_diff_with_options,
+    get_git_log, get_git_stash_diff, get_merge_conflicts, get_merged_pull_requests,
+    get_untracked_files, get_version_info, prefix_diff_paths, split_diff_by_file, truncate_diff,
+};
+use code2prompt_core::tokenizer::{TokenCountMode, TokenizerType};
 
 #[cfg(test)]
 mod tests {
@@ -263,6 +269,9 @@ mod tests {
         // Assert that the log contains the expected content
         assert!(log.contains("First commit in development"));
         assert!(log.contains("Second commit in development"));
+
+        let branches = get_branches(repo_path).expect("Failed to list branches");
+        assert_eq!(branches, vec!["development", "master"]);
     }
 
     #[test]
@@ -380,9 +389,708 @@ mod tests {
         let result = get_git_diff_between_branches(repo_path, "nonexistent_reference", "HEAD");
 
         assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("Branch nonexistent_reference doesn't exist!"));
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Branch nonexistent_reference doesn't exist!")
+        );
+    }
+
+    #[test]
+    fn test_split_diff_by_file() {
+        let diff = "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1 +1 @@\n-a\n+b\ndiff --git a/README.md b/README.md\n--- a/README.md\n+++ b/README.md\n@@ -1 +1,2 @@\n # Title\n+More\n";
+
+        let files = split_diff_by_file(diff);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].0, "src/a.rs");
+        assert!(files[0].1.starts_with("diff --git a/src/a.rs"));
+        assert!(files[0].1.ends_with("+b\n"));
+        assert_eq!(files[1].0, "README.md");
+        assert!(files[1].1.contains("+More"));
+        assert_eq!(
+            files
+                .iter()
+                .map(|(_, diff)| diff.as_str())
+                .collect::<String>(),
+            diff
+        );
+
+        assert!(split_diff_by_file("").is_empty());
+    }
+
+    #[test]
+    fn test_prefix_diff_paths() {
+        let diff = "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1,2 +1 @@\n-- a/b\n--- a/c\n+b\ndiff --git a/new.rs b/new.rs\nnew file mode 100644\n--- /dev/null\n+++ b/new.rs\n@@ -0,0 +1 @@\n+new\n";
+
+        let prefixed = prefix_diff_paths(diff, "repo");
+        assert!(prefixed.starts_with(
+            "diff --git a/repo/src/a.rs b/repo/src/a.rs\n--- a/repo/src/a.rs\n+++ b/repo/src/a.rs\n"
+        ));
+        // Removed lines looking like headers are kept
+        assert!(prefixed.contains("\n-- a/b\n--- a/c\n"));
+        assert!(prefixed.contains("--- /dev/null\n+++ b/repo/new.rs\n"));
+        assert_eq!(split_diff_by_file(&prefixed)[1].0, "repo/new.rs");
+    }
+
+    #[test]
+    fn test_truncate_diff_drops_generated_files_first() {
+        let lock_body = "+\"version\": \"1.0.0\",\n".repeat(200);
+        let diff = format!(
+            "diff --git a/package-lock.json b/package-lock.json\n--- a/package-lock.json\n+++ b/package-lock.json\n@@ -1,1 +1,200 @@\n{}diff --git a/src/main.rs b/src/main.rs\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,1 +1,1 @@\n-fn main() {{}}\n+fn main() {{ run(); }}\n",
+            lock_body
+        );
+
+        let untouched = truncate_diff(
+            &diff,
+            100_000,
+            &TokenizerType::Cl100kBase,
+            None,
+            TokenCountMode::Exact,
+        );
+        assert_eq!(untouched, diff);
+
+        let truncated = truncate_diff(
+            &diff,
+            200,
+            &TokenizerType::Cl100kBase,
+            None,
+            TokenCountMode::Exact,
+        );
+        assert!(truncated.contains("+++ b/package-lock.json"));
+        assert!(truncated.contains("[... 1 hunk(s), 200 line(s) truncated ...]"));
+        assert!(!truncated.contains("\"version\""));
+        assert!(truncated.contains("+fn main() { run(); }"));
+        assert!(truncated.contains(
+            "Note: diff truncated to fit 200 tokens; hunk bodies omitted for 1 file(s)."
+        ));
+    }
+
+    #[test]
+    fn test_get_git_diff_with_path_patterns() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let repo_path = temp_dir.path();
+        let repo = Repository::init(repo_path).expect("Failed to initialize repository");
+        let signature =
+            Signature::now("Test", "test@example.com").expect("Failed to create signature");
+
+        let stage = |files: &[(&str, &str)]| {
+            let mut index = repo.index().expect("Failed to get repository index");
+            for (name, content) in files {
+                fs::write(repo_path.join(name), content).expect("Failed to write file");
+                index
+                    .add_path(std::path::Path::new(name))
+                    .expect("Failed to add file to index");
+            }
+            index.write().expect("Failed to write index");
+            index.write_tree().expect("Failed to write tree")
+        };
+
+        let tree_id = stage(&[("main.rs", "fn main() {}"), ("package-lock.json", "{}")]);
+        let tree = repo.find_tree(tree_id).expect("Failed to find tree");
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )
+        .expect("Failed to commit");
+
+        stage(&[
+            ("main.rs", "fn main() { run(); }"),
+            ("package-lock.json", "{\"lockfileVersion\": 3}"),
+        ]);
+
+        let options = GitDiffOptions {
+            exclude_patterns: vec!["package-lock.json".to_string()],
+            ..Default::default()
+        };
+        let diff = get_git_diff_with_options(repo_path, &options).expect("Failed to get git diff");
+        assert!(diff.contains("fn main() { run(); }"));
+        assert!(!diff.contains("lockfileVersion"));
+
+        let options = GitDiffOptions {
+            include_patterns: vec!["*.json".to_string()],
+            ..Default::default()
+        };
+        let diff = get_git_diff_with_options(repo_path, &options).expect("Failed to get git diff");
+        assert!(diff.contains("lockfileVersion"));
+        assert!(!diff.contains("run();"));
+    }
+
+    #[test]
+    fn test_get_git_diff_styles() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let repo_path = temp_dir.path();
+        let repo = Repository::init(repo_path).expect("Failed to initialize repository");
+        let signature =
+            Signature::now("Test", "test@example.com").expect("Failed to create signature");
+
+        let stage = |content: &str| {
+            fs::write(repo_path.join("lib.py"), content).expect("Failed to write file");
+            let mut index = repo.index().expect("Failed to get repository index");
+            index
+                .add_path(std::path::Path::new("lib.py"))
+                .expect("Failed to add file to index");
+            index.write().expect("Failed to write index");
+            index.write_tree().expect("Failed to write tree")
+        };
+
+        let body: String = (0..10).map(|i| format!("    x{} = {}\n", i, i)).collect();
+        let tree_id = stage(&format!("def compute():\n{}    return 1\n", body));
+        let tree = repo.find_tree(tree_id).expect("Failed to find tree");
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )
+        .expect("Failed to commit");
+        stage(&format!("def compute():\n{}    return 2\n", body));
+
+        let unified = get_git_diff_with_options(
+            repo_path,
+            &GitDiffOptions {
+                context_lines: Some(0),
+                ..Default::default()
+            },
+        )
+        .expect("Failed to get git diff");
+        assert!(unified.contains("-    return 1\n+    return 2"));
+        assert!(!unified.contains("x9 = 9"));
+
+        if std::process::Command::new("git")
+            .arg("--version")
+            .output()
+            .is_err()
+        {
+            return;
+        }
+
+        let word = get_git_diff_with_options(
+            repo_path,
+            &GitDiffOptions {
+                style: DiffStyle::Word,
+                ..Default::default()
+            },
+        )
+        .expect("Failed to get word diff");
+        assert!(word.contains("return [-1-]{+2+}"));
+
+        let function = get_git_diff_with_options(
+            repo_path,
+            &GitDiffOptions {
+                style: DiffStyle::Function,
+                context_lines: Some(0),
+                ..Default::default()
+            },
+        )
+        .expect("Failed to get function diff");
+        assert!(function.contains(" def compute():"));
+        assert!(function.contains(" x0 = 0"));
+    }
+
+    #[test]
+    fn test_get_merge_conflicts() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let repo_path = temp_dir.path();
+        let mut opts = RepositoryInitOptions::new();
+        opts.initial_head("master");
+        let repo =
+            Repository::init_opts(repo_path, &opts).expect("Failed to initialize repository");
+        let signature =
+            Signature::now("Test", "test@example.com").expect("Failed to create signature");
+
+        let commit = |content: &str, message: &str, parents: &[&git2::Commit]| {
+            fs::write(repo_path.join("config.txt"), content).expect("Failed to write file");
+            let mut index = repo.index().expect("Failed to get repository index");
+            index
+                .add_path(std::path::Path::new("config.txt"))
+                .expect("Failed to add file to index");
+            index.write().expect("Failed to write index");
+            let tree_id = index.write_tree().expect("Failed to write tree");
+            let tree = repo.find_tree(tree_id).expect("Failed to find tree");
+            let oid = repo
+                .commit(None, &signature, &signature, message, &tree, parents)
+                .expect("Failed to commit");
+            repo.find_commit(oid).expect("Failed to find commit")
+        };
+
+        let base = commit("timeout = 10\n", "Initial commit", &[]);
+        let ours = commit("timeout = 20\n", "Raise timeout", &[&base]);
+        let theirs = commit("timeout = 5\n", "Lower timeout", &[&base]);
+        repo.branch("feature", &theirs, false)
+            .expect("Failed to create branch");
+        repo.reference("refs/heads/master", ours.id(), true, "master")
+            .expect("Failed to update master");
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .expect("Failed to checkout");
+        assert!(get_merge_conflicts(repo_path).unwrap().is_none());
+
+        let reference = repo.find_reference("refs/heads/feature").unwrap();
+        let annotated = repo.reference_to_annotated_commit(&reference).unwrap();
+        repo.merge(&[&annotated], None, None)
+            .expect("Failed to merge");
+
+        let conflicts = get_merge_conflicts(repo_path)
+            .expect("Failed to get conflicts")
+            .expect("Expected conflicts");
+        assert_eq!(conflicts.ours_branch, "master");
+        assert_eq!(conflicts.theirs_branch.as_deref(), Some("feature"));
+        assert!(conflicts.ours_commit.ends_with("Raise timeout"));
+        assert_eq!(conflicts.theirs_commits.len(), 1);
+        assert!(conflicts.theirs_commits[0].ends_with("Lower timeout"));
+
+        let file = &conflicts.files[0];
+        assert_eq!(file.path, "config.txt");
+        assert_eq!(file.base.as_deref(), Some("timeout = 10\n"));
+        assert_eq!(file.ours.as_deref(), Some("timeout = 20\n"));
+        assert_eq!(file.theirs.as_deref(), Some("timeout = 5\n"));
+        let merged = file.merged.as_deref().unwrap();
+        assert!(merged.contains("<<<<<<< master"));
+        assert!(merged.contains("||||||| base\ntimeout = 10"));
+        assert!(merged.contains(">>>>>>> feature"));
+    }
+
+    #[test]
+    fn test_get_git_stash_diff_and_untracked_files() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let repo_path = temp_dir.path();
+        let mut opts = RepositoryInitOptions::new();
+        opts.initial_head("master");
+        let mut repo =
+            Repository::init_opts(repo_path, &opts).expect("Failed to initialize repository");
+        let signature =
+            Signature::now("Test", "test@example.com").expect("Failed to create signature");
+
+        fs::write(repo_path.join("app.txt"), "v1\n").expect("Failed to write file");
+        let mut index = repo.index().expect("Failed to get repository index");
+        index
+            .add_path(std::path::Path::new("app.txt"))
+            .expect("Failed to add file to index");
+        index.write().expect("Failed to write index");
+        let tree_id = index.write_tree().expect("Failed to write tree");
+        {
+            let tree = repo.find_tree(tree_id).expect("Failed to find tree");
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Initial commit",
+                &tree,
+                &[],
+            )
+            .expect("Failed to commit");
+        }
+
+        fs::write(repo_path.join("app.txt"), "v2\n").expect("Failed to write file");
+        repo.stash_save(&signature, "try v2", None)
+            .expect("Failed to stash");
+        fs::write(repo_path.join("app.txt"), "v3\n").expect("Failed to write file");
+        repo.stash_save(&signature, "try v3", None)
+            .expect("Failed to stash");
+
+        let options = GitDiffOptions::default();
+        let latest = get_git_stash_diff(repo_path, None, &options).expect("Failed to get stash");
+        assert!(latest.starts_with("stash@{0}: On master: try v3"));
+        assert!(latest.contains("+v3"));
+
+        let older = get_git_stash_diff(repo_path, Some("stash@{1}"), &options)
+            .expect("Failed to get stash");
+        assert!(older.contains("+v2"));
+        let by_message =
+            get_git_stash_diff(repo_path, Some("try v2"), &options).expect("Failed to get stash");
+        assert_eq!(by_message, older);
+        assert!(get_git_stash_diff(repo_path, Some("missing"), &options).is_err());
+
+        fs::create_dir(repo_path.join("notes")).expect("Failed to create dir");
+        fs::write(repo_path.join("notes/todo.md"), "- wip").expect("Failed to write file");
+        let untracked = get_untracked_files(repo_path).expect("Failed to list untracked files");
+        assert_eq!(untracked, vec!["notes/todo.md".to_string()]);
+    }
+
+    #[test]
+    fn test_get_branch_changes() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let repo_path = temp_dir.path();
+        let mut opts = RepositoryInitOptions::new();
+        opts.initial_head("main");
+        let repo =
+            Repository::init_opts(repo_path, &opts).expect("Failed to initialize repository");
+        let signature =
+            Signature::now("Test", "test@example.com").expect("Failed to create signature");
+
+        let commit = |message: &str, files: &[(&str, Option<&str>)]| {
+            let mut index = repo.index().expect("Failed to get repository index");
+            for (name, content) in files {
+                match content {
+                    Some(content) => {
+                        fs::write(repo_path.join(name), content).expect("Failed to write file");
+                        index
+                            .add_path(std::path::Path::new(name))
+                            .expect("Failed to add file to index");
+                    }
+                    None => {
+                        fs::remove_file(repo_path.join(name)).expect("Failed to remove file");
+                        index
+                            .remove_path(std::path::Path::new(name))
+                            .expect("Failed to remove file from index");
+                    }
+                }
+            }
+            index.write().expect("Failed to write index");
+            let tree_id = index.write_tree().expect("Failed to write tree");
+            let tree = repo.find_tree(tree_id).expect("Failed to find tree");
+            let parents: Vec<git2::Commit> = repo
+                .head()
+                .ok()
+                .and_then(|head| head.peel_to_commit().ok())
+                .into_iter()
+                .collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .expect("Failed to commit")
+        };
+
+        let base = commit(
+            "Initial commit",
+            &[
+                ("lib.rs", Some("pub fn a() {}\n")),
+                ("old.txt", Some("old\n")),
+            ],
+        );
+        repo.branch("feature", &repo.find_commit(base).unwrap(), false)
+            .expect("Failed to create branch");
+        repo.set_head("refs/heads/feature")
+            .expect("Failed to set HEAD");
+
+        commit(
+            "Add b",
+            &[("lib.rs", Some("pub fn a() {}\npub fn b() {}\n"))],
+        );
+        commit(
+            "Add notes and drop old file",
+            &[("notes.md", Some("# Notes\n")), ("old.txt", None)],
+        );
+
+        let changes = get_branch_changes(repo_path, "main", "HEAD", &GitDiffOptions::default())
+            .expect("Failed to get branch changes");
+        assert_eq!(
+            changes.commits,
+            vec![
+                "Add b".to_string(),
+                "Add notes and drop old file".to_string()
+            ]
+        );
+        let files: Vec<(&str, &str)> = changes
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("lib.rs", "modified"),
+                ("notes.md", "added"),
+                ("old.txt", "deleted")
+            ]
+        );
+        assert!(changes.diff.contains("+pub fn b() {}"));
+
+        let options = GitDiffOptions {
+            include_patterns: vec!["*.md".to_string()],
+            ..Default::default()
+        };
+        let changes = get_branch_changes(repo_path, "main", "HEAD", &options)
+            .expect("Failed to get branch changes");
+        assert_eq!(changes.files.len(), 1);
+        assert!(!changes.diff.contains("pub fn b"));
+
+        assert!(
+            get_branch_changes(repo_path, "missing", "HEAD", &GitDiffOptions::default()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_get_merged_pull_requests() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let repo_path = temp_dir.path();
+        let repo = Repository::init(repo_path).expect("Failed to initialize repository");
+        let signature =
+            Signature::now("Test", "test@example.com").expect("Failed to create signature");
+
+        let tree_id = repo
+            .index()
+            .unwrap()
+            .write_tree()
+            .expect("Failed to write tree");
+        let tree = repo.find_tree(tree_id).expect("Failed to find tree");
+        let mut parents: Vec<git2::Oid> = Vec::new();
+        let mut commit = |message: &str| {
+            let parent_commits: Vec<git2::Commit> = parents
+                .iter()
+                .map(|id| repo.find_commit(*id).unwrap())
+                .collect();
+            let parent_refs: Vec<&git2::Commit> = parent_commits.iter().collect();
+            let id = repo
+                .commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    message,
+                    &tree,
+                    &parent_refs,
+                )
+                .expect("Failed to commit");
+            parents = vec![id];
+            id
+        };
+
+        let release = commit("Release 1.0 (#1)");
+        repo.tag_lightweight("v1.0.0", &repo.find_object(release, None).unwrap(), false)
+            .expect("Failed to tag");
+        commit("Merge pull request #4 from user/fast-parser\n\nMake the parser faster");
+        commit("Merge pull request #5 from user/no-title");
+        commit("Support TOML output (#7)");
+        commit("Fix a typo");
+
+        let pull_requests = get_merged_pull_requests(repo_path, "v1.0.0", "HEAD")
+            .expect("Failed to get merged pull requests");
+        let found: Vec<(u64, &str)> = pull_requests
+            .iter()
+            .map(|pr| (pr.number, pr.title.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (4, "Make the parser faster"),
+                (5, "user/no-title"),
+                (7, "Support TOML output")
+            ]
+        );
+
+        assert!(get_merged_pull_requests(repo_path, "v9.9.9", "HEAD").is_err());
+    }
+
+    #[test]
+    fn test_get_changed_files_and_diff_only_traversal() {
+        use code2prompt_core::configuration::Code2PromptConfig;
+        use code2prompt_core::path::traverse_directory;
+        use code2prompt_core::session::Code2PromptSession;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let repo_path = temp_dir.path();
+        let repo = Repository::init(repo_path).expect("Failed to initialize repository");
+        let signature =
+            Signature::now("Test", "test@example.com").expect("Failed to create signature");
+
+        fs::create_dir(repo_path.join("src")).unwrap();
+        for name in ["README.md", "src/lib.rs", "src/main.rs", "src/old.rs"] {
+            fs::write(repo_path.join(name), "initial\n").unwrap();
+        }
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+
+        // A staged change, an unstaged change and a staged deletion
+        fs::write(repo_path.join("src/lib.rs"), "staged\n").unwrap();
+        fs::write(repo_path.join("README.md"), "unstaged\n").unwrap();
+        fs::remove_file(repo_path.join("src/old.rs")).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("src/lib.rs")).unwrap();
+        index
+            .remove_path(std::path::Path::new("src/old.rs"))
+            .unwrap();
+        index.write().unwrap();
+
+        // Only the staged changes are rendered, so the unstaged README.md is left out
+        let changed = get_changed_files(repo_path, None, &GitDiffOptions::default()).unwrap();
+        assert_eq!(changed, ["src/lib.rs"]);
+
+        let options = GitDiffOptions {
+            include_patterns: vec!["src/*".to_string()],
+            ..Default::default()
+        };
+        let changed = get_changed_files(repo_path, None, &options).unwrap();
+        assert_eq!(changed, ["src/lib.rs"]);
+
+        // The traversal keeps the changed files, from a subdirectory too
+        let config = Code2PromptConfig::builder()
+            .path(repo_path.to_path_buf())
+            .include_only_changed(true)
+            .build()
+            .unwrap();
+        let (tree, files) = traverse_directory(&config, None).unwrap();
+        let mut paths: Vec<String> = files.into_iter().map(|file| file.path).collect();
+        paths.sort();
+        assert_eq!(paths, ["src/lib.rs"]);
+        assert!(!tree.contains("main.rs"));
+
+        let config = Code2PromptConfig::builder()
+            .path(repo_path.join("src"))
+            .git_dir(Some(repo_path.to_path_buf()))
+            .include_only_changed(true)
+            .build()
+            .unwrap();
+        let (_, files) = traverse_directory(&config, None).unwrap();
+        let paths: Vec<String> = files.into_iter().map(|file| file.path).collect();
+        assert_eq!(paths, ["lib.rs"]);
+
+        // The changed files come with the diff, and narrow the selection of the session
+        let config = Code2PromptConfig::builder()
+            .path(repo_path.to_path_buf())
+            .include_only_changed(true)
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+        assert!(rendered.prompt.contains("+staged"));
+        session.load_changed_files().unwrap();
+        assert!(session.is_file_selected(std::path::Path::new("src/lib.rs")));
+        assert!(!session.is_file_selected(std::path::Path::new("src/main.rs")));
+        session.config.include_only_changed = false;
+        assert!(session.is_file_selected(std::path::Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn test_get_file_history() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let repo_path = temp_dir.path();
+        let repo = Repository::init(repo_path).expect("Failed to initialize repository");
+        let signature =
+            Signature::now("Alice", "alice@example.com").expect("Failed to create signature");
+
+        assert!(
+            get_file_history(repo_path, "a.rs", 5, &GitDiffOptions::default())
+                .unwrap()
+                .is_empty()
+        );
+
+        let commit = |message: &str, name: &str, content: &str| {
+            fs::write(repo_path.join(name), content).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(std::path::Path::new(name)).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents: Vec<git2::Commit> = repo
+                .head()
+                .ok()
+                .and_then(|head| head.peel_to_commit().ok())
+                .into_iter()
+                .collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap();
+        };
+        commit("Add a", "a.rs", "fn a() {}\n");
+        commit("Add b", "b.rs", "fn b() {}\n");
+        commit(
+            "Make a public\n\nIt is used by b.",
+            "a.rs",
+            "pub fn a() {}\n",
+        );
+
+        let history = get_file_history(repo_path, "a.rs", 5, &GitDiffOptions::default()).unwrap();
+        let messages: Vec<&str> = history.iter().map(|c| c.message.as_str()).collect();
+        assert_eq!(messages, ["Make a public\n\nIt is used by b.", "Add a"]);
+        assert_eq!(history[0].author, "Alice");
+        assert_eq!(history[0].hash.len(), 7);
+        assert_eq!(history[0].date.len(), 10);
+        assert!(history[0].diff.contains("-fn a() {}"));
+        assert!(history[0].diff.contains("+pub fn a() {}"));
+        assert!(!history[0].diff.contains("b.rs"));
+
+        let history = get_file_history(repo_path, "a.rs", 1, &GitDiffOptions::default()).unwrap();
+        assert_eq!(history.len(), 1);
+    }
+
+    #[test]
+    fn test_get_version_info() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let repo_path = temp_dir.path();
+        let repo = Repository::init(repo_path).expect("Failed to initialize repository");
+        let signature =
+            Signature::now("Test", "test@example.com").expect("Failed to create signature");
+
+        let mut commits = Vec::new();
+        for (index, seconds) in [1_000_000, 2_000_000, 3_000_000, 4_000_000]
+            .into_iter()
+            .enumerate()
+        {
+            fs::write(repo_path.join("file.txt"), index.to_string()).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(std::path::Path::new("file.txt")).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let time =
+                Signature::new("Test", "test@example.com", &git2::Time::new(seconds, 0)).unwrap();
+            let parents: Vec<git2::Commit> = commits
+                .last()
+                .map(|id| repo.find_commit(*id).unwrap())
+                .into_iter()
+                .collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            let id = repo
+                .commit(Some("HEAD"), &time, &time, "Commit", &tree, &parents)
+                .unwrap();
+            commits.push(id);
+        }
+
+        // No tag yet
+        let info = get_version_info(repo_path, "HEAD").unwrap();
+        assert_eq!(info.commit, commits[3].to_string()[..7]);
+        assert_eq!(info.nearest_tag, None);
+        assert!(info.latest_tags.is_empty());
+
+        let object = |index: usize| repo.find_object(commits[index], None).unwrap();
+        repo.tag_lightweight("v1.0.0", &object(0), false).unwrap();
+        repo.tag(
+            "v1.1.0-rc",
+            &object(1),
+            &signature,
+            "Release candidate",
+            false,
+        )
+        .unwrap();
+
+        let info = get_version_info(repo_path, "HEAD").unwrap();
+        assert_eq!(info.tag, None);
+        assert_eq!(info.nearest_tag.as_deref(), Some("v1.1.0-rc"));
+        assert_eq!(info.distance, Some(2));
+        assert_eq!(
+            info.describe,
+            Some(format!("v1.1.0-rc-2-g{}", &commits[3].to_string()[..7]))
+        );
+        assert_eq!(info.latest_tags, ["v1.1.0-rc", "v1.0.0"]);
+
+        let info = get_version_info(repo_path, "v1.0.0").unwrap();
+        assert_eq!(info.tag.as_deref(), Some("v1.0.0"));
+        assert_eq!(info.distance, Some(0));
+        assert_eq!(info.describe.as_deref(), Some("v1.0.0"));
+
+        assert!(get_version_info(&repo_path.join("missing"), "HEAD").is_err());
     }
 }
@@ -0,0 +1,94 @@
+//! Tests for reading the codebase from the tree of a git revision
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::git_tree::{repository_name, traverse_git_tree};
+use code2prompt_core::session::Code2PromptSession;
+use git2::{Repository, Signature};
+use std::path::Path;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a bare repository with a single commit on `main`
+    fn bare_repo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init_bare(dir.path().join("project.git")).unwrap();
+
+        let mut src = repo.treebuilder(None).unwrap();
+        let main_rs = repo.blob(b"fn main() {}\n").unwrap();
+        src.insert("main.rs", main_rs, 0o100644).unwrap();
+        let src_id = src.write().unwrap();
+
+        let mut root = repo.treebuilder(None).unwrap();
+        let readme = repo.blob(b"# Project\n").unwrap();
+        let hidden = repo.blob(b"secret\n").unwrap();
+        let binary = repo.blob(&[0u8, 159, 146, 150, 0, 1]).unwrap();
+        root.insert("README.md", readme, 0o100644).unwrap();
+        root.insert(".env", hidden, 0o100644).unwrap();
+        root.insert("logo.bin", binary, 0o100644).unwrap();
+        root.insert("src", src_id, 0o040000).unwrap();
+        let tree = repo.find_tree(root.write().unwrap()).unwrap();
+
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(
+            Some("refs/heads/main"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_traverse_git_tree() {
+        let dir = bare_repo();
+        let repo_path = dir.path().join("project.git");
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .exclude_patterns(vec!["README.md".to_string()])
+            .build()
+            .unwrap();
+
+        let (tree, files) = traverse_git_tree(&config, &repo_path, "main", None).unwrap();
+        let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+
+        assert_eq!(paths, vec!["src/main.rs"]);
+        assert!(files[0].code.contains("fn main() {}"));
+        assert!(tree.starts_with("project"));
+        assert!(tree.contains("main.rs"));
+        assert!(!tree.contains(".env"));
+        assert!(traverse_git_tree(&config, &repo_path, "missing", None).is_err());
+    }
+
+    #[test]
+    fn test_session_with_bare_git_dir() {
+        let dir = bare_repo();
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .git_dir(Some(dir.path().join("project.git")))
+            .git_rev(Some("main".to_string()))
+            .build()
+            .unwrap();
+
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+
+        assert_eq!(rendered.files, vec!["README.md", "src/main.rs"]);
+        assert!(rendered.prompt.contains("Project Path: project"));
+        assert!(rendered.prompt.contains("# Project"));
+    }
+
+    #[test]
+    fn test_repository_name() {
+        assert_eq!(
+            repository_name(Path::new("/srv/git/project.git")),
+            "project"
+        );
+        assert_eq!(repository_name(Path::new("/srv/git/project")), "project");
+    }
+}
@@ -0,0 +1,130 @@
+//! # Inclusion Plan Tests
+//!
+//! Tests for the explanation of how each file of a codebase is included, and its
+//! quickfix output.
+
+use code2prompt_core::{
+    configuration::Code2PromptConfig,
+    inclusion_plan::{PlanReason, format_quickfix},
+    selection::FileInclusion,
+    session::Code2PromptSession,
+};
+use rstest::*;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+#[fixture]
+fn codebase() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let files: [(&str, &[u8]); 9] = [
+        ("src/main.rs", b"fn main() {}\n"),
+        ("src/lib.rs", b"pub fn lib() {}\n"),
+        ("src/generated/api.rs", b"// generated\n"),
+        ("docs/guide.md", b"# Guide\n"),
+        ("build/out.txt", b"output\n"),
+        ("build/more/out.txt", b"output\n"),
+        (".c2pignore", b"build/\n"),
+        ("logo.png", &[0x89, 0x50, 0x00, 0x00, 0x01]),
+        ("empty.txt", b""),
+    ];
+    for (path, content) in files {
+        let full_path = dir.path().join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(full_path, content).unwrap();
+    }
+    dir
+}
+
+fn session(dir: &TempDir) -> Code2PromptSession {
+    let config = Code2PromptConfig::builder()
+        .path(dir.path().to_path_buf())
+        .exclude_patterns(vec!["src/generated/**".to_string()])
+        .tree_only_patterns(vec!["docs/**".to_string()])
+        .build()
+        .unwrap();
+    Code2PromptSession::new(config)
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn test_plan_reasons(codebase: TempDir) {
+        let mut session = session(&codebase);
+        session.deselect_file(PathBuf::from("src/lib.rs"));
+        let plan = session.inclusion_plan().unwrap();
+
+        let entries: Vec<(&str, FileInclusion, PlanReason)> = plan
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry.inclusion, entry.reason))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (".c2pignore", FileInclusion::Excluded, PlanReason::Ignored),
+                ("build/", FileInclusion::Excluded, PlanReason::Ignored),
+                (
+                    "docs/guide.md",
+                    FileInclusion::TreeOnly,
+                    PlanReason::TreeOnlyPattern
+                ),
+                ("empty.txt", FileInclusion::Full, PlanReason::Patterns),
+                ("logo.png", FileInclusion::Full, PlanReason::Patterns),
+                (
+                    "src/generated/api.rs",
+                    FileInclusion::Excluded,
+                    PlanReason::ExcludePattern
+                ),
+                (
+                    "src/lib.rs",
+                    FileInclusion::Excluded,
+                    PlanReason::UserExcluded
+                ),
+                ("src/main.rs", FileInclusion::Full, PlanReason::Patterns),
+            ]
+        );
+
+        let warning = |path: &str| {
+            plan.iter()
+                .find(|entry| entry.path == path)
+                .and_then(|entry| entry.warning.clone())
+        };
+        assert_eq!(warning("empty.txt").as_deref(), Some("empty, left out"));
+        assert_eq!(warning("logo.png").as_deref(), Some("binary, left out"));
+        assert_eq!(warning("src/main.rs"), None);
+    }
+
+    #[rstest]
+    fn test_quickfix_lines(codebase: TempDir) {
+        let plan = session(&codebase).inclusion_plan().unwrap();
+        let root = codebase.path();
+        let quickfix = format_quickfix(&plan, root);
+
+        let lines: Vec<&str> = quickfix.lines().collect();
+        assert_eq!(lines.len(), plan.len() + 2);
+        assert!(
+            lines.contains(
+                &format!(
+                    "{}:1:1: excluded: matched by an exclude pattern",
+                    root.join("src/generated/api.rs").display()
+                )
+                .as_str()
+            )
+        );
+        assert!(
+            lines.contains(
+                &format!(
+                    "{}:1:1: warning: binary, left out",
+                    root.join("logo.png").display()
+                )
+                .as_str()
+            )
+        );
+    }
+}
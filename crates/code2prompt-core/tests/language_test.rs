@@ -0,0 +1,135 @@
+//! # Language Metadata Tests
+//!
+//! Tests for the description of the files given to the templates: their language, their
+//! blank and comment lines, and whether they are tests.
+
+use code2prompt_core::{
+    configuration::Code2PromptConfig,
+    language::{is_test_path, source_stats},
+    session::Code2PromptSession,
+};
+use rstest::*;
+use std::fs;
+use std::path::Path;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+const RUST_CODE: &str = "//! Crate docs\n\n/* A block\n   comment */\nfn main() { /* inline */\n    let x = 1; // trailing\n\n    /* one */ /* two */\n}\n";
+
+/// Creates a project with a source file and its test
+#[fixture]
+fn project() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::create_dir_all(dir.path().join("tests")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), RUST_CODE).unwrap();
+    fs::write(
+        dir.path().join("tests/cli_test.rs"),
+        "#[test]\nfn runs() {}\n",
+    )
+    .unwrap();
+    dir
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn test_rust_lines() {
+        let stats = source_stats(Path::new("src/main.rs"), RUST_CODE);
+        assert_eq!(stats.language, "Rust");
+        assert_eq!(stats.line_count, 9);
+        assert_eq!(stats.blank_lines, 2);
+        // The doc comment, the block comment and the line of two block comments
+        assert_eq!(stats.comment_lines, 4);
+        assert_eq!(stats.code_lines(), 3);
+        assert!(!stats.is_test);
+    }
+
+    #[rstest]
+    fn test_python_lines() {
+        let code = "# Module\nimport os\n\n    # indented\nx = '#'\n";
+        let stats = source_stats(Path::new("app/main.py"), code);
+        assert_eq!(stats.language, "Python");
+        assert_eq!(stats.comment_lines, 2);
+        assert_eq!(stats.blank_lines, 1);
+    }
+
+    #[rstest]
+    fn test_markup_comment_spanning_lines() {
+        let code = "<!-- Title\n\n-->\n<h1>Hi</h1> <!-- trailing -->\n";
+        let stats = source_stats(Path::new("index.html"), code);
+        assert_eq!(stats.language, "HTML");
+        assert_eq!(stats.comment_lines, 3);
+        assert_eq!(stats.blank_lines, 0);
+    }
+
+    #[rstest]
+    #[case("notes.rst", "rst")]
+    #[case("Makefile", "Text")]
+    #[case("web/App.TSX", "TypeScript")]
+    fn test_language_names(#[case] path: &str, #[case] expected: &str) {
+        assert_eq!(source_stats(Path::new(path), "").language, expected);
+    }
+
+    #[rstest]
+    #[case("tests/cli.rs", true)]
+    #[case("src/__tests__/app.js", true)]
+    #[case("test_utils.py", true)]
+    #[case("pkg/server_test.go", true)]
+    #[case("src/button.spec.ts", true)]
+    #[case("src/main/java/UserServiceTest.java", true)]
+    #[case("conftest.py", true)]
+    #[case("src/latest.rs", false)]
+    #[case("src/Test.java", false)]
+    #[case("src/testing.rs", false)]
+    fn test_test_paths(#[case] path: &str, #[case] expected: bool) {
+        assert_eq!(is_test_path(path), expected);
+    }
+
+    #[rstest]
+    fn test_files_carry_stats(project: TempDir) {
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.load_codebase().unwrap();
+
+        let files = session.data.files.as_ref().unwrap();
+        let main = files
+            .iter()
+            .find(|file| file.path == "src/main.rs")
+            .unwrap();
+        assert_eq!(main.stats.language, "Rust");
+        assert_eq!(main.stats.line_count, 9);
+        assert!(!main.stats.is_test);
+        let test = files
+            .iter()
+            .find(|file| file.path == "tests/cli_test.rs")
+            .unwrap();
+        assert!(test.stats.is_test);
+    }
+
+    #[rstest]
+    fn test_template_fields(project: TempDir) {
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .template_str(
+                "{{#each files}}{{path}}: {{language}}, {{line_count}} lines{{#if is_test}} (test){{/if}}\n{{/each}}",
+            )
+            .build()
+            .unwrap();
+        let prompt = Code2PromptSession::new(config)
+            .generate_prompt()
+            .unwrap()
+            .prompt;
+
+        assert!(prompt.contains("src/main.rs: Rust, 9 lines\n"));
+        assert!(prompt.contains("tests/cli_test.rs: Rust, 2 lines (test)"));
+    }
+}
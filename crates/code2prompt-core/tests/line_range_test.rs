@@ -0,0 +1,78 @@
+//! # Line Range Tests
+//!
+//! Tests for the parsing of line ranges and the selection of the lines of a file.
+
+use code2prompt_core::line_range::{
+    LineRange, format_ranges, pattern_line_ranges, select_lines, split_range_pattern,
+};
+use std::path::Path;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_range_pattern() {
+        let (path, range) = split_range_pattern("src/lib.rs:100-250");
+        assert_eq!(path, "src/lib.rs");
+        assert_eq!(range, Some(LineRange::new(100, 250).unwrap()));
+
+        // Patterns without a valid range are kept whole
+        assert_eq!(split_range_pattern("src/**/*.rs"), ("src/**/*.rs", None));
+        assert_eq!(split_range_pattern("notes:draft"), ("notes:draft", None));
+        assert_eq!(
+            split_range_pattern("src/lib.rs:9-3"),
+            ("src/lib.rs:9-3", None)
+        );
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        assert!("0-5".parse::<LineRange>().is_err());
+        assert!("12".parse::<LineRange>().is_err());
+        assert!("a-b".parse::<LineRange>().is_err());
+        assert_eq!(
+            "3 - 7".parse::<LineRange>().unwrap(),
+            LineRange::new(3, 7).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pattern_line_ranges() {
+        let patterns = vec![
+            "./src/lib.rs:30-40".to_string(),
+            "src/lib.rs:1-10".to_string(),
+            "src/main.rs:5-6".to_string(),
+            "src/*.rs".to_string(),
+        ];
+        let ranges = pattern_line_ranges(&patterns, Path::new("src/lib.rs"));
+        assert_eq!(format_ranges(&ranges), "1-10, 30-40");
+        assert!(pattern_line_ranges(&patterns, Path::new("src/other.rs")).is_empty());
+    }
+
+    #[test]
+    fn test_select_lines() {
+        let code = (1..=10)
+            .map(|n| format!("line {}", n))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let ranges = vec![
+            LineRange::new(2, 3).unwrap(),
+            LineRange::new(6, 20).unwrap(),
+        ];
+
+        assert_eq!(
+            select_lines(&code, &ranges, false),
+            "line 2\nline 3\n...\nline 6\nline 7\nline 8\nline 9\nline 10"
+        );
+        let numbered = select_lines(&code, &ranges[..1], true);
+        assert_eq!(numbered, "   2 | line 2\n   3 | line 3");
+    }
+
+    #[test]
+    fn test_select_lines_overlapping_ranges() {
+        let code = "a\nb\nc\nd\ne";
+        let ranges = vec![LineRange::new(1, 3).unwrap(), LineRange::new(2, 4).unwrap()];
+        assert_eq!(select_lines(code, &ranges, false), "a\nb\nc\nd");
+    }
+}
@@ -0,0 +1,235 @@
+//! # Language Server Tests
+//!
+//! Tests for the language server symbol source: the framing of the messages, the
+//! compression of the code from the reported symbols, and the fallback to the heuristic
+//! compression when the server cannot be started.
+
+use code2prompt_core::{
+    compression::CompressionLevel,
+    configuration::Code2PromptConfig,
+    lsp::{
+        LspClient, LspPosition, LspRange, LspSymbol, compress_with_symbols, encode_message,
+        file_uri, language_id, read_message,
+    },
+    session::Code2PromptSession,
+};
+use rstest::*;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+const RUST_CODE: &str = "/// Adds\nfn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nstruct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn new() -> Self {\n        Point { x: 0 }\n    }\n}\n";
+
+const PYTHON_CODE: &str =
+    "def greet(name):\n    \"\"\"Greets\"\"\"\n    print(name)\n    return name\n\nVALUE = 1\n";
+
+fn range(start: (usize, usize), end: (usize, usize)) -> LspRange {
+    LspRange {
+        start: LspPosition {
+            line: start.0,
+            character: start.1,
+        },
+        end: LspPosition {
+            line: end.0,
+            character: end.1,
+        },
+    }
+}
+
+fn symbol(
+    name: &str,
+    kind: u32,
+    whole: LspRange,
+    selection: LspRange,
+    children: Vec<LspSymbol>,
+) -> LspSymbol {
+    LspSymbol {
+        name: name.to_string(),
+        kind,
+        range: whole,
+        selection_range: selection,
+        children,
+    }
+}
+
+/// The symbols a server reports for `RUST_CODE`
+fn rust_symbols() -> Vec<LspSymbol> {
+    vec![
+        symbol(
+            "add",
+            12,
+            range((0, 0), (3, 1)),
+            range((1, 3), (1, 6)),
+            vec![],
+        ),
+        symbol(
+            "Point",
+            23,
+            range((5, 0), (7, 1)),
+            range((5, 7), (5, 12)),
+            vec![symbol(
+                "x",
+                8,
+                range((6, 4), (6, 10)),
+                range((6, 4), (6, 5)),
+                vec![],
+            )],
+        ),
+        symbol(
+            "impl Point",
+            19,
+            range((9, 0), (13, 1)),
+            range((9, 5), (9, 10)),
+            vec![symbol(
+                "new",
+                6,
+                range((10, 4), (12, 5)),
+                range((10, 7), (10, 10)),
+                vec![],
+            )],
+        ),
+    ]
+}
+
+/// Creates a Rust project compressed to its signatures
+#[fixture]
+fn project() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    fs::write(dir.path().join("lib.rs"), RUST_CODE).unwrap();
+    dir
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn test_message_round_trip() {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "result": "héllo" });
+        let mut stream = encode_message(&message);
+        stream.extend(encode_message(
+            &json!({ "jsonrpc": "2.0", "method": "exit" }),
+        ));
+        let mut reader = Cursor::new(stream);
+
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(
+            read_message(&mut reader).unwrap().unwrap()["method"],
+            "exit"
+        );
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[rstest]
+    fn test_message_without_length() {
+        let mut reader = Cursor::new(b"Content-Type: json\r\n\r\n{}".to_vec());
+        assert!(read_message(&mut reader).is_err());
+    }
+
+    #[rstest]
+    fn test_compress_functions_and_methods() {
+        let compressed = compress_with_symbols(RUST_CODE, &rust_symbols());
+        assert_eq!(
+            compressed,
+            "/// Adds\nfn add(a: i32, b: i32) -> i32 { ... }\n\nstruct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn new() -> Self { ... }\n}\n"
+        );
+    }
+
+    #[rstest]
+    fn test_compress_indented_body() {
+        let symbols = vec![symbol(
+            "greet",
+            12,
+            range((0, 0), (3, 15)),
+            range((0, 4), (0, 9)),
+            vec![],
+        )];
+        assert_eq!(
+            compress_with_symbols(PYTHON_CODE, &symbols),
+            "def greet(name):\n    ...\n\nVALUE = 1\n"
+        );
+    }
+
+    #[rstest]
+    fn test_compress_utf16_positions() {
+        // The emoji takes two UTF-16 code units and four bytes
+        let code = "fn a() { \"😀\" }\nfn b() { 1 }\n";
+        let symbols = vec![
+            symbol(
+                "a",
+                12,
+                range((0, 0), (0, 15)),
+                range((0, 3), (0, 4)),
+                vec![],
+            ),
+            symbol(
+                "b",
+                12,
+                range((1, 0), (1, 12)),
+                range((1, 3), (1, 4)),
+                vec![],
+            ),
+        ];
+        assert_eq!(
+            compress_with_symbols(code, &symbols),
+            "fn a() { ... }\nfn b() { ... }\n"
+        );
+    }
+
+    #[rstest]
+    fn test_compress_without_symbols() {
+        assert_eq!(compress_with_symbols(RUST_CODE, &[]), RUST_CODE);
+    }
+
+    #[rstest]
+    #[case("rs", "rust")]
+    #[case("tsx", "typescriptreact")]
+    #[case("PY", "python")]
+    #[case("zig", "zig")]
+    fn test_language_id(#[case] extension: &str, #[case] expected: &str) {
+        assert_eq!(language_id(extension), expected);
+    }
+
+    #[rstest]
+    fn test_file_uri() {
+        assert_eq!(
+            file_uri(Path::new("/src/my lib.rs")),
+            "file:///src/my%20lib.rs"
+        );
+    }
+
+    #[rstest]
+    fn test_missing_server(project: TempDir) {
+        let error = LspClient::start("code2prompt-missing-language-server", project.path())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("code2prompt-missing-language-server"));
+    }
+
+    #[rstest]
+    fn test_missing_server_keeps_heuristic(project: TempDir) {
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .compression_level(CompressionLevel::Signatures)
+            .lsp_servers(HashMap::from([(
+                "rs".to_string(),
+                "code2prompt-missing-language-server".to_string(),
+            )]))
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.load_codebase().unwrap();
+
+        assert!(session.apply_lsp_signatures().unwrap().is_empty());
+        let code = &session.data.files.as_ref().unwrap()[0].code;
+        assert!(code.contains("fn add(a: i32, b: i32) -> i32"));
+        assert!(!code.contains("a + b"));
+    }
+}
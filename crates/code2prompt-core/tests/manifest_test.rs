@@ -0,0 +1,71 @@
+//! Integration tests for stable content addressing and the file manifest
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::manifest::FileManifest;
+use code2prompt_core::session::Code2PromptSession;
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_for(project: &TempDir, manifest_file: &std::path::Path) -> Code2PromptSession {
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .manifest_file(Some(manifest_file.to_path_buf()))
+            .build()
+            .unwrap();
+        Code2PromptSession::new(config)
+    }
+
+    #[test]
+    fn test_content_hash_is_stable_across_runs() {
+        let project = TempDir::new().unwrap();
+        fs::write(project.path().join("a.rs"), "fn a() {}").unwrap();
+
+        let hash = |line_numbers: bool| {
+            let config = Code2PromptConfig::builder()
+                .path(project.path().to_path_buf())
+                .line_numbers(line_numbers)
+                .build()
+                .unwrap();
+            let mut session = Code2PromptSession::new(config);
+            session.load_codebase().unwrap();
+            session.data.files.unwrap()[0].content_hash.clone()
+        };
+
+        // The hash addresses the file content, not its rendering
+        assert_eq!(hash(false), hash(true));
+        assert_eq!(hash(false).len(), 16);
+    }
+
+    #[test]
+    fn test_manifest_tracks_changes_between_runs() {
+        let project = TempDir::new().unwrap();
+        let state_dir = TempDir::new().unwrap();
+        let manifest_file = state_dir.path().join("manifest.json");
+        fs::write(project.path().join("a.rs"), "fn a() {}").unwrap();
+        fs::write(project.path().join("b.rs"), "fn b() {}").unwrap();
+
+        let mut first = session_for(&project, &manifest_file);
+        first.generate_prompt().unwrap();
+        let changes = first.data.manifest_changes.unwrap();
+        assert_eq!(changes.added, vec!["a.rs", "b.rs"]);
+
+        fs::write(project.path().join("b.rs"), "fn b() -> u8 { 1 }").unwrap();
+        fs::write(project.path().join("c.rs"), "fn c() {}").unwrap();
+        fs::remove_file(project.path().join("a.rs")).unwrap();
+
+        let mut second = session_for(&project, &manifest_file);
+        second.generate_prompt().unwrap();
+        let changes = second.data.manifest_changes.clone().unwrap();
+        assert_eq!(changes.changed, vec!["b.rs"]);
+        assert_eq!(changes.added, vec!["c.rs"]);
+        assert_eq!(changes.removed, vec!["a.rs"]);
+        assert!(changes.unchanged.is_empty());
+
+        let manifest = FileManifest::load(&manifest_file).unwrap();
+        assert_eq!(manifest, second.manifest());
+    }
+}
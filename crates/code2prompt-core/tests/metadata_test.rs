@@ -0,0 +1,191 @@
+//! # Metadata Module Tests
+//!
+//! Tests for the export of the file metadata: the owners read from `CODEOWNERS`, the last
+//! commit of each file, and the CSV and Parquet files.
+
+#[cfg(feature = "parquet")]
+use code2prompt_core::parquet::{Column, write_parquet};
+use code2prompt_core::{
+    configuration::Code2PromptConfig,
+    metadata::{CodeOwners, MetadataFormat, collect_file_metadata, write_file_metadata},
+    session::Code2PromptSession,
+};
+use git2::{Repository, Signature};
+#[cfg(feature = "parquet")]
+use parquet::file::reader::{FileReader, SerializedFileReader};
+#[cfg(feature = "parquet")]
+use parquet::record::Field;
+use rstest::*;
+use std::fs;
+use std::path::Path;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+/// A repository with a `CODEOWNERS` file, a committed file and an uncommitted one
+#[fixture]
+fn repo() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let files = [
+        (
+            ".github/CODEOWNERS",
+            "* @everyone\n/src/ @core # core team\n",
+        ),
+        ("src/lib.rs", "pub fn lib() {}\n"),
+        ("README.md", "# Project\n"),
+    ];
+    for (path, content) in files {
+        let full_path = dir.path().join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(full_path, content).unwrap();
+    }
+
+    let repo = Repository::init(dir.path()).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("src/lib.rs")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Ada", "ada@example.com").unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "Add lib", &tree, &[])
+        .unwrap();
+    dir
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codeowners_rules() {
+        let owners = CodeOwners::parse(
+            "# Owners\n\
+             * @everyone\n\
+             *.js @web\n\
+             /docs/* @writers\n\
+             apps/ @apps\n\
+             /build/logs/ @ops\n\
+             /vendor/\n",
+        );
+        assert_eq!(owners.owners_of("Cargo.toml"), Some("@everyone"));
+        assert_eq!(owners.owners_of("web/src/app.js"), Some("@web"));
+        assert_eq!(owners.owners_of("docs/intro.md"), Some("@writers"));
+        assert_eq!(owners.owners_of("docs/guides/setup.md"), Some("@everyone"));
+        assert_eq!(owners.owners_of("services/apps/main.rs"), Some("@apps"));
+        assert_eq!(owners.owners_of("build/logs/today.log"), Some("@ops"));
+        assert_eq!(owners.owners_of("vendor/lib.rs"), None);
+        assert_eq!(CodeOwners::default().owners_of("main.rs"), None);
+    }
+
+    #[rstest]
+    fn test_collect_file_metadata(repo: TempDir) {
+        let config = Code2PromptConfig::builder()
+            .path(repo.path().to_path_buf())
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.load_codebase().unwrap();
+        let files = session.data.files.as_deref().unwrap();
+        let rows = collect_file_metadata(files, &session.config);
+
+        let lib = rows.iter().find(|row| row.path == "src/lib.rs").unwrap();
+        assert_eq!(lib.language, "rs");
+        assert_eq!(lib.size_bytes, 16);
+        assert!(lib.tokens > 0);
+        assert_eq!(lib.owners.as_deref(), Some("@core"));
+        assert_eq!(lib.last_commit_author.as_deref(), Some("Ada"));
+        assert_eq!(lib.last_commit_date.as_ref().map(String::len), Some(10));
+        assert!(lib.last_commit_timestamp.is_some());
+
+        let readme = rows.iter().find(|row| row.path == "README.md").unwrap();
+        assert_eq!(readme.owners.as_deref(), Some("@everyone"));
+        assert_eq!(readme.last_commit_date, None);
+    }
+
+    #[rstest]
+    fn test_write_csv(repo: TempDir) {
+        let config = Code2PromptConfig::builder()
+            .path(repo.path().to_path_buf())
+            .include_patterns(vec!["src/**".to_string()])
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.load_codebase().unwrap();
+        let rows = collect_file_metadata(session.data.files.as_deref().unwrap(), &session.config);
+
+        let output = repo.path().join("files.csv");
+        write_file_metadata(&rows, &output).unwrap();
+        let csv = fs::read_to_string(&output).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "path,language,size_bytes,tokens,owners,last_commit_date,\
+             last_commit_timestamp,last_commit_author"
+        );
+        assert!(lines[1].starts_with("src/lib.rs,rs,16,"));
+        assert!(lines[1].ends_with(",Ada"));
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn test_format_from_extension() {
+        assert_eq!(
+            MetadataFormat::from_path(Path::new("files.CSV")).unwrap(),
+            MetadataFormat::Csv
+        );
+        #[cfg(feature = "parquet")]
+        assert_eq!(
+            MetadataFormat::from_path(Path::new("out/files.parquet")).unwrap(),
+            MetadataFormat::Parquet
+        );
+        #[cfg(not(feature = "parquet"))]
+        assert!(MetadataFormat::from_path(Path::new("out/files.parquet")).is_err());
+        assert!(MetadataFormat::from_path(Path::new("files.json")).is_err());
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_round_trip() {
+        let columns = [
+            Column::strings(
+                "path",
+                vec![Some("a.rs".to_string()), Some("b.rs".to_string())],
+            ),
+            Column::integers("tokens", vec![Some(3), None]),
+        ];
+        assert!(columns[1].optional);
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("files.parquet");
+        write_parquet(&columns, fs::File::create(&path).unwrap()).unwrap();
+
+        let reader = SerializedFileReader::new(fs::File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 2);
+        assert!(metadata.created_by().unwrap().starts_with("code2prompt"));
+        let rows: Vec<Vec<(String, Field)>> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().into_columns())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    ("path".to_string(), Field::Str("a.rs".to_string())),
+                    ("tokens".to_string(), Field::Long(3)),
+                ],
+                vec![
+                    ("path".to_string(), Field::Str("b.rs".to_string())),
+                    ("tokens".to_string(), Field::Null),
+                ],
+            ]
+        );
+
+        let uneven = [
+            Column::integers("a", vec![Some(1)]),
+            Column::integers("b", vec![]),
+        ];
+        assert!(write_parquet(&uneven, Vec::new()).is_err());
+    }
+}
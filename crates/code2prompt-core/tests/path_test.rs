@@ -5,11 +5,12 @@
 
 use code2prompt_core::{
     configuration::Code2PromptConfig,
-    path::{EntryMetadata, FileEntry, traverse_directory},
+    path::{EntryMetadata, FileEntry, SymlinkPolicy, traverse_directory},
 };
 use git2::Repository;
 use rstest::*;
 use std::{
+    collections::HashMap,
     fs::{self},
     path::Path,
 };
@@ -146,6 +147,34 @@ mod tests {
         assert!(file_exists(&files, "target/debug/app"));
     }
 
+    #[rstest]
+    fn test_respects_nested_c2pignore(simple_dir_structure: TempDir) {
+        let root = simple_dir_structure.path();
+        fs::write(root.join(".c2pignore"), "file1.txt\n").unwrap();
+        // Relative to its own directory, and combined with the exclude patterns
+        fs::write(root.join("subdir/.c2pignore"), "/nested/\n").unwrap();
+        fs::write(root.join("subdir/file4.txt"), "Content 4").unwrap();
+
+        let config = Code2PromptConfig::builder()
+            .path(root.to_path_buf())
+            .exclude_patterns(vec!["**/file4.txt".to_string()])
+            .build()
+            .unwrap();
+        let (tree_str, files) = traverse_directory(&config, None).unwrap();
+
+        assert_eq!(files.len(), 1);
+        assert!(file_exists(&files, "subdir/file2.txt"));
+        assert!(!tree_str.contains("nested"));
+
+        let config = Code2PromptConfig::builder()
+            .path(root.to_path_buf())
+            .no_ignore(true)
+            .build()
+            .unwrap();
+        let (_, files) = traverse_directory(&config, None).unwrap();
+        assert_eq!(files.len(), 4);
+    }
+
     // ~~~ Hidden Files Tests ~~~
     #[rstest]
     fn test_excludes_hidden_files_by_default(simple_dir_structure: TempDir) {
@@ -199,6 +228,24 @@ mod tests {
         }
     }
 
+    #[rstest]
+    fn test_fence_languages(simple_dir_structure: TempDir) {
+        fs::write(simple_dir_structure.path().join("main.TF"), "resource {}").unwrap();
+        let config = Code2PromptConfig::builder()
+            .path(simple_dir_structure.path().to_path_buf())
+            .fence_languages(HashMap::from([("tf".to_string(), "hcl".to_string())]))
+            .build()
+            .unwrap();
+
+        let (_, files) = traverse_directory(&config, None).unwrap();
+
+        // Unmapped extensions keep their own tag, mapped ones match regardless of case
+        let file1 = files.iter().find(|f| f.path.contains("file1.txt")).unwrap();
+        assert!(file1.code.starts_with("```txt\n"));
+        let main = files.iter().find(|f| f.path.contains("main.TF")).unwrap();
+        assert!(main.code.starts_with("```hcl\n"));
+    }
+
     // ~~~ Metadata Tests ~~~
 
     #[rstest]
@@ -245,30 +292,91 @@ mod tests {
         );
     }
 
+    #[rstest]
+    fn test_path_alias(simple_dir_structure: TempDir) {
+        let config = Code2PromptConfig::builder()
+            .path(simple_dir_structure.path().to_path_buf())
+            .path_alias(Some("{repo}/".to_string()))
+            .absolute_path(true)
+            .build()
+            .unwrap();
+
+        let (tree, files) = traverse_directory(&config, None).unwrap();
+
+        // The alias takes precedence over absolute paths and names the root of the tree
+        let name = simple_dir_structure
+            .path()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let prefix = format!("{}/", name);
+        assert!(files.iter().all(|file| file.path.starts_with(&prefix)));
+        assert!(
+            files
+                .iter()
+                .any(|file| file.path == format!("{}file1.txt", prefix))
+        );
+        assert!(tree.starts_with(&format!("{}\n", name)));
+        assert_eq!(
+            config.root_relative_path(&format!("{}subdir/file2.txt", prefix)),
+            Path::new("subdir/file2.txt")
+        );
+    }
+
     // ~~~ Symlink Tests ~~~
 
-    // #[rstest]
-    // #[cfg(unix)] // Only run on Unix
-    // fn test_symlink_following_disabled_by_default(simple_dir_structure: TempDir) {
-    //     // Create a symlink to file1.txt
-    //     let link_path = simple_dir_structure.path().join("link_to_file");
-    //     std::os::unix::fs::symlink(simple_dir_structure.path().join("file1.txt"), &link_path)
-    //         .unwrap();
+    #[rstest]
+    #[cfg(unix)] // Only run on Unix
+    fn test_symlink_skipped_by_default(simple_dir_structure: TempDir) {
+        // Create a symlink to file1.txt
+        let link_path = simple_dir_structure.path().join("link_to_file");
+        std::os::unix::fs::symlink(simple_dir_structure.path().join("file1.txt"), &link_path)
+            .unwrap();
 
-    //     // Traverse with follow_symlinks=false (default)
-    //     let config = base_config(simple_dir_structure.path());
-    //     let (tree_str, files) = traverse_directory(&config, None).unwrap();
+        // Traverse with the default symlink policy
+        let config = base_config(simple_dir_structure.path());
+        let (tree_str, files) = traverse_directory(&config, None).unwrap();
 
-    //     // 1. Symlink should appear in the tree (it's a directory entry)
-    //     assert!(tree_str.contains("link_to_file"));
+        // 1. Symlink should appear in the tree (it's a directory entry)
+        assert!(tree_str.contains("link_to_file"));
 
-    //     // 2. But its *content* (file1.txt's content) should NOT appear in `files`
-    //     //    because we didn't follow the symlink.
-    //     assert!(!file_exists(&files, "link_to_file"));
+        // 2. But its *content* (file1.txt's content) should NOT appear in `files`
+        //    because we didn't follow the symlink.
+        assert!(!file_exists(&files, "link_to_file"));
 
-    //     // 3. file1.txt should still exist independently
-    //     assert!(file_exists(&files, "file1.txt"));
-    // }
+        // 3. file1.txt should still exist independently
+        assert!(file_exists(&files, "file1.txt"));
+    }
+
+    #[rstest]
+    #[cfg(unix)]
+    fn test_symlink_cycle_detection(simple_dir_structure: TempDir) {
+        let root = simple_dir_structure.path();
+        // Two links to the same directory, and a link back to the root
+        std::os::unix::fs::symlink(root.join("subdir/nested"), root.join("vendored")).unwrap();
+        std::os::unix::fs::symlink(root.join("subdir/nested"), root.join("vendored_again"))
+            .unwrap();
+        std::os::unix::fs::symlink(root, root.join("subdir/loop")).unwrap();
+
+        let count = |policy: SymlinkPolicy| {
+            let config = Code2PromptConfig::builder()
+                .path(root.to_path_buf())
+                .symlink_policy(policy)
+                .build()
+                .unwrap();
+            let (_, files) = traverse_directory(&config, None).unwrap();
+            files
+                .iter()
+                .filter(|file| file.path.ends_with("file3.txt"))
+                .count()
+        };
+
+        // Followed links reach file3.txt through every path, once with cycle detection
+        assert!(count(SymlinkPolicy::Follow) >= 3);
+        assert_eq!(count(SymlinkPolicy::FollowWithCycleDetection), 1);
+        assert_eq!(count(SymlinkPolicy::Skip), 1);
+    }
 
     #[rstest]
     fn test_symlink_following_when_enabled(simple_dir_structure: TempDir) {
@@ -281,7 +389,7 @@ mod tests {
 
         let config = Code2PromptConfig::builder()
             .path(simple_dir_structure.path().to_path_buf())
-            .follow_symlinks(true)
+            .symlink_policy(SymlinkPolicy::Follow)
             .build()
             .unwrap();
 
@@ -0,0 +1,127 @@
+//! Tests for the placement of the instruction, git diffs and key files
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::placement::{Placement, place_key_files};
+use code2prompt_core::session::Code2PromptSession;
+use code2prompt_core::sort::FileSortMethod;
+use code2prompt_core::template::OutputFormat;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_project() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "pub mod utils;\n").unwrap();
+        fs::write(dir.path().join("README.md"), "# Test Project\n").unwrap();
+        dir
+    }
+
+    fn session(dir: &TempDir, placement: Placement) -> Code2PromptSession {
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .sort_method(FileSortMethod::NameAsc)
+            .instruction(Some("Fix the bug".to_string()))
+            .placement(placement)
+            .key_files(vec!["src/main.rs".to_string()])
+            .build()
+            .unwrap();
+        Code2PromptSession::new(config)
+    }
+
+    #[test]
+    fn test_place_key_files_keeps_relative_order() {
+        let files = ["a.rs", "key/b.rs", "c.rs", "key/d.rs"];
+        for (placement, expected) in [
+            (Placement::End, ["a.rs", "c.rs", "key/b.rs", "key/d.rs"]),
+            (
+                Placement::Beginning,
+                ["key/b.rs", "key/d.rs", "a.rs", "c.rs"],
+            ),
+            (
+                Placement::Sandwich,
+                ["key/b.rs", "key/d.rs", "a.rs", "c.rs"],
+            ),
+        ] {
+            let config = Code2PromptConfig::builder()
+                .key_files(vec!["key/**".to_string()])
+                .placement(placement)
+                .build()
+                .unwrap();
+            let mut placed = files;
+            place_key_files(&mut placed, |file| Path::new(file), &config);
+            assert_eq!(placed, expected, "{}", placement);
+        }
+    }
+
+    #[test]
+    fn test_instruction_placement() {
+        let dir = create_test_project();
+        for (placement, first, last) in [
+            (Placement::End, false, true),
+            (Placement::Beginning, true, false),
+            (Placement::Sandwich, true, true),
+        ] {
+            let rendered = session(&dir, placement).generate_prompt().unwrap();
+            let prompt = rendered.prompt.trim_end();
+            assert_eq!(
+                prompt.starts_with("## Instruction\n\nFix the bug"),
+                first,
+                "{}",
+                placement
+            );
+            assert_eq!(prompt.ends_with("Fix the bug"), last, "{}", placement);
+            assert_eq!(rendered.token_breakdown.total(), rendered.token_count);
+        }
+    }
+
+    #[test]
+    fn test_key_files_and_diff_placement() {
+        let dir = create_test_project();
+
+        let mut end = session(&dir, Placement::End);
+        end.load_codebase().unwrap();
+        end.data.git_diff = Some("DIFF".to_string());
+        let context = end.build_template_data();
+        let prompt = end.render_prompt(&context).unwrap().prompt;
+        let (main, lib, diff) = (
+            prompt.find("`src/main.rs`").unwrap(),
+            prompt.find("`src/lib.rs`").unwrap(),
+            prompt.find("Git Diff:").unwrap(),
+        );
+        assert!(lib < main && main < diff);
+
+        let mut beginning = session(&dir, Placement::Beginning);
+        beginning.load_codebase().unwrap();
+        beginning.data.git_diff = Some("DIFF".to_string());
+        let context = beginning.build_template_data();
+        let prompt = beginning.render_prompt(&context).unwrap().prompt;
+        let (diff, tree, main, readme) = (
+            prompt.find("Git Diff:\nDIFF\n\n").unwrap(),
+            prompt.find("Source Tree:").unwrap(),
+            prompt.find("`src/main.rs`").unwrap(),
+            prompt.find("`README.md`").unwrap(),
+        );
+        assert!(diff < tree && main < readme);
+        assert_eq!(prompt.matches("DIFF").count(), 1);
+    }
+
+    #[test]
+    fn test_placement_recorded_in_json_output() {
+        let dir = create_test_project();
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .output_format(OutputFormat::Json)
+            .placement(Placement::Sandwich)
+            .build()
+            .unwrap();
+        let rendered = Code2PromptSession::new(config).generate_prompt().unwrap();
+        let output: serde_json::Value = serde_json::from_str(&rendered.prompt).unwrap();
+        assert_eq!(output["placement"], "sandwich");
+    }
+}
@@ -0,0 +1,168 @@
+//! Tests for the post-processing pipeline of rendered prompts
+
+use anyhow::Result;
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::language::SourceStats;
+use code2prompt_core::path::{EntryMetadata, FileEntry};
+use code2prompt_core::post_process::{
+    PostProcessContext, PostProcessStep, PostProcessor, run_pipeline,
+};
+use code2prompt_core::session::Code2PromptSession;
+use code2prompt_core::template::OutputFormat;
+use std::fs;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, token_count: usize) -> FileEntry {
+        FileEntry {
+            path: path.to_string(),
+            extension: String::new(),
+            code: String::new(),
+            token_count,
+            content_hash: String::new(),
+            metadata: EntryMetadata {
+                is_dir: false,
+                is_symlink: false,
+            },
+            mod_time: None,
+            history: None,
+            line_range: None,
+            stats: SourceStats::default(),
+            symbols: None,
+        }
+    }
+
+    fn run_with(steps: &[PostProcessStep], prompt: &str, output_format: OutputFormat) -> String {
+        let main = FileEntry {
+            code: "```rs\n`README.md`:\n```".to_string(),
+            ..file("src/main.rs", 120)
+        };
+        let files = [main, file("README.md", 30)];
+        let context = PostProcessContext {
+            output_format,
+            files: &files,
+            token_count: 1234,
+            model_info: "test tokenizer",
+        };
+        let processors: Vec<Box<dyn PostProcessor>> =
+            steps.iter().map(PostProcessStep::processor).collect();
+        let processors: Vec<&dyn PostProcessor> = processors.iter().map(Box::as_ref).collect();
+        run_pipeline(prompt.to_string(), &processors, &context).unwrap()
+    }
+
+    fn run(steps: &[PostProcessStep], prompt: &str) -> String {
+        run_with(steps, prompt, OutputFormat::Markdown)
+    }
+
+    #[test]
+    fn test_collapse_blank_lines() {
+        let prompt = "a\n\n\n\n\nb\n\nc\n";
+        assert_eq!(
+            run(&[PostProcessStep::CollapseBlankLines], prompt),
+            "a\n\n\nb\n\nc\n"
+        );
+    }
+
+    #[test]
+    fn test_number_headers_skips_code_blocks() {
+        let prompt = "## Setup\n### Install\n```sh\n# not a header\n```\n### Run\n## Usage\n";
+        assert_eq!(
+            run(&[PostProcessStep::NumberHeaders], prompt),
+            "## 1. Setup\n### 1.1. Install\n```sh\n# not a header\n```\n### 1.2. Run\n## 2. Usage\n"
+        );
+    }
+
+    #[test]
+    fn test_table_of_contents() {
+        let prompt = "Intro\n# Project Overview\n## Files & Tree\n## Files & Tree\n";
+        let output = run(&[PostProcessStep::TableOfContents], prompt);
+        assert_eq!(
+            output,
+            "Intro\nTable of Contents:\n\n\
+             - [Project Overview](#project-overview)\n\
+             \x20 - [Files & Tree](#files--tree)\n\
+             \x20 - [Files & Tree](#files--tree-1)\n\n\
+             # Project Overview\n## Files & Tree\n## Files & Tree\n"
+        );
+
+        // Without headers, the prompt is left as is
+        assert_eq!(run(&[PostProcessStep::TableOfContents], "a\n"), "a\n");
+    }
+
+    #[test]
+    fn test_footer_and_order() {
+        let output = run(
+            &[PostProcessStep::Footer, PostProcessStep::CollapseBlankLines],
+            "body\n\n\n\n",
+        );
+        assert_eq!(
+            output,
+            "body\n\n---\nGenerated by code2prompt: 2 files, 1234 tokens (test tokenizer)\n"
+        );
+    }
+
+    #[test]
+    fn test_file_index_markdown() {
+        let prompt = "Source Tree:\n\n`src/main.rs`:\n\n```rs\n`README.md`:\n```\n\n`README.md`:\n\n# Title\n";
+        assert_eq!(
+            run(&[PostProcessStep::FileIndex], prompt),
+            "Table of Files (2 files, 150 tokens):\n\n\
+             - [src/main.rs](#file-src-main-rs) (120 tokens)\n\
+             - [README.md](#file-readme-md) (30 tokens)\n\n\
+             Source Tree:\n\n\
+             <a id=\"file-src-main-rs\"></a>\n`src/main.rs`:\n\n```rs\n`README.md`:\n```\n\n\
+             <a id=\"file-readme-md\"></a>\n`README.md`:\n\n# Title\n"
+        );
+    }
+
+    #[test]
+    fn test_file_index_xml_and_unknown_templates() {
+        let prompt =
+            "<files>\n  <file path=\"src/main.rs\">\n    fn main() {}\n  </file>\n</files>";
+        assert_eq!(
+            run_with(&[PostProcessStep::FileIndex], prompt, OutputFormat::Xml),
+            "<table-of-files files=\"2\" tokens=\"150\">\n\
+             \x20 <file path=\"src/main.rs\" ref=\"file-src-main-rs\" tokens=\"120\" />\n\
+             \x20 <file path=\"README.md\" tokens=\"30\" />\n\
+             </table-of-files>\n\n\
+             <files>\n  <file path=\"src/main.rs\" id=\"file-src-main-rs\">\n    fn main() {}\n  </file>\n</files>"
+        );
+    }
+
+    #[derive(Debug)]
+    struct Shout;
+
+    impl PostProcessor for Shout {
+        fn process(&self, prompt: &str, _context: &PostProcessContext) -> Result<String> {
+            Ok(prompt.to_uppercase())
+        }
+    }
+
+    #[test]
+    fn test_session_runs_configured_steps_then_added_processors() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .post_process(vec![PostProcessStep::Footer])
+            .instruction(Some("keep me".to_string()))
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.add_post_processor(Arc::new(Shout));
+
+        let rendered = session.generate_prompt().unwrap();
+        assert!(rendered.prompt.contains("FN MAIN() {}"));
+        assert!(
+            rendered
+                .prompt
+                .contains("GENERATED BY CODE2PROMPT: 1 FILE, ")
+        );
+        // The instruction is appended after post-processing
+        assert!(rendered.prompt.trim_end().ends_with("keep me"));
+    }
+}
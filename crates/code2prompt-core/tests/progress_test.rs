@@ -0,0 +1,103 @@
+//! # Progress Tests
+//!
+//! Tests for the progress events reported while a prompt is generated: the steps in their
+//! order, a file loaded event for each file, and the closures used as listeners.
+
+use code2prompt_core::{
+    configuration::Code2PromptConfig,
+    progress::{ProgressEvent, ProgressFn},
+    session::Code2PromptSession,
+};
+use rstest::*;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, mpsc};
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+/// Creates a project of three files
+#[fixture]
+fn project() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "pub fn run() {}\n").unwrap();
+    fs::write(dir.path().join("README.md"), "# Project\n").unwrap();
+    dir
+}
+
+fn session(dir: &TempDir) -> Code2PromptSession {
+    let config = Code2PromptConfig::builder()
+        .path(dir.path().to_path_buf())
+        .build()
+        .expect("Failed to build config");
+    Code2PromptSession::new(config)
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn test_events_of_a_run(project: TempDir) {
+        let (tx, rx) = mpsc::channel();
+        let mut session = session(&project);
+        session.set_progress_listener(Arc::new(tx));
+        let rendered = session.generate_prompt().unwrap();
+        let events: Vec<ProgressEvent> = rx.try_iter().collect();
+
+        assert_eq!(events.first(), Some(&ProgressEvent::TraversalStarted));
+        assert_eq!(events[1], ProgressEvent::FilesFound { count: 3 });
+        let mut loaded: Vec<&str> = events
+            .iter()
+            .filter_map(|event| match event {
+                ProgressEvent::FileLoaded { path, tokens } => {
+                    assert!(*tokens > 0);
+                    Some(path.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        loaded.sort();
+        assert_eq!(loaded, vec!["README.md", "src/lib.rs", "src/main.rs"]);
+        assert!(!events.contains(&ProgressEvent::GitDiffLoaded));
+        assert_eq!(
+            &events[events.len() - 2..],
+            &[
+                ProgressEvent::RenderStarted,
+                ProgressEvent::Done {
+                    token_count: rendered.token_count
+                }
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_closure_listener(project: TempDir) {
+        let loaded = Arc::new(AtomicUsize::new(0));
+        let counter = loaded.clone();
+        let mut session = session(&project);
+        session.set_progress_listener(Arc::new(ProgressFn(move |event| {
+            if let ProgressEvent::FileLoaded { .. } = event {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        })));
+        session.load_codebase().unwrap();
+        assert_eq!(loaded.load(Ordering::SeqCst), 3);
+    }
+
+    #[rstest]
+    fn test_no_listener(project: TempDir) {
+        let mut session = session(&project);
+        assert!(
+            session
+                .generate_prompt()
+                .unwrap()
+                .prompt
+                .contains("fn main")
+        );
+    }
+}
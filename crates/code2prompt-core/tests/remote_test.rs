@@ -0,0 +1,138 @@
+//! # Remote Repository Tests
+//!
+//! Tests for the repository URLs given as the path of the codebase: their parsing, and
+//! the temporary clone the codebase is read from.
+
+use code2prompt_core::{
+    configuration::Code2PromptConfig, remote::RemoteRepository, session::Code2PromptSession,
+};
+use git2::{Repository, Signature};
+use rstest::*;
+use std::path::{Path, PathBuf};
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+/// Creates a bare repository with `app.py` on `main`, and `feature.py` too on `feature`
+#[fixture]
+fn origin() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let repo = Repository::init_bare(dir.path().join("project.git")).unwrap();
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+
+    let mut root = repo.treebuilder(None).unwrap();
+    root.insert("app.py", repo.blob(b"print('app')\n").unwrap(), 0o100644)
+        .unwrap();
+    let tree = repo.find_tree(root.write().unwrap()).unwrap();
+    let main = repo
+        .commit(
+            Some("refs/heads/main"),
+            &signature,
+            &signature,
+            "App",
+            &tree,
+            &[],
+        )
+        .unwrap();
+    repo.set_head("refs/heads/main").unwrap();
+
+    root.insert(
+        "feature.py",
+        repo.blob(b"print('feature')\n").unwrap(),
+        0o100644,
+    )
+    .unwrap();
+    let tree = repo.find_tree(root.write().unwrap()).unwrap();
+    let parent = repo.find_commit(main).unwrap();
+    repo.commit(
+        Some("refs/heads/feature"),
+        &signature,
+        &signature,
+        "Feature",
+        &tree,
+        &[&parent],
+    )
+    .unwrap();
+    dir
+}
+
+fn url(origin: &TempDir) -> String {
+    format!("file://{}", origin.path().join("project.git").display())
+}
+
+fn remote(url: &str, branch: Option<&str>) -> RemoteRepository {
+    RemoteRepository {
+        url: url.to_string(),
+        branch: branch.map(str::to_string),
+    }
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    #[case(
+        "https://github.com/org/repo",
+        Some(remote("https://github.com/org/repo", None))
+    )]
+    #[case(
+        "https://github.com/org/repo.git#dev",
+        Some(remote("https://github.com/org/repo.git", Some("dev")))
+    )]
+    #[case("http://host/repo/#", Some(remote("http://host/repo", None)))]
+    #[case("./src", None)]
+    #[case("/home/user/repo", None)]
+    fn test_parse(#[case] path: &str, #[case] expected: Option<RemoteRepository>) {
+        assert_eq!(RemoteRepository::parse(Path::new(path)), expected);
+    }
+
+    #[rstest]
+    #[case("https://github.com/org/repo.git", "repo")]
+    #[case("https://github.com/org/repo", "repo")]
+    #[case("https://", "repository")]
+    fn test_name(#[case] url: &str, #[case] expected: &str) {
+        assert_eq!(remote(url, None).name(), expected);
+    }
+
+    #[rstest]
+    fn test_clone_branch(origin: TempDir) {
+        let checkout = remote(&url(&origin), Some("feature"))
+            .clone_to_temp()
+            .unwrap();
+        let path = checkout.path().to_path_buf();
+        assert!(path.ends_with("project"));
+        assert!(path.join("feature.py").exists());
+
+        drop(checkout);
+        assert!(!path.exists());
+    }
+
+    #[rstest]
+    fn test_clone_missing_branch(origin: TempDir) {
+        assert!(
+            remote(&url(&origin), Some("missing"))
+                .clone_to_temp()
+                .is_err()
+        );
+    }
+
+    #[rstest]
+    fn test_session_reads_clone(origin: TempDir) {
+        let config = Code2PromptConfig::builder()
+            .path(PathBuf::from(url(&origin)))
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+        assert!(rendered.prompt.contains("print('app')"));
+        assert!(!rendered.prompt.contains("feature.py"));
+
+        let clone = session.config.path.clone();
+        assert!(clone.exists());
+        drop(session);
+        assert!(!clone.exists());
+    }
+}
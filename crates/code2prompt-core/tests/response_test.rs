@@ -0,0 +1,99 @@
+use code2prompt_core::response::{Edit, parse_response};
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fenced_block_with_path() {
+        let response = "Here is the fix:\n\n**src/main.rs**\n```rust\nfn main() {}\n```\n\n```python app.py\nprint('hi')\n```\n";
+        let edits = parse_response(response);
+
+        assert_eq!(
+            edits,
+            vec![
+                Edit::WriteFile {
+                    path: PathBuf::from("src/main.rs"),
+                    content: "fn main() {}\n".to_string(),
+                },
+                Edit::WriteFile {
+                    path: PathBuf::from("app.py"),
+                    content: "print('hi')\n".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_block_without_path_is_ignored() {
+        let response = "Run this:\n```bash\ncargo test\n```\n";
+        assert!(parse_response(response).is_empty());
+    }
+
+    #[test]
+    fn test_parse_and_apply_unified_diff() {
+        let response = "```diff\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,3 +1,3 @@\n fn a() {}\n-fn b() {}\n+fn b() -> u8 { 1 }\n fn c() {}\n```\n";
+        let edits = parse_response(response);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].path(), Path::new("src/lib.rs"));
+
+        let original = "// header\nfn a() {}\nfn b() {}\nfn c() {}\n";
+        let patched = edits[0].apply(Some(original)).unwrap().unwrap();
+        assert_eq!(
+            patched,
+            "// header\nfn a() {}\nfn b() -> u8 { 1 }\nfn c() {}\n"
+        );
+
+        assert!(edits[0].apply(Some("unrelated\n")).is_err());
+    }
+
+    #[test]
+    fn test_parse_unfenced_multi_file_diff() {
+        let response = "diff --git a/new.txt b/new.txt\nnew file mode 100644\n--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+hello\n+world\ndiff --git a/old.txt b/old.txt\ndeleted file mode 100644\n--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n\nThat's all.";
+        let edits = parse_response(response);
+
+        assert_eq!(edits.len(), 2);
+        assert_eq!(
+            edits[0].apply(None).unwrap(),
+            Some("hello\nworld\n".to_string())
+        );
+        assert_eq!(
+            edits[1],
+            Edit::DeleteFile {
+                path: PathBuf::from("old.txt")
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_search_replace_blocks() {
+        let response = "src/config.py\n```python\n<<<<<<< SEARCH\nDEBUG = True\n=======\nDEBUG = False\n>>>>>>> REPLACE\n```\n";
+        let edits = parse_response(response);
+
+        assert_eq!(
+            edits,
+            vec![Edit::SearchReplace {
+                path: PathBuf::from("src/config.py"),
+                search: "DEBUG = True".to_string(),
+                replace: "DEBUG = False".to_string(),
+            }]
+        );
+        let patched = edits[0].apply(Some("X = 1\nDEBUG = True\n")).unwrap();
+        assert_eq!(patched, Some("X = 1\nDEBUG = False\n".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_rejects_paths_outside_root() {
+        let root = Path::new("/repo");
+        let inside = Edit::DeleteFile {
+            path: PathBuf::from("src/main.rs"),
+        };
+        let outside = Edit::DeleteFile {
+            path: PathBuf::from("../etc/passwd"),
+        };
+
+        assert_eq!(inside.resolve(root).unwrap(), root.join("src/main.rs"));
+        assert!(outside.resolve(root).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! # Multi-Root Tests
+//!
+//! Tests for the prompts rendering several codebase roots: their labels, merged trees and
+//! files, and the `roots` exposed to the templates.
+
+use code2prompt_core::{
+    configuration::Code2PromptConfig, roots::labeled_roots, session::Code2PromptSession,
+    sort::FileSortMethod,
+};
+use rstest::*;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+#[fixture]
+fn monorepo() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let files = [
+        ("backend/src/main.rs", "fn main() {}\n"),
+        ("backend/Cargo.toml", "[package]\n"),
+        ("shared-lib/src/lib.rs", "pub fn shared() {}\n"),
+        ("frontend/app.ts", "export {}\n"),
+    ];
+    for (path, content) in files {
+        let full_path = dir.path().join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(full_path, content).unwrap();
+    }
+    dir
+}
+
+fn session(dir: &TempDir, template: &str) -> Code2PromptSession {
+    let config = Code2PromptConfig::builder()
+        .path(dir.path().join("backend"))
+        .extra_paths(vec![dir.path().join("shared-lib")])
+        .sort_method(FileSortMethod::NameAsc)
+        .template_str(template)
+        .template_name("roots")
+        .build()
+        .unwrap();
+    Code2PromptSession::new(config)
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn test_files_and_tree_of_every_root(monorepo: TempDir) {
+        let mut session = session(&monorepo, "");
+        session.load_codebase().unwrap();
+
+        let paths: Vec<&str> = session
+            .data
+            .files
+            .as_deref()
+            .unwrap()
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "backend/Cargo.toml",
+                "backend/src/main.rs",
+                "shared-lib/src/lib.rs"
+            ]
+        );
+        let tree = session.data.source_tree.as_deref().unwrap();
+        assert!(tree.starts_with("backend\n"));
+        assert!(tree.contains("\nshared-lib\n"));
+        assert!(!tree.contains("frontend"));
+        assert_eq!(
+            session.data.absolute_code_path.as_deref(),
+            Some("backend, shared-lib")
+        );
+    }
+
+    #[rstest]
+    fn test_roots_in_template(monorepo: TempDir) {
+        let rendered = session(
+            &monorepo,
+            "{{#each roots}}{{name}}: {{file_count}} files\n{{/each}}",
+        )
+        .generate_prompt()
+        .unwrap();
+        assert_eq!(
+            rendered.prompt.trim(),
+            "backend: 2 files\nshared-lib: 1 files"
+        );
+    }
+
+    #[rstest]
+    fn test_labels_are_unique(monorepo: TempDir) {
+        let nested = monorepo.path().join("frontend/backend");
+        fs::create_dir_all(&nested).unwrap();
+        let config = Code2PromptConfig::builder()
+            .path(monorepo.path().join("backend"))
+            .extra_paths(vec![nested.clone(), monorepo.path().join("frontend")])
+            .build()
+            .unwrap();
+
+        let labels: Vec<(PathBuf, String)> = labeled_roots(&config);
+        let names: Vec<&str> = labels.iter().map(|(_, label)| label.as_str()).collect();
+        assert_eq!(names, vec!["backend", "backend-2", "frontend"]);
+        assert_eq!(labels[1].0, nested);
+    }
+}
@@ -0,0 +1,131 @@
+//! # Selection Import Tests
+//!
+//! Tests for the selections handed over by other tools: their schema, and how their
+//! patterns, pins and ranges select the files of a codebase.
+
+use code2prompt_core::{
+    configuration::Code2PromptConfig, selection_import::SelectionImport,
+    session::Code2PromptSession,
+};
+use rstest::*;
+use std::fs;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+#[fixture]
+fn codebase() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let files = [
+        ("src/main.rs", "fn main() {}\n"),
+        ("src/lib.rs", "line 1\nline 2\nline 3\nline 4\nline 5\n"),
+        ("src/generated/api.rs", "// generated\n"),
+        ("docs/guide.md", "# Guide\n"),
+        ("notes.txt", "notes\n"),
+    ];
+    for (path, content) in files {
+        let full_path = dir.path().join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(full_path, content).unwrap();
+    }
+    dir
+}
+
+fn loaded_paths(session: &mut Code2PromptSession) -> Vec<String> {
+    session.load_codebase().unwrap();
+    let mut paths: Vec<String> = session
+        .data
+        .files
+        .as_deref()
+        .unwrap()
+        .iter()
+        .map(|file| file.path.clone())
+        .collect();
+    paths.sort();
+    paths
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_schema() {
+        let selection = SelectionImport::parse(
+            r#"{
+                "version": 1,
+                "includes": ["src/**"],
+                "pins": ["README.md"],
+                "ranges": { "src/lib.rs": ["10-40", "80-90"] }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(selection.includes, vec!["src/**"]);
+        assert!(selection.excludes.is_empty());
+        assert_eq!(selection.ranges["src/lib.rs"].len(), 2);
+
+        assert!(SelectionImport::parse(r#"{ "includes": [] }"#).is_err());
+        assert!(SelectionImport::parse(r#"{ "version": 2 }"#).is_err());
+        assert!(SelectionImport::parse(r#"{ "version": 1, "include": [] }"#).is_err());
+        assert!(SelectionImport::parse(r#"{ "version": 1, "ranges": { "a": ["5-1"] } }"#).is_err());
+    }
+
+    #[rstest]
+    fn test_apply_patterns_and_pins(codebase: TempDir) {
+        let selection = SelectionImport::parse(&format!(
+            r#"{{
+                "version": 1,
+                "includes": ["src/**"],
+                "excludes": ["src/generated/**"],
+                "pins": ["{}"]
+            }}"#,
+            codebase.path().join("docs/guide.md").display()
+        ))
+        .unwrap();
+        let config = Code2PromptConfig::builder()
+            .path(codebase.path().to_path_buf())
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        selection.apply(&mut session);
+
+        assert_eq!(session.config.key_files, vec!["docs/guide.md"]);
+        assert_eq!(
+            loaded_paths(&mut session),
+            vec!["docs/guide.md", "src/lib.rs", "src/main.rs"]
+        );
+    }
+
+    #[rstest]
+    fn test_apply_ranges(codebase: TempDir) {
+        let selection = SelectionImport::parse(
+            r#"{ "version": 1, "excludes": ["src/**"], "ranges": { "src/lib.rs": ["2-3"] } }"#,
+        )
+        .unwrap();
+        let config = Code2PromptConfig::builder()
+            .path(codebase.path().to_path_buf())
+            .exclude_patterns(vec!["notes.txt".to_string()])
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        selection.apply(&mut session);
+
+        assert_eq!(session.config.exclude_patterns, vec!["notes.txt", "src/**"]);
+        assert_eq!(
+            loaded_paths(&mut session),
+            vec!["docs/guide.md", "src/lib.rs"]
+        );
+        let lib = session
+            .data
+            .files
+            .as_deref()
+            .unwrap()
+            .iter()
+            .find(|file| file.path == "src/lib.rs")
+            .unwrap();
+        assert!(lib.code.contains("line 2"));
+        assert!(!lib.code.contains("line 5"));
+    }
+}
@@ -1,7 +1,11 @@
 //! Integration tests for the session with simplified file selection
 
 use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::line_range::LineRange;
+use code2prompt_core::prompt_cache::PromptCacheProvider;
+use code2prompt_core::selection::FileInclusion;
 use code2prompt_core::session::Code2PromptSession;
+use code2prompt_core::template::{FileDelimiter, OutputFormat};
 use std::fs;
 use tempfile::TempDir;
 
@@ -154,6 +158,28 @@ mod tests {
         assert_eq!(session.config.exclude_patterns[0], "**/test*");
     }
 
+    #[test]
+    fn test_session_set_patterns_keeps_user_actions() {
+        let temp_dir = create_test_project();
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .exclude_patterns(vec!["*".to_string()])
+            .build()
+            .unwrap();
+
+        let mut session = Code2PromptSession::new(config);
+        session.select_file("README.md".into());
+        assert!(!session.is_file_selected(std::path::Path::new("src/main.rs")));
+
+        session.set_patterns(vec!["src/**".to_string()], Vec::new());
+
+        assert_eq!(session.config.include_patterns, vec!["src/**"]);
+        assert!(session.config.exclude_patterns.is_empty());
+        assert!(session.is_file_selected(std::path::Path::new("src/main.rs")));
+        assert!(session.is_file_selected(std::path::Path::new("README.md")));
+        assert!(!session.is_file_selected(std::path::Path::new("tests/test_main.rs")));
+    }
+
     #[test]
     fn test_session_relative_path_handling() {
         let temp_dir = create_test_project();
@@ -178,4 +204,393 @@ mod tests {
         assert_eq!(selected_files.len(), 1);
         assert_eq!(selected_files[0], main_rs_relative);
     }
+
+    #[test]
+    fn test_session_response_contract() {
+        let temp_dir = create_test_project();
+        let schema = r#"{"type": "object", "required": ["summary"]}"#;
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .response_schema(Some(schema.to_string()))
+            .build()
+            .unwrap();
+
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+
+        assert!(rendered.prompt.contains("## Response Format"));
+        assert!(
+            rendered
+                .prompt
+                .trim_end()
+                .ends_with(&format!("```json\n{}\n```", schema))
+        );
+    }
+
+    #[test]
+    fn test_session_token_breakdown() {
+        let temp_dir = create_test_project();
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .instruction(Some("Add logging".to_string()))
+            .build()
+            .unwrap();
+
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+        let breakdown = rendered.token_breakdown;
+
+        assert!(breakdown.tree > 0);
+        assert!(breakdown.files > 0);
+        assert!(breakdown.instruction > 0);
+        assert_eq!(breakdown.git, 0);
+        assert_eq!(breakdown.total(), rendered.token_count);
+    }
+
+    #[test]
+    fn test_session_repeat_instruction() {
+        let temp_dir = create_test_project();
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .instruction(Some("Add logging".to_string()))
+            .repeat_instruction(true)
+            .build()
+            .unwrap();
+
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+
+        assert!(
+            rendered
+                .prompt
+                .starts_with("## Instruction\n\nAdd logging\n\n")
+        );
+        assert!(
+            rendered
+                .prompt
+                .trim_end()
+                .ends_with("## Instruction\n\nAdd logging")
+        );
+        assert_eq!(rendered.prompt.matches("Add logging").count(), 2);
+        assert_eq!(rendered.token_breakdown.total(), rendered.token_count);
+    }
+
+    #[test]
+    fn test_session_config_template_variable() {
+        let temp_dir = create_test_project();
+        let template = "{{#if config.line_numbers}}numbered{{else}}plain{{/if}} {{config.output_format}} {{#if config.diff_enabled}}diff{{/if}}";
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .template_str(template.to_string())
+            .template_name("conditional".to_string())
+            .line_numbers(true)
+            .build()
+            .unwrap();
+
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+
+        assert_eq!(rendered.prompt, "numbered markdown");
+    }
+
+    #[test]
+    fn test_session_version_info_template_variable() {
+        let temp_dir = create_test_project();
+        let template = "{{#if version_info}}{{version_info.describe}}{{else}}untracked{{/if}}";
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .template_str(template.to_string())
+            .template_name("version".to_string())
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        assert_eq!(session.generate_prompt().unwrap().prompt, "untracked");
+
+        let repo = git2::Repository::init(temp_dir.path()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let commit = repo
+            .commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+        repo.tag_lightweight("v0.1.0", &repo.find_object(commit, None).unwrap(), false)
+            .unwrap();
+
+        assert_eq!(session.generate_prompt().unwrap().prompt, "v0.1.0");
+    }
+
+    #[test]
+    fn test_session_reload_reads_new_commits_of_file_history() {
+        let temp_dir = create_test_project();
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .file_history(std::collections::HashMap::from([(
+                "src/main.rs".to_string(),
+                5,
+            )]))
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+
+        let repo = git2::Repository::init(temp_dir.path()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let commit = |message: &str| {
+            let mut index = repo.index().unwrap();
+            index
+                .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+                .unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents: Vec<_> = repo
+                .head()
+                .ok()
+                .and_then(|head| head.peel_to_commit().ok())
+                .into_iter()
+                .collect();
+            let parents: Vec<_> = parents.iter().collect();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap();
+        };
+        let history_len = |session: &Code2PromptSession| {
+            let files = session.data.files.as_ref().unwrap();
+            let main = files
+                .iter()
+                .find(|file| file.path == "src/main.rs")
+                .unwrap();
+            main.history.as_ref().map_or(0, Vec::len)
+        };
+
+        commit("Initial");
+        session.load_codebase().unwrap();
+        assert_eq!(history_len(&session), 1);
+
+        fs::write(temp_dir.path().join("src/main.rs"), "fn main() { run(); }").unwrap();
+        commit("Run");
+        session.load_codebase().unwrap();
+        assert_eq!(history_len(&session), 2);
+    }
+
+    #[test]
+    fn test_session_prompt_cache_breakpoint() {
+        let temp_dir = create_test_project();
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .instruction(Some("Add logging".to_string()))
+            .prompt_cache(Some(PromptCacheProvider::Anthropic))
+            .build()
+            .unwrap();
+
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+        let body: serde_json::Value = serde_json::from_str(&rendered.prompt).unwrap();
+        let content = &body["messages"][0]["content"];
+
+        // The codebase is the cacheable prefix, the instruction follows the breakpoint
+        assert!(
+            content[0]["text"]
+                .as_str()
+                .unwrap()
+                .contains("fn main() {}")
+        );
+        assert_eq!(content[0]["cache_control"]["type"], "ephemeral");
+        assert_eq!(content[1]["text"], "## Instruction\n\nAdd logging");
+        assert!(content[1].get("cache_control").is_none());
+    }
+
+    #[test]
+    fn test_session_jsonl_output() {
+        let temp_dir = create_test_project();
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .output_format(OutputFormat::Jsonl)
+            .build()
+            .unwrap();
+
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+        let records: Vec<serde_json::Value> = rendered
+            .prompt
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        // One record per file, then the summary
+        assert_eq!(records.len(), 6);
+        let main = records
+            .iter()
+            .find(|record| record["path"] == "src/main.rs")
+            .unwrap();
+        assert_eq!(main["type"], "file");
+        assert!(main["code"].as_str().unwrap().contains("fn main() {}"));
+        assert!(main["token_count"].as_u64().unwrap() > 0);
+        assert!(main["metadata"].is_object());
+
+        let summary = records.last().unwrap();
+        assert_eq!(summary["type"], "summary");
+        assert_eq!(summary["file_count"], 5);
+        assert_eq!(summary["token_count"], rendered.token_count);
+    }
+
+    #[test]
+    fn test_session_tree_only_files() {
+        let temp_dir = create_test_project();
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .tree_only_patterns(vec!["*.md".to_string()])
+            .build()
+            .unwrap();
+
+        let mut session = Code2PromptSession::new(config);
+        assert_eq!(
+            session.file_inclusion(std::path::Path::new("README.md")),
+            FileInclusion::TreeOnly
+        );
+        session.set_file_inclusion(std::path::PathBuf::from("tests"), FileInclusion::TreeOnly);
+        session.cycle_file_inclusion(std::path::PathBuf::from("src/utils.rs"));
+        session.load_codebase().unwrap();
+
+        let tree = session.data.source_tree.clone().unwrap();
+        let paths: Vec<String> = session
+            .data
+            .files
+            .unwrap()
+            .into_iter()
+            .map(|file| file.path)
+            .collect();
+
+        // Tree-only files are listed in the tree, without their content
+        assert!(tree.contains("README.md") && tree.contains("test_main.rs"));
+        assert!(tree.contains("utils.rs"));
+        assert!(!paths.iter().any(|path| path.ends_with("README.md")));
+        assert!(!paths.iter().any(|path| path.contains("test_main.rs")));
+        assert!(!paths.iter().any(|path| path.contains("utils.rs")));
+        assert!(paths.iter().any(|path| path.contains("main.rs")));
+    }
+
+    #[test]
+    fn test_session_line_ranges() {
+        let temp_dir = create_test_project();
+        let lines: Vec<String> = (1..=20).map(|n| format!("let x{} = {};", n, n)).collect();
+        fs::write(temp_dir.path().join("src/big.rs"), lines.join("\n")).unwrap();
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .include_patterns(vec!["src/big.rs:3-4".to_string()])
+            .line_numbers(true)
+            .build()
+            .unwrap();
+
+        let mut session = Code2PromptSession::new(config);
+        session.include_file_range(
+            std::path::PathBuf::from("src/main.rs"),
+            LineRange::new(1, 1).unwrap(),
+        );
+        session.include_file_range(
+            std::path::PathBuf::from("src/big.rs"),
+            LineRange::new(10, 11).unwrap(),
+        );
+        let rendered = session.generate_prompt().unwrap();
+        let files = session.data.files.clone().unwrap();
+
+        let big = files
+            .iter()
+            .find(|file| file.path.ends_with("big.rs"))
+            .unwrap();
+        assert_eq!(big.line_range.as_deref(), Some("3-4, 10-11"));
+        assert!(big.code.contains("   3 | let x3 = 3;"));
+        assert!(big.code.contains("...\n  10 | let x10 = 10;"));
+        assert!(!big.code.contains("let x5 ="));
+        assert!(files.iter().any(|file| file.path.ends_with("main.rs")));
+        assert!(!files.iter().any(|file| file.path.ends_with("lib.rs")));
+        assert!(rendered.prompt.contains("big.rs` (lines 3-4, 10-11):"));
+    }
+
+    #[test]
+    fn test_session_file_delimiters() {
+        let temp_dir = create_test_project();
+        let render = |delimiter: FileDelimiter, header: Option<&str>, format: OutputFormat| {
+            let config = Code2PromptConfig::builder()
+                .path(temp_dir.path().to_path_buf())
+                .include_patterns(vec!["src/main.rs".to_string()])
+                .output_format(format)
+                .file_delimiter(delimiter)
+                .file_header(header.map(str::to_string))
+                .build()
+                .unwrap();
+            Code2PromptSession::new(config)
+                .generate_prompt()
+                .unwrap()
+                .prompt
+        };
+
+        let markdown = render(FileDelimiter::Auto, None, OutputFormat::Markdown);
+        assert!(markdown.contains("`src/main.rs`:\n\n```rs\nfn main() {}"));
+        let xml = render(FileDelimiter::Auto, None, OutputFormat::Xml);
+        assert!(xml.contains("<file path=\"src/main.rs\">"));
+        assert!(xml.contains("</file>"));
+
+        let banner = render(FileDelimiter::Banner, None, OutputFormat::Markdown);
+        assert!(banner.contains("===== FILE: src/main.rs =====\n\n```rs"));
+        assert!(banner.contains("===== END FILE: src/main.rs ====="));
+        assert!(!banner.contains("`src/main.rs`:"));
+
+        // A custom header replaces the one of the delimiter, keeping its footer
+        let custom = render(
+            FileDelimiter::Xml,
+            Some("### {{path}} ({{extension}})"),
+            OutputFormat::Markdown,
+        );
+        assert!(custom.contains("### src/main.rs (rs)\n"));
+        assert!(custom.contains("</file>"));
+    }
+
+    #[test]
+    fn test_session_template_preview() {
+        let temp_dir = create_test_project();
+        fs::write(temp_dir.path().join("src/long.rs"), "// line\n".repeat(50)).unwrap();
+        let config = Code2PromptConfig::builder()
+            .path(temp_dir.path().to_path_buf())
+            .build()
+            .unwrap();
+        let session = Code2PromptSession::new(config);
+
+        let files = session.sample_files(
+            &[
+                "src/long.rs".into(),
+                temp_dir.path().join("src/main.rs"),
+                "missing.rs".into(),
+            ],
+            3,
+        );
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "src/long.rs");
+        assert_eq!(files[0].code.matches("// line").count(), 3);
+        assert_eq!(files[1].path, "src/main.rs");
+
+        let variables = [("team".to_string(), "core".to_string())].into();
+        let preview = session
+            .preview_template(
+                "{{team}}\n{{source_tree}}\n{{#each files}}[{{path}}]{{/each}}",
+                &files,
+                &variables,
+            )
+            .unwrap();
+        assert!(preview.starts_with("core\n"));
+        assert!(preview.contains("src/long.rs\nsrc/main.rs"));
+        assert!(preview.contains("[src/long.rs][src/main.rs]"));
+
+        assert!(
+            session
+                .preview_template("{{#each files}}", &files, &variables)
+                .is_err()
+        );
+    }
 }
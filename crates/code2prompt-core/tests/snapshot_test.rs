@@ -0,0 +1,121 @@
+//! # Snapshot Tests
+//!
+//! Tests for the comparison of two generated runs: the files added, removed and changed,
+//! their token deltas, and the snapshots taken from a session.
+
+use code2prompt_core::{
+    configuration::Code2PromptConfig,
+    session::Code2PromptSession,
+    snapshot::{FileDelta, FileSnapshot, SessionSnapshot, diff_snapshots},
+};
+use rstest::*;
+use std::fs;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+fn snapshot(token_count: usize, files: &[(&str, usize, &str)]) -> SessionSnapshot {
+    SessionSnapshot {
+        token_count,
+        files: files
+            .iter()
+            .map(|(path, tokens, hash)| {
+                (
+                    path.to_string(),
+                    FileSnapshot {
+                        token_count: *tokens,
+                        content_hash: hash.to_string(),
+                    },
+                )
+            })
+            .collect(),
+    }
+}
+
+fn delta(path: &str, before: usize, after: usize) -> FileDelta {
+    FileDelta {
+        path: path.to_string(),
+        before,
+        after,
+    }
+}
+
+#[fixture]
+fn project() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    fs::write(dir.path().join("lib.rs"), "pub fn lib() {}\n").unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    dir
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn test_added_removed_and_changed_files() {
+        let before = snapshot(
+            1000,
+            &[("a.rs", 100, "1"), ("b.rs", 200, "2"), ("c.rs", 300, "3")],
+        );
+        let after = snapshot(
+            21500,
+            &[
+                ("a.rs", 100, "1"),
+                ("b.rs", 250, "2b"),
+                ("d.rs", 20000, "4"),
+                ("e.rs", 5, "5"),
+            ],
+        );
+
+        let diff = diff_snapshots(&before, &after);
+        assert_eq!(
+            diff.added,
+            vec![delta("d.rs", 0, 20000), delta("e.rs", 0, 5)]
+        );
+        assert_eq!(diff.removed, vec![delta("c.rs", 300, 0)]);
+        assert_eq!(diff.changed, vec![delta("b.rs", 200, 250)]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.token_delta(), 20500);
+        assert_eq!(diff.files_token_delta(), 20000 + 5 - 300 + 50);
+        assert!(!diff.is_empty());
+    }
+
+    #[rstest]
+    fn test_changes_sorted_by_size() {
+        let before = snapshot(0, &[("a.rs", 100, "1"), ("b.rs", 100, "2")]);
+        let after = snapshot(0, &[("a.rs", 90, "1b"), ("b.rs", 400, "2b")]);
+
+        let diff = diff_snapshots(&before, &after);
+        assert_eq!(
+            diff.changed,
+            vec![delta("b.rs", 100, 400), delta("a.rs", 100, 90)]
+        );
+        assert_eq!(diff.changed[1].delta(), -10);
+    }
+
+    #[rstest]
+    fn test_identical_runs(project: TempDir) {
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        let first = session.generate_prompt().unwrap();
+        let before = session.snapshot(first.token_count);
+        assert_eq!(before.files.len(), 2);
+
+        fs::write(project.path().join("extra.rs"), "pub fn extra() {}\n").unwrap();
+        let second = session.generate_prompt().unwrap();
+        let after = session.snapshot(second.token_count);
+
+        assert!(diff_snapshots(&before, &before).is_empty());
+        let diff = diff_snapshots(&before, &after);
+        assert_eq!(diff.added.len(), 1);
+        assert!(diff.added[0].path.ends_with("extra.rs"));
+        assert_eq!(diff.unchanged, 2);
+        assert!(diff.token_delta() > 0);
+    }
+}
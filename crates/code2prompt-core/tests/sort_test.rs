@@ -1,6 +1,30 @@
+use code2prompt_core::language::SourceStats;
 use code2prompt_core::path::{EntryMetadata, FileEntry};
 use code2prompt_core::sort::{FileSortMethod, sort_files, sort_tree};
 
+fn entry(path: &str, code: &str, token_count: usize) -> FileEntry {
+    FileEntry {
+        path: path.to_string(),
+        extension: path.rsplit_once('.').map_or("", |(_, ext)| ext).to_string(),
+        code: code.to_string(),
+        token_count,
+        content_hash: String::new(),
+        metadata: EntryMetadata {
+            is_dir: false,
+            is_symlink: false,
+        },
+        mod_time: None,
+        history: None,
+        line_range: None,
+        stats: SourceStats::default(),
+        symbols: None,
+    }
+}
+
+fn paths(files: &[FileEntry]) -> Vec<&str> {
+    files.iter().map(|file| file.path.as_str()).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -15,33 +39,48 @@ mod tests {
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(100),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
             FileEntry {
                 path: "alpha.txt".to_string(),
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(200),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
             FileEntry {
                 path: "beta.txt".to_string(),
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(150),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
         ];
 
@@ -63,33 +102,48 @@ mod tests {
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(100),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
             FileEntry {
                 path: "zeta.txt".to_string(),
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(200),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
             FileEntry {
                 path: "beta.txt".to_string(),
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(150),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
         ];
 
@@ -111,33 +165,48 @@ mod tests {
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(300),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
             FileEntry {
                 path: "file2.txt".to_string(),
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(100),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
             FileEntry {
                 path: "file3.txt".to_string(),
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(200),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
         ];
 
@@ -159,33 +228,48 @@ mod tests {
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(300),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
             FileEntry {
                 path: "file2.txt".to_string(),
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(100),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
             FileEntry {
                 path: "file3.txt".to_string(),
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some(200),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             },
         ];
 
@@ -210,11 +294,16 @@ mod tests {
                 extension: "txt".to_string(),
                 code: String::new(),
                 token_count: 0,
+                content_hash: String::new(),
                 metadata: EntryMetadata {
                     is_dir: false,
                     is_symlink: false,
                 },
                 mod_time: Some((i as u64 + 1) * 100),
+                history: None,
+                line_range: None,
+                stats: SourceStats::default(),
+                symbols: None,
             })
             .collect();
 
@@ -289,4 +378,55 @@ mod tests {
 
         assert_eq!(original, after);
     }
+
+    #[test]
+    fn test_sort_files_extension() {
+        let mut files = vec![
+            entry("src/main.rs", "", 0),
+            entry("README.md", "", 0),
+            entry("docs/guide.md", "", 0),
+            entry("Cargo.toml", "", 0),
+        ];
+        sort_files(&mut files, Some(FileSortMethod::Extension));
+        assert_eq!(
+            paths(&files),
+            vec!["README.md", "docs/guide.md", "src/main.rs", "Cargo.toml"]
+        );
+    }
+
+    #[test]
+    fn test_sort_files_tokens_desc() {
+        let mut files = vec![
+            entry("b.rs", "", 10),
+            entry("a.rs", "", 500),
+            entry("c.rs", "", 10),
+        ];
+        sort_files(&mut files, Some(FileSortMethod::TokensDesc));
+        assert_eq!(paths(&files), vec!["a.rs", "b.rs", "c.rs"]);
+    }
+
+    #[test]
+    fn test_sort_files_imports() {
+        let mut files = vec![
+            entry("src/main.rs", "use crate::util::helper;\nfn main() {}\n", 0),
+            entry("src/util.rs", "use crate::config::Config;\n", 0),
+            entry("src/config.rs", "pub struct Config;\n", 0),
+            entry("README.md", "# Readme\n", 0),
+        ];
+        sort_files(&mut files, Some(FileSortMethod::Imports));
+        assert_eq!(
+            paths(&files),
+            vec!["README.md", "src/config.rs", "src/util.rs", "src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn test_sort_method_cycle() {
+        let mut method = FileSortMethod::NameAsc;
+        for _ in 0..FileSortMethod::ALL.len() - 1 {
+            method = method.next();
+            assert_ne!(method, FileSortMethod::NameAsc);
+        }
+        assert_eq!(method.next(), FileSortMethod::NameAsc);
+    }
 }
@@ -0,0 +1,109 @@
+//! # Split Tests
+//!
+//! Tests for the prompt split into parts under a token ceiling: the files of each part,
+//! the headers, the source tree repeated in each part and the names of the part files.
+
+use code2prompt_core::{
+    configuration::Code2PromptConfig,
+    session::Code2PromptSession,
+    sort::FileSortMethod,
+    split::{part_header, part_path},
+    template::OutputFormat,
+};
+use rstest::*;
+use std::fs;
+use std::path::Path;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+/// Creates a project of five files of about 300 tokens each
+#[fixture]
+fn project() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    for name in ["a.rs", "b.rs", "c.rs", "d.rs", "e.rs"] {
+        let content: String = (0..30)
+            .map(|i| format!("fn {}_{}() -> usize {{ {} }}\n", &name[..1], i, i))
+            .collect();
+        fs::write(dir.path().join(name), content).expect("Failed to write file");
+    }
+    dir
+}
+
+fn session(dir: &TempDir, output_format: OutputFormat) -> Code2PromptSession {
+    let config = Code2PromptConfig::builder()
+        .path(dir.path().to_path_buf())
+        .sort_method(Some(FileSortMethod::NameAsc))
+        .output_format(output_format)
+        .instruction(Some("Find the bug.".to_string()))
+        .build()
+        .expect("Failed to build config");
+    let mut session = Code2PromptSession::new(config);
+    session.load_codebase().expect("Failed to load codebase");
+    session
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn test_parts_under_the_ceiling(project: TempDir) {
+        let session = session(&project, OutputFormat::Markdown);
+        let parts = session.render_parts(1000).unwrap();
+
+        assert!(parts.len() > 1);
+        let files: Vec<&String> = parts.iter().flat_map(|part| &part.files).collect();
+        assert_eq!(files, vec!["a.rs", "b.rs", "c.rs", "d.rs", "e.rs"]);
+        for (index, part) in parts.iter().enumerate() {
+            assert!(part.token_count <= 1000, "{}", part.token_count);
+            assert!(
+                part.prompt
+                    .starts_with(&part_header(index + 1, parts.len()))
+            );
+            assert!(part.prompt.contains("Source Tree:"));
+            assert!(part.prompt.contains("e.rs"), "the tree lists every file");
+        }
+        assert!(parts.last().unwrap().prompt.contains("Find the bug."));
+        assert!(!parts[0].prompt.contains("Find the bug."));
+    }
+
+    #[rstest]
+    fn test_single_part(project: TempDir) {
+        let session = session(&project, OutputFormat::Markdown);
+        let parts = session.render_parts(100_000).unwrap();
+
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].files.len(), 5);
+        assert!(
+            parts[0]
+                .prompt
+                .starts_with("[Part 1/1 of the prompt, the last one")
+        );
+    }
+
+    #[rstest]
+    fn test_file_over_the_ceiling(project: TempDir) {
+        let session = session(&project, OutputFormat::Xml);
+        let parts = session.render_parts(50).unwrap();
+
+        assert_eq!(parts.len(), 5);
+        assert!(parts.iter().all(|part| part.files.len() == 1));
+    }
+
+    #[rstest]
+    fn test_json_cannot_be_split(project: TempDir) {
+        let session = session(&project, OutputFormat::Json);
+        assert!(session.render_parts(1000).is_err());
+    }
+
+    #[rstest]
+    #[case("prompt.md", 2, "prompt.part2.md")]
+    #[case("out/prompt", 1, "out/prompt.part1")]
+    #[case("out/prompt.tar.xml", 3, "out/prompt.tar.part3.xml")]
+    fn test_part_path(#[case] path: &str, #[case] index: usize, #[case] expected: &str) {
+        assert_eq!(part_path(Path::new(path), index), Path::new(expected));
+    }
+}
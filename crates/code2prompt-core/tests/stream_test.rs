@@ -0,0 +1,216 @@
+//! Integration tests for the streaming traversal and renderer
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::line_range::LineRange;
+use code2prompt_core::session::Code2PromptSession;
+use code2prompt_core::sort::FileSortMethod;
+use code2prompt_core::template::OutputFormat;
+use git2::{Repository, Signature};
+use std::collections::HashMap;
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_project() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "pub mod utils;\n").unwrap();
+        fs::write(dir.path().join("README.md"), "# Test Project\n").unwrap();
+        fs::write(dir.path().join("logo.png"), [0x89, 0x50, 0x00, 0x00, 0x01]).unwrap();
+        dir
+    }
+
+    fn session(dir: &TempDir, output_format: OutputFormat) -> Code2PromptSession {
+        let config = Code2PromptConfig::builder()
+            .repeat_instruction(output_format == OutputFormat::Xml)
+            .path(dir.path().to_path_buf())
+            .sort_method(FileSortMethod::NameAsc)
+            .output_format(output_format)
+            .instruction(Some("Review the code".to_string()))
+            .build()
+            .unwrap();
+        Code2PromptSession::new(config)
+    }
+
+    #[test]
+    fn test_stream_files_matches_load_codebase() {
+        let dir = create_test_project();
+        let mut loaded = session(&dir, OutputFormat::Markdown);
+        loaded.load_codebase().unwrap();
+
+        let mut streamed = session(&dir, OutputFormat::Markdown);
+        let files: Vec<_> = streamed.stream_files().unwrap().collect();
+
+        let expected = loaded.data.files.as_ref().unwrap();
+        assert_eq!(files.len(), 3);
+        for (file, expected) in files.iter().zip(expected) {
+            assert_eq!(file.path, expected.path);
+            assert_eq!(file.code, expected.code);
+        }
+        assert_eq!(streamed.data.source_tree, loaded.data.source_tree);
+        assert!(streamed.data.files.is_none());
+    }
+
+    #[test]
+    fn test_estimate_lists_files_without_reading_them() {
+        let dir = create_test_project();
+        let mut loaded = session(&dir, OutputFormat::Markdown);
+        loaded.load_codebase().unwrap();
+
+        let mut estimated = session(&dir, OutputFormat::Markdown);
+        let estimate = estimated.estimate().unwrap();
+
+        // Binary files cannot be told apart without reading them
+        let paths: Vec<&str> = estimate.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["README.md", "logo.png", "src/lib.rs", "src/main.rs"]
+        );
+        assert_eq!(estimate.total_size, 15 + 5 + 15 + 13);
+        assert!(estimate.files.iter().all(|file| file.token_count > 0));
+        assert!(estimate.token_count > estimate.files.iter().map(|f| f.token_count).sum());
+        assert_eq!(
+            Some(&estimate.source_tree),
+            loaded.data.source_tree.as_ref()
+        );
+        assert!(estimated.data.files.is_none());
+    }
+
+    #[test]
+    fn test_render_to_writer_matches_generate_prompt() {
+        let dir = create_test_project();
+        for output_format in [OutputFormat::Markdown, OutputFormat::Xml] {
+            let rendered = session(&dir, output_format).generate_prompt().unwrap();
+
+            let mut output = Vec::new();
+            let streamed = session(&dir, output_format)
+                .render_to_writer(&mut output)
+                .unwrap();
+
+            assert_eq!(String::from_utf8(output).unwrap(), rendered.prompt);
+            assert_eq!(streamed.files, rendered.files);
+            assert!(streamed.token_count > 0);
+        }
+    }
+
+    /// Commits every file of the project
+    fn commit_all(dir: &TempDir) {
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_render_to_writer_matches_generate_prompt_with_file_details() {
+        let dir = create_test_project();
+        fs::write(
+            dir.path().join("src/main.rs"),
+            "fn main() {\n    run();\n}\n\nfn run() {}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("tags"),
+            "main\tsrc/main.rs\t1;\"\tf\nrun\tsrc/main.rs\t5;\"\tf\n",
+        )
+        .unwrap();
+        commit_all(&dir);
+
+        for output_format in [OutputFormat::Markdown, OutputFormat::Xml] {
+            let config = || {
+                Code2PromptConfig::builder()
+                    .path(dir.path().to_path_buf())
+                    .sort_method(FileSortMethod::NameAsc)
+                    .output_format(output_format)
+                    .exclude_patterns(vec!["tags".to_string()])
+                    .tags_file(Some(dir.path().join("tags")))
+                    .file_history(HashMap::from([("src/main.rs".to_string(), 1)]))
+                    .line_ranges(HashMap::from([(
+                        "src/lib.rs".to_string(),
+                        vec![LineRange::new(1, 1).unwrap()],
+                    )]))
+                    .build()
+                    .unwrap()
+            };
+            let rendered = Code2PromptSession::new(config()).generate_prompt().unwrap();
+            assert!(rendered.prompt.contains("line=\"5\"") || rendered.prompt.contains("`run`"));
+            assert!(rendered.prompt.contains("Initial commit"));
+
+            let mut output = Vec::new();
+            Code2PromptSession::new(config())
+                .render_to_writer(&mut output)
+                .unwrap();
+            assert_eq!(String::from_utf8(output).unwrap(), rendered.prompt);
+        }
+    }
+
+    #[test]
+    fn test_render_to_writer_rejects_position_dependent_files() {
+        let dir = create_test_project();
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .template_str("{{#each files}}{{@index}}. {{path}}\n{{/each}}")
+            .template_name("numbered")
+            .build()
+            .unwrap();
+
+        let mut output = Vec::new();
+        let result = Code2PromptSession::new(config).render_to_writer(&mut output);
+        assert!(result.is_err());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_render_to_writer_with_custom_template() {
+        let dir = create_test_project();
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .sort_method(FileSortMethod::NameAsc)
+            .template_str("Files:\n{{#each files}}- {{path}} ({{extension}})\n{{/each}}Done")
+            .template_name("list")
+            .build()
+            .unwrap();
+
+        let mut output = Vec::new();
+        Code2PromptSession::new(config)
+            .render_to_writer(&mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Files:\n- README.md (md)\n- src/lib.rs (rs)\n- src/main.rs (rs)\nDone"
+        );
+    }
+
+    #[test]
+    fn test_render_to_writer_rejects_several_file_loops() {
+        let dir = create_test_project();
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .template_str("{{#each files}}{{path}}{{/each}}\n---\n{{#each files}}{{code}}{{/each}}")
+            .template_name("twice")
+            .build()
+            .unwrap();
+
+        let mut output = Vec::new();
+        let result = Code2PromptSession::new(config).render_to_writer(&mut output);
+        assert!(result.is_err());
+        assert!(output.is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+//! # Summarize Tests
+//!
+//! Tests for the files over a token cap replaced with a summary: the label in the output,
+//! the summaries cached by content hash, and the files left as they are.
+
+use anyhow::{Result, bail};
+use code2prompt_core::{
+    configuration::Code2PromptConfig,
+    session::Code2PromptSession,
+    summarize::{FileSummarizer, SummaryCache},
+};
+use rstest::*;
+use std::fs;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+/// A summarizer counting its calls, failing on the files named `broken`
+#[derive(Debug, Default)]
+struct FakeSummarizer {
+    calls: AtomicUsize,
+}
+
+impl FileSummarizer for FakeSummarizer {
+    fn summarize(&self, prompt: &str) -> Result<String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if prompt.contains("`broken.rs`") {
+            bail!("model unavailable");
+        }
+        Ok("Generated parser tables.".to_string())
+    }
+}
+
+/// Creates a project with a large file, a large broken file and a small file
+#[fixture]
+fn project() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let large: String = (0..200)
+        .map(|i| format!("const TABLE_{}: [u8; 4] = [{}, 1, 2, 3];\n", i, i))
+        .collect();
+    fs::write(dir.path().join("parser.rs"), &large).unwrap();
+    fs::write(
+        dir.path().join("broken.rs"),
+        format!("// broken\n{}", large),
+    )
+    .unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    dir
+}
+
+fn session(dir: &TempDir, summarizer: Arc<FakeSummarizer>) -> Code2PromptSession {
+    let config = Code2PromptConfig::builder()
+        .path(dir.path().to_path_buf())
+        .summarize_over(Some(500))
+        .summary_cache(Some(dir.path().join("cache/summaries.json")))
+        .exclude_patterns(vec!["cache/**".to_string()])
+        .build()
+        .expect("Failed to build config");
+    let mut session = Code2PromptSession::new(config);
+    session.set_summarizer(summarizer);
+    session
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn test_oversized_files_are_summarized(project: TempDir) {
+        let summarizer = Arc::new(FakeSummarizer::default());
+        let mut session = session(&project, summarizer.clone());
+        let rendered = session.generate_prompt().unwrap();
+
+        assert!(
+            rendered
+                .prompt
+                .contains("[Summary written by a model in place of the ")
+        );
+        assert!(rendered.prompt.contains("Generated parser tables."));
+        assert!(rendered.prompt.contains("fn main() {}"));
+        assert_eq!(summarizer.calls.load(Ordering::SeqCst), 2);
+
+        // The broken file keeps its content
+        let files = session.data.files.as_ref().unwrap();
+        let file = |path: &str| files.iter().find(|file| file.path == path).unwrap();
+        assert!(file("broken.rs").code.contains("TABLE_199"));
+        assert!(!file("parser.rs").code.contains("TABLE_199"));
+        assert!(file("parser.rs").token_count < 500);
+    }
+
+    #[rstest]
+    fn test_summaries_are_cached(project: TempDir) {
+        // A copy of a summarized file shares its summary
+        fs::copy(
+            project.path().join("parser.rs"),
+            project.path().join("parser_copy.rs"),
+        )
+        .unwrap();
+        let summarizer = Arc::new(FakeSummarizer::default());
+        let mut session = session(&project, summarizer.clone());
+        session.generate_prompt().unwrap();
+        let summarized = session.summarize_oversized_files().unwrap();
+        assert!(summarized.is_empty(), "the summaries are already in place");
+        assert_eq!(summarizer.calls.load(Ordering::SeqCst), 3);
+
+        let mut session = self::session(&project, summarizer.clone());
+        session.load_codebase().unwrap();
+        let summarized = session.summarize_oversized_files().unwrap();
+        assert!(summarized.contains(&"parser.rs".to_string()));
+        assert!(summarized.contains(&"parser_copy.rs".to_string()));
+        assert!(!summarized.contains(&"broken.rs".to_string()));
+        // Only the broken file is asked again
+        assert_eq!(summarizer.calls.load(Ordering::SeqCst), 4);
+
+        let cache = SummaryCache::load(&project.path().join("cache/summaries.json")).unwrap();
+        assert_eq!(cache.summaries.len(), 1);
+    }
+
+    #[rstest]
+    fn test_no_summarizer(project: TempDir) {
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .summarize_over(Some(500))
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        let rendered = session.generate_prompt().unwrap();
+        assert!(rendered.prompt.contains("TABLE_199"));
+    }
+}
@@ -0,0 +1,194 @@
+//! # Tags File Tests
+//!
+//! Tests for the symbols read from a tags file: the formats of ctags and GNU Global, the
+//! symbols given to the files, and the selection of the files defining some symbols.
+
+use code2prompt_core::{
+    configuration::Code2PromptConfig,
+    session::Code2PromptSession,
+    tags::{TagSymbol, TagsIndex},
+    template::OutputFormat,
+};
+use rstest::*;
+use std::fs;
+use std::path::Path;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+const CTAGS: &str = "!_TAG_FILE_FORMAT\t2\t/extended format/\n\
+!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted/\n\
+Session\tsrc/session.rs\t/^pub struct Session {$/;\"\ts\tline:3\n\
+load\tsrc/session.rs\t/^    pub fn load(\\tpath) {$/;\"\tkind:method\tline:7\timpl:Session\n\
+main\t./src/main.rs\t1;\"\tf\n\
+helper\t../outside.rs\t4;\"\tf\n";
+
+/// Creates a project with a ctags tags file at its root
+#[fixture]
+fn project() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.path().join("src/session.rs"), "pub struct Session {}\n").unwrap();
+    fs::write(dir.path().join("src/util.rs"), "pub fn util() {}\n").unwrap();
+    fs::write(dir.path().join("tags"), CTAGS).unwrap();
+    dir
+}
+
+fn symbol(name: &str, kind: Option<&str>, line: Option<usize>) -> TagSymbol {
+    TagSymbol {
+        name: name.to_string(),
+        kind: kind.map(str::to_string),
+        line,
+    }
+}
+
+fn config(dir: &TempDir) -> Code2PromptConfig {
+    Code2PromptConfig::builder()
+        .path(dir.path().to_path_buf())
+        .tags_file(Some(dir.path().join("tags")))
+        .exclude_patterns(vec!["tags".to_string()])
+        .build()
+        .unwrap()
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn test_parse_ctags() {
+        let index = TagsIndex::parse(CTAGS);
+        assert_eq!(
+            index.files["src/session.rs"],
+            vec![
+                symbol("Session", Some("struct"), Some(3)),
+                symbol("load", Some("method"), Some(7)),
+            ]
+        );
+        assert_eq!(
+            index.files["src/main.rs"],
+            vec![symbol("main", Some("function"), Some(1))]
+        );
+    }
+
+    #[rstest]
+    fn test_parse_cross_references() {
+        let global = "Session          12 src/session.rs   pub struct\tSession {\n";
+        let ctags = "load             method       30 src/session.rs   pub fn load() {\n";
+        let index = TagsIndex::parse(&format!("{}{}", global, ctags));
+        assert_eq!(
+            index.files["src/session.rs"],
+            vec![
+                symbol("Session", None, Some(12)),
+                symbol("load", Some("method"), Some(30)),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_load_relative_to_root(project: TempDir) {
+        let index = TagsIndex::load(&project.path().join("tags"), project.path()).unwrap();
+        assert_eq!(
+            index.files.keys().collect::<Vec<_>>(),
+            vec!["src/main.rs", "src/session.rs"]
+        );
+        assert_eq!(
+            index.files_defining(&["load".to_string(), "main".to_string()]),
+            ["src/main.rs".to_string(), "src/session.rs".to_string()].into()
+        );
+        assert!(index.symbols(Path::new("src/util.rs")).is_none());
+    }
+
+    #[rstest]
+    fn test_files_carry_symbols(project: TempDir) {
+        let mut session = Code2PromptSession::new(config(&project));
+        session.load_codebase().unwrap();
+        let files = session.data.files.as_ref().unwrap();
+        let symbols = |path: &str| {
+            files
+                .iter()
+                .find(|file| file.path == path)
+                .unwrap()
+                .symbols
+                .clone()
+        };
+        assert_eq!(symbols("src/session.rs").unwrap().len(), 2);
+        assert_eq!(symbols("src/util.rs"), None);
+
+        let prompt = session.generate_prompt().unwrap().prompt;
+        assert!(
+            prompt.contains("Symbols of `src/session.rs`: `Session` (struct), `load` (method)")
+        );
+    }
+
+    #[rstest]
+    fn test_xml_symbols(project: TempDir) {
+        let mut config = config(&project);
+        config.output_format = OutputFormat::Xml;
+        let prompt = Code2PromptSession::new(config)
+            .generate_prompt()
+            .unwrap()
+            .prompt;
+        assert!(prompt.contains(r#"<symbol name="main" kind="function" line="1" />"#));
+    }
+
+    #[rstest]
+    fn test_symbol_selection(project: TempDir) {
+        let mut config = config(&project);
+        config.symbols = vec!["Session".to_string()];
+        let mut session = Code2PromptSession::new(config);
+        session.load_codebase().unwrap();
+        let paths: Vec<&str> = session
+            .data
+            .files
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["src/session.rs"]);
+    }
+
+    #[rstest]
+    fn test_symbol_selection_without_tags(project: TempDir) {
+        let mut config = config(&project);
+        config.tags_file = None;
+        config.symbols = vec!["Session".to_string()];
+        assert!(Code2PromptSession::new(config).load_codebase().is_err());
+    }
+
+    #[rstest]
+    fn test_reload_reads_modified_tags(project: TempDir) {
+        let mut session = Code2PromptSession::new(config(&project));
+        session.load_codebase().unwrap();
+
+        let tags = project.path().join("tags");
+        fs::write(&tags, "util\tsrc/util.rs\t1;\"\tf\n").unwrap();
+        let modified = std::time::SystemTime::now() + std::time::Duration::from_secs(10);
+        fs::File::options()
+            .write(true)
+            .open(&tags)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        session.load_codebase().unwrap();
+
+        let files = session.data.files.as_ref().unwrap();
+        let symbols = |path: &str| {
+            files
+                .iter()
+                .find(|file| file.path == path)
+                .unwrap()
+                .symbols
+                .clone()
+        };
+        assert_eq!(symbols("src/session.rs"), None);
+        assert_eq!(
+            symbols("src/util.rs"),
+            Some(vec![symbol("util", Some("function"), Some(1))])
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! Tests for the Handlebars helpers available in templates
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::session::Code2PromptSession;
+use code2prompt_core::template::{handlebars_setup, render_template};
+use code2prompt_core::template_helpers::{
+    TRUNCATED_MARKER, dedent, strip_comments, truncate_tokens,
+};
+use handlebars::handlebars_helper;
+use serde_json::json;
+use std::fs;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, data: serde_json::Value) -> String {
+        let handlebars = handlebars_setup(template, "test").unwrap();
+        render_template(&handlebars, "test", &data).unwrap()
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let code = "// header\nfn main() {\n    let url = \"http://x\"; // trailing\n    /* block\n       comment */\n    run();\n}\n";
+        assert_eq!(
+            strip_comments(code, "rs"),
+            "fn main() {\n    let url = \"http://x\";\n    run();\n}\n"
+        );
+
+        let code = "# setup\nx = '#not a comment'  # note\n";
+        assert_eq!(strip_comments(code, "py"), "x = '#not a comment'\n");
+
+        // Unknown languages are left as is
+        assert_eq!(strip_comments("# title\n", "txt"), "# title\n");
+    }
+
+    #[test]
+    fn test_dedent_and_truncate_tokens() {
+        assert_eq!(dedent("    a\n      b\n\n    c\n"), "a\n  b\n\nc\n");
+
+        let text = "line one\n".repeat(50);
+        assert_eq!(truncate_tokens(&text, 1000), text);
+        let truncated = truncate_tokens(&text, 10);
+        assert!(truncated.starts_with("line one\nline one\n"));
+        assert!(truncated.ends_with(TRUNCATED_MARKER));
+        assert!(truncated.len() < 60);
+    }
+
+    #[test]
+    fn test_builtin_helpers_in_templates() {
+        let data = json!({
+            "path": "src/utils/Helper.rs",
+            "code": "    // comment\n    fn helper() {}\n",
+        });
+        assert_eq!(
+            render(
+                "{{basename path}} {{uppercase (basename path)}} {{lowercase path}}",
+                data.clone()
+            ),
+            "Helper.rs HELPER.RS src/utils/helper.rs"
+        );
+        assert_eq!(
+            render("[{{dedent (strip_comments code \"rs\")}}]", data),
+            "[fn helper() {}\n]"
+        );
+    }
+
+    handlebars_helper!(shout: |text: str| format!("{}!", text.to_uppercase()));
+    handlebars_helper!(basename_with_dir: |path: str| format!("<{}>", path));
+
+    #[test]
+    fn test_session_registered_helpers() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .template_str(
+                "{{#each files}}{{shout (basename path)}} {{basename path}}{{/each}}".to_string(),
+            )
+            .template_name("custom".to_string())
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session
+            .register_helper("shout", Arc::new(shout))
+            .register_helper("basename", Arc::new(basename_with_dir));
+        assert_eq!(
+            session.helpers.names().collect::<Vec<_>>(),
+            ["shout", "basename"]
+        );
+
+        let rendered = session.generate_prompt().unwrap();
+        // Registered helpers replace the built-in ones of the same name
+        assert_eq!(rendered.prompt, "<MAIN.RS>! <main.rs>");
+    }
+}
@@ -1,4 +1,8 @@
-use code2prompt_core::template::{extract_undefined_variables, handlebars_setup, render_template};
+use code2prompt_core::template::{
+    extract_template_variables, extract_undefined_variables, handlebars_setup, map_template_args,
+    nest_variables, parse_front_matter, render_template,
+};
+use std::collections::HashMap;
 
 #[cfg(test)]
 mod tests {
@@ -34,6 +38,78 @@ mod tests {
         let template_str = "{{name}} is learning {{language}} and {{framework}}!";
         let variables = extract_undefined_variables(template_str);
         assert_eq!(variables, vec!["name", "language", "framework"]);
+
+        let nested = extract_undefined_variables("{{meta.author}} {{config.line_numbers}}");
+        assert_eq!(nested, vec!["meta.author"]);
+
+        let stash = extract_undefined_variables("{{#if git_stash}}{{ git_stash }}{{/if}}");
+        assert!(stash.is_empty());
+    }
+
+    #[test]
+    fn test_extract_variables_skips_block_scopes() {
+        let template = "{{#if ready}}{{name}}{{else}}{{fallback}}{{/if}}\n\
+            {{#each items}}{{this}} {{@index}} {{label}}{{else}}{{empty}}{{/each}}\n\
+            {{#with meta}}{{author}}{{/with}}\n\
+            {{#each rows as |row index|}}{{row.cell}}{{/each}}\n\
+            {{{raw}}} {{~trimmed~}} {{> file_header}} {{!-- {{commented}} --}}";
+        assert_eq!(
+            extract_template_variables(template),
+            vec!["name", "fallback", "empty", "raw", "trimmed"]
+        );
+    }
+
+    #[test]
+    fn test_extract_undefined_variables_skips_this() {
+        let template = "{{#each context_delta.unchanged}}- `{{this}}`\n{{/each}}{{this}}";
+        assert!(extract_undefined_variables(template).is_empty());
+
+        let strings = extract_undefined_variables("{{#each tickets}}{{this}}{{/each}} {{owner}}");
+        assert_eq!(strings, vec!["owner"]);
+    }
+
+    #[test]
+    fn test_extract_undefined_variables_skips_commit_fields() {
+        let template = "{{#each files}}{{#if history}}{{#each history}}\
+            {{hash}} - {{date}} - {{author}}: {{message}}\n{{diff}}\n\
+            {{/each}}{{/if}}{{/each}}";
+        assert!(extract_undefined_variables(template).is_empty());
+    }
+
+    #[test]
+    fn test_extract_undefined_variables_skips_symbol_fields() {
+        let template = "{{#each files}}{{#each symbols}}{{#unless @first}}, {{/unless}}\
+            `{{name}}`{{#if kind}} ({{kind}}){{/if}}{{/each}}{{/each}}";
+        assert!(extract_undefined_variables(template).is_empty());
+    }
+
+    #[test]
+    fn test_default_templates_have_no_undefined_variables() {
+        for template in [
+            include_str!("../src/default_template_md.hbs"),
+            include_str!("../src/default_template_xml.hbs"),
+        ] {
+            assert_eq!(extract_undefined_variables(template), Vec::<String>::new());
+        }
+    }
+
+    #[test]
+    fn test_nest_variables() {
+        let variables = HashMap::from([
+            ("meta.author".to_string(), "alice".to_string()),
+            ("meta.ticket".to_string(), "JIRA-42".to_string()),
+            ("meta".to_string(), "shadowed".to_string()),
+            ("name".to_string(), "Bernard".to_string()),
+        ]);
+        let nested = serde_json::Value::Object(nest_variables(&variables));
+        assert_eq!(
+            nested,
+            json!({ "meta": { "author": "alice", "ticket": "JIRA-42" }, "name": "Bernard" })
+        );
+
+        let handlebars = handlebars_setup("{{meta.author}} ({{meta.ticket}})", "nested").unwrap();
+        let rendered = render_template(&handlebars, "nested", &nested).unwrap();
+        assert_eq!(rendered, "alice (JIRA-42)");
     }
 
     #[test]
@@ -49,4 +125,30 @@ mod tests {
             Err(e) => panic!("Template rendering failed: {}", e),
         }
     }
+
+    #[test]
+    fn test_parse_front_matter() {
+        let template_str = "{{!--\nname = \"Bug fix\"\ndescription = \"Fix a bug\"\nargs = [\"error\", \"component\"]\n--}}\n{{error}}";
+        let front_matter = parse_front_matter(template_str);
+        assert_eq!(front_matter.name.as_deref(), Some("Bug fix"));
+        assert_eq!(front_matter.description.as_deref(), Some("Fix a bug"));
+        assert_eq!(front_matter.args, vec!["error", "component"]);
+
+        assert_eq!(parse_front_matter("{{name}}").args, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_map_template_args() {
+        let front_matter = parse_front_matter("{{!-- args = [\"error\", \"component\"] --}}");
+        let args = vec!["component=auth".to_string(), "panic at line 42".to_string()];
+        let variables = map_template_args(&front_matter, &args).unwrap();
+        assert_eq!(variables["error"], "panic at line 42");
+        assert_eq!(variables["component"], "auth");
+
+        let dotted = map_template_args(&front_matter, &["meta.author=alice".to_string()]).unwrap();
+        assert_eq!(dotted["meta.author"], "alice");
+
+        let too_many = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert!(map_template_args(&front_matter, &too_many).is_err());
+    }
 }
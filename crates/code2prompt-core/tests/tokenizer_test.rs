@@ -0,0 +1,218 @@
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "sentencepiece")]
+    use code2prompt_core::sentencepiece::SentencePieceTokenizer;
+    use code2prompt_core::tokenizer::{
+        TokenCountMode, TokenizerType, bytes_per_token, count_tokens, count_tokens_with_mode,
+        estimate_tokens, prepare_tokenizer,
+    };
+    #[cfg(feature = "sentencepiece")]
+    use code2prompt_core::tokenizer::{Tokenizer, load_sentencepiece_model};
+
+    #[test]
+    fn test_estimate_tokens_uses_extension_density() {
+        let text = "x".repeat(36);
+        assert_eq!(estimate_tokens(&text, ""), 10);
+        assert_eq!(estimate_tokens(&text, "json"), 12);
+        assert_eq!(estimate_tokens(&text, "md"), 9);
+        assert_eq!(bytes_per_token("RS"), bytes_per_token("rs"));
+        assert_eq!(estimate_tokens("", "rs"), 0);
+    }
+
+    #[test]
+    fn test_count_tokens_with_mode() {
+        let text = "fn main() {\n    println!(\"Hello, world!\");\n}\n";
+        let encoding = TokenizerType::Cl100kBase;
+        assert_eq!(
+            count_tokens_with_mode(text, "rs", &encoding, None, TokenCountMode::Exact),
+            count_tokens(text, &encoding, None)
+        );
+        assert_eq!(
+            count_tokens_with_mode(text, "rs", &encoding, None, TokenCountMode::Heuristic),
+            estimate_tokens(text, "rs")
+        );
+    }
+
+    #[test]
+    fn test_char_ratio_tokenizers() {
+        assert_eq!(count_tokens("abcdefg", &TokenizerType::Claude, None), 2);
+        assert_eq!(count_tokens("abcdefgh", &TokenizerType::Chars, None), 2);
+        assert_eq!(count_tokens("abcdefghi", &TokenizerType::Chars, None), 3);
+        // Characters, not bytes
+        assert_eq!(count_tokens("éééé", &TokenizerType::Chars, None), 1);
+        let encoding: TokenizerType = serde_json::from_str("\"anthropic\"").unwrap();
+        assert_eq!(encoding, TokenizerType::Claude);
+    }
+
+    #[cfg(feature = "sentencepiece")]
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    #[cfg(feature = "sentencepiece")]
+    fn message(field: u64, content: &[u8]) -> Vec<u8> {
+        let mut bytes = varint(field << 3 | 2);
+        bytes.extend(varint(content.len() as u64));
+        bytes.extend(content);
+        bytes
+    }
+
+    #[cfg(feature = "sentencepiece")]
+    /// A serialized SentencePiece model with the given pieces and model type
+    fn model(pieces: &[(&str, f32, u64)], model_type: u64, byte_fallback: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (text, score, piece_type) in pieces {
+            let mut piece = message(1, text.as_bytes());
+            piece.push(2 << 3 | 5);
+            piece.extend(score.to_le_bytes());
+            piece.extend(varint(3 << 3));
+            piece.extend(varint(*piece_type));
+            bytes.extend(message(1, &piece));
+        }
+        let mut trainer_spec = varint(3 << 3);
+        trainer_spec.extend(varint(model_type));
+        trainer_spec.extend(varint(35 << 3));
+        trainer_spec.extend(varint(byte_fallback as u64));
+        bytes.extend(message(2, &trainer_spec));
+        bytes
+    }
+
+    #[cfg(feature = "sentencepiece")]
+    #[test]
+    fn test_sentencepiece_unigram() {
+        let pieces = [
+            ("<unk>", 0.0, 2),
+            ("\u{2581}hello", -1.0, 1),
+            ("\u{2581}world", -1.0, 1),
+            ("\u{2581}he", -2.0, 1),
+            ("llo", -3.0, 1),
+            ("\u{2581}", -4.0, 1),
+            ("h", -5.0, 1),
+            ("e", -5.0, 1),
+            ("l", -5.0, 1),
+            ("o", -5.0, 1),
+            ("unused", -1.0, 5),
+            ("<0xC3>", 0.0, 6),
+            ("<0xA9>", 0.0, 6),
+        ];
+        let tokenizer = SentencePieceTokenizer::from_bytes(&model(&pieces, 1, false)).unwrap();
+        assert_eq!(tokenizer.count_tokens("hello world"), 2);
+        assert_eq!(tokenizer.count_tokens("hello  world"), 2);
+        assert_eq!(tokenizer.count_tokens("hellx"), 4);
+        assert_eq!(tokenizer.count_tokens(""), 0);
+
+        let tokenizer = SentencePieceTokenizer::from_bytes(&model(&pieces, 1, true)).unwrap();
+        assert_eq!(tokenizer.count_tokens("helé"), 4);
+
+        assert!(SentencePieceTokenizer::from_bytes(b"\x0a\xff").is_err());
+    }
+
+    #[cfg(feature = "sentencepiece")]
+    /// Compares the counts of a real `tokenizer.model` with those of the `tokenizer.json`
+    /// published with it, as counted by the Hugging Face tokenizer. No model ships with the
+    /// repository: set `CODE2PROMPT_SENTENCEPIECE_DIR` to a directory holding both files,
+    /// such as a download of a Llama or Mistral model, and run the ignored tests.
+    #[test]
+    #[ignore = "needs CODE2PROMPT_SENTENCEPIECE_DIR with a tokenizer.model and tokenizer.json"]
+    fn test_sentencepiece_reference_counts() {
+        let dir = std::path::PathBuf::from(
+            std::env::var("CODE2PROMPT_SENTENCEPIECE_DIR")
+                .expect("CODE2PROMPT_SENTENCEPIECE_DIR is not set"),
+        );
+        let model = SentencePieceTokenizer::from_file(&dir.join("tokenizer.model")).unwrap();
+        let reference = tokenizers::Tokenizer::from_file(dir.join("tokenizer.json")).unwrap();
+
+        let texts = [
+            "Hello world",
+            "  leading and   repeated   spaces ",
+            "fn main() {\n    println!(\"Hello, world!\");\n}\n",
+            "def add(a, b):\n\treturn a + b  # tabs and comments\n",
+            "Ünïcödé, 日本語のテキスト, emoji 🦀 and ﬁ ligatures",
+            "<s> control tokens are plain text </s>",
+            include_str!("../src/sentencepiece.rs"),
+        ];
+        for text in texts {
+            let expected = reference.encode(text, false).unwrap().len();
+            assert_eq!(model.count_tokens(text), expected, "counting {:?}", text);
+        }
+    }
+
+    #[cfg(feature = "sentencepiece")]
+    #[test]
+    fn test_sentencepiece_bpe() {
+        let pieces = [
+            ("ll", -1.0, 1),
+            ("he", -2.0, 1),
+            ("llo", -3.0, 1),
+            ("\u{2581}he", -4.0, 1),
+            ("\u{2581}hello", -5.0, 1),
+            ("\u{2581}", -6.0, 1),
+            ("h", -6.0, 1),
+            ("e", -6.0, 1),
+            ("l", -6.0, 1),
+            ("o", -6.0, 1),
+        ];
+        let tokenizer = SentencePieceTokenizer::from_bytes(&model(&pieces, 2, false)).unwrap();
+        assert_eq!(tokenizer.count_tokens("hello"), 1);
+        assert_eq!(tokenizer.count_tokens("hell"), 2);
+        assert_eq!(tokenizer.count_tokens("hello hello"), 2);
+    }
+
+    #[cfg(feature = "sentencepiece")]
+    #[test]
+    fn test_llama_tokenizer_model() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let hello = dir.path().join("hello.model");
+        std::fs::write(
+            &hello,
+            model(
+                &[("\u{2581}hello", -1.0, 1), ("\u{2581}", -2.0, 1)],
+                1,
+                false,
+            ),
+        )
+        .unwrap();
+        let letters = dir.path().join("letters.model");
+        std::fs::write(
+            &letters,
+            model(
+                &[("\u{2581}", -1.0, 1), ("h", -1.0, 1), ("e", -1.0, 1)],
+                1,
+                false,
+            ),
+        )
+        .unwrap();
+
+        assert!(prepare_tokenizer(&TokenizerType::Llama, None).is_err());
+        assert!(
+            prepare_tokenizer(&TokenizerType::Llama, Some(&dir.path().join("missing"))).is_err()
+        );
+        // Each model file counts with its own model, whichever was loaded last
+        load_sentencepiece_model(&hello).unwrap();
+        load_sentencepiece_model(&letters).unwrap();
+        assert_eq!(
+            count_tokens("hello hello", &TokenizerType::Llama, Some(&hello)),
+            2
+        );
+        assert_eq!(
+            count_tokens("he he", &TokenizerType::Llama, Some(&letters)),
+            6
+        );
+    }
+
+    #[cfg(not(feature = "sentencepiece"))]
+    #[test]
+    fn test_llama_tokenizer_needs_feature() {
+        let path = std::path::Path::new("tokenizer.model");
+        assert!(prepare_tokenizer(&TokenizerType::Llama, Some(path)).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! Integration tests for the onboarding tour
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::session::Code2PromptSession;
+use code2prompt_core::tour::{TourSectionKind, plan_tour};
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &str) -> (String, String) {
+        (path.to_string(), content.to_string())
+    }
+
+    fn section_paths(files: &[(String, String)]) -> Vec<(TourSectionKind, Vec<String>)> {
+        plan_tour(files)
+            .sections
+            .into_iter()
+            .map(|section| {
+                (
+                    section.kind,
+                    section.stops.into_iter().map(|stop| stop.path).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_modules_follow_their_dependencies() {
+        let files = vec![
+            file("README.md", "# Demo\n"),
+            file("tests/api_test.rs", "use demo::api::serve;\n"),
+            file(
+                "src/api/mod.rs",
+                "use crate::db::Pool;\nuse super::model::User;\n",
+            ),
+            file("src/db.rs", "use crate::model::{User, Post};\n"),
+            file("src/model.rs", "//! Data model.\npub struct User;\n"),
+            file("src/lib.rs", "pub mod api;\npub mod db;\npub mod model;\n"),
+            file("src/bin/tool.rs", "fn main() {}\n"),
+        ];
+
+        assert_eq!(
+            section_paths(&files),
+            vec![
+                (
+                    TourSectionKind::EntryPoints,
+                    vec!["src/bin/tool.rs".to_string(), "src/lib.rs".to_string()]
+                ),
+                (
+                    TourSectionKind::CoreModules,
+                    vec![
+                        "src/model.rs".to_string(),
+                        "src/db.rs".to_string(),
+                        "src/api/mod.rs".to_string()
+                    ]
+                ),
+                (
+                    TourSectionKind::SupportingFiles,
+                    vec!["README.md".to_string()]
+                ),
+                (
+                    TourSectionKind::Tests,
+                    vec!["tests/api_test.rs".to_string()]
+                ),
+            ]
+        );
+
+        let tour = plan_tour(&files);
+        let model = &tour.sections[1].stops[0];
+        assert_eq!(model.summary.as_deref(), Some("Data model."));
+        assert_eq!(model.used_by, vec!["src/api/mod.rs", "src/db.rs"]);
+        assert!(
+            model
+                .note
+                .starts_with("A foundation with no local dependency")
+        );
+        assert!(
+            tour.sections[1]
+                .intro
+                .contains("The most widely used are `src/model.rs`")
+        );
+    }
+
+    #[test]
+    fn test_script_and_python_imports() {
+        let files = vec![
+            file("src/index.ts", "import { App } from './app';\n"),
+            file("src/app.tsx", "import { fetchUser } from \"./api\";\n"),
+            file("src/api/index.ts", "const http = require('../lib/http');\n"),
+            file("src/lib/http.js", "export const get = () => {};\n"),
+            file("src/app.test.tsx", "import { App } from './app';\n"),
+            file("pkg/__main__.py", "from .service import run\n"),
+            file("pkg/service.py", "from pkg.store import Store\nimport os\n"),
+            file("pkg/store.py", "class Store:\n    pass\n"),
+        ];
+        let sections = section_paths(&files);
+
+        assert_eq!(sections[0].0, TourSectionKind::EntryPoints);
+        assert_eq!(
+            sections[0].1,
+            vec!["pkg/__main__.py", "src/app.tsx", "src/index.ts"]
+        );
+        assert_eq!(
+            sections[1].1,
+            vec![
+                "pkg/store.py",
+                "pkg/service.py",
+                "src/lib/http.js",
+                "src/api/index.ts"
+            ]
+        );
+        assert_eq!(sections[2].1, vec!["src/app.test.tsx"]);
+
+        let tour = plan_tour(&files);
+        let test = &tour.sections[2].stops[0];
+        assert_eq!(test.note, "Tests `src/app.tsx`.");
+    }
+
+    #[test]
+    fn test_cycles_keep_every_file() {
+        let files = vec![
+            file("src/b.rs", "use crate::a::A;\n"),
+            file("src/a.rs", "use crate::b::B;\n"),
+            file("src/c.rs", "use crate::a::A;\n"),
+        ];
+        let sections = section_paths(&files);
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].1, vec!["src/a.rs", "src/b.rs", "src/c.rs"]);
+    }
+
+    #[test]
+    fn test_session_reorders_files() {
+        let project = TempDir::new().unwrap();
+        fs::create_dir(project.path().join("tests")).unwrap();
+        fs::write(project.path().join("tests/app_test.py"), "import app\n").unwrap();
+        fs::write(project.path().join("util.py"), "def helper():\n    pass\n").unwrap();
+        fs::write(project.path().join("app.py"), "from util import helper\n").unwrap();
+
+        let config = Code2PromptConfig::builder()
+            .path(project.path().to_path_buf())
+            .absolute_path(true)
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        assert!(session.load_onboarding_tour().is_err());
+
+        session.load_codebase().unwrap();
+        session.load_onboarding_tour().unwrap();
+
+        let tour = session.data.onboarding_tour.as_ref().unwrap();
+        let paths: Vec<&str> = tour.paths().collect();
+        assert_eq!(paths, vec!["app.py", "util.py", "tests/app_test.py"]);
+        assert!(
+            tour.sections[0].stops[0]
+                .code
+                .as_deref()
+                .unwrap()
+                .contains("from util import helper")
+        );
+
+        let files: Vec<&str> = session
+            .data
+            .files
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|file| file.path.rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(files, vec!["app.py", "util.py", "app_test.py"]);
+    }
+}
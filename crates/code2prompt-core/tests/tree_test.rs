@@ -0,0 +1,147 @@
+//! # Tree Module Tests
+//!
+//! Tests for the rendering of the source tree: collapsed directories, their annotations,
+//! empty directories and drawing styles.
+
+use code2prompt_core::{
+    configuration::Code2PromptConfig,
+    path::traverse_directory,
+    sort::FileSortMethod,
+    tree::{TreeOptions, TreeStyle},
+};
+use rstest::*;
+use std::fs;
+use tempfile::{TempDir, tempdir};
+
+// ~~~ Fixtures ~~~
+
+/// Creates a directory with nested files and an empty directory
+#[fixture]
+fn nested_dir() -> TempDir {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let files = vec![
+        ("main.rs", "fn main() {}"),
+        ("src/lib.rs", "pub fn lib() {}"),
+        ("src/util/mod.rs", "pub mod strings;"),
+        ("src/util/strings.rs", "pub fn trim() {}"),
+    ];
+    for (path, content) in files {
+        let full_path = dir.path().join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).expect("Failed to create dir");
+        fs::write(full_path, content).expect("Failed to write file");
+    }
+    fs::create_dir_all(dir.path().join("empty/nested")).expect("Failed to create dir");
+    dir
+}
+
+/// Renders the tree of a directory with the given options
+fn render_tree(dir: &TempDir, options: TreeOptions) -> String {
+    let config = Code2PromptConfig::builder()
+        .path(dir.path().to_path_buf())
+        .sort_method(Some(FileSortMethod::NameAsc))
+        .tree_options(options)
+        .build()
+        .expect("Failed to build config");
+    let (tree, _) = traverse_directory(&config, None).expect("Failed to traverse");
+    tree
+}
+
+// ~~~ Tests ~~~
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn test_empty_dirs_pruned_by_default(nested_dir: TempDir) {
+        let tree = render_tree(&nested_dir, TreeOptions::default());
+        assert!(tree.contains("strings.rs"));
+        assert!(!tree.contains("empty"));
+        assert!(!tree.contains("nested"));
+    }
+
+    #[rstest]
+    fn test_empty_dirs_kept(nested_dir: TempDir) {
+        let options = TreeOptions {
+            include_empty_dirs: true,
+            ..TreeOptions::default()
+        };
+        let tree = render_tree(&nested_dir, options);
+        assert!(tree.contains("empty"));
+        assert!(tree.contains("nested"));
+    }
+
+    #[rstest]
+    fn test_collapsed_directories_annotated(nested_dir: TempDir) {
+        let options = TreeOptions {
+            max_depth: Some(1),
+            ..TreeOptions::default()
+        };
+        let tree = render_tree(&nested_dir, options);
+        assert!(tree.contains("main.rs"));
+        assert!(tree.contains("src/ (3 files, "));
+        assert!(tree.contains(" tokens)"));
+        assert!(!tree.contains("lib.rs"));
+        assert!(!tree.contains("util"));
+    }
+
+    #[rstest]
+    fn test_all_directories_annotated(nested_dir: TempDir) {
+        let options = TreeOptions {
+            annotate_directories: true,
+            include_empty_dirs: true,
+            ..TreeOptions::default()
+        };
+        let tree = render_tree(&nested_dir, options);
+        assert!(tree.contains("src/ (3 files, "));
+        assert!(tree.contains("util/ (2 files, "));
+        assert!(tree.contains("empty/ (empty)"));
+        assert!(tree.contains("lib.rs"));
+    }
+
+    #[rstest]
+    fn test_ascii_style(nested_dir: TempDir) {
+        let options = TreeOptions {
+            style: TreeStyle::Ascii,
+            ..TreeOptions::default()
+        };
+        let tree = render_tree(&nested_dir, options);
+        assert!(tree.is_ascii());
+        assert!(tree.contains("|-- main.rs"));
+        assert!(tree.contains("`-- src"));
+        assert!(tree.contains("    |-- lib.rs"));
+    }
+
+    #[rstest]
+    fn test_indented_style(nested_dir: TempDir) {
+        let options = TreeOptions {
+            style: TreeStyle::Indented,
+            max_depth: Some(2),
+            ..TreeOptions::default()
+        };
+        let tree = render_tree(&nested_dir, options);
+        let lines: Vec<&str> = tree.lines().skip(1).collect();
+        assert_eq!(lines[..3], ["- main.rs", "- src", "  - lib.rs"]);
+        assert!(lines[3].starts_with("  - util/ (2 files, "));
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[rstest]
+    fn test_json_style(nested_dir: TempDir) {
+        let options = TreeOptions {
+            style: TreeStyle::Json,
+            max_depth: Some(1),
+            ..TreeOptions::default()
+        };
+        let tree: serde_json::Value =
+            serde_json::from_str(&render_tree(&nested_dir, options)).unwrap();
+        assert_eq!(tree["type"], "directory");
+        let children = tree["children"].as_array().unwrap();
+        assert_eq!(children[0]["name"], "main.rs");
+        assert_eq!(children[0]["type"], "file");
+        assert_eq!(children[1]["name"], "src");
+        assert_eq!(children[1]["files"], 3);
+        assert_eq!(children[1]["collapsed"], true);
+        assert!(children[1].get("children").is_none());
+    }
+}
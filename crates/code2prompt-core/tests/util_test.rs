@@ -1,4 +1,5 @@
-use code2prompt_core::util::strip_utf8_bom;
+use code2prompt_core::util::{content_hash, relative_path, strip_utf8_bom};
+use std::path::Path;
 
 #[cfg(test)]
 mod tests {
@@ -45,4 +46,29 @@ mod tests {
             "Input that is only a BOM should return an empty slice."
         );
     }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        // Reference FNV-1a values, which must never change as hashes are persisted
+        assert_eq!(content_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(content_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(
+            content_hash(b"fn main() {}"),
+            content_hash(b"fn main() { }")
+        );
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let root = Path::new("/project");
+        assert_eq!(
+            relative_path(root, Path::new("/project/src/lib.rs")),
+            "src/lib.rs"
+        );
+        assert_eq!(relative_path(root, Path::new("src/lib.rs")), "src/lib.rs");
+        assert_eq!(
+            relative_path(root, Path::new("/elsewhere/a.rs")),
+            "/elsewhere/a.rs"
+        );
+    }
 }
@@ -0,0 +1,82 @@
+//! Tests for the planning of A/B prompt variants
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::placement::Placement;
+use code2prompt_core::session::Code2PromptSession;
+use code2prompt_core::variants::{VariantAxis, plan_variants};
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(config: &Code2PromptConfig, axes: &[VariantAxis], count: usize) -> Vec<String> {
+        plan_variants(config, axes, count)
+            .unwrap()
+            .iter()
+            .map(|variant| {
+                variant
+                    .settings
+                    .values()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_variants_starts_from_configured_values() {
+        let config = Code2PromptConfig::builder()
+            .placement(Placement::Beginning)
+            .build()
+            .unwrap();
+        let axes = [VariantAxis::Placement, VariantAxis::SummaryMode];
+        assert_eq!(
+            settings(&config, &axes, 3),
+            ["beginning,full", "beginning,summarized", "end,full"]
+        );
+
+        // No more variants than combinations, duplicated axes count once
+        let axes = [
+            VariantAxis::LineNumbers,
+            VariantAxis::SummaryMode,
+            VariantAxis::LineNumbers,
+        ];
+        assert_eq!(
+            settings(&config, &axes, 10),
+            // Settings are listed in the axis order, line numbers after the summary mode
+            ["full,off", "summarized,off", "full,on", "summarized,on"]
+        );
+    }
+
+    #[test]
+    fn test_variants_apply_their_settings() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        let config = Code2PromptConfig::builder()
+            .path(dir.path().to_path_buf())
+            .instruction(Some("Fix the bug".to_string()))
+            .manifest_file(Some(dir.path().join("manifest.json")))
+            .build()
+            .unwrap();
+
+        let variants = plan_variants(&config, &[VariantAxis::Placement], 2).unwrap();
+        assert_eq!(variants[0].name, "variant-1");
+        assert_eq!(variants[1].config.placement, Placement::Beginning);
+        assert!(variants.iter().all(|v| v.config.manifest_file.is_none()));
+
+        let prompts: Vec<String> = variants
+            .into_iter()
+            .map(|v| {
+                Code2PromptSession::new(v.config)
+                    .generate_prompt()
+                    .unwrap()
+                    .prompt
+            })
+            .collect();
+        assert!(prompts[0].trim_end().ends_with("Fix the bug"));
+        assert!(prompts[1].starts_with("## Instruction\n\nFix the bug"));
+    }
+}
@@ -0,0 +1,144 @@
+//! Integration tests for the selection of workspace packages
+
+use code2prompt_core::architecture::WorkspacePackage;
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::session::Code2PromptSession;
+use code2prompt_core::workspace::PackageScope;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, path: &str, ecosystem: &'static str, deps: &[&str]) -> WorkspacePackage {
+        WorkspacePackage {
+            name: name.to_string(),
+            path: path.to_string(),
+            ecosystem,
+            internal_dependencies: deps.iter().map(|dep| dep.to_string()).collect(),
+            external_dependencies: 0,
+        }
+    }
+
+    fn monorepo() -> Vec<WorkspacePackage> {
+        vec![
+            package("root", ".", "npm", &[]),
+            package(
+                "payments-service",
+                "services/payments",
+                "cargo",
+                &["ledger"],
+            ),
+            package("ledger", "libs/ledger", "cargo", &["money"]),
+            package("money", "libs/money", "cargo", &[]),
+            package("search-service", "services/search", "cargo", &["money"]),
+            package("ledger", "web/ledger", "npm", &[]),
+        ]
+    }
+
+    fn request(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_scope_keeps_transitive_dependencies() {
+        let scope = PackageScope::resolve(&monorepo(), &request(&["payments-service"])).unwrap();
+        assert_eq!(scope.packages, vec!["ledger", "money", "payments-service"]);
+
+        assert!(scope.contains(Path::new("services/payments/src/main.rs")));
+        assert!(scope.contains(Path::new("libs/ledger/src/lib.rs")));
+        assert!(scope.contains(Path::new("libs/money/Cargo.toml")));
+        assert!(!scope.contains(Path::new("services/search/src/main.rs")));
+        // The npm package of the same name is another ecosystem
+        assert!(!scope.contains(Path::new("web/ledger/index.ts")));
+        // Files of the root package that is not selected
+        assert!(!scope.contains(Path::new("README.md")));
+    }
+
+    #[test]
+    fn test_files_outside_packages_are_kept() {
+        let packages = vec![
+            package("app", "apps/app", "npm", &[]),
+            package("admin", "apps/admin", "npm", &[]),
+        ];
+        let scope = PackageScope::resolve(&packages, &request(&["app"])).unwrap();
+        assert!(scope.contains(Path::new("README.md")));
+        assert!(scope.contains(Path::new("apps/app/index.js")));
+        assert!(!scope.contains(Path::new("apps/admin/index.js")));
+        // Directories are matched by path components, not by prefix
+        assert!(scope.contains(Path::new("apps/application.md")));
+    }
+
+    #[test]
+    fn test_ambiguous_and_unknown_packages() {
+        let error = PackageScope::resolve(&monorepo(), &request(&["ledger"]))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("declared in several directories (libs/ledger, web/ledger)"));
+
+        let scope = PackageScope::resolve(&monorepo(), &request(&["web/ledger/"])).unwrap();
+        assert_eq!(scope.packages, vec!["ledger"]);
+        assert!(scope.contains(Path::new("web/ledger/index.ts")));
+        assert!(!scope.contains(Path::new("libs/ledger/src/lib.rs")));
+
+        let error = PackageScope::resolve(&monorepo(), &request(&["billing"]))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Unknown package `billing`"));
+        assert!(error.contains("ledger, money, payments-service, root, search-service"));
+    }
+
+    #[test]
+    fn test_session_loads_selected_packages() {
+        let project = TempDir::new().unwrap();
+        let root = project.path();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+        write(
+            "crates/api/Cargo.toml",
+            "[package]\nname = \"api\"\n\n[dependencies]\ncore = { path = \"../core\" }\n",
+        );
+        write("crates/api/src/main.rs", "fn main() {}\n");
+        write("crates/core/Cargo.toml", "[package]\nname = \"core\"\n");
+        write("crates/core/src/lib.rs", "pub fn run() {}\n");
+        write("crates/cli/Cargo.toml", "[package]\nname = \"cli\"\n");
+        write("crates/cli/src/main.rs", "fn main() {}\n");
+        write(
+            "web/node_modules/core/package.json",
+            "{\"name\": \"core\"}\n",
+        );
+
+        let config = Code2PromptConfig::builder()
+            .path(root.to_path_buf())
+            .include_patterns(vec!["*.rs".to_string()])
+            .packages(vec!["api".to_string()])
+            .build()
+            .unwrap();
+        let mut session = Code2PromptSession::new(config);
+        session.load_codebase().unwrap();
+
+        let mut files: Vec<String> = session
+            .data
+            .files
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|file| file.path.replace('\\', "/"))
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec!["crates/api/src/main.rs", "crates/core/src/lib.rs"]
+        );
+        assert!(!session.data.source_tree.as_ref().unwrap().contains("cli"));
+
+        session.config.packages = vec!["web".to_string()];
+        assert!(session.load_codebase().is_err());
+    }
+}
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use code2prompt_core::configuration::Code2PromptConfigBuilder;
+use code2prompt_core::path::SymlinkPolicy;
 use code2prompt_core::session::Code2PromptSession;
 use code2prompt_core::sort::FileSortMethod;
 use code2prompt_core::template::OutputFormat;
@@ -126,7 +127,11 @@ impl PyCode2PromptSession {
 
     fn follow_symlinks(&mut self, value: bool) -> PyResult<Py<Self>> {
         let mut config = self.inner.config.clone();
-        config.follow_symlinks = value;
+        config.symlink_policy = if value {
+            SymlinkPolicy::Follow
+        } else {
+            SymlinkPolicy::Skip
+        };
         self.inner = Code2PromptSession::new(config);
 
         Python::attach(|py| {
@@ -176,9 +181,12 @@ impl PyCode2PromptSession {
             "name_desc" => config.sort_method = Some(FileSortMethod::NameDesc),
             "date" | "date_asc" => config.sort_method = Some(FileSortMethod::DateAsc),
             "date_desc" => config.sort_method = Some(FileSortMethod::DateDesc),
+            "extension" => config.sort_method = Some(FileSortMethod::Extension),
+            "tokens" | "tokens_desc" => config.sort_method = Some(FileSortMethod::TokensDesc),
+            "imports" => config.sort_method = Some(FileSortMethod::Imports),
             _ => {
                 return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
-                    "Invalid sort method: {}. Valid values: name_asc, name_desc, date_asc, date_desc",
+                    "Invalid sort method: {}. Valid values: name_asc, name_desc, date_asc, date_desc, extension, tokens_desc, imports",
                     method
                 )));
             }
@@ -202,6 +210,8 @@ impl PyCode2PromptSession {
             // Assuming from the error that there's a Plain variant - please replace if needed
             "xml" | "text" => config.output_format = OutputFormat::Xml,
             "json" => config.output_format = OutputFormat::Json,
+            "jsonl" => config.output_format = OutputFormat::Jsonl,
+            "chunks" => config.output_format = OutputFormat::Chunks,
             _ => {
                 return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
                     "Invalid output format: {}",
@@ -229,6 +239,9 @@ impl PyCode2PromptSession {
             "p50k" => config.encoding = TokenizerType::P50kBase,
             "p50k_edit" => config.encoding = TokenizerType::P50kEdit,
             "r50k" => config.encoding = TokenizerType::R50kBase,
+            "claude" => config.encoding = TokenizerType::Claude,
+            "llama" => config.encoding = TokenizerType::Llama,
+            "chars" => config.encoding = TokenizerType::Chars,
             _ => {
                 return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
                     "Invalid token encoding: {}",
@@ -248,6 +261,21 @@ impl PyCode2PromptSession {
         })
     }
 
+    fn with_tokenizer_model(&mut self, path: &str) -> PyResult<Py<Self>> {
+        let mut config = self.inner.config.clone();
+        config.tokenizer_model = Some(PathBuf::from(path));
+        self.inner = Code2PromptSession::new(config);
+
+        Python::attach(|py| {
+            Ok(Py::new(
+                py,
+                Self {
+                    inner: self.inner.clone(),
+                },
+            )?)
+        })
+    }
+
     fn with_token_format(&mut self, format: &str) -> PyResult<Py<Self>> {
         let mut config = self.inner.config.clone();
         match format.to_lowercase().as_str() {
@@ -0,0 +1,193 @@
+//! Round-trip agent mode.
+//!
+//! Generates the prompt for the codebase, appends the user instruction, queries the
+//! model, parses the proposed edits, previews them as a diff and applies them once
+//! the user confirms.
+
+use anyhow::{Context, Result};
+use code2prompt_core::response::{Edit, parse_response};
+use colored::*;
+use inquire::Confirm;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::args::{AgentArgs, Cli};
+use crate::config;
+use crate::config_loader::load_config;
+use crate::llm::{build_llm_command, query_model};
+
+/// Instructions describing the edit format expected from the model.
+const EDIT_FORMAT_INSTRUCTIONS: &str = r#"Describe every change as a search/replace block in a fenced code block, preceded by the path of the file relative to the repository root on its own line:
+
+path/to/file.ext
+```
+<<<<<<< SEARCH
+exact lines to replace
+=======
+new lines
+>>>>>>> REPLACE
+```
+
+Use an empty SEARCH section to create a new file. Keep SEARCH sections short but unique within the file."#;
+
+/// The original and proposed content of a file touched by the model.
+struct FileChange {
+    original: Option<String>,
+    proposed: Option<String>,
+}
+
+/// Runs the agent loop: generate → ask → parse → preview → apply.
+pub fn run_agent(args: &Cli, agent_args: &AgentArgs) -> Result<()> {
+    let config_source = load_config(args.quiet)?;
+    let mut session = config::build_session(Some(&config_source), args, false)?;
+    let root = session.config.path.clone();
+    session.config.instruction = Some(format!(
+        "{}\n\n{}",
+        agent_args.instruction, EDIT_FORMAT_INSTRUCTIONS
+    ));
+
+    // ~~~ Generate ~~~
+    info(args.quiet, "Generating codebase context...");
+    let prompt = session.generate_prompt()?.prompt;
+
+    // ~~~ Ask ~~~
+    let llm_command = agent_args
+        .llm_command
+        .as_deref()
+        .or(config_source.config.llm_command.as_deref());
+    let command = build_llm_command(llm_command, agent_args.model.as_deref());
+    info(args.quiet, &format!("Querying model with `{}`...", command));
+    let response = query_model(&command, &prompt)?;
+
+    // ~~~ Parse ~~~
+    let edits = parse_response(&response);
+    if edits.is_empty() {
+        info(args.quiet, "The model did not propose any edit:");
+        println!("{}", response.trim());
+        return Ok(());
+    }
+
+    let changes = collect_changes(&root, &edits);
+    if changes.is_empty() {
+        info(args.quiet, "None of the proposed edits could be applied.");
+        return Ok(());
+    }
+
+    // ~~~ Preview ~~~
+    for (path, change) in &changes {
+        print_diff(path, change)?;
+    }
+
+    // ~~~ Apply ~~~
+    let confirmed = agent_args.yes
+        || Confirm::new(&format!("Apply changes to {} file(s)?", changes.len()))
+            .with_default(false)
+            .prompt()
+            .unwrap_or(false);
+    if !confirmed {
+        info(args.quiet, "No changes applied.");
+        return Ok(());
+    }
+
+    for (path, change) in &changes {
+        let full_path = root.join(path);
+        match &change.proposed {
+            Some(content) => {
+                if let Some(parent) = full_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&full_path, content)
+                    .with_context(|| format!("Failed to write {}", full_path.display()))?;
+            }
+            None => std::fs::remove_file(&full_path)
+                .with_context(|| format!("Failed to delete {}", full_path.display()))?,
+        }
+    }
+
+    if !args.quiet {
+        eprintln!(
+            "{}{}{} {}",
+            "[".bold().white(),
+            "✓".bold().green(),
+            "]".bold().white(),
+            format!("Applied changes to {} file(s).", changes.len()).green()
+        );
+    }
+    Ok(())
+}
+
+/// Applies the edits in memory, grouping them by file.
+/// Edits that cannot be applied are reported and skipped.
+fn collect_changes(root: &Path, edits: &[Edit]) -> BTreeMap<PathBuf, FileChange> {
+    let mut changes: BTreeMap<PathBuf, FileChange> = BTreeMap::new();
+
+    for edit in edits {
+        let full_path = match edit.resolve(root) {
+            Ok(full_path) => full_path,
+            Err(e) => {
+                warn(&e.to_string());
+                continue;
+            }
+        };
+        let change = changes.entry(edit.path().to_path_buf()).or_insert_with(|| {
+            let original = std::fs::read_to_string(&full_path).ok();
+            FileChange {
+                proposed: original.clone(),
+                original,
+            }
+        });
+        match edit.apply(change.proposed.as_deref()) {
+            Ok(proposed) => change.proposed = proposed,
+            Err(e) => warn(&format!("Skipping edit: {}", e)),
+        }
+    }
+
+    changes.retain(|_, change| change.original != change.proposed);
+    changes
+}
+
+/// Prints a colored unified diff of a file change.
+fn print_diff(path: &Path, change: &FileChange) -> Result<()> {
+    let old = change.original.as_deref().unwrap_or("");
+    let new = change.proposed.as_deref().unwrap_or("");
+    let mut patch =
+        git2::Patch::from_buffers(old.as_bytes(), Some(path), new.as_bytes(), Some(path), None)?;
+    let buf = patch.to_buf()?;
+
+    for line in String::from_utf8_lossy(&buf).lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            println!("{}", line.bold());
+        } else if line.starts_with('+') {
+            println!("{}", line.green());
+        } else if line.starts_with('-') {
+            println!("{}", line.red());
+        } else if line.starts_with("@@") {
+            println!("{}", line.cyan());
+        } else {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+fn info(quiet: bool, message: &str) {
+    if !quiet {
+        eprintln!(
+            "{}{}{} {}",
+            "[".bold().white(),
+            "i".bold().blue(),
+            "]".bold().white(),
+            message
+        );
+    }
+}
+
+fn warn(message: &str) {
+    eprintln!(
+        "{}{}{} {}",
+        "[".bold().white(),
+        "!".bold().red(),
+        "]".bold().white(),
+        message.red()
+    );
+}
@@ -0,0 +1,84 @@
+//! Architecture brief prompt generation.
+//!
+//! Builds a prompt describing the architecture of the codebase from its workspace
+//! packages, the dependencies between them, its directories, READMEs and an outline of
+//! its source files. The brief is pruned to fit a token budget whatever the size of
+//! the repository, and what was left out is reported.
+
+use anyhow::{Context, Result};
+use code2prompt_core::builtin_templates::BuiltinTemplates;
+use code2prompt_core::template::write_to_file;
+use colored::*;
+use std::io::Write;
+
+use crate::args::{ArchitectureArgs, Cli};
+use crate::config;
+use crate::config_loader::load_config;
+
+/// Generates the architecture brief prompt.
+pub fn run_architecture(args: &Cli, architecture_args: &ArchitectureArgs) -> Result<()> {
+    let config_source = load_config(args.quiet)?;
+    let mut session = config::build_session(Some(&config_source), args, false)?;
+
+    // The built-in template is used unless one was given explicitly
+    if args.template.is_none() {
+        let template = BuiltinTemplates::get_template("architecture-brief")
+            .context("The architecture-brief template is missing")?;
+        session.config.template_str = template.content.to_string();
+        session.config.template_name = "architecture-brief".to_string();
+    }
+
+    session.load_codebase()?;
+    session.load_architecture_brief(architecture_args.max_tokens)?;
+
+    let data = session.build_template_data();
+    let rendered = session.render_prompt(&data)?;
+
+    if !args.quiet {
+        let brief = session
+            .data
+            .architecture_brief
+            .as_ref()
+            .context("The architecture brief is missing")?;
+        for decision in &brief.pruned {
+            eprintln!(
+                "{}{}{} {}",
+                "[".bold().white(),
+                "-".bold().yellow(),
+                "]".bold().white(),
+                decision
+            );
+        }
+        // The token count of the session covers every loaded file, the brief measured
+        // the prompt it is rendered in
+        if brief.token_count > brief.max_tokens {
+            eprintln!(
+                "{}{}{} The brief still takes {} tokens, over the budget of {}",
+                "[".bold().white(),
+                "!".bold().red(),
+                "]".bold().white(),
+                brief.token_count,
+                brief.max_tokens
+            );
+        }
+        eprintln!(
+            "{}{}{} Architecture brief: {} tokens (budget {})",
+            "[".bold().white(),
+            "i".bold().blue(),
+            "]".bold().white(),
+            brief.token_count,
+            brief.max_tokens
+        );
+    }
+
+    match args.output_file.as_deref() {
+        Some(path) if path != "-" => write_to_file(path, &rendered.prompt)?,
+        _ => {
+            print!("{}", rendered.prompt);
+            std::io::stdout()
+                .flush()
+                .context("Failed to flush stdout")?;
+        }
+    }
+    Ok(())
+}
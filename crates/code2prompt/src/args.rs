@@ -5,15 +5,15 @@
 //! comprehensive configuration options for file selection, output formatting,
 //! tokenization, and git integration.
 use anyhow::{Result, anyhow};
-use clap::{Parser, builder::ValueParser};
-use code2prompt_core::{
-    sort::FileSortMethod, template::OutputFormat, tokenizer::TokenFormat, tokenizer::TokenizerType,
-};
+use clap::{Args, Parser, Subcommand, builder::ValueParser};
+use code2prompt_core::{architecture::DEFAULT_BRIEF_MAX_TOKENS, variants::VariantAxis};
 use serde::de::DeserializeOwned;
 use std::path::PathBuf;
 
+use crate::options::OptionValues;
+
 // ~~~ CLI Arguments ~~~
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[clap(
     name = env!("CARGO_PKG_NAME"),
     version = env!("CARGO_PKG_VERSION"),
@@ -25,6 +25,11 @@ pub struct Cli {
     #[arg(value_name = "PATH_TO_ANALYZE", default_value = ".")]
     pub path: PathBuf,
 
+    /// Further codebase directories rendered in the same prompt, such as sibling packages
+    /// of a monorepo, each labeled with its directory name
+    #[arg(value_name = "MORE_PATHS")]
+    pub extra_paths: Vec<PathBuf>,
+
     /// Optional output file (use "-" for stdout)
     #[arg(short = 'O', long = "output-file", value_name = "FILE")]
     pub output_file: Option<String>,
@@ -33,79 +38,136 @@ pub struct Cli {
     #[clap(long)]
     pub tui: bool,
 
-    /// Patterns to include
-    #[clap(short = 'i', long = "include")]
-    pub include: Vec<String>,
+    /// Keep the session open and answer commands read line by line on stdin, such as
+    /// `toggle src/a.rs`, `tokens` or `generate`, for editor integrations
+    #[clap(long, conflicts_with = "tui")]
+    pub stdin_commands: bool,
+
+    /// Start the TUI without restoring the session saved in .code2prompt/session.toml
+    #[clap(long, requires = "tui")]
+    pub fresh: bool,
 
-    /// Patterns to exclude
-    #[clap(short = 'e', long = "exclude")]
-    pub exclude: Vec<String>,
+    /// Render each TUI tab once to a text snapshot file and exit
+    #[clap(long, value_name = "FILE", requires = "tui")]
+    pub screenshot: Option<PathBuf>,
 
-    /// Output format
+    /// Terminal size used for the snapshot, repeat to render several sizes
     #[clap(
-        short = 'F',
-        long = "output-format",
-        value_name = "markdown, json, xml",
-        value_parser = ValueParser::new(parse_serde::<OutputFormat>)
+        long = "screenshot-size",
+        value_name = "WIDTHxHEIGHT",
+        requires = "screenshot",
+        value_parser = ValueParser::new(parse_terminal_size)
     )]
-    pub output_format: Option<OutputFormat>,
+    pub screenshot_sizes: Vec<(u16, u16)>,
 
-    /// Optional Path to a custom Handlebars template
+    /// Keep colors and text styles in the snapshot as ANSI escape sequences
+    #[clap(long, requires = "screenshot")]
+    pub screenshot_ansi: bool,
+
+    /// Optional Path to a custom Handlebars template, or the name of a template of the library
+    /// (`.code2prompt/templates` of the project or `code2prompt/templates` in the config directory)
     #[clap(short, long, value_name = "TEMPLATE")]
     pub template: Option<PathBuf>,
 
-    /// List the full directory tree
+    /// Template argument, either NAME=VALUE or a bare value mapped to the next argument
+    /// declared in the template front-matter. Dotted names (meta.author=alice) build nested variables
+    #[clap(long = "arg", visible_alias = "var", value_name = "[NAME=]VALUE")]
+    pub template_args: Vec<String>,
+
+    /// Path to a JSON schema file the response must match, appended as a response contract
+    #[clap(long, value_name = "FILE")]
+    pub response_schema: Option<PathBuf>,
+
+    /// Manifest of file content hashes, compared with the previous run and updated
+    #[clap(long, value_name = "FILE")]
+    pub manifest: Option<PathBuf>,
+
+    /// Conversation file tracking the context already sent; follow-up runs only include changed files
+    #[clap(long, value_name = "FILE")]
+    pub conversation: Option<PathBuf>,
+
+    /// Write the files, their metadata and token counts, and the git information to a
+    /// SQLite database. Needs the sqlite feature
+    #[clap(long, value_name = "FILE")]
+    pub export_sqlite: Option<PathBuf>,
+
+    /// Render the prompt from a database written by --export-sqlite instead of the codebase.
+    /// Needs the sqlite feature
+    #[clap(long, value_name = "FILE")]
+    pub from_sqlite: Option<PathBuf>,
+
+    /// Apply a selection handed over by another tool: a JSON file of includes, excludes,
+    /// pinned files and line ranges, added to the patterns
+    #[clap(long, value_name = "FILE")]
+    pub import_selection: Option<PathBuf>,
+
+    /// Write the metadata of the included files, without their content, to a .csv or
+    /// .parquet file: path, language, size, tokens, owners and last commit. Parquet needs
+    /// the parquet feature
+    #[clap(long, value_name = "FILE")]
+    pub export_metadata: Option<PathBuf>,
+
+    /// List the files that would be included with a token estimate from their sizes,
+    /// without reading them or rendering the prompt
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Print how each file is included and why, and the files that will be left out
+    /// despite their selection, as quickfix lines for Vim and Neovim
     #[clap(long)]
-    pub full_directory_tree: bool,
+    pub quickfix: bool,
 
-    /// Token encoding to use for token count
+    /// Generate this many prompt variants differing along the --vary axes, written to
+    /// --variants-dir with a manifest
+    #[clap(long, value_name = "COUNT", requires = "vary")]
+    pub variants: Option<usize>,
+
+    /// Axes the variants differ on: "placement", "summary_mode", "output_format",
+    /// "line_numbers", "fixture_policy", "budget_strategy" or "diff_style"
     #[clap(
         long,
-        value_name = "cl100k, p50k, p50k_edit, r50k",
-        value_parser = ValueParser::new(parse_serde::<TokenizerType>),
+        value_name = "AXES",
+        value_delimiter = ',',
+        requires = "variants",
+        value_parser = ValueParser::new(parse_serde::<VariantAxis>)
     )]
-    pub encoding: Option<TokenizerType>,
+    pub vary: Vec<VariantAxis>,
 
-    /// Display the token count of the generated prompt. Accepts a format: "raw" (machine parsable) or "format" (human readable)
+    /// Directory the prompt variants and their manifest are written to
     #[clap(
         long,
-        value_name = "raw,format",
-        value_parser = ValueParser::new(parse_serde::<TokenFormat>),
+        value_name = "DIR",
+        default_value = "variants",
+        requires = "variants"
     )]
-    pub token_format: Option<TokenFormat>,
+    pub variants_dir: PathBuf,
 
-    /// Include git diff
-    #[clap(short, long)]
-    pub diff: bool,
+    /// When the prompt exceeds --max-tokens, choose which of the largest files to drop,
+    /// summarize or truncate, instead of pruning them automatically
+    #[clap(long, requires = "max_tokens")]
+    pub negotiate_budget: bool,
 
-    /// Generate git diff between two branches
-    #[clap(long, value_name = "BRANCHES", num_args = 2, value_delimiter = ',')]
-    pub git_diff_branch: Option<Vec<String>>,
+    /// Split the prompt into sequential parts of at most this many tokens, at file
+    /// boundaries, for small context windows and message size limits
+    #[clap(long, value_name = "TOKENS")]
+    pub split_tokens: Option<usize>,
 
-    /// Retrieve git log between two branches
-    #[clap(long, value_name = "BRANCHES", num_args = 2, value_delimiter = ',')]
-    pub git_log_branch: Option<Vec<String>>,
+    /// The model writing the summaries of --summarize-over, preferably a cheap one
+    #[clap(long, value_name = "MODEL")]
+    pub summary_model: Option<String>,
 
-    /// Add line numbers to the source code
-    #[clap(short, long)]
-    pub line_numbers: bool,
+    /// Git repository to use, which may be bare. The codebase is read from the tree of --rev
+    #[clap(long, value_name = "GIT_DIR")]
+    pub git_dir: Option<PathBuf>,
 
-    /// If true, paths in the output will be absolute instead of relative.
-    #[clap(long)]
-    pub absolute_paths: bool,
+    /// Read the codebase from the tree of this revision instead of the working tree (defaults to HEAD with --git-dir)
+    #[clap(long, value_name = "REV")]
+    pub rev: Option<String>,
 
-    /// Follow symlinks
+    /// Follow symlinks, as `--symlink-policy follow`
     #[clap(short = 'L', long)]
     pub follow_symlinks: bool,
 
-    /// Include hidden directories and files
-    #[clap(long)]
-    pub hidden: bool,
-
-    /// Disable wrapping code inside markdown code blocks
-    #[clap(long)]
-    pub no_codeblock: bool,
-
     /// Copy output to clipboard
     #[clap(short = 'c', long)]
     pub clipboard: bool,
@@ -114,26 +176,10 @@ pub struct Cli {
     #[clap(long, hide = true)]
     pub no_clipboard: bool,
 
-    /// Skip .gitignore rules
-    #[clap(long)]
-    pub no_ignore: bool,
-
-    /// Sort order for files
-    #[clap(
-        long,
-        value_name = "name_asc, name_desc, date_asc, date_desc",
-        value_parser = ValueParser::new(parse_serde::<FileSortMethod>),
-    )]
-    pub sort: Option<FileSortMethod>,
-
     /// Suppress progress and success messages
     #[clap(short = 'q', long)]
     pub quiet: bool,
 
-    /// Display a visual token map of files (similar to disk usage tools)
-    #[clap(long)]
-    pub token_map: bool,
-
     /// Maximum number of lines to display in token map (default: terminal height - 10)
     #[clap(long, value_name = "NUMBER")]
     pub token_map_lines: Option<usize>,
@@ -144,6 +190,169 @@ pub struct Cli {
 
     #[arg(long, hide = true)]
     pub clipboard_daemon: bool,
+
+    /// The options of [`crate::options::OPTIONS`] given on the command line, their flags
+    /// being added to the command by [`crate::options::parse`]
+    #[arg(skip)]
+    pub options: OptionValues,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+// ~~~ Subcommands ~~~
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Generate the prompt, ask a model for edits, preview and apply them
+    Agent(AgentArgs),
+    /// Print a prompt asking for the commit message of the staged changes
+    Commitmsg(CommitMsgArgs),
+    /// Print a prompt asking for the description of the current branch as a pull request,
+    /// or ask a model for it
+    PrDescription(PrDescriptionArgs),
+    /// Print a prompt asking for the release notes of the changes since a release,
+    /// or ask a model for them
+    ReleaseNotes(ReleaseNotesArgs),
+    /// Print a prompt asking for the doc comments of the undocumented public items
+    DocCoverage(DocCoverageArgs),
+    /// Print an architecture brief of the codebase, pruned to fit a token budget
+    Architecture(ArchitectureArgs),
+    /// Print the files as an onboarding tour: entry points, core modules in dependency
+    /// order, supporting files and tests
+    Tour,
+    /// Send the prompt variants to a model, grade the responses with a rubric and write
+    /// the scores as CSV
+    Eval(EvalArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct AgentArgs {
+    /// The change to request from the model
+    #[clap(long)]
+    pub instruction: String,
+
+    /// Model name passed to the LLM command
+    #[clap(long)]
+    pub model: Option<String>,
+
+    /// Command reading the prompt on stdin and writing the response on stdout.
+    /// `{model}` is replaced by the model name
+    #[clap(long, value_name = "COMMAND")]
+    pub llm_command: Option<String>,
+
+    /// Apply the edits without asking for confirmation
+    #[clap(short = 'y', long)]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CommitMsgArgs {
+    /// Number of recent commit messages included as style examples
+    #[clap(long, value_name = "COUNT", default_value_t = 5)]
+    pub examples: usize,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct PrDescriptionArgs {
+    /// Branch the pull request targets
+    #[clap(long, value_name = "BRANCH", default_value = "main")]
+    pub base: String,
+
+    /// Branch of the pull request
+    #[clap(long, value_name = "BRANCH", default_value = "HEAD")]
+    pub head: String,
+
+    /// Send the prompt to the LLM command and print its answer instead of the prompt
+    #[clap(long)]
+    pub ask: bool,
+
+    /// Model name passed to the LLM command
+    #[clap(long, requires = "ask")]
+    pub model: Option<String>,
+
+    /// Command reading the prompt on stdin and writing the response on stdout.
+    /// `{model}` is replaced by the model name
+    #[clap(long, value_name = "COMMAND", requires = "ask")]
+    pub llm_command: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ReleaseNotesArgs {
+    /// The previous release, usually a tag
+    #[clap(long, value_name = "REVISION")]
+    pub since: String,
+
+    /// The revision being released
+    #[clap(long, value_name = "REVISION", default_value = "HEAD")]
+    pub until: String,
+
+    /// Send the prompt to the LLM command and print its answer instead of the prompt
+    #[clap(long)]
+    pub ask: bool,
+
+    /// Model name passed to the LLM command
+    #[clap(long, requires = "ask")]
+    pub model: Option<String>,
+
+    /// Command reading the prompt on stdin and writing the response on stdout.
+    /// `{model}` is replaced by the model name
+    #[clap(long, value_name = "COMMAND", requires = "ask")]
+    pub llm_command: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DocCoverageArgs {
+    /// Write the undocumented items and the coverage totals to this file, as JSON
+    #[clap(long, value_name = "FILE")]
+    pub report: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ArchitectureArgs {
+    /// Token budget of the brief
+    #[clap(long, value_name = "TOKENS", default_value_t = DEFAULT_BRIEF_MAX_TOKENS)]
+    pub max_tokens: usize,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct EvalArgs {
+    /// Manifest of the variants written by --variants
+    #[clap(long, value_name = "FILE", default_value = "variants/manifest.json")]
+    pub manifest: PathBuf,
+
+    /// Grading rubric, a Handlebars template receiving `response`, `variant` and `settings`
+    #[clap(long, value_name = "FILE")]
+    pub rubric: PathBuf,
+
+    /// Number of responses requested for each variant
+    #[clap(long, value_name = "COUNT", default_value_t = 1)]
+    pub runs: usize,
+
+    /// Model name passed to the LLM command
+    #[clap(long)]
+    pub model: Option<String>,
+
+    /// Model grading the responses, the answering model by default
+    #[clap(long, value_name = "MODEL")]
+    pub grader_model: Option<String>,
+
+    /// Command reading the prompt on stdin and writing the response on stdout.
+    /// `{model}` is replaced by the model name
+    #[clap(long, value_name = "COMMAND")]
+    pub llm_command: Option<String>,
+}
+
+/// Parses a terminal size written as `WIDTHxHEIGHT`.
+fn parse_terminal_size(s: &str) -> Result<(u16, u16)> {
+    let (width, height) = s
+        .split_once(['x', 'X'])
+        .ok_or_else(|| anyhow!("Expected WIDTHxHEIGHT, got '{}'", s))?;
+    let width: u16 = width.trim().parse()?;
+    let height: u16 = height.trim().parse()?;
+    if width == 0 || height == 0 {
+        return Err(anyhow!("Terminal size must be at least 1x1"));
+    }
+    Ok((width, height))
 }
 
 /// Helper function to parse serde deserializable enum from string inputs.
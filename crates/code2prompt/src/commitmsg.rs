@@ -0,0 +1,43 @@
+//! Commit message prompt generation.
+//!
+//! Builds a prompt from exactly the staged diff and the style of the recent commits,
+//! and writes it to stdout so that it can be piped to a model:
+//! `code2prompt commitmsg | llm`.
+
+use anyhow::{Context, Result};
+use code2prompt_core::builtin_templates::BuiltinTemplates;
+use code2prompt_core::template::write_to_file;
+use std::io::Write;
+
+use crate::args::{Cli, CommitMsgArgs};
+use crate::config;
+use crate::config_loader::load_config;
+
+/// Generates the commit message prompt for the staged changes.
+pub fn run_commitmsg(args: &Cli, commit_args: &CommitMsgArgs) -> Result<()> {
+    let config_source = load_config(args.quiet)?;
+    let mut session = config::build_session(Some(&config_source), args, false)?;
+
+    // The built-in template is used unless one was given explicitly
+    if args.template.is_none() {
+        let template = BuiltinTemplates::get_template("commit-message")
+            .context("The commit-message template is missing")?;
+        session.config.template_str = template.content.to_string();
+        session.config.template_name = "commit-message".to_string();
+    }
+
+    session.load_commit_message_context(commit_args.examples)?;
+    let data = session.build_template_data();
+    let rendered = session.render_prompt(&data)?;
+
+    match args.output_file.as_deref() {
+        Some(path) if path != "-" => write_to_file(path, &rendered.prompt)?,
+        _ => {
+            print!("{}", rendered.prompt);
+            std::io::stdout()
+                .flush()
+                .context("Failed to flush stdout")?;
+        }
+    }
+    Ok(())
+}
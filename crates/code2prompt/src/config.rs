@@ -4,18 +4,22 @@
 //! Code2PromptSession instances, consolidating all configuration parsing
 //! logic in one place for better maintainability and separation of concerns.
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use code2prompt_core::{
     configuration::Code2PromptConfig,
+    path::SymlinkPolicy,
+    selection_import::SelectionImport,
     session::Code2PromptSession,
     sort::FileSortMethod,
-    template::{OutputFormat, extract_undefined_variables},
-    tokenizer::TokenizerType,
+    template::{extract_undefined_variables, map_template_args, parse_front_matter},
+    tokenizer::{TokenCountMode, TokenFormat},
 };
 use inquire::Text;
 use log::error;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use crate::options;
+use crate::template_library::find_template;
 use crate::{args::Cli, config_loader::ConfigSource};
 
 /// Unified session builder that merges configuration layering in one place
@@ -42,81 +46,25 @@ pub fn build_session(
         configuration.path(args.path.clone());
     }
 
-    // Include/Exclude patterns:
-    // If CLI provides any patterns, they override config patterns completely (to avoid conflicts)
-    let use_cli_patterns = !args.include.is_empty() || !args.exclude.is_empty();
-    let (include_patterns, exclude_patterns) = if use_cli_patterns {
-        (
-            expand_comma_separated_patterns(&args.include),
-            expand_comma_separated_patterns(&args.exclude),
-        )
-    } else if let Some(c) = cfg {
-        (c.include_patterns.clone(), c.exclude_patterns.clone())
-    } else {
-        (
-            expand_comma_separated_patterns(&args.include),
-            expand_comma_separated_patterns(&args.exclude),
-        )
-    };
-    configuration
-        .include_patterns(include_patterns)
-        .exclude_patterns(exclude_patterns);
-
-    // Display options: CLI overrides config (logical-or semantics for booleans)
-    let cfg_line_numbers = cfg.map(|c| c.line_numbers).unwrap_or(false);
-    let cfg_absolute = cfg.map(|c| c.absolute_path).unwrap_or(false);
-    let cfg_full_tree = cfg.map(|c| c.full_directory_tree).unwrap_or(false);
-    configuration
-        .line_numbers(args.line_numbers || cfg_line_numbers)
-        .absolute_path(args.absolute_paths || cfg_absolute)
-        .full_directory_tree(args.full_directory_tree || cfg_full_tree);
-
-    // Output format: CLI overrides config
-    let output_format = if let Some(output_format_str) = args.output_format {
-        output_format_str
-    } else if let Some(c) = cfg {
-        c.output_format.unwrap_or(OutputFormat::Markdown)
-    } else {
-        OutputFormat::Markdown
-    };
-    configuration.output_format(output_format);
-
-    // Sort method: CLI overrides config
-    let sort_method = if let Some(sort_str) = args.sort {
-        sort_str
-    } else if let Some(c) = cfg {
-        c.sort_method.unwrap_or(FileSortMethod::NameAsc)
-    } else {
-        FileSortMethod::NameAsc
-    };
-    configuration.sort_method(sort_method);
-
-    // Tokenizer settings: CLI overrides config
-    let tokenizer_type = if let Some(encoding) = args.encoding {
-        encoding
-    } else if let Some(c) = cfg {
-        c.encoding.unwrap_or(TokenizerType::Cl100kBase)
-    } else {
-        TokenizerType::Cl100kBase
-    };
-
-    // Token format: CLI overrides config
-    let token_format = if let Some(format) = args.token_format {
-        format
-    } else if let Some(c) = cfg {
-        c.token_format
-            .unwrap_or(code2prompt_core::tokenizer::TokenFormat::Format)
-    } else {
-        code2prompt_core::tokenizer::TokenFormat::Format
+    // Further roots: CLI overrides config
+    let extra_paths = match cfg {
+        Some(c) if args.extra_paths.is_empty() => c.extra_paths.iter().map(PathBuf::from).collect(),
+        _ => args.extra_paths.clone(),
     };
+    configuration.extra_paths(extra_paths);
 
+    // Settings of the config file only
     configuration
-        .encoding(tokenizer_type)
-        .token_format(token_format);
+        .compression_overrides(
+            cfg.map(|c| c.compression_overrides.clone())
+                .unwrap_or_default(),
+        )
+        .fence_languages(cfg.map(|c| c.fence_languages.clone()).unwrap_or_default())
+        .summary_cache(dirs::cache_dir().map(|dir| dir.join("code2prompt").join("summaries.json")));
 
     // Template: CLI overrides config
     let (template_str, template_name) = if args.template.is_some() {
-        parse_template(&args.template).map_err(|e| {
+        parse_template(&args.template, &args.path).map_err(|e| {
             error!("Failed to parse template: {}", e);
             e
         })?
@@ -131,95 +79,126 @@ pub fn build_session(
         ("".to_string(), "default".to_string())
     };
 
+    // Template arguments are mapped using the front-matter of the selected template
+    let template_args = if args.template_args.is_empty() {
+        Default::default()
+    } else {
+        map_template_args(&parse_front_matter(&template_str), &args.template_args)?
+    };
+
     configuration
         .template_str(template_str)
         .template_name(template_name);
 
-    // Git options: CLI overrides config
-    let diff_branches = parse_branch_argument(&args.git_diff_branch).or_else(|| {
-        cfg.and_then(|c| {
-            c.diff_branches.as_ref().and_then(|branches| {
-                if branches.len() == 2 {
-                    Some((branches[0].clone(), branches[1].clone()))
-                } else {
-                    None
-                }
-            })
-        })
-    });
-
-    let log_branches = parse_branch_argument(&args.git_log_branch).or_else(|| {
-        cfg.and_then(|c| {
-            c.log_branches.as_ref().and_then(|branches| {
-                if branches.len() == 2 {
-                    Some((branches[0].clone(), branches[1].clone()))
-                } else {
-                    None
-                }
-            })
-        })
-    });
-
-    let cfg_diff_enabled = cfg.map(|c| c.diff_enabled).unwrap_or(false);
-    let cfg_token_map_enabled = cfg.map(|c| c.token_map_enabled).unwrap_or(false);
-
+    // Repository and tracking files: command line only
     configuration
-        .diff_enabled(args.diff || cfg_diff_enabled)
-        .diff_branches(diff_branches)
-        .log_branches(log_branches)
-        .no_ignore(args.no_ignore)
-        .hidden(args.hidden)
-        .no_codeblock(args.no_codeblock)
-        .follow_symlinks(args.follow_symlinks)
-        .token_map_enabled(args.token_map || cfg_token_map_enabled || tui_mode);
+        .git_dir(args.git_dir.clone())
+        .git_rev(args.rev.clone())
+        .manifest_file(args.manifest.clone())
+        .conversation_file(args.conversation.clone());
+
+    // User variables from config (if available), CLI template arguments take precedence
+    let mut user_variables = cfg.map(|c| c.user_variables.clone()).unwrap_or_default();
+    user_variables.extend(template_args);
+    configuration.user_variables(user_variables);
+
+    // Response contract: CLI overrides config
+    let response_schema_path = args
+        .response_schema
+        .clone()
+        .or_else(|| cfg.and_then(|c| c.response_schema.as_ref().map(PathBuf::from)));
+    if let Some(path) = response_schema_path {
+        configuration.response_schema(Some(parse_response_schema(&path)?));
+    }
 
-    // User variables from config (if available)
-    if let Some(c) = cfg {
-        configuration.user_variables(c.user_variables.clone());
+    // Defaults of the command line differing from those of the library; the TUI estimates
+    // the token counts to stay responsive
+    configuration
+        .sort_method(FileSortMethod::NameAsc)
+        .token_format(TokenFormat::Format)
+        .token_count_mode(if tui_mode {
+            TokenCountMode::Heuristic
+        } else {
+            TokenCountMode::Exact
+        });
+    let mut config = configuration.build()?;
+
+    // Options of the shared schema: CLI overrides config. `--follow-symlinks` stands for
+    // `--symlink-policy follow`
+    let mut given = args.options.clone();
+    if args.follow_symlinks {
+        given
+            .entry("symlink_policy")
+            .or_insert_with(|| serde_json::json!(SymlinkPolicy::Follow));
     }
+    options::apply(&mut config, &given, cfg)?;
+    config.token_map_enabled |= tui_mode;
+    if !config.symbols.is_empty() && config.tags_file.is_none() {
+        bail!("Keeping the files of symbols needs a tags file, given by --tags");
+    }
+
+    let mut session = Code2PromptSession::new(config);
 
-    let session = Code2PromptSession::new(configuration.build()?);
+    // A selection handed over by another tool goes on top of the patterns
+    if let Some(path) = &args.import_selection {
+        SelectionImport::load(path)?.apply(&mut session);
+    }
     Ok(session)
 }
 
-/// Parses the branch argument from command line options.
+/// Loads a template from a file path or returns default values.
 ///
-/// Takes an optional vector of strings and converts it to a tuple of two branch names
-/// if exactly two branches are provided.
+/// A bare name that is not an existing file selects the template of that name in the
+/// template library.
 ///
 /// # Arguments
 ///
-/// * `branch_arg` - An optional vector containing branch names
+/// * `template_arg` - An optional path to a template file, or the name of a template
+/// * `project_root` - The project whose `.code2prompt/templates` are searched for names
 ///
 /// # Returns
 ///
-/// * `Option<(String, String)>` - A tuple of (from_branch, to_branch) if two branches were provided, None otherwise
-pub fn parse_branch_argument(branch_arg: &Option<Vec<String>>) -> Option<(String, String)> {
-    match branch_arg {
-        Some(branches) if branches.len() == 2 => Some((branches[0].clone(), branches[1].clone())),
-        _ => None,
+/// * `Result<(String, String)>` - A tuple containing (template_content, template_name)
+///   where template_name is "custom" for user-provided templates or "default" otherwise
+pub fn parse_template(
+    template_arg: &Option<PathBuf>,
+    project_root: &Path,
+) -> Result<(String, String)> {
+    let Some(path) = template_arg else {
+        return Ok(("".to_string(), "default".to_string()));
+    };
+
+    // A bare name selects a template of the library
+    if !path.exists()
+        && path.components().count() == 1
+        && let Some(name) = path.to_str()
+        && let Some(template) = find_template(name, Some(project_root))?
+    {
+        let template_str = std::fs::read_to_string(&template.path)
+            .with_context(|| format!("Failed to load template {}", template.path.display()))?;
+        return Ok((template_str, "custom".to_string()));
     }
+
+    let template_str =
+        std::fs::read_to_string(path).context("Failed to load custom template file")?;
+    Ok((template_str, "custom".to_string()))
 }
 
-/// Loads a template from a file path or returns default values.
+/// Loads a JSON schema file used as the response contract.
 ///
 /// # Arguments
 ///
-/// * `template_arg` - An optional path to a template file
+/// * `path` - The path to the JSON schema file
 ///
 /// # Returns
 ///
-/// * `Result<(String, String)>` - A tuple containing (template_content, template_name)
-///   where template_name is "custom" for user-provided templates or "default" otherwise
-pub fn parse_template(template_arg: &Option<PathBuf>) -> Result<(String, String)> {
-    match template_arg {
-        Some(path) => {
-            let template_str =
-                std::fs::read_to_string(path).context("Failed to load custom template file")?;
-            Ok((template_str, "custom".to_string()))
-        }
-        None => Ok(("".to_string(), "default".to_string())),
-    }
+/// * `Result<String>` - The pretty-printed schema, or an error if the file is not valid JSON
+pub fn parse_response_schema(path: &Path) -> Result<String> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to load response schema file {}", path.display()))?;
+    let schema: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("Response schema {} is not valid JSON", path.display()))?;
+    Ok(serde_json::to_string_pretty(&schema)?)
 }
 
 /// Handles user-defined variables in the template and adds them to the session.
@@ -255,36 +234,3 @@ pub fn handle_undefined_variables(
 
     Ok(())
 }
-
-/// Expands comma-separated patterns while preserving brace expansion patterns
-///
-/// This function handles the expansion of comma-separated include/exclude patterns
-/// while being careful not to split patterns that contain brace expansion syntax.
-///
-/// # Arguments
-///
-/// * `patterns` - A vector of pattern strings that may contain comma-separated values
-///
-/// # Returns
-///
-/// * `Vec<String>` - A vector of individual patterns
-fn expand_comma_separated_patterns(patterns: &[String]) -> Vec<String> {
-    let mut expanded = Vec::new();
-
-    for pattern in patterns {
-        // If the pattern contains braces, don't split on commas (preserve brace expansion)
-        if pattern.contains('{') && pattern.contains('}') {
-            expanded.push(pattern.clone());
-        } else {
-            // Split on commas for regular patterns
-            for part in pattern.split(',') {
-                let trimmed = part.trim();
-                if !trimmed.is_empty() {
-                    expanded.push(trimmed.to_string());
-                }
-            }
-        }
-    }
-
-    expanded
-}
@@ -7,7 +7,7 @@ use anyhow::{Context, Result};
 use code2prompt_core::configuration::{OutputDestination, TomlConfig};
 use colored::*;
 use log::{debug, info};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Configuration source information
 #[derive(Debug, Clone)]
@@ -18,7 +18,7 @@ pub struct ConfigSource {
 /// Load configuration with proper priority handling
 pub fn load_config(quiet: bool) -> Result<ConfigSource> {
     // Check for local config first (.c2pconfig in current directory)
-    let local_config_path = std::env::current_dir()?.join(".c2pconfig");
+    let local_config_path = local_config_path()?;
     if local_config_path.exists() {
         match load_config_from_file(&local_config_path) {
             Ok(config) => {
@@ -41,29 +41,28 @@ pub fn load_config(quiet: bool) -> Result<ConfigSource> {
     }
 
     // Check for global config (~/.config/code2prompt/.c2pconfig)
-    if let Some(config_dir) = dirs::config_dir() {
-        let global_config_path = config_dir.join("code2prompt").join(".c2pconfig");
-        if global_config_path.exists() {
-            match load_config_from_file(&global_config_path) {
-                Ok(config) => {
-                    if !quiet {
-                        eprintln!(
-                            "{}{}{} Using config from: {}",
-                            "[".bold().white(),
-                            "i".bold().blue(),
-                            "]".bold().white(),
-                            global_config_path.display()
-                        );
-                    }
-                    info!(
-                        "Loaded global config from: {}",
+    if let Some(global_config_path) = global_config_path()
+        && global_config_path.exists()
+    {
+        match load_config_from_file(&global_config_path) {
+            Ok(config) => {
+                if !quiet {
+                    eprintln!(
+                        "{}{}{} Using config from: {}",
+                        "[".bold().white(),
+                        "i".bold().blue(),
+                        "]".bold().white(),
                         global_config_path.display()
                     );
-                    return Ok(ConfigSource { config });
-                }
-                Err(e) => {
-                    debug!("Failed to load global config: {}", e);
                 }
+                info!(
+                    "Loaded global config from: {}",
+                    global_config_path.display()
+                );
+                return Ok(ConfigSource { config });
+            }
+            Err(e) => {
+                debug!("Failed to load global config: {}", e);
             }
         }
     }
@@ -84,6 +83,37 @@ pub fn load_config(quiet: bool) -> Result<ConfigSource> {
     })
 }
 
+/// Load configuration again after a change, with the same priority as `load_config`.
+///
+/// Unlike `load_config`, a config file that cannot be parsed is reported instead of
+/// being skipped, so that a typo does not silently replace the configuration.
+pub fn reload_config() -> Result<ConfigSource> {
+    for path in config_file_candidates()? {
+        if path.exists() {
+            let config = load_config_from_file(&path)?;
+            return Ok(ConfigSource { config });
+        }
+    }
+    Ok(ConfigSource {
+        config: TomlConfig::default(),
+    })
+}
+
+/// The config files, by priority: local `.c2pconfig` first, then the global one
+pub fn config_file_candidates() -> Result<Vec<PathBuf>> {
+    let mut candidates = vec![local_config_path()?];
+    candidates.extend(global_config_path());
+    Ok(candidates)
+}
+
+fn local_config_path() -> Result<PathBuf> {
+    Ok(std::env::current_dir()?.join(".c2pconfig"))
+}
+
+fn global_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("code2prompt").join(".c2pconfig"))
+}
+
 /// Load TOML configuration from a file
 fn load_config_from_file(path: &Path) -> Result<TomlConfig> {
     let content = std::fs::read_to_string(path)
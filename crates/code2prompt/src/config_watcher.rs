@@ -0,0 +1,72 @@
+//! Config file watching for the TUI.
+//!
+//! The config files are polled from the event loop: when one of them is created,
+//! modified or removed, the configuration is rebuilt from the files and the
+//! command-line arguments, exactly as at startup.
+
+use anyhow::Result;
+use code2prompt_core::configuration::Code2PromptConfig;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::args::Cli;
+use crate::config;
+use crate::config_loader::{config_file_candidates, reload_config};
+
+/// Delay between two checks of the config files
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// State of a config file: modification time and size, `None` if it does not exist
+type FileStamp = Option<(SystemTime, u64)>;
+
+/// Watches the config files by polling their modification time
+pub struct ConfigWatcher {
+    args: Cli,
+    paths: Vec<PathBuf>,
+    stamps: Vec<FileStamp>,
+    last_poll: Instant,
+}
+
+impl ConfigWatcher {
+    /// Start watching the config files, rebuilding the configuration with these arguments.
+    pub fn new(args: Cli) -> Result<Self> {
+        let paths = config_file_candidates()?;
+        let stamps = paths.iter().map(stamp).collect();
+        Ok(Self {
+            args,
+            paths,
+            stamps,
+            last_poll: Instant::now(),
+        })
+    }
+
+    /// Check the config files, at most once per `POLL_INTERVAL`.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Result<Code2PromptConfig>>` - The rebuilt configuration if a config file
+    ///   changed, or the error preventing it from being loaded
+    pub fn poll(&mut self) -> Option<Result<Code2PromptConfig>> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+
+        let stamps: Vec<FileStamp> = self.paths.iter().map(stamp).collect();
+        if stamps == self.stamps {
+            return None;
+        }
+        self.stamps = stamps;
+
+        Some(
+            reload_config()
+                .and_then(|source| config::build_session(Some(&source), &self.args, true))
+                .map(|session| session.config),
+        )
+    }
+}
+
+fn stamp(path: &PathBuf) -> FileStamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
@@ -0,0 +1,126 @@
+//! Panic safety for the TUI.
+//!
+//! While the TUI runs, a panic hook restores the terminal (raw mode and alternate screen)
+//! before anything is printed, and writes a crash report with the last events handled
+//! and a summary of the model, so that a crash never leaves the terminal unusable.
+
+use crate::model::Message;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Number of events kept for the crash report
+const MAX_EVENTS: usize = 50;
+
+/// Longest description of an event, in characters
+const MAX_EVENT_LENGTH: usize = 200;
+
+/// What the crash report knows about the TUI
+struct CrashContext {
+    events: VecDeque<String>,
+    model_summary: String,
+}
+
+static CRASH_CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    events: VecDeque::new(),
+    model_summary: String::new(),
+});
+
+/// Record a handled message, keeping only the last [`MAX_EVENTS`]
+pub fn record_message(message: &Message) {
+    // Progress would push the meaningful events out of the report
+    if let Message::AnalysisProgress(_) = message {
+        return;
+    }
+    let description = match message {
+        // Results hold the whole prompt, keep them out of the report
+        Message::AnalysisComplete(results) => format!(
+            "AnalysisComplete({} files, {:?} tokens)",
+            results.file_count, results.token_count
+        ),
+        message => {
+            let mut description = format!("{:?}", message);
+            if let Some((index, _)) = description.char_indices().nth(MAX_EVENT_LENGTH) {
+                description.truncate(index);
+                description.push('…');
+            }
+            description
+        }
+    };
+
+    if let Ok(mut context) = CRASH_CONTEXT.lock() {
+        if context.events.len() == MAX_EVENTS {
+            context.events.pop_front();
+        }
+        context.events.push_back(description);
+    }
+}
+
+/// Record the summary of the model after the last update
+pub fn record_model_summary(summary: String) {
+    if let Ok(mut context) = CRASH_CONTEXT.lock() {
+        context.model_summary = summary;
+    }
+}
+
+/// Install the panic hook restoring the terminal and writing a crash report.
+///
+/// # Arguments
+///
+/// * `restore_terminal` - Restores the terminal to its normal state
+pub fn install_panic_hook(restore_terminal: fn()) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+        match write_crash_report(info) {
+            Ok(path) => eprintln!("code2prompt crashed. Crash report: {}", path.display()),
+            Err(e) => eprintln!("code2prompt crashed. Failed to write crash report: {}", e),
+        }
+        // Panics in background tasks would otherwise leave the TUI running on a restored terminal
+        std::process::exit(101);
+    }));
+}
+
+/// Remove the panic hook installed by [`install_panic_hook`]
+pub fn remove_panic_hook() {
+    let _ = std::panic::take_hook();
+}
+
+/// Write the crash report to a file in the temporary directory
+fn write_crash_report(info: &PanicHookInfo) -> std::io::Result<PathBuf> {
+    let timestamp = chrono::Local::now();
+    let path = std::env::temp_dir().join(format!(
+        "code2prompt-crash-{}.txt",
+        timestamp.format("%Y%m%d_%H%M%S")
+    ));
+    let mut file = std::fs::File::create(&path)?;
+
+    writeln!(
+        file,
+        "code2prompt {} crash report",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(file, "Time: {}", timestamp.to_rfc3339())?;
+    writeln!(file, "Panic: {}", info)?;
+    writeln!(
+        file,
+        "Backtrace:\n{}",
+        std::backtrace::Backtrace::force_capture()
+    )?;
+
+    // The panicking thread may hold the lock
+    match CRASH_CONTEXT.try_lock() {
+        Ok(context) => {
+            writeln!(file, "\nModel:\n{}", context.model_summary)?;
+            writeln!(file, "\nLast {} events:", context.events.len())?;
+            for event in &context.events {
+                writeln!(file, "  {}", event)?;
+            }
+        }
+        Err(_) => writeln!(file, "\nModel and events unavailable")?,
+    }
+    Ok(path)
+}
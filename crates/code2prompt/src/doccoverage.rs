@@ -0,0 +1,73 @@
+//! Documentation coverage prompt generation.
+//!
+//! Measures which public items of the codebase have no doc comment, then builds a
+//! prompt restricted to the files containing them and asking for the missing
+//! documentation. The gaps can also be written as a JSON report, e.g. for CI.
+
+use anyhow::{Context, Result};
+use code2prompt_core::builtin_templates::BuiltinTemplates;
+use code2prompt_core::template::write_to_file;
+use colored::*;
+use std::io::Write;
+
+use crate::args::{Cli, DocCoverageArgs};
+use crate::config;
+use crate::config_loader::load_config;
+
+/// Generates the prompt documenting the undocumented public items, and the gap report.
+pub fn run_doc_coverage(args: &Cli, coverage_args: &DocCoverageArgs) -> Result<()> {
+    let config_source = load_config(args.quiet)?;
+    let mut session = config::build_session(Some(&config_source), args, false)?;
+
+    // The built-in template is used unless one was given explicitly
+    if args.template.is_none() {
+        let template = BuiltinTemplates::get_template("write-missing-docs")
+            .context("The write-missing-docs template is missing")?;
+        session.config.template_str = template.content.to_string();
+        session.config.template_name = "write-missing-docs".to_string();
+    }
+
+    session.load_codebase()?;
+    session.load_doc_coverage()?;
+    let report = session
+        .data
+        .doc_coverage
+        .as_ref()
+        .context("The documentation coverage is missing")?;
+
+    if let Some(path) = &coverage_args.report {
+        let json = serde_json::to_string_pretty(report)?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write the report to {}", path.display()))?;
+    }
+
+    if !args.quiet {
+        eprintln!(
+            "{}{}{} Documentation coverage: {:.1}% ({} of {} public items in {} files)",
+            "[".bold().white(),
+            "i".bold().blue(),
+            "]".bold().white(),
+            report.coverage,
+            report.documented_items,
+            report.total_items,
+            report.analyzed_files
+        );
+    }
+    if report.gap_count() == 0 {
+        return Ok(());
+    }
+
+    let data = session.build_template_data();
+    let rendered = session.render_prompt(&data)?;
+
+    match args.output_file.as_deref() {
+        Some(path) if path != "-" => write_to_file(path, &rendered.prompt)?,
+        _ => {
+            print!("{}", rendered.prompt);
+            std::io::stdout()
+                .flush()
+                .context("Failed to flush stdout")?;
+        }
+    }
+    Ok(())
+}
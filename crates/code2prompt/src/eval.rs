@@ -0,0 +1,79 @@
+//! Evaluation of prompt variants.
+//!
+//! Sends each variant of a `--variants` manifest to a model, grades the responses with a
+//! rubric prompt and writes the scores as CSV, one row per variant and run:
+//! `code2prompt eval --rubric rubric.hbs -O scores.csv`.
+
+use anyhow::{Context, Result};
+use code2prompt_core::eval::{EvalScore, grading_prompt, parse_score, scores_csv};
+use code2prompt_core::template::write_to_file;
+use code2prompt_core::variants::VariantManifest;
+use colored::*;
+use std::io::Write;
+use std::path::Path;
+
+use crate::args::{Cli, EvalArgs};
+use crate::llm::{build_llm_command, query_model};
+
+/// Runs the evaluation of the variants of a manifest.
+pub fn run_eval(args: &Cli, eval_args: &EvalArgs) -> Result<()> {
+    let manifest = VariantManifest::load(&eval_args.manifest)?;
+    let dir = eval_args.manifest.parent().unwrap_or(Path::new("."));
+    let rubric = std::fs::read_to_string(&eval_args.rubric)
+        .with_context(|| format!("Failed to read rubric {}", eval_args.rubric.display()))?;
+
+    let llm_command = eval_args.llm_command.as_deref();
+    let model = eval_args.model.as_deref();
+    let answer_command = build_llm_command(llm_command, model);
+    let grade_command = build_llm_command(llm_command, eval_args.grader_model.as_deref().or(model));
+
+    let mut scores = Vec::new();
+    for variant in &manifest.variants {
+        let path = dir.join(&variant.file);
+        let prompt = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read variant {}", path.display()))?;
+        for run in 1..=eval_args.runs {
+            let response = query_model(&answer_command, &prompt)?;
+            let grade = query_model(
+                &grade_command,
+                &grading_prompt(&rubric, variant, &response)?,
+            )?;
+            let score = parse_score(&grade);
+
+            if !args.quiet {
+                let (symbol, message) = match score {
+                    Some(score) => ("✓".bold().green(), format!("score {}", score)),
+                    None => ("!".bold().red(), "no score in the grade".to_string()),
+                };
+                eprintln!(
+                    "{}{}{} {} run {}: {}",
+                    "[".bold().white(),
+                    symbol,
+                    "]".bold().white(),
+                    variant.name,
+                    run,
+                    message
+                );
+            }
+            scores.push(EvalScore {
+                variant: variant.name.clone(),
+                run,
+                settings: variant.settings.clone(),
+                token_count: variant.token_count,
+                score,
+            });
+        }
+    }
+
+    let csv = scores_csv(&manifest.axes, &scores);
+    match args.output_file.as_deref() {
+        Some(path) if path != "-" => write_to_file(path, &csv)?,
+        _ => {
+            print!("{}", csv);
+            std::io::stdout()
+                .flush()
+                .context("Failed to flush stdout")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! Lookups on the GitHub API.
+//!
+//! code2prompt has no HTTP client of its own: requests are made with `curl`, the token
+//! being passed on its standard input so that it never shows up in the process list.
+//! Lookups are best effort, callers keep what they already know when one fails.
+
+use anyhow::{Context, Result, bail};
+use code2prompt_core::git::MergedPullRequest;
+use log::{debug, warn};
+use serde::Deserialize;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// API root used when `GITHUB_API_URL` is not set.
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+#[derive(Deserialize)]
+struct PullRequest {
+    title: String,
+}
+
+/// The GitHub token from the environment (`GITHUB_TOKEN`, then `GH_TOKEN`) or the config.
+pub fn github_token(configured: Option<&str>) -> Option<String> {
+    ["GITHUB_TOKEN", "GH_TOKEN"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .or_else(|| configured.map(str::to_string))
+        .filter(|token| !token.trim().is_empty())
+}
+
+/// Replaces the titles of merged pull requests by their current title on GitHub.
+///
+/// Titles read from merge commits may be missing or outdated. Pull requests that
+/// cannot be looked up keep the title found in the history.
+///
+/// # Arguments
+///
+/// * `repo_path` - The repository, whose `origin` remote must be hosted on GitHub
+/// * `token` - The token used to authenticate
+/// * `pull_requests` - The pull requests to update
+pub fn update_pull_request_titles(
+    repo_path: &Path,
+    token: &str,
+    pull_requests: &mut [MergedPullRequest],
+) {
+    let Some((owner, repo)) = github_repository(repo_path) else {
+        warn!(
+            "The origin remote is not a GitHub repository, pull request titles are not looked up"
+        );
+        return;
+    };
+    let api_url = std::env::var("GITHUB_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+
+    for pull_request in pull_requests {
+        let url = format!(
+            "{}/repos/{}/{}/pulls/{}",
+            api_url.trim_end_matches('/'),
+            owner,
+            repo,
+            pull_request.number
+        );
+        match fetch_pull_request(&url, token) {
+            Ok(found) => pull_request.title = found.title,
+            Err(e) => debug!(
+                "Failed to look up pull request #{}: {}",
+                pull_request.number, e
+            ),
+        }
+    }
+}
+
+/// The owner and name of the GitHub repository the `origin` remote points to.
+fn github_repository(repo_path: &Path) -> Option<(String, String)> {
+    let repo = git2::Repository::discover(repo_path).ok()?;
+    let remote = repo.find_remote("origin").ok()?;
+    parse_github_url(remote.url()?)
+}
+
+/// Reads `owner/name` from the HTTPS and SSH forms of a GitHub remote URL.
+fn parse_github_url(url: &str) -> Option<(String, String)> {
+    let (_, path) = url.split_once("github.com")?;
+    let path = path.trim_start_matches([':', '/']);
+    let path = path
+        .strip_suffix(".git")
+        .unwrap_or(path)
+        .trim_end_matches('/');
+    let (owner, name) = path.split_once('/')?;
+    if owner.is_empty() || name.is_empty() || name.contains('/') {
+        return None;
+    }
+    Some((owner.to_string(), name.to_string()))
+}
+
+fn fetch_pull_request(url: &str, token: &str) -> Result<PullRequest> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .args(["--header", "@-"])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run curl")?;
+
+    child
+        .stdin
+        .take()
+        .context("Failed to open curl stdin")?
+        .write_all(format!("Authorization: Bearer {}\n", token).as_bytes())
+        .context("Failed to pass the token to curl")?;
+
+    let output = child
+        .wait_with_output()
+        .context("Failed to read curl output")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    serde_json::from_slice(&output.stdout).context("Unexpected response from GitHub")
+}
@@ -0,0 +1,114 @@
+//! Dispatch of prompts to a language model.
+//!
+//! code2prompt does not talk to model providers directly. The prompt is piped to the
+//! standard input of an external command (by default the `llm` CLI) and the standard
+//! output of that command is used as the model response.
+
+use anyhow::{Context, Result, bail};
+use code2prompt_core::summarize::FileSummarizer;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Command used to query the model when none is configured.
+pub const DEFAULT_LLM_COMMAND: &str = "llm";
+
+/// Builds the shell command used to query the model.
+///
+/// The model name replaces the `{model}` placeholder of the command, or is passed
+/// as `-m <model>` when the command has no placeholder.
+///
+/// # Arguments
+///
+/// * `command` - The configured LLM command, if any
+/// * `model` - The model name, if any
+///
+/// # Returns
+///
+/// * `String` - The shell command to run
+pub fn build_llm_command(command: Option<&str>, model: Option<&str>) -> String {
+    let command = command.unwrap_or(DEFAULT_LLM_COMMAND);
+    match model {
+        Some(model) if command.contains("{model}") => {
+            command.replace("{model}", &shell_quote(model))
+        }
+        Some(model) => format!("{} -m {}", command, shell_quote(model)),
+        None => command.replace("{model}", ""),
+    }
+}
+
+/// Sends the prompt to the model and returns its response.
+///
+/// # Arguments
+///
+/// * `command` - The shell command to run, as returned by [`build_llm_command`]
+/// * `prompt` - The prompt written to the command standard input
+///
+/// # Returns
+///
+/// * `Result<String>` - The command standard output
+pub fn query_model(command: &str, prompt: &str) -> Result<String> {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("Failed to run LLM command `{}`", command))?;
+
+    // Write from another thread so large prompts cannot deadlock on a full stdout pipe
+    let mut stdin = child
+        .stdin
+        .take()
+        .context("Failed to open LLM command stdin")?;
+    let prompt = prompt.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(prompt.as_bytes()));
+
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to read output of LLM command `{}`", command))?;
+    writer
+        .join()
+        .map_err(|_| anyhow::anyhow!("Failed to write prompt to LLM command"))?
+        .context("Failed to write prompt to LLM command")?;
+
+    if !output.status.success() {
+        bail!("LLM command `{}` failed with {}", command, output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Writes the summaries of oversized files by querying the model.
+#[derive(Debug)]
+pub struct LlmSummarizer {
+    /// The shell command to run, as returned by [`build_llm_command`]
+    pub command: String,
+}
+
+impl FileSummarizer for LlmSummarizer {
+    fn summarize(&self, prompt: &str) -> Result<String> {
+        query_model(&self.command, prompt)
+    }
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(windows)]
+fn shell_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\\\""))
+}
@@ -1,26 +1,55 @@
 //! code2prompt is a command-line tool to generate an LLM prompt from a codebase directory.
 //!
 //! Authors: Olivier D'Ancona (@ODAncona), Mufeed VH (@mufeedvh)
+mod agent;
+mod architecture;
 mod args;
 mod clipboard;
+mod commitmsg;
 mod config;
 mod config_loader;
+mod config_watcher;
+mod crash_report;
+mod doccoverage;
+mod eval;
+mod github;
+mod llm;
 mod model;
+mod negotiate;
+mod options;
+mod prdescription;
+mod releasenotes;
+mod screenshot;
+mod sqlite;
+mod stdin_commands;
+mod template_library;
 mod token_map;
+mod tour;
 mod tui;
+mod tui_session;
+mod tui_state;
 mod utils;
+mod variants;
 mod view;
 mod widgets;
 
+use crate::llm::{LlmSummarizer, build_llm_command};
 use crate::utils::format_number;
 use anyhow::{Context, Result};
-use args::Cli;
-use clap::Parser;
+use args::{Cli, Command};
+use code2prompt_core::conversation::ConversationState;
+use code2prompt_core::inclusion_plan::format_quickfix;
+use code2prompt_core::manifest::FileManifest;
+use code2prompt_core::metadata::{MetadataFormat, collect_file_metadata, write_file_metadata};
+use code2prompt_core::remote::RemoteRepository;
+use code2prompt_core::split::{PromptPart, part_path};
 use code2prompt_core::template::write_to_file;
+use code2prompt_core::tokenizer::TokenCountMode;
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
 use log::{debug, error, info};
-use std::io::Write;
+use std::io::{IsTerminal, Write};
+use std::sync::Arc;
 use tui::run_tui;
 
 #[tokio::main]
@@ -28,7 +57,7 @@ async fn main() -> Result<()> {
     env_logger::init();
     info! {"Args: {:?}", std::env::args().collect::<Vec<_>>()};
 
-    let args: Cli = Cli::parse();
+    let args: Cli = options::parse();
 
     // ~~~ Clipboard Daemon ~~~
     #[cfg(target_os = "linux")]
@@ -42,14 +71,59 @@ async fn main() -> Result<()> {
         }
     }
 
+    // ~~~ Subcommands ~~~
+    match &args.command {
+        Some(Command::Agent(agent_args)) => return agent::run_agent(&args, agent_args),
+        Some(Command::Commitmsg(commit_args)) => {
+            return commitmsg::run_commitmsg(&args, commit_args);
+        }
+        Some(Command::PrDescription(pr_args)) => {
+            return prdescription::run_pr_description(&args, pr_args);
+        }
+        Some(Command::ReleaseNotes(release_args)) => {
+            return releasenotes::run_release_notes(&args, release_args);
+        }
+        Some(Command::DocCoverage(coverage_args)) => {
+            return doccoverage::run_doc_coverage(&args, coverage_args);
+        }
+        Some(Command::Architecture(architecture_args)) => {
+            return architecture::run_architecture(&args, architecture_args);
+        }
+        Some(Command::Tour) => return tour::run_tour(&args),
+        Some(Command::Eval(eval_args)) => return eval::run_eval(&args, eval_args),
+        None => {}
+    }
+
+    // ~~~ Editor Line Protocol ~~~
+    if args.stdin_commands {
+        return stdin_commands::run_stdin_commands(&args);
+    }
+
     // ~~~ TUI or CLI Mode ~~~
     if args.tui {
         // ~~~ Build Session for TUI ~~~
-        let session = config::build_session(None, &args, args.tui).unwrap_or_else(|e| {
-            error!("Failed to create session: {}", e);
-            std::process::exit(1);
-        });
-        run_tui(session).await
+        // Config files are watched while the TUI runs, load them the same way on startup
+        let session = config_loader::load_config(true)
+            .and_then(|source| config::build_session(Some(&source), &args, args.tui))
+            .and_then(|mut session| {
+                // A repository URL is cloned before the file tree is drawn
+                session.resolve_remote()?;
+                Ok(session)
+            })
+            .unwrap_or_else(|e| {
+                error!("Failed to create session: {}", e);
+                std::process::exit(1);
+            });
+        if let Some(path) = &args.screenshot {
+            return screenshot::write_screenshot(
+                session,
+                !args.fresh,
+                path,
+                &args.screenshot_sizes,
+                args.screenshot_ansi,
+            );
+        }
+        run_tui(session, &args).await
     } else {
         run_cli_mode_with_args(args).await
     }
@@ -67,6 +141,43 @@ async fn run_cli_mode_with_args(args: Cli) -> Result<()> {
 
     // ~~~ Build Session with config + CLI args ~~~
     let mut session = config::build_session(Some(&config_source), &args, false)?;
+    if let Some(remote) = RemoteRepository::parse(&session.config.path) {
+        if !quiet_mode {
+            eprintln!(
+                "{}{}{} Cloning {}",
+                "[".bold().white(),
+                "i".bold().blue(),
+                "]".bold().white(),
+                remote.url
+            );
+        }
+        session.resolve_remote()?;
+    }
+
+    // ~~~ Prompt Variants ~~~
+    if let Some(count) = args.variants {
+        return variants::write_variants(session, &args, count);
+    }
+
+    // ~~~ Quickfix ~~~
+    if args.quickfix {
+        let plan = session.inclusion_plan()?;
+        print!(
+            "{}",
+            format_quickfix(&plan, &session.config.path.canonicalize()?)
+        );
+        return Ok(());
+    }
+
+    // ~~~ Dry Run ~~~
+    if args.dry_run {
+        return print_estimate(&mut session);
+    }
+
+    // The format of the metadata export is checked before any work
+    if let Some(path) = &args.export_metadata {
+        MetadataFormat::from_path(path)?;
+    }
 
     // ~~~ Determine Output Behavior ~~~
     let default_output = get_default_output_destination(&config_source);
@@ -103,20 +214,79 @@ async fn run_cli_mode_with_args(args: Cli) -> Result<()> {
     };
 
     // ~~~ Gather Repository Data ~~~
-    session.load_codebase().map_err(|e| {
-        if let Some(s) = spinner.as_ref() {
-            s.finish_with_message("Failed!".red().to_string())
-        }
-        error!("Failed to build directory tree: \n{}", e);
-        anyhow::anyhow!("Failed to build directory tree: {}", e)
-    })?;
+    if let Some(path) = &args.from_sqlite {
+        sqlite::import_sqlite(&mut session, path).inspect_err(|_| {
+            if let Some(s) = spinner.as_ref() {
+                s.finish_with_message("Failed!".red().to_string())
+            }
+        })?;
+    } else {
+        session.load_codebase().map_err(|e| {
+            if let Some(s) = spinner.as_ref() {
+                s.finish_with_message("Failed!".red().to_string())
+            }
+            error!("Failed to build directory tree: \n{:#}", e);
+            anyhow::anyhow!("Failed to build directory tree: {:#}", e)
+        })?;
+    }
     if let Some(s) = spinner.as_ref() {
         s.set_message("Proceeding…")
     }
 
+    // ~~~ Signatures from Language Servers ~~~
+    if !session.config.lsp_servers.is_empty() {
+        if let Some(s) = spinner.as_ref() {
+            s.set_message("Querying language servers...")
+        }
+        session.apply_lsp_signatures()?;
+    }
+
+    // ~~~ Summaries of Oversized Files ~~~
+    if session.config.summarize_over.is_some() {
+        let model = args
+            .summary_model
+            .as_deref()
+            .or(config_source.config.summary_model.as_deref());
+        let command = build_llm_command(config_source.config.llm_command.as_deref(), model);
+        session.set_summarizer(Arc::new(LlmSummarizer { command }));
+        if let Some(s) = spinner.as_ref() {
+            s.set_message("Summarizing oversized files...")
+        }
+        let summarized = session.summarize_oversized_files()?;
+        if !quiet_mode && !summarized.is_empty() {
+            eprintln!(
+                "{}{}{} Summarized {} oversized files: {}",
+                "[".bold().white(),
+                "i".bold().blue(),
+                "]".bold().white(),
+                summarized.len(),
+                summarized.join(", ")
+            );
+        }
+    }
+
+    // ~~~ Manifest ~~~
+    let manifest = match session.config.manifest_file.clone() {
+        Some(path) => {
+            session.load_manifest_changes(&FileManifest::load(&path)?);
+            Some((path, session.manifest()))
+        }
+        None => None,
+    };
+
+    // ~~~ Conversation Delta ~~~
+    let conversation = match session.config.conversation_file.clone() {
+        Some(path) => {
+            let mut state = ConversationState::load(&path)?;
+            session.apply_conversation_delta(&mut state);
+            Some((path, state))
+        }
+        None => None,
+    };
+
     // ~~~ Git Related ~~~
     // Git Diff
-    if session.config.diff_enabled {
+    if session.config.working_diff_enabled() {
         if let Some(s) = spinner.as_ref() {
             s.set_message("Generating git diff...")
         }
@@ -159,6 +329,53 @@ async fn run_cli_mode_with_args(args: Cli) -> Result<()> {
         });
     }
 
+    // Load work in progress if requested
+    if session.config.stash.is_some() {
+        if let Some(ref s) = spinner {
+            s.set_message("Generating git stash diff...");
+        }
+        session.load_git_stash().unwrap_or_else(|e| {
+            if let Some(ref s) = spinner {
+                s.finish_with_message("Failed!".red().to_string());
+            }
+            error!("Failed to generate git stash diff: {}", e);
+            std::process::exit(1);
+        });
+    }
+    if session.config.untracked_enabled {
+        session.load_untracked_files().unwrap_or_else(|e| {
+            if let Some(ref s) = spinner {
+                s.finish_with_message("Failed!".red().to_string());
+            }
+            error!("Failed to list untracked files: {}", e);
+            std::process::exit(1);
+        });
+    }
+
+    // Load unresolved merge conflicts if requested
+    if session.config.conflicts_enabled {
+        if let Some(ref s) = spinner {
+            s.set_message("Collecting merge conflicts...");
+        }
+        session.load_merge_conflicts().unwrap_or_else(|e| {
+            if let Some(ref s) = spinner {
+                s.finish_with_message("Failed!".red().to_string());
+            }
+            error!("Failed to collect merge conflicts: {}", e);
+            std::process::exit(1);
+        });
+    }
+
+    // ~~~ Token Budget ~~~
+    if args.negotiate_budget && std::io::stdin().is_terminal() {
+        match spinner.as_ref() {
+            Some(s) => s.suspend(|| negotiate::negotiate_budget(&mut session))?,
+            None => negotiate::negotiate_budget(&mut session)?,
+        }
+    } else {
+        session.enforce_token_budget();
+    }
+
     // ~~~ Template ~~~
 
     // Handle undefined variables (modifies session.config.user_variables)
@@ -186,7 +403,10 @@ async fn run_cli_mode_with_args(args: Cli) -> Result<()> {
 
     // ~~~ Token Count ~~~
     let token_count = rendered.token_count;
-    let formatted_token_count = format_number(token_count, &session.config.token_format);
+    let mut formatted_token_count = format_number(token_count, &session.config.token_format);
+    if session.config.token_count_mode == TokenCountMode::Heuristic {
+        formatted_token_count = format!("~{}", formatted_token_count);
+    }
     let model_info = rendered.model_info;
 
     if !quiet_mode {
@@ -199,9 +419,90 @@ async fn run_cli_mode_with_args(args: Cli) -> Result<()> {
             model_info
         );
     }
+    if !quiet_mode && let Some(report) = &session.data.duplicates {
+        for duplicate in &report.duplicates {
+            eprintln!(
+                "{}{}{} {}:{}-{} duplicates {}:{}-{} ({} tokens)",
+                "[".bold().white(),
+                "i".bold().blue(),
+                "]".bold().white(),
+                duplicate.path,
+                duplicate.start_line,
+                duplicate.end_line,
+                duplicate.original_path,
+                duplicate.original_start_line,
+                duplicate.original_end_line,
+                duplicate.tokens
+            );
+        }
+        if !report.duplicates.is_empty() {
+            let summary = if report.stubbed {
+                format!("Stubbing the duplicates saved {} tokens", report.tokens)
+            } else {
+                format!(
+                    "Stubbing the duplicates with --duplicates stub would save {} tokens",
+                    report.tokens
+                )
+            };
+            eprintln!(
+                "{}{}{} {}",
+                "[".bold().white(),
+                "i".bold().blue(),
+                "]".bold().white(),
+                summary
+            );
+        }
+    }
+    // Sections and files pruned to fit --max-tokens are reported even in quiet mode
+    if let Some(budget) = &rendered.budget {
+        let pruned = budget
+            .trimmed_sections
+            .iter()
+            .map(|name| format!("Trimmed the {} section", name))
+            .chain(
+                budget
+                    .dropped_files
+                    .iter()
+                    .map(|path| format!("Dropped {}", path)),
+            )
+            .chain(
+                budget
+                    .summarized_files
+                    .iter()
+                    .map(|path| format!("Summarized {}", path)),
+            )
+            .chain(
+                budget
+                    .truncated_files
+                    .iter()
+                    .map(|path| format!("Truncated {}", path)),
+            );
+        for message in pruned {
+            eprintln!(
+                "{}{}{} {}",
+                "[".bold().white(),
+                "-".bold().yellow(),
+                "]".bold().white(),
+                message
+            );
+        }
+        if !budget.fits {
+            eprintln!(
+                "{}{}{} {}",
+                "[".bold().white(),
+                "!".bold().red(),
+                "]".bold().white(),
+                format!(
+                    "The prompt still exceeds the budget of {} tokens",
+                    budget.max_tokens
+                )
+                .red()
+            );
+        }
+    }
 
     // ~~~ Token Map Display ~~~
-    if args.token_map {
+    if session.config.token_map_enabled {
         use crate::token_map::{display_token_map, generate_token_map_with_limit};
 
         if let Some(files) = session.data.files.as_ref() {
@@ -230,8 +531,48 @@ async fn run_cli_mode_with_args(args: Cli) -> Result<()> {
         }
     }
 
+    // ~~~ Split Output ~~~
+    let parts = match args.split_tokens {
+        Some(max_tokens) => {
+            let parts = session.render_parts(max_tokens)?;
+            if !quiet_mode {
+                eprintln!(
+                    "{}{}{} Split into {} parts of at most {} tokens",
+                    "[".bold().white(),
+                    "i".bold().blue(),
+                    "]".bold().white(),
+                    parts.len(),
+                    format_number(max_tokens, &session.config.token_format)
+                );
+            }
+            for (index, part) in parts.iter().enumerate() {
+                if part.token_count > max_tokens {
+                    eprintln!(
+                        "{}{}{} {}",
+                        "[".bold().white(),
+                        "!".bold().yellow(),
+                        "]".bold().white(),
+                        format!(
+                            "Part {} takes {} tokens: {} does not fit in a part",
+                            index + 1,
+                            part.token_count,
+                            part.files.join(", ")
+                        )
+                        .yellow()
+                    );
+                }
+            }
+            Some(parts)
+        }
+        None => None,
+    };
+
     // ~~~ Output to Stdout ~~~
-    if output_to_stdout {
+    if output_to_stdout && let Some(parts) = &parts {
+        for part in parts {
+            println!("{}\n", part.prompt);
+        }
+    } else if output_to_stdout {
         print!("{}", &rendered.prompt);
         std::io::stdout()
             .flush()
@@ -239,7 +580,9 @@ async fn run_cli_mode_with_args(args: Cli) -> Result<()> {
     }
 
     // ~~~ Copy to Clipboard ~~~
-    if output_to_clipboard {
+    if output_to_clipboard && let Some(parts) = &parts {
+        copy_parts_to_clipboard(parts, quiet_mode)?;
+    } else if output_to_clipboard {
         use crate::clipboard::copy_to_clipboard;
         match copy_to_clipboard(&rendered.prompt) {
             Ok(_) => {
@@ -270,6 +613,14 @@ async fn run_cli_mode_with_args(args: Cli) -> Result<()> {
     // ~~~ Output File ~~~
     if let Some(ref output_file) = args.output_file
         && output_file != "-"
+        && let Some(parts) = &parts
+    {
+        for (index, part) in parts.iter().enumerate() {
+            let path = part_path(std::path::Path::new(output_file), index + 1);
+            output_prompt(Some(&path), &part.prompt, quiet_mode)?;
+        }
+    } else if let Some(ref output_file) = args.output_file
+        && output_file != "-"
     {
         output_prompt(
             Some(std::path::Path::new(output_file)),
@@ -278,6 +629,43 @@ async fn run_cli_mode_with_args(args: Cli) -> Result<()> {
         )?;
     }
 
+    // ~~~ SQLite Export ~~~
+    if let Some(path) = &args.export_sqlite {
+        sqlite::export_sqlite(&session, &rendered, path)?;
+        if !quiet_mode {
+            eprintln!(
+                "{}{}{} {}",
+                "[".bold().white(),
+                "✓".bold().green(),
+                "]".bold().white(),
+                format!("Exported the session to {}", path.display()).green()
+            );
+        }
+    }
+
+    // ~~~ Metadata Export ~~~
+    if let Some(path) = &args.export_metadata {
+        let files = session.data.files.as_deref().unwrap_or_default();
+        write_file_metadata(&collect_file_metadata(files, &session.config), path)?;
+        if !quiet_mode {
+            eprintln!(
+                "{}{}{} {}",
+                "[".bold().white(),
+                "✓".bold().green(),
+                "]".bold().white(),
+                format!("Exported the file metadata to {}", path.display()).green()
+            );
+        }
+    }
+
+    // ~~~ Manifest and Conversation State ~~~
+    if let Some((path, manifest)) = manifest {
+        manifest.save(&path)?;
+    }
+    if let Some((path, state)) = conversation {
+        state.save(&path)?;
+    }
+
     Ok(())
 }
 
@@ -317,7 +705,74 @@ fn setup_spinner(message: &str) -> ProgressBar {
     spinner
 }
 
+/// Prints the files that would be included and the estimated token count, without
+/// reading the files.
+///
+/// # Arguments
+///
+/// * `session` - The session, before its codebase is loaded
+fn print_estimate(session: &mut code2prompt_core::session::Code2PromptSession) -> Result<()> {
+    let estimate = session.estimate()?;
+    let format = &session.config.token_format;
+    for file in &estimate.files {
+        println!(
+            "{}  {} bytes  ~{} tokens",
+            file.path,
+            file.size,
+            format_number(file.token_count, format)
+        );
+    }
+    eprintln!(
+        "{}{}{} {} files, {} bytes, estimated token count: ~{}",
+        "[".bold().white(),
+        "i".bold().blue(),
+        "]".bold().white(),
+        estimate.files.len(),
+        estimate.total_size,
+        format_number(estimate.token_count, format)
+    );
+    Ok(())
+}
+
 // ~~~ Output to file or stdout ~~~
+/// Copies the parts of a split prompt to the clipboard one after the other, waiting for
+/// Enter between them when run from a terminal. Otherwise only the first part is copied.
+fn copy_parts_to_clipboard(parts: &[PromptPart], quiet: bool) -> Result<()> {
+    use crate::clipboard::copy_to_clipboard;
+    let interactive = std::io::stdin().is_terminal();
+    for (index, part) in parts.iter().enumerate() {
+        copy_to_clipboard(&part.prompt)
+            .map_err(|e| anyhow::anyhow!("Failed to copy to clipboard: {}", e))?;
+        if !quiet {
+            eprintln!(
+                "{}{}{} {}",
+                "[".bold().white(),
+                "✓".bold().green(),
+                "]".bold().white(),
+                format!("Copied part {}/{} to clipboard.", index + 1, parts.len()).green()
+            );
+        }
+        if index + 1 == parts.len() {
+            break;
+        }
+        if !interactive {
+            eprintln!(
+                "{}{}{} {}",
+                "[".bold().white(),
+                "!".bold().yellow(),
+                "]".bold().white(),
+                "Only the first part was copied, write the parts to files with --output-file"
+                    .yellow()
+            );
+            break;
+        }
+        eprint!("Press Enter to copy part {}/{}...", index + 2, parts.len());
+        std::io::stderr().flush()?;
+        std::io::stdin().read_line(&mut String::new())?;
+    }
+    Ok(())
+}
+
 fn output_prompt(
     effective_output: Option<&std::path::Path>,
     rendered: &str,
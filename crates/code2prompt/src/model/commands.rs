@@ -5,6 +5,9 @@
 //! file I/O, and clipboard operations.
 
 use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::LayoutPrefs;
 
 /// Commands represent side effects that should be executed after model updates.
 /// This allows Model::update() to remain pure while still triggering necessary
@@ -26,9 +29,24 @@ pub enum Cmd {
     /// Save text to file
     SaveToFile { filename: String, content: String },
 
-    /// Save template to custom directory
-    SaveTemplate { filename: String, content: String },
+    /// Save template to the user library under a name
+    SaveTemplate { name: String, content: String },
 
     /// Refresh file tree from session
     RefreshFileTree,
+
+    /// Count the tokens of files of the tree in background
+    CountFileTokens(Vec<PathBuf>),
+
+    /// List the branches of the repository
+    LoadBranches,
+
+    /// Count the tokens of the diff between two branches in background
+    PreviewBranchDiff { base: String, head: String },
+
+    /// Load the diff of the prompt, split by file, in background
+    LoadDiffFiles,
+
+    /// Persist the layout preferences in the state file
+    SaveLayout(LayoutPrefs),
 }
@@ -0,0 +1,213 @@
+//! Git state management for the TUI application.
+//!
+//! This module contains the state of the Git tab: the branches of the repository,
+//! the two branches picked for comparison, the token count of their diff and the
+//! diff viewer reviewing the diff of the prompt file by file.
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::git::DiffStyle;
+use std::collections::BTreeSet;
+
+/// Token count of the diff between the picked branches
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum BranchDiffPreview {
+    #[default]
+    None,
+    InProgress,
+    Tokens(usize),
+    Failed(String),
+}
+
+/// Git state containing the branches and the comparison being set up
+#[derive(Debug, Default, Clone)]
+pub struct GitState {
+    /// Branches of the repository, `None` until they are loaded
+    pub branches: Option<Vec<String>>,
+    /// Why the branches could not be loaded
+    pub load_error: Option<String>,
+    pub cursor: usize,
+    /// The branch the comparison starts from, picked first
+    pub base: Option<String>,
+    /// The branch the comparison goes to, picked second
+    pub head: Option<String>,
+    pub preview: BranchDiffPreview,
+    /// Whether the diff viewer replaces the branches
+    pub show_diff: bool,
+    /// Files of the diff viewer, `None` while the diff loads
+    pub diff_files: Option<Vec<DiffFile>>,
+    /// Why the diff could not be loaded
+    pub diff_error: Option<String>,
+    /// Row of the diff viewer under the cursor
+    pub diff_cursor: usize,
+    /// Paths excluded from the diff with the viewer
+    pub excluded_diff_files: BTreeSet<String>,
+}
+
+/// A file of the diff viewer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffFile {
+    pub path: String,
+    /// Lines of the file diff, without the `diff --git` line
+    pub lines: Vec<String>,
+    pub added: usize,
+    pub removed: usize,
+    pub expanded: bool,
+}
+
+impl DiffFile {
+    pub fn new(path: String, diff: &str) -> Self {
+        let lines: Vec<String> = diff.lines().skip(1).map(str::to_string).collect();
+        let count = |sign: char, header: &str| {
+            lines
+                .iter()
+                .filter(|line| line.starts_with(sign) && !line.starts_with(header))
+                .count()
+        };
+        Self {
+            path,
+            added: count('+', "+++"),
+            removed: count('-', "---"),
+            lines,
+            expanded: false,
+        }
+    }
+}
+
+/// A row of the diff viewer: the header of a file, or one of its lines when expanded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffRow {
+    pub file: usize,
+    pub line: Option<usize>,
+}
+
+impl GitState {
+    /// Picks a branch for the comparison: the base first, then the head.
+    /// Picking once both are set starts a new comparison from the picked branch.
+    ///
+    /// # Arguments
+    ///
+    /// * `branch` - The picked branch
+    pub fn pick(&mut self, branch: String) {
+        match (&self.base, &self.head) {
+            (Some(base), None) if *base != branch => self.head = Some(branch),
+            // Picking the base again unpicks it
+            (Some(_), None) => self.base = None,
+            _ => {
+                self.base = Some(branch);
+                self.head = None;
+            }
+        }
+        self.preview = BranchDiffPreview::None;
+    }
+
+    /// Clears the picked branches.
+    pub fn clear(&mut self) {
+        self.base = None;
+        self.head = None;
+        self.preview = BranchDiffPreview::None;
+    }
+
+    /// The picked branches, once both are set
+    pub fn pair(&self) -> Option<(String, String)> {
+        Some((self.base.clone()?, self.head.clone()?))
+    }
+
+    /// The rows of the diff viewer
+    pub fn diff_rows(&self) -> Vec<DiffRow> {
+        let mut rows = Vec::new();
+        for (index, file) in self.diff_files.iter().flatten().enumerate() {
+            rows.push(DiffRow {
+                file: index,
+                line: None,
+            });
+            if file.expanded {
+                rows.extend((0..file.lines.len()).map(|line| DiffRow {
+                    file: index,
+                    line: Some(line),
+                }));
+            }
+        }
+        rows
+    }
+
+    /// The file of the diff viewer row under the cursor
+    pub fn diff_file_at_cursor(&self) -> Option<usize> {
+        self.diff_rows().get(self.diff_cursor).map(|row| row.file)
+    }
+
+    /// Expands or collapses a file of the diff viewer, keeping the cursor on it.
+    pub fn toggle_diff_file(&mut self, index: usize) {
+        let Some(file) = self
+            .diff_files
+            .as_mut()
+            .and_then(|files| files.get_mut(index))
+        else {
+            return;
+        };
+        file.expanded = !file.expanded;
+        self.diff_cursor = self
+            .diff_rows()
+            .iter()
+            .position(|row| row.file == index && row.line.is_none())
+            .unwrap_or(0);
+    }
+
+    /// Marks as excluded the files of the diff viewer whose exclude pattern is in
+    /// `patterns`, after the patterns changed outside of the viewer.
+    pub fn sync_excluded_diff_files(&mut self, patterns: &[String]) {
+        self.excluded_diff_files
+            .retain(|path| patterns.contains(&diff_exclude_pattern(path)));
+        for file in self.diff_files.iter().flatten() {
+            if patterns.contains(&diff_exclude_pattern(&file.path)) {
+                self.excluded_diff_files.insert(file.path.clone());
+            }
+        }
+    }
+
+    /// The diff scope options shown under the branches, as `(key, name, value)`
+    pub fn scope_options(config: &Code2PromptConfig) -> Vec<(&'static str, &'static str, String)> {
+        let on_off = |value: bool| if value { "ON" } else { "OFF" }.to_string();
+        let range = |branches: &Option<(String, String)>| match branches {
+            Some((base, head)) => format!("{}..{}", base, head),
+            None => "-".to_string(),
+        };
+        vec![
+            ("d", "Branch diff", range(&config.diff_branches)),
+            ("l", "Branch log", range(&config.log_branches)),
+            (
+                "o",
+                "Changed files only",
+                on_off(config.include_only_changed),
+            ),
+            ("w", "Diff style", config.diff_style.to_string()),
+            (
+                "+/-",
+                "Context lines",
+                config
+                    .diff_context_lines
+                    .map_or("default".to_string(), |lines| lines.to_string()),
+            ),
+        ]
+    }
+}
+
+/// The next diff style, in display order
+pub fn next_diff_style(style: DiffStyle) -> DiffStyle {
+    match style {
+        DiffStyle::Unified => DiffStyle::Word,
+        DiffStyle::Word => DiffStyle::Function,
+        DiffStyle::Function => DiffStyle::Unified,
+    }
+}
+
+/// The exclude pattern matching exactly the file at `path` of the diff.
+///
+/// Glob metacharacters are escaped so that they match literally.
+pub fn diff_exclude_pattern(path: &str) -> String {
+    path.chars()
+        .map(|c| match c {
+            '*' | '?' | '[' | ']' | '{' | '}' => format!("[{}]", c),
+            c => c.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,96 @@
+//! Undo and redo of the edits of the file selection and the settings.
+//!
+//! Every edit of the selection, of the filter patterns or of a setting is recorded with
+//! the state it changed, before and after. Undoing an edit restores the state before it,
+//! redoing restores the state after it.
+
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::selection::SelectionEngine;
+use code2prompt_core::session::Code2PromptSession;
+
+/// Number of edits kept for undo, the oldest being dropped first
+pub const MAX_UNDO_EDITS: usize = 100;
+
+/// The part of the session an edit changes: the configuration, with its patterns and
+/// settings, and the user selection
+#[derive(Debug, Clone)]
+pub struct EditState {
+    pub config: Code2PromptConfig,
+    pub selection: SelectionEngine,
+}
+
+impl EditState {
+    /// The editable state of a session
+    pub fn of(session: &Code2PromptSession) -> Self {
+        Self {
+            config: session.config.clone(),
+            selection: session.selection_engine.clone(),
+        }
+    }
+
+    /// Restores the state in a session.
+    pub fn restore(self, session: &mut Code2PromptSession) {
+        session.config = self.config;
+        session.selection_engine = self.selection;
+    }
+}
+
+/// An edit that can be undone, described by its label
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub label: String,
+    pub before: EditState,
+    pub after: EditState,
+}
+
+/// The edits that can be undone, and the undone edits that can be redone
+#[derive(Debug, Clone, Default)]
+pub struct EditHistory {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl EditHistory {
+    /// Records an edit, after which the undone edits can no longer be redone.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - What the edit did, shown when it is undone or redone
+    /// * `before` - The state before the edit
+    /// * `after` - The state after the edit
+    pub fn record(&mut self, label: String, before: EditState, after: EditState) {
+        self.undo.push(Edit {
+            label,
+            before,
+            after,
+        });
+        if self.undo.len() > MAX_UNDO_EDITS {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Takes the last edit to undo it, keeping it to be redone.
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo.pop()?;
+        self.redo.push(edit.clone());
+        Some(edit)
+    }
+
+    /// Takes the last undone edit to redo it, keeping it to be undone again.
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo.pop()?;
+        self.undo.push(edit.clone());
+        Some(edit)
+    }
+
+    /// Number of edits that can be undone
+    pub fn undo_count(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Number of edits that can be redone
+    pub fn redo_count(&self) -> usize {
+        self.redo.len()
+    }
+}
@@ -0,0 +1,492 @@
+//! Central registry of the TUI keybindings.
+//!
+//! The registry describes every binding and the context in which it applies. The help
+//! overlay and the controls footers are generated from it, showing only the bindings
+//! valid in the current tab and mode, with the keys remapped in the keymap file.
+
+use super::keymap::{Action, Keymap};
+use super::template::{FocusMode, TemplateFocus};
+use super::{FileTreeInputMode, Model, Tab};
+use std::borrow::Cow;
+
+/// Context in which a keybinding is active
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyContext {
+    Global,
+    FileTree,
+    FileTreeSearch,
+    Settings,
+    SettingsSearch,
+    SettingsPatterns,
+    SettingsPatternInput,
+    Statistics,
+    Template,
+    TemplatePicker,
+    TemplateEditing,
+    TemplateSaving,
+    Variables,
+    VariableInput,
+    Output,
+    OutputSearch,
+    Git,
+    GitDiff,
+}
+
+impl KeyContext {
+    /// Title of the context in the help overlay
+    pub fn title(&self) -> &'static str {
+        match self {
+            KeyContext::Global => "Global",
+            KeyContext::FileTree => "Selection",
+            KeyContext::FileTreeSearch => "Selection search",
+            KeyContext::Settings => "Settings",
+            KeyContext::SettingsSearch => "Settings search",
+            KeyContext::SettingsPatterns => "Pattern editor",
+            KeyContext::SettingsPatternInput => "New pattern",
+            KeyContext::Statistics => "Statistics",
+            KeyContext::Template => "Template",
+            KeyContext::TemplatePicker => "Template picker",
+            KeyContext::TemplateEditing => "Template editor",
+            KeyContext::TemplateSaving => "Save template",
+            KeyContext::Variables => "Template variables",
+            KeyContext::VariableInput => "Variable input",
+            KeyContext::Output => "Output",
+            KeyContext::OutputSearch => "Output search",
+            KeyContext::Git => "Git",
+            KeyContext::GitDiff => "Diff viewer",
+        }
+    }
+}
+
+/// A keybinding, as displayed to the user
+#[derive(Debug, Clone, Copy)]
+pub struct KeyBinding {
+    pub context: KeyContext,
+    /// Keys shown while the actions keep their default keys
+    pub keys: &'static str,
+    /// Remappable actions behind the binding, none for navigation and text input
+    pub actions: &'static [Action],
+    pub description: &'static str,
+}
+
+impl KeyBinding {
+    /// The keys of the binding, as remapped by the keymap
+    pub fn label(&self, keymap: &Keymap) -> Cow<'static, str> {
+        if !self
+            .actions
+            .iter()
+            .any(|&action| keymap.is_remapped(action))
+        {
+            return Cow::Borrowed(self.keys);
+        }
+        let labels: Vec<String> = self
+            .actions
+            .iter()
+            .map(|&action| keymap.label(action))
+            .collect();
+        Cow::Owned(labels.join("/"))
+    }
+}
+
+const fn bind(context: KeyContext, keys: &'static str, description: &'static str) -> KeyBinding {
+    KeyBinding {
+        context,
+        keys,
+        actions: &[],
+        description,
+    }
+}
+
+const fn bind_actions(
+    context: KeyContext,
+    keys: &'static str,
+    actions: &'static [Action],
+    description: &'static str,
+) -> KeyBinding {
+    KeyBinding {
+        context,
+        keys,
+        actions,
+        description,
+    }
+}
+
+/// All keybindings, most useful first within each context
+pub const KEY_BINDINGS: &[KeyBinding] = &[
+    bind_actions(
+        KeyContext::Global,
+        "Enter",
+        &[Action::RunAnalysis],
+        "Run analysis",
+    ),
+    bind_actions(
+        KeyContext::Global,
+        "1-6",
+        &[
+            Action::SwitchToSelection,
+            Action::SwitchToSettings,
+            Action::SwitchToStatistics,
+            Action::SwitchToTemplate,
+            Action::SwitchToOutput,
+            Action::SwitchToGit,
+        ],
+        "Switch tab",
+    ),
+    bind_actions(
+        KeyContext::Global,
+        "Tab/Shift+Tab",
+        &[Action::NextTab, Action::PreviousTab],
+        "Next/previous tab",
+    ),
+    bind_actions(
+        KeyContext::Global,
+        "U/Ctrl+R",
+        &[Action::Undo, Action::Redo],
+        "Undo/redo selection and settings",
+    ),
+    bind_actions(
+        KeyContext::Global,
+        "?",
+        &[Action::ToggleHelp],
+        "Toggle help",
+    ),
+    bind_actions(KeyContext::Global, "Esc/Ctrl+Q", &[Action::Quit], "Quit"),
+    bind(KeyContext::FileTree, "↑↓", "Navigate"),
+    bind_actions(
+        KeyContext::FileTree,
+        "Space",
+        &[Action::ToggleSelect],
+        "Select/deselect",
+    ),
+    bind_actions(
+        KeyContext::FileTree,
+        "T",
+        &[Action::CycleInclusion],
+        "Cycle full/tree only/excluded",
+    ),
+    bind(KeyContext::FileTree, "←→", "Collapse/expand"),
+    bind_actions(
+        KeyContext::FileTree,
+        "/ or S",
+        &[Action::SearchFiles],
+        "Search",
+    ),
+    bind(KeyContext::FileTree, "PgUp/PgDn", "Scroll by page"),
+    bind(KeyContext::FileTree, "Home/End", "First/last entry"),
+    bind_actions(
+        KeyContext::FileTree,
+        "R",
+        &[Action::RefreshTree],
+        "Refresh tree",
+    ),
+    bind(KeyContext::FileTreeSearch, "Type", "Filter files"),
+    bind(KeyContext::FileTreeSearch, "Backspace", "Delete character"),
+    bind(KeyContext::FileTreeSearch, "Enter/Esc", "Exit search"),
+    bind(KeyContext::Settings, "↑↓", "Navigate"),
+    bind_actions(
+        KeyContext::Settings,
+        "Space",
+        &[Action::ToggleSetting],
+        "Toggle",
+    ),
+    bind(KeyContext::Settings, "←→", "Cycle options"),
+    bind_actions(
+        KeyContext::Settings,
+        "/",
+        &[Action::SearchSettings],
+        "Search settings",
+    ),
+    bind_actions(
+        KeyContext::Settings,
+        "R",
+        &[Action::ResetSetting],
+        "Reset to launch value",
+    ),
+    bind_actions(
+        KeyContext::Settings,
+        "P",
+        &[Action::EditPatterns],
+        "Edit include/exclude patterns",
+    ),
+    bind(KeyContext::SettingsSearch, "Type", "Filter settings"),
+    bind(KeyContext::SettingsSearch, "↑↓", "Navigate matches"),
+    bind(KeyContext::SettingsSearch, "Enter", "Toggle selected match"),
+    bind(KeyContext::SettingsSearch, "Esc", "Exit search"),
+    bind(KeyContext::SettingsPatterns, "↑↓", "Navigate patterns"),
+    bind_actions(
+        KeyContext::SettingsPatterns,
+        "I",
+        &[Action::AddIncludePattern],
+        "Add include pattern",
+    ),
+    bind_actions(
+        KeyContext::SettingsPatterns,
+        "X",
+        &[Action::AddExcludePattern],
+        "Add exclude pattern",
+    ),
+    bind_actions(
+        KeyContext::SettingsPatterns,
+        "D/Del",
+        &[Action::RemovePattern],
+        "Remove pattern",
+    ),
+    bind_actions(
+        KeyContext::SettingsPatterns,
+        "[/]",
+        &[Action::MovePatternUp, Action::MovePatternDown],
+        "Move pattern up/down",
+    ),
+    bind_actions(
+        KeyContext::SettingsPatterns,
+        "P/Esc",
+        &[Action::ClosePatterns],
+        "Back to settings",
+    ),
+    bind(KeyContext::SettingsPatternInput, "Type", "Glob pattern"),
+    bind(KeyContext::SettingsPatternInput, "Enter", "Add pattern"),
+    bind(KeyContext::SettingsPatternInput, "Esc", "Cancel"),
+    bind(KeyContext::Statistics, "←→", "Switch view"),
+    bind(KeyContext::Statistics, "↑↓/PgUp/PgDn", "Scroll"),
+    bind(KeyContext::Statistics, "Home/End", "Top/bottom"),
+    bind_actions(
+        KeyContext::Statistics,
+        "S",
+        &[Action::SortBreakdown],
+        "Sort the overview breakdown",
+    ),
+    bind_actions(
+        KeyContext::Template,
+        "E",
+        &[Action::EditTemplate],
+        "Edit template",
+    ),
+    bind_actions(
+        KeyContext::Template,
+        "V",
+        &[Action::EditVariables],
+        "Edit variables",
+    ),
+    bind_actions(
+        KeyContext::Template,
+        "P",
+        &[Action::FocusPicker],
+        "Focus picker",
+    ),
+    bind_actions(
+        KeyContext::Template,
+        "S",
+        &[Action::SaveTemplate],
+        "Save as named template",
+    ),
+    bind_actions(
+        KeyContext::Template,
+        "W",
+        &[Action::TogglePreview],
+        "Toggle preview pane",
+    ),
+    bind_actions(
+        KeyContext::Template,
+        "R",
+        &[Action::ReloadTemplate],
+        "Reload default template",
+    ),
+    bind_actions(
+        KeyContext::Template,
+        "</>",
+        &[Action::ShrinkColumn, Action::GrowColumn],
+        "Resize focused column",
+    ),
+    bind_actions(
+        KeyContext::Template,
+        "[/]",
+        &[Action::MoveColumnLeft, Action::MoveColumnRight],
+        "Move focused column",
+    ),
+    bind_actions(
+        KeyContext::Template,
+        "=",
+        &[Action::ResetLayout],
+        "Reset layout",
+    ),
+    bind(KeyContext::TemplatePicker, "↑↓", "Navigate templates"),
+    bind_actions(
+        KeyContext::TemplatePicker,
+        "L/Space",
+        &[Action::LoadTemplate],
+        "Load template",
+    ),
+    bind(KeyContext::TemplateEditing, "Type", "Edit template"),
+    bind(KeyContext::TemplateEditing, "Esc", "Exit edit mode"),
+    bind(KeyContext::TemplateSaving, "Type", "Template name"),
+    bind(
+        KeyContext::TemplateSaving,
+        "Enter",
+        "Save to the user library",
+    ),
+    bind(KeyContext::TemplateSaving, "Esc", "Cancel"),
+    bind(KeyContext::Variables, "↑↓", "Navigate"),
+    bind(KeyContext::Variables, "Enter/Space", "Set missing variable"),
+    bind(KeyContext::Variables, "Esc", "Exit variables"),
+    bind(KeyContext::VariableInput, "Type", "Enter value"),
+    bind(KeyContext::VariableInput, "Enter", "Save"),
+    bind(KeyContext::VariableInput, "Esc", "Cancel"),
+    bind(KeyContext::Output, "↑↓/PgUp/PgDn", "Scroll"),
+    bind(KeyContext::Output, "Home/End", "Top/bottom"),
+    bind_actions(
+        KeyContext::Output,
+        "C/Y",
+        &[Action::CopyPrompt],
+        "Copy to clipboard",
+    ),
+    bind_actions(
+        KeyContext::Output,
+        "S",
+        &[Action::SavePrompt],
+        "Save to file",
+    ),
+    bind_actions(
+        KeyContext::Output,
+        "/",
+        &[Action::SearchPrompt],
+        "Search the prompt",
+    ),
+    bind_actions(
+        KeyContext::Output,
+        "n/N",
+        &[Action::NextMatch, Action::PreviousMatch],
+        "Next/previous match",
+    ),
+    bind(KeyContext::Output, "Esc", "Clear search"),
+    bind(KeyContext::OutputSearch, "Type", "Search the prompt"),
+    bind(
+        KeyContext::OutputSearch,
+        "Enter",
+        "Keep matches highlighted",
+    ),
+    bind(KeyContext::OutputSearch, "Esc", "Exit search"),
+    bind(KeyContext::Git, "↑↓", "Navigate branches"),
+    bind_actions(
+        KeyContext::Git,
+        "Space",
+        &[Action::PickBranch],
+        "Pick base, then head",
+    ),
+    bind_actions(
+        KeyContext::Git,
+        "D",
+        &[Action::ToggleBranchDiff],
+        "Include/remove branch diff",
+    ),
+    bind_actions(
+        KeyContext::Git,
+        "L",
+        &[Action::ToggleBranchLog],
+        "Include/remove branch log",
+    ),
+    bind_actions(
+        KeyContext::Git,
+        "O",
+        &[Action::ChangedFilesOnly],
+        "Changed files only",
+    ),
+    bind_actions(
+        KeyContext::Git,
+        "W",
+        &[Action::CycleDiffStyle],
+        "Cycle diff style",
+    ),
+    bind_actions(
+        KeyContext::Git,
+        "+/-",
+        &[Action::MoreDiffContext, Action::LessDiffContext],
+        "Diff context lines",
+    ),
+    bind_actions(KeyContext::Git, "X", &[Action::ClearPicks], "Clear picks"),
+    bind_actions(
+        KeyContext::Git,
+        "R",
+        &[Action::ReloadBranches],
+        "Reload branches",
+    ),
+    bind_actions(
+        KeyContext::Git,
+        "V",
+        &[Action::OpenDiffViewer],
+        "Review the diff",
+    ),
+    bind(KeyContext::GitDiff, "↑↓/PgUp/PgDn", "Navigate"),
+    bind(KeyContext::GitDiff, "Enter/←→", "Expand/collapse file"),
+    bind_actions(
+        KeyContext::GitDiff,
+        "Space",
+        &[Action::ExcludeDiffFile],
+        "Exclude/include file",
+    ),
+    bind_actions(
+        KeyContext::GitDiff,
+        "R",
+        &[Action::ReloadDiff],
+        "Reload diff",
+    ),
+    bind_actions(
+        KeyContext::GitDiff,
+        "V",
+        &[Action::CloseDiffViewer],
+        "Back to branches",
+    ),
+];
+
+/// The contexts active in the current tab and mode, most specific first
+pub fn active_contexts(model: &Model) -> Vec<KeyContext> {
+    match model.current_tab {
+        Tab::FileTree if model.file_tree_input_mode == FileTreeInputMode::Search => {
+            vec![KeyContext::FileTreeSearch]
+        }
+        Tab::FileTree => vec![KeyContext::FileTree, KeyContext::Global],
+        Tab::Settings if model.settings.search_active => vec![KeyContext::SettingsSearch],
+        Tab::Settings if model.settings.patterns.is_typing() => {
+            vec![KeyContext::SettingsPatternInput]
+        }
+        Tab::Settings if model.settings.patterns.active => {
+            vec![KeyContext::SettingsPatterns, KeyContext::Global]
+        }
+        Tab::Settings => vec![KeyContext::Settings, KeyContext::Global],
+        Tab::Statistics => vec![KeyContext::Statistics, KeyContext::Global],
+        Tab::Template => match model.template.get_focus_mode() {
+            FocusMode::EditingTemplate => vec![KeyContext::TemplateEditing],
+            FocusMode::SavingTemplate => vec![KeyContext::TemplateSaving],
+            FocusMode::EditingVariable if model.template.variables.is_editing() => {
+                vec![KeyContext::VariableInput]
+            }
+            FocusMode::EditingVariable => vec![KeyContext::Variables],
+            FocusMode::Normal if model.template.get_focus() == TemplateFocus::Picker => vec![
+                KeyContext::TemplatePicker,
+                KeyContext::Template,
+                KeyContext::Global,
+            ],
+            FocusMode::Normal => vec![KeyContext::Template, KeyContext::Global],
+        },
+        Tab::PromptOutput if model.prompt_output.search_active => {
+            vec![KeyContext::OutputSearch]
+        }
+        Tab::PromptOutput => vec![KeyContext::Output, KeyContext::Global],
+        Tab::Git if model.git.show_diff => vec![KeyContext::GitDiff, KeyContext::Global],
+        Tab::Git => vec![KeyContext::Git, KeyContext::Global],
+    }
+}
+
+/// The bindings of a context
+pub fn bindings_for(context: KeyContext) -> impl Iterator<Item = &'static KeyBinding> {
+    KEY_BINDINGS
+        .iter()
+        .filter(move |binding| binding.context == context)
+}
+
+/// The bindings valid in the current tab and mode, most specific first
+pub fn active_bindings(model: &Model) -> Vec<&'static KeyBinding> {
+    active_contexts(model)
+        .into_iter()
+        .flat_map(bindings_for)
+        .collect()
+}
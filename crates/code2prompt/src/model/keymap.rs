@@ -0,0 +1,599 @@
+//! Remappable TUI keys.
+//!
+//! Every command of the TUI is an [`Action`] with default keys. The keys can be remapped in
+//! `<config dir>/code2prompt/keymap.toml`, which maps action names to a key or a list of
+//! keys:
+//!
+//! ```toml
+//! run_analysis = "F5"
+//! quit = ["Ctrl+Q", "Esc"]
+//! refresh_tree = "Ctrl+L"
+//! ```
+//!
+//! Navigation (arrows, PgUp/PgDn, Home/End) and text input are not remappable.
+
+use super::keybindings::KeyContext;
+use anyhow::{Context, Result, bail};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+
+/// A command of the TUI that can be bound to keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    RunAnalysis,
+    Quit,
+    Undo,
+    Redo,
+    ToggleHelp,
+    NextTab,
+    PreviousTab,
+    SwitchToSelection,
+    SwitchToSettings,
+    SwitchToStatistics,
+    SwitchToTemplate,
+    SwitchToOutput,
+    SwitchToGit,
+    ToggleSelect,
+    CycleInclusion,
+    SearchFiles,
+    RefreshTree,
+    ToggleSetting,
+    SearchSettings,
+    ResetSetting,
+    EditPatterns,
+    AddIncludePattern,
+    AddExcludePattern,
+    RemovePattern,
+    MovePatternUp,
+    MovePatternDown,
+    ClosePatterns,
+    SortBreakdown,
+    EditTemplate,
+    EditVariables,
+    FocusPicker,
+    SaveTemplate,
+    TogglePreview,
+    ReloadTemplate,
+    GrowColumn,
+    ShrinkColumn,
+    MoveColumnLeft,
+    MoveColumnRight,
+    ResetLayout,
+    LoadTemplate,
+    /// Rescan the template library, without a default key
+    RefreshTemplates,
+    CopyPrompt,
+    SavePrompt,
+    SearchPrompt,
+    NextMatch,
+    PreviousMatch,
+    PickBranch,
+    ToggleBranchDiff,
+    ToggleBranchLog,
+    ChangedFilesOnly,
+    CycleDiffStyle,
+    MoreDiffContext,
+    LessDiffContext,
+    ClearPicks,
+    ReloadBranches,
+    OpenDiffViewer,
+    ExcludeDiffFile,
+    ReloadDiff,
+    CloseDiffViewer,
+}
+
+impl Action {
+    /// All actions, in the order they are resolved within a context
+    pub const ALL: &'static [Action] = &[
+        Action::RunAnalysis,
+        Action::Quit,
+        Action::Undo,
+        Action::Redo,
+        Action::ToggleHelp,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::SwitchToSelection,
+        Action::SwitchToSettings,
+        Action::SwitchToStatistics,
+        Action::SwitchToTemplate,
+        Action::SwitchToOutput,
+        Action::SwitchToGit,
+        Action::ToggleSelect,
+        Action::CycleInclusion,
+        Action::SearchFiles,
+        Action::RefreshTree,
+        Action::ToggleSetting,
+        Action::SearchSettings,
+        Action::ResetSetting,
+        Action::EditPatterns,
+        Action::AddIncludePattern,
+        Action::AddExcludePattern,
+        Action::RemovePattern,
+        Action::MovePatternUp,
+        Action::MovePatternDown,
+        Action::ClosePatterns,
+        Action::SortBreakdown,
+        Action::EditTemplate,
+        Action::EditVariables,
+        Action::FocusPicker,
+        Action::SaveTemplate,
+        Action::TogglePreview,
+        Action::ReloadTemplate,
+        Action::GrowColumn,
+        Action::ShrinkColumn,
+        Action::MoveColumnLeft,
+        Action::MoveColumnRight,
+        Action::ResetLayout,
+        Action::LoadTemplate,
+        Action::RefreshTemplates,
+        Action::CopyPrompt,
+        Action::SavePrompt,
+        Action::SearchPrompt,
+        Action::NextMatch,
+        Action::PreviousMatch,
+        Action::PickBranch,
+        Action::ToggleBranchDiff,
+        Action::ToggleBranchLog,
+        Action::ChangedFilesOnly,
+        Action::CycleDiffStyle,
+        Action::MoreDiffContext,
+        Action::LessDiffContext,
+        Action::ClearPicks,
+        Action::ReloadBranches,
+        Action::OpenDiffViewer,
+        Action::ExcludeDiffFile,
+        Action::ReloadDiff,
+        Action::CloseDiffViewer,
+    ];
+
+    /// Name of the action in the keymap file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::RunAnalysis => "run_analysis",
+            Action::Quit => "quit",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::ToggleHelp => "toggle_help",
+            Action::NextTab => "next_tab",
+            Action::PreviousTab => "previous_tab",
+            Action::SwitchToSelection => "switch_to_selection",
+            Action::SwitchToSettings => "switch_to_settings",
+            Action::SwitchToStatistics => "switch_to_statistics",
+            Action::SwitchToTemplate => "switch_to_template",
+            Action::SwitchToOutput => "switch_to_output",
+            Action::SwitchToGit => "switch_to_git",
+            Action::ToggleSelect => "toggle_select",
+            Action::CycleInclusion => "cycle_inclusion",
+            Action::SearchFiles => "search_files",
+            Action::RefreshTree => "refresh_tree",
+            Action::ToggleSetting => "toggle_setting",
+            Action::SearchSettings => "search_settings",
+            Action::ResetSetting => "reset_setting",
+            Action::EditPatterns => "edit_patterns",
+            Action::AddIncludePattern => "add_include_pattern",
+            Action::AddExcludePattern => "add_exclude_pattern",
+            Action::RemovePattern => "remove_pattern",
+            Action::MovePatternUp => "move_pattern_up",
+            Action::MovePatternDown => "move_pattern_down",
+            Action::ClosePatterns => "close_patterns",
+            Action::SortBreakdown => "sort_breakdown",
+            Action::EditTemplate => "edit_template",
+            Action::EditVariables => "edit_variables",
+            Action::FocusPicker => "focus_picker",
+            Action::SaveTemplate => "save_template",
+            Action::TogglePreview => "toggle_preview",
+            Action::ReloadTemplate => "reload_template",
+            Action::GrowColumn => "grow_column",
+            Action::ShrinkColumn => "shrink_column",
+            Action::MoveColumnLeft => "move_column_left",
+            Action::MoveColumnRight => "move_column_right",
+            Action::ResetLayout => "reset_layout",
+            Action::LoadTemplate => "load_template",
+            Action::RefreshTemplates => "refresh_templates",
+            Action::CopyPrompt => "copy_prompt",
+            Action::SavePrompt => "save_prompt",
+            Action::SearchPrompt => "search_prompt",
+            Action::NextMatch => "next_match",
+            Action::PreviousMatch => "previous_match",
+            Action::PickBranch => "pick_branch",
+            Action::ToggleBranchDiff => "toggle_branch_diff",
+            Action::ToggleBranchLog => "toggle_branch_log",
+            Action::ChangedFilesOnly => "changed_files_only",
+            Action::CycleDiffStyle => "cycle_diff_style",
+            Action::MoreDiffContext => "more_diff_context",
+            Action::LessDiffContext => "less_diff_context",
+            Action::ClearPicks => "clear_picks",
+            Action::ReloadBranches => "reload_branches",
+            Action::OpenDiffViewer => "open_diff_viewer",
+            Action::ExcludeDiffFile => "exclude_diff_file",
+            Action::ReloadDiff => "reload_diff",
+            Action::CloseDiffViewer => "close_diff_viewer",
+        }
+    }
+
+    /// Find an action by its name in the keymap file
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+
+    /// Context in which the action applies
+    pub fn context(&self) -> KeyContext {
+        match self {
+            Action::RunAnalysis
+            | Action::Quit
+            | Action::Undo
+            | Action::Redo
+            | Action::ToggleHelp
+            | Action::NextTab
+            | Action::PreviousTab
+            | Action::SwitchToSelection
+            | Action::SwitchToSettings
+            | Action::SwitchToStatistics
+            | Action::SwitchToTemplate
+            | Action::SwitchToOutput
+            | Action::SwitchToGit => KeyContext::Global,
+            Action::ToggleSelect
+            | Action::CycleInclusion
+            | Action::SearchFiles
+            | Action::RefreshTree => KeyContext::FileTree,
+            Action::ToggleSetting
+            | Action::SearchSettings
+            | Action::ResetSetting
+            | Action::EditPatterns => KeyContext::Settings,
+            Action::AddIncludePattern
+            | Action::AddExcludePattern
+            | Action::RemovePattern
+            | Action::MovePatternUp
+            | Action::MovePatternDown
+            | Action::ClosePatterns => KeyContext::SettingsPatterns,
+            Action::SortBreakdown => KeyContext::Statistics,
+            Action::EditTemplate
+            | Action::EditVariables
+            | Action::FocusPicker
+            | Action::SaveTemplate
+            | Action::TogglePreview
+            | Action::ReloadTemplate
+            | Action::GrowColumn
+            | Action::ShrinkColumn
+            | Action::MoveColumnLeft
+            | Action::MoveColumnRight
+            | Action::ResetLayout => KeyContext::Template,
+            Action::LoadTemplate | Action::RefreshTemplates => KeyContext::TemplatePicker,
+            Action::CopyPrompt
+            | Action::SavePrompt
+            | Action::SearchPrompt
+            | Action::NextMatch
+            | Action::PreviousMatch => KeyContext::Output,
+            Action::PickBranch
+            | Action::ToggleBranchDiff
+            | Action::ToggleBranchLog
+            | Action::ChangedFilesOnly
+            | Action::CycleDiffStyle
+            | Action::MoreDiffContext
+            | Action::LessDiffContext
+            | Action::ClearPicks
+            | Action::ReloadBranches
+            | Action::OpenDiffViewer => KeyContext::Git,
+            Action::ExcludeDiffFile | Action::ReloadDiff | Action::CloseDiffViewer => {
+                KeyContext::GitDiff
+            }
+        }
+    }
+
+    /// Keys bound to the action when the keymap does not remap it
+    pub fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::RunAnalysis => &["Enter"],
+            Action::Quit => &["Esc", "Ctrl+Q"],
+            Action::Undo => &["u", "U"],
+            Action::Redo => &["Ctrl+R"],
+            Action::ToggleHelp => &["?"],
+            Action::NextTab => &["Tab"],
+            Action::PreviousTab => &["Shift+Tab"],
+            Action::SwitchToSelection => &["1"],
+            Action::SwitchToSettings => &["2"],
+            Action::SwitchToStatistics => &["3"],
+            Action::SwitchToTemplate => &["4"],
+            Action::SwitchToOutput => &["5"],
+            Action::SwitchToGit => &["6"],
+            Action::ToggleSelect => &["Space"],
+            Action::CycleInclusion => &["t", "T"],
+            Action::SearchFiles => &["/", "s", "S"],
+            Action::RefreshTree => &["r", "R"],
+            Action::ToggleSetting => &["Space"],
+            Action::SearchSettings => &["/"],
+            Action::ResetSetting => &["r", "R"],
+            Action::EditPatterns => &["p", "P"],
+            Action::AddIncludePattern => &["i", "I"],
+            Action::AddExcludePattern => &["x", "X"],
+            Action::RemovePattern => &["d", "D", "Delete"],
+            Action::MovePatternUp => &["["],
+            Action::MovePatternDown => &["]"],
+            Action::ClosePatterns => &["p", "P"],
+            Action::SortBreakdown => &["s", "S"],
+            Action::EditTemplate => &["e", "E"],
+            Action::EditVariables => &["v", "V"],
+            Action::FocusPicker => &["p", "P"],
+            Action::SaveTemplate => &["s", "S"],
+            Action::TogglePreview => &["w", "W"],
+            Action::ReloadTemplate => &["r", "R"],
+            Action::GrowColumn => &[">"],
+            Action::ShrinkColumn => &["<"],
+            Action::MoveColumnLeft => &["["],
+            Action::MoveColumnRight => &["]"],
+            Action::ResetLayout => &["="],
+            Action::LoadTemplate => &["l", "L", "Space"],
+            Action::RefreshTemplates => &[],
+            Action::CopyPrompt => &["c", "C", "y", "Y"],
+            Action::SavePrompt => &["s", "S"],
+            Action::SearchPrompt => &["/"],
+            Action::NextMatch => &["n"],
+            Action::PreviousMatch => &["N"],
+            Action::PickBranch => &["Space"],
+            Action::ToggleBranchDiff => &["d", "D"],
+            Action::ToggleBranchLog => &["l", "L"],
+            Action::ChangedFilesOnly => &["o", "O"],
+            Action::CycleDiffStyle => &["w", "W"],
+            Action::MoreDiffContext => &["+", "="],
+            Action::LessDiffContext => &["-"],
+            Action::ClearPicks => &["x", "X"],
+            Action::ReloadBranches => &["r", "R"],
+            Action::OpenDiffViewer => &["v", "V"],
+            Action::ExcludeDiffFile => &["Space"],
+            Action::ReloadDiff => &["r", "R"],
+            Action::CloseDiffViewer => &["v", "V"],
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Whether the actions of two contexts can be triggered from the same screen
+fn contexts_overlap(a: KeyContext, b: KeyContext) -> bool {
+    let template = [KeyContext::Template, KeyContext::TemplatePicker];
+    a == b
+        || a == KeyContext::Global
+        || b == KeyContext::Global
+        || (template.contains(&a) && template.contains(&b))
+}
+
+/// A key with its Ctrl and Alt modifiers.
+///
+/// Characters are matched as typed, so `r` and `R` are different keys. Shift is only
+/// meaningful with Tab, as `Shift+Tab`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// The chord of a key event
+    pub fn from_event(key: KeyEvent) -> Self {
+        let modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let code = match key.code {
+            KeyCode::Tab if key.modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            // Terminals report Ctrl+Shift+R as Ctrl+R or Ctrl+Shift+r
+            KeyCode::Char(c) if !modifiers.is_empty() => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parse a key as written in the keymap file, such as `Ctrl+R`, `Enter` or `s`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec;
+        // A lone "+" is a key, not a modifier separator
+        while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers |= KeyModifiers::CONTROL,
+                "alt" => modifiers |= KeyModifiers::ALT,
+                "shift" => modifiers |= KeyModifiers::SHIFT,
+                _ => bail!("Unknown modifier `{}` in key `{}`", modifier, spec),
+            }
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=24).contains(&n) => KeyCode::F(n),
+                    _ => bail!("Unknown key `{}`", spec),
+                },
+            },
+        };
+
+        Ok(Self::from_event(KeyEvent::new(code, modifiers)))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) if !self.modifiers.is_empty() => {
+                write!(f, "{}", c.to_ascii_uppercase())
+            }
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::BackTab => f.write_str("Shift+Tab"),
+            KeyCode::Delete => f.write_str("Del"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            code => write!(f, "{}", code),
+        }
+    }
+}
+
+/// One key or a list of keys in the keymap file
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "a key or a list of keys")]
+enum KeySpecs {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The keys bound to each action
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    keys: HashMap<Action, Vec<KeyChord>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let keys = Action::ALL
+            .iter()
+            .map(|&action| {
+                let chords = action
+                    .default_keys()
+                    .iter()
+                    .map(|spec| KeyChord::parse(spec).expect("Invalid default key"))
+                    .collect();
+                (action, chords)
+            })
+            .collect();
+        Self { keys }
+    }
+}
+
+impl Keymap {
+    /// Parse a keymap file. The actions it does not mention keep their default keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The TOML content, mapping action names to a key or a list of keys
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Keymap>` - The keymap, or an error for an unknown action or key, or for a
+    ///   key bound to two actions of the same screen
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let specs: BTreeMap<String, KeySpecs> = toml::from_str(content)?;
+        let mut keymap = Self::default();
+        for (name, specs) in specs {
+            let action =
+                Action::from_name(&name).with_context(|| format!("Unknown action `{}`", name))?;
+            let specs = match specs {
+                KeySpecs::One(spec) => vec![spec],
+                KeySpecs::Many(specs) => specs,
+            };
+            let chords = specs
+                .iter()
+                .map(|spec| KeyChord::parse(spec))
+                .collect::<Result<Vec<_>>>()
+                .with_context(|| format!("Invalid keys for `{}`", name))?;
+            keymap.keys.insert(action, chords);
+        }
+        keymap.check_conflicts()?;
+        Ok(keymap)
+    }
+
+    /// Fail when a key triggers two actions of the same screen
+    fn check_conflicts(&self) -> Result<()> {
+        for (index, &first) in Action::ALL.iter().enumerate() {
+            for &second in &Action::ALL[index + 1..] {
+                if !contexts_overlap(first.context(), second.context()) {
+                    continue;
+                }
+                if let Some(chord) = self
+                    .keys(first)
+                    .iter()
+                    .find(|chord| self.keys(second).contains(chord))
+                {
+                    bail!("`{}` is bound to both `{}` and `{}`", chord, first, second);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The keys bound to an action
+    pub fn keys(&self, action: Action) -> &[KeyChord] {
+        self.keys
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Whether the keymap binds other keys than the default ones to an action
+    pub fn is_remapped(&self, action: Action) -> bool {
+        let defaults = action
+            .default_keys()
+            .iter()
+            .filter_map(|spec| KeyChord::parse(spec).ok());
+        !defaults.eq(self.keys(action).iter().copied())
+    }
+
+    /// The keys of an action as shown to the user, such as `Ctrl+R/F5`
+    pub fn label(&self, action: Action) -> String {
+        self.keys(action)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// The action of a context bound to a key, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The context whose actions are considered
+    /// * `key` - The key event
+    pub fn action(&self, context: KeyContext, key: KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from_event(key);
+        Action::ALL
+            .iter()
+            .copied()
+            .filter(|action| action.context() == context)
+            .find(|&action| self.keys(action).contains(&chord))
+    }
+}
+
+/// Path of the keymap file, if a configuration directory is available
+pub fn keymap_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("code2prompt").join("keymap.toml"))
+}
+
+/// Load the keymap file, falling back to the default keys when the file is missing
+pub fn load_keymap() -> Result<Keymap> {
+    let Some(path) = keymap_file_path().filter(|path| path.exists()) else {
+        return Ok(Keymap::default());
+    };
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Keymap::from_toml(&content).with_context(|| format!("Invalid keymap {}", path.display()))
+}
@@ -0,0 +1,107 @@
+//! Layout preferences of the TUI tabs.
+//!
+//! These are adjusted with keyboard shortcuts and persisted across sessions
+//! in the TUI state file.
+
+use super::template::TemplateFocus;
+use serde::{Deserialize, Serialize};
+
+/// Smallest width of a template column, in percent of the tab width.
+const MIN_COLUMN_PERCENT: u16 = 10;
+
+/// Step used when resizing a column, in percent of the tab width.
+pub const RESIZE_STEP: i16 = 5;
+
+/// Layout preferences persisted in the state file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutPrefs {
+    /// Template tab columns, from left to right
+    pub template_order: [TemplateFocus; 3],
+    /// Widths of the template tab columns, from left to right, in percent
+    pub template_widths: [u16; 3],
+}
+
+impl Default for LayoutPrefs {
+    fn default() -> Self {
+        Self {
+            template_order: [
+                TemplateFocus::Editor,
+                TemplateFocus::Variables,
+                TemplateFocus::Picker,
+            ],
+            template_widths: [40, 35, 25],
+        }
+    }
+}
+
+impl LayoutPrefs {
+    /// Position of a template column, from left to right
+    pub fn template_position(&self, column: TemplateFocus) -> usize {
+        self.template_order
+            .iter()
+            .position(|&c| c == column)
+            .unwrap_or_default()
+    }
+
+    /// Grow (or shrink, with a negative delta) a template column, taking the space from
+    /// its right neighbour, or its left one for the last column.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the layout changed
+    pub fn resize_template_column(&mut self, column: TemplateFocus, delta: i16) -> bool {
+        let position = self.template_position(column);
+        let neighbour = if position + 1 < self.template_widths.len() {
+            position + 1
+        } else {
+            position - 1
+        };
+
+        let widths = &mut self.template_widths;
+        let max = widths[position] + widths[neighbour] - MIN_COLUMN_PERCENT;
+        let resized =
+            (widths[position] as i16 + delta).clamp(MIN_COLUMN_PERCENT as i16, max as i16) as u16;
+        if resized == widths[position] {
+            return false;
+        }
+        widths[neighbour] = widths[neighbour] + widths[position] - resized;
+        widths[position] = resized;
+        true
+    }
+
+    /// Move a template column one position to the left (negative direction) or right,
+    /// keeping its width.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the layout changed
+    pub fn move_template_column(&mut self, column: TemplateFocus, direction: i8) -> bool {
+        let position = self.template_position(column);
+        let target = if direction < 0 {
+            position.checked_sub(1)
+        } else {
+            Some(position + 1).filter(|&target| target < self.template_order.len())
+        };
+        let Some(target) = target else {
+            return false;
+        };
+        self.template_order.swap(position, target);
+        self.template_widths.swap(position, target);
+        true
+    }
+
+    /// Whether the preferences are usable: every column shown once, widths adding up to 100%
+    /// and no column narrower than the minimum. Invalid preferences are replaced by defaults.
+    pub fn is_valid(&self) -> bool {
+        let [a, b, c] = self.template_order;
+        a != b
+            && b != c
+            && a != c
+            && self.template_widths.iter().sum::<u16>() == 100
+            && self
+                .template_widths
+                .iter()
+                .all(|&width| width >= MIN_COLUMN_PERCENT)
+    }
+}
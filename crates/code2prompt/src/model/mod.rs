@@ -6,21 +6,35 @@
 //! for the terminal user interface.
 
 pub mod commands;
+pub mod git;
+pub mod history;
+pub mod keybindings;
+pub mod keymap;
+pub mod layout;
+pub mod patterns;
 pub mod prompt_output;
 pub mod settings;
 pub mod statistics;
 pub mod template;
 
 pub use commands::*;
+pub use git::*;
+pub use history::*;
+pub use layout::*;
+pub use patterns::*;
 pub use prompt_output::*;
 pub use settings::*;
 pub use statistics::*;
 pub use template::*;
 
 use crate::utils::directory_contains_selected_files;
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::selection::FileInclusion;
 use code2prompt_core::session::Code2PromptSession;
+use keymap::Keymap;
+use std::sync::Arc;
 
-/// The five main tabs of the TUI
+/// The six main tabs of the TUI
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Tab {
     FileTree,
@@ -28,6 +42,7 @@ pub enum Tab {
     Statistics,
     Template,
     PromptOutput,
+    Git,
 }
 
 /// Input mode for the FileTree tab
@@ -47,6 +62,8 @@ pub struct DisplayFileNode {
     pub level: usize,
     pub children_loaded: bool,
     pub children: Vec<DisplayFileNode>,
+    /// Token count of a file, filled in asynchronously once the node is loaded
+    pub token_count: Option<usize>,
 }
 
 impl DisplayFileNode {
@@ -66,6 +83,7 @@ impl DisplayFileNode {
             level,
             children_loaded: false,
             children: Vec::new(),
+            token_count: None,
         }
     }
 
@@ -135,13 +153,17 @@ impl DisplayFileNode {
 pub enum Message {
     SwitchTab(Tab),
     Quit,
+    ToggleHelp,
+    ScrollHelp(i16),
 
     UpdateSearchQuery(String),
     ToggleFileSelection(usize),
+    CycleFileInclusion(usize),
     ExpandDirectory(usize),
     CollapseDirectory(usize),
     MoveTreeCursor(i32),
     RefreshFileTree,
+    FileTokensCounted(Vec<(std::path::PathBuf, usize)>),
 
     EnterSearchMode,
     ExitSearchMode,
@@ -149,18 +171,41 @@ pub enum Message {
     MoveSettingsCursor(i32),
     ToggleSetting(usize),
     CycleSetting(usize),
+    ResetSetting(usize),
+    EnterSettingsSearch,
+    ExitSettingsSearch,
+    UpdateSettingsSearch(String),
+    TogglePatternEditor,
+    MovePatternCursor(i32),
+    StartPatternInput(PatternKind),
+    UpdatePatternInput(String),
+    SubmitPatternInput,
+    CancelPatternInput,
+    RemovePattern,
+    MovePattern(i8),
+    ConfigReloaded(Box<Code2PromptConfig>),
+    ConfigReloadFailed(String),
 
     RunAnalysis,
+    AnalysisProgress(AnalysisProgress),
     AnalysisComplete(AnalysisResults),
     AnalysisError(String),
 
     CopyToClipboard,
     SaveToFile(String),
     ScrollOutput(i16),
+    EnterOutputSearch,
+    SubmitOutputSearch,
+    ExitOutputSearch,
+    UpdateOutputSearch(String),
+    MoveOutputMatch(i8),
 
     CycleStatisticsView(i8),
     ScrollStatistics(i16),
+    CycleBreakdownSort,
 
+    StartSavingTemplate,
+    UpdateTemplateName(String),
     SaveTemplate(String),
     ReloadTemplate,
     LoadTemplate,
@@ -169,7 +214,11 @@ pub enum Message {
     SetTemplateFocus(TemplateFocus, FocusMode),
     SetTemplateFocusMode(FocusMode),
     TemplateEditorInput(ratatui::crossterm::event::KeyEvent),
+    ToggleTemplatePreview,
     TemplatePickerMove(i32),
+    ResizeTemplateColumn(i16),
+    MoveTemplateColumn(i8),
+    ResetLayout,
 
     VariableStartEditing(String),
     VariableInputChar(char),
@@ -178,6 +227,57 @@ pub enum Message {
     VariableInputCancel,
     VariableNavigateUp,
     VariableNavigateDown,
+
+    ReloadBranches,
+    BranchesLoaded(Result<Vec<String>, String>),
+    MoveBranchCursor(i32),
+    PickBranch(usize),
+    ClearBranchPicks,
+    ToggleDiffBranches,
+    ToggleLogBranches,
+    ToggleChangedFilesOnly,
+    CycleDiffStyle,
+    AdjustDiffContext(i32),
+    BranchDiffPreviewed {
+        base: String,
+        head: String,
+        result: Result<usize, String>,
+    },
+    ToggleDiffViewer,
+    ReloadDiffFiles,
+    DiffFilesLoaded(Result<Vec<(String, String)>, String>),
+    MoveDiffCursor(i32),
+    ToggleDiffFileExpanded,
+    ToggleDiffFileExcluded,
+
+    Undo,
+    Redo,
+}
+
+/// How long a toast stays on screen
+pub const TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Notification shown in a corner of the screen for `TOAST_DURATION`
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    pub is_error: bool,
+    pub shown_at: std::time::Instant,
+}
+
+impl Toast {
+    pub fn new(message: String, is_error: bool) -> Self {
+        Self {
+            message,
+            is_error,
+            shown_at: std::time::Instant::now(),
+        }
+    }
+
+    /// Whether the toast is still displayed
+    pub fn is_visible(&self) -> bool {
+        self.shown_at.elapsed() < TOAST_DURATION
+    }
 }
 
 /// Represents the overall state of the TUI application.
@@ -195,6 +295,16 @@ pub struct Model {
     pub statistics: StatisticsState,
     pub template: TemplateState,
     pub prompt_output: PromptOutputState,
+    pub git: GitState,
+    /// Edits of the selection and settings that can be undone
+    pub history: EditHistory,
+    pub layout: LayoutPrefs,
+    /// Keys bound to the actions, shared by the key handlers and the help
+    pub keymap: Arc<Keymap>,
+    pub show_help: bool,
+    pub help_scroll: u16,
+    /// Short-lived notification shown over the current tab
+    pub toast: Option<Toast>,
     pub status_message: String,
 }
 
@@ -216,6 +326,13 @@ impl Default for Model {
             statistics: StatisticsState::default(),
             template: TemplateState::default(),
             prompt_output: PromptOutputState::default(),
+            git: GitState::default(),
+            history: EditHistory::default(),
+            layout: LayoutPrefs::default(),
+            keymap: Arc::default(),
+            show_help: false,
+            help_scroll: 0,
+            toast: None,
             status_message: String::new(),
         }
     }
@@ -223,6 +340,11 @@ impl Default for Model {
 
 impl Model {
     pub fn new(session: Code2PromptSession) -> Self {
+        let settings = SettingsState::new(session.config.clone());
+        let mut template = TemplateState::default();
+        template
+            .picker
+            .set_project_root(session.config.path.clone());
         Model {
             session,
             current_tab: Tab::FileTree,
@@ -232,17 +354,64 @@ impl Model {
             search_query: String::new(),
             tree_cursor: 0,
             file_tree_scroll: 0,
-            settings: SettingsState::default(),
+            settings,
             statistics: StatisticsState::default(),
-            template: TemplateState::default(),
+            template,
             prompt_output: PromptOutputState::default(),
+            git: GitState::default(),
+            history: EditHistory::default(),
+            layout: LayoutPrefs::default(),
+            keymap: Arc::default(),
+            show_help: false,
+            help_scroll: 0,
+            toast: None,
             status_message: String::new(),
         }
     }
 
+    /// Summary of the state, written to crash reports
+    pub fn crash_summary(&self) -> String {
+        let config = &self.session.config;
+        format!(
+            "tab: {:?}\n\
+             path: {}\n\
+             file tree: {} root node(s), cursor {}, mode {:?}, search {:?}\n\
+             settings: cursor {}, search {:?}\n\
+             template: focus {:?}, mode {:?}, {} byte(s)\n\
+             analysis: in progress {}, error {:?}, {} file(s), {:?} token(s)\n\
+             output: {:?}, line numbers {}, diff {}, token counting {:?}\n\
+             edits: {} to undo, {} to redo\n\
+             help shown: {}\n\
+             status: {}",
+            self.current_tab,
+            config.path.display(),
+            self.file_tree_nodes.len(),
+            self.tree_cursor,
+            self.file_tree_input_mode,
+            self.search_query,
+            self.settings.settings_cursor,
+            self.settings.search_query,
+            self.template.get_focus(),
+            self.template.get_focus_mode(),
+            self.template.get_template_content().len(),
+            self.prompt_output.analysis_in_progress,
+            self.prompt_output.analysis_error,
+            self.prompt_output.file_count,
+            self.prompt_output.token_count,
+            config.output_format,
+            config.line_numbers,
+            config.diff_enabled,
+            config.token_count_mode,
+            self.history.undo_count(),
+            self.history.redo_count(),
+            self.show_help,
+            self.status_message,
+        )
+    }
+
     /// Get grouped settings for display
     pub fn get_settings_groups(&self) -> Vec<SettingsGroup> {
-        crate::view::format_settings_groups(&self.session)
+        self.settings.get_settings_groups(&self.session)
     }
 
     pub fn update(&self, message: Message) -> (Self, Cmd) {
@@ -255,9 +424,29 @@ impl Model {
                 (new_model, Cmd::None)
             }
 
+            Message::ToggleHelp => {
+                new_model.show_help = !new_model.show_help;
+                new_model.help_scroll = 0;
+                (new_model, Cmd::None)
+            }
+
+            Message::ScrollHelp(delta) => {
+                // Clamped by the help widget, which knows the viewport height
+                new_model.help_scroll = if delta < 0 {
+                    new_model.help_scroll.saturating_sub((-delta) as u16)
+                } else {
+                    new_model.help_scroll.saturating_add(delta as u16)
+                };
+                (new_model, Cmd::None)
+            }
+
             Message::SwitchTab(tab) => {
                 new_model.current_tab = tab;
                 new_model.status_message = format!("Switched to {:?} tab", tab);
+                // Branches are listed the first time the Git tab is opened
+                if tab == Tab::Git && new_model.git.branches.is_none() {
+                    return (new_model, Cmd::LoadBranches);
+                }
                 (new_model, Cmd::None)
             }
 
@@ -266,6 +455,11 @@ impl Model {
                 (new_model, Cmd::RefreshFileTree)
             }
 
+            Message::FileTokensCounted(counts) => {
+                crate::utils::apply_token_counts(&mut new_model.file_tree_nodes, &counts);
+                (new_model, Cmd::None)
+            }
+
             Message::UpdateSearchQuery(query) => {
                 new_model.search_query = query;
                 new_model.tree_cursor = 0; // Reset cursor when search changes
@@ -346,11 +540,47 @@ impl Model {
                         };
 
                     // Update session selection state (single source of truth)
+                    let before = EditState::of(&new_model.session);
                     new_model.session.toggle_file_selection(relative_path);
 
                     let action = if current { "Deselected" } else { "Selected" };
                     let extra = if is_directory { " (and contents)" } else { "" };
                     new_model.status_message = format!("{} {}{}", action, name, extra);
+                    new_model.record_edit(before);
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::CycleFileInclusion(index) => {
+                let visible_nodes = crate::utils::get_visible_nodes(
+                    &new_model.file_tree_nodes,
+                    &new_model.search_query,
+                    &mut new_model.session,
+                );
+
+                if let Some(display_node) = visible_nodes.get(index) {
+                    let node_path = display_node.node.path.clone();
+                    let name = display_node.node.name.clone();
+                    let is_directory = display_node.node.is_directory;
+                    let next = display_node.inclusion.next();
+
+                    let relative_path =
+                        if let Ok(rel) = node_path.strip_prefix(&new_model.session.config.path) {
+                            rel.to_path_buf()
+                        } else {
+                            node_path.clone()
+                        };
+                    let before = EditState::of(&new_model.session);
+                    new_model.session.set_file_inclusion(relative_path, next);
+
+                    let action = match next {
+                        FileInclusion::Full => "Included",
+                        FileInclusion::TreeOnly => "Listed in the tree only",
+                        FileInclusion::Excluded => "Excluded",
+                    };
+                    let extra = if is_directory { " (and contents)" } else { "" };
+                    new_model.status_message = format!("{}: {}{}", action, name, extra);
+                    new_model.record_edit(before);
                 }
                 (new_model, Cmd::None)
             }
@@ -406,7 +636,8 @@ impl Model {
                         new_model.status_message = format!("Could not find directory {}", name);
                     }
                 }
-                (new_model, Cmd::None)
+                let uncounted = crate::utils::uncounted_files(&new_model.file_tree_nodes);
+                (new_model, Cmd::CountFileTokens(uncounted))
             }
 
             Message::CollapseDirectory(index) => {
@@ -445,12 +676,14 @@ impl Model {
             Message::ToggleSetting(index) => {
                 let items = new_model.settings.get_settings_items(&new_model.session);
                 if let Some(item) = items.get(index) {
+                    let before = EditState::of(&new_model.session);
                     let setting_name = new_model.settings.update_setting_by_key(
                         &mut new_model.session,
                         item.key,
                         SettingAction::Toggle,
                     );
                     new_model.status_message = format!("Toggled {}", setting_name);
+                    new_model.record_edit(before);
                 } else {
                     new_model.status_message = format!("Invalid setting index: {}", index);
                 }
@@ -460,21 +693,181 @@ impl Model {
             Message::CycleSetting(index) => {
                 let items = new_model.settings.get_settings_items(&new_model.session);
                 if let Some(item) = items.get(index) {
+                    let before = EditState::of(&new_model.session);
                     let setting_name = new_model.settings.update_setting_by_key(
                         &mut new_model.session,
                         item.key,
                         SettingAction::Cycle,
                     );
                     new_model.status_message = format!("Cycled {}", setting_name);
+                    new_model.record_edit(before);
+                } else {
+                    new_model.status_message = format!("Invalid setting index: {}", index);
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::ResetSetting(index) => {
+                let items = new_model.settings.get_settings_items(&new_model.session);
+                if let Some(item) = items.get(index) {
+                    let before = EditState::of(&new_model.session);
+                    let setting_name = new_model
+                        .settings
+                        .reset_setting_by_key(&mut new_model.session, item.key);
+                    new_model.status_message = format!("Reset {}", setting_name);
+                    new_model.record_edit(before);
                 } else {
                     new_model.status_message = format!("Invalid setting index: {}", index);
                 }
                 (new_model, Cmd::None)
             }
 
+            Message::ConfigReloaded(config) => {
+                let outcome = new_model
+                    .settings
+                    .apply_new_defaults(&mut new_model.session, *config);
+                let mut toast = if outcome.updated.is_empty() {
+                    "Config reloaded, no change applied".to_string()
+                } else {
+                    format!("Config reloaded: {}", outcome.updated.join(", "))
+                };
+                if !outcome.conflicts.is_empty() {
+                    toast.push_str(&format!(
+                        " | Kept local changes to {} (r resets them to the file)",
+                        outcome.conflicts.join(", ")
+                    ));
+                }
+                new_model.status_message = toast.clone();
+                new_model.toast = Some(Toast::new(toast, false));
+
+                // Patterns and walk options change the tree
+                let cmd = if outcome.updated.is_empty() {
+                    Cmd::None
+                } else {
+                    Cmd::RefreshFileTree
+                };
+                (new_model, cmd)
+            }
+
+            Message::ConfigReloadFailed(error) => {
+                new_model.status_message = format!("Config reload failed: {}", error);
+                new_model.toast = Some(Toast::new(new_model.status_message.clone(), true));
+                (new_model, Cmd::None)
+            }
+
+            Message::EnterSettingsSearch => {
+                new_model.settings.search_active = true;
+                new_model.status_message =
+                    "Settings search - Type to filter, Enter to toggle, Esc to exit".to_string();
+                (new_model, Cmd::None)
+            }
+
+            Message::ExitSettingsSearch => {
+                new_model.settings.search_active = false;
+                new_model.settings.search_query.clear();
+                new_model.settings.settings_cursor = 0;
+                new_model.status_message = "Exited settings search".to_string();
+                (new_model, Cmd::None)
+            }
+
+            Message::UpdateSettingsSearch(query) => {
+                new_model.settings.search_query = query;
+                new_model.settings.settings_cursor = 0;
+                (new_model, Cmd::None)
+            }
+
+            Message::TogglePatternEditor => {
+                let editor = &mut new_model.settings.patterns;
+                editor.active = !editor.active;
+                editor.input = None;
+                editor.move_cursor(&new_model.session, 0);
+                new_model.status_message = if editor.active {
+                    "Editing the include and exclude patterns".to_string()
+                } else {
+                    "Closed the pattern editor".to_string()
+                };
+                (new_model, Cmd::None)
+            }
+
+            Message::MovePatternCursor(delta) => {
+                new_model
+                    .settings
+                    .patterns
+                    .move_cursor(&new_model.session, delta);
+                (new_model, Cmd::None)
+            }
+
+            Message::StartPatternInput(kind) => {
+                new_model.settings.patterns.input = Some((kind, String::new()));
+                new_model.status_message = format!(
+                    "New {} pattern - Enter to add, Esc to cancel",
+                    kind.as_str()
+                );
+                (new_model, Cmd::None)
+            }
+
+            Message::UpdatePatternInput(pattern) => {
+                if let Some((_, input)) = &mut new_model.settings.patterns.input {
+                    *input = pattern;
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::SubmitPatternInput => {
+                let before = EditState::of(&new_model.session);
+                match new_model
+                    .settings
+                    .patterns
+                    .add_input(&mut new_model.session)
+                {
+                    Some(status) => {
+                        new_model.status_message = status;
+                        new_model.record_edit(before);
+                    }
+                    None => {
+                        new_model.status_message =
+                            "Pattern empty or already listed, nothing added".to_string();
+                    }
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::CancelPatternInput => {
+                new_model.settings.patterns.input = None;
+                new_model.status_message = "Cancelled the new pattern".to_string();
+                (new_model, Cmd::None)
+            }
+
+            Message::RemovePattern => {
+                let before = EditState::of(&new_model.session);
+                if let Some(status) = new_model
+                    .settings
+                    .patterns
+                    .remove_selected(&mut new_model.session)
+                {
+                    new_model.status_message = status;
+                    new_model.record_edit(before);
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::MovePattern(delta) => {
+                let before = EditState::of(&new_model.session);
+                if let Some(status) = new_model
+                    .settings
+                    .patterns
+                    .move_selected(&mut new_model.session, delta)
+                {
+                    new_model.status_message = status;
+                    new_model.record_edit(before);
+                }
+                (new_model, Cmd::None)
+            }
+
             Message::RunAnalysis => {
                 if !new_model.prompt_output.analysis_in_progress {
                     new_model.prompt_output.analysis_in_progress = true;
+                    new_model.prompt_output.progress = Some(AnalysisProgress::default());
                     new_model.prompt_output.analysis_error = None;
                     new_model.status_message = "Running analysis...".to_string();
                     new_model.current_tab = Tab::PromptOutput; // Switch to output tab
@@ -490,24 +883,49 @@ impl Model {
                 }
             }
 
+            Message::AnalysisProgress(progress) => {
+                // Progress of an analysis that already ended is ignored
+                if new_model.prompt_output.analysis_in_progress {
+                    new_model.prompt_output.progress = Some(progress);
+                }
+                (new_model, Cmd::None)
+            }
+
             Message::AnalysisComplete(results) => {
                 new_model.prompt_output.analysis_in_progress = false;
+                new_model.prompt_output.progress = None;
                 new_model.prompt_output.generated_prompt = Some(results.generated_prompt);
                 new_model.prompt_output.token_count = results.token_count;
+                new_model.prompt_output.token_breakdown = results.token_breakdown;
                 new_model.prompt_output.file_count = results.file_count;
                 // Reset output scroll so the new content starts at the top.
                 new_model.prompt_output.output_scroll = 0;
+                new_model.prompt_output.update_search_matches();
                 new_model.statistics.token_map_entries = results.token_map_entries;
+                new_model.statistics.token_distribution = results.token_distribution;
+                if let Some(tokens) = results.token_count {
+                    new_model.statistics.record_run(tokens);
+                }
+                new_model.statistics.record_snapshot(results.snapshot);
                 let tokens = results.token_count.unwrap_or(0);
                 new_model.status_message = format!(
                     "Analysis complete! {} tokens, {} files",
                     tokens, results.file_count
                 );
+                if let Some(diff) = &new_model.statistics.snapshot_diff
+                    && !diff.is_empty()
+                {
+                    new_model.status_message.push_str(&format!(
+                        " ({:+} tokens since the last run, see Statistics > Delta)",
+                        diff.token_delta()
+                    ));
+                }
                 (new_model, Cmd::None)
             }
 
             Message::AnalysisError(error) => {
                 new_model.prompt_output.analysis_in_progress = false;
+                new_model.prompt_output.progress = None;
                 new_model.prompt_output.analysis_error = Some(error.clone());
                 new_model.status_message = format!("Analysis failed: {}", error);
                 (new_model, Cmd::None)
@@ -553,6 +971,56 @@ impl Model {
                 (new_model, Cmd::None)
             }
 
+            Message::EnterOutputSearch => {
+                new_model.prompt_output.search_active = true;
+                new_model.status_message =
+                    "Output search - Type to search, Enter to keep, Esc to exit".to_string();
+                (new_model, Cmd::None)
+            }
+
+            Message::SubmitOutputSearch => {
+                let output = &mut new_model.prompt_output;
+                output.search_active = false;
+                new_model.status_message = if output.search_matches.is_empty() {
+                    format!("No match for '{}'", output.search_query)
+                } else {
+                    format!(
+                        "{} matches for '{}' - n/N to navigate, Esc to clear",
+                        output.search_matches.len(),
+                        output.search_query
+                    )
+                };
+                (new_model, Cmd::None)
+            }
+
+            Message::ExitOutputSearch => {
+                let output = &mut new_model.prompt_output;
+                output.search_active = false;
+                output.search_query.clear();
+                output.update_search_matches();
+                new_model.status_message = "Exited output search".to_string();
+                (new_model, Cmd::None)
+            }
+
+            Message::UpdateOutputSearch(query) => {
+                new_model.prompt_output.search_query = query;
+                new_model.prompt_output.update_search_matches();
+                (new_model, Cmd::None)
+            }
+
+            Message::MoveOutputMatch(direction) => {
+                let output = &mut new_model.prompt_output;
+                output.move_search_match(direction);
+                if !output.search_matches.is_empty() {
+                    new_model.status_message = format!(
+                        "Match {}/{}",
+                        output.search_index + 1,
+                        output.search_matches.len()
+                    );
+                }
+                (new_model, Cmd::None)
+            }
+
             Message::CycleStatisticsView(direction) => {
                 new_model.statistics.view = if direction > 0 {
                     new_model.statistics.view.next()
@@ -575,12 +1043,37 @@ impl Model {
                 (new_model, Cmd::None)
             }
 
-            Message::SaveTemplate(filename) => {
+            Message::CycleBreakdownSort => {
+                new_model.statistics.breakdown_sort = new_model.statistics.breakdown_sort.next();
+                new_model.status_message = format!(
+                    "Token breakdown sorted by {}",
+                    new_model.statistics.breakdown_sort.as_str()
+                );
+                (new_model, Cmd::None)
+            }
+
+            Message::StartSavingTemplate => {
+                new_model.template.save_name.clear();
+                new_model
+                    .template
+                    .set_focus_mode(crate::model::template::FocusMode::SavingTemplate);
+                new_model.status_message =
+                    "Name the template - Enter to save, Esc to cancel".to_string();
+                (new_model, Cmd::None)
+            }
+
+            Message::UpdateTemplateName(name) => {
+                new_model.template.save_name = name;
+                (new_model, Cmd::None)
+            }
+
+            Message::SaveTemplate(name) => {
                 let content = new_model.template.get_template_content().to_string();
-                let cmd = Cmd::SaveTemplate {
-                    filename: filename.clone(),
-                    content,
-                };
+                new_model
+                    .template
+                    .set_focus_mode(crate::model::template::FocusMode::Normal);
+                new_model.template.save_name.clear();
+                let cmd = Cmd::SaveTemplate { name, content };
                 new_model.status_message = "Saving template...".to_string();
                 (new_model, cmd)
             }
@@ -588,6 +1081,7 @@ impl Model {
             Message::ReloadTemplate => {
                 new_model.template.editor = crate::model::template::EditorState::default();
                 new_model.template.sync_variables_with_template();
+                new_model.template.refresh_preview(&new_model.session);
                 new_model.status_message = "Reloaded template".to_string();
                 (new_model, Cmd::None)
             }
@@ -597,6 +1091,7 @@ impl Model {
                 match result {
                     Ok(template_name) => {
                         new_model.template.sync_variables_with_template();
+                        new_model.template.refresh_preview(&new_model.session);
                         new_model.status_message = format!("Loaded template: {}", template_name);
                     }
                     Err(e) => {
@@ -636,6 +1131,23 @@ impl Model {
                 new_model.template.editor.sync_content_from_textarea();
                 new_model.template.editor.validate_template();
                 new_model.template.sync_variables_with_template();
+                new_model.template.refresh_preview(&new_model.session);
+                (new_model, Cmd::None)
+            }
+
+            Message::ToggleTemplatePreview => {
+                let preview = &mut new_model.template.preview;
+                preview.visible = !preview.visible;
+                if preview.visible {
+                    preview.sample(&mut new_model.session, &new_model.file_tree_nodes);
+                    new_model.template.refresh_preview(&new_model.session);
+                    new_model.status_message = format!(
+                        "Previewing the template with {} selected files",
+                        new_model.template.preview.files.len()
+                    );
+                } else {
+                    new_model.status_message = "Template preview hidden".to_string();
+                }
                 (new_model, Cmd::None)
             }
 
@@ -648,6 +1160,36 @@ impl Model {
                 (new_model, Cmd::None)
             }
 
+            Message::ResizeTemplateColumn(delta) => {
+                let column = new_model.template.get_focus();
+                if new_model.layout.resize_template_column(column, delta) {
+                    new_model.status_message =
+                        format!("Template columns: {:?}%", new_model.layout.template_widths);
+                    let cmd = Cmd::SaveLayout(new_model.layout);
+                    (new_model, cmd)
+                } else {
+                    (new_model, Cmd::None)
+                }
+            }
+
+            Message::MoveTemplateColumn(direction) => {
+                let column = new_model.template.get_focus();
+                if new_model.layout.move_template_column(column, direction) {
+                    new_model.status_message = format!("Moved {:?} column", column);
+                    let cmd = Cmd::SaveLayout(new_model.layout);
+                    (new_model, cmd)
+                } else {
+                    (new_model, Cmd::None)
+                }
+            }
+
+            Message::ResetLayout => {
+                new_model.layout = LayoutPrefs::default();
+                new_model.status_message = "Layout reset".to_string();
+                let cmd = Cmd::SaveLayout(new_model.layout);
+                (new_model, cmd)
+            }
+
             Message::VariableStartEditing(var_name) => {
                 new_model.template.variables.editing_variable = Some(var_name.clone());
                 new_model.template.variables.show_variable_input = true;
@@ -670,6 +1212,7 @@ impl Model {
                 if let Some((var_name, value)) = new_model.template.variables.finish_editing() {
                     new_model.status_message = format!("Set {} = {}", var_name, value);
                     new_model.template.sync_variables_with_template();
+                    new_model.template.refresh_preview(&new_model.session);
                 }
                 (new_model, Cmd::None)
             }
@@ -694,6 +1237,296 @@ impl Model {
                 }
                 (new_model, Cmd::None)
             }
+
+            Message::ReloadBranches => {
+                new_model.status_message = "Reloading branches...".to_string();
+                (new_model, Cmd::LoadBranches)
+            }
+
+            Message::BranchesLoaded(result) => {
+                match result {
+                    Ok(branches) => {
+                        new_model.status_message = format!("Found {} branches", branches.len());
+                        new_model.git.cursor =
+                            new_model.git.cursor.min(branches.len().saturating_sub(1));
+                        new_model.git.branches = Some(branches);
+                        new_model.git.load_error = None;
+                    }
+                    Err(e) => {
+                        new_model.status_message = format!("Failed to list branches: {}", e);
+                        new_model.git.branches = Some(Vec::new());
+                        new_model.git.load_error = Some(e);
+                    }
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::MoveBranchCursor(delta) => {
+                let count = new_model.git.branches.as_ref().map_or(0, Vec::len);
+                if count > 0 {
+                    new_model.git.cursor = if delta > 0 {
+                        (new_model.git.cursor + delta as usize).min(count - 1)
+                    } else {
+                        new_model.git.cursor.saturating_sub((-delta) as usize)
+                    };
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::PickBranch(index) => {
+                let branch = new_model
+                    .git
+                    .branches
+                    .as_ref()
+                    .and_then(|branches| branches.get(index))
+                    .cloned();
+                if let Some(branch) = branch {
+                    new_model.git.pick(branch);
+                    new_model.status_message = match (&new_model.git.base, &new_model.git.head) {
+                        (Some(base), Some(head)) => format!("Comparing {}..{}", base, head),
+                        (Some(base), None) => format!("Base {}, pick the head branch", base),
+                        _ => "Pick the base branch".to_string(),
+                    };
+                }
+                let cmd = new_model.branch_diff_preview();
+                (new_model, cmd)
+            }
+
+            Message::ClearBranchPicks => {
+                new_model.git.clear();
+                new_model.status_message = "Cleared the picked branches".to_string();
+                (new_model, Cmd::None)
+            }
+
+            Message::ToggleDiffBranches => {
+                let before = EditState::of(&new_model.session);
+                let config = &mut new_model.session.config;
+                new_model.status_message =
+                    toggle_branches(&mut config.diff_branches, new_model.git.pair(), "diff");
+                if new_model.session.config.diff_branches != before.config.diff_branches {
+                    new_model.record_edit(before);
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::ToggleLogBranches => {
+                let before = EditState::of(&new_model.session);
+                let config = &mut new_model.session.config;
+                new_model.status_message =
+                    toggle_branches(&mut config.log_branches, new_model.git.pair(), "log");
+                if new_model.session.config.log_branches != before.config.log_branches {
+                    new_model.record_edit(before);
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::ToggleChangedFilesOnly => {
+                let before = EditState::of(&new_model.session);
+                let config = &mut new_model.session.config;
+                config.include_only_changed = !config.include_only_changed;
+                new_model.status_message = if config.include_only_changed {
+                    "Including only the files changed by the diff".to_string()
+                } else {
+                    "Including all files".to_string()
+                };
+                new_model.record_edit(before);
+                (new_model, Cmd::RefreshFileTree)
+            }
+
+            Message::CycleDiffStyle => {
+                let before = EditState::of(&new_model.session);
+                let config = &mut new_model.session.config;
+                config.diff_style = next_diff_style(config.diff_style);
+                new_model.status_message = format!("Diff style: {}", config.diff_style);
+                new_model.record_edit(before);
+                let cmd = new_model.branch_diff_preview();
+                (new_model, cmd)
+            }
+
+            Message::AdjustDiffContext(delta) => {
+                let before = EditState::of(&new_model.session);
+                let config = &mut new_model.session.config;
+                // git shows 3 lines of context by default
+                let lines = config.diff_context_lines.unwrap_or(3) as i64 + delta as i64;
+                config.diff_context_lines = Some(lines.max(0) as u32);
+                new_model.status_message = format!("Diff context: {} lines", lines.max(0));
+                new_model.record_edit(before);
+                let cmd = new_model.branch_diff_preview();
+                (new_model, cmd)
+            }
+
+            Message::BranchDiffPreviewed { base, head, result } => {
+                // Ignore the previews of an earlier comparison
+                if new_model.git.pair() == Some((base, head)) {
+                    new_model.git.preview = match result {
+                        Ok(tokens) => BranchDiffPreview::Tokens(tokens),
+                        Err(e) => BranchDiffPreview::Failed(e),
+                    };
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::ToggleDiffViewer => {
+                new_model.git.show_diff = !new_model.git.show_diff;
+                if !new_model.git.show_diff {
+                    new_model.status_message = "Back to the branches".to_string();
+                    return (new_model, Cmd::None);
+                }
+                // The diff options may have changed since the last time
+                new_model.git.diff_files = None;
+                new_model.git.diff_error = None;
+                new_model.status_message = "Loading the diff...".to_string();
+                (new_model, Cmd::LoadDiffFiles)
+            }
+
+            Message::ReloadDiffFiles => {
+                new_model.git.diff_files = None;
+                new_model.git.diff_error = None;
+                new_model.status_message = "Reloading the diff...".to_string();
+                (new_model, Cmd::LoadDiffFiles)
+            }
+
+            Message::DiffFilesLoaded(result) => {
+                match result {
+                    Ok(files) => {
+                        new_model.status_message = format!("{} files in the diff", files.len());
+                        new_model.git.diff_files = Some(
+                            files
+                                .into_iter()
+                                .map(|(path, diff)| DiffFile::new(path, &diff))
+                                .collect(),
+                        );
+                        new_model.git.diff_error = None;
+                    }
+                    Err(e) => {
+                        new_model.status_message = format!("Failed to load the diff: {}", e);
+                        new_model.git.diff_files = Some(Vec::new());
+                        new_model.git.diff_error = Some(e);
+                    }
+                }
+                let rows = new_model.git.diff_rows().len();
+                new_model.git.diff_cursor = new_model.git.diff_cursor.min(rows.saturating_sub(1));
+                (new_model, Cmd::None)
+            }
+
+            Message::MoveDiffCursor(delta) => {
+                let count = new_model.git.diff_rows().len();
+                if count > 0 {
+                    new_model.git.diff_cursor = if delta > 0 {
+                        (new_model.git.diff_cursor + delta as usize).min(count - 1)
+                    } else {
+                        new_model.git.diff_cursor.saturating_sub((-delta) as usize)
+                    };
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::ToggleDiffFileExpanded => {
+                if let Some(index) = new_model.git.diff_file_at_cursor() {
+                    new_model.git.toggle_diff_file(index);
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::ToggleDiffFileExcluded => {
+                let path = new_model
+                    .git
+                    .diff_file_at_cursor()
+                    .and_then(|index| new_model.git.diff_files.as_ref()?.get(index))
+                    .map(|file| file.path.clone());
+                if let Some(path) = path {
+                    // The exclusion goes through the diff exclude patterns, which filter
+                    // the diff of the prompt
+                    let pattern = diff_exclude_pattern(&path);
+                    let before = EditState::of(&new_model.session);
+                    let patterns = &mut new_model.session.config.diff_exclude_patterns;
+                    if new_model.git.excluded_diff_files.remove(&path) {
+                        patterns.retain(|existing| *existing != pattern);
+                        new_model.status_message = format!("Included {} in the diff", path);
+                    } else {
+                        if !patterns.contains(&pattern) {
+                            patterns.push(pattern);
+                        }
+                        new_model.git.excluded_diff_files.insert(path.clone());
+                        new_model.status_message = format!("Excluded {} from the diff", path);
+                    }
+                    new_model.record_edit(before);
+                }
+                (new_model, Cmd::None)
+            }
+
+            Message::Undo => {
+                let Some(edit) = new_model.history.undo() else {
+                    new_model.status_message = "Nothing to undo".to_string();
+                    return (new_model, Cmd::None);
+                };
+                new_model.restore_edit_state(edit.before);
+                new_model.status_message = format!("Undone: {}", edit.label);
+                (new_model, Cmd::None)
+            }
+
+            Message::Redo => {
+                let Some(edit) = new_model.history.redo() else {
+                    new_model.status_message = "Nothing to redo".to_string();
+                    return (new_model, Cmd::None);
+                };
+                new_model.restore_edit_state(edit.after);
+                new_model.status_message = format!("Redone: {}", edit.label);
+                (new_model, Cmd::None)
+            }
+        }
+    }
+
+    /// Records the edit just made, labeled by the status message, for undo.
+    ///
+    /// # Arguments
+    ///
+    /// * `before` - The state before the edit
+    fn record_edit(&mut self, before: EditState) {
+        let after = EditState::of(&self.session);
+        self.history
+            .record(self.status_message.clone(), before, after);
+    }
+
+    /// Restores the state of an undone or redone edit, with the diff exclusions shown by
+    /// the diff viewer and the cursor of the pattern editor.
+    fn restore_edit_state(&mut self, state: EditState) {
+        state.restore(&mut self.session);
+        self.git
+            .sync_excluded_diff_files(&self.session.config.diff_exclude_patterns);
+        self.settings.patterns.move_cursor(&self.session, 0);
+    }
+
+    /// Starts counting the tokens of the diff between the picked branches, if both are set.
+    fn branch_diff_preview(&mut self) -> Cmd {
+        match self.git.pair() {
+            Some((base, head)) => {
+                self.git.preview = BranchDiffPreview::InProgress;
+                Cmd::PreviewBranchDiff { base, head }
+            }
+            None => Cmd::None,
+        }
+    }
+}
+
+/// Sets the branches of a comparison to the picked pair, or unsets them when they
+/// already are the picked pair or no pair is picked.
+///
+/// # Returns
+///
+/// * `String` - The status message describing the change
+fn toggle_branches(
+    branches: &mut Option<(String, String)>,
+    pair: Option<(String, String)>,
+    kind: &str,
+) -> String {
+    match pair {
+        Some(pair) if branches.as_ref() != Some(&pair) => {
+            let message = format!("Including the {} of {}..{}", kind, pair.0, pair.1);
+            *branches = Some(pair);
+            message
         }
+        _ if branches.take().is_some() => format!("Removed the branch {}", kind),
+        _ => "Pick two branches first".to_string(),
     }
 }
@@ -0,0 +1,229 @@
+//! Editor of the include and exclude patterns, a sub-panel of the settings tab.
+//!
+//! The patterns given on the command line or in a config file can be added, removed and
+//! reordered while the TUI runs. Each pattern shows how many of the files loaded in the
+//! file tree it matches, and so does the pattern being typed.
+
+use super::DisplayFileNode;
+use code2prompt_core::filter::{FilterEngine, build_globset};
+use code2prompt_core::session::Code2PromptSession;
+use std::path::{Path, PathBuf};
+
+/// The list a pattern belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+    Include,
+    Exclude,
+}
+
+impl PatternKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PatternKind::Include => "include",
+            PatternKind::Exclude => "exclude",
+        }
+    }
+}
+
+/// A pattern of the editor with the number of loaded files it matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternRow {
+    pub kind: PatternKind,
+    pub pattern: String,
+    pub matches: usize,
+}
+
+/// Live preview of the patterns over the files loaded in the file tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternPreview {
+    pub rows: Vec<PatternRow>,
+    /// Number of files loaded in the file tree
+    pub loaded_files: usize,
+    /// Number of loaded files kept by the include and exclude patterns together
+    pub kept_files: usize,
+    /// Number of loaded files matched by the pattern being typed
+    pub input_matches: Option<usize>,
+}
+
+/// State of the pattern editor
+#[derive(Debug, Clone, Default)]
+pub struct PatternEditorState {
+    /// Whether the editor is shown instead of the settings list
+    pub active: bool,
+    /// Position in the include patterns followed by the exclude patterns
+    pub cursor: usize,
+    /// The pattern being typed, with the list it is added to
+    pub input: Option<(PatternKind, String)>,
+}
+
+impl PatternEditorState {
+    /// Whether a pattern is being typed
+    pub fn is_typing(&self) -> bool {
+        self.input.is_some()
+    }
+
+    /// The list and the index in it of the pattern at the cursor
+    pub fn selected(&self, session: &Code2PromptSession) -> Option<(PatternKind, usize)> {
+        let includes = session.config.include_patterns.len();
+        let excludes = session.config.exclude_patterns.len();
+        if self.cursor < includes {
+            Some((PatternKind::Include, self.cursor))
+        } else if self.cursor < includes + excludes {
+            Some((PatternKind::Exclude, self.cursor - includes))
+        } else {
+            None
+        }
+    }
+
+    /// Moves the cursor, staying on the patterns.
+    pub fn move_cursor(&mut self, session: &Code2PromptSession, delta: i32) {
+        let count = session.config.include_patterns.len() + session.config.exclude_patterns.len();
+        self.cursor =
+            (self.cursor as i64 + delta as i64).clamp(0, count.max(1) as i64 - 1) as usize;
+    }
+
+    /// Adds the pattern being typed to its list, placing the cursor on it.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - What was done, `None` when the pattern is empty or already listed
+    pub fn add_input(&mut self, session: &mut Code2PromptSession) -> Option<String> {
+        let (kind, pattern) = self.input.take()?;
+        let pattern = pattern.trim().to_string();
+        let (mut includes, mut excludes) = patterns_of(session);
+        let list = match kind {
+            PatternKind::Include => &mut includes,
+            PatternKind::Exclude => &mut excludes,
+        };
+        if pattern.is_empty() || list.contains(&pattern) {
+            return None;
+        }
+        list.push(pattern.clone());
+        self.cursor = match kind {
+            PatternKind::Include => includes.len() - 1,
+            PatternKind::Exclude => includes.len() + excludes.len() - 1,
+        };
+        session.set_patterns(includes, excludes);
+        Some(format!("Added {} pattern {}", kind.as_str(), pattern))
+    }
+
+    /// Removes the pattern at the cursor.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - What was done, `None` when there is no pattern
+    pub fn remove_selected(&mut self, session: &mut Code2PromptSession) -> Option<String> {
+        let (kind, index) = self.selected(session)?;
+        let (mut includes, mut excludes) = patterns_of(session);
+        let pattern = match kind {
+            PatternKind::Include => includes.remove(index),
+            PatternKind::Exclude => excludes.remove(index),
+        };
+        session.set_patterns(includes, excludes);
+        self.move_cursor(session, 0);
+        Some(format!("Removed {} pattern {}", kind.as_str(), pattern))
+    }
+
+    /// Moves the pattern at the cursor up or down its list, the cursor following it.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - What was done, `None` when the pattern cannot move further
+    pub fn move_selected(&mut self, session: &mut Code2PromptSession, delta: i8) -> Option<String> {
+        let (kind, index) = self.selected(session)?;
+        let (mut includes, mut excludes) = patterns_of(session);
+        let list = match kind {
+            PatternKind::Include => &mut includes,
+            PatternKind::Exclude => &mut excludes,
+        };
+        let target = index.checked_add_signed(delta as isize)?;
+        if target >= list.len() {
+            return None;
+        }
+        list.swap(index, target);
+        let pattern = list[target].clone();
+        self.cursor = self.cursor + target - index;
+        session.set_patterns(includes, excludes);
+        Some(format!("Moved {} pattern {}", kind.as_str(), pattern))
+    }
+
+    /// Counts the loaded files matched by each pattern, by the whole set and by the
+    /// pattern being typed.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - The session holding the patterns
+    /// * `nodes` - The nodes of the file tree
+    pub fn preview(
+        &self,
+        session: &Code2PromptSession,
+        nodes: &[DisplayFileNode],
+    ) -> PatternPreview {
+        let files = loaded_files(nodes, &session.config.path);
+        let count = |pattern: &str| {
+            let globset = build_globset(&[pattern.to_string()]);
+            files.iter().filter(|file| globset.is_match(file)).count()
+        };
+        let (includes, excludes) = patterns_of(session);
+        let rows = includes
+            .iter()
+            .map(|pattern| (PatternKind::Include, pattern))
+            .chain(
+                excludes
+                    .iter()
+                    .map(|pattern| (PatternKind::Exclude, pattern)),
+            )
+            .map(|(kind, pattern)| PatternRow {
+                kind,
+                pattern: pattern.clone(),
+                matches: count(pattern),
+            })
+            .collect();
+        let filter = FilterEngine::new(&includes, &excludes);
+
+        PatternPreview {
+            rows,
+            loaded_files: files.len(),
+            kept_files: files
+                .iter()
+                .filter(|file| filter.matches_patterns(file))
+                .count(),
+            input_matches: self
+                .input
+                .as_ref()
+                .filter(|(_, pattern)| !pattern.trim().is_empty())
+                .map(|(_, pattern)| count(pattern.trim())),
+        }
+    }
+}
+
+/// The include and exclude patterns of a session
+fn patterns_of(session: &Code2PromptSession) -> (Vec<String>, Vec<String>) {
+    (
+        session.config.include_patterns.clone(),
+        session.config.exclude_patterns.clone(),
+    )
+}
+
+/// The files loaded in the file tree, relative to the root
+fn loaded_files(nodes: &[DisplayFileNode], root: &Path) -> Vec<PathBuf> {
+    fn collect(node: &DisplayFileNode, root: &Path, files: &mut Vec<PathBuf>) {
+        if node.is_directory {
+            for child in &node.children {
+                collect(child, root, files);
+            }
+        } else {
+            files.push(
+                node.path
+                    .strip_prefix(root)
+                    .unwrap_or(&node.path)
+                    .to_path_buf(),
+            );
+        }
+    }
+    let mut files = Vec::new();
+    for node in nodes {
+        collect(node, root, &mut files);
+    }
+    files
+}
@@ -3,15 +3,147 @@
 //! This module contains the prompt output state and related functionality
 //! for managing generated prompts and analysis results in the TUI.
 
+use code2prompt_core::progress::ProgressEvent;
+use code2prompt_core::session::TokenBreakdown;
+use code2prompt_core::snapshot::SessionSnapshot;
+
 /// Prompt output state containing all prompt output related data
 #[derive(Debug, Default, Clone)]
 pub struct PromptOutputState {
     pub generated_prompt: Option<String>,
     pub token_count: Option<usize>,
+    pub token_breakdown: Option<TokenBreakdown>,
     pub file_count: usize,
     pub analysis_in_progress: bool,
+    /// Where the running analysis stands
+    pub progress: Option<AnalysisProgress>,
     pub analysis_error: Option<String>,
     pub output_scroll: u16,
+    /// Whether the search query is being typed
+    pub search_active: bool,
+    pub search_query: String,
+    /// Line and byte offset of each match of the query in the prompt
+    pub search_matches: Vec<(usize, usize)>,
+    /// Index of the current match in `search_matches`
+    pub search_index: usize,
+}
+
+/// Step of a running analysis
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisStage {
+    #[default]
+    Walking,
+    Reading,
+    Git,
+    Rendering,
+}
+
+/// Progress of a running analysis, built from the events of the session
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AnalysisProgress {
+    pub stage: AnalysisStage,
+    pub files_found: usize,
+    pub files_loaded: usize,
+    /// Tokens of the files loaded so far
+    pub tokens: usize,
+}
+
+impl AnalysisProgress {
+    /// Take a progress event of the session into account
+    pub fn apply(&mut self, event: &ProgressEvent) {
+        match event {
+            ProgressEvent::TraversalStarted => *self = Self::default(),
+            ProgressEvent::FilesFound { count } => {
+                self.stage = AnalysisStage::Reading;
+                self.files_found = *count;
+            }
+            ProgressEvent::FileLoaded { tokens, .. } => {
+                self.files_loaded += 1;
+                self.tokens += tokens;
+            }
+            ProgressEvent::GitDiffLoaded => self.stage = AnalysisStage::Git,
+            ProgressEvent::RenderStarted | ProgressEvent::Done { .. } => {
+                self.stage = AnalysisStage::Rendering
+            }
+        }
+    }
+
+    /// Completed part of the analysis, between 0 and 1
+    pub fn ratio(&self) -> f64 {
+        match self.stage {
+            AnalysisStage::Walking => 0.0,
+            AnalysisStage::Reading if self.files_found == 0 => 1.0,
+            AnalysisStage::Reading => (self.files_loaded as f64 / self.files_found as f64).min(1.0),
+            AnalysisStage::Git | AnalysisStage::Rendering => 1.0,
+        }
+    }
+
+    /// Description of the current step
+    pub fn label(&self) -> String {
+        match self.stage {
+            AnalysisStage::Walking => "Walking the codebase...".to_string(),
+            AnalysisStage::Reading => format!(
+                "Reading files: {}/{} ({} tokens)",
+                self.files_loaded, self.files_found, self.tokens
+            ),
+            AnalysisStage::Git => format!(
+                "Loaded {} files ({} tokens), then the git sections...",
+                self.files_loaded, self.tokens
+            ),
+            AnalysisStage::Rendering => format!(
+                "Rendering the prompt from {} files ({} tokens)...",
+                self.files_loaded, self.tokens
+            ),
+        }
+    }
+}
+
+impl PromptOutputState {
+    /// Finds the matches of the search query in the prompt, ignoring ASCII case, and
+    /// selects the first match at or after the scrolled line.
+    pub fn update_search_matches(&mut self) {
+        self.search_matches.clear();
+        self.search_index = 0;
+        let (Some(prompt), false) = (&self.generated_prompt, self.search_query.is_empty()) else {
+            return;
+        };
+        let query = self.search_query.to_ascii_lowercase();
+        for (line_index, line) in prompt.lines().enumerate() {
+            let line = line.to_ascii_lowercase();
+            self.search_matches.extend(
+                line.match_indices(&query)
+                    .map(|(offset, _)| (line_index, offset)),
+            );
+        }
+        let scroll = self.output_scroll as usize;
+        self.search_index = self
+            .search_matches
+            .iter()
+            .position(|(line, _)| *line >= scroll)
+            .unwrap_or(0);
+        self.scroll_to_match();
+    }
+
+    /// Moves to the next match, or the previous one, wrapping around the prompt
+    pub fn move_search_match(&mut self, direction: i8) {
+        let count = self.search_matches.len();
+        if count == 0 {
+            return;
+        }
+        self.search_index = if direction < 0 {
+            (self.search_index + count - 1) % count
+        } else {
+            (self.search_index + 1) % count
+        };
+        self.scroll_to_match();
+    }
+
+    /// Scrolls the output so that the current match is on screen, below a line of context
+    fn scroll_to_match(&mut self) {
+        if let Some((line, _)) = self.search_matches.get(self.search_index) {
+            self.output_scroll = line.saturating_sub(1).min(u16::MAX as usize) as u16;
+        }
+    }
 }
 
 /// Results from code2prompt analysis
@@ -19,6 +151,9 @@ pub struct PromptOutputState {
 pub struct AnalysisResults {
     pub file_count: usize,
     pub token_count: Option<usize>,
+    pub token_breakdown: Option<TokenBreakdown>,
     pub generated_prompt: String,
     pub token_map_entries: Vec<crate::token_map::TokenMapEntry>,
+    pub token_distribution: crate::model::TokenDistribution,
+    pub snapshot: SessionSnapshot,
 }
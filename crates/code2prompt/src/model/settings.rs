@@ -3,14 +3,24 @@
 //! This module contains the settings state, settings groups, and related
 //! functionality for managing configuration options in the TUI.
 
+use super::PatternEditorState;
+use crate::options::{self, OptionKind};
+use code2prompt_core::configuration::Code2PromptConfig;
 use code2prompt_core::session::Code2PromptSession;
-use code2prompt_core::template::OutputFormat;
-use code2prompt_core::tokenizer::TokenFormat;
 
 /// Settings state containing cursor position and related data
 #[derive(Default, Debug, Clone)]
 pub struct SettingsState {
     pub settings_cursor: usize,
+    /// Whether the `/` search input is active
+    pub search_active: bool,
+    /// Text filtering the settings by group, name or description
+    pub search_query: String,
+    /// The configuration the TUI was launched with (defaults, config file and flags).
+    /// Settings differing from it are marked as modified and can be reset to it.
+    pub defaults: Code2PromptConfig,
+    /// Editor of the include and exclude patterns
+    pub patterns: PatternEditorState,
 }
 
 /// Settings group for organizing settings
@@ -23,13 +33,16 @@ pub struct SettingsGroup {
 /// Settings item for display and interaction
 #[derive(Debug, Clone)]
 pub struct SettingsItem {
-    pub key: SettingKey,
+    /// Key of the option in [`crate::options::OPTIONS`]
+    pub key: &'static str,
     pub name: String,
     pub description: String,
     pub setting_type: SettingType,
+    /// Whether the value differs from the launch configuration
+    pub modified: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SettingType {
     Boolean(bool),
     Choice {
@@ -38,131 +51,163 @@ pub enum SettingType {
     },
 }
 
+/// What changed when new defaults were applied after a config reload
+#[derive(Debug, Clone, Default)]
+pub struct ReloadOutcome {
+    /// Settings that took the new value from the config file
+    pub updated: Vec<String>,
+    /// Settings changed in the TUI whose local value was kept over the new one
+    pub conflicts: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub enum SettingAction {
     Toggle,
     Cycle,
 }
 
-/// Unique identifier for each setting
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum SettingKey {
-    LineNumbers,
-    AbsolutePaths,
-    NoCodeblock,
-    OutputFormat,
-    TokenFormat,
-    FullDirectoryTree,
-    SortMethod,
-    TokenizerType,
-    GitDiff,
-    FollowSymlinks,
-    HiddenFiles,
-    NoIgnore,
-}
-
 impl SettingsState {
-    /// Get flattened list of settings for display (uses format_settings_groups)
+    /// Creates the settings state, using the given configuration as the defaults.
+    pub fn new(defaults: Code2PromptConfig) -> Self {
+        Self {
+            defaults,
+            ..Default::default()
+        }
+    }
+
+    /// Get the settings groups for display, marking modified settings and keeping only
+    /// the ones matching the search query. A group whose name matches keeps all its items.
+    pub fn get_settings_groups(&self, session: &Code2PromptSession) -> Vec<SettingsGroup> {
+        let defaults: Vec<SettingsItem> = crate::view::format_settings_groups(&self.defaults)
+            .into_iter()
+            .flat_map(|group| group.items)
+            .collect();
+        let query = self.search_query.to_lowercase();
+        let matches = |text: &str| text.to_lowercase().contains(&query);
+
+        crate::view::format_settings_groups(&session.config)
+            .into_iter()
+            .filter_map(|mut group| {
+                for item in &mut group.items {
+                    item.modified = defaults
+                        .iter()
+                        .find(|default| default.key == item.key)
+                        .is_some_and(|default| default.setting_type != item.setting_type);
+                }
+                if !matches(&group.name) {
+                    group
+                        .items
+                        .retain(|item| matches(&item.name) || matches(&item.description));
+                }
+                (!group.items.is_empty()).then_some(group)
+            })
+            .collect()
+    }
+
+    /// Get flattened list of settings for display (uses get_settings_groups)
     pub fn get_settings_items(&self, session: &Code2PromptSession) -> Vec<SettingsItem> {
-        crate::view::format_settings_groups(session)
+        self.get_settings_groups(session)
             .into_iter()
             .flat_map(|group| group.items)
             .collect()
     }
 
-    /// Update setting based on SettingKey and action
-    pub fn update_setting_by_key(
-        &self,
+    /// Replace the defaults with a configuration reloaded from the config files.
+    ///
+    /// Settings left at their previous default follow the new one. Settings changed in
+    /// the TUI keep their local value and are reported as conflicts when the config file
+    /// changed them too. The include/exclude patterns follow the same rule.
+    pub fn apply_new_defaults(
+        &mut self,
         session: &mut Code2PromptSession,
-        key: SettingKey,
-        action: SettingAction,
-    ) -> &'static str {
-        match (key, action) {
-            (SettingKey::LineNumbers, SettingAction::Toggle | SettingAction::Cycle) => {
-                session.config.line_numbers = !session.config.line_numbers;
-                "Line Numbers"
-            }
-            (SettingKey::AbsolutePaths, SettingAction::Toggle | SettingAction::Cycle) => {
-                session.config.absolute_path = !session.config.absolute_path;
-                "Absolute Paths"
-            }
-            (SettingKey::NoCodeblock, SettingAction::Toggle | SettingAction::Cycle) => {
-                session.config.no_codeblock = !session.config.no_codeblock;
-                "No Codeblock"
-            }
-            (SettingKey::OutputFormat, SettingAction::Cycle) => {
-                session.config.output_format = match session.config.output_format {
-                    OutputFormat::Markdown => OutputFormat::Json,
-                    OutputFormat::Json => OutputFormat::Xml,
-                    OutputFormat::Xml => OutputFormat::Markdown,
-                };
-                "Output Format"
-            }
-            (SettingKey::TokenFormat, SettingAction::Cycle) => {
-                session.config.token_format = match session.config.token_format {
-                    TokenFormat::Raw => TokenFormat::Format,
-                    TokenFormat::Format => TokenFormat::Raw,
-                };
-                "Token Format"
-            }
-            (SettingKey::FullDirectoryTree, SettingAction::Toggle | SettingAction::Cycle) => {
-                session.config.full_directory_tree = !session.config.full_directory_tree;
-                "Full Directory Tree"
-            }
-            (SettingKey::SortMethod, SettingAction::Cycle) => {
-                session.config.sort_method = Some(match session.config.sort_method {
-                    Some(code2prompt_core::sort::FileSortMethod::NameAsc) => {
-                        code2prompt_core::sort::FileSortMethod::NameDesc
-                    }
-                    Some(code2prompt_core::sort::FileSortMethod::NameDesc) => {
-                        code2prompt_core::sort::FileSortMethod::DateAsc
-                    }
-                    Some(code2prompt_core::sort::FileSortMethod::DateAsc) => {
-                        code2prompt_core::sort::FileSortMethod::DateDesc
-                    }
-                    Some(code2prompt_core::sort::FileSortMethod::DateDesc) | None => {
-                        code2prompt_core::sort::FileSortMethod::NameAsc
-                    }
-                });
-                "Sort Method"
+        defaults: Code2PromptConfig,
+    ) -> ReloadOutcome {
+        let values = |config: &Code2PromptConfig| -> Vec<SettingsItem> {
+            crate::view::format_settings_groups(config)
+                .into_iter()
+                .flat_map(|group| group.items)
+                .collect()
+        };
+        let previous = values(&self.defaults);
+        let current = values(&session.config);
+        let old_patterns = (
+            self.defaults.include_patterns.clone(),
+            self.defaults.exclude_patterns.clone(),
+        );
+        self.defaults = defaults;
+
+        let mut outcome = ReloadOutcome::default();
+        for item in values(&self.defaults) {
+            let value_of = |items: &[SettingsItem]| {
+                items
+                    .iter()
+                    .find(|other| other.key == item.key)
+                    .map(|other| other.setting_type.clone())
+            };
+            let previous = value_of(&previous);
+            if previous.as_ref() == Some(&item.setting_type) {
+                continue;
             }
-            (SettingKey::TokenizerType, SettingAction::Cycle) => {
-                session.config.encoding = match session.config.encoding {
-                    code2prompt_core::tokenizer::TokenizerType::Cl100kBase => {
-                        code2prompt_core::tokenizer::TokenizerType::O200kBase
-                    }
-                    code2prompt_core::tokenizer::TokenizerType::O200kBase => {
-                        code2prompt_core::tokenizer::TokenizerType::P50kBase
-                    }
-                    code2prompt_core::tokenizer::TokenizerType::P50kBase => {
-                        code2prompt_core::tokenizer::TokenizerType::P50kEdit
-                    }
-                    code2prompt_core::tokenizer::TokenizerType::P50kEdit => {
-                        code2prompt_core::tokenizer::TokenizerType::R50kBase
-                    }
-                    code2prompt_core::tokenizer::TokenizerType::R50kBase => {
-                        code2prompt_core::tokenizer::TokenizerType::Cl100kBase
-                    }
-                };
-                "Tokenizer Type"
+            if value_of(&current) == previous {
+                self.reset_setting_by_key(session, item.key);
+                outcome.updated.push(item.name);
+            } else if value_of(&current).as_ref() != Some(&item.setting_type) {
+                outcome.conflicts.push(item.name);
             }
-            (SettingKey::GitDiff, SettingAction::Toggle | SettingAction::Cycle) => {
-                session.config.diff_enabled = !session.config.diff_enabled;
-                "Git Diff"
+        }
+
+        let new_patterns = (
+            self.defaults.include_patterns.clone(),
+            self.defaults.exclude_patterns.clone(),
+        );
+        let current_patterns = (
+            session.config.include_patterns.clone(),
+            session.config.exclude_patterns.clone(),
+        );
+        if new_patterns != old_patterns {
+            if current_patterns == old_patterns {
+                session.set_patterns(new_patterns.0, new_patterns.1);
+                outcome.updated.push("Filter Patterns".to_string());
+            } else if current_patterns != new_patterns {
+                outcome.conflicts.push("Filter Patterns".to_string());
             }
-            (SettingKey::FollowSymlinks, SettingAction::Toggle | SettingAction::Cycle) => {
-                session.config.follow_symlinks = !session.config.follow_symlinks;
-                "Follow Symlinks"
+        }
+
+        outcome
+    }
+
+    /// Reset a setting to its value in the launch configuration
+    pub fn reset_setting_by_key(
+        &self,
+        session: &mut Code2PromptSession,
+        key: &str,
+    ) -> &'static str {
+        match options::option(key) {
+            Some(spec) => {
+                spec.copy(&self.defaults, &mut session.config);
+                spec.label()
             }
-            (SettingKey::HiddenFiles, SettingAction::Toggle | SettingAction::Cycle) => {
-                session.config.hidden = !session.config.hidden;
-                "Hidden Files"
+            None => "Unknown Setting",
+        }
+    }
+
+    /// Update a setting: a toggle turns a flag on or off, a cycle moves a flag or a choice
+    /// to its next value
+    pub fn update_setting_by_key(
+        &self,
+        session: &mut Code2PromptSession,
+        key: &str,
+        action: SettingAction,
+    ) -> &'static str {
+        match (options::option(key), action) {
+            (Some(spec), SettingAction::Toggle) if !matches!(spec.kind, OptionKind::Flag) => {
+                "Unknown Setting"
             }
-            (SettingKey::NoIgnore, SettingAction::Toggle | SettingAction::Cycle) => {
-                session.config.no_ignore = !session.config.no_ignore;
-                "No Ignore"
+            (Some(spec), _) => {
+                spec.cycle(&mut session.config);
+                spec.label()
             }
-            _ => "Unknown Setting",
+            (None, _) => "Unknown Setting",
         }
     }
 }
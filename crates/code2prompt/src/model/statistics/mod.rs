@@ -7,14 +7,34 @@ pub mod types;
 
 use crate::model::DisplayFileNode;
 use crate::utils::format_number;
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::path::FileEntry;
+use code2prompt_core::snapshot::{SessionSnapshot, SnapshotDiff, diff_snapshots};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::Path;
 pub use types::*;
 
+/// Number of runs kept in the token history
+pub const TOKEN_HISTORY_RUNS: usize = 20;
+
+/// Levels of the sparkline, lowest first
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
 /// Statistics state containing all statistics-related data
 #[derive(Debug, Clone)]
 pub struct StatisticsState {
     pub view: StatisticsView,
     pub scroll: u16,
     pub token_map_entries: Vec<crate::token_map::TokenMapEntry>,
+    pub token_distribution: TokenDistribution,
+    pub breakdown_sort: BreakdownSort,
+    /// Total tokens of the recent runs, oldest first
+    pub token_history: Vec<usize>,
+    /// The files and tokens of the last run
+    pub last_snapshot: Option<SessionSnapshot>,
+    /// The changes of the last run since the one before
+    pub snapshot_diff: Option<SnapshotDiff>,
 }
 
 impl Default for StatisticsState {
@@ -23,6 +43,11 @@ impl Default for StatisticsState {
             view: StatisticsView::Overview,
             scroll: 0,
             token_map_entries: Vec::new(),
+            token_distribution: TokenDistribution::default(),
+            breakdown_sort: BreakdownSort::default(),
+            token_history: Vec::new(),
+            last_snapshot: None,
+            snapshot_diff: None,
         }
     }
 }
@@ -83,4 +108,123 @@ impl StatisticsState {
         ext_vec.sort_by(|a, b| b.1.cmp(&a.1));
         ext_vec
     }
+
+    /// Record the total tokens of a run, dropping the oldest runs beyond the limit
+    pub fn record_run(&mut self, token_count: usize) {
+        self.token_history.push(token_count);
+        let excess = self.token_history.len().saturating_sub(TOKEN_HISTORY_RUNS);
+        self.token_history.drain(..excess);
+    }
+
+    /// Record the snapshot of a run, comparing it with the previous one
+    pub fn record_snapshot(&mut self, snapshot: SessionSnapshot) {
+        if let Some(previous) = &self.last_snapshot {
+            self.snapshot_diff = Some(diff_snapshots(previous, &snapshot));
+        }
+        self.last_snapshot = Some(snapshot);
+    }
+
+    /// Sparkline of the token history, scaled between its lowest and highest run so that
+    /// small changes remain visible. A flat history is drawn at mid height.
+    pub fn token_sparkline(&self) -> String {
+        let min = self.token_history.iter().copied().min().unwrap_or(0);
+        let max = self.token_history.iter().copied().max().unwrap_or(0);
+        self.token_history
+            .iter()
+            .map(|&tokens| {
+                let level = if max == min {
+                    SPARK_LEVELS.len() / 2
+                } else {
+                    (tokens - min) * (SPARK_LEVELS.len() - 1) / (max - min)
+                };
+                SPARK_LEVELS[level]
+            })
+            .collect()
+    }
+
+    /// Rows of the breakdown, in the order of `breakdown_sort`
+    pub fn sorted_breakdown(&self, rows: &[BreakdownRow]) -> Vec<BreakdownRow> {
+        let mut rows = rows.to_vec();
+        match self.breakdown_sort {
+            BreakdownSort::Tokens => rows.sort_by_key(|row| Reverse(row.tokens)),
+            BreakdownSort::Files => rows.sort_by_key(|row| Reverse(row.files)),
+            BreakdownSort::Name => rows.sort_by(|a, b| a.name.cmp(&b.name)),
+        }
+        rows
+    }
+}
+
+impl TokenDistribution {
+    /// Sums the tokens of the files of the prompt by top-level directory, by extension and
+    /// by language, and their lines.
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - The files of the prompt
+    /// * `config` - The configuration, to find the paths relative to the root
+    pub fn from_files(files: &[FileEntry], config: &Code2PromptConfig) -> Self {
+        let mut directories: HashMap<String, (usize, usize)> = HashMap::new();
+        let mut extensions: HashMap<String, (usize, usize)> = HashMap::new();
+        let mut languages: HashMap<String, (usize, usize)> = HashMap::new();
+        let mut distribution = Self::default();
+        for file in files {
+            let relative_path = config.root_relative_path(&file.path);
+            let mut components = relative_path.components();
+            let first = components.next();
+            let directory = match (first, components.next()) {
+                (Some(directory), Some(_)) => {
+                    format!("{}/", directory.as_os_str().to_string_lossy())
+                }
+                _ => "(root files)".to_string(),
+            };
+            let extension = match Path::new(&file.path).extension() {
+                Some(extension) => format!(".{}", extension.to_string_lossy()),
+                None => "(no extension)".to_string(),
+            };
+            let language = match file.stats.language.as_str() {
+                "" => "(other)".to_string(),
+                language => language.to_string(),
+            };
+            for (group, groups) in [
+                (directory, &mut directories),
+                (extension, &mut extensions),
+                (language, &mut languages),
+            ] {
+                let (tokens, count) = groups.entry(group).or_insert((0, 0));
+                *tokens += file.token_count;
+                *count += 1;
+            }
+
+            distribution.code_lines += file.stats.code_lines();
+            distribution.comment_lines += file.stats.comment_lines;
+            distribution.blank_lines += file.stats.blank_lines;
+            if file.stats.is_test {
+                distribution.test_files += 1;
+                distribution.test_tokens += file.token_count;
+            }
+        }
+        let rows = |groups: HashMap<String, (usize, usize)>| {
+            let mut rows: Vec<BreakdownRow> = groups
+                .into_iter()
+                .map(|(name, (tokens, files))| BreakdownRow {
+                    name,
+                    tokens,
+                    files,
+                })
+                .collect();
+            rows.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.name.cmp(&b.name)));
+            rows
+        };
+        Self {
+            directories: rows(directories),
+            extensions: rows(extensions),
+            languages: rows(languages),
+            ..distribution
+        }
+    }
+
+    /// Tokens of all the files
+    pub fn total_tokens(&self) -> usize {
+        self.directories.iter().map(|row| row.tokens).sum()
+    }
 }
@@ -9,6 +9,8 @@ pub enum StatisticsView {
     Overview,   // General statistics and summary
     TokenMap,   // Token distribution by directory/file
     Extensions, // Token distribution by file extension
+    Chart,      // Token distribution as bar charts
+    Delta,      // Token and file changes since the previous run
 }
 
 impl StatisticsView {
@@ -16,15 +18,19 @@ impl StatisticsView {
         match self {
             StatisticsView::Overview => StatisticsView::TokenMap,
             StatisticsView::TokenMap => StatisticsView::Extensions,
-            StatisticsView::Extensions => StatisticsView::Overview,
+            StatisticsView::Extensions => StatisticsView::Chart,
+            StatisticsView::Chart => StatisticsView::Delta,
+            StatisticsView::Delta => StatisticsView::Overview,
         }
     }
 
     pub fn prev(&self) -> Self {
         match self {
-            StatisticsView::Overview => StatisticsView::Extensions,
+            StatisticsView::Overview => StatisticsView::Delta,
             StatisticsView::TokenMap => StatisticsView::Overview,
             StatisticsView::Extensions => StatisticsView::TokenMap,
+            StatisticsView::Chart => StatisticsView::Extensions,
+            StatisticsView::Delta => StatisticsView::Chart,
         }
     }
 
@@ -33,6 +39,59 @@ impl StatisticsView {
             StatisticsView::Overview => "Overview",
             StatisticsView::TokenMap => "Token Map",
             StatisticsView::Extensions => "Extensions",
+            StatisticsView::Chart => "Chart",
+            StatisticsView::Delta => "Delta",
         }
     }
 }
+
+/// Column the token breakdown of the overview is sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BreakdownSort {
+    #[default]
+    Tokens,
+    Files,
+    Name,
+}
+
+impl BreakdownSort {
+    pub fn next(&self) -> Self {
+        match self {
+            BreakdownSort::Tokens => BreakdownSort::Files,
+            BreakdownSort::Files => BreakdownSort::Name,
+            BreakdownSort::Name => BreakdownSort::Tokens,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BreakdownSort::Tokens => "tokens",
+            BreakdownSort::Files => "files",
+            BreakdownSort::Name => "name",
+        }
+    }
+}
+
+/// Tokens and number of the files of a group, a top-level directory, an extension or a
+/// language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakdownRow {
+    pub name: String,
+    pub tokens: usize,
+    pub files: usize,
+}
+
+/// Tokens of the files of the prompt by top-level directory, by extension and by
+/// language, with the line counts of the files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenDistribution {
+    pub directories: Vec<BreakdownRow>,
+    pub extensions: Vec<BreakdownRow>,
+    pub languages: Vec<BreakdownRow>,
+    pub code_lines: usize,
+    pub comment_lines: usize,
+    pub blank_lines: usize,
+    /// Number of the test files, and their tokens
+    pub test_files: usize,
+    pub test_tokens: usize,
+}
@@ -3,8 +3,7 @@
 //! This module contains the state and logic for the template editor component,
 //! including TextArea management, validation, and content synchronization.
 
-use regex::Regex;
-use std::collections::HashSet;
+use code2prompt_core::template::extract_template_variables;
 use tui_textarea::TextArea;
 
 /// State for the template editor component
@@ -15,6 +14,8 @@ pub struct EditorState {
     pub current_template_name: String,
     pub is_valid: bool,
     pub validation_message: String,
+    /// Line of the syntax error, numbered from 1
+    pub error_line: Option<usize>,
     pub template_variables: Vec<String>, // Variables found in template
 }
 
@@ -32,6 +33,7 @@ impl Clone for EditorState {
             current_template_name: self.current_template_name.clone(),
             is_valid: self.is_valid,
             validation_message: self.validation_message.clone(),
+            error_line: self.error_line,
             template_variables: self.template_variables.clone(),
         }
     }
@@ -56,6 +58,7 @@ impl Default for EditorState {
             current_template_name: "Default (Markdown)".to_string(),
             is_valid: true,
             validation_message: String::new(),
+            error_line: None,
             template_variables: Vec::new(),
         };
 
@@ -65,24 +68,25 @@ impl Default for EditorState {
 }
 
 impl EditorState {
+    /// Replace the template in the editor, then re-analyze and validate it
+    pub fn set_content(&mut self, template_name: String, content: String) {
+        self.editor = TextArea::from(content.lines());
+        self.content = content;
+        self.current_template_name = template_name;
+
+        self.sync_content_from_textarea();
+        self.validate_template();
+    }
+
     /// Update content from TextArea and re-analyze variables
     pub fn sync_content_from_textarea(&mut self) {
         self.content = self.editor.lines().join("\n");
         self.analyze_template_variables();
     }
 
-    /// Parse template content to extract all {{variable}} references
+    /// Parse template content to extract the {{variable}} references of the root context
     pub fn analyze_template_variables(&mut self) {
-        let re = Regex::new(r"\{\{\s*([a-zA-Z_][a-zA-Z0-9_]*)\s*\}\}").unwrap();
-        let mut found_vars = HashSet::new();
-
-        for cap in re.captures_iter(&self.content) {
-            if let Some(var_name) = cap.get(1) {
-                found_vars.insert(var_name.as_str().to_string());
-            }
-        }
-
-        self.template_variables = found_vars.into_iter().collect();
+        self.template_variables = extract_template_variables(&self.content);
         self.template_variables.sort();
     }
 
@@ -91,7 +95,8 @@ impl EditorState {
         &self.template_variables
     }
 
-    /// Validate template syntax with enhanced Handlebars checking
+    /// Validate template syntax with enhanced Handlebars checking, locating the line of
+    /// the error when it can
     pub fn validate_template(&mut self) {
         // First check for balanced braces
         let open_count = self.content.matches("{{").count();
@@ -99,10 +104,14 @@ impl EditorState {
 
         if open_count != close_count {
             self.is_valid = false;
+            self.error_line = unbalanced_brace_line(&self.content);
             self.validation_message = format!(
                 "Unbalanced braces: {} opening, {} closing",
                 open_count, close_count
             );
+            if let Some(line) = self.error_line {
+                self.validation_message = format!("line {}: {}", line, self.validation_message);
+            }
             return;
         }
 
@@ -110,26 +119,28 @@ impl EditorState {
         match self.compile_template() {
             Ok(_) => {
                 self.is_valid = true;
+                self.error_line = None;
                 self.validation_message = String::new();
             }
             Err(e) => {
                 self.is_valid = false;
-                self.validation_message = format!("Template syntax error: {}", e);
+                self.error_line = e.pos().map(|(line, _)| line);
+                self.validation_message = match self.error_line {
+                    Some(line) => format!("line {}: {}", line, e.reason()),
+                    None => format!("Template syntax error: {}", e),
+                };
             }
         }
     }
 
     /// Attempt to compile the template to check for syntax errors
-    fn compile_template(&self) -> Result<(), String> {
+    fn compile_template(&self) -> Result<(), handlebars::TemplateError> {
         let mut handlebars = handlebars::Handlebars::new();
 
         // Set strict mode to catch undefined variables
         handlebars.set_strict_mode(false); // Allow undefined variables for now
 
-        match handlebars.register_template_string("test", &self.content) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!("{}", e)),
-        }
+        handlebars.register_template_string("test", &self.content)
     }
 
     /// Get current template content
@@ -137,3 +148,21 @@ impl EditorState {
         &self.content
     }
 }
+
+/// The line, numbered from 1, of the first `}}` closing nothing, or else of the last
+/// `{{` left open. `None` when the braces are balanced.
+fn unbalanced_brace_line(content: &str) -> Option<usize> {
+    let mut open = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let mut rest = line;
+        while let Some(position) = rest.find("{{").into_iter().chain(rest.find("}}")).min() {
+            if rest[position..].starts_with("{{") {
+                open.push(index + 1);
+            } else if open.pop().is_none() {
+                return Some(index + 1);
+            }
+            rest = &rest[position + 2..];
+        }
+    }
+    open.pop()
+}
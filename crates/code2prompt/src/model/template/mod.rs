@@ -4,17 +4,25 @@
 //! - Editor: Template content editing and validation
 //! - Variable: Variable management and validation
 //! - Picker: Template selection and loading
+//!
+//! The preview pane, shown under the editor, renders the template against a sample of
+//! the selected files.
 
 pub mod editor;
 pub mod picker;
+pub mod preview;
 pub mod variable;
 
 pub use editor::EditorState;
 pub use picker::{ActiveList, PickerState};
+pub use preview::PreviewState;
 pub use variable::{VariableCategory, VariableInfo, VariableState};
 
+use serde::{Deserialize, Serialize};
+
 /// Which component is currently focused
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TemplateFocus {
     Editor,
     Variables,
@@ -27,6 +35,7 @@ pub enum FocusMode {
     Normal,          // Can switch between panels with e/v/p
     EditingTemplate, // Locked to editor, ESC to exit
     EditingVariable, // Locked to variables, ESC to exit
+    SavingTemplate,  // Typing the name the template is saved as, ESC to cancel
 }
 
 /// Coordinated template state containing all sub-components
@@ -35,9 +44,12 @@ pub struct TemplateState {
     pub editor: EditorState,
     pub variables: VariableState,
     pub picker: PickerState,
+    pub preview: PreviewState,
     pub focus: TemplateFocus,
     pub focus_mode: FocusMode,
     pub status_message: String,
+    /// Name typed for the template being saved
+    pub save_name: String,
 }
 
 impl Default for TemplateState {
@@ -46,9 +58,11 @@ impl Default for TemplateState {
             editor: EditorState::default(),
             variables: VariableState::default(),
             picker: PickerState::default(),
+            preview: PreviewState::default(),
             focus: TemplateFocus::Editor,
             focus_mode: FocusMode::Normal,
             status_message: String::new(),
+            save_name: String::new(),
         };
 
         // Initialize variable state with template variables
@@ -70,6 +84,17 @@ impl TemplateState {
         self.variables.update_missing_variables(template_vars);
     }
 
+    /// Renders the template again in the preview pane, if it is shown
+    pub fn refresh_preview(&mut self, session: &code2prompt_core::session::Code2PromptSession) {
+        if self.preview.visible {
+            self.preview.render(
+                session,
+                self.editor.get_content(),
+                &self.variables.user_variables,
+            );
+        }
+    }
+
     /// Set focus to a specific component
     pub fn set_focus(&mut self, focus: TemplateFocus) {
         self.focus = focus;
@@ -94,7 +119,7 @@ impl TemplateState {
     pub fn is_in_editing_mode(&self) -> bool {
         matches!(
             self.focus_mode,
-            FocusMode::EditingTemplate | FocusMode::EditingVariable
+            FocusMode::EditingTemplate | FocusMode::EditingVariable | FocusMode::SavingTemplate
         )
     }
 
@@ -145,17 +170,7 @@ impl TemplateState {
             (content, selected_template.name.clone())
         };
 
-        // Update editor with new content
-        self.editor.content = content.clone();
-        self.editor.current_template_name = template_name.clone();
-
-        // Create new TextArea with the content
-        self.editor.editor = tui_textarea::TextArea::from(content.lines());
-
-        // Sync and validate
-        self.editor.sync_content_from_textarea();
-        self.editor.validate_template();
-
+        self.editor.set_content(template_name.clone(), content);
         Ok(template_name)
     }
 
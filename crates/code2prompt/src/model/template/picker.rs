@@ -10,6 +10,10 @@ use std::path::PathBuf;
 pub struct TemplateFile {
     pub name: String,
     pub path: PathBuf,
+    /// Description from the template front-matter
+    pub description: Option<String>,
+    /// Whether the template comes from the project rather than the user library
+    pub project: bool,
 }
 
 /// Which list is currently active in the picker
@@ -27,6 +31,8 @@ pub struct PickerState {
     pub active_list: ActiveList,
     pub default_cursor: usize,
     pub custom_cursor: usize,
+    /// Project whose `.code2prompt/templates` are listed with the custom templates
+    pub project_root: Option<PathBuf>,
 }
 
 impl Default for PickerState {
@@ -37,6 +43,7 @@ impl Default for PickerState {
             active_list: ActiveList::Default,
             default_cursor: 0,
             custom_cursor: 0,
+            project_root: None,
         };
 
         state.load_all_templates();
@@ -45,6 +52,12 @@ impl Default for PickerState {
 }
 
 impl PickerState {
+    /// List the templates of a project with the custom templates
+    pub fn set_project_root(&mut self, project_root: PathBuf) {
+        self.project_root = Some(project_root);
+        self.refresh();
+    }
+
     /// Load all templates from default and custom directories
     pub fn load_all_templates(&mut self) {
         self.load_default_templates();
@@ -66,6 +79,8 @@ impl PickerState {
             self.default_templates.push(TemplateFile {
                 name: template.name.to_string(),
                 path: PathBuf::from(format!("builtin://{}", key)),
+                description: Some(template.description.to_string()),
+                project: false,
             });
         }
     }
@@ -74,13 +89,15 @@ impl PickerState {
     fn load_custom_templates(&mut self) {
         self.custom_templates.clear();
 
-        // Load templates from custom directory using utility function
-        if let Ok(all_templates) = crate::utils::load_all_templates() {
-            for (name, path) in all_templates {
-                // All templates from load_all_templates are custom
+        // Load templates from the user library and the project
+        let project_root = self.project_root.as_deref();
+        if let Ok(all_templates) = crate::template_library::load_all_templates(project_root) {
+            for template in all_templates {
                 self.custom_templates.push(TemplateFile {
-                    name,
-                    path: PathBuf::from(path),
+                    name: template.name,
+                    path: template.path,
+                    description: template.description,
+                    project: template.project,
                 });
             }
         }
@@ -0,0 +1,85 @@
+//! Template preview state management.
+//!
+//! The preview renders the template being edited against the first files selected in
+//! the file tree, each cut to its first lines, so that a template can be checked without
+//! running the analysis.
+
+use crate::model::DisplayFileNode;
+use code2prompt_core::path::FileEntry;
+use code2prompt_core::selection::FileInclusion;
+use code2prompt_core::session::Code2PromptSession;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Number of selected files the template is previewed with
+pub const PREVIEW_SAMPLE_FILES: usize = 3;
+
+/// Number of lines kept of each file of the preview
+pub const PREVIEW_SAMPLE_LINES: usize = 20;
+
+/// State of the preview pane
+#[derive(Debug, Clone, Default)]
+pub struct PreviewState {
+    /// Whether the pane is shown under the editor
+    pub visible: bool,
+    /// The sampled files the template is rendered with
+    pub files: Vec<FileEntry>,
+    /// The rendered template
+    pub rendered: String,
+    /// Why the template could not be rendered
+    pub error: Option<String>,
+}
+
+impl PreviewState {
+    /// Samples the first files selected in full among those loaded in the file tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - The session holding the selection
+    /// * `nodes` - The nodes of the file tree
+    pub fn sample(&mut self, session: &mut Code2PromptSession, nodes: &[DisplayFileNode]) {
+        fn collect(
+            node: &DisplayFileNode,
+            session: &mut Code2PromptSession,
+            paths: &mut Vec<PathBuf>,
+        ) {
+            if paths.len() >= PREVIEW_SAMPLE_FILES {
+                return;
+            }
+            if node.is_directory {
+                for child in &node.children {
+                    collect(child, session, paths);
+                }
+            } else if session.file_inclusion(&node.path) == FileInclusion::Full {
+                paths.push(node.path.clone());
+            }
+        }
+        let mut paths = Vec::new();
+        for node in nodes {
+            collect(node, session, &mut paths);
+        }
+        self.files = session.sample_files(&paths, PREVIEW_SAMPLE_LINES);
+    }
+
+    /// Renders the template against the sampled files.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - The session providing the helpers and the rest of the data
+    /// * `template` - The template being edited
+    /// * `user_variables` - The variables entered in the variables panel
+    pub fn render(
+        &mut self,
+        session: &Code2PromptSession,
+        template: &str,
+        user_variables: &HashMap<String, String>,
+    ) {
+        match session.preview_template(template, &self.files, user_variables) {
+            Ok(rendered) => {
+                self.rendered = rendered;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("{:#}", e)),
+        }
+    }
+}
@@ -70,6 +70,10 @@ impl VariableState {
             "git_diff".to_string(),
             "Git diff output (if enabled)".to_string(),
         );
+        vars.insert(
+            "git_stash".to_string(),
+            "Diff of a stash (if --stash is given)".to_string(),
+        );
         vars.insert(
             "git_diff_branch".to_string(),
             "Git diff between branches".to_string(),
@@ -78,6 +82,66 @@ impl VariableState {
             "git_log_branch".to_string(),
             "Git log between branches".to_string(),
         );
+        vars.insert(
+            "conflicts".to_string(),
+            "Merge conflicts of the working tree (if --conflicts is given)".to_string(),
+        );
+        vars.insert(
+            "untracked_files".to_string(),
+            "Untracked files of the working tree (if --untracked is given)".to_string(),
+        );
+        vars.insert(
+            "recent_commits".to_string(),
+            "Recent commit messages, for commit message generation".to_string(),
+        );
+        vars.insert(
+            "branch_changes".to_string(),
+            "Changes of a branch for a PR description".to_string(),
+        );
+        vars.insert(
+            "merged_pull_requests".to_string(),
+            "Pull requests merged since a tag, for release notes".to_string(),
+        );
+        vars.insert(
+            "doc_coverage".to_string(),
+            "Documentation coverage report".to_string(),
+        );
+        vars.insert(
+            "architecture_brief".to_string(),
+            "Architecture overview of the codebase".to_string(),
+        );
+        vars.insert(
+            "onboarding_tour".to_string(),
+            "Files ordered as an onboarding learning path".to_string(),
+        );
+        vars.insert(
+            "environment_spec".to_string(),
+            "Nix and devcontainer environment summary".to_string(),
+        );
+        vars.insert(
+            "build_config".to_string(),
+            "Bundler and asset pipeline configuration".to_string(),
+        );
+        vars.insert(
+            "context_delta".to_string(),
+            "Files unchanged or removed since an earlier conversation turn".to_string(),
+        );
+        vars.insert(
+            "manifest_changes".to_string(),
+            "Files changed since the last manifest".to_string(),
+        );
+        vars.insert(
+            "version_info".to_string(),
+            "Tag and release metadata".to_string(),
+        );
+        vars.insert(
+            "roots".to_string(),
+            "Roots of a multi-root workspace".to_string(),
+        );
+        vars.insert(
+            "config".to_string(),
+            "Effective configuration (e.g. config.line_numbers)".to_string(),
+        );
 
         // File object properties (used within {{#each files}} loops)
         vars.insert(
@@ -108,12 +172,18 @@ impl VariableState {
         vars
     }
 
+    /// Whether a variable, or the namespace it belongs to, is a system variable
+    fn is_system_variable(&self, var: &str) -> bool {
+        let root = var.split('.').next().unwrap_or(var);
+        self.system_variables.contains_key(root)
+    }
+
     /// Update missing variables based on template variables
     pub fn update_missing_variables(&mut self, template_variables: &[String]) {
         self.missing_variables.clear();
 
         for var in template_variables {
-            if !self.system_variables.contains_key(var) && !self.user_variables.contains_key(var) {
+            if !self.is_system_variable(var) && !self.user_variables.contains_key(var) {
                 self.missing_variables.push(var.clone());
             }
         }
@@ -127,7 +197,10 @@ impl VariableState {
 
         // System variables (only those used in template)
         for var in template_variables {
-            if let Some(desc) = self.system_variables.get(var) {
+            if let Some(desc) = self
+                .system_variables
+                .get(var.split('.').next().unwrap_or(var))
+            {
                 variables.push(VariableInfo {
                     name: var.clone(),
                     value: Some("(system)".to_string()),
@@ -139,6 +212,9 @@ impl VariableState {
 
         // User variables (only those used in template)
         for var in template_variables {
+            if self.is_system_variable(var) {
+                continue;
+            }
             if let Some(value) = self.user_variables.get(var) {
                 variables.push(VariableInfo {
                     name: var.clone(),
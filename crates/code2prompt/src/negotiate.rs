@@ -0,0 +1,136 @@
+//! Interactive budget negotiation.
+//!
+//! When the prompt exceeds `--max-tokens`, lists the files taking the most tokens and lets
+//! the user drop, summarize or truncate them one by one, measuring the prompt again after
+//! each decision, instead of pruning files automatically.
+
+use anyhow::Result;
+use code2prompt_core::budget::BudgetAction;
+use code2prompt_core::session::Code2PromptSession;
+use colored::*;
+use inquire::Select;
+use std::fmt;
+
+use crate::utils::format_number;
+
+/// Number of files offered at each step
+const TOP_CONSUMERS: usize = 10;
+
+/// A choice of the negotiation menu
+enum Choice {
+    File { path: String, tokens: usize },
+    Automatic,
+    Keep,
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Choice::File { path, tokens } => write!(f, "{:>10} tokens  {}", tokens, path),
+            Choice::Automatic => write!(f, "Prune files automatically (--budget-strategy)"),
+            Choice::Keep => write!(f, "Keep the prompt as it is"),
+        }
+    }
+}
+
+/// An action of the file menu
+struct ActionChoice(BudgetAction);
+
+impl fmt::Display for ActionChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            BudgetAction::Drop => write!(f, "Drop the file"),
+            BudgetAction::Summarize => write!(f, "Summarize: keep its signatures only"),
+            BudgetAction::Truncate => write!(f, "Truncate: cut its end, keeping at least half"),
+        }
+    }
+}
+
+/// Negotiates the token budget with the user until the prompt fits or the user stops.
+///
+/// # Arguments
+///
+/// * `session` - The session, with everything the prompt includes loaded
+pub fn negotiate_budget(session: &mut Code2PromptSession) -> Result<()> {
+    let max_tokens = session.config.max_tokens.unwrap_or_default();
+    while let Some(excess) = session.token_budget_excess().filter(|&excess| excess > 0) {
+        eprintln!(
+            "{}{}{} The prompt takes {} tokens, {} over the budget of {}",
+            "[".bold().white(),
+            "!".bold().yellow(),
+            "]".bold().white(),
+            format_number(max_tokens + excess, &session.config.token_format),
+            format_number(excess, &session.config.token_format),
+            format_number(max_tokens, &session.config.token_format),
+        );
+
+        let mut files: Vec<(String, usize)> = session
+            .data
+            .files
+            .iter()
+            .flatten()
+            .map(|file| (file.path.clone(), file.token_count))
+            .collect();
+        files.sort_by_key(|(_, tokens)| std::cmp::Reverse(*tokens));
+        let mut choices: Vec<Choice> = files
+            .into_iter()
+            .take(TOP_CONSUMERS)
+            .map(|(path, tokens)| Choice::File { path, tokens })
+            .collect();
+        choices.push(Choice::Automatic);
+        choices.push(Choice::Keep);
+
+        let choice = Select::new("Which file should give way?", choices)
+            .with_help_message("The files taking the most tokens, Esc keeps the prompt")
+            .prompt_skippable()?;
+        let path = match choice {
+            Some(Choice::File { path, .. }) => path,
+            Some(Choice::Automatic) => {
+                // The files already negotiated stay in the report
+                let negotiated = session.data.budget_report.take().unwrap_or_default();
+                session.enforce_token_budget();
+                if let Some(report) = session.data.budget_report.as_mut() {
+                    report.summarized_files = negotiated.summarized_files;
+                    report.dropped_files.splice(0..0, negotiated.dropped_files);
+                    for path in negotiated.truncated_files.into_iter().rev() {
+                        if !report.truncated_files.contains(&path) {
+                            report.truncated_files.insert(0, path);
+                        }
+                    }
+                }
+                return Ok(());
+            }
+            Some(Choice::Keep) | None => return Ok(()),
+        };
+
+        let actions = vec![
+            ActionChoice(BudgetAction::Drop),
+            ActionChoice(BudgetAction::Summarize),
+            ActionChoice(BudgetAction::Truncate),
+        ];
+        let Some(ActionChoice(action)) = Select::new(&format!("{}:", path), actions)
+            .with_help_message("Esc goes back to the files")
+            .prompt_skippable()?
+        else {
+            continue;
+        };
+        match session.apply_budget_action(&path, action) {
+            Ok(saved) => eprintln!(
+                "{}{}{} {}: saved {} tokens",
+                "[".bold().white(),
+                "-".bold().yellow(),
+                "]".bold().white(),
+                path,
+                format_number(saved, &session.config.token_format)
+            ),
+            Err(e) => eprintln!(
+                "{}{}{} {}",
+                "[".bold().white(),
+                "!".bold().red(),
+                "]".bold().white(),
+                format!("{:#}", e).red()
+            ),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,1569 @@
+//! The options shared by the command line, the config file and the TUI settings.
+//!
+//! Each option is described once in [`OPTIONS`]: its key in the config file, its flag, its
+//! place in the TUI settings if it has one and how it is read from and written to a
+//! [`Code2PromptConfig`]. The values go through serde, so a choice is spelled the same way
+//! on the command line as in the config file.
+//!
+//! The flags of the command line, their layering over the config file and the settings of
+//! the TUI are all derived from this table: an option added to it appears everywhere.
+
+use anyhow::{Context, Result, anyhow};
+use clap::{Arg, ArgAction, ArgMatches, Command, CommandFactory, FromArgMatches};
+use code2prompt_core::{
+    configuration::{Code2PromptConfig, TomlConfig},
+    git::DEFAULT_FILE_HISTORY_COMMITS,
+    path::SymlinkPolicy,
+    sort::FileSortMethod,
+    template::OutputFormat,
+    tokenizer::{TokenCountMode, TokenFormat, TokenizerType},
+};
+use log::error;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::args::Cli;
+
+/// Values of the options given on the command line, by key
+pub type OptionValues = BTreeMap<&'static str, Value>;
+
+/// The values an option takes
+pub enum OptionKind {
+    /// On or off, turned on by its flag
+    Flag,
+    /// One of a list of values, cycled through in the TUI
+    Choice {
+        /// The values shown in the help of the flag
+        value_name: &'static str,
+        /// The values in their serde spelling, with their labels in the TUI
+        values: fn() -> Vec<(Value, String)>,
+    },
+    /// A single value, such as a number, a path or a name
+    Value {
+        value_name: &'static str,
+        /// Reads the value of the flag
+        parse: fn(&str) -> Result<Value>,
+        /// The value of the flag given without one, if it may be
+        default_missing: Option<&'static str>,
+    },
+    /// A list, the values of the repeated flag replacing those of the config file
+    List {
+        value_name: &'static str,
+        /// Reads the values of one occurrence of the flag
+        parse: fn(&str) -> Result<Vec<Value>>,
+        /// The options whose config file values are left out as well when this one is given
+        /// on the command line, the patterns to include and to exclude going together
+        replaces: &'static [&'static str],
+    },
+    /// A table, the entries of the repeated flag being added to those of the config file
+    Entries {
+        value_name: &'static str,
+        /// Reads the name and the value of one occurrence of the flag
+        parse: fn(&str) -> Result<(String, Value)>,
+    },
+}
+
+/// The place of an option in the TUI settings, which shows flags and choices
+pub struct Setting {
+    pub group: &'static str,
+    pub label: &'static str,
+    pub description: &'static str,
+}
+
+/// An option of the configuration
+pub struct OptionSpec {
+    /// Key in the config file, identifying the option. The options of a table of the config
+    /// file, such as `tree_options`, are keyed by their dotted path
+    pub key: &'static str,
+    /// Long flag, without its dashes
+    pub long: &'static str,
+    pub short: Option<char>,
+    /// Help of the flag
+    pub help: &'static str,
+    /// Group, name and description in the TUI settings, if the option is shown there
+    pub setting: Option<Setting>,
+    pub kind: OptionKind,
+    /// Reads the value from the configuration
+    pub get: fn(&Code2PromptConfig) -> Value,
+    /// Writes the value to the configuration
+    pub set: fn(&mut Code2PromptConfig, Value) -> serde_json::Result<()>,
+}
+
+/// The options, those of the TUI settings first and in their order
+pub static OPTIONS: &[OptionSpec] = &[
+    OptionSpec {
+        key: "line_numbers",
+        long: "line-numbers",
+        short: Some('l'),
+        help: "Add line numbers to the source code",
+        setting: Some(Setting {
+            group: "Output Format",
+            label: "Line Numbers",
+            description: "Show line numbers in output",
+        }),
+        kind: OptionKind::Flag,
+        get: |config| json!(config.line_numbers),
+        set: |config, value| {
+            config.line_numbers = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "absolute_path",
+        long: "absolute-paths",
+        short: None,
+        help: "If true, paths in the output will be absolute instead of relative.",
+        setting: Some(Setting {
+            group: "Output Format",
+            label: "Absolute Paths",
+            description: "Use absolute instead of relative paths",
+        }),
+        kind: OptionKind::Flag,
+        get: |config| json!(config.absolute_path),
+        set: |config, value| {
+            config.absolute_path = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "no_codeblock",
+        long: "no-codeblock",
+        short: None,
+        help: "Disable wrapping code inside markdown code blocks",
+        setting: Some(Setting {
+            group: "Output Format",
+            label: "No Codeblock",
+            description: "Don't wrap code in markdown blocks",
+        }),
+        kind: OptionKind::Flag,
+        get: |config| json!(config.no_codeblock),
+        set: |config, value| {
+            config.no_codeblock = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "output_format",
+        long: "output-format",
+        short: Some('F'),
+        help: "Output format",
+        setting: Some(Setting {
+            group: "Output Format",
+            label: "Output Format",
+            description: "Format for generated output",
+        }),
+        kind: OptionKind::Choice {
+            value_name: "markdown, json, xml, jsonl, chunks",
+            values: || {
+                labeled(&[
+                    (OutputFormat::Markdown, "Markdown"),
+                    (OutputFormat::Json, "JSON"),
+                    (OutputFormat::Xml, "XML"),
+                    (OutputFormat::Jsonl, "JSONL"),
+                    (OutputFormat::Chunks, "Chunks"),
+                ])
+            },
+        },
+        get: |config| json!(config.output_format),
+        set: |config, value| {
+            config.output_format = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "token_format",
+        long: "token-format",
+        short: None,
+        help: "Display the token count of the generated prompt. Accepts a format: \"raw\" (machine parsable) or \"format\" (human readable)",
+        setting: Some(Setting {
+            group: "Output Format",
+            label: "Token Format",
+            description: "How to display token counts",
+        }),
+        kind: OptionKind::Choice {
+            value_name: "raw,format",
+            values: || displayed(&[TokenFormat::Raw, TokenFormat::Format]),
+        },
+        get: |config| json!(config.token_format),
+        set: |config, value| {
+            config.token_format = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "full_directory_tree",
+        long: "full-directory-tree",
+        short: None,
+        help: "List the full directory tree",
+        setting: Some(Setting {
+            group: "Output Format",
+            label: "Full Directory Tree",
+            description: "Show complete directory structure",
+        }),
+        kind: OptionKind::Flag,
+        get: |config| json!(config.full_directory_tree),
+        set: |config, value| {
+            config.full_directory_tree = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "sort_method",
+        long: "sort",
+        short: None,
+        help: "Sort order for files",
+        setting: Some(Setting {
+            group: "Sorting & Organization",
+            label: "Sort Method",
+            description: "How to sort files in output",
+        }),
+        kind: OptionKind::Choice {
+            value_name: "name_asc, name_desc, date_asc, date_desc, extension, tokens_desc, imports",
+            values: || displayed(&FileSortMethod::ALL),
+        },
+        get: |config| json!(config.sort_method.unwrap_or(FileSortMethod::NameAsc)),
+        set: |config, value| {
+            config.sort_method = Some(serde_json::from_value(value)?);
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "encoding",
+        long: "encoding",
+        short: None,
+        help: "Token encoding to use for token count: the tiktoken encodings, \"claude\" (approximation for Anthropic models), \"llama\" (SentencePiece model given by --tokenizer-model) or \"chars\" (4 characters per token)",
+        setting: Some(Setting {
+            group: "Tokenizer & Encoding",
+            label: "Tokenizer Type",
+            description: "Encoding method for token counting",
+        }),
+        kind: OptionKind::Choice {
+            value_name: "o200k, cl100k, p50k, p50k_edit, r50k, claude, llama, chars",
+            values: || {
+                displayed(&[
+                    TokenizerType::Cl100kBase,
+                    TokenizerType::O200kBase,
+                    TokenizerType::P50kBase,
+                    TokenizerType::P50kEdit,
+                    TokenizerType::R50kBase,
+                    TokenizerType::Claude,
+                    TokenizerType::Llama,
+                    TokenizerType::Chars,
+                ])
+            },
+        },
+        get: |config| json!(config.encoding),
+        set: |config, value| {
+            config.encoding = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "token_count_mode",
+        long: "token-count-mode",
+        short: None,
+        help: "How token counts are computed: \"exact\" (tokenizer, slow) or \"heuristic\" (byte-length estimate, fast)",
+        setting: Some(Setting {
+            group: "Tokenizer & Encoding",
+            label: "Token Counting",
+            description: "Fast heuristic estimate or exact (slow) tokenization",
+        }),
+        kind: OptionKind::Choice {
+            value_name: "exact,heuristic",
+            values: || displayed(&[TokenCountMode::Heuristic, TokenCountMode::Exact]),
+        },
+        get: |config| json!(config.token_count_mode),
+        set: |config, value| {
+            config.token_count_mode = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "diff_enabled",
+        long: "diff",
+        short: Some('d'),
+        help: "Include git diff",
+        setting: Some(Setting {
+            group: "Git Integration",
+            label: "Git Diff",
+            description: "Include git diff in output",
+        }),
+        kind: OptionKind::Flag,
+        get: |config| json!(config.diff_enabled),
+        set: |config, value| {
+            config.diff_enabled = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "symlink_policy",
+        long: "symlink-policy",
+        short: None,
+        help: "How symbolic links are handled: \"skip\" (listed without content), \"follow\" or \"follow_with_cycle_detection\" (each file or directory traversed once)",
+        setting: Some(Setting {
+            group: "File Selection",
+            label: "Symlinks",
+            description: "Skip, follow, or follow symbolic links once",
+        }),
+        kind: OptionKind::Choice {
+            value_name: "skip,follow,follow_with_cycle_detection",
+            values: || {
+                displayed(&[
+                    SymlinkPolicy::Skip,
+                    SymlinkPolicy::Follow,
+                    SymlinkPolicy::FollowWithCycleDetection,
+                ])
+            },
+        },
+        get: |config| json!(config.symlink_policy),
+        set: |config, value| {
+            config.symlink_policy = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "hidden",
+        long: "hidden",
+        short: None,
+        help: "Include hidden directories and files",
+        setting: Some(Setting {
+            group: "File Selection",
+            label: "Hidden Files",
+            description: "Include hidden files and directories",
+        }),
+        kind: OptionKind::Flag,
+        get: |config| json!(config.hidden),
+        set: |config, value| {
+            config.hidden = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "no_ignore",
+        long: "no-ignore",
+        short: None,
+        help: "Skip .gitignore and .c2pignore rules",
+        setting: Some(Setting {
+            group: "File Selection",
+            label: "No Ignore",
+            description: "Ignore .gitignore and .c2pignore rules",
+        }),
+        kind: OptionKind::Flag,
+        get: |config| json!(config.no_ignore),
+        set: |config, value| {
+            config.no_ignore = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "include_patterns",
+        long: "include",
+        short: Some('i'),
+        help: "Patterns to include, a file path being limited to some lines with `path:start-end`",
+        setting: None,
+        kind: OptionKind::List {
+            value_name: "PATTERNS",
+            parse: patterns,
+            replaces: &["exclude_patterns"],
+        },
+        get: |config| json!(config.include_patterns),
+        set: |config, value| {
+            config.include_patterns = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "exclude_patterns",
+        long: "exclude",
+        short: Some('e'),
+        help: "Patterns to exclude",
+        setting: None,
+        kind: OptionKind::List {
+            value_name: "PATTERNS",
+            parse: patterns,
+            replaces: &["include_patterns"],
+        },
+        get: |config| json!(config.exclude_patterns),
+        set: |config, value| {
+            config.exclude_patterns = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "tree_only_patterns",
+        long: "tree-only",
+        short: None,
+        help: "Patterns of the files listed in the source tree without their content",
+        setting: None,
+        kind: OptionKind::List {
+            value_name: "PATTERNS",
+            parse: patterns,
+            replaces: &[],
+        },
+        get: |config| json!(config.tree_only_patterns),
+        set: |config, value| {
+            config.tree_only_patterns = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "collapse_patterns",
+        long: "collapse",
+        short: None,
+        help: "Patterns of the directories collapsed in the output: a single line in the tree and a single summarized entry instead of their files, the selection being unchanged",
+        setting: None,
+        kind: OptionKind::List {
+            value_name: "PATTERNS",
+            parse: patterns,
+            replaces: &[],
+        },
+        get: |config| json!(config.collapse_patterns),
+        set: |config, value| {
+            config.collapse_patterns = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "packages",
+        long: "package",
+        short: None,
+        help: "Keep only this workspace package, by name or directory, and the internal packages it depends on",
+        setting: None,
+        kind: OptionKind::List {
+            value_name: "PACKAGE",
+            parse: patterns,
+            replaces: &[],
+        },
+        get: |config| json!(config.packages),
+        set: |config, value| {
+            config.packages = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "bazel_targets",
+        long: "bazel-target",
+        short: None,
+        help: "Keep only the sources of this Bazel or Buck target and of its transitive dependencies, read from the BUILD files (e.g. //services/auth:auth)",
+        setting: None,
+        kind: OptionKind::List {
+            value_name: "LABEL",
+            parse: item,
+            replaces: &[],
+        },
+        get: |config| json!(config.bazel_targets),
+        set: |config, value| {
+            config.bazel_targets = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "tags_file",
+        long: "tags",
+        short: None,
+        help: "Tags file generated by ctags (`ctags -R`) or GNU Global (`global -x`), listing the symbols of each file in the output",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "FILE",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.tags_file),
+        set: |config, value| {
+            config.tags_file = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "symbols",
+        long: "symbol",
+        short: None,
+        help: "Keep only the files defining this symbol, as listed in the --tags file. Can be repeated",
+        setting: None,
+        kind: OptionKind::List {
+            value_name: "NAME",
+            parse: item,
+            replaces: &[],
+        },
+        get: |config| json!(config.symbols),
+        set: |config, value| {
+            config.symbols = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "file_delimiter",
+        long: "file-delimiter",
+        short: None,
+        help: "Delimiters around each file: \"markdown\", \"xml\", \"banner\", or \"auto\" for those of the output format [default: auto]",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "auto,markdown,xml,banner",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.file_delimiter),
+        set: |config, value| {
+            config.file_delimiter = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "chunk_options.lines",
+        long: "chunk-lines",
+        short: None,
+        help: "Maximum number of lines of a chunk of the \"chunks\" output format [default: 60]",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "LINES",
+            parse: number,
+            default_missing: None,
+        },
+        get: |config| json!(config.chunk_options.lines),
+        set: |config, value| {
+            config.chunk_options.lines = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "chunk_options.overlap",
+        long: "chunk-overlap",
+        short: None,
+        help: "Number of lines a chunk repeats from the previous one [default: 10]",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "LINES",
+            parse: number,
+            default_missing: None,
+        },
+        get: |config| json!(config.chunk_options.overlap),
+        set: |config, value| {
+            config.chunk_options.overlap = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "file_header",
+        long: "file-header",
+        short: None,
+        help: "Handlebars template of the line before each file, as '===== FILE: {{path}} ====='",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "TEMPLATE",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.file_header),
+        set: |config, value| {
+            config.file_header = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "file_footer",
+        long: "file-footer",
+        short: None,
+        help: "Handlebars template of the line after each file, as '===== END: {{path}} ====='",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "TEMPLATE",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.file_footer),
+        set: |config, value| {
+            config.file_footer = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "post_process",
+        long: "post-process",
+        short: None,
+        help: "Post-processing steps applied to the rendered prompt, in order: \"collapse_blank_lines\", \"number_headers\", \"table_of_contents\", \"file_index\", \"footer\"",
+        setting: None,
+        kind: OptionKind::List {
+            value_name: "STEPS",
+            parse: separated,
+            replaces: &[],
+        },
+        get: |config| json!(config.post_process),
+        set: |config, value| {
+            config.post_process = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "instruction",
+        long: "instruction",
+        short: None,
+        help: "Instruction or task appended after the codebase",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "TEXT",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.instruction),
+        set: |config, value| {
+            config.instruction = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "repeat_instruction",
+        long: "repeat-instruction",
+        short: None,
+        help: "Also render the instruction before the codebase, so that the model reads it first and last",
+        setting: None,
+        kind: OptionKind::Flag,
+        get: |config| json!(config.repeat_instruction),
+        set: |config, value| {
+            config.repeat_instruction = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "placement",
+        long: "placement",
+        short: None,
+        help: "Where the instruction, the git diffs and the key files go: \"end\", \"beginning\" or \"sandwich\"",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "PLACEMENT",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.placement),
+        set: |config, value| {
+            config.placement = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "key_files",
+        long: "key-files",
+        short: None,
+        help: "Patterns of the files the task is about, placed first or last following --placement",
+        setting: None,
+        kind: OptionKind::List {
+            value_name: "PATTERNS",
+            parse: patterns,
+            replaces: &[],
+        },
+        get: |config| json!(config.key_files),
+        set: |config, value| {
+            config.key_files = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "prompt_cache",
+        long: "prompt-cache",
+        short: None,
+        help: "Emit the prompt in a provider messages format with the codebase marked as cacheable: \"anthropic\" or \"openai\"",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "PROVIDER",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.prompt_cache),
+        set: |config, value| {
+            config.prompt_cache = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "tree_options.max_depth",
+        long: "tree-depth",
+        short: None,
+        help: "Collapse the directories of the tree from this depth, annotated with their file count and tokens (1 keeps only the top-level entries)",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "DEPTH",
+            parse: number,
+            default_missing: None,
+        },
+        get: |config| json!(config.tree_options.max_depth),
+        set: |config, value| {
+            config.tree_options.max_depth = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "tree_options.annotate_directories",
+        long: "tree-annotations",
+        short: None,
+        help: "Annotate every directory of the tree with its file count and tokens",
+        setting: None,
+        kind: OptionKind::Flag,
+        get: |config| json!(config.tree_options.annotate_directories),
+        set: |config, value| {
+            config.tree_options.annotate_directories = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "tree_options.include_empty_dirs",
+        long: "empty-dirs",
+        short: None,
+        help: "Keep the directories holding no file in the tree",
+        setting: None,
+        kind: OptionKind::Flag,
+        get: |config| json!(config.tree_options.include_empty_dirs),
+        set: |config, value| {
+            config.tree_options.include_empty_dirs = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "tree_options.style",
+        long: "tree-style",
+        short: None,
+        help: "Drawing style of the source tree [default: unicode]",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "unicode,ascii,indented,json",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.tree_options.style),
+        set: |config, value| {
+            config.tree_options.style = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "frontend_summary",
+        long: "frontend-summary",
+        short: None,
+        help: "Summarize build tool configurations (Vite, webpack, Next.js, Tailwind...) instead of including them, and group component files by route or feature in the tree",
+        setting: None,
+        kind: OptionKind::Flag,
+        get: |config| json!(config.frontend_summary),
+        set: |config, value| {
+            config.frontend_summary = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "fixture_policy",
+        long: "fixtures",
+        short: None,
+        help: "How test fixtures, snapshots and golden files are handled: \"include\", \"stub\" (a line with their kind, length and last update) or \"exclude\"",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "include,stub,exclude",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.fixture_policy),
+        set: |config, value| {
+            config.fixture_policy = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "binary_handling",
+        long: "binary",
+        short: None,
+        help: "How binary files are handled: \"skip\", \"path_only\" (listed with their size, without content) or \"base64\" (content encoded in base64)",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "skip,path_only,base64",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.binary_handling),
+        set: |config, value| {
+            config.binary_handling = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "compression_level",
+        long: "compression",
+        short: None,
+        help: "How much of the source files is kept: \"full\", or \"signatures\" (function bodies replaced with `...`, keeping signatures, doc comments and type definitions)",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "full,signatures",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.compression_level),
+        set: |config, value| {
+            config.compression_level = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "lsp_servers",
+        long: "lsp-server",
+        short: None,
+        help: "Compress the signatures of the files with an extension from the symbols reported by a language server, as EXT=COMMAND (e.g. `rs=rust-analyzer`). Can be repeated",
+        setting: None,
+        kind: OptionKind::Entries {
+            value_name: "EXT=COMMAND",
+            parse: lsp_server,
+        },
+        get: |config| json!(config.lsp_servers),
+        set: |config, value| {
+            config.lsp_servers = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "duplicates",
+        long: "duplicates",
+        short: None,
+        help: "Find blocks of code repeated across the files: \"report\" lists them with the tokens they cost, \"stub\" also replaces each copy with a reference to the first one",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "report,stub",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.duplicates),
+        set: |config, value| {
+            config.duplicates = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "duplicate_min_lines",
+        long: "duplicate-min-lines",
+        short: None,
+        help: "Minimum number of non-blank lines of a duplicated block [default: 6]",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "LINES",
+            parse: number,
+            default_missing: None,
+        },
+        get: |config| json!(config.duplicate_min_lines),
+        set: |config, value| {
+            config.duplicate_min_lines = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "data_sample",
+        long: "data-sample",
+        short: None,
+        help: "Keep the first HEAD and last TAIL rows of data files (CSV, TSV, JSON Lines), with their columns and row count, instead of a single sample row",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "HEAD,TAIL",
+            parse: data_sample,
+            default_missing: None,
+        },
+        get: |config| json!(config.data_sample),
+        set: |config, value| {
+            config.data_sample = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "summarize_logs",
+        long: "summarize-logs",
+        short: None,
+        help: "Summarize log files: collapse similar lines and keep the errors and warnings with their context, the first and the last lines",
+        setting: None,
+        kind: OptionKind::Flag,
+        get: |config| json!(config.summarize_logs),
+        set: |config, value| {
+            config.summarize_logs = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "tokenizer_model",
+        long: "tokenizer-model",
+        short: None,
+        help: "SentencePiece model file (tokenizer.model or tokenizer.json) used by the llama encoding",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "PATH",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.tokenizer_model),
+        set: |config, value| {
+            config.tokenizer_model = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "max_tokens",
+        long: "max-tokens",
+        short: None,
+        help: "Drop or truncate files until the prompt fits in this many tokens",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "TOKENS",
+            parse: number,
+            default_missing: None,
+        },
+        get: |config| json!(config.max_tokens),
+        set: |config, value| {
+            config.max_tokens = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "budget_strategy",
+        long: "budget-strategy",
+        short: None,
+        help: "Which files give way first when the prompt exceeds --max-tokens: \"largest\", \"smallest\", \"order\" (the last ones in the sort order) or \"truncate\" (cut the largest files)",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "largest,smallest,order,truncate",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.budget_strategy),
+        set: |config, value| {
+            config.budget_strategy = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "summarize_over",
+        long: "summarize-over",
+        short: None,
+        help: "Replace the files over this many tokens with a summary written by --summary-model, through the configured `llm_command`, cached by content hash",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "TOKENS",
+            parse: number,
+            default_missing: None,
+        },
+        get: |config| json!(config.summarize_over),
+        set: |config, value| {
+            config.summarize_over = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "diff_branches",
+        long: "git-diff-branch",
+        short: None,
+        help: "Generate git diff between two branches",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "BRANCHES",
+            parse: branches,
+            default_missing: None,
+        },
+        get: |config| json!(config.diff_branches),
+        set: |config, value| {
+            config.diff_branches = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "include_only_changed",
+        long: "diff-only",
+        short: None,
+        help: "Only include the files changed by the git diff, or by the --git-diff-branch diff, together with the diff itself",
+        setting: None,
+        kind: OptionKind::Flag,
+        get: |config| json!(config.include_only_changed),
+        set: |config, value| {
+            config.include_only_changed = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "file_history",
+        long: "file-history",
+        short: None,
+        help: "Include the latest commits changing a file, with their messages and diffs: PATH[:COUNT], COUNT defaulting to 5. Can be repeated",
+        setting: None,
+        kind: OptionKind::Entries {
+            value_name: "PATH[:COUNT]",
+            parse: file_history,
+        },
+        get: |config| json!(config.file_history),
+        set: |config, value| {
+            config.file_history = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "diff_include_patterns",
+        long: "diff-include",
+        short: None,
+        help: "Patterns a changed path must match to appear in git diffs, independently of --include",
+        setting: None,
+        kind: OptionKind::List {
+            value_name: "PATTERN",
+            parse: patterns,
+            replaces: &["diff_exclude_patterns"],
+        },
+        get: |config| json!(config.diff_include_patterns),
+        set: |config, value| {
+            config.diff_include_patterns = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "diff_exclude_patterns",
+        long: "diff-exclude",
+        short: None,
+        help: "Patterns excluding changed paths from git diffs, independently of --exclude",
+        setting: None,
+        kind: OptionKind::List {
+            value_name: "PATTERN",
+            parse: patterns,
+            replaces: &["diff_include_patterns"],
+        },
+        get: |config| json!(config.diff_exclude_patterns),
+        set: |config, value| {
+            config.diff_exclude_patterns = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "diff_context_lines",
+        long: "diff-context",
+        short: None,
+        help: "Number of context lines around changes in git diffs",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "LINES",
+            parse: number,
+            default_missing: None,
+        },
+        get: |config| json!(config.diff_context_lines),
+        set: |config, value| {
+            config.diff_context_lines = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "diff_style",
+        long: "diff-style",
+        short: None,
+        help: "How git diffs are rendered: \"unified\", \"word\" (inline [-old-]{+new+} markers) or \"function\" (whole enclosing function, like git diff -W)",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "unified,word,function",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.diff_style),
+        set: |config, value| {
+            config.diff_style = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "diff_max_tokens",
+        long: "diff-max-tokens",
+        short: None,
+        help: "Truncate git diffs larger than this many tokens, dropping hunk bodies of generated and large files first",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "TOKENS",
+            parse: number,
+            default_missing: None,
+        },
+        get: |config| json!(config.diff_max_tokens),
+        set: |config, value| {
+            config.diff_max_tokens = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "log_branches",
+        long: "git-log-branch",
+        short: None,
+        help: "Retrieve git log between two branches",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "BRANCHES",
+            parse: branches,
+            default_missing: None,
+        },
+        get: |config| json!(config.log_branches),
+        set: |config, value| {
+            config.log_branches = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "conflicts_enabled",
+        long: "conflicts",
+        short: None,
+        help: "Include unresolved merge conflicts (base, ours, theirs and a diff3 merge of each file)",
+        setting: None,
+        kind: OptionKind::Flag,
+        get: |config| json!(config.conflicts_enabled),
+        set: |config, value| {
+            config.conflicts_enabled = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "stash",
+        long: "stash",
+        short: None,
+        help: "Include the diff of a stash: the latest one, or stash@{N}, N, or text from its message",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "STASH",
+            parse: text,
+            default_missing: Some("stash@{0}"),
+        },
+        get: |config| json!(config.stash),
+        set: |config, value| {
+            config.stash = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "untracked_enabled",
+        long: "untracked",
+        short: None,
+        help: "List untracked files, to capture new work in progress",
+        setting: None,
+        kind: OptionKind::Flag,
+        get: |config| json!(config.untracked_enabled),
+        set: |config, value| {
+            config.untracked_enabled = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "path_alias",
+        long: "path-alias",
+        short: None,
+        help: "Prefix the paths in the output with an alias instead of the root, `{repo}` standing for the name of the codebase, as `--path-alias '{repo}'`. Takes precedence over `--absolute-paths`",
+        setting: None,
+        kind: OptionKind::Value {
+            value_name: "ALIAS",
+            parse: text,
+            default_missing: None,
+        },
+        get: |config| json!(config.path_alias),
+        set: |config, value| {
+            config.path_alias = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+    OptionSpec {
+        key: "token_map_enabled",
+        long: "token-map",
+        short: None,
+        help: "Display a visual token map of files (similar to disk usage tools)",
+        setting: None,
+        kind: OptionKind::Flag,
+        get: |config| json!(config.token_map_enabled),
+        set: |config, value| {
+            config.token_map_enabled = serde_json::from_value(value)?;
+            Ok(())
+        },
+    },
+];
+
+/// The option of a key.
+pub fn option(key: &str) -> Option<&'static OptionSpec> {
+    OPTIONS.iter().find(|spec| spec.key == key)
+}
+
+impl OptionSpec {
+    /// The flag of the option on the command line. A value is checked by writing it to a
+    /// configuration, so that a choice accepts the spellings of the config file, serde
+    /// aliases included.
+    fn arg(&'static self) -> Arg {
+        let arg = Arg::new(self.key).long(self.long).help(self.help);
+        let arg = match self.short {
+            Some(short) => arg.short(short),
+            None => arg,
+        };
+        match &self.kind {
+            OptionKind::Flag => arg.action(ArgAction::SetTrue),
+            OptionKind::Choice { value_name, .. } => arg
+                .value_name(*value_name)
+                .value_parser(move |text: &str| self.check(Value::String(text.to_string()))),
+            OptionKind::Value {
+                value_name,
+                parse,
+                default_missing,
+            } => {
+                let arg = arg
+                    .value_name(*value_name)
+                    .value_parser(move |text: &str| self.check(parse(text)?));
+                match default_missing {
+                    Some(missing) => arg.num_args(0..=1).default_missing_value(*missing),
+                    None => arg,
+                }
+            }
+            OptionKind::List {
+                value_name, parse, ..
+            } => arg
+                .value_name(*value_name)
+                .action(ArgAction::Append)
+                .value_parser(move |text: &str| self.check(Value::Array(parse(text)?))),
+            OptionKind::Entries { value_name, parse } => arg
+                .value_name(*value_name)
+                .action(ArgAction::Append)
+                .value_parser(move |text: &str| {
+                    let (name, value) = parse(text)?;
+                    self.check(Value::Object(Map::from_iter([(name, value)])))
+                }),
+        }
+    }
+
+    /// The value as read back from a configuration it is written to.
+    fn check(&self, value: Value) -> Result<Value> {
+        let mut scratch = Code2PromptConfig::default();
+        (self.set)(&mut scratch, value).map_err(|e| anyhow!("Failed to parse value: {}", e))?;
+        Ok((self.get)(&scratch))
+    }
+
+    /// The name of the option in the TUI settings, or its key.
+    pub fn label(&self) -> &'static str {
+        self.setting
+            .as_ref()
+            .map_or(self.key, |setting| setting.label)
+    }
+
+    /// The values a flag or a choice is cycled through, in their serde spelling, with their
+    /// labels. Other options are not cycled.
+    pub fn values(&self) -> Vec<(Value, String)> {
+        match &self.kind {
+            OptionKind::Flag => vec![
+                (Value::Bool(false), "off".to_string()),
+                (Value::Bool(true), "on".to_string()),
+            ],
+            OptionKind::Choice { values, .. } => values(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Position of the value of the configuration among the values of the option.
+    pub fn selected(&self, config: &Code2PromptConfig) -> usize {
+        let value = (self.get)(config);
+        self.values()
+            .iter()
+            .position(|(candidate, _)| *candidate == value)
+            .unwrap_or(0)
+    }
+
+    /// Moves the option to its next value: a flag is toggled, a choice takes the value
+    /// after the current one.
+    pub fn cycle(&self, config: &mut Code2PromptConfig) {
+        let values = self.values();
+        if values.is_empty() {
+            return;
+        }
+        let next = values[(self.selected(config) + 1) % values.len()].0.clone();
+        self.write(config, next);
+    }
+
+    /// Copies the value of the option from another configuration.
+    pub fn copy(&self, from: &Code2PromptConfig, to: &mut Code2PromptConfig) {
+        self.write(to, (self.get)(from));
+    }
+
+    /// Writes a value read from a configuration, which always fits the option.
+    fn write(&self, config: &mut Code2PromptConfig, value: Value) {
+        if let Err(e) = (self.set)(config, value) {
+            error!("Failed to set {}: {}", self.key, e);
+        }
+    }
+
+    /// The value of the option in the config file, none if it is not set.
+    fn read(&self, file: &Value) -> Option<Value> {
+        let value = file.pointer(&format!("/{}", self.key.replace('.', "/")));
+        debug_assert!(
+            value.is_some(),
+            "option {} is not a key of the config file",
+            self.key
+        );
+        value.filter(|value| !value.is_null()).cloned()
+    }
+}
+
+/// The command line: the arguments of [`Cli`] and the flags of [`OPTIONS`].
+pub fn command() -> Command {
+    OPTIONS
+        .iter()
+        .fold(Cli::command(), |command, spec| command.arg(spec.arg()))
+}
+
+/// Parses the command line, keeping the options given on it in `Cli::options`.
+pub fn parse() -> Cli {
+    let matches = command().get_matches();
+    let mut args = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    args.options = given(&matches);
+    args
+}
+
+/// The options given on the command line: the flags turned on, the values given and the
+/// values of the repeated flags gathered.
+fn given(matches: &ArgMatches) -> OptionValues {
+    OPTIONS
+        .iter()
+        .filter_map(|spec| {
+            let value = match spec.kind {
+                OptionKind::Flag => matches.get_flag(spec.key).then_some(Value::Bool(true)),
+                OptionKind::Choice { .. } | OptionKind::Value { .. } => {
+                    matches.get_one::<Value>(spec.key).cloned()
+                }
+                OptionKind::List { .. } => matches.get_many::<Value>(spec.key).map(|lists| {
+                    lists
+                        .filter_map(Value::as_array)
+                        .flatten()
+                        .cloned()
+                        .collect()
+                }),
+                OptionKind::Entries { .. } => matches.get_many::<Value>(spec.key).map(|tables| {
+                    Value::Object(
+                        tables
+                            .filter_map(Value::as_object)
+                            .flatten()
+                            .map(|(name, value)| (name.clone(), value.clone()))
+                            .collect(),
+                    )
+                }),
+            };
+            value.map(|value| (spec.key, value))
+        })
+        .collect()
+}
+
+/// Sets the options of the configuration: the values given on the command line take
+/// precedence over the config file, which takes precedence over the values already in
+/// the configuration. The entries of a table are merged, those of the command line
+/// replacing those of the config file of the same name.
+///
+/// # Arguments
+///
+/// * `config` - The configuration, holding the default values
+/// * `given` - The options given on the command line
+/// * `file` - The config file, if any
+pub fn apply(
+    config: &mut Code2PromptConfig,
+    given: &OptionValues,
+    file: Option<&TomlConfig>,
+) -> Result<()> {
+    let file = file.map(serde_json::to_value).transpose()?;
+    let replaced: Vec<&str> = OPTIONS
+        .iter()
+        .filter(|spec| given.contains_key(spec.key))
+        .flat_map(|spec| match spec.kind {
+            OptionKind::List { replaces, .. } => replaces,
+            _ => &[],
+        })
+        .copied()
+        .collect();
+    for spec in OPTIONS {
+        let from_file = file
+            .as_ref()
+            .filter(|_| !replaced.contains(&spec.key))
+            .and_then(|file| spec.read(file));
+        let value = match (given.get(spec.key), from_file) {
+            (Some(Value::Object(given)), Some(Value::Object(mut entries))) => {
+                entries.extend(given.clone());
+                Some(Value::Object(entries))
+            }
+            (Some(given), _) => Some(given.clone()),
+            (None, from_file) => from_file,
+        };
+        if let Some(value) = value {
+            (spec.set)(config, value.clone())
+                .with_context(|| format!("Invalid value of {}: {}", spec.key, value))?;
+        }
+    }
+    Ok(())
+}
+
+/// Values labeled by their display.
+fn displayed<T: Serialize + Display>(values: &[T]) -> Vec<(Value, String)> {
+    values
+        .iter()
+        .map(|value| (json!(value), value.to_string()))
+        .collect()
+}
+
+/// Values with the given labels.
+fn labeled<T: Serialize>(values: &[(T, &str)]) -> Vec<(Value, String)> {
+    values
+        .iter()
+        .map(|(value, label)| (json!(value), label.to_string()))
+        .collect()
+}
+
+/// Reads a value as text, checked by the option it is written to.
+fn text(text: &str) -> Result<Value> {
+    Ok(Value::String(text.to_string()))
+}
+
+/// Reads a count.
+fn number(text: &str) -> Result<Value> {
+    let number: usize = text
+        .trim()
+        .parse()
+        .with_context(|| format!("Expected a number, got '{}'", text))?;
+    Ok(json!(number))
+}
+
+/// Reads the rows kept from data files, written as `HEAD[,TAIL]`.
+fn data_sample(text: &str) -> Result<Value> {
+    let (head, tail) = text.split_once(',').unwrap_or((text, "0"));
+    Ok(json!({ "head": number(head)?, "tail": number(tail)? }))
+}
+
+/// Reads two branches, written as `FROM,TO`.
+fn branches(text: &str) -> Result<Value> {
+    match text.split(',').map(str::trim).collect::<Vec<_>>()[..] {
+        [from, to] if !from.is_empty() && !to.is_empty() => Ok(json!([from, to])),
+        _ => Err(anyhow!("Expected two branches, as main,feature")),
+    }
+}
+
+/// Reads a single item of a list.
+fn item(text: &str) -> Result<Vec<Value>> {
+    Ok(vec![Value::String(text.to_string())])
+}
+
+/// Reads comma-separated patterns, a pattern with brace expansion being kept whole.
+fn patterns(text: &str) -> Result<Vec<Value>> {
+    if text.contains('{') && text.contains('}') {
+        return item(text);
+    }
+    separated(text)
+}
+
+/// Reads comma-separated values.
+fn separated(text: &str) -> Result<Vec<Value>> {
+    Ok(text
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| Value::String(value.to_string()))
+        .collect())
+}
+
+/// Reads a file whose history is included, written as `PATH[:COUNT]`.
+fn file_history(text: &str) -> Result<(String, Value)> {
+    if let Some((path, count)) = text.rsplit_once(':')
+        && !path.is_empty()
+        && let Ok(count) = count.parse::<usize>()
+    {
+        return Ok((path.to_string(), json!(count)));
+    }
+    Ok((text.to_string(), json!(DEFAULT_FILE_HISTORY_COMMITS)))
+}
+
+/// Reads a language server of an extension, written as `EXT=COMMAND`.
+fn lsp_server(text: &str) -> Result<(String, Value)> {
+    match text.split_once('=') {
+        Some((extension, command)) if !extension.is_empty() && !command.trim().is_empty() => Ok((
+            extension.trim_start_matches('.').to_string(),
+            json!(command.trim()),
+        )),
+        _ => Err(anyhow!("Expected EXT=COMMAND, such as rs=rust-analyzer")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A value differing from the default one, as written on the command line, of each
+    /// option other than the flags and the choices.
+    fn sample(key: &str) -> &'static str {
+        match key {
+            "include_patterns" => "src/**,*.rs",
+            "exclude_patterns" => "target/**",
+            "tree_only_patterns" => "docs/**",
+            "collapse_patterns" => "vendor/**",
+            "packages" => "core",
+            "bazel_targets" => "//services/auth:auth",
+            "tags_file" => "tags",
+            "symbols" => "Session",
+            "file_delimiter" => "xml",
+            "chunk_options.lines" => "40",
+            "chunk_options.overlap" => "5",
+            "file_header" => "===== FILE: {{path}} =====",
+            "file_footer" => "===== END: {{path}} =====",
+            "post_process" => "collapse_blank_lines,footer",
+            "instruction" => "Review the error handling",
+            "placement" => "sandwich",
+            "key_files" => "src/lib.rs",
+            "prompt_cache" => "anthropic",
+            "tree_options.max_depth" => "2",
+            "tree_options.style" => "ascii",
+            "fixture_policy" => "stub",
+            "binary_handling" => "base64",
+            "compression_level" => "signatures",
+            "lsp_servers" => "rs=rust-analyzer",
+            "duplicates" => "report",
+            "duplicate_min_lines" => "8",
+            "data_sample" => "2,1",
+            "tokenizer_model" => "tokenizer.json",
+            "max_tokens" => "1000",
+            "budget_strategy" => "truncate",
+            "summarize_over" => "500",
+            "diff_branches" => "main,feature",
+            "file_history" => "src/lib.rs:3",
+            "diff_include_patterns" => "src/**",
+            "diff_exclude_patterns" => "*.lock",
+            "diff_context_lines" => "1",
+            "diff_style" => "word",
+            "diff_max_tokens" => "300",
+            "log_branches" => "main,feature",
+            "stash" => "stash@{1}",
+            "path_alias" => "{repo}",
+            _ => panic!("no sample value of option {}", key),
+        }
+    }
+
+    /// Each option given on the command line is read back the same from the config file
+    #[test]
+    fn test_options_round_trip_through_config_file() {
+        let defaults = Code2PromptConfig::default();
+        for spec in OPTIONS {
+            let mut line = vec!["code2prompt".to_string(), format!("--{}", spec.long)];
+            match spec.kind {
+                OptionKind::Flag => {}
+                OptionKind::Choice { .. } => {
+                    let (value, _) = spec
+                        .values()
+                        .into_iter()
+                        .find(|(value, _)| *value != (spec.get)(&defaults))
+                        .expect("a choice has several values");
+                    line.push(value.as_str().expect("a choice is text").to_string());
+                }
+                _ => line.push(sample(spec.key).to_string()),
+            }
+            let matches = command()
+                .try_get_matches_from(&line)
+                .unwrap_or_else(|e| panic!("{}: {}", spec.key, e));
+            let given = given(&matches);
+            let mut from_command_line = Code2PromptConfig::default();
+            apply(&mut from_command_line, &given, None).unwrap();
+            let value = (spec.get)(&from_command_line);
+            assert_ne!(value, (spec.get)(&defaults), "{} is not set", spec.key);
+
+            let mut file = serde_json::to_value(TomlConfig::default()).unwrap();
+            *file
+                .pointer_mut(&format!("/{}", spec.key.replace('.', "/")))
+                .unwrap_or_else(|| panic!("{} is not a key of the config file", spec.key)) =
+                given[spec.key].clone();
+            let file: TomlConfig = serde_json::from_value(file).unwrap();
+            let file = TomlConfig::from_toml_str(&file.to_string().unwrap()).unwrap();
+            let mut from_file = Code2PromptConfig::default();
+            apply(&mut from_file, &OptionValues::new(), Some(&file)).unwrap();
+            assert_eq!((spec.get)(&from_file), value, "{}", spec.key);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Pull request description generation.
+//!
+//! Builds a prompt from the diff of the branch against its base, the commits of the
+//! branch and the files it touches. The prompt is written to stdout so that it can be
+//! piped to a model, or sent to the configured LLM command with `--ask`, in which
+//! case the generated description is printed instead.
+
+use anyhow::{Context, Result};
+use code2prompt_core::builtin_templates::BuiltinTemplates;
+use code2prompt_core::template::write_to_file;
+use std::io::Write;
+
+use crate::args::{Cli, PrDescriptionArgs};
+use crate::config;
+use crate::config_loader::load_config;
+use crate::llm::{build_llm_command, query_model};
+
+/// Generates the pull request description prompt, or the description itself with `--ask`.
+pub fn run_pr_description(args: &Cli, pr_args: &PrDescriptionArgs) -> Result<()> {
+    let config_source = load_config(args.quiet)?;
+    let mut session = config::build_session(Some(&config_source), args, false)?;
+
+    // The built-in template is used unless one was given explicitly
+    if args.template.is_none() {
+        let template = BuiltinTemplates::get_template("pr-description")
+            .context("The pr-description template is missing")?;
+        session.config.template_str = template.content.to_string();
+        session.config.template_name = "pr-description".to_string();
+    }
+
+    session.load_pull_request_context(&pr_args.base, &pr_args.head)?;
+    let data = session.build_template_data();
+    let rendered = session.render_prompt(&data)?;
+
+    let output = if pr_args.ask {
+        let llm_command = pr_args
+            .llm_command
+            .as_deref()
+            .or(config_source.config.llm_command.as_deref());
+        let command = build_llm_command(llm_command, pr_args.model.as_deref());
+        let response = query_model(&command, &rendered.prompt)?;
+        format!("{}\n", response.trim())
+    } else {
+        rendered.prompt
+    };
+
+    match args.output_file.as_deref() {
+        Some(path) if path != "-" => write_to_file(path, &output)?,
+        _ => {
+            print!("{}", output);
+            std::io::stdout()
+                .flush()
+                .context("Failed to flush stdout")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! Release notes generation.
+//!
+//! Builds a prompt from the history since a previous release: the commit log, the
+//! pull requests merged in the meantime and the diff of the paths that matter to a
+//! changelog. The prompt is written to stdout, or sent to the configured LLM command
+//! with `--ask`, in which case the generated markdown release notes are printed.
+
+use anyhow::{Context, Result};
+use code2prompt_core::builtin_templates::BuiltinTemplates;
+use code2prompt_core::template::write_to_file;
+use std::io::Write;
+
+use crate::args::{Cli, ReleaseNotesArgs};
+use crate::config;
+use crate::config_loader::load_config;
+use crate::github::{github_token, update_pull_request_titles};
+use crate::llm::{build_llm_command, query_model};
+
+/// Generates the release notes prompt, or the release notes themselves with `--ask`.
+pub fn run_release_notes(args: &Cli, release_args: &ReleaseNotesArgs) -> Result<()> {
+    let config_source = load_config(args.quiet)?;
+    let mut session = config::build_session(Some(&config_source), args, false)?;
+
+    // The built-in template is used unless one was given explicitly
+    if args.template.is_none() {
+        let template = BuiltinTemplates::get_template("release-notes")
+            .context("The release-notes template is missing")?;
+        session.config.template_str = template.content.to_string();
+        session.config.template_name = "release-notes".to_string();
+    }
+
+    session.load_release_context(&release_args.since, &release_args.until)?;
+
+    // Titles on GitHub are more accurate than the ones left in merge commits
+    let repo_path = session.repo_path().to_path_buf();
+    if let Some(token) = github_token(config_source.config.github_token.as_deref())
+        && let Some(pull_requests) = session.data.merged_pull_requests.as_mut()
+        && !pull_requests.is_empty()
+    {
+        update_pull_request_titles(&repo_path, &token, pull_requests);
+    }
+
+    let data = session.build_template_data();
+    let rendered = session.render_prompt(&data)?;
+
+    let output = if release_args.ask {
+        let llm_command = release_args
+            .llm_command
+            .as_deref()
+            .or(config_source.config.llm_command.as_deref());
+        let command = build_llm_command(llm_command, release_args.model.as_deref());
+        let response = query_model(&command, &rendered.prompt)?;
+        format!("{}\n", response.trim())
+    } else {
+        rendered.prompt
+    };
+
+    match args.output_file.as_deref() {
+        Some(path) if path != "-" => write_to_file(path, &output)?,
+        _ => {
+            print!("{}", output);
+            std::io::stdout()
+                .flush()
+                .context("Failed to flush stdout")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,214 @@
+//! Non-interactive TUI snapshots.
+//!
+//! Renders every tab of the TUI once into an off-screen buffer and writes the result
+//! as plain text or ANSI, so layouts can be documented and checked in CI without a
+//! real terminal.
+
+use anyhow::{Context, Result};
+use code2prompt_core::git::get_branches;
+use code2prompt_core::session::Code2PromptSession;
+use ratatui::{Terminal, backend::TestBackend, buffer::Buffer, style::Color, style::Modifier};
+use std::fmt::Write as _;
+use std::path::Path;
+use unicode_width::UnicodeWidthStr;
+
+use crate::model::{Cmd, Message, Model, Tab};
+use crate::tui::{TuiApp, run_analysis};
+use crate::tui_session::load_tui_session;
+use crate::utils::{
+    apply_token_counts, build_file_tree_from_session, count_file_tokens, uncounted_files,
+};
+
+/// Terminal size used when no size is requested.
+pub const DEFAULT_SCREENSHOT_SIZE: (u16, u16) = (120, 40);
+
+/// The tabs in display order, with the label shown in the tab bar.
+const TABS: [(Tab, &str); 6] = [
+    (Tab::FileTree, "1. Selection"),
+    (Tab::Settings, "2. Settings"),
+    (Tab::Statistics, "3. Statistics"),
+    (Tab::Template, "4. Template"),
+    (Tab::PromptOutput, "5. Output"),
+    (Tab::Git, "6. Git"),
+];
+
+/// Render each tab of the TUI once per terminal size and write the snapshots to a file.
+///
+/// The file tree is loaded and the analysis is run beforehand so that the statistics
+/// and output tabs show real content.
+///
+/// # Arguments
+///
+/// * `session` - The session built from the command line
+/// * `restore` - Whether to restore the session saved for the project, as the TUI would
+/// * `path` - The file to write the snapshots to
+/// * `sizes` - The terminal sizes to render, `DEFAULT_SCREENSHOT_SIZE` if empty
+/// * `ansi` - Whether to keep colors and styles as ANSI escape sequences
+///
+/// # Returns
+///
+/// * `Result<()>` - An error if a tab cannot be rendered or the file cannot be written
+pub fn write_screenshot(
+    session: Code2PromptSession,
+    restore: bool,
+    path: &Path,
+    sizes: &[(u16, u16)],
+    ansi: bool,
+) -> Result<()> {
+    let model = prepare_model(session, restore);
+    let sizes = if sizes.is_empty() {
+        &[DEFAULT_SCREENSHOT_SIZE][..]
+    } else {
+        sizes
+    };
+
+    let mut output = String::new();
+    for &(width, height) in sizes {
+        for (tab, label) in TABS {
+            let mut model = model.clone();
+            model.current_tab = tab;
+            let buffer = render(&model, width, height)?;
+
+            let _ = writeln!(output, "=== {} ({}x{}) ===", label, width, height);
+            output.push_str(&buffer_to_string(&buffer, ansi));
+            output.push('\n');
+        }
+    }
+
+    std::fs::write(path, output)
+        .with_context(|| format!("Failed to write screenshot to {}", path.display()))
+}
+
+/// Build the model the way the TUI would after loading files and branches and running
+/// the analysis.
+fn prepare_model(session: Code2PromptSession, restore: bool) -> Model {
+    let mut model = Model::new(session);
+    if restore && let Some(saved) = load_tui_session(&model.session.config.path) {
+        saved.apply(&mut model);
+    }
+
+    match build_file_tree_from_session(&mut model.session) {
+        Ok(tree) => model.file_tree_nodes = tree,
+        Err(e) => model.status_message = format!("Error loading files: {}", e),
+    }
+    // Counted synchronously, where the TUI would count in background
+    let uncounted = uncounted_files(&model.file_tree_nodes);
+    let counts = count_file_tokens(&uncounted, &model.session.config);
+    apply_token_counts(&mut model.file_tree_nodes, &counts);
+
+    let branches = get_branches(model.session.repo_path()).map_err(|e| format!("{:#}", e));
+    let model = model.update(Message::BranchesLoaded(branches)).0;
+
+    let (mut model, cmd) = model.update(Message::RunAnalysis);
+    if let Cmd::RunAnalysis {
+        template_content,
+        user_variables,
+    } = cmd
+    {
+        let message = run_analysis(model.session.clone(), template_content, user_variables);
+        model = model.update(message).0;
+    }
+    model
+}
+
+/// Draw the model into an off-screen buffer of the given size.
+fn render(model: &Model, width: u16, height: u16) -> Result<Buffer> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    terminal.draw(|frame| TuiApp::render_with_model(model, frame))?;
+    Ok(terminal.backend().buffer().clone())
+}
+
+/// Convert a rendered buffer to text, one line per row, optionally with ANSI styling.
+fn buffer_to_string(buffer: &Buffer, ansi: bool) -> String {
+    let area = buffer.area;
+    let mut output = String::new();
+
+    for y in area.top()..area.bottom() {
+        let mut line = String::new();
+        let mut current_style = None;
+        // Wide characters span several cells, the cells they cover are skipped
+        let mut skip = 0;
+
+        for x in area.left()..area.right() {
+            let cell = &buffer[(x, y)];
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            skip = cell.symbol().width().saturating_sub(1);
+
+            if ansi {
+                let style = (cell.fg, cell.bg, cell.modifier);
+                if current_style != Some(style) {
+                    line.push_str(&sgr(cell.fg, cell.bg, cell.modifier));
+                    current_style = Some(style);
+                }
+            }
+            line.push_str(cell.symbol());
+        }
+
+        if ansi {
+            line.push_str("\x1b[0m");
+        } else {
+            line.truncate(line.trim_end().len());
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Build the escape sequence selecting the given colors and modifiers.
+fn sgr(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let mut codes = vec!["0".to_string()];
+
+    for (flag, code) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::SLOW_BLINK, "5"),
+        (Modifier::RAPID_BLINK, "6"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::HIDDEN, "8"),
+        (Modifier::CROSSED_OUT, "9"),
+    ] {
+        if modifier.contains(flag) {
+            codes.push(code.to_string());
+        }
+    }
+    codes.extend(color_code(fg, false));
+    codes.extend(color_code(bg, true));
+
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+/// The SGR parameter for a foreground or background color, `None` for the default color.
+fn color_code(color: Color, background: bool) -> Option<String> {
+    let offset = if background { 10 } else { 0 };
+    let base = match color {
+        Color::Reset => return None,
+        Color::Black => 30,
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+        Color::Blue => 34,
+        Color::Magenta => 35,
+        Color::Cyan => 36,
+        Color::Gray => 37,
+        Color::DarkGray => 90,
+        Color::LightRed => 91,
+        Color::LightGreen => 92,
+        Color::LightYellow => 93,
+        Color::LightBlue => 94,
+        Color::LightMagenta => 95,
+        Color::LightCyan => 96,
+        Color::White => 97,
+        Color::Rgb(r, g, b) => {
+            return Some(format!("{};2;{};{};{}", 38 + offset, r, g, b));
+        }
+        Color::Indexed(index) => return Some(format!("{};5;{}", 38 + offset, index)),
+    };
+    Some((base + offset).to_string())
+}
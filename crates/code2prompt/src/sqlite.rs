@@ -0,0 +1,290 @@
+//! Export of the session data to a SQLite database, and import back.
+//!
+//! The database is written and read with the SQLite library bundled in the binary, the
+//! values being bound to the statements.
+//!
+//! The database holds the files with their metadata and token counts, the history of each
+//! file when it was requested, and the source tree and git information as key/value pairs.
+//! A prompt can be rendered again from it without the codebase, with any template.
+//!
+//! The SQLite library is only built with the `sqlite` feature; without it, both functions
+//! fail with an error saying so.
+
+#[cfg(feature = "sqlite")]
+use anyhow::Context;
+use anyhow::{Result, bail};
+#[cfg(feature = "sqlite")]
+use code2prompt_core::git::FileCommit;
+#[cfg(feature = "sqlite")]
+use code2prompt_core::language::source_stats;
+#[cfg(feature = "sqlite")]
+use code2prompt_core::path::{EntryMetadata, FileEntry};
+use code2prompt_core::session::{Code2PromptSession, RenderedPrompt};
+#[cfg(feature = "sqlite")]
+use rusqlite::{Connection, OpenFlags, params};
+use std::path::Path;
+
+/// Version of the database layout, stored in the `session` table
+#[cfg(feature = "sqlite")]
+const SCHEMA_VERSION: u32 = 1;
+
+#[cfg(feature = "sqlite")]
+const SCHEMA: &str = "
+CREATE TABLE session (key TEXT PRIMARY KEY, value TEXT);
+CREATE TABLE files (
+    position INTEGER PRIMARY KEY,
+    path TEXT NOT NULL,
+    extension TEXT NOT NULL,
+    code TEXT NOT NULL,
+    token_count INTEGER NOT NULL,
+    content_hash TEXT NOT NULL,
+    mod_time INTEGER,
+    is_symlink INTEGER NOT NULL,
+    line_range TEXT
+);
+CREATE TABLE file_commits (
+    path TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    author TEXT NOT NULL,
+    date TEXT NOT NULL,
+    message TEXT NOT NULL,
+    diff TEXT NOT NULL
+);
+CREATE INDEX file_commits_path ON file_commits (path);
+";
+
+/// A row of the `file_commits` table
+#[cfg(feature = "sqlite")]
+struct CommitRow {
+    path: String,
+    /// The commit, until it is attached to its file
+    commit: Option<FileCommit>,
+}
+
+/// Writes the session data to a new SQLite database, replacing the file if it exists.
+///
+/// # Arguments
+///
+/// * `session` - The session, with its codebase and git information loaded
+/// * `rendered` - The prompt rendered from the session, whose token count is stored
+/// * `path` - The database file to write
+///
+/// # Returns
+///
+/// * `Result<()>` - An error if the database cannot be written
+#[cfg(feature = "sqlite")]
+pub fn export_sqlite(
+    session: &Code2PromptSession,
+    rendered: &RenderedPrompt,
+    path: &Path,
+) -> Result<()> {
+    let data = &session.data;
+    if path.exists() {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+    }
+    let mut connection = Connection::open(path)
+        .with_context(|| format!("Failed to create the database {}", path.display()))?;
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHEMA)?;
+
+    let json = |value: &Option<Vec<String>>| {
+        value
+            .as_ref()
+            .map(|list| serde_json::to_string(list).unwrap_or_default())
+    };
+    let entries = [
+        ("schema_version", Some(SCHEMA_VERSION.to_string())),
+        (
+            "code2prompt_version",
+            Some(env!("CARGO_PKG_VERSION").to_string()),
+        ),
+        ("absolute_code_path", data.absolute_code_path.clone()),
+        ("source_tree", data.source_tree.clone()),
+        ("token_count", Some(rendered.token_count.to_string())),
+        ("model_info", Some(rendered.model_info.to_string())),
+        ("git_diff", data.git_diff.clone()),
+        ("git_diff_branch", data.git_diff_branch.clone()),
+        ("git_log_branch", data.git_log_branch.clone()),
+        ("git_stash", data.git_stash.clone()),
+        ("untracked_files", json(&data.untracked_files)),
+        ("recent_commits", json(&data.recent_commits)),
+    ];
+    {
+        let mut insert_entry = transaction.prepare("INSERT INTO session VALUES (?1, ?2)")?;
+        for (key, value) in entries {
+            if let Some(value) = value {
+                insert_entry.execute(params![key, value])?;
+            }
+        }
+
+        let mut insert_file =
+            transaction.prepare("INSERT INTO files VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)")?;
+        let mut insert_commit =
+            transaction.prepare("INSERT INTO file_commits VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+        for (position, file) in data.files.as_deref().unwrap_or_default().iter().enumerate() {
+            insert_file.execute(params![
+                position,
+                file.path,
+                file.extension,
+                file.code,
+                file.token_count,
+                file.content_hash,
+                file.mod_time,
+                file.metadata.is_symlink,
+                file.line_range,
+            ])?;
+            for (position, commit) in file.history.iter().flatten().enumerate() {
+                insert_commit.execute(params![
+                    file.path,
+                    position,
+                    commit.hash,
+                    commit.author,
+                    commit.date,
+                    commit.message,
+                    commit.diff,
+                ])?;
+            }
+        }
+    }
+    transaction
+        .commit()
+        .with_context(|| format!("Failed to write the database {}", path.display()))?;
+    Ok(())
+}
+
+/// Loads the session data stored by `export_sqlite`, in place of the codebase.
+///
+/// The files, the source tree and the git information are restored. The analyses not
+/// stored in the database, such as the documentation coverage, are left out.
+///
+/// # Arguments
+///
+/// * `session` - The session to fill
+/// * `path` - The database file to read
+///
+/// # Returns
+///
+/// * `Result<()>` - An error if the database cannot be read or was not written by code2prompt
+#[cfg(feature = "sqlite")]
+pub fn import_sqlite(session: &mut Code2PromptSession, path: &Path) -> Result<()> {
+    if !path.is_file() {
+        bail!("No database at {}", path.display());
+    }
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open the database {}", path.display()))?;
+    let entries: Vec<(String, Option<String>)> = connection
+        .prepare("SELECT key, value FROM session")
+        .and_then(|mut statement| {
+            statement
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect()
+        })
+        .with_context(|| format!("{} is not a code2prompt database", path.display()))?;
+    let value = |key: &str| {
+        entries
+            .iter()
+            .find(|(entry, _)| entry == key)
+            .and_then(|(_, value)| value.clone())
+    };
+    let list = |key: &str| value(key).and_then(|list| serde_json::from_str(&list).ok());
+    match value("schema_version").and_then(|version| version.parse::<u32>().ok()) {
+        Some(version) if version <= SCHEMA_VERSION => {}
+        Some(version) => bail!(
+            "{} uses a newer database layout ({}), upgrade code2prompt to read it",
+            path.display(),
+            version
+        ),
+        None => bail!("{} is not a code2prompt database", path.display()),
+    }
+
+    let mut commits: Vec<CommitRow> = connection
+        .prepare(
+            "SELECT path, hash, author, date, message, diff FROM file_commits \
+             ORDER BY path, position",
+        )?
+        .query_map([], |row| {
+            Ok(CommitRow {
+                path: row.get(0)?,
+                commit: Some(FileCommit {
+                    hash: row.get(1)?,
+                    author: row.get(2)?,
+                    date: row.get(3)?,
+                    message: row.get(4)?,
+                    diff: row.get(5)?,
+                }),
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    let files: Vec<FileEntry> = connection
+        .prepare(
+            "SELECT path, extension, code, token_count, content_hash, mod_time, is_symlink, \
+             line_range FROM files ORDER BY position",
+        )?
+        .query_map([], |row| {
+            let path: String = row.get(0)?;
+            Ok(FileEntry {
+                // The line counts are not stored, only what the path tells
+                stats: source_stats(Path::new(&path), ""),
+                path,
+                extension: row.get(1)?,
+                code: row.get(2)?,
+                token_count: row.get(3)?,
+                content_hash: row.get(4)?,
+                metadata: EntryMetadata {
+                    is_dir: false,
+                    is_symlink: row.get(6)?,
+                },
+                mod_time: row.get(5)?,
+                history: None,
+                line_range: row.get(7)?,
+                symbols: None,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    let files = files
+        .into_iter()
+        .map(|mut file| {
+            let history: Vec<FileCommit> = commits
+                .iter_mut()
+                .filter(|row| row.path == file.path)
+                .filter_map(|row| row.commit.take())
+                .collect();
+            file.history = (!history.is_empty()).then_some(history);
+            file
+        })
+        .collect();
+
+    let data = &mut session.data;
+    data.absolute_code_path = value("absolute_code_path");
+    data.source_tree = value("source_tree");
+    data.files = Some(files);
+    data.git_diff = value("git_diff");
+    data.git_diff_branch = value("git_diff_branch");
+    data.git_log_branch = value("git_log_branch");
+    data.git_stash = value("git_stash");
+    data.untracked_files = list("untracked_files");
+    data.recent_commits = list("recent_commits");
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn export_sqlite(
+    _session: &Code2PromptSession,
+    _rendered: &RenderedPrompt,
+    path: &Path,
+) -> Result<()> {
+    bail!(
+        "Cannot write {}: code2prompt was built without the sqlite feature",
+        path.display()
+    )
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn import_sqlite(_session: &mut Code2PromptSession, path: &Path) -> Result<()> {
+    bail!(
+        "Cannot read {}: code2prompt was built without the sqlite feature",
+        path.display()
+    )
+}
@@ -0,0 +1,160 @@
+//! Line protocol for editor integrations.
+//!
+//! With `--stdin-commands`, code2prompt keeps its session open and reads one command
+//! per line on stdin, which can be a named pipe, answering each on stdout. A response
+//! starts with `ok` or `error`; responses carrying a body give its number of lines,
+//! which follow:
+//!
+//! ```text
+//! toggle src/a.rs        ok excluded src/a.rs
+//! include src/b.rs       ok included src/b.rs
+//! exclude src/c.rs       ok excluded src/c.rs
+//! reset                  ok
+//! files                  ok 2, then the selected files
+//! plan                   ok 9, then the inclusion plan as quickfix lines
+//! tokens                 ok 1834
+//! generate               ok 120, then the prompt
+//! quit                   ok
+//! ```
+
+use anyhow::{Context, Result};
+use code2prompt_core::inclusion_plan::format_quickfix;
+use code2prompt_core::session::Code2PromptSession;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::args::Cli;
+use crate::config;
+use crate::config_loader::load_config;
+
+/// Runs the line protocol on stdin and stdout until `quit` or the end of the input.
+pub fn run_stdin_commands(args: &Cli) -> Result<()> {
+    let config_source = load_config(true)?;
+    let mut session = config::build_session(Some(&config_source), args, false)?;
+    session.resolve_remote()?;
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    run_commands(&mut session, stdin.lock(), stdout.lock())
+}
+
+/// Answers the commands read from `input` on `output`, flushing after each response.
+///
+/// # Arguments
+///
+/// * `session` - The session the commands act on
+/// * `input` - The commands, one per line
+/// * `output` - Where the responses are written
+fn run_commands<R: BufRead, W: Write>(
+    session: &mut Code2PromptSession,
+    input: R,
+    mut output: W,
+) -> Result<()> {
+    for line in input.lines() {
+        let line = line.context("Failed to read a command")?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = argument.trim();
+
+        match answer(session, command, argument) {
+            Ok(Response::Quit) => {
+                writeln!(output, "ok")?;
+                output.flush()?;
+                break;
+            }
+            Ok(Response::Status(status)) if status.is_empty() => writeln!(output, "ok")?,
+            Ok(Response::Status(status)) => writeln!(output, "ok {}", status)?,
+            Ok(Response::Body(body)) => {
+                let lines: Vec<&str> = body.lines().collect();
+                writeln!(output, "ok {}", lines.len())?;
+                for line in lines {
+                    writeln!(output, "{}", line)?;
+                }
+            }
+            // Errors stay on one line, so that they cannot be read as a body
+            Err(e) => writeln!(output, "error {}", format!("{:#}", e).replace('\n', " "))?,
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// The response to a command
+enum Response {
+    /// A single line following `ok`, if any
+    Status(String),
+    /// Lines following `ok` and their count
+    Body(String),
+    /// The end of the session
+    Quit,
+}
+
+/// Runs a command on the session.
+fn answer(session: &mut Code2PromptSession, command: &str, argument: &str) -> Result<Response> {
+    let root = session
+        .config
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| session.config.path.clone());
+    // Absolute paths, such as those of the quickfix lines, are made relative to the root
+    let path = || -> Result<PathBuf> {
+        if argument.is_empty() {
+            anyhow::bail!("{} expects a file path", command);
+        }
+        let path = Path::new(argument);
+        Ok(path.strip_prefix(&root).unwrap_or(path).to_path_buf())
+    };
+    let inclusion = |session: &mut Code2PromptSession, path: PathBuf| {
+        let state = if session.is_file_selected(&path) {
+            "included"
+        } else {
+            "excluded"
+        };
+        Response::Status(format!("{} {}", state, path.display()))
+    };
+
+    match command {
+        "toggle" => {
+            let path = path()?;
+            session.toggle_file_selection(path.clone());
+            Ok(inclusion(session, path))
+        }
+        "include" => {
+            let path = path()?;
+            session.select_file(path.clone());
+            Ok(inclusion(session, path))
+        }
+        "exclude" => {
+            let path = path()?;
+            session.deselect_file(path.clone());
+            Ok(inclusion(session, path))
+        }
+        "reset" => {
+            session.clear_user_actions();
+            Ok(Response::Status(String::new()))
+        }
+        "files" => {
+            session.load_codebase()?;
+            let files = session.data.files.as_deref().unwrap_or_default();
+            Ok(Response::Body(
+                files
+                    .iter()
+                    .map(|file| format!("{}\n", file.path))
+                    .collect(),
+            ))
+        }
+        "plan" => {
+            let plan = session.inclusion_plan()?;
+            Ok(Response::Body(format_quickfix(&plan, &root)))
+        }
+        "tokens" => {
+            let rendered = session.generate_prompt()?;
+            Ok(Response::Status(rendered.token_count.to_string()))
+        }
+        "generate" => Ok(Response::Body(session.generate_prompt()?.prompt)),
+        "quit" => Ok(Response::Quit),
+        _ => anyhow::bail!("Unknown command: {}", command),
+    }
+}
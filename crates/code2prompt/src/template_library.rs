@@ -0,0 +1,182 @@
+//! Library of the templates saved by the user.
+//!
+//! Templates are read from the user library, `code2prompt/templates` in the config
+//! directory (`~/.config/code2prompt/templates` on Linux), from the `.code2prompt/templates`
+//! directory of the project and, for compatibility, from `templates` in the working
+//! directory. Their name and description come from the front-matter comment of the
+//! template when it declares them.
+
+use anyhow::{Context, Result, bail};
+use code2prompt_core::template::parse_front_matter;
+use std::path::{Path, PathBuf};
+
+/// Extensions of the files read as templates
+const TEMPLATE_EXTENSIONS: [&str; 4] = ["hbs", "handlebars", "md", "tmpl"];
+
+/// A template of the library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryTemplate {
+    /// The front-matter name, or the file name without extension
+    pub name: String,
+    pub path: PathBuf,
+    pub description: Option<String>,
+    /// Whether the template comes from the project rather than the user library
+    pub project: bool,
+}
+
+/// Directory of the user template library, if the platform has a config directory.
+pub fn user_templates_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|cfg| cfg.join("code2prompt").join("templates"))
+}
+
+/// Directory of the templates of a project.
+pub fn project_templates_dir(project_root: &Path) -> PathBuf {
+    project_root.join(".code2prompt").join("templates")
+}
+
+/// Lists the templates of the library, sorted by name.
+///
+/// # Arguments
+///
+/// * `project_root` - The project whose `.code2prompt/templates` are listed too, if any
+///
+/// # Returns
+///
+/// * `Result<Vec<LibraryTemplate>>` - The templates, or an error if a directory cannot be read
+pub fn load_all_templates(project_root: Option<&Path>) -> Result<Vec<LibraryTemplate>> {
+    let mut roots = Vec::new();
+    if let Some(root) = project_root {
+        roots.push((project_templates_dir(root), true));
+    }
+    roots.push((std::env::current_dir()?.join("templates"), false));
+    if let Some(dir) = user_templates_dir() {
+        roots.push((dir, false));
+    }
+
+    let mut templates: Vec<LibraryTemplate> = Vec::new();
+    for (root, project) in roots {
+        if !root.exists() {
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(&root).min_depth(1).max_depth(2) {
+            let entry = entry?;
+            let path = entry.path();
+            if !path.is_file() || !is_template(path) {
+                continue;
+            }
+            let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+            // The same directory can be reached from several roots
+            if templates.iter().any(|template| template.path == path) {
+                continue;
+            }
+            templates.push(read_template(path, project));
+        }
+    }
+
+    templates.sort_by(|a, b| a.name.cmp(&b.name).then(a.path.cmp(&b.path)));
+    Ok(templates)
+}
+
+/// Finds a template of the library by name, matching the front-matter name or the
+/// file name without extension. Project templates take precedence.
+///
+/// # Arguments
+///
+/// * `name` - The name of the template
+/// * `project_root` - The project whose templates are searched too, if any
+///
+/// # Returns
+///
+/// * `Result<Option<LibraryTemplate>>` - The template, if one has that name
+pub fn find_template(name: &str, project_root: Option<&Path>) -> Result<Option<LibraryTemplate>> {
+    let matches = |template: &LibraryTemplate| {
+        template.name == name
+            || template.path.file_stem().and_then(|stem| stem.to_str()) == Some(name)
+    };
+    let mut templates = load_all_templates(project_root)?;
+    templates.sort_by_key(|template| !template.project);
+    Ok(templates.into_iter().find(matches))
+}
+
+/// Saves a template in the user library, replacing the template of the same name.
+///
+/// # Arguments
+///
+/// * `name` - The name of the template, used as its file name
+/// * `content` - The template content
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - The path of the saved template
+pub fn save_template(name: &str, content: &str) -> Result<PathBuf> {
+    let dir = match user_templates_dir() {
+        Some(dir) => dir,
+        // Fallback to current directory if config_dir not available
+        None => std::env::current_dir()?.join("templates"),
+    };
+    save_template_to(&dir, name, content)
+}
+
+/// Saves a template in a directory, as `<name>.hbs` unless the name has a template
+/// extension.
+///
+/// # Arguments
+///
+/// * `dir` - The directory of the library
+/// * `name` - The name of the template
+/// * `content` - The template content
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - The path of the saved template, or an error if the name is empty
+pub fn save_template_to(dir: &Path, name: &str, content: &str) -> Result<PathBuf> {
+    let file_name: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let file_name = file_name.trim_matches(|c| c == '-' || c == '.');
+    if file_name.is_empty() {
+        bail!("The template name is empty");
+    }
+
+    let mut path = dir.join(file_name);
+    if !is_template(&path) {
+        path.set_extension("hbs");
+    }
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    std::fs::write(&path, content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn is_template(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| TEMPLATE_EXTENSIONS.contains(&extension))
+}
+
+/// Reads the name and description of a template from its front-matter.
+fn read_template(path: PathBuf, project: bool) -> LibraryTemplate {
+    let front_matter = std::fs::read_to_string(&path)
+        .map(|content| parse_front_matter(&content))
+        .unwrap_or_default();
+    let name = front_matter.name.unwrap_or_else(|| {
+        path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("template")
+            .to_string()
+    });
+    LibraryTemplate {
+        name,
+        path,
+        description: front_matter.description,
+        project,
+    }
+}
@@ -0,0 +1,65 @@
+//! Onboarding tour prompt generation.
+//!
+//! Orders the files of the codebase as a learning path, entry points first, then the
+//! core modules after the modules they depend on, the supporting files and the tests,
+//! with an introduction to each section. The prompt asks to explain the codebase to a
+//! new hire following that path.
+
+use anyhow::{Context, Result};
+use code2prompt_core::builtin_templates::BuiltinTemplates;
+use code2prompt_core::template::write_to_file;
+use colored::*;
+use std::io::Write;
+
+use crate::args::Cli;
+use crate::config;
+use crate::config_loader::load_config;
+
+/// Generates the onboarding tour prompt.
+pub fn run_tour(args: &Cli) -> Result<()> {
+    let config_source = load_config(args.quiet)?;
+    let mut session = config::build_session(Some(&config_source), args, false)?;
+
+    // The built-in template is used unless one was given explicitly
+    if args.template.is_none() {
+        let template = BuiltinTemplates::get_template("onboarding-tour")
+            .context("The onboarding-tour template is missing")?;
+        session.config.template_str = template.content.to_string();
+        session.config.template_name = "onboarding-tour".to_string();
+    }
+
+    session.load_codebase()?;
+    session.load_onboarding_tour()?;
+
+    let data = session.build_template_data();
+    let rendered = session.render_prompt(&data)?;
+
+    if !args.quiet {
+        let tour = session
+            .data
+            .onboarding_tour
+            .as_ref()
+            .context("The onboarding tour is missing")?;
+        for section in &tour.sections {
+            eprintln!(
+                "{}{}{} {}: {} files",
+                "[".bold().white(),
+                "i".bold().blue(),
+                "]".bold().white(),
+                section.title,
+                section.stops.len()
+            );
+        }
+    }
+
+    match args.output_file.as_deref() {
+        Some(path) if path != "-" => write_to_file(path, &rendered.prompt)?,
+        _ => {
+            print!("{}", rendered.prompt);
+            std::io::stdout()
+                .flush()
+                .context("Failed to flush stdout")?;
+        }
+    }
+    Ok(())
+}
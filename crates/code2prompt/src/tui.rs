@@ -6,29 +6,50 @@
 //! file tree browsing, real-time analysis, and clipboard integration.
 
 use anyhow::Result;
+use code2prompt_core::git::get_branches;
+use code2prompt_core::progress::{ProgressEvent, ProgressListener};
 use code2prompt_core::session::Code2PromptSession;
+use code2prompt_core::tokenizer::TokenCountMode;
 use crossterm::{
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
+use log::warn;
 use ratatui::{
-    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    crossterm::event::{KeyCode, KeyEvent},
     prelude::*,
     widgets::*,
 };
 use std::io::{Stdout, stdout};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+use crate::args::Cli;
 use crate::clipboard::copy_to_clipboard;
+use crate::config_watcher::ConfigWatcher;
+use crate::crash_report::{
+    install_panic_hook, record_message, record_model_summary, remove_panic_hook,
+};
+use crate::model::keybindings::KeyContext;
+use crate::model::keymap::{Action, load_keymap};
 use crate::model::{
-    AnalysisResults, Cmd, FileTreeInputMode, Message, Model, StatisticsView, Tab, TemplateState,
+    AnalysisProgress, AnalysisResults, Cmd, FileTreeInputMode, Message, Model, PatternKind,
+    RESIZE_STEP, StatisticsView, Tab, TemplateState, Toast, TokenDistribution,
+    diff_exclude_pattern,
     template::{FocusMode, TemplateFocus, VariableCategory},
 };
+use crate::template_library::save_template;
 use crate::token_map::generate_token_map_with_limit;
-use crate::utils::{save_template_to_custom_dir, save_to_file};
+use crate::tui_session::{TuiSession, load_tui_session, save_tui_session};
+use crate::tui_state::{load_tui_state, update_tui_state};
+use crate::utils::{count_file_tokens, format_number, save_to_file, uncounted_files};
+use crate::view::layout::{Breakpoint, COMPACT_WIDTH, is_too_small};
 use crate::widgets::{
-    FileSelectionWidget, OutputWidget, SettingsWidget, StatisticsByExtensionWidget,
-    StatisticsOverviewWidget, StatisticsTokenMapWidget, TemplateWidget,
+    FileSelectionWidget, GitWidget, HelpWidget, OutputWidget, SettingsWidget,
+    StatisticsByExtensionWidget, StatisticsChartWidget, StatisticsDeltaWidget,
+    StatisticsOverviewWidget, StatisticsTokenMapWidget, TemplateWidget, ToastWidget,
+    TooSmallWidget,
 };
 
 use crate::utils::build_file_tree_from_session;
@@ -38,6 +59,7 @@ pub struct TuiApp {
     terminal: Terminal<CrosstermBackend<Stdout>>,
     message_tx: mpsc::UnboundedSender<Message>,
     message_rx: mpsc::UnboundedReceiver<Message>,
+    config_watcher: Option<ConfigWatcher>,
 }
 
 impl TuiApp {
@@ -46,17 +68,44 @@ impl TuiApp {
     /// Initializes the terminal and sets up the application state from the provided session.
     /// The initial file tree is requested via a `RefreshFileTree` message in `run()`.
     ///
+    /// The config files are watched so that editing them updates the settings.
+    ///
+    /// The session saved for the project on the previous exit is restored, unless
+    /// `--fresh` is given, and the keys are remapped from the keymap file.
+    ///
     /// Returns an error if the terminal cannot be initialized.
-    pub fn new(session: Code2PromptSession) -> Result<Self> {
+    pub fn new(session: Code2PromptSession, args: &Cli) -> Result<Self> {
         let terminal = init_terminal()?;
         let (message_tx, message_rx) = mpsc::unbounded_channel();
-        let model = Model::new(session);
+        let mut model = Model::new(session);
+        model.layout = load_tui_state().layout;
+        let saved = load_tui_session(&model.session.config.path);
+        // The token history survives --fresh, so that the trend covers the past runs
+        if let Some(saved) = &saved {
+            model.statistics.token_history = saved.token_history.clone();
+        }
+        if !args.fresh
+            && let Some(saved) = saved
+        {
+            saved.apply(&mut model);
+            model.toast = Some(Toast::new(
+                "Restored the previous session, --fresh to start over".to_string(),
+                false,
+            ));
+        }
+        // An invalid keymap falls back to the default keys, with the error shown
+        match load_keymap() {
+            Ok(keymap) => model.keymap = Arc::new(keymap),
+            Err(e) => model.toast = Some(Toast::new(format!("{:#}", e), true)),
+        }
+        let config_watcher = ConfigWatcher::new(args.clone()).ok();
 
         Ok(Self {
             model,
             terminal,
             message_tx,
             message_rx,
+            config_watcher,
         })
     }
 
@@ -99,6 +148,14 @@ impl TuiApp {
                 self.handle_message(message)?;
             }
 
+            // Pick up edits of the config files
+            if let Some(reloaded) = self.config_watcher.as_mut().and_then(ConfigWatcher::poll) {
+                self.handle_message(match reloaded {
+                    Ok(config) => Message::ConfigReloaded(Box::new(config)),
+                    Err(e) => Message::ConfigReloadFailed(format!("{:#}", e)),
+                })?;
+            }
+
             // Render the UI
             let model = self.model.clone();
             self.terminal.draw(|frame| {
@@ -127,21 +184,26 @@ impl TuiApp {
     /// * `model` - The current application state model
     /// * `frame` - The frame to render the UI components onto
     ///
-    fn render_with_model(model: &Model, frame: &mut Frame) {
+    pub(crate) fn render_with_model(model: &Model, frame: &mut Frame) {
         let area = frame.area();
+        if is_too_small(model.current_tab, area) {
+            frame.render_widget(TooSmallWidget::new(model.current_tab), area);
+            return;
+        }
 
-        // ~~~ Main layout ~~~
+        // ~~~ Main layout, with single-line bars on small terminals ~~~
+        let breakpoint = Breakpoint::for_area(area);
         let main_layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(3), // Tab bar
-                Constraint::Min(0),    // Content
-                Constraint::Length(3), // Status bar
+                Constraint::Length(breakpoint.chrome_height()), // Tab bar
+                Constraint::Min(0),                             // Content
+                Constraint::Length(breakpoint.chrome_height()), // Status bar
             ])
             .split(area);
 
         // Tab bar
-        Self::render_tab_bar_static(model, frame, main_layout[0]);
+        Self::render_tab_bar_static(model, frame, main_layout[0], breakpoint);
 
         // Current tab content
         match model.current_tab {
@@ -170,6 +232,14 @@ impl TuiApp {
                     let mut state = ();
                     frame.render_stateful_widget(widget, main_layout[1], &mut state);
                 }
+                StatisticsView::Chart => {
+                    let widget = StatisticsChartWidget::new(model);
+                    frame.render_widget(widget, main_layout[1]);
+                }
+                StatisticsView::Delta => {
+                    let widget = StatisticsDeltaWidget::new(model);
+                    frame.render_widget(widget, main_layout[1]);
+                }
             },
             Tab::Template => {
                 let widget = TemplateWidget::new(model);
@@ -181,10 +251,25 @@ impl TuiApp {
                 let mut state = ();
                 frame.render_stateful_widget(widget, main_layout[1], &mut state);
             }
+            Tab::Git => {
+                let widget = GitWidget::new(model);
+                let mut state = ();
+                frame.render_stateful_widget(widget, main_layout[1], &mut state);
+            }
         }
 
         // Status bar
-        Self::render_status_bar_static(model, frame, main_layout[2]);
+        Self::render_status_bar_static(model, frame, main_layout[2], breakpoint);
+
+        // Notifications, over the current tab
+        if let Some(toast) = model.toast.as_ref().filter(|toast| toast.is_visible()) {
+            frame.render_widget(ToastWidget::new(toast), area);
+        }
+
+        // Help overlay, over everything else
+        if model.show_help {
+            frame.render_widget(HelpWidget::new(model), area);
+        }
     }
 
     /// Handle a key event and return an optional message.
@@ -201,6 +286,23 @@ impl TuiApp {
     /// * `Option<Message>` - An optional message to be processed by the main loop.
     ///   
     fn handle_key_event(&self, key: KeyEvent) -> Option<Message> {
+        // The help overlay captures all keys until it is closed
+        if self.model.show_help {
+            let toggle =
+                self.model.keymap.action(KeyContext::Global, key) == Some(Action::ToggleHelp);
+            return match key.code {
+                KeyCode::Char('?') | KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                    Some(Message::ToggleHelp)
+                }
+                _ if toggle => Some(Message::ToggleHelp),
+                KeyCode::Up => Some(Message::ScrollHelp(-1)),
+                KeyCode::Down => Some(Message::ScrollHelp(1)),
+                KeyCode::PageUp => Some(Message::ScrollHelp(-10)),
+                KeyCode::PageDown => Some(Message::ScrollHelp(10)),
+                _ => None,
+            };
+        }
+
         // Check if we're in search mode first - this takes priority over global shortcuts
         if self.model.file_tree_input_mode == FileTreeInputMode::Search
             && self.model.current_tab == Tab::FileTree
@@ -208,6 +310,28 @@ impl TuiApp {
             return self.handle_file_tree_keys(key);
         }
 
+        if self.model.settings.search_active && self.model.current_tab == Tab::Settings {
+            return self.handle_settings_keys(key);
+        }
+
+        // Esc clears the output search before it quits
+        let output = &self.model.prompt_output;
+        if self.model.current_tab == Tab::PromptOutput
+            && (output.search_active
+                || (key.code == KeyCode::Esc && !output.search_query.is_empty()))
+        {
+            return self.handle_prompt_output_keys(key);
+        }
+
+        // The pattern editor takes the typed pattern, and Esc closes it instead of quitting
+        let patterns = &self.model.settings.patterns;
+        if self.model.current_tab == Tab::Settings
+            && patterns.active
+            && (patterns.is_typing() || key.code == KeyCode::Esc)
+        {
+            return self.handle_settings_keys(key);
+        }
+
         // Check if we're in template editing mode - ESC should exit editing mode, not quit app
         if self.model.current_tab == Tab::Template && self.model.template.is_in_editing_mode() {
             if key.code == KeyCode::Esc {
@@ -217,36 +341,40 @@ impl TuiApp {
             return self.handle_template_keys(key);
         }
 
-        // Global shortcuts (only when not in search mode or template editing mode)
-        match key.code {
-            KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                return Some(Message::Quit);
-            }
-            KeyCode::Esc => return Some(Message::Quit),
-            KeyCode::Char('1') => return Some(Message::SwitchTab(Tab::FileTree)),
-            KeyCode::Char('2') => return Some(Message::SwitchTab(Tab::Settings)),
-            KeyCode::Char('3') => return Some(Message::SwitchTab(Tab::Statistics)),
-            KeyCode::Char('4') => return Some(Message::SwitchTab(Tab::Template)),
-            KeyCode::Char('5') => return Some(Message::SwitchTab(Tab::PromptOutput)),
-            KeyCode::Tab if !key.modifiers.contains(KeyModifiers::SHIFT) => {
-                // Cycle through tabs: Selection -> Settings -> Statistics -> Template -> Output -> Selection
+        // Global shortcuts (only when not in search mode or template editing mode). Running
+        // the analysis is resolved by the tabs, after their own actions.
+        match self.model.keymap.action(KeyContext::Global, key) {
+            Some(Action::Quit) => return Some(Message::Quit),
+            Some(Action::Redo) => return Some(Message::Redo),
+            Some(Action::Undo) => return Some(Message::Undo),
+            Some(Action::ToggleHelp) => return Some(Message::ToggleHelp),
+            Some(Action::SwitchToSelection) => return Some(Message::SwitchTab(Tab::FileTree)),
+            Some(Action::SwitchToSettings) => return Some(Message::SwitchTab(Tab::Settings)),
+            Some(Action::SwitchToStatistics) => return Some(Message::SwitchTab(Tab::Statistics)),
+            Some(Action::SwitchToTemplate) => return Some(Message::SwitchTab(Tab::Template)),
+            Some(Action::SwitchToOutput) => return Some(Message::SwitchTab(Tab::PromptOutput)),
+            Some(Action::SwitchToGit) => return Some(Message::SwitchTab(Tab::Git)),
+            Some(Action::NextTab) => {
+                // Cycle through tabs: Selection -> Settings -> Statistics -> Template -> Output -> Git -> Selection
                 let next_tab = match self.model.current_tab {
                     Tab::FileTree => Tab::Settings,
                     Tab::Settings => Tab::Statistics,
                     Tab::Statistics => Tab::Template,
                     Tab::Template => Tab::PromptOutput,
-                    Tab::PromptOutput => Tab::FileTree,
+                    Tab::PromptOutput => Tab::Git,
+                    Tab::Git => Tab::FileTree,
                 };
                 return Some(Message::SwitchTab(next_tab));
             }
-            KeyCode::BackTab | KeyCode::Tab if key.modifiers.contains(KeyModifiers::SHIFT) => {
-                // Cycle through tabs in reverse: Selection <- Settings <- Statistics <- Template <- Output <- Selection
+            Some(Action::PreviousTab) => {
+                // Cycle through tabs in reverse: Selection <- Settings <- Statistics <- Template <- Output <- Git <- Selection
                 let prev_tab = match self.model.current_tab {
-                    Tab::FileTree => Tab::PromptOutput,
+                    Tab::FileTree => Tab::Git,
                     Tab::Settings => Tab::FileTree,
                     Tab::Statistics => Tab::Settings,
                     Tab::Template => Tab::Statistics,
                     Tab::PromptOutput => Tab::Template,
+                    Tab::Git => Tab::PromptOutput,
                 };
                 return Some(Message::SwitchTab(prev_tab));
             }
@@ -260,13 +388,29 @@ impl TuiApp {
             Tab::Statistics => self.handle_statistics_keys(key),
             Tab::Template => self.handle_template_keys(key),
             Tab::PromptOutput => self.handle_prompt_output_keys(key),
+            Tab::Git => self.handle_git_keys(key),
         }
     }
 
+    /// Resolve the action of a tab bound to a key, falling back to running the analysis.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The context of the tab and mode
+    /// * `key` - The key event
+    fn tab_action(&self, context: KeyContext, key: KeyEvent) -> Option<Action> {
+        let keymap = &self.model.keymap;
+        keymap.action(context, key).or_else(|| {
+            keymap
+                .action(KeyContext::Global, key)
+                .filter(|&action| action == Action::RunAnalysis)
+        })
+    }
+
     fn handle_file_tree_keys(&self, key: KeyEvent) -> Option<Message> {
         // Pure logic in TUI - no direct widget calls (Elm/Redux pattern)
         if self.model.file_tree_input_mode == FileTreeInputMode::Search {
-            match key.code {
+            return match key.code {
                 KeyCode::Esc => Some(Message::ExitSearchMode),
                 KeyCode::Enter => {
                     // Apply search and exit search mode
@@ -283,44 +427,124 @@ impl TuiApp {
                     Some(Message::UpdateSearchQuery(query))
                 }
                 _ => None,
-            }
-        } else {
-            // Normal navigation mode
-            match key.code {
-                KeyCode::Up => Some(Message::MoveTreeCursor(-1)),
-                KeyCode::Down => Some(Message::MoveTreeCursor(1)),
-                KeyCode::PageUp => Some(Message::MoveTreeCursor(-10)),
-                KeyCode::PageDown => Some(Message::MoveTreeCursor(10)),
-                KeyCode::Home => Some(Message::MoveTreeCursor(-9999)),
-                KeyCode::End => Some(Message::MoveTreeCursor(9999)),
-                KeyCode::Char(' ') => Some(Message::ToggleFileSelection(self.model.tree_cursor)),
-                KeyCode::Enter => Some(Message::RunAnalysis),
-                KeyCode::Right => Some(Message::ExpandDirectory(self.model.tree_cursor)),
-                KeyCode::Left => Some(Message::CollapseDirectory(self.model.tree_cursor)),
-                KeyCode::Char('/') => Some(Message::EnterSearchMode),
-                KeyCode::Char('s') | KeyCode::Char('S') => Some(Message::EnterSearchMode),
-                KeyCode::Char('r') | KeyCode::Char('R') => Some(Message::RefreshFileTree),
-                _ => None,
-            }
+            };
+        }
+
+        // Normal navigation mode
+        let cursor = self.model.tree_cursor;
+        match self.tab_action(KeyContext::FileTree, key) {
+            Some(Action::ToggleSelect) => return Some(Message::ToggleFileSelection(cursor)),
+            Some(Action::CycleInclusion) => return Some(Message::CycleFileInclusion(cursor)),
+            Some(Action::SearchFiles) => return Some(Message::EnterSearchMode),
+            Some(Action::RefreshTree) => return Some(Message::RefreshFileTree),
+            Some(Action::RunAnalysis) => return Some(Message::RunAnalysis),
+            _ => {}
+        }
+        match key.code {
+            KeyCode::Up => Some(Message::MoveTreeCursor(-1)),
+            KeyCode::Down => Some(Message::MoveTreeCursor(1)),
+            KeyCode::PageUp => Some(Message::MoveTreeCursor(-10)),
+            KeyCode::PageDown => Some(Message::MoveTreeCursor(10)),
+            KeyCode::Home => Some(Message::MoveTreeCursor(-9999)),
+            KeyCode::End => Some(Message::MoveTreeCursor(9999)),
+            KeyCode::Right => Some(Message::ExpandDirectory(cursor)),
+            KeyCode::Left => Some(Message::CollapseDirectory(cursor)),
+            _ => None,
         }
     }
 
     fn handle_settings_keys(&self, key: KeyEvent) -> Option<Message> {
+        let cursor = self.model.settings.settings_cursor;
+        if self.model.settings.search_active {
+            return match key.code {
+                KeyCode::Esc => Some(Message::ExitSettingsSearch),
+                KeyCode::Up => Some(Message::MoveSettingsCursor(-1)),
+                KeyCode::Down => Some(Message::MoveSettingsCursor(1)),
+                // Quick toggle of the selected match; cycling also toggles booleans
+                KeyCode::Enter => Some(Message::CycleSetting(cursor)),
+                KeyCode::Backspace => {
+                    let mut query = self.model.settings.search_query.clone();
+                    query.pop();
+                    Some(Message::UpdateSettingsSearch(query))
+                }
+                KeyCode::Char(c) => {
+                    let mut query = self.model.settings.search_query.clone();
+                    query.push(c);
+                    Some(Message::UpdateSettingsSearch(query))
+                }
+                _ => None,
+            };
+        }
+
+        let patterns = &self.model.settings.patterns;
+        if let Some((_, input)) = &patterns.input {
+            return match key.code {
+                KeyCode::Esc => Some(Message::CancelPatternInput),
+                KeyCode::Enter => Some(Message::SubmitPatternInput),
+                KeyCode::Backspace => {
+                    let mut input = input.clone();
+                    input.pop();
+                    Some(Message::UpdatePatternInput(input))
+                }
+                KeyCode::Char(c) => {
+                    let mut input = input.clone();
+                    input.push(c);
+                    Some(Message::UpdatePatternInput(input))
+                }
+                _ => None,
+            };
+        }
+        if patterns.active {
+            // Esc always closes the pattern editor
+            if key.code == KeyCode::Esc {
+                return Some(Message::TogglePatternEditor);
+            }
+            return match self.tab_action(KeyContext::SettingsPatterns, key) {
+                Some(Action::AddIncludePattern) => {
+                    Some(Message::StartPatternInput(PatternKind::Include))
+                }
+                Some(Action::AddExcludePattern) => {
+                    Some(Message::StartPatternInput(PatternKind::Exclude))
+                }
+                Some(Action::RemovePattern) => Some(Message::RemovePattern),
+                Some(Action::MovePatternUp) => Some(Message::MovePattern(-1)),
+                Some(Action::MovePatternDown) => Some(Message::MovePattern(1)),
+                Some(Action::ClosePatterns) => Some(Message::TogglePatternEditor),
+                Some(Action::RunAnalysis) => Some(Message::RunAnalysis),
+                _ => match key.code {
+                    KeyCode::Up => Some(Message::MovePatternCursor(-1)),
+                    KeyCode::Down => Some(Message::MovePatternCursor(1)),
+                    _ => None,
+                },
+            };
+        }
+
+        match self.tab_action(KeyContext::Settings, key) {
+            Some(Action::ToggleSetting) => return Some(Message::ToggleSetting(cursor)),
+            Some(Action::SearchSettings) => return Some(Message::EnterSettingsSearch),
+            Some(Action::ResetSetting) => return Some(Message::ResetSetting(cursor)),
+            Some(Action::EditPatterns) => return Some(Message::TogglePatternEditor),
+            Some(Action::RunAnalysis) => return Some(Message::RunAnalysis),
+            _ => {}
+        }
         match key.code {
             KeyCode::Up => Some(Message::MoveSettingsCursor(-1)),
             KeyCode::Down => Some(Message::MoveSettingsCursor(1)),
-            KeyCode::Char(' ') => Some(Message::ToggleSetting(self.model.settings.settings_cursor)),
-            KeyCode::Left | KeyCode::Right => {
-                Some(Message::CycleSetting(self.model.settings.settings_cursor))
-            }
-            KeyCode::Enter => Some(Message::RunAnalysis),
+            KeyCode::Left | KeyCode::Right => Some(Message::CycleSetting(cursor)),
             _ => None,
         }
     }
 
     fn handle_statistics_keys(&self, key: KeyEvent) -> Option<Message> {
+        match self.tab_action(KeyContext::Statistics, key) {
+            Some(Action::SortBreakdown) => {
+                return (self.model.statistics.view == StatisticsView::Overview)
+                    .then_some(Message::CycleBreakdownSort);
+            }
+            Some(Action::RunAnalysis) => return Some(Message::RunAnalysis),
+            _ => {}
+        }
         match key.code {
-            KeyCode::Enter => Some(Message::RunAnalysis),
             KeyCode::Left => Some(Message::CycleStatisticsView(-1)), // Previous view
             KeyCode::Right => Some(Message::CycleStatisticsView(1)), // Next view
             KeyCode::Up => Some(Message::ScrollStatistics(-1)),
@@ -342,6 +566,22 @@ impl TuiApp {
             return Some(Message::SetTemplateFocusMode(FocusMode::Normal));
         }
 
+        if self.model.template.get_focus_mode() == FocusMode::SavingTemplate {
+            let mut name = self.model.template.save_name.clone();
+            return match key.code {
+                KeyCode::Enter => Some(Message::SaveTemplate(name)),
+                KeyCode::Backspace => {
+                    name.pop();
+                    Some(Message::UpdateTemplateName(name))
+                }
+                KeyCode::Char(c) => {
+                    name.push(c);
+                    Some(Message::UpdateTemplateName(name))
+                }
+                _ => None,
+            };
+        }
+
         if is_in_editing_mode {
             match current_focus {
                 TemplateFocus::Editor => {
@@ -381,62 +621,91 @@ impl TuiApp {
             }
         }
 
-        // Normal mode: Handle global shortcuts and focus switching
-        match key.code {
-            KeyCode::Char('e') | KeyCode::Char('E') => {
-                return Some(Message::SetTemplateFocus(
-                    TemplateFocus::Editor,
-                    FocusMode::EditingTemplate,
-                ));
-            }
-            KeyCode::Char('v') | KeyCode::Char('V') => {
-                return Some(Message::SetTemplateFocus(
-                    TemplateFocus::Variables,
-                    FocusMode::EditingVariable,
-                ));
-            }
-            KeyCode::Char('p') | KeyCode::Char('P') => {
-                return Some(Message::SetTemplateFocus(
-                    TemplateFocus::Picker,
-                    FocusMode::Normal,
-                ));
-            }
-            KeyCode::Char('s') | KeyCode::Char('S') => {
-                // Save template with timestamp
-                let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-                let filename = format!("custom_template_{}", timestamp);
-                return Some(Message::SaveTemplate(filename));
-            }
-            KeyCode::Char('r') | KeyCode::Char('R') => {
-                // Reload default template
-                return Some(Message::ReloadTemplate);
-            }
-            KeyCode::Enter => {
-                // Run analysis
-                return Some(Message::RunAnalysis);
-            }
-            _ => {}
+        // Normal mode: Handle global shortcuts and focus switching, then the picker
+        let picker_action = || {
+            (current_focus == TemplateFocus::Picker)
+                .then(|| self.model.keymap.action(KeyContext::TemplatePicker, key))
+                .flatten()
+        };
+        let action = self
+            .model
+            .keymap
+            .action(KeyContext::Template, key)
+            .or_else(picker_action)
+            .or_else(|| self.tab_action(KeyContext::Template, key));
+        match action {
+            Some(Action::EditTemplate) => Some(Message::SetTemplateFocus(
+                TemplateFocus::Editor,
+                FocusMode::EditingTemplate,
+            )),
+            Some(Action::EditVariables) => Some(Message::SetTemplateFocus(
+                TemplateFocus::Variables,
+                FocusMode::EditingVariable,
+            )),
+            Some(Action::FocusPicker) => Some(Message::SetTemplateFocus(
+                TemplateFocus::Picker,
+                FocusMode::Normal,
+            )),
+            Some(Action::SaveTemplate) => Some(Message::StartSavingTemplate),
+            Some(Action::TogglePreview) => Some(Message::ToggleTemplatePreview),
+            // Reload default template
+            Some(Action::ReloadTemplate) => Some(Message::ReloadTemplate),
+            // Layout: resize and move the focused column
+            Some(Action::GrowColumn) => Some(Message::ResizeTemplateColumn(RESIZE_STEP)),
+            Some(Action::ShrinkColumn) => Some(Message::ResizeTemplateColumn(-RESIZE_STEP)),
+            Some(Action::MoveColumnRight) => Some(Message::MoveTemplateColumn(1)),
+            Some(Action::MoveColumnLeft) => Some(Message::MoveTemplateColumn(-1)),
+            Some(Action::ResetLayout) => Some(Message::ResetLayout),
+            Some(Action::LoadTemplate) => Some(Message::LoadTemplate),
+            Some(Action::RefreshTemplates) => Some(Message::RefreshTemplates),
+            Some(Action::RunAnalysis) => Some(Message::RunAnalysis),
+            // Handle input for focused component in normal mode
+            _ if current_focus == TemplateFocus::Picker => match key.code {
+                KeyCode::Up => Some(Message::TemplatePickerMove(-1)),
+                KeyCode::Down => Some(Message::TemplatePickerMove(1)),
+                _ => None,
+            },
+            _ => None,
         }
+    }
 
-        // Handle input for focused component in normal mode
-        if current_focus == TemplateFocus::Picker {
-            match key.code {
-                KeyCode::Up => return Some(Message::TemplatePickerMove(-1)),
-                KeyCode::Down => return Some(Message::TemplatePickerMove(1)),
-                KeyCode::Enter | KeyCode::Char('l') | KeyCode::Char('L') | KeyCode::Char(' ') => {
-                    return Some(Message::LoadTemplate);
+    fn handle_prompt_output_keys(&self, key: KeyEvent) -> Option<Message> {
+        let output = &self.model.prompt_output;
+        if output.search_active {
+            return match key.code {
+                KeyCode::Esc => Some(Message::ExitOutputSearch),
+                KeyCode::Enter => Some(Message::SubmitOutputSearch),
+                KeyCode::Backspace => {
+                    let mut query = output.search_query.clone();
+                    query.pop();
+                    Some(Message::UpdateOutputSearch(query))
                 }
-                KeyCode::Char('r') | KeyCode::Char('R') => {
-                    return Some(Message::RefreshTemplates);
+                KeyCode::Char(c) => {
+                    let mut query = output.search_query.clone();
+                    query.push(c);
+                    Some(Message::UpdateOutputSearch(query))
                 }
-                _ => {}
-            }
+                _ => None,
+            };
         }
 
-        None
-    }
-
-    fn handle_prompt_output_keys(&self, key: KeyEvent) -> Option<Message> {
+        // Esc always clears the search
+        if key.code == KeyCode::Esc {
+            return Some(Message::ExitOutputSearch);
+        }
+        match self.tab_action(KeyContext::Output, key) {
+            Some(Action::SearchPrompt) => return Some(Message::EnterOutputSearch),
+            Some(Action::NextMatch) => return Some(Message::MoveOutputMatch(1)),
+            Some(Action::PreviousMatch) => return Some(Message::MoveOutputMatch(-1)),
+            Some(Action::CopyPrompt) => return Some(Message::CopyToClipboard),
+            Some(Action::SavePrompt) => {
+                let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+                let filename = format!("prompt_{}.md", timestamp);
+                return Some(Message::SaveToFile(filename));
+            }
+            Some(Action::RunAnalysis) => return Some(Message::RunAnalysis),
+            _ => {}
+        }
         match key.code {
             KeyCode::Up => Some(Message::ScrollOutput(-1)),
             KeyCode::Down => Some(Message::ScrollOutput(1)),
@@ -444,13 +713,57 @@ impl TuiApp {
             KeyCode::PageDown => Some(Message::ScrollOutput(10)),
             KeyCode::Home => Some(Message::ScrollOutput(-9999)),
             KeyCode::End => Some(Message::ScrollOutput(9999)),
-            KeyCode::Char('c') | KeyCode::Char('C') => Some(Message::CopyToClipboard),
-            KeyCode::Char('s') | KeyCode::Char('S') => {
-                let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-                let filename = format!("prompt_{}.md", timestamp);
-                Some(Message::SaveToFile(filename))
+            _ => None,
+        }
+    }
+
+    fn handle_git_keys(&self, key: KeyEvent) -> Option<Message> {
+        if self.model.git.show_diff {
+            return self.handle_diff_viewer_keys(key);
+        }
+        match self.tab_action(KeyContext::Git, key) {
+            Some(Action::PickBranch) => return Some(Message::PickBranch(self.model.git.cursor)),
+            Some(Action::ToggleBranchDiff) => return Some(Message::ToggleDiffBranches),
+            Some(Action::ToggleBranchLog) => return Some(Message::ToggleLogBranches),
+            Some(Action::ChangedFilesOnly) => return Some(Message::ToggleChangedFilesOnly),
+            Some(Action::CycleDiffStyle) => return Some(Message::CycleDiffStyle),
+            Some(Action::MoreDiffContext) => return Some(Message::AdjustDiffContext(1)),
+            Some(Action::LessDiffContext) => return Some(Message::AdjustDiffContext(-1)),
+            Some(Action::ClearPicks) => return Some(Message::ClearBranchPicks),
+            Some(Action::ReloadBranches) => return Some(Message::ReloadBranches),
+            Some(Action::OpenDiffViewer) => return Some(Message::ToggleDiffViewer),
+            Some(Action::RunAnalysis) => return Some(Message::RunAnalysis),
+            _ => {}
+        }
+        match key.code {
+            KeyCode::Up => Some(Message::MoveBranchCursor(-1)),
+            KeyCode::Down => Some(Message::MoveBranchCursor(1)),
+            KeyCode::PageUp => Some(Message::MoveBranchCursor(-10)),
+            KeyCode::PageDown => Some(Message::MoveBranchCursor(10)),
+            KeyCode::Home => Some(Message::MoveBranchCursor(-9999)),
+            KeyCode::End => Some(Message::MoveBranchCursor(9999)),
+            _ => None,
+        }
+    }
+
+    /// Handle keys in the diff viewer of the Git tab
+    fn handle_diff_viewer_keys(&self, key: KeyEvent) -> Option<Message> {
+        match self.model.keymap.action(KeyContext::GitDiff, key) {
+            Some(Action::ExcludeDiffFile) => return Some(Message::ToggleDiffFileExcluded),
+            Some(Action::ReloadDiff) => return Some(Message::ReloadDiffFiles),
+            Some(Action::CloseDiffViewer) => return Some(Message::ToggleDiffViewer),
+            _ => {}
+        }
+        match key.code {
+            KeyCode::Up => Some(Message::MoveDiffCursor(-1)),
+            KeyCode::Down => Some(Message::MoveDiffCursor(1)),
+            KeyCode::PageUp => Some(Message::MoveDiffCursor(-10)),
+            KeyCode::PageDown => Some(Message::MoveDiffCursor(10)),
+            KeyCode::Home => Some(Message::MoveDiffCursor(-9999)),
+            KeyCode::End => Some(Message::MoveDiffCursor(9999)),
+            KeyCode::Enter | KeyCode::Left | KeyCode::Right => {
+                Some(Message::ToggleDiffFileExpanded)
             }
-            KeyCode::Enter => Some(Message::RunAnalysis),
             _ => None,
         }
     }
@@ -458,8 +771,10 @@ impl TuiApp {
     /// Handle a message using the Elm/Redux pattern.
     /// This uses the pure Model::update() function and executes any side effects.
     fn handle_message(&mut self, message: Message) -> Result<()> {
+        record_message(&message);
         let (new_model, cmd) = self.model.update(message);
         self.model = new_model;
+        record_model_summary(self.model.crash_summary());
 
         // Execute any side effects
         self.execute_cmd(cmd)?;
@@ -476,13 +791,20 @@ impl TuiApp {
             }
 
             Cmd::RefreshFileTree => {
+                let changed = self.model.session.load_changed_files();
                 // Always use session-based tree building for proper pattern initialization
                 match build_file_tree_from_session(&mut self.model.session) {
                     Ok(tree) => {
                         self.model.file_tree_nodes = tree;
-                        self.model.status_message =
-                            "File tree loaded with patterns applied and files auto-expanded"
-                                .to_string();
+                        self.model.status_message = match changed {
+                            Ok(()) => {
+                                "File tree loaded with patterns applied and files auto-expanded"
+                                    .to_string()
+                            }
+                            Err(e) => format!("Error listing changed files: {}", e),
+                        };
+                        let uncounted = uncounted_files(&self.model.file_tree_nodes);
+                        self.execute_cmd(Cmd::CountFileTokens(uncounted))?;
                     }
                     Err(e) => {
                         self.model.status_message = format!("Error loading files: {}", e);
@@ -497,51 +819,39 @@ impl TuiApp {
                 // Use the current session state (with all user selections)
                 let mut session = self.model.session.clone();
                 let tx = self.message_tx.clone();
+                session.set_progress_listener(Arc::new(ProgressForwarder::new(tx.clone())));
 
                 tokio::spawn(async move {
-                    // Set custom template content
-                    session.config.template_str = template_content;
-                    session.config.template_name = "Custom Template".to_string();
-
-                    // Transfer user variables from TUI to session config
-                    session.config.user_variables = user_variables;
-
-                    match session.generate_prompt() {
-                        Ok(rendered) => {
-                            // Convert to AnalysisResults format expected by TUI
-                            let token_map_entries = if rendered.token_count > 0 {
-                                if let Some(files) = session.data.files.as_ref() {
-                                    generate_token_map_with_limit(
-                                        files,
-                                        rendered.token_count,
-                                        Some(50),
-                                        Some(0.5),
-                                    )
-                                } else {
-                                    Vec::new()
-                                }
-                            } else {
-                                Vec::new()
-                            };
-
-                            let result = AnalysisResults {
-                                file_count: rendered.files.len(),
-                                token_count: Some(rendered.token_count),
-                                generated_prompt: rendered.prompt,
-                                token_map_entries,
-                            };
-                            let _ = tx.send(Message::AnalysisComplete(result));
-                        }
-                        Err(e) => {
-                            let _ = tx.send(Message::AnalysisError(e.to_string()));
-                        }
-                    }
+                    let _ = tx.send(run_analysis(session, template_content, user_variables));
                 });
             }
 
+            Cmd::CountFileTokens(paths) => {
+                if !paths.is_empty() {
+                    let config = self.model.session.config.clone();
+                    let tx = self.message_tx.clone();
+
+                    // Tokenizing is CPU-bound, keep it off the async workers
+                    tokio::task::spawn_blocking(move || {
+                        let counts = count_file_tokens(&paths, &config);
+                        let _ = tx.send(Message::FileTokensCounted(counts));
+                    });
+                }
+            }
+
             Cmd::CopyToClipboard(content) => match copy_to_clipboard(&content) {
                 Ok(_) => {
-                    self.model.status_message = "Copied to clipboard!".to_string();
+                    self.model.status_message = match self.model.prompt_output.token_count {
+                        Some(count) => {
+                            let config = &self.model.session.config;
+                            let mut tokens = format_number(count, &config.token_format);
+                            if config.token_count_mode == TokenCountMode::Heuristic {
+                                tokens = format!("~{}", tokens);
+                            }
+                            format!("Copied {} tokens to clipboard!", tokens)
+                        }
+                        None => "Copied to clipboard!".to_string(),
+                    };
                 }
                 Err(e) => {
                     self.model.status_message = format!("Copy failed: {}", e);
@@ -559,10 +869,57 @@ impl TuiApp {
                 }
             }
 
-            Cmd::SaveTemplate { filename, content } => {
-                match save_template_to_custom_dir(std::path::Path::new(&filename), &content) {
-                    Ok(_) => {
-                        self.model.status_message = format!("Template saved as {}", filename);
+            Cmd::LoadBranches => {
+                let result =
+                    get_branches(self.model.session.repo_path()).map_err(|e| format!("{:#}", e));
+                self.handle_message(Message::BranchesLoaded(result))?;
+            }
+
+            Cmd::PreviewBranchDiff { base, head } => {
+                let session = self.model.session.clone();
+                let tx = self.message_tx.clone();
+
+                // Diffing and tokenizing are CPU-bound, keep them off the async workers
+                tokio::task::spawn_blocking(move || {
+                    let result = session
+                        .branch_diff_tokens(&base, &head)
+                        .map_err(|e| format!("{:#}", e));
+                    let _ = tx.send(Message::BranchDiffPreviewed { base, head, result });
+                });
+            }
+
+            Cmd::LoadDiffFiles => {
+                let mut session = self.model.session.clone();
+                // Load the files excluded with the viewer too, so that they can be included back
+                let excluded: Vec<String> = self
+                    .model
+                    .git
+                    .excluded_diff_files
+                    .iter()
+                    .map(|path| diff_exclude_pattern(path))
+                    .collect();
+                session
+                    .config
+                    .diff_exclude_patterns
+                    .retain(|pattern| !excluded.contains(pattern));
+                let tx = self.message_tx.clone();
+
+                tokio::task::spawn_blocking(move || {
+                    let result = session.diff_files().map_err(|e| format!("{:#}", e));
+                    let _ = tx.send(Message::DiffFilesLoaded(result));
+                });
+            }
+
+            Cmd::SaveLayout(layout) => {
+                if let Err(e) = update_tui_state(|state| state.layout = layout) {
+                    self.model.status_message = format!("Failed to save layout: {}", e);
+                }
+            }
+
+            Cmd::SaveTemplate { name, content } => {
+                match save_template(&name, &content) {
+                    Ok(path) => {
+                        self.model.status_message = format!("Template saved as {}", path.display());
                         // Refresh templates to show the new one
                         self.model.template.picker.refresh();
                     }
@@ -576,28 +933,37 @@ impl TuiApp {
         Ok(())
     }
 
-    fn render_tab_bar_static(model: &Model, frame: &mut Frame, area: Rect) {
-        let tabs = vec![
-            "1. Selection",
-            "2. Settings",
-            "3. Statistics",
-            "4. Template",
-            "5. Output",
-        ];
+    fn render_tab_bar_static(model: &Model, frame: &mut Frame, area: Rect, breakpoint: Breakpoint) {
+        let tabs = if area.width >= COMPACT_WIDTH {
+            vec![
+                "1. Selection",
+                "2. Settings",
+                "3. Statistics",
+                "4. Template",
+                "5. Output",
+                "6. Git",
+            ]
+        } else {
+            vec!["1.Files", "2.Set", "3.Stats", "4.Tmpl", "5.Out", "6.Git"]
+        };
         let selected = match model.current_tab {
             Tab::FileTree => 0,
             Tab::Settings => 1,
             Tab::Statistics => 2,
             Tab::Template => 3,
             Tab::PromptOutput => 4,
+            Tab::Git => 5,
         };
 
-        let tabs_widget = Tabs::new(tabs)
-            .block(
+        let mut tabs_widget = Tabs::new(tabs);
+        if breakpoint == Breakpoint::Regular {
+            tabs_widget = tabs_widget.block(
                 Block::default()
                     .borders(Borders::ALL)
                     .title("Code2Prompt TUI"),
-            )
+            );
+        }
+        let tabs_widget = tabs_widget
             .select(selected)
             .style(Style::default().fg(Color::White))
             .highlight_style(
@@ -609,16 +975,23 @@ impl TuiApp {
         frame.render_widget(tabs_widget, area);
     }
 
-    fn render_status_bar_static(model: &Model, frame: &mut Frame, area: Rect) {
+    fn render_status_bar_static(
+        model: &Model,
+        frame: &mut Frame,
+        area: Rect,
+        breakpoint: Breakpoint,
+    ) {
         let status_text = if !model.status_message.is_empty() {
             model.status_message.clone()
         } else {
-            "Tab/Shift+Tab: Switch tabs | 1/2/3/4: Direct tab | Enter: Run Analysis | Esc/Ctrl+Q: Quit".to_string()
+            "Tab/Shift+Tab: Switch tabs | 1-6: Direct tab | Enter: Run Analysis | Esc/Ctrl+Q: Quit"
+                .to_string()
         };
 
-        let status_widget = Paragraph::new(status_text)
-            .block(Block::default().borders(Borders::ALL))
-            .style(Style::default().fg(Color::Cyan));
+        let mut status_widget = Paragraph::new(status_text).style(Style::default().fg(Color::Cyan));
+        if breakpoint == Breakpoint::Regular {
+            status_widget = status_widget.block(Block::default().borders(Borders::ALL));
+        }
         frame.render_widget(status_widget, area);
     }
 
@@ -677,6 +1050,18 @@ impl TuiApp {
                 *delta1 += delta2;
                 true
             }
+            (Message::ScrollHelp(delta1), Message::ScrollHelp(delta2)) => {
+                *delta1 += delta2;
+                true
+            }
+            (Message::MoveBranchCursor(delta1), Message::MoveBranchCursor(delta2)) => {
+                *delta1 += delta2;
+                true
+            }
+            (Message::MoveDiffCursor(delta1), Message::MoveDiffCursor(delta2)) => {
+                *delta1 += delta2;
+                true
+            }
             (Message::TemplatePickerMove(delta1), Message::TemplatePickerMove(delta2)) => {
                 *delta1 += delta2;
                 true
@@ -686,11 +1071,110 @@ impl TuiApp {
     }
 }
 
+/// Interval between two reports of the files loaded by an analysis
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Forwards the progress of an analysis to the event loop. The files loaded are reported
+/// at most every [`PROGRESS_INTERVAL`], so that large repositories do not flood the loop.
+#[derive(Debug)]
+struct ProgressForwarder {
+    tx: mpsc::UnboundedSender<Message>,
+    /// The progress so far and when it was last sent
+    state: Mutex<(AnalysisProgress, Instant)>,
+}
+
+impl ProgressForwarder {
+    fn new(tx: mpsc::UnboundedSender<Message>) -> Self {
+        Self {
+            tx,
+            state: Mutex::new((AnalysisProgress::default(), Instant::now())),
+        }
+    }
+}
+
+impl ProgressListener for ProgressForwarder {
+    fn on_progress(&self, event: ProgressEvent) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let (progress, sent_at) = &mut *state;
+        let stage = progress.stage;
+        progress.apply(&event);
+        if progress.stage != stage || sent_at.elapsed() >= PROGRESS_INTERVAL {
+            *sent_at = Instant::now();
+            let _ = self.tx.send(Message::AnalysisProgress(progress.clone()));
+        }
+    }
+}
+
+/// Generate the prompt for the session and convert the outcome into the message
+/// reporting it back to the model.
+///
+/// # Arguments
+///
+/// * `session` - A copy of the session holding the user selections
+/// * `template_content` - The template to render
+/// * `user_variables` - The template variables entered in the TUI
+///
+/// # Returns
+///
+/// * `Message` - `AnalysisComplete` with the results, or `AnalysisError`
+pub(crate) fn run_analysis(
+    mut session: Code2PromptSession,
+    template_content: String,
+    user_variables: std::collections::HashMap<String, String>,
+) -> Message {
+    // Set custom template content
+    session.config.template_str = template_content;
+    session.config.template_name = "Custom Template".to_string();
+
+    // Transfer user variables from TUI to session config
+    session.config.user_variables = user_variables;
+
+    match session.generate_prompt() {
+        Ok(rendered) => {
+            // Convert to AnalysisResults format expected by TUI
+            let token_map_entries = if rendered.token_count > 0 {
+                if let Some(files) = session.data.files.as_ref() {
+                    generate_token_map_with_limit(files, rendered.token_count, Some(50), Some(0.5))
+                } else {
+                    Vec::new()
+                }
+            } else {
+                Vec::new()
+            };
+
+            let token_distribution = session
+                .data
+                .files
+                .as_deref()
+                .map(|files| TokenDistribution::from_files(files, &session.config))
+                .unwrap_or_default();
+
+            Message::AnalysisComplete(AnalysisResults {
+                file_count: rendered.files.len(),
+                token_count: Some(rendered.token_count),
+                token_breakdown: Some(rendered.token_breakdown),
+                generated_prompt: rendered.prompt,
+                token_map_entries,
+                token_distribution,
+                snapshot: session.snapshot(rendered.token_count),
+            })
+        }
+        Err(e) => Message::AnalysisError(e.to_string()),
+    }
+}
+
 /// Run the Terminal User Interface.
 ///
 /// This is the main entry point for the TUI mode. It parses command-line arguments,
 /// initializes the TUI application, and runs the main event loop until the user exits.
 ///
+/// # Arguments
+///
+/// * `session` - The session built from the config files and the command line
+/// * `args` - The command-line arguments, applied again when the config files are reloaded
+///
 /// # Returns
 ///
 /// * `Result<()>` - Ok on successful exit, Err if initialization or runtime errors occur
@@ -698,13 +1182,22 @@ impl TuiApp {
 /// # Errors
 ///
 /// Returns an error if the TUI cannot be initialized or if runtime errors occur during execution.
-pub async fn run_tui(session: Code2PromptSession) -> Result<()> {
-    let mut app = TuiApp::new(session)?;
+pub async fn run_tui(session: Code2PromptSession, args: &Cli) -> Result<()> {
+    install_panic_hook(|| {
+        let _ = restore_terminal();
+    });
+    let mut app = TuiApp::new(session, args)?;
 
     let result = app.run().await;
 
     // Clean up terminal
     restore_terminal()?;
+    remove_panic_hook();
+
+    let saved = TuiSession::capture(&app.model);
+    if let Err(e) = save_tui_session(&app.model.session.config.path, &saved) {
+        warn!("Failed to save the TUI session: {:#}", e);
+    }
 
     result
 }
@@ -0,0 +1,227 @@
+//! Per-project TUI session.
+//!
+//! The explicit selections, the template, the template variables and the settings changed
+//! in the TUI are written to `.code2prompt/session.toml` in the project on exit, and
+//! restored on the next launch unless `--fresh` is given. The token counts of the recent
+//! runs are kept with them, and restored even with `--fresh`.
+
+use crate::model::{EditorState, Model};
+use anyhow::{Context, Result};
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::path::SymlinkPolicy;
+use code2prompt_core::selection::{ActionType, FileInclusion};
+use code2prompt_core::sort::FileSortMethod;
+use code2prompt_core::template::OutputFormat;
+use code2prompt_core::tokenizer::{TokenCountMode, TokenFormat, TokenizerType};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Session restored in the TUI of a project
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TuiSession {
+    /// Files and directories explicitly included or excluded, oldest first
+    pub selections: Vec<SavedSelection>,
+    /// The template in the editor, when it is not the default one
+    pub template: Option<SavedTemplate>,
+    pub user_variables: BTreeMap<String, String>,
+    pub settings: SavedSettings,
+    /// Total tokens of the recent runs, oldest first
+    pub token_history: Vec<usize>,
+}
+
+/// A file or directory included or excluded in the file tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSelection {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    pub action: ActionType,
+}
+
+/// The template in the editor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedTemplate {
+    pub name: String,
+    pub content: String,
+}
+
+/// Settings changed in the TUI, the others follow the launch configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedSettings {
+    pub line_numbers: Option<bool>,
+    pub absolute_path: Option<bool>,
+    pub no_codeblock: Option<bool>,
+    pub output_format: Option<OutputFormat>,
+    pub token_format: Option<TokenFormat>,
+    pub full_directory_tree: Option<bool>,
+    pub sort_method: Option<FileSortMethod>,
+    pub encoding: Option<TokenizerType>,
+    pub token_count_mode: Option<TokenCountMode>,
+    pub diff_enabled: Option<bool>,
+    pub symlink_policy: Option<SymlinkPolicy>,
+    pub hidden: Option<bool>,
+    pub no_ignore: Option<bool>,
+}
+
+impl SavedSettings {
+    /// The settings of `config` differing from `defaults`
+    fn changed(config: &Code2PromptConfig, defaults: &Code2PromptConfig) -> Self {
+        fn changed<T: PartialEq>(value: T, default: T) -> Option<T> {
+            (value != default).then_some(value)
+        }
+        Self {
+            line_numbers: changed(config.line_numbers, defaults.line_numbers),
+            absolute_path: changed(config.absolute_path, defaults.absolute_path),
+            no_codeblock: changed(config.no_codeblock, defaults.no_codeblock),
+            output_format: changed(config.output_format, defaults.output_format),
+            token_format: changed(config.token_format, defaults.token_format),
+            full_directory_tree: changed(config.full_directory_tree, defaults.full_directory_tree),
+            sort_method: changed(config.sort_method, defaults.sort_method).flatten(),
+            encoding: changed(config.encoding, defaults.encoding),
+            token_count_mode: changed(config.token_count_mode, defaults.token_count_mode),
+            diff_enabled: changed(config.diff_enabled, defaults.diff_enabled),
+            symlink_policy: changed(config.symlink_policy, defaults.symlink_policy),
+            hidden: changed(config.hidden, defaults.hidden),
+            no_ignore: changed(config.no_ignore, defaults.no_ignore),
+        }
+    }
+
+    /// Set the saved settings on `config`
+    fn apply(&self, config: &mut Code2PromptConfig) {
+        fn apply<T: Copy>(saved: Option<T>, value: &mut T) {
+            if let Some(saved) = saved {
+                *value = saved;
+            }
+        }
+        apply(self.line_numbers, &mut config.line_numbers);
+        apply(self.absolute_path, &mut config.absolute_path);
+        apply(self.no_codeblock, &mut config.no_codeblock);
+        apply(self.output_format, &mut config.output_format);
+        apply(self.token_format, &mut config.token_format);
+        apply(self.full_directory_tree, &mut config.full_directory_tree);
+        if self.sort_method.is_some() {
+            config.sort_method = self.sort_method;
+        }
+        apply(self.encoding, &mut config.encoding);
+        apply(self.token_count_mode, &mut config.token_count_mode);
+        apply(self.diff_enabled, &mut config.diff_enabled);
+        apply(self.symlink_policy, &mut config.symlink_policy);
+        apply(self.hidden, &mut config.hidden);
+        apply(self.no_ignore, &mut config.no_ignore);
+    }
+}
+
+impl TuiSession {
+    /// Capture the session of the TUI
+    pub fn capture(model: &Model) -> Self {
+        // An action is superseded by a later action on the same path
+        let actions = model.session.selection_engine.user_actions();
+        let selections = actions
+            .iter()
+            .enumerate()
+            .filter(|(index, action)| {
+                !actions[index + 1..]
+                    .iter()
+                    .any(|later| later.path == action.path)
+            })
+            .map(|(_, action)| SavedSelection {
+                path: action.path.clone(),
+                action: action.action,
+            })
+            .collect();
+
+        let editor = &model.template.editor;
+        let default = EditorState::default();
+        let template = (editor.current_template_name != default.current_template_name
+            || !editor.content.lines().eq(default.content.lines()))
+        .then(|| SavedTemplate {
+            name: editor.current_template_name.clone(),
+            content: editor.content.clone(),
+        });
+
+        Self {
+            selections,
+            template,
+            user_variables: model
+                .template
+                .variables
+                .user_variables
+                .clone()
+                .into_iter()
+                .collect(),
+            settings: SavedSettings::changed(&model.session.config, &model.settings.defaults),
+            token_history: model.statistics.token_history.clone(),
+        }
+    }
+
+    /// Restore the session in the TUI. Selections of paths that no longer exist are dropped.
+    pub fn apply(&self, model: &mut Model) {
+        let root = model.session.config.path.clone();
+        for selection in &self.selections {
+            if !root.join(&selection.path).exists() {
+                continue;
+            }
+            match selection.action {
+                ActionType::Include => model.session.select_file(selection.path.clone()),
+                ActionType::Exclude => model.session.deselect_file(selection.path.clone()),
+                ActionType::TreeOnly => model
+                    .session
+                    .set_file_inclusion(selection.path.clone(), FileInclusion::TreeOnly),
+            };
+        }
+
+        if let Some(template) = &self.template {
+            model
+                .template
+                .editor
+                .set_content(template.name.clone(), template.content.clone());
+        }
+        for (name, value) in &self.user_variables {
+            model
+                .template
+                .variables
+                .set_user_variable(name.clone(), value.clone());
+        }
+        model.template.sync_variables_with_template();
+
+        self.settings.apply(&mut model.session.config);
+    }
+}
+
+/// Path of the session file of a project
+pub fn session_file_path(root: &Path) -> PathBuf {
+    root.join(".code2prompt").join("session.toml")
+}
+
+/// Load the session of a project, if one was saved and is valid
+pub fn load_tui_session(root: &Path) -> Option<TuiSession> {
+    let path = session_file_path(root);
+    let content = std::fs::read_to_string(&path).ok()?;
+    toml::from_str(&content)
+        .map_err(|e| {
+            warn!(
+                "Ignoring invalid TUI session file {}: {}",
+                path.display(),
+                e
+            )
+        })
+        .ok()
+}
+
+/// Save the session of a project. Nothing is written for an empty session, unless a
+/// previous session has to be overwritten.
+pub fn save_tui_session(root: &Path, session: &TuiSession) -> Result<()> {
+    let path = session_file_path(root);
+    if *session == TuiSession::default() && !path.exists() {
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let content = toml::to_string_pretty(session)?;
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
@@ -0,0 +1,57 @@
+//! Persistent TUI state.
+//!
+//! Preferences that survive across TUI sessions, such as the layout of the tabs,
+//! are stored as TOML in `<config dir>/code2prompt/tui-state.toml`.
+
+use crate::model::LayoutPrefs;
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// State persisted across TUI sessions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TuiState {
+    pub layout: LayoutPrefs,
+}
+
+/// Path of the state file, if a configuration directory is available
+pub fn state_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("code2prompt").join("tui-state.toml"))
+}
+
+/// Load the persisted state, falling back to defaults when the file is missing or invalid
+pub fn load_tui_state() -> TuiState {
+    let Some(path) = state_file_path() else {
+        return TuiState::default();
+    };
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return TuiState::default();
+    };
+
+    let mut state: TuiState = toml::from_str(&content).unwrap_or_else(|e| {
+        warn!("Ignoring invalid TUI state file {}: {}", path.display(), e);
+        TuiState::default()
+    });
+    if !state.layout.is_valid() {
+        warn!("Ignoring invalid layout preferences in {}", path.display());
+        state.layout = LayoutPrefs::default();
+    }
+    state
+}
+
+/// Update the persisted state, keeping the parts not touched by `update`
+pub fn update_tui_state(update: impl FnOnce(&mut TuiState)) -> Result<()> {
+    let path = state_file_path().context("No configuration directory to store the TUI state")?;
+    let mut state = load_tui_state();
+    update(&mut state);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let content = toml::to_string_pretty(&state)?;
+    std::fs::write(&path, content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
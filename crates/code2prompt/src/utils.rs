@@ -5,9 +5,16 @@
 
 use crate::model::DisplayFileNode;
 use anyhow::Result;
+use code2prompt_core::configuration::Code2PromptConfig;
+use code2prompt_core::path::walk_builder;
+use code2prompt_core::selection::FileInclusion;
 use code2prompt_core::session::Code2PromptSession;
+use code2prompt_core::tokenizer::count_tokens_with_mode;
 use regex::Regex;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Bytes inspected for a NUL byte to tell binary files apart
+const BINARY_SNIFF_BYTES: usize = 8192;
 
 /// Build hierarchical file tree from session using traverse_directory with SelectionEngine
 pub fn build_file_tree_from_session(
@@ -15,12 +22,9 @@ pub fn build_file_tree_from_session(
 ) -> Result<Vec<DisplayFileNode>> {
     let mut root_nodes = Vec::new();
 
-    // Build root level nodes using ignore crate to respect gitignore
-    use ignore::WalkBuilder;
-    let walker = WalkBuilder::new(&session.config.path)
+    // Build root level nodes with the ignore, hidden and symlink settings of the traversal
+    let walker = walk_builder(&session.config.path, &session.config)
         .max_depth(Some(1))
-        .git_ignore(!session.config.no_ignore) // Respect the no_ignore flag
-        .hidden(!session.config.hidden) // Also respect the hidden flag for consistency
         .build();
 
     for entry in walker {
@@ -146,7 +150,9 @@ fn matches(m: &QueryMatcher, text: &str) -> bool {
 #[derive(Debug, Clone)]
 pub struct DisplayNodeWithSelection {
     pub node: DisplayFileNode,
+    /// Whether the content of the node is included
     pub is_selected: bool,
+    pub inclusion: FileInclusion,
 }
 
 /// Recursively collect visible nodes
@@ -187,7 +193,7 @@ fn collect_visible_nodes_recursive(
                 } else {
                     &node.path
                 };
-                let is_selected = session.is_file_selected(relative_path);
+                let inclusion = session.file_inclusion(relative_path);
 
                 // Show directories as expanded in search results for better context
                 let mut node_clone = node.clone();
@@ -197,7 +203,8 @@ fn collect_visible_nodes_recursive(
 
                 visible.push(DisplayNodeWithSelection {
                     node: node_clone,
-                    is_selected,
+                    is_selected: inclusion == FileInclusion::Full,
+                    inclusion,
                 });
 
                 visible.extend(child_results);
@@ -210,11 +217,12 @@ fn collect_visible_nodes_recursive(
                 } else {
                     &node.path
                 };
-                let is_selected = session.is_file_selected(relative_path);
+                let inclusion = session.file_inclusion(relative_path);
 
                 visible.push(DisplayNodeWithSelection {
                     node: node.clone(),
-                    is_selected,
+                    is_selected: inclusion == FileInclusion::Full,
+                    inclusion,
                 });
 
                 // Only descend if the directory is expanded
@@ -270,6 +278,59 @@ pub fn format_number(num: usize, format: &code2prompt_core::tokenizer::TokenForm
     }
 }
 
+/// Collect the files of the loaded tree whose tokens are not counted yet
+pub fn uncounted_files(nodes: &[DisplayFileNode]) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for node in nodes {
+        if node.is_directory {
+            paths.extend(uncounted_files(&node.children));
+        } else if node.token_count.is_none() {
+            paths.push(node.path.clone());
+        }
+    }
+    paths
+}
+
+/// Count the tokens of files with the tokenizer and counting mode of the session.
+///
+/// Binary and unreadable files are skipped.
+///
+/// # Arguments
+/// * `paths` - The files to count
+/// * `config` - The configuration giving the tokenizer and the counting mode
+///
+/// # Returns
+/// The token count of each counted file
+pub fn count_file_tokens(paths: &[PathBuf], config: &Code2PromptConfig) -> Vec<(PathBuf, usize)> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let content = std::fs::read(path).ok()?;
+            if content[..content.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+                return None;
+            }
+            let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+            let count = count_tokens_with_mode(
+                &String::from_utf8_lossy(&content),
+                extension,
+                &config.encoding,
+                config.tokenizer_model.as_deref(),
+                config.token_count_mode,
+            );
+            Some((path.clone(), count))
+        })
+        .collect()
+}
+
+/// Store token counts in the matching nodes of the tree
+pub fn apply_token_counts(nodes: &mut [DisplayFileNode], counts: &[(PathBuf, usize)]) {
+    for (path, count) in counts {
+        if let Some(node) = nodes.iter_mut().find_map(|root| root.find_node_mut(path)) {
+            node.token_count = Some(*count);
+        }
+    }
+}
+
 /// Load children for search mode without mutating the original tree
 fn get_children_for_search(
     node: &DisplayFileNode,
@@ -286,12 +347,9 @@ fn get_children_for_search(
     // Load children on the fly without mutating the original tree
     let mut children: Vec<DisplayFileNode> = Vec::new();
 
-    // Use ignore crate to respect gitignore
-    use ignore::WalkBuilder;
-    let walker = WalkBuilder::new(&node.path)
+    // With the ignore, hidden and symlink settings of the traversal
+    let walker = walk_builder(&node.path, &session.config)
         .max_depth(Some(1))
-        .git_ignore(!session.config.no_ignore) // Respect the no_ignore flag
-        .hidden(!session.config.hidden) // Also respect the hidden flag for consistency
         .build();
 
     for entry in walker.flatten() {
@@ -320,72 +378,6 @@ fn get_children_for_search(
     children
 }
 
-/// Save template to custom directory
-pub fn save_template_to_custom_dir(filename: &Path, content: &str) -> Result<()> {
-    let templates_dir = if let Some(cfg) = dirs::config_dir() {
-        cfg.join("code2prompt").join("templates")
-    } else {
-        // Fallback to current directory if config_dir not available
-        std::env::current_dir()?.join("templates")
-    };
-
-    std::fs::create_dir_all(&templates_dir)?;
-    let full_path = templates_dir.join(filename);
-    std::fs::write(full_path, content)?;
-    Ok(())
-}
-
-/// Find custom templates and return (display_name, absolute_path).
-pub fn load_all_templates() -> Result<Vec<(String, String)>> {
-    let mut out = Vec::new();
-
-    // Candidate roots
-    let mut roots = Vec::new();
-    roots.push(std::env::current_dir()?.join("templates"));
-    if let Some(cfg) = dirs::config_dir() {
-        roots.push(cfg.join("code2prompt").join("templates"));
-    }
-
-    // Accept common template extensions
-    let is_template = |p: &Path| {
-        matches!(
-            p.extension().and_then(|e| e.to_str()),
-            Some("hbs") | Some("handlebars") | Some("md") | Some("tmpl")
-        )
-    };
-
-    for root in roots {
-        if !root.exists() {
-            continue;
-        }
-        for entry in walkdir::WalkDir::new(&root).min_depth(1).max_depth(2) {
-            let entry = entry?;
-            let p = entry.path();
-            if p.is_file() && is_template(p) {
-                let name = p
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("template")
-                    .to_string();
-                out.push((
-                    name,
-                    p.canonicalize()
-                        .unwrap_or_else(|_| p.to_path_buf())
-                        .to_string_lossy()
-                        .into(),
-                ));
-            }
-        }
-    }
-
-    // De-duplicate (same path could appear twice)
-    // Let the compiler infer tuple types for the sort closure.
-    out.sort_by(|a: &(String, String), b: &(String, String)| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
-    out.dedup_by(|a, b| a.1 == b.1);
-
-    Ok(out)
-}
-
 /// Ensure a path exists in the file tree by creating missing intermediate nodes
 pub fn ensure_path_exists_in_tree(
     root_nodes: &mut Vec<DisplayFileNode>,
@@ -0,0 +1,89 @@
+//! A/B prompt variant generation.
+//!
+//! Renders the prompt once per combination of the `--vary` axes, up to `--variants`
+//! prompts, and writes them to `--variants-dir` with a `manifest.json` giving the settings
+//! and the token count of each variant.
+
+use anyhow::{Context, Result};
+use code2prompt_core::session::Code2PromptSession;
+use code2prompt_core::template::{OutputFormat, write_to_file};
+use code2prompt_core::variants::{VariantManifest, VariantRecord, plan_variants};
+use colored::*;
+
+use crate::args::Cli;
+use crate::config;
+
+/// Writes the prompt variants of the session and their manifest.
+///
+/// # Arguments
+///
+/// * `session` - The session built from the configuration and the arguments
+/// * `args` - The command line arguments giving the count, the axes and the directory
+/// * `count` - The number of variants
+///
+/// # Returns
+///
+/// * `Result<()>` - An empty result indicating success or an error
+pub fn write_variants(mut session: Code2PromptSession, args: &Cli, count: usize) -> Result<()> {
+    // Asked once, for all the variants
+    let template_str = session.config.template_str.clone();
+    config::handle_undefined_variables(&mut session, &template_str)?;
+
+    // All the variants are rendered before any is written, so that none of them includes
+    // the others when the directory is in the codebase
+    let variants = plan_variants(&session.config, &args.vary, count)?
+        .into_iter()
+        .map(|variant| {
+            let rendered = Code2PromptSession::new(variant.config.clone())
+                .generate_prompt()
+                .with_context(|| format!("Failed to render {}", variant.name))?;
+            Ok((variant, rendered))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let dir = &args.variants_dir;
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+    let mut manifest = VariantManifest {
+        axes: args.vary.clone(),
+        variants: Vec::new(),
+    };
+    for (variant, rendered) in variants {
+        let extension = match variant.config.output_format {
+            OutputFormat::Markdown => "md",
+            OutputFormat::Xml => "xml",
+            OutputFormat::Json => "json",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Chunks => "chunks.jsonl",
+        };
+        let file = format!("{}.{}", variant.name, extension);
+        let path = dir.join(&file);
+        write_to_file(&path.to_string_lossy(), &rendered.prompt)
+            .with_context(|| format!("Failed to write to file: {}", path.display()))?;
+
+        if !args.quiet {
+            let settings: Vec<String> = variant
+                .settings
+                .iter()
+                .map(|(axis, value)| format!("{}={}", axis, value))
+                .collect();
+            eprintln!(
+                "{}{}{} {}: {} ({} tokens)",
+                "[".bold().white(),
+                "✓".bold().green(),
+                "]".bold().white(),
+                path.display(),
+                settings.join(", "),
+                rendered.token_count
+            );
+        }
+        manifest.variants.push(VariantRecord {
+            name: variant.name,
+            file,
+            settings: variant.settings,
+            token_count: rendered.token_count,
+        });
+    }
+
+    manifest.save(&dir.join("manifest.json"))
+}
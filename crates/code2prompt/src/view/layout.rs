@@ -0,0 +1,139 @@
+//! Responsive layout rules.
+//!
+//! Every tab declares the smallest terminal it can be drawn in. Above that size the
+//! tabs adapt to the available space: the chrome shrinks on small terminals and
+//! secondary panes are dropped, least important first, before the main pane gets
+//! too short to be useful.
+
+use crate::model::Tab;
+use ratatui::layout::Rect;
+
+/// Width below which the compact layout is used
+pub const COMPACT_WIDTH: u16 = 80;
+
+/// Height below which the compact layout is used
+pub const COMPACT_HEIGHT: u16 = 24;
+
+/// Rows a main pane keeps before secondary panes are dropped: borders and a line of content
+pub const MAIN_MIN_HEIGHT: u16 = 3;
+
+/// Height of the controls footer
+pub const CONTROLS_HEIGHT: u16 = 3;
+
+/// Layout breakpoint, selected from the terminal size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Single-line tab and status bars, short tab labels, fewer columns
+    Compact,
+    /// The full layout
+    Regular,
+}
+
+impl Breakpoint {
+    /// Breakpoint matching the size of the terminal
+    pub fn for_area(area: Rect) -> Self {
+        if area.width < COMPACT_WIDTH || area.height < COMPACT_HEIGHT {
+            Breakpoint::Compact
+        } else {
+            Breakpoint::Regular
+        }
+    }
+
+    /// Height of the tab bar and of the status bar
+    pub fn chrome_height(&self) -> u16 {
+        match self {
+            Breakpoint::Compact => 1,
+            Breakpoint::Regular => 3,
+        }
+    }
+}
+
+/// Smallest terminal size, as `(width, height)`, in which a tab can be drawn
+pub fn min_size(tab: Tab) -> (u16, u16) {
+    match tab {
+        Tab::FileTree => (40, 10),
+        Tab::Settings => (50, 10),
+        Tab::Statistics => (40, 10),
+        Tab::Template => (40, 12),
+        Tab::PromptOutput => (40, 10),
+        Tab::Git => (50, 10),
+    }
+}
+
+/// Whether the terminal is too small to draw a tab
+pub fn is_too_small(tab: Tab, area: Rect) -> bool {
+    let (width, height) = min_size(tab);
+    area.width < width || area.height < height
+}
+
+/// A fixed-height pane stacked below the main pane of a tab
+#[derive(Debug, Clone, Copy)]
+pub struct Pane {
+    pub height: u16,
+    /// Panes with the lowest priority are dropped first when space runs out
+    pub priority: u8,
+}
+
+impl Pane {
+    pub const fn new(height: u16, priority: u8) -> Self {
+        Self { height, priority }
+    }
+}
+
+/// Split an area into a main pane on top and fixed-height panes below it.
+///
+/// Panes are dropped, lowest priority first, until the main pane keeps at least
+/// `main_min` rows. Dropped panes are returned as `None`.
+///
+/// # Arguments
+///
+/// * `area` - The area to split
+/// * `main_min` - The minimum height of the main pane
+/// * `panes` - The panes below the main pane, from top to bottom
+///
+/// # Returns
+///
+/// * `(Rect, [Option<Rect>; N])` - The main pane and the area of each pane that fits
+pub fn stack_panes<const N: usize>(
+    area: Rect,
+    main_min: u16,
+    panes: [Pane; N],
+) -> (Rect, [Option<Rect>; N]) {
+    let mut shown = [true; N];
+    let mut by_priority: Vec<usize> = (0..N).collect();
+    by_priority.sort_by_key(|&i| panes[i].priority);
+
+    let panes_height =
+        |shown: &[bool; N]| -> u16 { (0..N).filter(|&i| shown[i]).map(|i| panes[i].height).sum() };
+    for i in by_priority {
+        if area.height.saturating_sub(panes_height(&shown)) >= main_min {
+            break;
+        }
+        shown[i] = false;
+    }
+
+    let main_height = area.height.saturating_sub(panes_height(&shown));
+    let main = Rect {
+        height: main_height,
+        ..area
+    };
+    let mut y = area.y + main_height;
+    let rects = std::array::from_fn(|i| {
+        shown[i].then(|| {
+            let rect = Rect {
+                y,
+                height: panes[i].height,
+                ..area
+            };
+            y += panes[i].height;
+            rect
+        })
+    });
+    (main, rects)
+}
+
+/// Split an area into a main pane and the controls footer, dropped when space runs out.
+pub fn with_controls(area: Rect) -> (Rect, Option<Rect>) {
+    let (main, [controls]) = stack_panes(area, MAIN_MIN_HEIGHT, [Pane::new(CONTROLS_HEIGHT, 0)]);
+    (main, controls)
+}
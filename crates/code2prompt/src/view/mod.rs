@@ -5,5 +5,6 @@
 //! and return formatted strings or display structures.
 
 pub mod formatters;
+pub mod layout;
 
 pub use formatters::*;
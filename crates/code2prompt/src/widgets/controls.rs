@@ -0,0 +1,32 @@
+//! Controls footer widget, generated from the keybinding registry.
+
+use crate::model::Model;
+use crate::model::keybindings::active_bindings;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+
+/// Widget showing the bindings valid in the current tab and mode that fit on one line
+pub struct ControlsWidget<'a> {
+    pub model: &'a Model,
+}
+
+impl<'a> ControlsWidget<'a> {
+    pub fn new(model: &'a Model) -> Self {
+        Self { model }
+    }
+}
+
+impl Widget for ControlsWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bindings = active_bindings(self.model);
+        let width = area.width.saturating_sub(2) as usize; // Account for borders
+        let hint = crate::view::format_controls_hint(&bindings, &self.model.keymap, width);
+
+        Paragraph::new(hint)
+            .block(Block::default().borders(Borders::ALL).title("Controls"))
+            .style(Style::default().fg(Color::Gray))
+            .render(area, buf);
+    }
+}
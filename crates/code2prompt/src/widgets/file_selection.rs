@@ -1,6 +1,10 @@
 //! File selection widget for directory tree navigation and file selection.
 
 use crate::model::Model;
+use crate::utils::format_number;
+use crate::view::layout::{CONTROLS_HEIGHT, MAIN_MIN_HEIGHT, Pane, stack_panes};
+use crate::widgets::ControlsWidget;
+use code2prompt_core::selection::FileInclusion;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, ListItem, Paragraph},
@@ -24,15 +28,16 @@ impl<'a> StatefulWidget for FileSelectionWidget<'a> {
     type State = FileSelectionState;
 
     fn render(self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(0),    // File tree
-                Constraint::Length(3), // Search bar
-                Constraint::Length(3), // Pattern info
-                Constraint::Length(3), // Instructions
-            ])
-            .split(area);
+        // On short terminals the pattern info goes first, then the controls
+        let (tree_area, [search_area, pattern_area, controls_area]) = stack_panes(
+            area,
+            MAIN_MIN_HEIGHT,
+            [
+                Pane::new(3, 2), // Search bar
+                Pane::new(3, 0), // Pattern info
+                Pane::new(CONTROLS_HEIGHT, 1),
+            ],
+        );
 
         // File tree with scroll support - use new session-based approach
         let mut session_clone = self.model.session.clone();
@@ -44,8 +49,8 @@ impl<'a> StatefulWidget for FileSelectionWidget<'a> {
         let total_nodes = visible_nodes.len();
 
         // Calculate viewport dimensions
-        let tree_area = layout[0];
         let content_height = tree_area.height.saturating_sub(2).max(1) as usize; // Account for borders, keep >= 1
+        let inner_width = tree_area.width.saturating_sub(2) as usize;
 
         // Derive a local, clamped scroll that keeps the cursor visible
         let cursor = self.model.tree_cursor.min(total_nodes.saturating_sub(1));
@@ -76,9 +81,22 @@ impl<'a> StatefulWidget for FileSelectionWidget<'a> {
                 } else {
                     "📄"
                 };
-                let checkbox = if is_selected { "☑" } else { "☐" };
+                let checkbox = match display_node.inclusion {
+                    FileInclusion::Full => "☑",
+                    FileInclusion::TreeOnly => "◐",
+                    FileInclusion::Excluded => "☐",
+                };
 
-                let content = format!("{}{} {} {}", indent, icon, checkbox, node.name);
+                let mut content = format!("{}{} {} {}", indent, icon, checkbox, node.name);
+                // Token counts form a right-aligned column, left out when the name is too long
+                if let Some(count) = node.token_count {
+                    let count = format_number(count, &self.model.session.config.token_format);
+                    let used = Line::from(content.as_str()).width() + count.len();
+                    if used < inner_width {
+                        content.push_str(&" ".repeat(inner_width - used));
+                        content.push_str(&count);
+                    }
+                }
                 let mut style = Style::default();
 
                 // Adjust cursor position for viewport
@@ -88,6 +106,8 @@ impl<'a> StatefulWidget for FileSelectionWidget<'a> {
 
                 if is_selected {
                     style = style.fg(Color::Green);
+                } else if display_node.inclusion == FileInclusion::TreeOnly {
+                    style = style.fg(Color::Yellow);
                 }
 
                 ListItem::new(content).style(style)
@@ -114,9 +134,12 @@ impl<'a> StatefulWidget for FileSelectionWidget<'a> {
             )
             .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
 
-        Widget::render(tree_widget, layout[0], buf);
+        Widget::render(tree_widget, tree_area, buf);
 
         // Search bar - read directly from Model
+        let Some(search_area) = search_area else {
+            return;
+        };
         let title_spans = vec![
             Span::styled(
                 "s",
@@ -139,9 +162,22 @@ impl<'a> StatefulWidget for FileSelectionWidget<'a> {
                     Color::Green
                 }),
             );
-        Widget::render(search_widget, layout[1], buf);
+        Widget::render(search_widget, search_area, buf);
 
         // Pattern info
+        if let Some(pattern_area) = pattern_area {
+            self.render_pattern_info(pattern_area, buf);
+        }
+
+        // Instructions
+        if let Some(controls_area) = controls_area {
+            ControlsWidget::new(self.model).render(controls_area, buf);
+        }
+    }
+}
+
+impl FileSelectionWidget<'_> {
+    fn render_pattern_info(&self, area: Rect, buf: &mut Buffer) {
         let include_text = if self.model.session.config.include_patterns.is_empty() {
             "All files".to_string()
         } else {
@@ -167,14 +203,6 @@ impl<'a> StatefulWidget for FileSelectionWidget<'a> {
                     .title("Filter Patterns"),
             )
             .style(Style::default().fg(Color::Cyan));
-        Widget::render(pattern_widget, layout[2], buf);
-
-        // Instructions
-        let instructions = Paragraph::new(
-            "Enter: Run Analysis | ↑↓: Navigate | Space: Select/Deselect | ←→: Expand/Collapse | PgUp/PgDn: Scroll | S: Search Mode | Esc: Exit"
-        )
-        .block(Block::default().borders(Borders::ALL).title("Controls"))
-        .style(Style::default().fg(Color::Gray));
-        Widget::render(instructions, layout[3], buf);
+        Widget::render(pattern_widget, area, buf);
     }
 }
@@ -0,0 +1,260 @@
+//! Git widget for picking the branches to compare and the diff scope, and for
+//! reviewing the diff of the prompt file by file.
+
+use crate::model::{BranchDiffPreview, DiffFile, GitState, Model};
+use crate::utils::format_number;
+use crate::view::layout::with_controls;
+use crate::widgets::ControlsWidget;
+use code2prompt_core::tokenizer::TokenCountMode;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+};
+
+/// State for the git widget - read directly from Model
+pub type GitWidgetState = ();
+
+/// Widget listing the branches, the picked comparison and the diff scope options
+pub struct GitWidget<'a> {
+    pub model: &'a Model,
+}
+
+impl<'a> GitWidget<'a> {
+    pub fn new(model: &'a Model) -> Self {
+        Self { model }
+    }
+
+    fn branch_items(&self) -> Vec<ListItem<'a>> {
+        let git = &self.model.git;
+        let Some(branches) = git.branches.as_ref() else {
+            return vec![ListItem::new("  Loading branches...")];
+        };
+        if let Some(error) = &git.load_error {
+            return vec![
+                ListItem::new(format!("  {}", error)).style(Style::default().fg(Color::Red)),
+            ];
+        }
+        if branches.is_empty() {
+            return vec![ListItem::new("  No branch").style(Style::default().fg(Color::Gray))];
+        }
+
+        branches
+            .iter()
+            .enumerate()
+            .map(|(index, branch)| {
+                let (marker, mut style) = if git.base.as_ref() == Some(branch) {
+                    ("base", Style::default().fg(Color::Green))
+                } else if git.head.as_ref() == Some(branch) {
+                    ("head", Style::default().fg(Color::Cyan))
+                } else {
+                    ("", Style::default())
+                };
+                if index == git.cursor {
+                    style = style
+                        .bg(Color::Blue)
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD);
+                }
+                ListItem::new(format!(" {:<5} {}", marker, branch)).style(style)
+            })
+            .collect()
+    }
+
+    fn comparison_lines(&self) -> Vec<Line<'a>> {
+        let git = &self.model.git;
+        let config = &self.model.session.config;
+        let label = Style::default().fg(Color::Yellow);
+
+        let comparison = match (&git.base, &git.head) {
+            (Some(base), Some(head)) => format!("{}..{}", base, head),
+            (Some(base), None) => format!("{}..(pick the head)", base),
+            _ => "(pick the base)".to_string(),
+        };
+        let preview = match &git.preview {
+            BranchDiffPreview::None => "-".to_string(),
+            BranchDiffPreview::InProgress => "counting...".to_string(),
+            BranchDiffPreview::Tokens(tokens) => {
+                let mut tokens = format_number(*tokens, &config.token_format);
+                if config.token_count_mode == TokenCountMode::Heuristic {
+                    tokens = format!("~{}", tokens);
+                }
+                format!("{} tokens", tokens)
+            }
+            BranchDiffPreview::Failed(error) => error.clone(),
+        };
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("Comparison: ", label),
+                Span::raw(comparison),
+            ]),
+            Line::from(vec![
+                Span::styled("Diff size:  ", label),
+                Span::raw(preview),
+            ]),
+            Line::from(""),
+            Line::styled("── Diff scope ──", label.add_modifier(Modifier::BOLD)),
+        ];
+        for (key, name, value) in GitState::scope_options(config) {
+            lines.push(Line::from(vec![
+                Span::styled(format!(" {:<4}", key), Style::default().fg(Color::Gray)),
+                Span::raw(format!("{:<20}", name)),
+                Span::styled(value, Style::default().fg(Color::Cyan)),
+            ]));
+        }
+        lines
+    }
+
+    fn diff_items(&self) -> Vec<ListItem<'a>> {
+        let git = &self.model.git;
+        let Some(files) = git.diff_files.as_ref() else {
+            return vec![ListItem::new("  Loading the diff...")];
+        };
+        if let Some(error) = &git.diff_error {
+            return vec![
+                ListItem::new(format!("  {}", error)).style(Style::default().fg(Color::Red)),
+            ];
+        }
+        if files.is_empty() {
+            return vec![ListItem::new("  No change").style(Style::default().fg(Color::Gray))];
+        }
+
+        git.diff_rows()
+            .into_iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let file = &files[row.file];
+                let excluded = git.excluded_diff_files.contains(&file.path);
+                let mut item = match row.line {
+                    None => file_header(file, excluded),
+                    Some(line) => diff_line(&file.lines[line], excluded),
+                };
+                if index == git.diff_cursor {
+                    item = item.style(Style::default().bg(Color::Blue).fg(Color::White));
+                }
+                item
+            })
+            .collect()
+    }
+
+    fn diff_title(&self) -> String {
+        let git = &self.model.git;
+        let config = &self.model.session.config;
+        let source = match &config.diff_branches {
+            Some((base, head)) => format!("{}..{}", base, head),
+            None => "working tree".to_string(),
+        };
+        let mut title = format!("Diff of the {}", source);
+        if let Some(files) = &git.diff_files {
+            let excluded = files
+                .iter()
+                .filter(|file| git.excluded_diff_files.contains(&file.path))
+                .count();
+            title.push_str(&format!(" ({} files, {} excluded)", files.len(), excluded));
+        }
+        if config.diff_branches.is_none() && !config.working_diff_enabled() {
+            title.push_str(" - not in the prompt, enable Git diff in the settings");
+        }
+        title
+    }
+}
+
+/// The header row of a file of the diff viewer
+fn file_header<'a>(file: &DiffFile, excluded: bool) -> ListItem<'a> {
+    let marker = if file.expanded { "▼" } else { "▶" };
+    if excluded {
+        return ListItem::new(format!("{} {}  (excluded)", marker, file.path)).style(
+            Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::CROSSED_OUT),
+        );
+    }
+    ListItem::new(Line::from(vec![
+        Span::styled(
+            format!("{} {}  ", marker, file.path),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("+{}", file.added),
+            Style::default().fg(Color::Green),
+        ),
+        Span::raw(" "),
+        Span::styled(
+            format!("-{}", file.removed),
+            Style::default().fg(Color::Red),
+        ),
+    ]))
+}
+
+/// A line of an expanded file, colored by its kind
+fn diff_line<'a>(line: &str, excluded: bool) -> ListItem<'a> {
+    let style = if excluded {
+        Style::default().fg(Color::DarkGray)
+    } else if line.starts_with("+++") || line.starts_with("---") {
+        Style::default()
+            .fg(Color::Gray)
+            .add_modifier(Modifier::BOLD)
+    } else if line.starts_with('+') {
+        Style::default().fg(Color::Green)
+    } else if line.starts_with('-') {
+        Style::default().fg(Color::Red)
+    } else if line.starts_with("@@") {
+        Style::default().fg(Color::Cyan)
+    } else if !line.starts_with(' ') {
+        // Extended headers: index, mode and rename lines
+        Style::default().fg(Color::Gray)
+    } else {
+        Style::default()
+    };
+    ListItem::new(format!("    {}", line.replace('\t', "    "))).style(style)
+}
+
+impl<'a> StatefulWidget for GitWidget<'a> {
+    type State = GitWidgetState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        let (content, controls) = with_controls(area);
+        if self.model.git.show_diff {
+            // Scroll the diff so the cursor stays visible
+            let mut list_state =
+                ListState::default().with_selected(Some(self.model.git.diff_cursor));
+            let diff = List::new(self.diff_items()).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(self.diff_title()),
+            );
+            StatefulWidget::render(diff, content, buf, &mut list_state);
+            if let Some(controls) = controls {
+                ControlsWidget::new(self.model).render(controls, buf);
+            }
+            return;
+        }
+
+        let [branches_area, comparison_area] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(content);
+
+        // Scroll the list so the cursor stays visible
+        let git = &self.model.git;
+        let selected = git
+            .branches
+            .as_ref()
+            .filter(|b| !b.is_empty())
+            .map(|_| git.cursor);
+        let mut list_state = ListState::default().with_selected(selected);
+        let branches = List::new(self.branch_items())
+            .block(Block::default().borders(Borders::ALL).title("Branches"));
+        StatefulWidget::render(branches, branches_area, buf, &mut list_state);
+
+        Paragraph::new(self.comparison_lines())
+            .block(Block::default().borders(Borders::ALL).title("Comparison"))
+            .wrap(Wrap { trim: false })
+            .render(comparison_area, buf);
+
+        if let Some(controls) = controls {
+            ControlsWidget::new(self.model).render(controls, buf);
+        }
+    }
+}
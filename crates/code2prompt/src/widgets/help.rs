@@ -0,0 +1,75 @@
+//! Full-screen help overlay listing the keybindings of the current tab and mode.
+
+use crate::model::Model;
+use crate::model::keybindings::{active_contexts, bindings_for};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// Help overlay widget
+pub struct HelpWidget<'a> {
+    pub model: &'a Model,
+}
+
+impl<'a> HelpWidget<'a> {
+    pub fn new(model: &'a Model) -> Self {
+        Self { model }
+    }
+}
+
+impl Widget for HelpWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let keymap = &self.model.keymap;
+        let contexts = active_contexts(self.model);
+        let key_width = contexts
+            .iter()
+            .flat_map(|&context| bindings_for(context))
+            .map(|binding| binding.label(keymap).chars().count())
+            .max()
+            .unwrap_or_default();
+
+        let mut lines = Vec::new();
+        for context in contexts {
+            lines.push(Line::from(Span::styled(
+                format!("── {} ──", context.title()),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            for binding in bindings_for(context) {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  {:<width$}  ", binding.label(keymap), width = key_width),
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(binding.description),
+                ]));
+            }
+            lines.push(Line::from(""));
+        }
+
+        // Scroll when the bindings do not fit on the screen
+        let content_height = area.height.saturating_sub(2) as usize; // Account for borders
+        let max_scroll = lines.len().saturating_sub(content_height);
+        let scroll = (self.model.help_scroll as usize).min(max_scroll);
+        let title = if max_scroll > 0 {
+            "Help (↑↓/PgUp/PgDn: Scroll | ? or Esc to close)"
+        } else {
+            "Help (? or Esc to close)"
+        };
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(Style::default().fg(Color::Yellow)),
+            )
+            .scroll((scroll as u16, 0))
+            .render(area, buf);
+    }
+}
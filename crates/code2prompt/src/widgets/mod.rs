@@ -3,18 +3,32 @@
 //! This module contains all the widget implementations using Ratatui's native widget system.
 //! Each widget is responsible for rendering a specific part of the UI and managing its own state.
 
+pub mod controls;
 pub mod file_selection;
+pub mod git;
+pub mod help;
 pub mod output;
 pub mod settings;
 pub mod statistics_by_extension;
+pub mod statistics_chart;
+pub mod statistics_delta;
 pub mod statistics_overview;
 pub mod statistics_token_map;
 pub mod template;
+pub mod toast;
+pub mod too_small;
 
+pub use controls::ControlsWidget;
 pub use file_selection::FileSelectionWidget;
+pub use git::GitWidget;
+pub use help::HelpWidget;
 pub use output::OutputWidget;
 pub use settings::SettingsWidget;
 pub use statistics_by_extension::StatisticsByExtensionWidget;
+pub use statistics_chart::StatisticsChartWidget;
+pub use statistics_delta::StatisticsDeltaWidget;
 pub use statistics_overview::StatisticsOverviewWidget;
 pub use statistics_token_map::StatisticsTokenMapWidget;
 pub use template::TemplateWidget;
+pub use toast::ToastWidget;
+pub use too_small::TooSmallWidget;
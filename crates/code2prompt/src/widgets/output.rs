@@ -1,9 +1,11 @@
 //! Output widget for displaying generated prompt with scrolling capability.
 
-use crate::model::Model;
+use crate::model::{Model, PromptOutputState};
+use crate::view::layout::{MAIN_MIN_HEIGHT, with_controls};
+use crate::widgets::ControlsWidget;
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, Gauge, Paragraph, Wrap},
 };
 
 /// State for the output widget - no longer needed, read directly from Model
@@ -18,28 +20,93 @@ impl<'a> OutputWidget<'a> {
     pub fn new(model: &'a Model) -> Self {
         Self { model }
     }
+
+    /// Render the prompt, scrolled and with the search matches highlighted
+    fn render_prompt(&self, prompt_area: Rect, buf: &mut Buffer) {
+        let output = &self.model.prompt_output;
+        // Prompt content
+        let content = if self.model.prompt_output.analysis_in_progress {
+            Text::from("Generating prompt...")
+        } else if let Some(prompt) = &self.model.prompt_output.generated_prompt {
+            highlight_matches(prompt, output)
+        } else {
+            Text::from(
+                "Press <Enter> to run analysis and generate prompt.\n\nSelected files will be processed according to your settings.",
+            )
+        };
+
+        // Compute viewport-aware scroll
+        let content_height = prompt_area.height.saturating_sub(2).max(1) as usize; // borders
+        let (display_scroll, scroll_info) =
+            if let Some(prompt) = &self.model.prompt_output.generated_prompt {
+                let total_lines = prompt.lines().count();
+                let max_scroll = total_lines.saturating_sub(content_height);
+                let ds = self
+                    .model
+                    .prompt_output
+                    .output_scroll
+                    .min(max_scroll as u16);
+                let current_line = ds as usize + 1;
+                let mut title = format!("Generated Prompt (Line {}/{})", current_line, total_lines);
+                if !output.search_matches.is_empty() {
+                    title.push_str(&format!(
+                        " | Match {}/{}",
+                        output.search_index + 1,
+                        output.search_matches.len()
+                    ));
+                }
+                (ds, title)
+            } else {
+                (
+                    self.model.prompt_output.output_scroll,
+                    "Generated Prompt".to_string(),
+                )
+            };
+
+        let prompt_widget = Paragraph::new(content)
+            .block(Block::default().borders(Borders::ALL).title(scroll_info))
+            .wrap(Wrap { trim: false })
+            .scroll((display_scroll, 0));
+        Widget::render(prompt_widget, prompt_area, buf);
+    }
 }
 
 impl<'a> StatefulWidget for OutputWidget<'a> {
     type State = OutputState;
 
     fn render(self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(3), // Info bar
-                Constraint::Min(0),    // Prompt content
-                Constraint::Length(3), // Controls
-            ])
-            .split(area);
+        // On short terminals the info bar goes first, then the controls
+        let (body, controls_area) = with_controls(area);
+        let (info_area, prompt_area) = if body.height >= MAIN_MIN_HEIGHT + 3 {
+            let [info, prompt] =
+                Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(body);
+            (Some(info), prompt)
+        } else {
+            (None, body)
+        };
 
         // Simplified status bar - focus only on prompt availability
-        let info_text = if self.model.prompt_output.analysis_in_progress {
+        let output = &self.model.prompt_output;
+        let info_text = if output.search_active || !output.search_query.is_empty() {
+            let cursor = if output.search_active { "█" } else { "" };
+            let position = if output.search_matches.is_empty() {
+                "no match".to_string()
+            } else {
+                format!(
+                    "match {}/{}",
+                    output.search_index + 1,
+                    output.search_matches.len()
+                )
+            };
+            format!("Search: {}{} ({})", output.search_query, cursor, position)
+        } else if let Some(progress) = &output.progress {
+            progress.label()
+        } else if self.model.prompt_output.analysis_in_progress {
             "Generating prompt...".to_string()
         } else if let Some(error) = &self.model.prompt_output.analysis_error {
             format!("Generation failed: {}", error)
         } else if self.model.prompt_output.generated_prompt.is_some() {
-            "✓ Prompt ready! Copy (C) or Save (S)".to_string()
+            "✓ Prompt ready! Copy (C/Y) or Save (S)".to_string()
         } else {
             "Press Enter to generate prompt from selected files".to_string()
         };
@@ -57,56 +124,72 @@ impl<'a> StatefulWidget for OutputWidget<'a> {
             } else {
                 Style::default().fg(Color::Green)
             });
-        Widget::render(info_widget, layout[0], buf);
+        if let Some(info_area) = info_area {
+            Widget::render(info_widget, info_area, buf);
+        }
 
-        // Prompt content
-        let content = if self.model.prompt_output.analysis_in_progress {
-            "Generating prompt...".to_string()
-        } else if let Some(prompt) = &self.model.prompt_output.generated_prompt {
-            prompt.clone()
-        } else {
-            "Press <Enter> to run analysis and generate prompt.\n\nSelected files will be processed according to your settings.".to_string()
-        };
-
-        // Compute viewport-aware scroll
-        let content_height = layout[1].height.saturating_sub(2).max(1) as usize; // borders
-        let (display_scroll, scroll_info) =
-            if let Some(prompt) = &self.model.prompt_output.generated_prompt {
-                let total_lines = prompt.lines().count();
-                let max_scroll = total_lines.saturating_sub(content_height);
-                let ds = self
-                    .model
-                    .prompt_output
-                    .output_scroll
-                    .min(max_scroll as u16);
-                let current_line = ds as usize + 1;
-                (
-                    ds,
-                    format!("Generated Prompt (Line {}/{})", current_line, total_lines),
+        // Progress bar of the running analysis
+        if let Some(progress) = &output.progress {
+            Gauge::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Generating Prompt"),
                 )
-            } else {
-                (
-                    self.model.prompt_output.output_scroll,
-                    "Generated Prompt".to_string(),
-                )
-            };
-
-        let prompt_widget = Paragraph::new(content)
-            .block(Block::default().borders(Borders::ALL).title(scroll_info))
-            .wrap(Wrap { trim: false })
-            .scroll((display_scroll, 0));
-        Widget::render(prompt_widget, layout[1], buf);
+                .gauge_style(Style::default().fg(Color::Yellow))
+                .ratio(progress.ratio())
+                .label(progress.label())
+                .render(prompt_area, buf);
+        } else {
+            self.render_prompt(prompt_area, buf);
+        }
 
         // Controls
-        let controls_text = if self.model.prompt_output.generated_prompt.is_some() {
-            "↑↓/PgUp/PgDn: Scroll | C: Copy | S: Save | Enter: Re-run"
-        } else {
-            "Enter: Run Analysis"
-        };
+        if let Some(controls_area) = controls_area {
+            ControlsWidget::new(self.model).render(controls_area, buf);
+        }
+    }
+}
 
-        let controls_widget = Paragraph::new(controls_text)
-            .block(Block::default().borders(Borders::ALL).title("Controls"))
-            .style(Style::default().fg(Color::Gray));
-        Widget::render(controls_widget, layout[2], buf);
+/// Splits the prompt into lines, highlighting the matches of the search query and the
+/// current match apart
+fn highlight_matches<'a>(prompt: &'a str, output: &PromptOutputState) -> Text<'a> {
+    if output.search_matches.is_empty() {
+        return Text::from(prompt);
     }
+    let match_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let current_style = Style::default().fg(Color::Black).bg(Color::LightRed);
+    let query_len = output.search_query.len();
+    let current = output.search_matches.get(output.search_index).copied();
+
+    let mut matches = output.search_matches.iter().peekable();
+    let lines: Vec<Line> = prompt
+        .lines()
+        .enumerate()
+        .map(|(line_index, line)| {
+            let mut spans = Vec::new();
+            let mut start = 0;
+            while let Some(&&(match_line, offset)) = matches.peek() {
+                if match_line != line_index {
+                    break;
+                }
+                matches.next();
+                // Overlapping matches are highlighted as one
+                if offset < start {
+                    continue;
+                }
+                let style = if current == Some((match_line, offset)) {
+                    current_style
+                } else {
+                    match_style
+                };
+                spans.push(Span::raw(&line[start..offset]));
+                spans.push(Span::styled(&line[offset..offset + query_len], style));
+                start = offset + query_len;
+            }
+            spans.push(Span::raw(&line[start..]));
+            Line::from(spans)
+        })
+        .collect();
+    Text::from(lines)
 }
@@ -1,9 +1,11 @@
 //! Settings widget for configuration management.
 
-use crate::model::Model;
+use crate::model::{Model, PatternKind};
+use crate::view::layout::{COMPACT_WIDTH, with_controls};
+use crate::widgets::ControlsWidget;
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
 };
 
 /// State for the settings widget - no longer needed, read directly from Model
@@ -20,23 +22,112 @@ impl<'a> SettingsWidget<'a> {
     }
 }
 
+impl SettingsWidget<'_> {
+    /// Renders the pattern editor with the files each pattern matches, and the input of
+    /// the new pattern while one is typed.
+    fn render_patterns(&self, area: Rect, buf: &mut Buffer) {
+        let editor = &self.model.settings.patterns;
+        let preview = editor.preview(&self.model.session, &self.model.file_tree_nodes);
+        let (content, controls) = with_controls(area);
+
+        let mut items: Vec<ListItem> = Vec::new();
+        let mut cursor_row = None;
+        for (kind, title, empty) in [
+            (
+                PatternKind::Include,
+                "Include Patterns",
+                "(none, every file is included)",
+            ),
+            (PatternKind::Exclude, "Exclude Patterns", "(none)"),
+        ] {
+            items.push(
+                ListItem::new(format!("── {} ──", title)).style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            );
+            let mut listed = false;
+            for (index, row) in preview.rows.iter().enumerate() {
+                if row.kind != kind {
+                    continue;
+                }
+                listed = true;
+                let mut style = Style::default().fg(match kind {
+                    PatternKind::Include => Color::Green,
+                    PatternKind::Exclude => Color::Red,
+                });
+                if index == editor.cursor && !editor.is_typing() {
+                    cursor_row = Some(items.len());
+                    style = style.bg(Color::Blue).add_modifier(Modifier::BOLD);
+                }
+                let files = if row.matches == 1 { "file" } else { "files" };
+                items.push(
+                    ListItem::new(format!("  {:<40} {} {}", row.pattern, row.matches, files))
+                        .style(style),
+                );
+            }
+            if !listed {
+                items.push(
+                    ListItem::new(format!("  {}", empty)).style(Style::default().fg(Color::Gray)),
+                );
+            }
+            items.push(ListItem::new(""));
+        }
+        items.push(ListItem::new(format!(
+            "  Kept by the patterns: {} of {} loaded files",
+            preview.kept_files, preview.loaded_files
+        )));
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Settings › Filter Patterns"),
+        );
+        let mut list_state = ListState::default().with_selected(cursor_row);
+        StatefulWidget::render(list, content, buf, &mut list_state);
+
+        let Some(controls) = controls else {
+            return;
+        };
+        match &editor.input {
+            Some((kind, input)) => {
+                let matches = match preview.input_matches {
+                    Some(count) => format!("matches {} loaded files", count),
+                    None => "type a glob".to_string(),
+                };
+                let input = Paragraph::new(format!("{}: {}", kind.as_str(), input))
+                    .block(Block::default().borders(Borders::ALL).title(format!(
+                        "New pattern ({} | Enter: Add | Esc: Cancel)",
+                        matches
+                    )))
+                    .style(Style::default().fg(Color::Yellow));
+                Widget::render(input, controls, buf);
+            }
+            None => ControlsWidget::new(self.model).render(controls, buf),
+        }
+    }
+}
+
 impl<'a> StatefulWidget for SettingsWidget<'a> {
     type State = SettingsState;
 
     fn render(self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
+        if self.model.settings.patterns.active {
+            self.render_patterns(area, buf);
+            return;
+        }
+
         let settings_groups = self.model.get_settings_groups();
 
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(0),    // Settings list
-                Constraint::Length(3), // Instructions
-            ])
-            .split(area);
+        let (content, controls) = with_controls(area);
 
         // Build grouped settings display
         let mut items: Vec<ListItem> = Vec::new();
         let mut item_index = 0;
+        let mut cursor_row = None;
+        // Descriptions only fit on wide terminals
+        let show_description = content.width >= COMPACT_WIDTH;
 
         for group in &settings_groups {
             // Group header
@@ -66,14 +157,21 @@ impl<'a> StatefulWidget for SettingsWidget<'a> {
                 };
 
                 // Better aligned layout: Name (20 chars) | Value (15 chars) | Description
-                let content = format!(
-                    "  {:<20} {:<15} {}",
-                    item.name, value_display, item.description
-                );
+                // Settings changed from the launch configuration are marked with `*`
+                let marker = if item.modified { "*" } else { " " };
+                let line = if show_description {
+                    format!(
+                        " {}{:<20} {:<15} {}",
+                        marker, item.name, value_display, item.description
+                    )
+                } else {
+                    format!(" {}{:<20} {}", marker, item.name, value_display)
+                };
                 let mut style = Style::default();
 
                 // Read cursor directly from Model
                 if item_index == self.model.settings.settings_cursor {
+                    cursor_row = Some(items.len());
                     style = style
                         .bg(Color::Blue)
                         .fg(Color::White)
@@ -93,7 +191,7 @@ impl<'a> StatefulWidget for SettingsWidget<'a> {
                     }
                 }
 
-                items.push(ListItem::new(content).style(style));
+                items.push(ListItem::new(line).style(style));
                 item_index += 1;
             }
 
@@ -101,18 +199,41 @@ impl<'a> StatefulWidget for SettingsWidget<'a> {
             items.push(ListItem::new(""));
         }
 
-        let settings_widget = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("Settings"))
-            .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
-
-        Widget::render(settings_widget, layout[0], buf);
+        let settings = &self.model.settings;
+        if settings_groups.is_empty() {
+            items.push(
+                ListItem::new(format!("  No setting matches '{}'", settings.search_query))
+                    .style(Style::default().fg(Color::Gray)),
+            );
+        }
 
-        // Instructions
-        let instructions = Paragraph::new(
-            "Enter: Run Analysis | ↑↓: Navigate | Space: Toggle | ←→: Cycle Options",
-        )
-        .block(Block::default().borders(Borders::ALL).title("Controls"))
-        .style(Style::default().fg(Color::Gray));
-        Widget::render(instructions, layout[1], buf);
+        let title = if settings.search_query.is_empty() {
+            "Settings".to_string()
+        } else {
+            format!("Settings (filter: {})", settings.search_query)
+        };
+        let settings_widget =
+            List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+
+        // Scroll the list so the cursor stays visible on short terminals
+        let mut list_state = ListState::default().with_selected(cursor_row);
+        StatefulWidget::render(settings_widget, content, buf, &mut list_state);
+
+        // Instructions, replaced by the search input while searching
+        let Some(controls) = controls else {
+            return;
+        };
+        if settings.search_active {
+            let instructions = Paragraph::new(format!("/{}", settings.search_query))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Search (Enter: Toggle | ↑↓: Navigate | Esc: Exit)"),
+                )
+                .style(Style::default().fg(Color::Yellow));
+            Widget::render(instructions, controls, buf);
+        } else {
+            ControlsWidget::new(self.model).render(controls, buf);
+        }
     }
 }
@@ -1,6 +1,8 @@
 //! Statistics by extension widget for displaying extension-based histogram.
 
 use crate::model::{Model, StatisticsState};
+use crate::view::layout::with_controls;
+use crate::widgets::ControlsWidget;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
@@ -24,13 +26,7 @@ impl<'a> StatefulWidget for StatisticsByExtensionWidget<'a> {
     type State = ExtensionState;
 
     fn render(self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(0),    // Extension statistics content
-                Constraint::Length(3), // Instructions
-            ])
-            .split(area);
+        let (content, controls) = with_controls(area);
 
         let title = "📁 By Extension";
 
@@ -47,14 +43,12 @@ impl<'a> StatefulWidget for StatisticsByExtensionWidget<'a> {
                 .style(Style::default().fg(Color::Gray))
                 .alignment(Alignment::Center);
 
-            Widget::render(placeholder_widget, layout[0], buf);
+            Widget::render(placeholder_widget, content, buf);
 
             // Instructions
-            let instructions =
-                Paragraph::new("Enter: Run Analysis | ←→: Switch View | Tab/Shift+Tab: Switch Tab")
-                    .block(Block::default().borders(Borders::ALL).title("Controls"))
-                    .style(Style::default().fg(Color::Gray));
-            Widget::render(instructions, layout[1], buf);
+            if let Some(controls) = controls {
+                ControlsWidget::new(self.model).render(controls, buf);
+            }
             return;
         }
 
@@ -63,14 +57,14 @@ impl<'a> StatefulWidget for StatisticsByExtensionWidget<'a> {
         let total_tokens = self.model.prompt_output.token_count.unwrap_or(0);
 
         // Calculate viewport for scrolling - read directly from Model
-        let content_height = layout[0].height.saturating_sub(2).max(1) as usize;
+        let content_height = content.height.saturating_sub(2).max(1) as usize;
         let total = ext_vec.len();
         let max_scroll = total.saturating_sub(content_height);
         let scroll_start = (self.model.statistics.scroll as usize).min(max_scroll);
         let scroll_end = (scroll_start + content_height).min(total);
 
         // Calculate dynamic column widths based on available space and content
-        let available_width = layout[0].width.saturating_sub(4) as usize; // Account for borders and padding
+        let available_width = content.width.saturating_sub(4) as usize; // Account for borders and padding
 
         // Calculate maximum widths needed for each column
         let max_ext_width = ext_vec
@@ -210,12 +204,11 @@ impl<'a> StatefulWidget for StatisticsByExtensionWidget<'a> {
             .block(Block::default().borders(Borders::ALL).title(scroll_title))
             .style(Style::default().fg(Color::White));
 
-        Widget::render(extensions_widget, layout[0], buf);
+        Widget::render(extensions_widget, content, buf);
 
         // Instructions
-        let instructions = Paragraph::new("Enter: Run Analysis | ←→: Switch View | ↑↓/PgUp/PgDn: Scroll | Tab/Shift+Tab: Switch Tab")
-            .block(Block::default().borders(Borders::ALL).title("Controls"))
-            .style(Style::default().fg(Color::Gray));
-        Widget::render(instructions, layout[1], buf);
+        if let Some(controls) = controls {
+            ControlsWidget::new(self.model).render(controls, buf);
+        }
     }
 }
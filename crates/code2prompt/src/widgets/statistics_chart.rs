@@ -0,0 +1,119 @@
+//! Statistics chart widget for displaying the token distribution as bar charts.
+
+use crate::model::{BreakdownRow, Model, StatisticsState};
+use crate::view::layout::with_controls;
+use crate::widgets::ControlsWidget;
+use ratatui::{
+    prelude::*,
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Paragraph, Wrap},
+};
+
+/// Width of each bar, its label being cut to it
+const BAR_WIDTH: u16 = 9;
+
+/// Gap between two bars
+const BAR_GAP: u16 = 1;
+
+/// Widget for the token distribution by directory and by extension (stateless)
+pub struct StatisticsChartWidget<'a> {
+    pub model: &'a Model,
+}
+
+impl<'a> StatisticsChartWidget<'a> {
+    pub fn new(model: &'a Model) -> Self {
+        Self { model }
+    }
+
+    /// Renders the largest groups of a breakdown that fit the width, largest first
+    fn render_chart(
+        &self,
+        area: Rect,
+        buf: &mut Buffer,
+        title: &str,
+        color: Color,
+        rows: &[BreakdownRow],
+    ) {
+        let token_format = &self.model.session.config.token_format;
+        let fitting = (area.width.saturating_sub(2) + BAR_GAP) / (BAR_WIDTH + BAR_GAP);
+        let shown = rows.len().min(fitting.max(1) as usize);
+        let bars: Vec<Bar> = rows
+            .iter()
+            .take(shown)
+            .map(|row| {
+                Bar::default()
+                    .value(row.tokens as u64)
+                    .text_value(StatisticsState::format_number(row.tokens, token_format))
+                    .label(Line::from(truncate(&row.name, BAR_WIDTH as usize)))
+            })
+            .collect();
+
+        let title = if rows.len() > shown {
+            format!("{} | Largest {} of {}", title, shown, rows.len())
+        } else {
+            title.to_string()
+        };
+        BarChart::default()
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .bar_width(BAR_WIDTH)
+            .bar_gap(BAR_GAP)
+            .bar_style(Style::default().fg(color))
+            .value_style(Style::default().fg(Color::Black).bg(color))
+            .label_style(Style::default().fg(Color::White))
+            .data(BarGroup::default().bars(&bars))
+            .render(area, buf);
+    }
+}
+
+impl<'a> Widget for StatisticsChartWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (content, controls) = with_controls(area);
+        let distribution = &self.model.statistics.token_distribution;
+
+        if distribution.directories.is_empty() {
+            let placeholder_text = if self.model.prompt_output.generated_prompt.is_some() {
+                "\nNo files in the prompt.\n\nPress Enter to re-run analysis."
+            } else {
+                "\nRun analysis first to see the token distribution.\n\nPress Enter to run analysis."
+            };
+            Paragraph::new(placeholder_text)
+                .block(Block::default().borders(Borders::ALL).title("📊 Chart"))
+                .wrap(Wrap { trim: true })
+                .style(Style::default().fg(Color::Gray))
+                .alignment(Alignment::Center)
+                .render(content, buf);
+        } else {
+            let charts = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(content);
+            self.render_chart(
+                charts[0],
+                buf,
+                "📂 Tokens by Directory",
+                Color::Blue,
+                &distribution.directories,
+            );
+            self.render_chart(
+                charts[1],
+                buf,
+                "🏷️  Tokens by Extension",
+                Color::Green,
+                &distribution.extensions,
+            );
+        }
+
+        if let Some(controls) = controls {
+            ControlsWidget::new(self.model).render(controls, buf);
+        }
+    }
+}
+
+/// Cuts a label to a width, marking the cut with an ellipsis
+fn truncate(label: &str, width: usize) -> String {
+    if label.chars().count() <= width {
+        label.to_string()
+    } else {
+        let kept: String = label.chars().take(width.saturating_sub(1)).collect();
+        format!("{}…", kept)
+    }
+}
@@ -0,0 +1,157 @@
+//! Statistics delta widget for displaying what changed since the previous run.
+
+use crate::model::{Model, StatisticsState};
+use crate::view::layout::with_controls;
+use crate::widgets::ControlsWidget;
+use code2prompt_core::snapshot::FileDelta;
+use code2prompt_core::tokenizer::TokenFormat;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+/// Widget for the token and file deltas between the last two runs (stateless)
+pub struct StatisticsDeltaWidget<'a> {
+    pub model: &'a Model,
+}
+
+impl<'a> StatisticsDeltaWidget<'a> {
+    pub fn new(model: &'a Model) -> Self {
+        Self { model }
+    }
+
+    /// A token change with its sign, as `+1,234` or `-56`
+    fn signed(&self, delta: i64) -> String {
+        let sign = if delta < 0 { "-" } else { "+" };
+        format!(
+            "{}{}",
+            sign,
+            StatisticsState::format_number(delta.unsigned_abs() as usize, self.token_format())
+        )
+    }
+
+    fn token_format(&self) -> &TokenFormat {
+        &self.model.session.config.token_format
+    }
+
+    /// The rows of a section of the delta, under its heading
+    fn section(
+        &self,
+        items: &mut Vec<ListItem<'a>>,
+        title: &str,
+        color: Color,
+        deltas: &[FileDelta],
+    ) {
+        if deltas.is_empty() {
+            return;
+        }
+        let net: i64 = deltas.iter().map(FileDelta::delta).sum();
+        items.push(
+            ListItem::new(format!(
+                "{} ({} files, {} tokens)",
+                title,
+                deltas.len(),
+                self.signed(net)
+            ))
+            .style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        );
+        let delta_width = deltas
+            .iter()
+            .map(|delta| self.signed(delta.delta()).len())
+            .max()
+            .unwrap_or(0);
+        for delta in deltas {
+            items.push(ListItem::new(format!(
+                "  {:>delta_width$}  {}  ({} → {})",
+                self.signed(delta.delta()),
+                delta.path,
+                StatisticsState::format_number(delta.before, self.token_format()),
+                StatisticsState::format_number(delta.after, self.token_format()),
+            )));
+        }
+        items.push(ListItem::new(""));
+    }
+}
+
+impl<'a> Widget for StatisticsDeltaWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (content, controls) = with_controls(area);
+        let title = "🔀 Since Last Run";
+
+        let Some(diff) = &self.model.statistics.snapshot_diff else {
+            let placeholder_text = if self.model.prompt_output.generated_prompt.is_some() {
+                "\nNo previous run to compare with.\n\nPress Enter to re-run analysis."
+            } else {
+                "\nRun analysis twice to see what changed between the runs.\n\nPress Enter to run analysis."
+            };
+            Paragraph::new(placeholder_text)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .wrap(Wrap { trim: true })
+                .style(Style::default().fg(Color::Gray))
+                .alignment(Alignment::Center)
+                .render(content, buf);
+            if let Some(controls) = controls {
+                ControlsWidget::new(self.model).render(controls, buf);
+            }
+            return;
+        };
+
+        let token_delta = diff.token_delta();
+        let color = match token_delta {
+            0 => Color::Gray,
+            delta if delta > 0 => Color::Red,
+            _ => Color::Green,
+        };
+        let mut items = vec![
+            ListItem::new(format!(
+                "Total: {} → {} tokens ({})",
+                StatisticsState::format_number(diff.before_tokens, self.token_format()),
+                StatisticsState::format_number(diff.after_tokens, self.token_format()),
+                self.signed(token_delta)
+            ))
+            .style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            ListItem::new(format!(
+                "  Files: {} tokens, template and other content: {} tokens, {} files unchanged",
+                self.signed(diff.files_token_delta()),
+                self.signed(token_delta - diff.files_token_delta()),
+                diff.unchanged
+            )),
+            ListItem::new(""),
+        ];
+        if diff.is_empty() {
+            items.push(
+                ListItem::new("No change since the previous run.")
+                    .style(Style::default().fg(Color::Gray)),
+            );
+        }
+        self.section(&mut items, "➕ Added", Color::Red, &diff.added);
+        self.section(&mut items, "➖ Removed", Color::Green, &diff.removed);
+        self.section(&mut items, "✏️  Changed", Color::Yellow, &diff.changed);
+
+        let content_height = content.height.saturating_sub(2).max(1) as usize;
+        let total = items.len();
+        let scroll_start =
+            (self.model.statistics.scroll as usize).min(total.saturating_sub(content_height));
+        let title = if total > content_height {
+            format!(
+                "{} | Showing {}-{} of {}",
+                title,
+                scroll_start + 1,
+                (scroll_start + content_height).min(total),
+                total
+            )
+        } else {
+            title.to_string()
+        };
+        let items: Vec<ListItem> = items.into_iter().skip(scroll_start).collect();
+        Widget::render(
+            List::new(items).block(Block::default().borders(Borders::ALL).title(title)),
+            content,
+            buf,
+        );
+
+        if let Some(controls) = controls {
+            ControlsWidget::new(self.model).render(controls, buf);
+        }
+    }
+}
@@ -1,5 +1,7 @@
 //! Statistics overview widget for displaying analysis summary.
-use crate::model::{Model, StatisticsState};
+use crate::model::{BreakdownRow, Model, StatisticsState};
+use crate::view::layout::with_controls;
+use crate::widgets::ControlsWidget;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
@@ -14,17 +16,46 @@ impl<'a> StatisticsOverviewWidget<'a> {
     pub fn new(model: &'a Model) -> Self {
         Self { model }
     }
+
+    /// The rows of a token breakdown as a table, with a bar of their share of the tokens
+    fn breakdown_items(
+        &self,
+        rows: &[BreakdownRow],
+        total_tokens: usize,
+        bar_width: usize,
+    ) -> Vec<ListItem<'a>> {
+        let token_format = &self.model.session.config.token_format;
+        let name_width = rows.iter().map(|row| row.name.len()).max().unwrap_or(0);
+        let tokens_width = rows
+            .iter()
+            .map(|row| StatisticsState::format_number(row.tokens, token_format).len())
+            .max()
+            .unwrap_or(0);
+        rows.iter()
+            .map(|row| {
+                let percentage = if total_tokens > 0 {
+                    row.tokens as f64 / total_tokens as f64 * 100.0
+                } else {
+                    0.0
+                };
+                let filled = ((percentage / 100.0) * bar_width as f64).round() as usize;
+                ListItem::new(format!(
+                    "  {:<name_width$} │{}{}│ {:>tokens_width$} ({:>5.1}%) | {} files",
+                    row.name,
+                    "█".repeat(filled),
+                    "░".repeat(bar_width.saturating_sub(filled)),
+                    StatisticsState::format_number(row.tokens, token_format),
+                    percentage,
+                    row.files,
+                ))
+            })
+            .collect()
+    }
 }
 
 impl<'a> Widget for StatisticsOverviewWidget<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(0),    // Statistics content
-                Constraint::Length(3), // Instructions
-            ])
-            .split(area);
+        let (content, controls) = with_controls(area);
 
         // Check if analysis has been run
         if self.model.prompt_output.generated_prompt.is_none()
@@ -40,20 +71,22 @@ impl<'a> Widget for StatisticsOverviewWidget<'a> {
                 .style(Style::default().fg(Color::Gray))
                 .alignment(Alignment::Center);
 
-            Widget::render(placeholder_widget, layout[0], buf);
+            Widget::render(placeholder_widget, content, buf);
 
             // Instructions for when no analysis is available
-            let instructions = Paragraph::new("Enter: Go to Selection | Tab/Shift+Tab: Switch Tab")
-                .block(Block::default().borders(Borders::ALL).title("Controls"))
-                .style(Style::default().fg(Color::Gray));
-            Widget::render(instructions, layout[1], buf);
+            if let Some(controls) = controls {
+                ControlsWidget::new(self.model).render(controls, buf);
+            }
             return;
         }
 
         let mut stats_items: Vec<ListItem> = Vec::new();
 
         // Analysis Status (most important first)
-        let (status_text, status_color) = if self.model.prompt_output.analysis_in_progress {
+        let (status_text, status_color) = if let Some(progress) = &self.model.prompt_output.progress
+        {
+            (progress.label(), Color::Yellow)
+        } else if self.model.prompt_output.analysis_in_progress {
             ("Generating prompt...".to_string(), Color::Yellow)
         } else if self.model.prompt_output.analysis_error.is_some() {
             ("Analysis failed".to_string(), Color::Red)
@@ -111,6 +144,21 @@ impl<'a> Widget for StatisticsOverviewWidget<'a> {
                 percentage
             )));
         }
+        let distribution = &self.model.statistics.token_distribution;
+        if total_files > 0 {
+            let token_format = &self.model.session.config.token_format;
+            stats_items.push(ListItem::new(format!(
+                "  • Lines (last run): {} code, {} comments, {} blank",
+                StatisticsState::format_number(distribution.code_lines, token_format),
+                StatisticsState::format_number(distribution.comment_lines, token_format),
+                StatisticsState::format_number(distribution.blank_lines, token_format)
+            )));
+            stats_items.push(ListItem::new(format!(
+                "  • Test Files (last run): {} files, {} tokens",
+                distribution.test_files,
+                StatisticsState::format_number(distribution.test_tokens, token_format)
+            )));
+        }
         stats_items.push(ListItem::new(""));
 
         // Token Summary
@@ -140,11 +188,93 @@ impl<'a> Widget for StatisticsOverviewWidget<'a> {
                     )
                 )));
             }
+            if let Some(breakdown) = &self.model.prompt_output.token_breakdown {
+                for (section, tokens) in breakdown.sections() {
+                    if tokens == 0 {
+                        continue;
+                    }
+                    let percentage = if token_count > 0 {
+                        tokens as f64 / token_count as f64 * 100.0
+                    } else {
+                        0.0
+                    };
+                    stats_items.push(ListItem::new(format!(
+                        "    - {}: {} ({:.1}%)",
+                        section,
+                        StatisticsState::format_number(
+                            tokens,
+                            &self.model.session.config.token_format
+                        ),
+                        percentage
+                    )));
+                }
+            }
         } else {
             stats_items.push(ListItem::new("  • Total Tokens: Not calculated"));
         }
+        let history = &self.model.statistics.token_history;
+        if let (Some(first), Some(last), true) =
+            (history.first(), history.last(), history.len() > 1)
+        {
+            let change = if *first > 0 {
+                (*last as f64 - *first as f64) / *first as f64 * 100.0
+            } else {
+                0.0
+            };
+            let token_format = &self.model.session.config.token_format;
+            stats_items.push(ListItem::new(Line::from(vec![
+                Span::raw(format!("  • Trend (last {} runs): ", history.len())),
+                Span::styled(
+                    self.model.statistics.token_sparkline(),
+                    Style::default().fg(Color::Magenta),
+                ),
+                Span::raw(format!(
+                    " {} → {} ({:+.1}%)",
+                    StatisticsState::format_number(*first, token_format),
+                    StatisticsState::format_number(*last, token_format),
+                    change
+                )),
+            ])));
+        }
         stats_items.push(ListItem::new(""));
 
+        // Token breakdown of the files
+        if !distribution.directories.is_empty() {
+            let bar_width = (content.width.saturating_sub(4) as usize)
+                .saturating_sub(60)
+                .clamp(10, 30);
+            let sort = self.model.statistics.breakdown_sort.as_str();
+            for (title, color, rows) in [
+                (
+                    "📂 Tokens by Directory",
+                    Color::Blue,
+                    &distribution.directories,
+                ),
+                (
+                    "🏷️  Tokens by Extension",
+                    Color::Green,
+                    &distribution.extensions,
+                ),
+                (
+                    "🗣️  Tokens by Language",
+                    Color::LightCyan,
+                    &distribution.languages,
+                ),
+            ] {
+                stats_items.push(
+                    ListItem::new(format!("{} (by {}, S to sort)", title, sort))
+                        .style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                );
+                let rows = self.model.statistics.sorted_breakdown(rows);
+                stats_items.extend(self.breakdown_items(
+                    &rows,
+                    distribution.total_tokens(),
+                    bar_width,
+                ));
+                stats_items.push(ListItem::new(""));
+            }
+        }
+
         // Configuration Summary
         stats_items.push(
             ListItem::new("⚙️  Configuration").style(
@@ -158,6 +288,8 @@ impl<'a> Widget for StatisticsOverviewWidget<'a> {
             code2prompt_core::template::OutputFormat::Markdown => "Markdown",
             code2prompt_core::template::OutputFormat::Json => "JSON",
             code2prompt_core::template::OutputFormat::Xml => "XML",
+            code2prompt_core::template::OutputFormat::Jsonl => "JSONL",
+            code2prompt_core::template::OutputFormat::Chunks => "Chunks",
         };
         stats_items.push(ListItem::new(format!("  • Output: {}", output_format)));
         stats_items.push(ListItem::new(format!(
@@ -170,7 +302,7 @@ impl<'a> Widget for StatisticsOverviewWidget<'a> {
         )));
         stats_items.push(ListItem::new(format!(
             "  • Git Diff: {}",
-            if self.model.session.config.diff_enabled {
+            if self.model.session.config.working_diff_enabled() {
                 "On"
             } else {
                 "Off"
@@ -184,17 +316,21 @@ impl<'a> Widget for StatisticsOverviewWidget<'a> {
         );
         stats_items.push(ListItem::new(pattern_summary));
 
+        // Scroll, keeping the last item visible
+        let content_height = content.height.saturating_sub(2) as usize;
+        let max_scroll = stats_items.len().saturating_sub(content_height);
+        let scroll = (self.model.statistics.scroll as usize).min(max_scroll);
+        let stats_items: Vec<ListItem> = stats_items.into_iter().skip(scroll).collect();
+
         let stats_widget = List::new(stats_items)
             .block(Block::default().borders(Borders::ALL).title("📊 Overview"))
             .style(Style::default().fg(Color::White));
 
-        Widget::render(stats_widget, layout[0], buf);
+        Widget::render(stats_widget, content, buf);
 
         // Instructions
-        let instructions =
-            Paragraph::new("Enter: Run Analysis | ←→: Switch View | Tab/Shift+Tab: Switch Tab")
-                .block(Block::default().borders(Borders::ALL).title("Controls"))
-                .style(Style::default().fg(Color::Gray));
-        Widget::render(instructions, layout[1], buf);
+        if let Some(controls) = controls {
+            ControlsWidget::new(self.model).render(controls, buf);
+        }
     }
 }
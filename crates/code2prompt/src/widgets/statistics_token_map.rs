@@ -2,6 +2,8 @@
 
 use crate::model::Model;
 use crate::token_map::{TuiColor, format_token_map_for_tui};
+use crate::view::layout::with_controls;
+use crate::widgets::ControlsWidget;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
@@ -25,13 +27,7 @@ impl<'a> StatefulWidget for StatisticsTokenMapWidget<'a> {
     type State = TokenMapState;
 
     fn render(self, area: Rect, buf: &mut Buffer, _state: &mut Self::State) {
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(0),    // Token map content
-                Constraint::Length(3), // Instructions
-            ])
-            .split(area);
+        let (content, controls) = with_controls(area);
 
         let title = "🗂️  Token Map";
 
@@ -48,14 +44,12 @@ impl<'a> StatefulWidget for StatisticsTokenMapWidget<'a> {
                 .style(Style::default().fg(Color::Gray))
                 .alignment(Alignment::Center);
 
-            Widget::render(placeholder_widget, layout[0], buf);
+            Widget::render(placeholder_widget, content, buf);
 
             // Instructions
-            let instructions =
-                Paragraph::new("Enter: Run Analysis | ←→: Switch View | Tab/Shift+Tab: Switch Tab")
-                    .block(Block::default().borders(Borders::ALL).title("Controls"))
-                    .style(Style::default().fg(Color::Gray));
-            Widget::render(instructions, layout[1], buf);
+            if let Some(controls) = controls {
+                ControlsWidget::new(self.model).render(controls, buf);
+            }
             return;
         }
 
@@ -69,7 +63,7 @@ impl<'a> StatefulWidget for StatisticsTokenMapWidget<'a> {
         );
 
         // Calculate viewport for scrolling - read directly from Model
-        let content_height = layout[0].height.saturating_sub(2).max(1) as usize; // Account for borders
+        let content_height = content.height.saturating_sub(2).max(1) as usize; // Account for borders
         let total = formatted_lines.len();
         let max_scroll = total.saturating_sub(content_height);
         let scroll_start = (self.model.statistics.scroll as usize).min(max_scroll);
@@ -131,12 +125,11 @@ impl<'a> StatefulWidget for StatisticsTokenMapWidget<'a> {
         let token_map_widget =
             List::new(items).block(Block::default().borders(Borders::ALL).title(scroll_title));
 
-        Widget::render(token_map_widget, layout[0], buf);
+        Widget::render(token_map_widget, content, buf);
 
         // Instructions
-        let instructions = Paragraph::new("Enter: Run Analysis | ←→: Switch View | ↑↓/PgUp/PgDn: Scroll | Tab/Shift+Tab: Switch Tab")
-            .block(Block::default().borders(Borders::ALL).title("Controls"))
-            .style(Style::default().fg(Color::Gray));
-        Widget::render(instructions, layout[1], buf);
+        if let Some(controls) = controls {
+            ControlsWidget::new(self.model).render(controls, buf);
+        }
     }
 }
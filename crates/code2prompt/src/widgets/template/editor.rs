@@ -1,6 +1,7 @@
 //! Template Editor sub-widget.
 //!
-//! This widget provides an editable text area for template content with validation.
+//! This widget provides an editable text area for template content with validation,
+//! the line of a syntax error being highlighted.
 
 use crate::model::template::EditorState;
 use ratatui::{
@@ -92,7 +93,33 @@ impl TemplateEditorWidget {
 
         // Render the TextArea
         Widget::render(&textarea, area, buf);
+
+        if let Some(line) = state.error_line {
+            highlight_error_line(area, buf, state, line);
+        }
+    }
+}
+
+/// Highlights the line of a syntax error, if it is in view.
+///
+/// The editor is rendered from a fresh copy of the text area, whose viewport starts at
+/// the top and scrolls just enough to show the cursor, so the first visible line is known.
+fn highlight_error_line(area: Rect, buf: &mut Buffer, state: &EditorState, line: usize) {
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    let height = inner.height as usize;
+    if height == 0 {
+        return;
+    }
+    let cursor_row = state.editor.cursor().0;
+    let top = (cursor_row + 1).saturating_sub(height);
+    let row = line.saturating_sub(1);
+    if row < top || row >= top + height {
+        return;
     }
+    buf.set_style(
+        Rect::new(inner.x, inner.y + (row - top) as u16, inner.width, 1),
+        Style::default().bg(Color::Red).fg(Color::White),
+    );
 }
 
 impl Default for TemplateEditorWidget {
@@ -4,101 +4,123 @@
 //! - Editor: Template content editing and validation
 //! - Variable: Variable management and validation  
 //! - Picker: Template selection and loading
+//!
+//! The preview pane, when shown, takes the lower half of the editor column.
 
 pub mod editor;
 pub mod picker;
+pub mod preview;
 pub mod variable;
 
 pub use editor::TemplateEditorWidget;
 pub use picker::TemplatePickerWidget;
+pub use preview::TemplatePreviewWidget;
 pub use variable::TemplateVariableWidget;
 
-use crate::model::Model;
+use crate::model::keybindings::{KeyBinding, active_bindings};
+use crate::model::keymap::Keymap;
 use crate::model::template::{TemplateFocus, TemplateState};
+use crate::model::{LayoutPrefs, Model};
+use crate::view::layout::with_controls;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph},
 };
+use std::sync::Arc;
 
 /// Main Template widget that coordinates the 3 sub-widgets
 pub struct TemplateWidget {
     editor: TemplateEditorWidget,
     variables: TemplateVariableWidget,
     picker: TemplatePickerWidget,
+    preview: TemplatePreviewWidget,
+    layout: LayoutPrefs,
+    bindings: Vec<&'static KeyBinding>,
+    keymap: Arc<Keymap>,
 }
 
 impl TemplateWidget {
-    pub fn new(_model: &Model) -> Self {
+    pub fn new(model: &Model) -> Self {
         Self {
             editor: TemplateEditorWidget::new(),
             variables: TemplateVariableWidget::new(),
             picker: TemplatePickerWidget::new(),
+            preview: TemplatePreviewWidget::new(),
+            layout: model.layout,
+            bindings: active_bindings(model),
+            keymap: model.keymap.clone(),
         }
     }
 
     /// Render the template widget with 3 columns
     pub fn render(&self, area: Rect, buf: &mut Buffer, state: &mut TemplateState) {
-        // Main layout - content and footer
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(0),    // Content (3 columns)
-                Constraint::Length(3), // Footer
-            ])
-            .split(area);
+        // Main layout - content and footer, dropped on short terminals
+        let (content, footer) = with_controls(area);
 
         // 3-column layout for content
-        self.render_content(chunks[0], buf, state);
+        self.render_content(content, buf, state);
 
         // Footer
-        self.render_footer(chunks[1], buf, state);
+        if let Some(footer) = footer {
+            self.render_footer(footer, buf, state);
+        }
     }
 
     /// Render the 3-column content area
     fn render_content(&self, area: Rect, buf: &mut Buffer, state: &mut TemplateState) {
-        // Flexible 3-column layout
+        // Flexible 3-column layout, ordered and sized by the layout preferences
         let min_width = 30;
         let available_width = area.width.saturating_sub(6); // Account for borders
+        let order = self.layout.template_order;
 
-        let constraints = if available_width >= min_width * 3 {
+        let widths: [u16; 3] = if available_width >= min_width * 3 {
             // Full 3-column layout
-            vec![
-                Constraint::Percentage(40), // Editor
-                Constraint::Percentage(35), // Variables
-                Constraint::Percentage(25), // Picker
-            ]
+            self.layout.template_widths
         } else if available_width >= min_width * 2 {
-            // 2-column layout, hide picker or make it smaller
-            vec![
-                Constraint::Percentage(60), // Editor
-                Constraint::Percentage(40), // Variables
-                Constraint::Length(0),      // Picker hidden
-            ]
+            // 2-column layout, hide picker and share its width
+            let picker = self.layout.template_position(TemplateFocus::Picker);
+            let visible: u16 = (0..3)
+                .filter(|&i| i != picker)
+                .map(|i| self.layout.template_widths[i])
+                .sum();
+            std::array::from_fn(|i| {
+                if i == picker {
+                    0
+                } else {
+                    self.layout.template_widths[i] * 100 / visible.max(1)
+                }
+            })
         } else {
             // Single column, show only focused column
-            match state.get_focus() {
-                TemplateFocus::Editor => vec![
-                    Constraint::Percentage(100),
-                    Constraint::Length(0),
-                    Constraint::Length(0),
-                ],
-                TemplateFocus::Variables => vec![
-                    Constraint::Length(0),
-                    Constraint::Percentage(100),
-                    Constraint::Length(0),
-                ],
-                TemplateFocus::Picker => vec![
-                    Constraint::Length(0),
-                    Constraint::Length(0),
-                    Constraint::Percentage(100),
-                ],
-            }
+            std::array::from_fn(|i| {
+                if order[i] == state.get_focus() {
+                    100
+                } else {
+                    0
+                }
+            })
         };
 
-        let columns = Layout::default()
+        let constraints: Vec<Constraint> = widths
+            .iter()
+            .map(|&width| {
+                if width == 0 {
+                    Constraint::Length(0)
+                } else {
+                    Constraint::Percentage(width)
+                }
+            })
+            .collect();
+
+        let layout = Layout::default()
             .direction(Direction::Horizontal)
             .constraints(constraints)
             .split(area);
+        let columns = [
+            layout[self.layout.template_position(TemplateFocus::Editor)],
+            layout[self.layout.template_position(TemplateFocus::Variables)],
+            layout[self.layout.template_position(TemplateFocus::Picker)],
+        ];
 
         // Render each column if it has space
         if columns[0].width > 0 {
@@ -106,13 +128,24 @@ impl TemplateWidget {
             let is_editing_template =
                 state.get_focus_mode() == crate::model::template::FocusMode::EditingTemplate;
             let has_missing_vars = state.variables.has_missing_variables();
+            let (editor_area, preview_area) = if state.preview.visible {
+                let [editor, preview] =
+                    Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)])
+                        .areas(columns[0]);
+                (editor, Some(preview))
+            } else {
+                (columns[0], None)
+            };
             self.editor.render(
-                columns[0],
+                editor_area,
                 buf,
                 &mut state.editor,
                 is_editor_focused || is_editing_template,
                 has_missing_vars,
             );
+            if let Some(preview_area) = preview_area {
+                self.preview.render(preview_area, buf, &state.preview);
+            }
         }
 
         if columns[1].width > 0 {
@@ -141,77 +174,31 @@ impl TemplateWidget {
 
     /// Render the footer with controls and status
     fn render_footer(&self, area: Rect, buf: &mut Buffer, state: &TemplateState) {
+        // The name input replaces the controls while saving
+        if state.get_focus_mode() == crate::model::template::FocusMode::SavingTemplate {
+            Paragraph::new(format!("Save as: {}", state.save_name))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Save template (Enter: Save | Esc: Cancel)"),
+                )
+                .style(Style::default().fg(Color::Yellow))
+                .render(area, buf);
+            return;
+        }
+
         let footer_content = if !state.get_status().is_empty() {
             // Simple text for status messages
-            vec![Span::styled(
-                state.get_status(),
-                Style::default().fg(Color::Gray),
-            )]
+            state.get_status().to_string()
         } else {
-            // Show different controls based on focus mode
-            match state.get_focus_mode() {
-                crate::model::template::FocusMode::Normal => {
-                    // Normal mode: can switch focus with colored letters
-                    let mut spans = vec![
-                        Span::styled(
-                            "Enter: Run Analysis | Focus: ",
-                            Style::default().fg(Color::Gray),
-                        ),
-                        Span::styled(
-                            "e",
-                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled("(dit) ", Style::default().fg(Color::Gray)),
-                        Span::styled(
-                            "v",
-                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled("(ariables) ", Style::default().fg(Color::Gray)),
-                        Span::styled(
-                            "p",
-                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled("(icker) | ", Style::default().fg(Color::Gray)),
-                        Span::styled(
-                            "s",
-                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled("(ave Template) ", Style::default().fg(Color::Gray)),
-                    ];
-
-                    let specific_controls = match state.get_focus() {
-                        TemplateFocus::Editor => "",
-                        TemplateFocus::Variables => "",
-                        TemplateFocus::Picker => {
-                            TemplatePickerWidget::get_help_text(true, state.picker.active_list)
-                        }
-                    };
-
-                    spans.push(Span::styled(
-                        specific_controls,
-                        Style::default().fg(Color::Gray),
-                    ));
-                    spans
-                }
-                crate::model::template::FocusMode::EditingTemplate => {
-                    vec![Span::styled(
-                        "EDIT MODE: Type to edit template | ESC: Exit edit mode",
-                        Style::default().fg(Color::Gray),
-                    )]
-                }
-                crate::model::template::FocusMode::EditingVariable => {
-                    let text = if state.variables.is_editing() {
-                        "VARIABLE INPUT: Type value | Enter: Save | ESC: Cancel"
-                    } else {
-                        "VARIABLE MODE: ↑↓: Navigate | Space: Edit variable | Tab: Next | ESC: Exit"
-                    };
-                    vec![Span::styled(text, Style::default().fg(Color::Gray))]
-                }
-            }
+            // Bindings valid in the current focus mode
+            let width = area.width.saturating_sub(2) as usize; // Account for borders
+            crate::view::format_controls_hint(&self.bindings, &self.keymap, width)
         };
 
-        let footer = Paragraph::new(Line::from(footer_content))
-            .block(Block::default().borders(Borders::ALL).title("Controls"));
+        let footer = Paragraph::new(footer_content)
+            .block(Block::default().borders(Borders::ALL).title("Controls"))
+            .style(Style::default().fg(Color::Gray));
         footer.render(area, buf);
     }
 }
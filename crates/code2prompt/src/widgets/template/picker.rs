@@ -2,7 +2,8 @@
 //!
 //! This widget provides template selection with separate default and custom lists.
 
-use crate::model::template::{ActiveList, PickerState};
+use crate::model::template::PickerState;
+use crate::model::template::picker::TemplateFile;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, ListItem},
@@ -59,7 +60,11 @@ impl TemplatePickerWidget {
                 };
 
                 let prefix = if is_selected { "► " } else { "  " };
-                items.push(ListItem::new(format!("{}📄 {}", prefix, template.name)).style(style));
+                items.push(template_item(
+                    format!("{}📄 {}", prefix, template.name),
+                    template,
+                    style,
+                ));
                 item_index += 1;
             }
         }
@@ -100,7 +105,12 @@ impl TemplatePickerWidget {
                 };
 
                 let prefix = if is_selected { "► " } else { "  " };
-                items.push(ListItem::new(format!("{}📝 {}", prefix, template.name)).style(style));
+                let icon = if template.project { "📁" } else { "📝" };
+                items.push(template_item(
+                    format!("{}{} {}", prefix, icon, template.name),
+                    template,
+                    style,
+                ));
                 item_index += 1;
             }
         }
@@ -124,15 +134,18 @@ impl TemplatePickerWidget {
 
         Widget::render(list, area, buf);
     }
+}
 
-    /// Get help text for the picker
-    pub fn get_help_text(is_focused: bool, _active_list: ActiveList) -> &'static str {
-        if is_focused {
-            "↑↓: Navigate | l/Space: Load | r: Refresh"
-        } else {
-            "Press 'p' to focus picker"
-        }
+/// A template of the list, followed by its description
+fn template_item<'a>(label: String, template: &TemplateFile, style: Style) -> ListItem<'a> {
+    let mut spans = vec![Span::styled(label, style)];
+    if let Some(description) = &template.description {
+        spans.push(Span::styled(
+            format!(" - {}", description),
+            Style::default().fg(Color::DarkGray),
+        ));
     }
+    ListItem::new(Line::from(spans))
 }
 
 impl Default for TemplatePickerWidget {
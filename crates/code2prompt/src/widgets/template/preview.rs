@@ -0,0 +1,63 @@
+//! Template Preview sub-widget.
+//!
+//! This widget shows the template rendered against a sample of the selected files,
+//! under the editor.
+
+use crate::model::template::PreviewState;
+use crate::model::template::preview::PREVIEW_SAMPLE_LINES;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+/// Template Preview sub-widget
+pub struct TemplatePreviewWidget;
+
+impl TemplatePreviewWidget {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Render the preview, the render error first if the template failed to render
+    pub fn render(&self, area: Rect, buf: &mut Buffer, state: &PreviewState) {
+        let title = match state.files.len() {
+            0 => " Preview (no selected file loaded in the tree) ".to_string(),
+            count => format!(
+                " Preview ({} files, first {} lines) ",
+                count, PREVIEW_SAMPLE_LINES
+            ),
+        };
+
+        let mut lines = Vec::new();
+        if let Some(error) = &state.error {
+            lines.extend(
+                error
+                    .lines()
+                    .map(|line| Line::styled(line.to_string(), Style::default().fg(Color::Red))),
+            );
+            lines.push(Line::default());
+        }
+        lines.extend(
+            state
+                .rendered
+                .lines()
+                .map(|line| Line::raw(line.to_string())),
+        );
+
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(Style::default().fg(Color::Gray)),
+            )
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+}
+
+impl Default for TemplatePreviewWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -55,7 +55,8 @@ impl TemplateVariableWidget {
             "────────────────────────────────────────────────────────────────────────────────",
         )]));
 
-        // Variable rows
+        // Variable rows, with dotted names grouped under their namespace
+        let mut current_namespace: Option<(&str, &VariableCategory)> = None;
         for (i, var_info) in variables.iter().enumerate() {
             let is_selected = i == state.cursor && is_focused;
 
@@ -85,7 +86,24 @@ impl TemplateVariableWidget {
                 VariableCategory::Missing => "❌ ",
             };
 
-            let name_part = format!("{}{{{{{}}}}}", prefix, var_info.name);
+            let name_part = match var_info.name.rsplit_once('.') {
+                Some((namespace, leaf)) => {
+                    if current_namespace != Some((namespace, &var_info.category)) {
+                        lines.push(Line::from(Span::styled(
+                            format!("  {}{}", prefix, namespace),
+                            Style::default()
+                                .fg(Color::Gray)
+                                .add_modifier(Modifier::BOLD),
+                        )));
+                        current_namespace = Some((namespace, &var_info.category));
+                    }
+                    format!("   └ .{}", leaf)
+                }
+                None => {
+                    current_namespace = None;
+                    format!("{}{{{{{}}}}}", prefix, var_info.name)
+                }
+            };
             let name_padded = format!("{:<24}", name_part);
 
             let value_part = match var_info.category {
@@ -0,0 +1,54 @@
+//! Toast notification drawn in the top-right corner, over the current tab.
+
+use crate::model::Toast;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Widest a toast gets, borders included
+const MAX_TOAST_WIDTH: u16 = 60;
+
+/// Toast notification widget
+pub struct ToastWidget<'a> {
+    pub toast: &'a Toast,
+}
+
+impl<'a> ToastWidget<'a> {
+    pub fn new(toast: &'a Toast) -> Self {
+        Self { toast }
+    }
+}
+
+impl Widget for ToastWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let max_width = area.width.min(MAX_TOAST_WIDTH);
+        let text_width = self.toast.message.width() as u16;
+        let width = (text_width + 2).min(max_width);
+        let inner_width = width.saturating_sub(2).max(1);
+        let height = (text_width.div_ceil(inner_width) + 2).min(area.height);
+        let toast_area = Rect {
+            x: area.right().saturating_sub(width + 1).max(area.x),
+            y: area.y + 1,
+            width,
+            height: height.min(area.height.saturating_sub(1)),
+        };
+
+        let color = if self.toast.is_error {
+            Color::Red
+        } else {
+            Color::Green
+        };
+        Clear.render(toast_area, buf);
+        Paragraph::new(self.toast.message.as_str())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(color)),
+            )
+            .style(Style::default().fg(color))
+            .wrap(Wrap { trim: true })
+            .render(toast_area, buf);
+    }
+}
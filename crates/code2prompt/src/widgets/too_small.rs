@@ -0,0 +1,64 @@
+//! Screen shown instead of a tab when the terminal is too small to draw it.
+
+use crate::model::Tab;
+use crate::view::layout::min_size;
+use ratatui::{
+    prelude::*,
+    widgets::{Paragraph, Wrap},
+};
+
+/// "Terminal too small" screen with the current and required dimensions
+pub struct TooSmallWidget {
+    pub tab: Tab,
+}
+
+impl TooSmallWidget {
+    pub fn new(tab: Tab) -> Self {
+        Self { tab }
+    }
+}
+
+impl Widget for TooSmallWidget {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (width, height) = min_size(self.tab);
+        let size_style = |ok: bool| {
+            Style::default()
+                .fg(if ok { Color::Green } else { Color::Red })
+                .add_modifier(Modifier::BOLD)
+        };
+
+        let lines = vec![
+            Line::from(Span::styled(
+                "Terminal too small",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("Current: "),
+                Span::styled(area.width.to_string(), size_style(area.width >= width)),
+                Span::raw("x"),
+                Span::styled(area.height.to_string(), size_style(area.height >= height)),
+            ]),
+            Line::from(format!("Required: {}x{}", width, height)),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Resize the terminal to continue",
+                Style::default().fg(Color::Gray),
+            )),
+        ];
+
+        // Center vertically when there is room for it
+        let top = area.height.saturating_sub(lines.len() as u16) / 2;
+        let area = Rect {
+            y: area.y + top,
+            height: area.height - top,
+            ..area
+        };
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,42 @@
+//! Agent mode tests for code2prompt
+//!
+//! This module tests the generate → ask → apply loop using a shell command
+//! standing in for the model.
+
+use predicates::str::contains;
+use std::fs;
+use tempfile::tempdir;
+
+#[cfg(unix)]
+#[test]
+fn test_agent_applies_search_replace_edits() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("greet.py"), "print('hi')\n").unwrap();
+    fs::write(
+        dir.path().join("model.sh"),
+        "cat > /dev/null\ncat <<'EOF'\ngreet.py\n```python\n<<<<<<< SEARCH\nprint('hi')\n=======\nprint('hello')\n>>>>>>> REPLACE\n```\nEOF\n",
+    )
+    .unwrap();
+
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("code2prompt");
+    cmd.current_dir(dir.path())
+        .args([
+            ".",
+            "--exclude",
+            "model.sh",
+            "agent",
+            "--instruction",
+            "greet louder",
+            "--llm-command",
+            "sh model.sh",
+            "--yes",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("+print('hello')"));
+
+    assert_eq!(
+        fs::read_to_string(dir.path().join("greet.py")).unwrap(),
+        "print('hello')\n"
+    );
+}
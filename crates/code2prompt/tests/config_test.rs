@@ -38,6 +38,10 @@ token_map_enabled = true
 [user_variables]
 project = "code2prompt"
 author = "ODAncona"
+
+[fence_languages]
+tf = "hcl"
+unknownext = "text"
 "#;
 
     use code2prompt_core::configuration::TomlConfig;
@@ -82,6 +86,14 @@ author = "ODAncona"
         config.user_variables.get("author"),
         Some(&"ODAncona".to_string())
     );
+    assert_eq!(config.fence_languages.get("tf"), Some(&"hcl".to_string()));
+    assert_eq!(
+        config
+            .to_code2prompt_config()
+            .fence_languages
+            .get("unknownext"),
+        Some(&"text".to_string())
+    );
 }
 
 /// Test TOML config export functionality
@@ -197,6 +209,40 @@ exclude_patterns = ["*.py"]
         .stdout(contains("print('Hello')"));
 }
 
+/// Test that the shared options are read from the config file and overridden by flags
+#[test]
+fn test_config_options_and_flags() {
+    let temp_dir = TempDir::new().expect("Should create temp dir");
+    let toml_content = r#"
+hidden = true
+output_format = "json"
+"#;
+    fs::write(temp_dir.path().join(".c2pconfig"), toml_content).expect("Should write config");
+    fs::write(temp_dir.path().join(".hidden.py"), "print('Hidden')").expect("Should write file");
+
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("code2prompt");
+    cmd.current_dir(temp_dir.path())
+        .arg(".")
+        .arg("-O")
+        .arg("-")
+        .assert()
+        .success()
+        .stdout(contains("\"files\""))
+        .stdout(contains(".hidden.py"));
+
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("code2prompt");
+    cmd.current_dir(temp_dir.path())
+        .arg(".")
+        .arg("--output-format")
+        .arg("markdown")
+        .arg("-O")
+        .arg("-")
+        .assert()
+        .success()
+        .stdout(contains("\"files\"").not())
+        .stdout(contains("print('Hidden')"));
+}
+
 /// Test configuration info messages
 #[test]
 fn test_config_info_messages() {
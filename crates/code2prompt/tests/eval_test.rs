@@ -0,0 +1,62 @@
+//! Evaluation tests for code2prompt
+//!
+//! This module tests the variants → answer → grade loop using a shell command
+//! standing in for the model.
+
+use std::fs;
+use tempfile::tempdir;
+
+#[cfg(unix)]
+#[test]
+fn test_eval_writes_scores() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(
+        dir.path().join("rubric.hbs"),
+        "Rate this answer:\n{{response}}\n",
+    )
+    .unwrap();
+    // Answers with the length of the prompt, grades with the length of the answer
+    fs::write(
+        dir.path().join("model.sh"),
+        "input=$(cat)\ncase \"$input\" in\n  'Rate this answer'*) echo \"Score: $(echo \"$input\" | sed -n 2p)\" ;;\n  *) echo \"$input\" | wc -l | tr -d ' ' ;;\nesac\n",
+    )
+    .unwrap();
+
+    assert_cmd::cargo::cargo_bin_cmd!("code2prompt")
+        .current_dir(dir.path())
+        .args([
+            ".",
+            "--include",
+            "main.rs",
+            "--variants",
+            "2",
+            "--vary",
+            "line_numbers",
+            "--no-codeblock",
+        ])
+        .assert()
+        .success();
+
+    let scores = dir.path().join("scores.csv");
+    assert_cmd::cargo::cargo_bin_cmd!("code2prompt")
+        .current_dir(dir.path())
+        .args([
+            "-O",
+            scores.to_str().unwrap(),
+            "eval",
+            "--rubric",
+            "rubric.hbs",
+        ])
+        .args(["--runs", "2", "--llm-command", "sh model.sh"])
+        .assert()
+        .success();
+
+    let csv = fs::read_to_string(scores).unwrap();
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows[0], "variant,run,line_numbers,token_count,score");
+    assert_eq!(rows.len(), 5);
+    assert!(rows[1].starts_with("variant-1,1,off,"));
+    assert!(rows[4].starts_with("variant-2,2,on,"));
+    assert!(rows.iter().skip(1).all(|row| !row.ends_with(',')));
+}
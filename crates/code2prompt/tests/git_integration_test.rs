@@ -98,3 +98,343 @@ fn test_gitignore_patterns(
         pattern
     );
 }
+
+/// Test the commitmsg subcommand - staged diff plus recent commit style examples
+#[rstest]
+fn test_commitmsg(git_test_env: GitTestEnv) {
+    let path = git_test_env.dir.path();
+    let repo = git2::Repository::open(path).expect("Failed to open repository");
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+
+    let stage = |content: &str| {
+        std::fs::write(path.join("parser.rs"), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("parser.rs")).unwrap();
+        index.write().unwrap();
+        index.write_tree().unwrap()
+    };
+
+    // Nothing staged yet
+    git_test_env
+        .command()
+        .arg("commitmsg")
+        .assert()
+        .failure()
+        .stderr(contains("No staged changes"));
+
+    let tree_id = stage("fn parse() {}\n");
+    let tree = repo.find_tree(tree_id).unwrap();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "feat(parser): add parse entry point",
+        &tree,
+        &[],
+    )
+    .unwrap();
+    stage("fn parse() -> Ast { Ast::default() }\n");
+
+    git_test_env.command().arg("commitmsg").assert().success();
+
+    let output = git_test_env.read_output();
+    debug!("Test commitmsg output:\n{}", output);
+
+    assert!(contains("feat(parser): add parse entry point").eval(&output));
+    assert!(contains("+fn parse() -> Ast { Ast::default() }").eval(&output));
+    assert!(contains("Source Tree").not().eval(&output));
+}
+
+/// Test --diff-only - only the files changed by the diff, with the diff
+#[rstest]
+fn test_diff_only(git_test_env: GitTestEnv) {
+    let path = git_test_env.dir.path();
+    let repo = git2::Repository::open(path).expect("Failed to open repository");
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+
+    std::fs::write(path.join("parser.rs"), "fn parse() {}\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+        .unwrap();
+
+    std::fs::write(
+        path.join("parser.rs"),
+        "fn parse() -> Ast { Ast::default() }\n",
+    )
+    .unwrap();
+    index.add_path(std::path::Path::new("parser.rs")).unwrap();
+    index.write().unwrap();
+
+    git_test_env.command().arg("--diff-only").assert().success();
+
+    let output = git_test_env.read_output();
+    debug!("Test --diff-only output:\n{}", output);
+
+    assert!(contains("+fn parse() -> Ast { Ast::default() }").eval(&output));
+    assert!(contains("parser.rs").eval(&output));
+    assert!(contains("Included file").not().eval(&output));
+}
+
+/// Test --diff-only with an unstaged edit only - the file is not part of the rendered diff
+#[rstest]
+fn test_diff_only_ignores_unstaged_changes(git_test_env: GitTestEnv) {
+    let path = git_test_env.dir.path();
+    let repo = git2::Repository::open(path).expect("Failed to open repository");
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+
+    std::fs::write(path.join("parser.rs"), "fn parse() {}\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+        .unwrap();
+
+    std::fs::write(path.join("parser.rs"), "fn parse_unstaged() {}\n").unwrap();
+
+    git_test_env.command().arg("--diff-only").assert().success();
+
+    let output = git_test_env.read_output();
+    debug!("Test --diff-only unstaged output:\n{}", output);
+
+    assert!(contains("no diff between HEAD and index").eval(&output));
+    assert!(contains("parser.rs").not().eval(&output));
+    assert!(contains("parse_unstaged").not().eval(&output));
+}
+
+/// Test --file-history - the latest commits of a file, with their diffs
+#[rstest]
+fn test_file_history(git_test_env: GitTestEnv) {
+    let path = git_test_env.dir.path();
+    let repo = git2::Repository::open(path).expect("Failed to open repository");
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+
+    let mut parent = None;
+    for (message, content) in [
+        ("Add the parser", "fn parse() {}\n"),
+        (
+            "Return an AST from the parser",
+            "fn parse() -> Ast { Ast::default() }\n",
+        ),
+    ] {
+        std::fs::write(path.join("parser.rs"), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("parser.rs")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        let id = repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap();
+        parent = Some(repo.find_commit(id).unwrap());
+    }
+
+    git_test_env
+        .command()
+        .args(["--file-history", "parser.rs:1"])
+        .assert()
+        .success();
+
+    let output = git_test_env.read_output();
+    debug!("Test --file-history output:\n{}", output);
+
+    assert!(contains("History of `parser.rs`:").eval(&output));
+    assert!(contains("Return an AST from the parser").eval(&output));
+    assert!(contains("+fn parse() -> Ast { Ast::default() }").eval(&output));
+    assert!(contains("Add the parser").not().eval(&output));
+}
+
+/// Test the pr-description subcommand - branch commits, touched files and diff
+#[rstest]
+fn test_pr_description(git_test_env: GitTestEnv) {
+    let path = git_test_env.dir.path();
+    let repo = git2::Repository::open(path).expect("Failed to open repository");
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+
+    let commit = |content: &str, message: &str| {
+        std::fs::write(path.join("parser.rs"), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("parser.rs")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    };
+
+    let base = commit("fn parse() {}\n", "Initial commit");
+    let base = repo.find_commit(base).unwrap();
+    repo.branch("main", &base, true).unwrap();
+    repo.branch("feature", &base, false).unwrap();
+    repo.set_head("refs/heads/feature").unwrap();
+
+    // Nothing on the branch yet
+    git_test_env
+        .command()
+        .args(["pr-description", "--base", "main"])
+        .assert()
+        .failure()
+        .stderr(contains("No changes between main and HEAD"));
+
+    commit(
+        "fn parse() -> Ast { Ast::default() }\n",
+        "Return an AST from parse",
+    );
+
+    git_test_env
+        .command()
+        .args(["pr-description", "--base", "main"])
+        .assert()
+        .success();
+
+    let output = git_test_env.read_output();
+    debug!("Test pr-description output:\n{}", output);
+
+    assert!(contains("Return an AST from parse").eval(&output));
+    assert!(contains("Initial commit").not().eval(&output));
+    assert!(contains("parser.rs").eval(&output));
+    assert!(contains("+fn parse() -> Ast { Ast::default() }").eval(&output));
+}
+
+/// Test the release-notes subcommand - history since a tag and merged pull requests
+#[rstest]
+fn test_release_notes(git_test_env: GitTestEnv) {
+    let path = git_test_env.dir.path();
+    let repo = git2::Repository::open(path).expect("Failed to open repository");
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+
+    let commit = |files: &[(&str, &str)], message: &str| {
+        let mut index = repo.index().unwrap();
+        for (name, content) in files {
+            let file = path.join(name);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, content).unwrap();
+            index.add_path(std::path::Path::new(name)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    };
+
+    let release = commit(&[("parser.rs", "fn parse() {}\n")], "Initial release");
+    repo.tag_lightweight("v1.0.0", &repo.find_object(release, None).unwrap(), false)
+        .unwrap();
+    commit(
+        &[
+            ("parser.rs", "fn parse() -> Option<Ast> { None }\n"),
+            ("tests/parser_test.rs", "#[test] fn empty() {}\n"),
+        ],
+        "Do not crash on empty input (#12)",
+    );
+
+    git_test_env
+        .command()
+        .args(["release-notes", "--since", "v1.0.0"])
+        .env_remove("GITHUB_TOKEN")
+        .env_remove("GH_TOKEN")
+        .assert()
+        .success();
+
+    let output = git_test_env.read_output();
+    debug!("Test release-notes output:\n{}", output);
+
+    assert!(contains("#12 Do not crash on empty input").eval(&output));
+    assert!(contains("+fn parse() -> Option<Ast> { None }").eval(&output));
+    assert!(contains("Initial release").not().eval(&output));
+    // Tests are not relevant to a changelog
+    assert!(contains("parser_test.rs").not().eval(&output));
+
+    // Unknown revision
+    git_test_env
+        .command()
+        .args(["release-notes", "--since", "v9.9.9"])
+        .assert()
+        .failure();
+}
+
+/// Test that release-notes looks pull request titles up on GitHub when a token is set
+#[cfg(unix)]
+#[rstest]
+fn test_release_notes_github_titles(git_test_env: GitTestEnv) {
+    let path = git_test_env.dir.path();
+    let repo = git2::Repository::open(path).expect("Failed to open repository");
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+    repo.remote("origin", "git@github.com:acme/widget.git")
+        .unwrap();
+
+    let tree = repo
+        .find_tree(repo.index().unwrap().write_tree().unwrap())
+        .unwrap();
+    let release = repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Initial release",
+            &tree,
+            &[],
+        )
+        .unwrap();
+    repo.tag_lightweight("v1.0.0", &repo.find_object(release, None).unwrap(), false)
+        .unwrap();
+    let release = repo.find_commit(release).unwrap();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "Merge pull request #3 from acme/fix",
+        &tree,
+        &[&release],
+    )
+    .unwrap();
+
+    // Serve the API from files so that no network access is needed
+    let api = tempfile::tempdir().unwrap();
+    let pulls = api.path().join("repos/acme/widget/pulls");
+    std::fs::create_dir_all(&pulls).unwrap();
+    std::fs::write(pulls.join("3"), r#"{"title": "Handle empty input"}"#).unwrap();
+
+    git_test_env
+        .command()
+        .args(["release-notes", "--since", "v1.0.0"])
+        .env("GITHUB_TOKEN", "secret")
+        .env("GITHUB_API_URL", format!("file://{}", api.path().display()))
+        .assert()
+        .success();
+
+    let output = git_test_env.read_output();
+    assert!(contains("#3 Handle empty input").eval(&output));
+    assert!(contains("secret").not().eval(&output));
+}
@@ -179,3 +179,457 @@ fn test_command_helper(basic_test_env: BasicTestEnv) {
     let output = basic_test_env.read_output();
     assert!(!output.is_empty(), "Output should not be empty");
 }
+
+/// Test the doc-coverage subcommand - prompt restricted to the files with undocumented items
+#[rstest]
+fn test_doc_coverage(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    create_temp_file(
+        dir,
+        "src/lib.rs",
+        "/// Parses\npub fn parse() {}\npub fn render() {}\n",
+    );
+    create_temp_file(dir, "src/done.rs", "/// Done\npub struct Done;\n");
+    let report_path = dir.join("report.json");
+
+    basic_test_env
+        .command()
+        .arg("doc-coverage")
+        .arg("--report")
+        .arg(&report_path)
+        .assert()
+        .success()
+        .stderr(contains("Documentation coverage: 66.7%"));
+
+    let output = basic_test_env.read_output();
+    debug!("Test doc-coverage output:\n{}", output);
+
+    assert!(contains("`src/lib.rs`").eval(&output));
+    assert!(contains("- line 3: `fn render`").eval(&output));
+    assert!(contains("pub struct Done").not().eval(&output));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&report_path).unwrap()).unwrap();
+    assert_eq!(report["total_items"], 3);
+    assert_eq!(report["files"][0]["gaps"][0]["name"], "render");
+}
+
+/// Test the architecture subcommand - brief pruned to the token budget
+#[rstest]
+fn test_architecture_brief(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    create_temp_file(dir, "Cargo.toml", "[package]\nname = \"demo\"\n");
+    create_temp_file(dir, "README.md", "# Demo\n\nA demo project.\n");
+    create_temp_file(dir, "src/lib.rs", "//! Demo library.\n\npub fn run() {}\n");
+
+    basic_test_env
+        .command()
+        .arg("architecture")
+        .assert()
+        .success()
+        .stderr(contains("Architecture brief:"));
+
+    let output = basic_test_env.read_output();
+    debug!("Test architecture output:\n{}", output);
+
+    assert!(contains("- `demo` in `.` (cargo)").eval(&output));
+    assert!(contains("A demo project.").eval(&output));
+    assert!(contains("`src/lib.rs`: Demo library.").eval(&output));
+    assert!(contains("    pub fn run()").eval(&output));
+    assert!(contains("## Left Out").not().eval(&output));
+
+    for i in 0..30 {
+        create_temp_file(
+            dir,
+            &format!("src/module_{}.rs", i),
+            &format!(
+                "//! Module {}.\n\npub fn item_{}() {{}}\npub struct Item{};\n",
+                i, i, i
+            ),
+        );
+    }
+    // The pruning and the report measure the same rendered prompt
+    basic_test_env
+        .command()
+        .args(["architecture", "--max-tokens", "450"])
+        .assert()
+        .success()
+        .stderr(contains("Dropped the outlines of"))
+        .stderr(contains("(budget 450)"))
+        .stderr(contains("over the budget").not());
+    assert!(contains("## Left Out").eval(&basic_test_env.read_output()));
+}
+
+#[rstest]
+fn test_onboarding_tour(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    create_temp_file(
+        dir,
+        "src/main.rs",
+        "mod config;\nmod server;\nfn main() {}\n",
+    );
+    create_temp_file(
+        dir,
+        "src/server.rs",
+        "//! HTTP server.\nuse crate::config::Config;\n",
+    );
+    create_temp_file(dir, "src/config.rs", "//! Settings.\npub struct Config;\n");
+    create_temp_file(dir, "tests/server_test.rs", "fn test_server() {}\n");
+
+    basic_test_env
+        .command()
+        .arg("tour")
+        .assert()
+        .success()
+        .stderr(contains("Entry Points: 1 files"));
+
+    let output = basic_test_env.read_output();
+    debug!("Test tour output:\n{}", output);
+
+    let position = |text: &str| {
+        output
+            .find(text)
+            .unwrap_or_else(|| panic!("{} is missing", text))
+    };
+    assert!(position("## `src/main.rs`") < position("## `src/config.rs`"));
+    assert!(position("## `src/config.rs`") < position("## `src/server.rs`"));
+    assert!(position("## `src/server.rs`") < position("## `tests/server_test.rs`"));
+    assert!(contains("Builds on `src/config.rs`.").eval(&output));
+    assert!(contains("Explain this codebase to a new hire").eval(&output));
+}
+
+#[rstest]
+fn test_package_selection(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    create_temp_file(
+        dir,
+        "services/payments/package.json",
+        r#"{"name": "payments-service", "dependencies": {"money": "*", "react": "18"}}"#,
+    );
+    create_temp_file(dir, "services/payments/index.js", "// payments entry\n");
+    create_temp_file(dir, "libs/money/package.json", r#"{"name": "money"}"#);
+    create_temp_file(dir, "libs/money/index.js", "// money helpers\n");
+    create_temp_file(dir, "services/search/package.json", r#"{"name": "search"}"#);
+    create_temp_file(dir, "services/search/index.js", "// search entry\n");
+
+    basic_test_env
+        .command()
+        .args(["--package", "payments-service"])
+        .assert()
+        .success();
+
+    let output = basic_test_env.read_output();
+    debug!("Test package output:\n{}", output);
+    assert!(contains("payments entry").eval(&output));
+    assert!(contains("money helpers").eval(&output));
+    assert!(contains("search entry").not().eval(&output));
+    // Files outside of any package are kept
+    assert!(contains("content foo.py").eval(&output));
+
+    basic_test_env
+        .command()
+        .args(["--package", "billing"])
+        .assert()
+        .failure()
+        .stderr(contains("Unknown package `billing`"));
+}
+
+#[rstest]
+fn test_bazel_target_selection(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    create_temp_file(
+        dir,
+        "services/auth/BUILD",
+        "cc_binary(\n    name = \"auth\",\n    srcs = glob([\"*.cc\"]),\n    deps = [\"//libs/crypto\"],\n)\n",
+    );
+    create_temp_file(dir, "services/auth/main.cc", "// auth main\n");
+    create_temp_file(
+        dir,
+        "libs/crypto/BUILD",
+        "cc_library(name = \"crypto\", srcs = [\"hash.cc\"])\n",
+    );
+    create_temp_file(dir, "libs/crypto/hash.cc", "// crypto hash\n");
+    create_temp_file(dir, "libs/crypto/unused.cc", "// unused\n");
+
+    basic_test_env
+        .command()
+        .args(["--bazel-target", "//services/auth:auth"])
+        .assert()
+        .success();
+
+    let output = basic_test_env.read_output();
+    debug!("Test bazel output:\n{}", output);
+    assert!(contains("auth main").eval(&output));
+    assert!(contains("crypto hash").eval(&output));
+    assert!(contains("// unused").not().eval(&output));
+    assert!(contains("content foo.py").not().eval(&output));
+}
+
+#[rstest]
+fn test_frontend_summary(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    create_temp_file(
+        dir,
+        "vite.config.ts",
+        "import { defineConfig } from 'vite'\nexport default defineConfig({ server: { port: 4000 } })\n",
+    );
+    create_temp_file(dir, "src/routes/about.tsx", "// about page\n");
+
+    basic_test_env
+        .command()
+        .arg("--frontend-summary")
+        .assert()
+        .success();
+
+    let output = basic_test_env.read_output();
+    debug!("Test frontend output:\n{}", output);
+    assert!(contains("Build Configuration:").eval(&output));
+    assert!(contains("server.port = 4000").eval(&output));
+    assert!(contains("defineConfig({").not().eval(&output));
+    assert!(contains("/about\n").eval(&output));
+    assert!(contains("about page").eval(&output));
+}
+
+#[cfg(feature = "sqlite")]
+#[rstest]
+fn test_sqlite_round_trip(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    create_temp_file(dir, "quotes.py", "print('it''s'); -- not SQL\n");
+    let database = tempfile::tempdir().unwrap();
+    let database = database.path().join("context.db");
+
+    basic_test_env
+        .command()
+        .arg("--export-sqlite")
+        .arg(&database)
+        .assert()
+        .success();
+    let exported = basic_test_env.read_output();
+
+    basic_test_env
+        .command()
+        .arg("--from-sqlite")
+        .arg(&database)
+        .assert()
+        .success();
+    let imported = basic_test_env.read_output();
+    debug!("Test sqlite output:\n{}", imported);
+    assert!(contains("print('it''s'); -- not SQL").eval(&imported));
+    assert!(contains("quotes.py").eval(&imported));
+    assert!(contains("lowercase/foo.py").eval(&exported));
+    assert!(contains("content foo.py").eval(&imported));
+}
+
+#[cfg(not(feature = "sqlite"))]
+#[rstest]
+fn test_sqlite_needs_feature(basic_test_env: BasicTestEnv) {
+    basic_test_env
+        .command()
+        .arg("--from-sqlite")
+        .arg("context.db")
+        .assert()
+        .failure()
+        .stderr(contains("built without the sqlite feature"));
+}
+
+#[rstest]
+fn test_fixture_stubs(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    create_temp_file(
+        dir,
+        "tests/fixtures/payload.json",
+        "{\"fixture\": \"body\"}",
+    );
+
+    basic_test_env
+        .command()
+        .args(["--fixtures", "stub"])
+        .assert()
+        .success();
+
+    let output = basic_test_env.read_output();
+    debug!("Test fixtures output:\n{}", output);
+    assert!(contains("(fixture, 1 line, updated ").eval(&output));
+    assert!(contains("\"body\"").not().eval(&output));
+    assert!(contains("content foo.py").eval(&output));
+}
+
+#[rstest]
+fn test_data_sample(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    let rows: Vec<String> = (1..=10).map(|i| format!("{},item{}", i, i)).collect();
+    create_temp_file(
+        dir,
+        "data/items.csv",
+        &format!("id,name\n{}", rows.join("\n")),
+    );
+
+    basic_test_env
+        .command()
+        .args(["--data-sample", "2,1"])
+        .assert()
+        .success();
+
+    let output = basic_test_env.read_output();
+    debug!("Test data sample output:\n{}", output);
+    assert!(contains("CSV Data (first 2 and last 1 of 10 rows)").eval(&output));
+    assert!(contains("2,item2\n... [7 rows omitted]\n10,item10").eval(&output));
+    assert!(contains("5,item5").not().eval(&output));
+}
+
+#[rstest]
+fn test_summarize_logs(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    let mut log: Vec<String> = (0..40).map(|i| format!("tick {}", i)).collect();
+    log.push("ERROR worker crashed".to_string());
+    create_temp_file(dir, "logs/app.log", &log.join("\n"));
+
+    basic_test_env
+        .command()
+        .args(["--include", "*.log", "--no-ignore", "--summarize-logs"])
+        .assert()
+        .success();
+
+    let output = basic_test_env.read_output();
+    debug!("Test summarize logs output:\n{}", output);
+    assert!(contains("Log Summary (41 lines, 1 errors").eval(&output));
+    assert!(contains("tick 0 [repeated 40 times]\nERROR worker crashed").eval(&output));
+}
+
+#[rstest]
+fn test_max_tokens(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    let large: Vec<String> = (0..500).map(|i| format!("value_{} = {}", i, i)).collect();
+    create_temp_file(dir, "large.py", &large.join("\n"));
+
+    basic_test_env
+        .command()
+        .args(["--max-tokens", "1000"])
+        .assert()
+        .success()
+        .stderr(contains("Dropped large.py"));
+
+    let output = basic_test_env.read_output();
+    debug!("Test max tokens output:\n{}", output);
+    assert!(contains("value_250").not().eval(&output));
+    assert!(contains("content foo.py").eval(&output));
+}
+
+#[rstest]
+fn test_post_process(basic_test_env: BasicTestEnv) {
+    basic_test_env
+        .command()
+        .args(["--post-process", "collapse_blank_lines,footer"])
+        .assert()
+        .success();
+
+    let output = basic_test_env.read_output();
+    debug!("Test post-process output:\n{}", output);
+    assert!(contains("\n\n\n\n").not().eval(&output));
+    assert!(contains("---\nGenerated by code2prompt: ").eval(&output));
+}
+
+#[rstest]
+fn test_placement(basic_test_env: BasicTestEnv) {
+    basic_test_env
+        .command()
+        .args([
+            "--placement",
+            "sandwich",
+            "--key-files",
+            "uppercase/BAZ.py",
+            "--instruction",
+            "Explain the code",
+            "--sort",
+            "name_asc",
+        ])
+        .assert()
+        .success();
+
+    let output = basic_test_env.read_output();
+    debug!("Test placement output:\n{}", output);
+    assert!(output.starts_with("## Instruction\n\nExplain the code\n\n"));
+    assert!(output.trim_end().ends_with("Explain the code"));
+    let key_file = output.find("uppercase/BAZ.py`:").unwrap();
+    let other_file = output.find("lowercase/bar.py`:").unwrap();
+    assert!(key_file < other_file);
+}
+
+#[rstest]
+fn test_post_process_file_index(basic_test_env: BasicTestEnv) {
+    basic_test_env
+        .command()
+        .args(["--post-process", "file_index"])
+        .assert()
+        .success();
+
+    let output = basic_test_env.read_output();
+    debug!("Test file index output:\n{}", output);
+    assert!(output.starts_with("Table of Files (12 files, "));
+    assert!(contains("- [lowercase/foo.py](#file-lowercase-foo-py) (").eval(&output));
+    assert!(contains("<a id=\"file-lowercase-foo-py\"></a>\n`lowercase/foo.py`:").eval(&output));
+}
+
+#[rstest]
+fn test_variants(basic_test_env: BasicTestEnv) {
+    let variants_dir = basic_test_env.dir.path().join("variants");
+    basic_test_env
+        .command()
+        .args(["--variants", "3", "--vary", "placement,summary_mode"])
+        .arg("--variants-dir")
+        .arg(&variants_dir)
+        .args(["--instruction", "Explain the code"])
+        .assert()
+        .success();
+
+    let manifest = std::fs::read_to_string(variants_dir.join("manifest.json")).unwrap();
+    debug!("Test variants manifest:\n{}", manifest);
+    assert!(contains("\"axes\": [\n    \"placement\",\n    \"summary_mode\"\n  ]").eval(&manifest));
+    assert!(contains("\"file\": \"variant-3.md\"").eval(&manifest));
+    assert!(
+        contains("\"placement\": \"beginning\",\n        \"summary_mode\": \"full\"")
+            .eval(&manifest)
+    );
+
+    let first = std::fs::read_to_string(variants_dir.join("variant-1.md")).unwrap();
+    let third = std::fs::read_to_string(variants_dir.join("variant-3.md")).unwrap();
+    assert!(first.trim_end().ends_with("Explain the code"));
+    assert!(third.starts_with("## Instruction\n\nExplain the code"));
+    assert!(!variants_dir.join("variant-4.md").exists());
+}
+
+#[rstest]
+fn test_binary_path_only(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    std::fs::write(dir.join("icon.ico"), b"\0\0\x01\0\x01\0\x10\x10").unwrap();
+
+    basic_test_env
+        .command()
+        .args(["--binary", "path_only"])
+        .assert()
+        .success();
+
+    let output = basic_test_env.read_output();
+    debug!("Test binary output:\n{}", output);
+    assert!(contains("`icon.ico`:\n\n(binary file, 8 B)").eval(&output));
+}
+
+#[rstest]
+fn test_duplicates_stub(basic_test_env: BasicTestEnv) {
+    let dir = basic_test_env.dir.path();
+    let helper = "fn clamp(value: i32) -> i32 {\n    if value < 0 {\n        return 0;\n    }\n    if value > 100 {\n        return 100;\n    }\n    value\n}\n";
+    std::fs::write(dir.join("clamp_a.rs"), helper).unwrap();
+    std::fs::write(dir.join("clamp_b.rs"), helper).unwrap();
+
+    basic_test_env
+        .command()
+        .args(["--duplicates", "stub", "--include", "clamp_*.rs"])
+        .assert()
+        .success()
+        .stderr(contains("clamp_b.rs:1-9 duplicates clamp_a.rs:1-9"));
+
+    let output = basic_test_env.read_output();
+    debug!("Test duplicates output:\n{}", output);
+    assert!(contains("... [duplicate of clamp_a.rs:1-9]").eval(&output));
+    assert!(contains("return 100;").count(1).eval(&output));
+}
@@ -0,0 +1,193 @@
+//! TUI snapshot tests for code2prompt
+//!
+//! This module tests that `--tui --screenshot` renders every tab without a terminal.
+
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_tui_screenshot_renders_each_tab_per_size() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    let output = dir.path().join("snapshot.txt");
+
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("code2prompt");
+    cmd.arg(dir.path())
+        .args(["--tui", "--screenshot"])
+        .arg(&output)
+        .args(["--screenshot-size", "80x24", "--screenshot-size", "100x30"])
+        .assert()
+        .success();
+
+    let snapshot = fs::read_to_string(&output).unwrap();
+    for size in ["80x24", "100x30"] {
+        for tab in [
+            "1. Selection",
+            "2. Settings",
+            "3. Statistics",
+            "4. Template",
+            "5. Output",
+            "6. Git",
+        ] {
+            assert!(
+                snapshot.contains(&format!("=== {} ({}) ===", tab, size)),
+                "missing {} at {}",
+                tab,
+                size
+            );
+        }
+    }
+    assert!(snapshot.contains("main.rs"));
+    assert!(snapshot.contains("Analysis complete!"));
+    assert!(!snapshot.contains("MISSING VARIABLES"));
+    assert!(!snapshot.contains('\x1b'));
+    assert!(snapshot.lines().all(|line| line.chars().count() <= 100));
+}
+
+#[test]
+fn test_tui_screenshot_rejects_invalid_size() {
+    let dir = tempdir().unwrap();
+
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("code2prompt");
+    cmd.arg(dir.path())
+        .args([
+            "--tui",
+            "--screenshot",
+            "out.txt",
+            "--screenshot-size",
+            "wide",
+        ])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_tui_screenshot_adapts_to_small_terminals() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    let output = dir.path().join("snapshot.txt");
+
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("code2prompt");
+    cmd.arg(dir.path())
+        .args(["--tui", "--screenshot"])
+        .arg(&output)
+        .args(["--screenshot-size", "50x12", "--screenshot-size", "30x8"])
+        .assert()
+        .success();
+
+    let snapshot = fs::read_to_string(&output).unwrap();
+    let (compact, too_small) = snapshot.split_once("(30x8)").unwrap();
+    assert!(compact.contains("main.rs"));
+    assert!(!compact.contains("Terminal too small"));
+    assert!(!compact.contains("Filter Patterns"));
+    assert!(too_small.contains("Terminal too small"));
+    assert!(too_small.contains("Required: 40x10"));
+}
+
+#[test]
+fn test_tui_screenshot_shows_file_token_counts() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n".repeat(300)).unwrap();
+    let output = dir.path().join("snapshot.txt");
+
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("code2prompt");
+    cmd.arg(dir.path())
+        .args(["--tui", "--screenshot"])
+        .arg(&output)
+        .args(["--screenshot-size", "80x24"])
+        .assert()
+        .success();
+
+    let snapshot = fs::read_to_string(&output).unwrap();
+    let row = snapshot
+        .lines()
+        .find(|line| line.contains("main.rs"))
+        .unwrap();
+    // The count sits against the right border, after the padding
+    let count = row.trim_end_matches('│').rsplit(' ').next().unwrap();
+    assert!(row.contains("main.rs   "));
+    assert!(count.contains(','));
+    assert!(count.chars().all(|c| c.is_ascii_digit() || c == ','));
+}
+
+#[test]
+fn test_tui_screenshot_restores_saved_session() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.path().join("skip.rs"), "fn skip() {}\n").unwrap();
+    fs::create_dir(dir.path().join(".code2prompt")).unwrap();
+    fs::write(
+        dir.path().join(".code2prompt/session.toml"),
+        r#"
+[[selections]]
+path = "skip.rs"
+action = "exclude"
+
+[template]
+name = "Paths"
+content = "Paths: {{#each files}}{{path}};{{/each}} by {{author}}"
+
+[user_variables]
+author = "alice"
+
+[settings]
+line_numbers = true
+"#,
+    )
+    .unwrap();
+    let output_dir = tempdir().unwrap();
+
+    let screenshot = |extra: &[&str]| {
+        let output = output_dir.path().join("snapshot.txt");
+        let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("code2prompt");
+        cmd.arg(dir.path())
+            .args(["--tui", "--screenshot"])
+            .arg(&output)
+            .args(extra)
+            .assert()
+            .success();
+        fs::read_to_string(&output).unwrap()
+    };
+
+    let restored = screenshot(&[]);
+    assert!(restored.contains("Paths: main.rs; by alice"));
+    let skip_row = restored
+        .lines()
+        .find(|line| line.contains("skip.rs"))
+        .unwrap();
+    assert!(skip_row.contains('☐'));
+
+    let fresh = screenshot(&["--fresh"]);
+    assert!(!fresh.contains("Paths:"));
+    let skip_row = fresh.lines().find(|line| line.contains("skip.rs")).unwrap();
+    assert!(skip_row.contains('☑'));
+}
+
+#[test]
+fn test_tui_screenshot_lists_branches() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(std::path::Path::new("main.rs")).unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let commit = repo
+        .commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+        .unwrap();
+    repo.branch("feature-login", &repo.find_commit(commit).unwrap(), false)
+        .unwrap();
+    let output = dir.path().join("snapshot.txt");
+
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("code2prompt");
+    cmd.arg(dir.path())
+        .args(["--tui", "--screenshot"])
+        .arg(&output)
+        .assert()
+        .success();
+
+    let snapshot = fs::read_to_string(&output).unwrap();
+    let git_tab = snapshot.split("=== 6. Git").nth(1).unwrap();
+    assert!(git_tab.contains("feature-login"));
+    assert!(git_tab.contains("Diff scope"));
+}
@@ -187,3 +187,51 @@ fn test_template_command_creation(template_test_env: TemplateTestEnv) {
         "Output file should exist after command execution"
     );
 }
+
+/// Test that template arguments are mapped using the front-matter argument order
+#[rstest]
+fn test_template_args_from_front_matter(template_test_env: TemplateTestEnv) {
+    let template_path = template_test_env.dir.path().join("bugfix.hbs");
+    std::fs::write(
+        &template_path,
+        "{{!--\nargs = [\"error\", \"component\"]\n--}}\nError: {{error}} in {{component}}",
+    )
+    .unwrap();
+
+    let mut cmd = template_test_env.command();
+    cmd.arg("--template")
+        .arg(&template_path)
+        .arg("--arg")
+        .arg("panic at line 42")
+        .arg("--arg")
+        .arg("component=auth")
+        .assert()
+        .success();
+
+    let output = template_test_env.read_output();
+    assert!(contains("Error: panic at line 42 in auth").eval(&output));
+}
+
+/// Test that a bare template name selects a template of the project library
+#[rstest]
+fn test_named_template_from_project_library(template_test_env: TemplateTestEnv) {
+    let templates_dir = template_test_env.dir.path().join(".code2prompt/templates");
+    std::fs::create_dir_all(&templates_dir).unwrap();
+    std::fs::write(
+        templates_dir.join("review.hbs"),
+        "{{!--\nname = \"Code review\"\ndescription = \"Review the code\"\n--}}\nReview of {{absolute_code_path}}",
+    )
+    .unwrap();
+
+    for name in ["review", "Code review"] {
+        let mut cmd = template_test_env.command();
+        cmd.args(["-t", name]).assert().success();
+
+        let output = template_test_env.read_output();
+        debug!("Named template '{}' output:\n{}", name, output);
+        assert!(contains("Review of").eval(&output));
+    }
+
+    let mut cmd = template_test_env.command();
+    cmd.args(["-t", "missing-template"]).assert().failure();
+}